@@ -0,0 +1,22 @@
+#version 460 core
+#define VULKAN 100
+
+layout(location = 0) in vec4 pos;
+layout(location = 1) in vec2 norm;
+layout(location = 2) in vec2 uv;
+
+const float DEPTH_BIAS = 1e-4;
+
+layout(push_constant) uniform transform { mat4 model; }
+m;
+
+layout(set = 0, binding = 0) uniform camera {
+  mat4 view;
+  mat4 proj;
+}
+c;
+
+void main() {
+  gl_Position = c.proj * c.view * m.model * vec4(pos.xyz, 1.0);
+  gl_Position.z += DEPTH_BIAS;
+}
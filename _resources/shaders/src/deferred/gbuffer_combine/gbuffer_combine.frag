@@ -11,8 +11,42 @@ layout(input_attachment_index = 2, set = 0,
 layout(input_attachment_index = 3, set = 0,
        binding = 3) uniform subpassInputMS gDepth;
 
+layout(set = 1, binding = 0) uniform DirectionalLight {
+  mat4 light_space;
+  vec4 direction;
+  vec4 color;
+  vec4 encoding;
+}
+light;
+
+layout(set = 1, binding = 1) uniform sampler2DShadow shadowMap;
+
 layout(location = 0) out vec4 fragColor;
 
+const float AMBIENT = 0.15;
+const float SHADOW_BIAS = 2e-3;
+
+// Applied only when the swapchain format does not encode sRGB itself
+vec4 encode_output(vec4 color) {
+  if (light.encoding.x == 0.0) {
+    return color;
+  }
+  vec3 linear = max(color.rgb, vec3(0.0));
+  vec3 higher = 1.055 * pow(linear, vec3(1.0 / 2.4)) - 0.055;
+  vec3 lower = linear * 12.92;
+  return vec4(mix(higher, lower, lessThan(linear, vec3(0.0031308))), color.a);
+}
+
+float shadow_factor(vec3 position) {
+  vec4 light_pos = light.light_space * vec4(position, 1.0);
+  vec3 proj = light_pos.xyz / light_pos.w;
+  if (proj.z > 1.0) {
+    return 1.0;
+  }
+  vec2 uv = proj.xy * 0.5 + 0.5;
+  return texture(shadowMap, vec3(uv, proj.z - SHADOW_BIAS));
+}
+
 void main() {
   float depth = subpassLoad(gDepth, gl_SampleID).r;
   if (depth >= 1.0) {
@@ -23,7 +57,17 @@ void main() {
   vec4 normal = subpassLoad(gNormal, gl_SampleID);
   vec4 position = subpassLoad(gPosition, gl_SampleID);
 
-  // Do some lighting calculations here
-  // For now, just output the albedo
-  fragColor = albedo;
+  // Light disabled, output unlit albedo
+  if (light.direction.w == 0.0) {
+    fragColor = encode_output(albedo);
+    return;
+  }
+
+  vec3 n = normalize(normal.xyz);
+  vec3 l = -normalize(light.direction.xyz);
+  float diffuse = max(dot(n, l), 0.0);
+  float shadow = shadow_factor(position.xyz);
+  vec3 color =
+      albedo.rgb * (AMBIENT + diffuse * shadow * light.color.rgb);
+  fragColor = encode_output(vec4(color, albedo.a));
 }
@@ -26,12 +26,25 @@ layout(std140, set = 1, binding = 0) uniform PrbFactors {
   float metallic;
   float roughness;
   float occlusion;
+  // xy - scale, zw - offset
+  vec4 uvTransform;
 }
 pbrFactors;
 
+vec4 sample_map(uint index, vec2 uv) { return texture(pbrSamplers[index], uv); }
+
 void main() {
-  gNormal = vec4(fs_in.norm, 1.0);
-  gPosition = vec4(fs_in.pos, 1.0);
-  gAlbedo = 0.5 * texture(pbrSamplers[ALBEDO_SAMPLER_INDEX], fs_in.uv) +
-            0.5 * pbrFactors.baseColor;
+  vec2 uv = fs_in.uv * pbrFactors.uvTransform.xy + pbrFactors.uvTransform.zw;
+  vec4 baseColor = sample_map(ALBEDO_SAMPLER_INDEX, uv) * pbrFactors.baseColor;
+  // Metalness is stored in blue and roughness in green channel of the map
+  vec2 metallicRoughness =
+      sample_map(METALIC_ROUGHNESS_SAMPLER_INDEX, uv).bg *
+      vec2(pbrFactors.metallic, pbrFactors.roughness);
+  float occlusion =
+      mix(1.0, sample_map(OCCLUSION_SAMPLER_INDEX, uv).r, pbrFactors.occlusion);
+  vec3 emissive = sample_map(EMISSIVE_SAMPLER_INDEX, uv).rgb * pbrFactors.emissive;
+  // Metalness and roughness are kept in the w channels for the shading pass
+  gNormal = vec4(fs_in.norm, metallicRoughness.y);
+  gPosition = vec4(fs_in.pos, metallicRoughness.x);
+  gAlbedo = vec4(baseColor.rgb * occlusion + emissive, baseColor.a);
 }
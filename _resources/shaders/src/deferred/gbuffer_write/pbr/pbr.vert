@@ -14,9 +14,11 @@ layout(location = 0) out VS_OUT {
     vec2 uv;
 } vs_out;
 
-layout(set = 0, binding = 0) uniform Camera {
+layout(set = 0, binding = 0) uniform FrameGlobals {
     mat4 view;
     mat4 proj;
+    vec2 viewport;
+    float time;
 } c;
 
 layout(push_constant) uniform Model {
@@ -12,10 +12,16 @@ layout(location = 0) out vec4 gAlbedo;
 layout(location = 1) out vec4 gNormal;
 layout(location = 2) out vec4 gPosition;
 
-layout(set = 1, binding = 0) uniform sampler2D albedoMap;
+layout(std140, set = 1, binding = 0) uniform MaterialUniform {
+    vec4 tint;
+    // xy - scale, zw - offset
+    vec4 uvTransform;
+};
+
+layout(set = 1, binding = 1) uniform sampler2D albedoMap;
 
 void main() {
     gNormal = vec4(fs_in.norm, 1.0);
     gPosition = vec4(fs_in.pos, 1.0);
-    gAlbedo = texture(albedoMap, fs_in.uv);;
+    gAlbedo = tint * texture(albedoMap, fs_in.uv * uvTransform.xy + uvTransform.zw);
 }
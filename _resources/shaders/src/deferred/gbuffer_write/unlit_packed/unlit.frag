@@ -0,0 +1,27 @@
+#version 460 core
+
+#define VULKAN 100
+
+layout(location = 0) in VS_OUT {
+    vec3 pos;
+    vec3 norm;
+    vec2 uv;
+} fs_in;
+
+layout(location = 0) out vec4 gAlbedo;
+layout(location = 1) out vec4 gNormal;
+layout(location = 2) out vec4 gPosition;
+
+layout(std140, set = 1, binding = 0) uniform MaterialUniform {
+    vec4 tint;
+    // xy - scale, zw - offset
+    vec4 uvTransform;
+};
+
+layout(set = 1, binding = 1) uniform sampler2D albedoMap;
+
+void main() {
+    gNormal = vec4(fs_in.norm, 1.0);
+    gPosition = vec4(fs_in.pos, 1.0);
+    gAlbedo = tint * texture(albedoMap, fs_in.uv * uvTransform.xy + uvTransform.zw);
+}
@@ -0,0 +1,43 @@
+#version 460 core
+
+#define VULKAN 100
+
+// Positions normalized to the mesh bounding box, dequantization is baked into the model matrix
+layout(location = 0) in vec4 pos;
+layout(location = 1) in vec2 norm;
+layout(location = 2) in vec2 uv;
+
+layout(location = 0) out VS_OUT {
+    vec3 pos;
+    vec3 norm;
+    vec2 uv;
+} vs_out;
+
+layout(set = 0, binding = 0) uniform FrameGlobals {
+    mat4 view;
+    mat4 proj;
+    vec2 viewport;
+    float time;
+} c;
+
+layout(push_constant) uniform Model {
+    mat4 model;
+    mat3 model_inv_t;
+} m;
+
+vec3 decode_octahedral(vec2 e) {
+    vec3 n = vec3(e, 1.0 - abs(e.x) - abs(e.y));
+    float t = max(-n.z, 0.0);
+    n.x += n.x >= 0.0 ? -t : t;
+    n.y += n.y >= 0.0 ? -t : t;
+    return normalize(n);
+}
+
+void main() {
+    vec4 world_pos = m.model * vec4(pos.xyz, 1.0);
+    vec3 world_norm = normalize(m.model_inv_t * decode_octahedral(norm));
+    vs_out.pos = world_pos.xyz;
+    vs_out.norm = world_norm;
+    vs_out.uv = uv;
+    gl_Position = c.proj * c.view * world_pos;
+}
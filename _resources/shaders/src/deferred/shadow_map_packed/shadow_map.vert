@@ -0,0 +1,13 @@
+#version 460 core
+
+#define VULKAN 100
+
+layout(location = 0) in vec4 pos;
+layout(location = 1) in vec2 norm;
+layout(location = 2) in vec2 uv;
+
+// Light-space projection premultiplied with the model matrix
+layout(push_constant) uniform transform { mat4 light_model; }
+m;
+
+void main() { gl_Position = m.light_model * vec4(pos.xyz, 1.0); }
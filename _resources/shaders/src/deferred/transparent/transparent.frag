@@ -0,0 +1,22 @@
+#version 460 core
+
+#define VULKAN 100
+
+layout(location = 0) in VS_OUT {
+  vec3 pos;
+  vec3 norm;
+  vec3 color;
+  vec2 uv;
+}
+fs_in;
+
+layout(location = 0) out vec4 outColor;
+
+layout(std140, set = 1, binding = 0) uniform TransparentColor {
+  vec4 color;
+}
+material;
+
+void main() {
+  outColor = material.color;
+}
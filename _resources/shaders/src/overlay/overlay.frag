@@ -0,0 +1,14 @@
+#version 460 core
+
+layout(location = 0) in struct VS_OUT {
+  vec2 uv;
+  vec4 color;
+} fs_in;
+
+layout(location = 0) out vec4 frag_color;
+
+layout(set = 0, binding = 0) uniform sampler2D font_atlas;
+
+void main() {
+  frag_color = fs_in.color * texture(font_atlas, fs_in.uv);
+}
@@ -0,0 +1,16 @@
+#version 460 core
+
+layout(location = 0) in vec3 pos;
+layout(location = 1) in vec2 uv;
+layout(location = 2) in vec4 color;
+
+layout(location = 0) out struct VS_OUT {
+  vec2 uv;
+  vec4 color;
+} vs_out;
+
+void main() {
+  vs_out.uv = uv;
+  vs_out.color = color;
+  gl_Position = vec4(pos.xy, 0.0, 1.0);
+}
@@ -6,9 +6,12 @@ use std::{
     process::{Command, Output},
 };
 
-const SHADER_SOURCE_EXTENSIONS: &[&str] = &["frag", "vert"];
-const SHADER_SOURCE_DIRECTORY: &str = "_resources/shaders/src/";
-const SHADER_TARGET_DIRECTORY: &str = "_resources/shaders/spv/";
+const SHADER_SOURCE_EXTENSIONS: &[&str] = &["frag", "vert", "comp"];
+// (source, target) pairs, shaders embedded into vulkan backend are compiled next to the crate
+const SHADER_DIRECTORIES: &[(&str, &str)] = &[
+    ("_resources/shaders/src/", "_resources/shaders/spv/"),
+    ("vulkan/shaders/src/", "vulkan/shaders/spv/"),
+];
 
 fn to_str(path: &Path) -> Result<&str, Box<dyn Error>> {
     Ok(path
@@ -17,12 +20,19 @@ fn to_str(path: &Path) -> Result<&str, Box<dyn Error>> {
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
+    for (source_directory, target_directory) in SHADER_DIRECTORIES {
+        compile_directory(source_directory, target_directory)?;
+    }
+    Ok(())
+}
+
+fn compile_directory(source_directory: &str, target_directory: &str) -> Result<(), Box<dyn Error>> {
     for extension in SHADER_SOURCE_EXTENSIONS {
-        let pattern = format!("{}/**/*.{}", SHADER_SOURCE_DIRECTORY, extension);
+        let pattern = format!("{}/**/*.{}", source_directory, extension);
         for source_path in (glob(&pattern)?).flatten() {
-            let target_path = Path::new(SHADER_TARGET_DIRECTORY).join(
+            let target_path = Path::new(target_directory).join(
                 source_path
-                    .strip_prefix(SHADER_SOURCE_DIRECTORY)?
+                    .strip_prefix(source_directory)?
                     .with_file_name(format!("{}.spv", extension)),
             );
             create_dir_all(target_path.parent().unwrap())?;
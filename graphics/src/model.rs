@@ -1,6 +1,7 @@
-mod gltf;
+pub mod gltf;
 mod material;
 mod mesh;
+pub mod obj;
 
 use std::fmt::Debug;
 
@@ -1,4 +1,4 @@
-use super::{CommonVertex, Mesh, PbrMaps, PbrMaterial};
+use super::{compute_normals, compute_tangents, CommonVertex, Mesh, PbrMaps, PbrMaterial};
 use base64::Engine;
 use gltf::{self, buffer, mesh::Mode, Gltf, Semantic};
 use std::{error::Error, path::Path};
@@ -19,12 +19,13 @@ impl VertexBuilder {
         Self::default()
     }
 
+    // Missing attributes other than position are zero-filled
     fn build(self) -> CommonVertex {
         CommonVertex {
             pos: self.pos.unwrap(),
-            norm: self.normal.unwrap(),
-            uv: self.tex_coord.unwrap(),
-            tan: self.tangent.unwrap(),
+            norm: self.normal.unwrap_or(Vector3::zero()),
+            uv: self.tex_coord.unwrap_or(Vector2::zero()),
+            tan: self.tangent.unwrap_or(Vector4::zero()),
             color: Vector3::zero(),
         }
     }
@@ -115,13 +116,10 @@ impl DocumentReader {
         &self,
         primitive: gltf::Primitive,
     ) -> Result<(Vec<u32>, Vec<CommonVertex>), Box<dyn Error>> {
-        let mut reader = PrimitiveReaderBuilder::new().with_indices(
-            self.get_accessor(
-                primitive
-                    .indices()
-                    .ok_or_else::<Box<dyn Error>, _>(|| "Mesh indices not found".into())?,
-            )?,
-        );
+        let mut reader = PrimitiveReaderBuilder::new();
+        if let Some(indices) = primitive.indices() {
+            reader = reader.with_indices(self.get_accessor(indices)?);
+        }
         for (semantic, accessor) in primitive.attributes() {
             reader = reader.with_attribute(semantic, self.get_accessor(accessor)?)?;
         }
@@ -134,16 +132,18 @@ impl DocumentReader {
         for primitive in mesh.primitives() {
             if let Mode::Triangles = primitive.mode() {
                 let (p_indices, p_vertices) = self.get_primitive_data(primitive)?;
-                indices.push(p_indices);
-                vertices.push(p_vertices);
+                // Primitive indices are local to its own vertices
+                let base = vertices.len() as u32;
+                indices.extend(p_indices.into_iter().map(|index| index + base));
+                vertices.extend(p_vertices);
             } else {
                 // TODO: Should skip instaed of returning error
                 Err("Only triangle list models are supported")?;
             }
         }
         Ok(Mesh {
-            indices: indices.concat().into_boxed_slice(),
-            vertices: vertices.concat().into_boxed_slice(),
+            indices: indices.into_boxed_slice(),
+            vertices: vertices.into_boxed_slice(),
         })
     }
 
@@ -210,10 +210,12 @@ impl DocumentReader {
             );
         };
         if let Some(occlusion) = material.occlusion_texture() {
-            builder = builder.with_image(
-                self.get_image(occlusion.texture().source(), base)?,
-                PbrMaps::Occlusion,
-            );
+            builder = builder
+                .with_image(
+                    self.get_image(occlusion.texture().source(), base)?,
+                    PbrMaps::Occlusion,
+                )
+                .with_occlusion(occlusion.strength());
         };
         if let Some(emissive) = material.emissive_texture() {
             builder = builder.with_image(
@@ -230,42 +232,83 @@ impl DocumentReader {
 #[derive(Debug)]
 struct PrimitiveReader<'a> {
     pos: AttributeReader<'a>,
-    norm: AttributeReader<'a>,
-    uv: AttributeReader<'a>,
-    tan: AttributeReader<'a>,
-    indices: AttributeReader<'a>,
+    norm: Option<AttributeReader<'a>>,
+    uv: Option<AttributeReader<'a>>,
+    tan: Option<AttributeReader<'a>>,
+    indices: Option<AttributeReader<'a>>,
+}
+
+// Takes next element of optional attribute, attribute present in the primitive
+// must provide the data for each of the vertices
+fn next_element<'a>(
+    reader: &mut Option<AttributeReader<'a>>,
+    missing: &'static str,
+) -> Result<Option<&'a [u8]>, Box<dyn Error>> {
+    match reader {
+        Some(reader) => Ok(Some(reader.next().ok_or(missing)?)),
+        None => Ok(None),
+    }
+}
+
+fn read_indices(reader: AttributeReader) -> Result<Vec<u32>, Box<dyn Error>> {
+    let element_size = reader.element_size;
+    reader
+        .map(|bytes| match element_size {
+            1 => Ok(bytes[0] as u32),
+            2 => Ok(u16::from_le_bytes(<[u8; 2]>::try_from(bytes)?) as u32),
+            4 => Ok(u32::from_le_bytes(<[u8; 4]>::try_from(bytes)?)),
+            _ => Err("Unsupported index type".into()),
+        })
+        .collect()
+}
+
+// Replaces shared vertices with separate ones for each triangle,
+// so that each of them can take its face normal
+fn compute_face_normals(
+    indices: &[u32],
+    vertices: &[CommonVertex],
+) -> Result<(Vec<u32>, Vec<CommonVertex>), Box<dyn Error>> {
+    let mut face_vertices = Vec::with_capacity(indices.len());
+    for face in indices.chunks_exact(3) {
+        let [a, b, c] = [face[0], face[1], face[2]].map(|index| vertices.get(index as usize));
+        let (Some(&a), Some(&b), Some(&c)) = (a, b, c) else {
+            Err("Vertex index out of range")?
+        };
+        face_vertices.extend([a, b, c]);
+    }
+    let face_indices = (0..face_vertices.len() as u32).collect::<Vec<_>>();
+    compute_normals(&face_indices, &mut face_vertices);
+    Ok((face_indices, face_vertices))
 }
 
 impl<'a> PrimitiveReader<'a> {
     fn read(mut self) -> Result<(Vec<u32>, Vec<CommonVertex>), Box<dyn Error>> {
-        let mut indices = Vec::new();
-        for bytes in self.indices {
-            let bytes = <[u8; 2]>::try_from(bytes)?;
-            indices.push(u16::from_le_bytes(bytes) as u32);
-        }
+        let indices = match self.indices.take() {
+            Some(reader) => read_indices(reader)?,
+            None => (0..self.pos.count as u32).collect(),
+        };
         let mut vertices = Vec::new();
-        // TODO: Refactior following code to dont have to check for missing vertex data
         for pos in self.pos.by_ref() {
-            let normal = self
-                .norm
-                .next()
-                .ok_or_else::<Box<dyn Error>, _>(|| "Missing normal data".into())?;
-            let uv = self
-                .uv
-                .next()
-                .ok_or_else::<Box<dyn Error>, _>(|| "Missing uv data".into())?;
-            let tangent = self
-                .tan
-                .next()
-                .ok_or_else::<Box<dyn Error>, _>(|| "Missing normal data".into())?;
-            vertices.push(
-                VertexBuilder::new()
-                    .with_pos(Vector3::try_from_le_bytes(pos)?)
-                    .with_normal(Vector3::try_from_le_bytes(normal)?)
-                    .with_tex_coord(Vector2::try_from_le_bytes(uv)?)
-                    .with_tangent(Vector4::try_from_le_bytes(tangent)?)
-                    .build(),
-            );
+            let mut builder = VertexBuilder::new().with_pos(Vector3::try_from_le_bytes(pos)?);
+            if let Some(normal) = next_element(&mut self.norm, "Missing normal data")? {
+                builder = builder.with_normal(Vector3::try_from_le_bytes(normal)?);
+            }
+            if let Some(uv) = next_element(&mut self.uv, "Missing uv data")? {
+                builder = builder.with_tex_coord(Vector2::try_from_le_bytes(uv)?);
+            }
+            if let Some(tangent) = next_element(&mut self.tan, "Missing tangent data")? {
+                builder = builder.with_tangent(Vector4::try_from_le_bytes(tangent)?);
+            }
+            vertices.push(builder.build());
+        }
+        // Flat normals are required by the specification when normals are missing
+        let (indices, mut vertices) = if self.norm.is_none() {
+            compute_face_normals(&indices, &vertices)?
+        } else {
+            (indices, vertices)
+        };
+        if self.tan.is_none() {
+            compute_tangents(&indices, &mut vertices);
         }
         Ok((indices, vertices))
     }
@@ -312,14 +355,24 @@ impl<'a> PrimitiveReaderBuilder<'a> {
     fn build(self) -> Result<PrimitiveReader<'a>, Box<dyn Error>> {
         Ok(PrimitiveReader {
             pos: self.pos.ok_or("Missing position attribute")?,
-            norm: self.norm.ok_or("Missing normal attribute")?,
-            uv: self.uv.ok_or("Missing uv attribute")?,
-            tan: self.tan.ok_or("Missing tangent attribute")?,
-            indices: self.indices.ok_or("Missing vertex indices data")?,
+            norm: self.norm,
+            uv: self.uv,
+            tan: self.tan,
+            indices: self.indices,
         })
     }
 }
 
+// Reads all meshes of the document, materials are not loaded
+pub fn load_gltf(path: &Path) -> Result<Vec<Mesh<CommonVertex>>, Box<dyn Error>> {
+    let reader = DocumentReader::new(path)?;
+    reader
+        .document
+        .meshes()
+        .map(|mesh| reader.get_mesh(mesh))
+        .collect()
+}
+
 impl Mesh<CommonVertex> {
     pub fn load_gltf(path: &Path) -> Result<(Mesh<CommonVertex>, PbrMaterial), Box<dyn Error>> {
         let base = path.parent().unwrap_or(Path::new("./"));
@@ -336,3 +389,113 @@ impl Mesh<CommonVertex> {
         Ok((mesh, material))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vertex(x: f32, y: f32, z: f32) -> CommonVertex {
+        VertexBuilder::new().with_pos(Vector3::new(x, y, z)).build()
+    }
+
+    // Single triangle without normals, positions and u16 indices share one buffer
+    // embedded as base64 data uri
+    fn write_triangle_gltf() -> std::path::PathBuf {
+        let mut buffer = [[0.0f32, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]]
+            .iter()
+            .flatten()
+            .flat_map(|value| value.to_le_bytes())
+            .collect::<Vec<_>>();
+        buffer.extend([0u16, 1, 2, 0].iter().flat_map(|index| index.to_le_bytes()));
+        let uri = format!(
+            "data:application/octet-stream;base64,{}",
+            base64::engine::general_purpose::STANDARD.encode(&buffer)
+        );
+        let document = format!(
+            r#"{{
+                "asset": {{ "version": "2.0" }},
+                "buffers": [{{ "byteLength": {}, "uri": "{}" }}],
+                "bufferViews": [
+                    {{ "buffer": 0, "byteOffset": 0, "byteLength": 36 }},
+                    {{ "buffer": 0, "byteOffset": 36, "byteLength": 6 }}
+                ],
+                "accessors": [
+                    {{
+                        "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+                        "min": [0.0, 0.0, 0.0], "max": [1.0, 1.0, 0.0]
+                    }},
+                    {{ "bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR" }}
+                ],
+                "meshes": [{{
+                    "primitives": [{{ "attributes": {{ "POSITION": 0 }}, "indices": 1 }}]
+                }}]
+            }}"#,
+            buffer.len(),
+            uri
+        );
+        let path = std::env::temp_dir().join(format!("r_phy_triangle_{}.gltf", std::process::id()));
+        std::fs::write(&path, document).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_load_single_triangle() {
+        let path = write_triangle_gltf();
+        let meshes = load_gltf(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(meshes.len(), 1);
+        let mesh = &meshes[0];
+        assert_eq!(&*mesh.indices, &[0, 1, 2]);
+        assert_eq!(mesh.vertices.len(), 3);
+        // Missing normals are replaced by the unit face normal
+        for vertex in mesh.vertices.iter() {
+            assert!((vertex.norm.length() - 1.0).abs() < 1e-6);
+            assert!((vertex.norm.z - 1.0).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_face_normals_split_shared_vertices() {
+        // Quad folded along its diagonal, shared vertices take normal of each face
+        let vertices = [
+            vertex(0.0, 0.0, 0.0),
+            vertex(1.0, 0.0, 0.0),
+            vertex(0.0, 1.0, 0.0),
+            vertex(0.0, 0.0, 1.0),
+        ];
+        let (indices, face_vertices) =
+            compute_face_normals(&[0, 1, 2, 0, 3, 1], &vertices).unwrap();
+        assert_eq!(indices, (0..6).collect::<Vec<_>>());
+        assert_eq!(face_vertices.len(), 6);
+        for vertex in &face_vertices[..3] {
+            assert!((vertex.norm.z - 1.0).abs() < 1e-6);
+        }
+        for vertex in &face_vertices[3..] {
+            assert!((vertex.norm.y - 1.0).abs() < 1e-6);
+        }
+        assert!(face_vertices
+            .iter()
+            .all(|vertex| (vertex.norm.length() - 1.0).abs() < 1e-6));
+        assert!(compute_face_normals(&[0, 1, 4], &vertices).is_err());
+    }
+
+    #[test]
+    fn test_read_indices_of_each_component_size() {
+        let reader = |buffer: &'static [u8], element_size, stride| AttributeReader {
+            count: 3,
+            cursor: 0,
+            element_size,
+            view: BufferView { stride, buffer },
+        };
+        assert_eq!(read_indices(reader(&[0, 1, 2], 1, 0)).unwrap(), [0, 1, 2]);
+        // Interleaved view, every other u16 belongs to another attribute
+        let interleaved: &[u8] = &[3, 0, 9, 9, 4, 0, 9, 9, 0, 1];
+        assert_eq!(
+            read_indices(reader(interleaved, 2, 4)).unwrap(),
+            [3, 4, 256]
+        );
+        let wide: &[u8] = &[0, 0, 1, 0, 1, 0, 0, 0, 2, 0, 0, 0];
+        assert_eq!(read_indices(reader(wide, 4, 0)).unwrap(), [65536, 1, 2]);
+        assert!(read_indices(reader(&[0; 9], 3, 0)).is_err());
+    }
+}
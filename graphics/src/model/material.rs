@@ -3,11 +3,13 @@ use std::{
     any::TypeId, collections::HashMap, error::Error, marker::PhantomData, ops::Deref, path::PathBuf,
 };
 
-use bytemuck::AnyBitPattern;
+use bytemuck::{AnyBitPattern, Pod, Zeroable};
 
-use math::types::{Vector3, Vector4};
+use math::types::{Vector2, Vector3, Vector4};
 use type_kit::{Cons, Nil, TypedNil};
 
+use crate::renderer::ContextId;
+
 #[allow(dead_code)]
 pub const fn has_data<T: Material>() -> bool {
     T::NUM_IMAGES != 0 || size_of::<T::Uniform>() != 0
@@ -19,17 +21,123 @@ pub trait Material: 'static {
 
     fn images(&self) -> Option<impl Iterator<Item = &Image>>;
     fn uniform(&self) -> Option<&Self::Uniform>;
+
+    // Color space of the image at `index` of the `images` iterator
+    fn image_color_space(_index: usize) -> ImageColorSpace {
+        ImageColorSpace::Srgb
+    }
+
+    // Transparent materials are drawn after lighting, blended over the shaded image
+    fn transparent() -> bool {
+        false
+    }
+
+    // Sampler settings shared by all images of the material
+    // and transform applied to texture coordinates before sampling
+    fn sampling(&self) -> MaterialSampling {
+        MaterialSampling::default()
+    }
+}
+
+// Addressing of texture coordinates outside of the [0, 1] range
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AddressMode {
+    #[default]
+    Repeat,
+    MirroredRepeat,
+    ClampToEdge,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Filter {
+    Nearest,
+    #[default]
+    Linear,
+}
+
+// Texture coordinates are transformed as `uv * uv_scale + uv_offset`,
+// e.g. ground texture repeated 50 times uses `uv_scale` of 50.0 with `Repeat` mode
+#[derive(Debug, Clone, Copy)]
+pub struct MaterialSampling {
+    pub address_mode: AddressMode,
+    pub filter: Filter,
+    pub uv_scale: Vector2,
+    pub uv_offset: Vector2,
+}
+
+impl Default for MaterialSampling {
+    fn default() -> Self {
+        Self {
+            address_mode: AddressMode::default(),
+            filter: Filter::default(),
+            uv_scale: Vector2::new(1.0, 1.0),
+            uv_offset: Vector2::zero(),
+        }
+    }
+}
+
+impl MaterialSampling {
+    pub fn with_address_mode(self, address_mode: AddressMode) -> Self {
+        Self {
+            address_mode,
+            ..self
+        }
+    }
+
+    pub fn with_filter(self, filter: Filter) -> Self {
+        Self { filter, ..self }
+    }
+
+    pub fn with_uv_scale(self, uv_scale: Vector2) -> Self {
+        Self { uv_scale, ..self }
+    }
+
+    pub fn with_uv_offset(self, uv_offset: Vector2) -> Self {
+        Self { uv_offset, ..self }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub enum Image {
     Buffer(Vec<u8>),
     File(PathBuf),
+    // Texture added to the context builder, shared by all materials referencing it
+    Texture(TextureHandle),
+}
+
+// Color data is stored sRGB encoded and decoded when sampled,
+// normal and other non-color maps hold linear values
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ImageColorSpace {
+    #[default]
+    Srgb,
+    Linear,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextureHandle {
+    index: u32,
+    context: ContextId,
+}
+
+impl TextureHandle {
+    pub fn new(index: u32, context: ContextId) -> Self {
+        Self { index, context }
+    }
+
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    pub fn context(&self) -> ContextId {
+        self.context
+    }
 }
 
 #[derive(Debug)]
 pub struct MaterialHandle<M: Material> {
     index: u32,
+    context: ContextId,
     _phantom: PhantomData<M>,
 }
 
@@ -42,9 +150,10 @@ impl<M: Material> Clone for MaterialHandle<M> {
 impl<M: Material> Copy for MaterialHandle<M> {}
 
 impl<M: Material> MaterialHandle<M> {
-    pub fn new(index: u32) -> Self {
+    pub fn new(index: u32, context: ContextId) -> Self {
         Self {
             index,
+            context,
             _phantom: PhantomData,
         }
     }
@@ -52,10 +161,16 @@ impl<M: Material> MaterialHandle<M> {
     pub fn index(&self) -> u32 {
         self.index
     }
+
+    pub fn context(&self) -> ContextId {
+        self.context
+    }
 }
 
 pub struct UnlitMaterialBuilder {
     albedo: Option<Image>,
+    tint: Vector4,
+    sampling: MaterialSampling,
 }
 
 #[derive(Debug, Clone, Copy, Default)]
@@ -74,40 +189,92 @@ impl Material for EmptyMaterial {
     }
 }
 
+// Flat color material blended over the shaded scene,
+// alpha component of the color sets its opacity
+#[derive(Debug, Clone, Copy)]
+pub struct TransparentMaterial {
+    pub color: Vector4,
+}
+
+impl TransparentMaterial {
+    pub fn new(color: Vector4) -> Self {
+        Self { color }
+    }
+}
+
+impl Material for TransparentMaterial {
+    const NUM_IMAGES: usize = 0;
+    type Uniform = Vector4;
+
+    fn images(&self) -> Option<impl Iterator<Item = &Image>> {
+        Option::<slice::Iter<Image>>::None
+    }
+
+    fn uniform(&self) -> Option<&Self::Uniform> {
+        Some(&self.color)
+    }
+
+    fn transparent() -> bool {
+        true
+    }
+}
+
+// Albedo texture multiplied by the tint color
 #[derive(Debug, Clone)]
 pub struct UnlitMaterial {
     pub albedo: Image,
+    pub tint: Vector4,
+    pub sampling: MaterialSampling,
 }
 
 impl UnlitMaterialBuilder {
     pub fn build(self) -> Result<UnlitMaterial, Box<dyn Error>> {
         Ok(UnlitMaterial {
             albedo: self.albedo.ok_or("Albedo texture not provided!")?,
+            tint: self.tint,
+            sampling: self.sampling,
         })
     }
 
     pub fn with_albedo(self, image: Image) -> Self {
         Self {
             albedo: Some(image),
+            ..self
         }
     }
+
+    pub fn with_tint(self, tint: Vector4) -> Self {
+        Self { tint, ..self }
+    }
+
+    pub fn with_sampling(self, sampling: MaterialSampling) -> Self {
+        Self { sampling, ..self }
+    }
 }
 
 impl UnlitMaterial {
     pub fn builder() -> UnlitMaterialBuilder {
-        UnlitMaterialBuilder { albedo: None }
+        UnlitMaterialBuilder {
+            albedo: None,
+            tint: Vector4::new(1.0, 1.0, 1.0, 1.0),
+            sampling: MaterialSampling::default(),
+        }
     }
 }
 
 impl Material for UnlitMaterial {
     const NUM_IMAGES: usize = 1;
-    type Uniform = ();
+    type Uniform = Vector4;
 
     fn images(&self) -> Option<impl Iterator<Item = &Image>> {
         Some([&self.albedo].into_iter())
     }
     fn uniform(&self) -> Option<&Self::Uniform> {
-        None
+        Some(&self.tint)
+    }
+
+    fn sampling(&self) -> MaterialSampling {
+        self.sampling
     }
 }
 
@@ -120,15 +287,31 @@ pub enum PbrMaps {
     Emissive,
 }
 
+// Factors multiplied with the values sampled from the corresponding maps,
+// field layout follows std140 layout of the shader uniform block
 #[repr(C, align(16))]
-#[derive(Debug, Clone, Copy, Default, AnyBitPattern)]
+#[derive(Debug, Clone, Copy, Zeroable, Pod)]
 pub struct PbrFactors {
     pub base_color: Vector4,
     pub emissive: Vector3,
-    _padding: f32,
     pub metallic: f32,
     pub roughness: f32,
     pub occlusion: f32,
+    _padding: [f32; 2],
+}
+
+// Defaults of the glTF material model, maps are used unscaled
+impl Default for PbrFactors {
+    fn default() -> Self {
+        Self {
+            base_color: Vector4::new(1.0, 1.0, 1.0, 1.0),
+            emissive: Vector3::zero(),
+            metallic: 1.0,
+            roughness: 1.0,
+            occlusion: 1.0,
+            _padding: [0.0; 2],
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -140,6 +323,7 @@ pub struct PbrImages {
 pub struct PbrMaterial {
     images: PbrImages,
     factors: PbrFactors,
+    sampling: MaterialSampling,
 }
 
 impl PbrMaterial {
@@ -147,6 +331,7 @@ impl PbrMaterial {
         PbrMaterialBuilder {
             images: Default::default(),
             factors: Default::default(),
+            sampling: Default::default(),
         }
     }
 }
@@ -155,6 +340,7 @@ impl PbrMaterial {
 pub struct PbrMaterialBuilder {
     images: [Option<Image>; 5],
     factors: PbrFactors,
+    sampling: MaterialSampling,
 }
 
 impl PbrMaterialBuilder {
@@ -162,6 +348,7 @@ impl PbrMaterialBuilder {
         let Self {
             images: [albedo, normal, metallic_roughness, occlusion, emissive],
             factors,
+            sampling,
         } = self;
         Ok(PbrMaterial {
             images: PbrImages {
@@ -174,6 +361,7 @@ impl PbrMaterialBuilder {
                 ],
             },
             factors,
+            sampling,
         })
     }
 
@@ -206,6 +394,11 @@ impl PbrMaterialBuilder {
         self.factors.emissive = emissive;
         self
     }
+
+    pub fn with_sampling(mut self, sampling: MaterialSampling) -> Self {
+        self.sampling = sampling;
+        self
+    }
 }
 
 impl Material for PbrMaterial {
@@ -219,6 +412,19 @@ impl Material for PbrMaterial {
     fn uniform(&self) -> Option<&Self::Uniform> {
         Some(&self.factors)
     }
+
+    fn sampling(&self) -> MaterialSampling {
+        self.sampling
+    }
+
+    fn image_color_space(index: usize) -> ImageColorSpace {
+        const ALBEDO: usize = PbrMaps::Albedo as usize;
+        const EMISSIVE: usize = PbrMaps::Emissive as usize;
+        match index {
+            ALBEDO | EMISSIVE => ImageColorSpace::Srgb,
+            _ => ImageColorSpace::Linear,
+        }
+    }
 }
 
 pub trait MaterialTypeList: 'static {
@@ -308,3 +514,47 @@ impl<N: MaterialTypeList> Deref for Materials<N> {
         &self.list
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pbr_factors_pack_into_uniform() {
+        let image = || Image::Buffer(Vec::new());
+        let material = [
+            PbrMaps::Albedo,
+            PbrMaps::Normal,
+            PbrMaps::MetallicRoughness,
+            PbrMaps::Occlusion,
+            PbrMaps::Emissive,
+        ]
+        .into_iter()
+        .fold(PbrMaterial::builder(), |builder, map| {
+            builder.with_image(image(), map)
+        })
+        .with_base_color(Vector4::new(0.1, 0.2, 0.3, 0.4))
+        .with_emissive(Vector3::new(0.5, 0.6, 0.7))
+        .with_metallic(0.8)
+        .with_roughness(0.9)
+        .with_occlusion(0.25)
+        .build()
+        .unwrap();
+        // Metallic fills the last component of the emissive vec3 in std140 layout
+        let expected: [f32; 12] = [0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8, 0.9, 0.25, 0.0, 0.0];
+        assert_eq!(
+            bytemuck::bytes_of(material.uniform().unwrap()),
+            bytemuck::bytes_of(&expected)
+        );
+    }
+
+    #[test]
+    fn test_unlit_tint_defaults_to_white() {
+        let material = UnlitMaterial::builder()
+            .with_albedo(Image::Buffer(Vec::new()))
+            .build()
+            .unwrap();
+        let tint: &[f32; 4] = bytemuck::cast_ref(material.uniform().unwrap());
+        assert_eq!(tint, &[1.0; 4]);
+    }
+}
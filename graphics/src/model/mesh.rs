@@ -1,24 +1,57 @@
+mod packed;
+
+pub use packed::*;
+
 use std::{marker::PhantomData, mem::offset_of, ops::Deref};
 
 use bytemuck::{Pod, Zeroable};
 
-use math::types::{Vector2, Vector3, Vector4};
+use math::{
+    geometry::Aabb,
+    types::{Vector2, Vector3, Vector4},
+};
 use physics::shape;
 use type_kit::{Cons, Nil, TypedNil};
 
+use crate::renderer::ContextId;
+
+// Memory format of the vertex component, normalized formats are read by shaders
+// as floats in [0, 1] (unsigned) or [-1, 1] (signed) range
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComponentFormat {
+    Float,
+    Float2,
+    Float3,
+    Float4,
+    Half2,
+    Snorm16x2,
+    Unorm16x4,
+}
+
+impl ComponentFormat {
+    pub const fn size(self) -> usize {
+        match self {
+            ComponentFormat::Float | ComponentFormat::Half2 | ComponentFormat::Snorm16x2 => 4,
+            ComponentFormat::Float2 | ComponentFormat::Unorm16x4 => 8,
+            ComponentFormat::Float3 => 12,
+            ComponentFormat::Float4 => 16,
+        }
+    }
+}
+
 pub struct Component {
-    pub size: usize,
+    pub format: ComponentFormat,
     pub offset: usize,
 }
 
 pub trait Vertex: Pod + Zeroable {
-    fn pos(&mut self) -> &mut Vector3;
     fn components() -> &'static [Component];
 }
 
 #[derive(Debug)]
 pub struct MeshHandle<V: Vertex> {
     index: u32,
+    context: ContextId,
     _marker: PhantomData<V>,
 }
 
@@ -31,9 +64,10 @@ impl<V: Vertex> Clone for MeshHandle<V> {
 impl<V: Vertex> Copy for MeshHandle<V> {}
 
 impl<V: Vertex> MeshHandle<V> {
-    pub fn new(index: u32) -> Self {
+    pub fn new(index: u32, context: ContextId) -> Self {
         Self {
             index,
+            context,
             _marker: PhantomData,
         }
     }
@@ -41,6 +75,10 @@ impl<V: Vertex> MeshHandle<V> {
     pub fn index(&self) -> u32 {
         self.index
     }
+
+    pub fn context(&self) -> ContextId {
+        self.context
+    }
 }
 
 #[repr(C)]
@@ -54,30 +92,26 @@ pub struct CommonVertex {
 }
 
 impl Vertex for CommonVertex {
-    fn pos(&mut self) -> &mut Vector3 {
-        &mut self.pos
-    }
-
     fn components() -> &'static [Component] {
         const COMPONENTS: &'static [Component] = &[
             Component {
-                size: size_of::<Vector3>(),
+                format: ComponentFormat::Float3,
                 offset: offset_of!(CommonVertex, pos),
             },
             Component {
-                size: size_of::<Vector3>(),
+                format: ComponentFormat::Float3,
                 offset: offset_of!(CommonVertex, color),
             },
             Component {
-                size: size_of::<Vector3>(),
+                format: ComponentFormat::Float3,
                 offset: offset_of!(CommonVertex, norm),
             },
             Component {
-                size: size_of::<Vector2>(),
+                format: ComponentFormat::Float2,
                 offset: offset_of!(CommonVertex, uv),
             },
             Component {
-                size: size_of::<Vector4>(),
+                format: ComponentFormat::Float4,
                 offset: offset_of!(CommonVertex, tan),
             },
         ];
@@ -94,22 +128,18 @@ pub struct SimpleVertex {
 }
 
 impl Vertex for SimpleVertex {
-    fn pos(&mut self) -> &mut Vector3 {
-        &mut self.pos
-    }
-
     fn components() -> &'static [Component] {
         const COMPONENTS: &'static [Component] = &[
             Component {
-                size: size_of::<Vector3>(),
+                format: ComponentFormat::Float3,
                 offset: offset_of!(SimpleVertex, pos),
             },
             Component {
-                size: size_of::<Vector3>(),
+                format: ComponentFormat::Float3,
                 offset: offset_of!(SimpleVertex, color),
             },
             Component {
-                size: size_of::<Vector3>(),
+                format: ComponentFormat::Float3,
                 offset: offset_of!(SimpleVertex, norm),
             },
         ];
@@ -170,16 +200,16 @@ impl<V: Vertex> MeshBuilder<V> {
         self.vertices.extend(&value.vertices);
         self
     }
+}
 
+impl MeshBuilder<CommonVertex> {
     pub fn offset(mut self, offset: Vector3) -> Self {
         for vert in &mut self.vertices {
-            *vert.pos() = *vert.pos() + offset;
+            vert.pos = vert.pos + offset;
         }
         self
     }
-}
 
-impl MeshBuilder<CommonVertex> {
     pub fn plane_subdivided(
         num_subdiv: usize,
         u: Vector3,
@@ -322,15 +352,129 @@ impl<V: Vertex + From<CommonVertex>> From<shape::Box> for Mesh<V> {
     }
 }
 
+// Triangles of the index list, ones referring to missing vertices are skipped
+fn triangles(indices: &[u32], num_vertices: usize) -> impl Iterator<Item = [usize; 3]> + '_ {
+    indices
+        .chunks_exact(3)
+        .map(|face| [face[0], face[1], face[2]].map(|index| index as usize))
+        .filter(move |face| face.iter().all(|&index| index < num_vertices))
+}
+
+// Unit length vector perpendicular to the unit length `normal`
+fn orthogonal(normal: Vector3) -> Vector3 {
+    let axis = if normal.x.abs() < 0.9 {
+        Vector3::x()
+    } else {
+        Vector3::y()
+    };
+    normal.cross(axis).norm()
+}
+
+// Vertices not belonging to any non-degenerate triangle take the z axis as the normal
+pub(crate) fn compute_normals(indices: &[u32], vertices: &mut [CommonVertex]) {
+    let mut normals = vec![Vector3::zero(); vertices.len()];
+    for [a, b, c] in triangles(indices, vertices.len()) {
+        // Length of the cross product is twice the triangle area,
+        // which weights the contribution of each face
+        let normal = (vertices[b].pos - vertices[a].pos).cross(vertices[c].pos - vertices[a].pos);
+        for index in [a, b, c] {
+            normals[index] = normals[index] + normal;
+        }
+    }
+    for (vertex, normal) in vertices.iter_mut().zip(normals) {
+        vertex.norm = if normal.length_square() > f32::EPSILON {
+            normal.norm()
+        } else {
+            Vector3::z()
+        };
+    }
+}
+
+// Tangents follow the u texture coordinate direction, with bitangent equal to
+// cross(norm, tan.xyz) * tan.w. Triangles with degenerate uv mapping are skipped,
+// vertices left without tangent take arbitrary one perpendicular to the normal
+pub(crate) fn compute_tangents(indices: &[u32], vertices: &mut [CommonVertex]) {
+    let mut tangents = vec![(Vector3::zero(), Vector3::zero()); vertices.len()];
+    for [a, b, c] in triangles(indices, vertices.len()) {
+        let (edge_1, edge_2) = (
+            vertices[b].pos - vertices[a].pos,
+            vertices[c].pos - vertices[a].pos,
+        );
+        let (delta_1, delta_2) = (
+            vertices[b].uv - vertices[a].uv,
+            vertices[c].uv - vertices[a].uv,
+        );
+        let det = delta_1.x * delta_2.y - delta_2.x * delta_1.y;
+        if det.abs() <= f32::EPSILON {
+            continue;
+        }
+        let tangent = (delta_2.y * edge_1 - delta_1.y * edge_2) / det;
+        let bitangent = (delta_1.x * edge_2 - delta_2.x * edge_1) / det;
+        for index in [a, b, c] {
+            let (t, b) = tangents[index];
+            tangents[index] = (t + tangent, b + bitangent);
+        }
+    }
+    for (vertex, (tangent, bitangent)) in vertices.iter_mut().zip(tangents) {
+        let normal = if vertex.norm.length_square() > f32::EPSILON {
+            vertex.norm.norm()
+        } else {
+            Vector3::z()
+        };
+        // Gram-Schmidt orthogonalization against the normal
+        let tangent = tangent - (normal * tangent) * normal;
+        let tangent = if tangent.length_square() > f32::EPSILON {
+            tangent.norm()
+        } else {
+            orthogonal(normal)
+        };
+        let handedness = if normal.cross(tangent) * bitangent < 0.0 {
+            -1.0
+        } else {
+            1.0
+        };
+        vertex.tan = Vector4::new(tangent.x, tangent.y, tangent.z, handedness);
+    }
+}
+
+impl<V: Vertex> Mesh<V> {
+    // Position is read from the first vertex component, None for meshes without vertices
+    // and for packed positions, which are meaningful only with the mesh dequantization
+    pub fn aabb(&self) -> Option<Aabb> {
+        let position = V::components().first()?;
+        let size = match position.format {
+            ComponentFormat::Float2 | ComponentFormat::Float3 | ComponentFormat::Float4 => {
+                position.format.size().min(size_of::<Vector3>())
+            }
+            _ => return None,
+        };
+        Aabb::from_points(self.vertices.iter().map(|vertex| {
+            let mut pos = [0.0f32; 3];
+            bytemuck::cast_slice_mut::<_, u8>(&mut pos)[..size].copy_from_slice(
+                &bytemuck::bytes_of(vertex)[position.offset..position.offset + size],
+            );
+            Vector3::from(pos)
+        }))
+    }
+}
+
+impl Mesh<CommonVertex> {
+    // Area weighted average of normals of the faces sharing the vertex
+    pub fn recompute_normals(&mut self) {
+        compute_normals(&self.indices, &mut self.vertices);
+    }
+
+    // Requires valid vertex normals, see `recompute_normals`
+    pub fn recompute_tangents(&mut self) {
+        compute_tangents(&self.indices, &mut self.vertices);
+    }
+}
+
 #[repr(C)]
 #[derive(Debug, Clone, Copy, Pod, Zeroable)]
 pub struct VertexNone {}
 
 impl Vertex for VertexNone {
-    fn pos(&mut self) -> &mut Vector3 {
-        unreachable!()
-    }
-
     fn components() -> &'static [Component] {
         unreachable!()
     }
@@ -412,3 +556,108 @@ impl<L: MeshTypeList> Deref for Meshes<L> {
         &self.list
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vertex(pos: Vector3, uv: Vector2) -> CommonVertex {
+        CommonVertex {
+            pos,
+            uv,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_aabb_from_vertex_positions() {
+        let cube: Mesh<CommonVertex> = shape::Cube::new(2.0).into();
+        let aabb = cube.aabb().unwrap();
+        assert!(aabb.min.approx_equal(-Vector3::new(1.0, 1.0, 1.0)));
+        assert!(aabb.max.approx_equal(Vector3::new(1.0, 1.0, 1.0)));
+        let simple: Mesh<SimpleVertex> = shape::Cube::new(2.0).into();
+        assert!(simple.aabb().unwrap().max.approx_equal(aabb.max));
+        let empty = Mesh::<CommonVertex> {
+            vertices: Box::new([]),
+            indices: Box::new([]),
+        };
+        assert!(empty.aabb().is_none());
+        // Packed positions are normalized to the mesh bounds
+        assert!(cube.quantize().mesh.aabb().is_none());
+    }
+
+    #[test]
+    fn test_triangle_normal_and_tangent() {
+        let mut mesh = Mesh {
+            vertices: Box::new([
+                vertex(Vector3::zero(), Vector2::new(0.0, 0.0)),
+                vertex(Vector3::x(), Vector2::new(1.0, 0.0)),
+                vertex(Vector3::y(), Vector2::new(0.0, 1.0)),
+            ]),
+            indices: Box::new([0, 1, 2]),
+        };
+        mesh.recompute_normals();
+        mesh.recompute_tangents();
+        for vertex in mesh.vertices.iter() {
+            assert!(vertex.norm.approx_equal(Vector3::z()));
+            assert!(vertex.tan.approx_equal(Vector4::new(1.0, 0.0, 0.0, 1.0)));
+        }
+    }
+
+    #[test]
+    fn test_mirrored_uv_flips_handedness() {
+        let mut mesh = Mesh {
+            vertices: Box::new([
+                vertex(Vector3::zero(), Vector2::new(0.0, 0.0)),
+                vertex(Vector3::x(), Vector2::new(1.0, 0.0)),
+                vertex(Vector3::y(), Vector2::new(0.0, -1.0)),
+            ]),
+            indices: Box::new([0, 1, 2]),
+        };
+        mesh.recompute_normals();
+        mesh.recompute_tangents();
+        for vertex in mesh.vertices.iter() {
+            assert!(vertex.tan.approx_equal(Vector4::new(1.0, 0.0, 0.0, -1.0)));
+        }
+    }
+
+    #[test]
+    fn test_cube_normals_match_faces() {
+        let mut mesh: Mesh<CommonVertex> = shape::Cube::new(2.0).into();
+        // Face normals are set by the mesh builder, each vertex belongs to single face
+        let expected = mesh
+            .vertices
+            .iter()
+            .map(|vertex| vertex.norm)
+            .collect::<Vec<_>>();
+        mesh.recompute_normals();
+        mesh.recompute_tangents();
+        for (vertex, norm) in mesh.vertices.iter().zip(expected) {
+            assert!(vertex.norm.approx_equal(norm));
+            assert!(vertex.norm * vertex.pos > 0.0);
+            let tangent = Vector3::from(vertex.tan);
+            assert!((tangent.length() - 1.0).abs() < 1e-5);
+            assert!((tangent * vertex.norm).abs() < 1e-5);
+            assert!(vertex.tan.w.abs() == 1.0);
+        }
+    }
+
+    #[test]
+    fn test_degenerate_triangle_produces_finite_basis() {
+        let mut mesh = Mesh {
+            vertices: Box::new([
+                vertex(Vector3::zero(), Vector2::zero()),
+                vertex(Vector3::x(), Vector2::zero()),
+                vertex(2.0 * Vector3::x(), Vector2::zero()),
+            ]),
+            indices: Box::new([0, 1, 2]),
+        };
+        mesh.recompute_normals();
+        mesh.recompute_tangents();
+        for vertex in mesh.vertices.iter() {
+            assert!(vertex.norm.is_valid() && vertex.tan.is_valid());
+            assert!((vertex.norm.length() - 1.0).abs() < 1e-5);
+            assert!((Vector3::from(vertex.tan) * vertex.norm).abs() < 1e-5);
+        }
+    }
+}
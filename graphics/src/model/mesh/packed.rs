@@ -0,0 +1,222 @@
+use std::mem::offset_of;
+
+use bytemuck::{Pod, Zeroable};
+use math::{
+    geometry::Aabb,
+    types::{Matrix4, Vector3, Vector4},
+};
+
+use super::{CommonVertex, Component, ComponentFormat, Mesh, Vertex};
+
+// Static mesh vertex with positions normalized to the mesh bounding box,
+// octahedral encoded normals and half precision texture coordinates
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, Zeroable, Pod)]
+pub struct PackedStaticVertex {
+    pub(crate) pos: [u16; 4],
+    pub(crate) norm: [i16; 2],
+    pub(crate) uv: [u16; 2],
+}
+
+impl Vertex for PackedStaticVertex {
+    fn components() -> &'static [Component] {
+        const COMPONENTS: &[Component] = &[
+            Component {
+                format: ComponentFormat::Unorm16x4,
+                offset: offset_of!(PackedStaticVertex, pos),
+            },
+            Component {
+                format: ComponentFormat::Snorm16x2,
+                offset: offset_of!(PackedStaticVertex, norm),
+            },
+            Component {
+                format: ComponentFormat::Half2,
+                offset: offset_of!(PackedStaticVertex, uv),
+            },
+        ];
+        COMPONENTS
+    }
+}
+
+pub struct QuantizedMesh {
+    pub mesh: Mesh<PackedStaticVertex>,
+    // Maps normalized positions back into the mesh space,
+    // must be applied before the model matrix of each instance
+    pub dequantize: Matrix4,
+}
+
+// Rounds to nearest, values out of the half range saturate to infinity
+fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+    if exponent == 0xff {
+        let nan = if mantissa != 0 { 0x200 } else { 0 };
+        return sign | 0x7c00 | nan;
+    }
+    let exponent = exponent - 127 + 15;
+    if exponent >= 0x1f {
+        return sign | 0x7c00;
+    }
+    if exponent <= 0 {
+        if exponent < -10 {
+            return sign;
+        }
+        let mantissa = mantissa | 0x80_0000;
+        let shift = (14 - exponent) as u32;
+        let round = (mantissa >> (shift - 1)) & 1;
+        return sign | ((mantissa >> shift) + round) as u16;
+    }
+    // Carry of the rounding propagates into the exponent
+    let half = ((exponent as u32) << 10) | (mantissa >> 13);
+    let round = (mantissa >> 12) & 1;
+    sign | (half + round) as u16
+}
+
+fn sign_not_zero(value: f32) -> f32 {
+    if value >= 0.0 {
+        1.0
+    } else {
+        -1.0
+    }
+}
+
+// Projects unit vector onto octahedron unfolded into [-1, 1] square
+fn encode_octahedral(normal: Vector3) -> [i16; 2] {
+    let l1 = normal.x.abs() + normal.y.abs() + normal.z.abs();
+    let normal = if l1 > f32::EPSILON {
+        normal / l1
+    } else {
+        Vector3::z()
+    };
+    let (x, y) = if normal.z >= 0.0 {
+        (normal.x, normal.y)
+    } else {
+        (
+            (1.0 - normal.y.abs()) * sign_not_zero(normal.x),
+            (1.0 - normal.x.abs()) * sign_not_zero(normal.y),
+        )
+    };
+    [x, y].map(|value| (value.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16)
+}
+
+impl Mesh<CommonVertex> {
+    // Vertex colors and tangents are dropped. Normals are scaled by the bounding box
+    // extent, so that inverse transpose of model matrix with dequantization baked in
+    // maps them back to the mesh space normals
+    pub fn quantize(&self) -> QuantizedMesh {
+        let Aabb { min, max } = self
+            .aabb()
+            .unwrap_or(Aabb::new(Vector3::zero(), Vector3::zero()));
+        // Flat axes keep unit extent, so that dequantization stays invertible
+        let extent = Vector3::from([0, 1, 2].map(|axis| match max[axis] - min[axis] {
+            extent if extent > f32::EPSILON => extent,
+            _ => 1.0,
+        }));
+        let vertices = self
+            .vertices
+            .iter()
+            .map(|vertex| {
+                let pos = vertex.pos - min;
+                let [x, y, z] = [pos.x / extent.x, pos.y / extent.y, pos.z / extent.z]
+                    .map(|value| (value.clamp(0.0, 1.0) * u16::MAX as f32).round() as u16);
+                PackedStaticVertex {
+                    pos: [x, y, z, 0],
+                    norm: encode_octahedral(vertex.norm.hadamard(extent)),
+                    uv: [f32_to_f16(vertex.uv.x), f32_to_f16(vertex.uv.y)],
+                }
+            })
+            .collect();
+        let dequantize = Matrix4::new(
+            Vector4::vector(extent.x * Vector3::x()),
+            Vector4::vector(extent.y * Vector3::y()),
+            Vector4::vector(extent.z * Vector3::z()),
+            Vector4::point(min),
+        );
+        QuantizedMesh {
+            mesh: Mesh {
+                vertices,
+                indices: self.indices.clone(),
+            },
+            dequantize,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use math::types::Vector2;
+    use physics::shape;
+
+    use super::*;
+
+    fn decode_octahedral([x, y]: [i16; 2]) -> Vector3 {
+        let (x, y) = (x as f32 / i16::MAX as f32, y as f32 / i16::MAX as f32);
+        let z = 1.0 - x.abs() - y.abs();
+        let t = (-z).max(0.0);
+        Vector3::new(x - t * sign_not_zero(x), y - t * sign_not_zero(y), z).norm()
+    }
+
+    #[test]
+    fn test_f32_to_f16() {
+        assert_eq!(f32_to_f16(1.0), 0x3c00);
+        assert_eq!(f32_to_f16(0.5), 0x3800);
+        assert_eq!(f32_to_f16(-2.0), 0xc000);
+        assert_eq!(f32_to_f16(65504.0), 0x7bff);
+        assert_eq!(f32_to_f16(1.0e6), 0x7c00);
+        assert_eq!(f32_to_f16(2.0f32.powi(-24)), 0x0001);
+        assert_eq!(f32_to_f16(0.0), 0x0000);
+    }
+
+    #[test]
+    fn test_octahedral_round_trip() {
+        let normals = [
+            Vector3::x(),
+            -Vector3::y(),
+            -Vector3::z(),
+            Vector3::new(1.0, -2.0, -3.0).norm(),
+            Vector3::new(-0.3, 0.4, 0.5).norm(),
+        ];
+        for normal in normals {
+            let decoded = decode_octahedral(encode_octahedral(normal));
+            assert!((decoded - normal).length() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_quantized_cube_positions_and_size() {
+        let cube: Mesh<CommonVertex> = shape::Cube::new(2.0).into();
+        let QuantizedMesh { mesh, dequantize } = cube.quantize();
+        assert_eq!(mesh.indices, cube.indices);
+        assert!(size_of_val(&*mesh.vertices) * 3 < size_of_val(&*cube.vertices));
+        for (packed, vertex) in mesh.vertices.iter().zip(cube.vertices.iter()) {
+            let [x, y, z, _] = packed.pos.map(|value| value as f32 / u16::MAX as f32);
+            let pos = dequantize * Vector4::point(Vector3::new(x, y, z));
+            assert!((Vector3::from(pos) - vertex.pos).length() < 1e-4);
+            let normal = decode_octahedral(packed.norm);
+            let normal = dequantize.inv().transpose() * Vector4::vector(normal);
+            assert!((Vector3::from(normal).norm() - vertex.norm).length() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_quantize_flat_mesh() {
+        let mesh: Mesh<CommonVertex> = Mesh {
+            vertices: Box::new([Vector3::zero(), Vector3::x(), Vector3::y()].map(|pos| {
+                CommonVertex {
+                    pos,
+                    norm: Vector3::z(),
+                    uv: Vector2::new(pos.x, pos.y),
+                    ..Default::default()
+                }
+            })),
+            indices: Box::new([0, 1, 2]),
+        };
+        let QuantizedMesh { mesh, dequantize } = mesh.quantize();
+        assert!(dequantize.is_valid() && dequantize.det().abs() > 0.0);
+        assert_eq!(mesh.vertices[1].pos, [u16::MAX, 0, 0, 0]);
+        assert_eq!(mesh.vertices[2].uv, [0x0000, 0x3c00]);
+        assert!((decode_octahedral(mesh.vertices[0].norm) - Vector3::z()).length() < 1e-3);
+    }
+}
@@ -0,0 +1,280 @@
+use std::{collections::HashMap, error::Error, fs, path::Path, str::SplitWhitespace};
+
+use math::types::{Vector2, Vector3};
+
+use super::{CommonVertex, Mesh};
+
+// Mesh of single material group along with the material name
+pub type ObjMesh = (Mesh<CommonVertex>, String);
+
+// Zero based indices of position, texture coordinate and normal of the face vertex
+type VertexKey = (usize, Option<usize>, Option<usize>);
+
+#[derive(Debug, Default)]
+struct MeshGroup {
+    material: String,
+    vertices: Vec<CommonVertex>,
+    indices: Vec<u32>,
+    lookup: HashMap<VertexKey, u32>,
+    missing_normals: bool,
+}
+
+impl MeshGroup {
+    fn new(material: &str) -> Self {
+        Self {
+            material: material.to_owned(),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct ObjReader {
+    positions: Vec<Vector3>,
+    tex_coords: Vec<Vector2>,
+    normals: Vec<Vector3>,
+    groups: Vec<MeshGroup>,
+    current: Option<usize>,
+}
+
+fn parse_floats<const N: usize>(args: SplitWhitespace) -> Result<[f32; N], Box<dyn Error>> {
+    let mut values = [0.0; N];
+    let mut args = args;
+    for value in values.iter_mut() {
+        *value = args.next().ok_or("Missing vertex component")?.parse()?;
+    }
+    Ok(values)
+}
+
+// OBJ indices are one based, negative ones are relative to the end of the list
+fn resolve_index(index: &str, len: usize) -> Result<usize, Box<dyn Error>> {
+    let index: isize = index.parse()?;
+    let resolved = match index {
+        0 => None,
+        1.. => Some(index as usize - 1),
+        _ => len.checked_sub(index.unsigned_abs()),
+    };
+    resolved
+        .filter(|&index| index < len)
+        .ok_or_else(|| format!("Face index {} out of range", index).into())
+}
+
+impl ObjReader {
+    fn use_material(&mut self, material: &str) {
+        let index = match self
+            .groups
+            .iter()
+            .position(|group| group.material == material)
+        {
+            Some(index) => index,
+            None => {
+                self.groups.push(MeshGroup::new(material));
+                self.groups.len() - 1
+            }
+        };
+        self.current = Some(index);
+    }
+
+    fn resolve_vertex(&self, vertex: &str) -> Result<VertexKey, Box<dyn Error>> {
+        let mut it = vertex.split('/');
+        let pos = resolve_index(
+            it.next().ok_or("Missing position index")?,
+            self.positions.len(),
+        )?;
+        let tex_coord = match it.next() {
+            Some(index) if !index.is_empty() => Some(resolve_index(index, self.tex_coords.len())?),
+            _ => None,
+        };
+        let normal = match it.next() {
+            Some(index) if !index.is_empty() => Some(resolve_index(index, self.normals.len())?),
+            _ => None,
+        };
+        Ok((pos, tex_coord, normal))
+    }
+
+    fn get_vertex(&self, (pos, tex_coord, normal): VertexKey) -> CommonVertex {
+        CommonVertex {
+            pos: self.positions[pos],
+            norm: normal.map_or(Vector3::zero(), |index| self.normals[index]),
+            uv: tex_coord.map_or(Vector2::zero(), |index| self.tex_coords[index]),
+            ..Default::default()
+        }
+    }
+
+    // Polygons with more than three vertices are triangulated as a fan around the first one
+    fn add_face(&mut self, args: SplitWhitespace) -> Result<(), Box<dyn Error>> {
+        let keys = args
+            .map(|vertex| self.resolve_vertex(vertex))
+            .collect::<Result<Vec<_>, _>>()?;
+        if keys.len() < 3 {
+            Err("Face with less than three vertices")?;
+        }
+        let vertices = keys
+            .iter()
+            .map(|&key| (key, self.get_vertex(key)))
+            .collect::<Vec<_>>();
+        if self.current.is_none() {
+            self.use_material("");
+        }
+        let group = &mut self.groups[self.current.unwrap()];
+        group.missing_normals |= keys.iter().any(|&(_, _, normal)| normal.is_none());
+        let indices = vertices
+            .into_iter()
+            .map(|(key, vertex)| {
+                *group.lookup.entry(key).or_insert_with(|| {
+                    group.vertices.push(vertex);
+                    group.vertices.len() as u32 - 1
+                })
+            })
+            .collect::<Vec<_>>();
+        for i in 1..(indices.len() - 1) {
+            group
+                .indices
+                .extend_from_slice(&[indices[0], indices[i], indices[i + 1]]);
+        }
+        Ok(())
+    }
+
+    fn read_line(&mut self, line: &str) -> Result<(), Box<dyn Error>> {
+        let line = line.split('#').next().unwrap_or_default();
+        let mut args = line.split_whitespace();
+        match args.next() {
+            Some("v") => {
+                let [x, y, z] = parse_floats(args)?;
+                self.positions.push(Vector3::new(x, y, z));
+            }
+            Some("vt") => {
+                // OBJ texture coordinates origin is at the bottom left corner
+                let [u, v] = parse_floats(args)?;
+                self.tex_coords.push(Vector2::new(u, 1.0 - v));
+            }
+            Some("vn") => {
+                let [x, y, z] = parse_floats(args)?;
+                self.normals.push(Vector3::new(x, y, z));
+            }
+            Some("f") => self.add_face(args)?,
+            Some("usemtl") => self.use_material(args.next().unwrap_or_default()),
+            _ => (),
+        }
+        Ok(())
+    }
+
+    fn build(self) -> Vec<ObjMesh> {
+        self.groups
+            .into_iter()
+            .filter(|group| !group.indices.is_empty())
+            .map(|group| {
+                let mut mesh = Mesh {
+                    vertices: group.vertices.into_boxed_slice(),
+                    indices: group.indices.into_boxed_slice(),
+                };
+                // Normals are smoothed across faces sharing the vertex,
+                // tangents are never provided by the format
+                if group.missing_normals {
+                    mesh.recompute_normals();
+                }
+                mesh.recompute_tangents();
+                (mesh, group.material)
+            })
+            .collect()
+    }
+}
+
+// Meshes are split by `usemtl` statements, faces preceding the first one
+// are returned with empty material name
+pub fn parse_obj(source: &str) -> Result<Vec<ObjMesh>, Box<dyn Error>> {
+    let mut reader = ObjReader::default();
+    for (number, line) in source.lines().enumerate() {
+        reader
+            .read_line(line)
+            .map_err(|error| format!("Line {}: {}", number + 1, error))?;
+    }
+    Ok(reader.build())
+}
+
+pub fn load_obj(path: &Path) -> Result<Vec<ObjMesh>, Box<dyn Error>> {
+    parse_obj(&fs::read_to_string(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QUADS: &str = "
+        # Unit square in xy plane split into two quads by material
+        v 0.0 0.0 0.0
+        v 1.0 0.0 0.0
+        v 1.0 1.0 0.0
+        v 0.0 1.0 0.0
+        v 2.0 0.0 0.0
+        v 2.0 1.0 0.0
+        vt 0.0 0.0
+        vt 1.0 1.0
+        vn 0.0 0.0 1.0
+        f 1 2 3
+        usemtl first
+        f 1/1/1 2/1/1 3/2/1 4/2/1
+        usemtl second
+        f -5/1/1 -2/1/1 -1/2/1 -4/2/1
+        usemtl first
+        f 1/1/1 3/2/1 4/2/1
+    ";
+
+    #[test]
+    fn test_faces_split_by_material() {
+        let meshes = parse_obj(QUADS).unwrap();
+        let materials = meshes
+            .iter()
+            .map(|(_, material)| material.as_str())
+            .collect::<Vec<_>>();
+        // Faces preceding the first usemtl form group with empty name,
+        // returning to a material appends faces to its existing group
+        assert_eq!(materials, ["", "first", "second"]);
+        let (untextured, _) = &meshes[0];
+        assert_eq!(&*untextured.indices, &[0, 1, 2]);
+    }
+
+    #[test]
+    fn test_quads_fan_triangulated_with_shared_vertices() {
+        let meshes = parse_obj(QUADS).unwrap();
+        let (first, _) = &meshes[1];
+        // Quad fan around its first vertex followed by triangle reusing three of its vertices
+        assert_eq!(&*first.indices, &[0, 1, 2, 0, 2, 3, 0, 2, 3]);
+        assert_eq!(first.vertices.len(), 4);
+        let uv = first.vertices[2].uv;
+        // Texture coordinates are flipped to the top left origin
+        assert_eq!((uv.x, uv.y), (1.0, 0.0));
+    }
+
+    #[test]
+    fn test_negative_indices_relative_to_list_end() {
+        let meshes = parse_obj(QUADS).unwrap();
+        let (second, _) = &meshes[2];
+        assert_eq!(&*second.indices, &[0, 1, 2, 0, 2, 3]);
+        let positions = second
+            .vertices
+            .iter()
+            .map(|vertex| (vertex.pos.x, vertex.pos.y))
+            .collect::<Vec<_>>();
+        assert_eq!(positions, [(1.0, 0.0), (2.0, 0.0), (2.0, 1.0), (1.0, 1.0)]);
+    }
+
+    #[test]
+    fn test_vertex_with_distinct_attributes_not_shared() {
+        let meshes =
+            parse_obj("v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\nvt 1 0\nf 1/1 2/1 3/1\nf 1/2 3/1 2/1\n")
+                .unwrap();
+        let (mesh, _) = &meshes[0];
+        // Only the first position is referenced with another texture coordinate
+        assert_eq!(mesh.vertices.len(), 4);
+        assert_eq!(&*mesh.indices, &[0, 1, 2, 3, 2, 1]);
+    }
+
+    #[test]
+    fn test_out_of_range_index_reports_line() {
+        let Err(error) = parse_obj("v 0 0 0\nf 1 2 -3\n") else {
+            panic!("Face index out of range accepted");
+        };
+        assert!(error.to_string().starts_with("Line 2:"));
+    }
+}
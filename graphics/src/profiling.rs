@@ -0,0 +1,35 @@
+// Spans of the frame stages recorded with the `profiling` feature,
+// all of them share the `r_phy` target so they can be filtered together.
+// Without the feature `profile_scope!` expands to nothing
+#[cfg(feature = "profiling")]
+pub use tracing;
+
+// Span stays entered until the end of the enclosing block,
+// so scopes opened in nested calls show up as its children
+#[cfg(feature = "profiling")]
+#[macro_export]
+macro_rules! profile_scope {
+    ($name:literal) => {
+        let _profile_scope =
+            $crate::profiling::tracing::info_span!(target: "r_phy", $name).entered();
+    };
+}
+
+#[cfg(not(feature = "profiling"))]
+#[macro_export]
+macro_rules! profile_scope {
+    ($name:literal) => {};
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_profile_scope_in_nested_blocks() {
+        profile_scope!("outer");
+        let value = {
+            profile_scope!("inner");
+            1
+        };
+        assert_eq!(value, 1);
+    }
+}
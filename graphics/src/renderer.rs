@@ -1,24 +1,108 @@
 pub mod camera;
+pub mod light;
+pub mod stats;
+pub mod viewport;
 
-use math::types::Matrix4;
-use std::error::Error;
+use math::geometry::Aabb;
+use math::types::{Matrix4, Vector4};
+use std::{
+    error::Error,
+    fmt::Display,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU32, Ordering},
+};
 use type_kit::Nil;
-use winit::window::Window;
+use winit::window::{Window, WindowId};
 
 use crate::{
     model::Drawable,
     shader::{ShaderHandle, ShaderType},
 };
 
-use self::camera::Camera;
+use self::{camera::Camera, light::DirectionalLight, stats::RenderStats, viewport::Rect};
+
+// Identifies the context builder handles were created with, each builder is assigned
+// a unique id, so handles of one context are rejected by the others
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ContextId(u32);
+
+impl ContextId {
+    pub fn next() -> Self {
+        static NEXT_ID: AtomicU32 = AtomicU32::new(0);
+        Self(NEXT_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+impl Display for ContextId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "context #{}", self.0)
+    }
+}
+
+pub type ScreenshotResult = Result<PathBuf, Box<dyn Error + Send + Sync>>;
+pub type ScreenshotCallback = Box<dyn FnOnce(ScreenshotResult) + Send>;
+
+// Failures of the renderer context the caller may react to,
+// remaining ones are passed through as `Other`
+#[derive(Debug)]
+pub enum RenderError {
+    DeviceLost,
+    OutOfDeviceMemory,
+    OutOfHostMemory,
+    // Handle not created by the builder of the context, or not loaded into it
+    InvalidHandle(String),
+    // Swapchain no longer matches the window surface, the frame should be skipped
+    // and the renderer resized before the next one is started
+    SwapchainOutOfDate,
+    Other(Box<dyn Error>),
+}
+
+impl Display for RenderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RenderError::DeviceLost => write!(f, "Device lost"),
+            RenderError::OutOfDeviceMemory => write!(f, "Out of device memory"),
+            RenderError::OutOfHostMemory => write!(f, "Out of host memory"),
+            RenderError::InvalidHandle(error) => write!(f, "Invalid handle: {}", error),
+            RenderError::SwapchainOutOfDate => write!(f, "Swapchain out of date"),
+            RenderError::Other(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl Error for RenderError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RenderError::Other(error) => Some(error.as_ref()),
+            _ => None,
+        }
+    }
+}
 
-pub trait Renderer: 'static {}
+pub trait Renderer: 'static {
+    fn resize(&mut self, width: u32, height: u32) -> Result<(), Box<dyn Error>>;
+    // Additional windows share resources of renderer contexts with the primary one,
+    // frames are rendered into them with `RendererContext::begin_frame_for`
+    fn add_window(&mut self, window: &Window) -> Result<WindowId, Box<dyn Error>>;
+    fn remove_window(&mut self, window: WindowId) -> Result<(), Box<dyn Error>>;
+    fn resize_window(
+        &mut self,
+        window: WindowId,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Box<dyn Error>>;
+}
 
 pub trait ContextBuilder {
     type Renderer: Renderer;
     type Context: RendererContext<Renderer = Self::Renderer>;
 
     fn build(self, renderer: &Self::Renderer) -> Result<Self::Context, Box<dyn Error>>;
+    // Cube map drawn behind the scene each frame, `path` is a directory with right, left,
+    // top, bottom, front and back face images, missing faces are reported immediately
+    fn with_skybox(self, path: &Path) -> Result<Self, Box<dyn Error>>
+    where
+        Self: Sized;
 }
 
 pub trait RendererContext: 'static {
@@ -27,14 +111,57 @@ pub trait RendererContext: 'static {
     type Materials;
     type Meshes;
 
-    fn begin_frame<C: Camera>(&mut self, camera: &C) -> Result<(), Box<dyn Error>>;
-    fn end_frame(&mut self) -> Result<(), Box<dyn Error>>;
+    fn begin_frame<C: Camera>(&mut self, camera: &C) -> Result<(), RenderError>;
+    // Begins frame presented in the window registered with `Renderer::add_window`,
+    // primary window id renders into the primary swapchain
+    fn begin_frame_for<C: Camera + ?Sized>(
+        &mut self,
+        window: WindowId,
+        camera: &C,
+    ) -> Result<(), RenderError>;
+    fn end_frame(&mut self) -> Result<(), RenderError>;
+    // Statistics of the frame finished by the latest `end_frame`
+    fn stats(&self) -> &RenderStats;
+    // Draws issued until `end_view` are rendered from `camera` into `viewport` region
+    // of the current frame, multiple views may be recorded within a single frame
+    fn begin_view<C: Camera + ?Sized>(
+        &mut self,
+        viewport: Rect,
+        camera: &C,
+    ) -> Result<(), RenderError>;
+    // Returns to the full frame view of the camera passed to `begin_frame`
+    fn end_view(&mut self) -> Result<(), RenderError>;
     fn draw<S: ShaderType, D: Drawable<Material = S::Material, Vertex = S::Vertex>>(
         &mut self,
         shader: ShaderHandle<S>,
         drawable: &D,
         transform: &Matrix4,
-    ) -> Result<(), Box<dyn Error>>;
+    ) -> Result<(), RenderError>;
+    // Draws multiple objects sharing the same shader, binding its pipeline once
+    fn draw_batch<S: ShaderType, D: Drawable<Material = S::Material, Vertex = S::Vertex>>(
+        &mut self,
+        shader: ShaderHandle<S>,
+        items: &[(&D, Matrix4)],
+    ) -> Result<(), RenderError>;
+    // Skips the draw when world space `bounds` lie outside of the camera frustum
+    // captured in `begin_frame`, draws unconditionally when culling is disabled
+    fn draw_with_bounds<S: ShaderType, D: Drawable<Material = S::Material, Vertex = S::Vertex>>(
+        &mut self,
+        shader: ShaderHandle<S>,
+        drawable: &D,
+        transform: &Matrix4,
+        bounds: &Aabb,
+    ) -> Result<(), RenderError>;
+    fn set_directional_light(&mut self, light: Option<DirectionalLight>);
+    // Draws text over the current frame, `x` and `y` locate its top left corner
+    // in pixels of the presented image
+    fn draw_text(&mut self, x: f32, y: f32, text: &str, color: Vector4);
+    // Exposure of the tonemap pass applied to the following frames,
+    // ignored when the renderer does not tonemap its output
+    fn set_exposure(&mut self, exposure: f32);
+    // Saves the next presented frame as PNG file, the file is written on a worker
+    // thread and the outcome is reported through `on_complete`
+    fn capture_screenshot(&mut self, path: &Path, on_complete: ScreenshotCallback);
 }
 
 pub trait RendererBuilder: 'static {
@@ -42,7 +169,28 @@ pub trait RendererBuilder: 'static {
     fn build(self, window: &Window) -> Result<Self::Renderer, Box<dyn Error>>;
 }
 
-impl Renderer for Nil {}
+impl Renderer for Nil {
+    fn resize(&mut self, _width: u32, _height: u32) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    fn add_window(&mut self, window: &Window) -> Result<WindowId, Box<dyn Error>> {
+        Ok(window.id())
+    }
+
+    fn remove_window(&mut self, _window: WindowId) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    fn resize_window(
+        &mut self,
+        _window: WindowId,
+        _width: u32,
+        _height: u32,
+    ) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+}
 
 impl ContextBuilder for Nil {
     type Renderer = Nil;
@@ -51,6 +199,10 @@ impl ContextBuilder for Nil {
     fn build(self, _renderer: &Self::Renderer) -> Result<Self::Context, Box<dyn Error>> {
         Ok(Nil::new())
     }
+
+    fn with_skybox(self, _path: &Path) -> Result<Self, Box<dyn Error>> {
+        Ok(self)
+    }
 }
 
 impl RendererContext for Nil {
@@ -59,11 +211,35 @@ impl RendererContext for Nil {
     type Materials = Nil;
     type Meshes = Nil;
 
-    fn begin_frame<C: Camera>(&mut self, _camera: &C) -> Result<(), Box<dyn Error>> {
+    fn begin_frame<C: Camera>(&mut self, _camera: &C) -> Result<(), RenderError> {
+        unimplemented!()
+    }
+
+    fn begin_frame_for<C: Camera + ?Sized>(
+        &mut self,
+        _window: WindowId,
+        _camera: &C,
+    ) -> Result<(), RenderError> {
+        unimplemented!()
+    }
+
+    fn end_frame(&mut self) -> Result<(), RenderError> {
+        unimplemented!()
+    }
+
+    fn stats(&self) -> &RenderStats {
+        unimplemented!()
+    }
+
+    fn begin_view<C: Camera + ?Sized>(
+        &mut self,
+        _viewport: Rect,
+        _camera: &C,
+    ) -> Result<(), RenderError> {
         unimplemented!()
     }
 
-    fn end_frame(&mut self) -> Result<(), Box<dyn Error>> {
+    fn end_view(&mut self) -> Result<(), RenderError> {
         unimplemented!()
     }
 
@@ -72,7 +248,41 @@ impl RendererContext for Nil {
         _shader: ShaderHandle<S>,
         _drawable: &D,
         _transform: &Matrix4,
-    ) -> Result<(), Box<dyn Error>> {
+    ) -> Result<(), RenderError> {
+        unimplemented!()
+    }
+
+    fn draw_batch<S: ShaderType, D: Drawable<Material = S::Material, Vertex = S::Vertex>>(
+        &mut self,
+        _shader: ShaderHandle<S>,
+        _items: &[(&D, Matrix4)],
+    ) -> Result<(), RenderError> {
+        unimplemented!()
+    }
+
+    fn draw_with_bounds<S: ShaderType, D: Drawable<Material = S::Material, Vertex = S::Vertex>>(
+        &mut self,
+        _shader: ShaderHandle<S>,
+        _drawable: &D,
+        _transform: &Matrix4,
+        _bounds: &Aabb,
+    ) -> Result<(), RenderError> {
+        unimplemented!()
+    }
+
+    fn set_directional_light(&mut self, _light: Option<DirectionalLight>) {
+        unimplemented!()
+    }
+
+    fn draw_text(&mut self, _x: f32, _y: f32, _text: &str, _color: Vector4) {
+        unimplemented!()
+    }
+
+    fn set_exposure(&mut self, _exposure: f32) {
+        unimplemented!()
+    }
+
+    fn capture_screenshot(&mut self, _path: &Path, _on_complete: ScreenshotCallback) {
         unimplemented!()
     }
 }
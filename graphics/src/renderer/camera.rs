@@ -1,10 +1,11 @@
 pub mod first_person;
+pub mod orbit;
 
 use std::{cell::RefCell, rc::Rc};
 
 use bytemuck::{Pod, Zeroable};
-use input::InputHandler;
-use math::types::{Matrix4, Vector3};
+use input::Input;
+use math::types::{Matrix4, Vector3, Vector4};
 
 pub const UP: Vector3 = Vector3::z();
 
@@ -15,16 +16,23 @@ pub struct CameraMatrices {
     pub proj: Matrix4,
 }
 
+impl CameraMatrices {
+    // Normalized planes of the camera frustum, see `Matrix4::frustum_planes`
+    pub fn frustum_planes(&self) -> [Vector4; 6] {
+        (self.proj * self.view).frustum_planes()
+    }
+}
+
 pub trait Camera: 'static {
     fn get_position(&self) -> Vector3;
     fn get_matrices(&self) -> CameraMatrices;
-    fn update(&mut self, elapsed_time: f32);
+    fn update(&mut self, input: &Input, elapsed_time: f32);
     fn set_active(&mut self, active: bool);
 }
 
 pub trait CameraBuilder: 'static {
     type Camera: Camera;
-    fn build(self, input_handler: &mut InputHandler) -> Rc<RefCell<Self::Camera>>;
+    fn build(self) -> Rc<RefCell<Self::Camera>>;
 }
 
 pub struct CameraNone;
@@ -38,7 +46,7 @@ impl Camera for CameraNone {
         unimplemented!()
     }
 
-    fn update(&mut self, _elapsed_time: f32) {
+    fn update(&mut self, _input: &Input, _elapsed_time: f32) {
         unimplemented!()
     }
 
@@ -49,7 +57,7 @@ impl Camera for CameraNone {
 
 impl CameraBuilder for CameraNone {
     type Camera = CameraNone;
-    fn build(self, _input_handler: &mut InputHandler) -> Rc<RefCell<Self::Camera>> {
+    fn build(self) -> Rc<RefCell<Self::Camera>> {
         panic!("Camera Type not provided!")
     }
 }
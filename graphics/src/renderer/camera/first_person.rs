@@ -5,10 +5,10 @@ use std::{
 };
 
 use math::types::{Matrix4, Vector3};
-use winit::{dpi::PhysicalPosition, keyboard::KeyCode};
+use winit::keyboard::KeyCode;
 
 use crate::renderer::camera::UP;
-use input::InputHandler;
+use input::Input;
 
 use super::{Camera, CameraBuilder, CameraMatrices};
 
@@ -21,16 +21,15 @@ impl Camera for FirstPersonCamera {
         self.into()
     }
 
-    fn update(&mut self, elapsed_time: f32) {
+    fn update(&mut self, input: &Input, elapsed_time: f32) {
         const MOVEMENT_SPEED: f32 = 4.0;
         if self.active {
-            if self.move_direction.length_square() > 0.0 {
+            self.rotate(input.mouse_delta());
+            let move_direction = self.get_move_direction(input);
+            if move_direction.length_square() > 0.0 {
                 self.position =
-                    self.position + elapsed_time * MOVEMENT_SPEED * self.move_direction.norm();
+                    self.position + elapsed_time * MOVEMENT_SPEED * move_direction.norm();
             }
-            self.forward = Vector3::from_euler(self.euler.x, self.euler.y, self.euler.z);
-            self.right = self.forward.cross(UP).norm();
-            self.move_direction = Vector3::zero();
         }
     }
 
@@ -52,10 +51,8 @@ impl FirstPersonCameraBuilder {
 impl CameraBuilder for FirstPersonCameraBuilder {
     type Camera = FirstPersonCamera;
 
-    fn build(self, input_handler: &mut InputHandler) -> Rc<RefCell<Self::Camera>> {
-        let camera = Rc::new(RefCell::new(FirstPersonCamera::new(self.proj)));
-        FirstPersonCamera::register_callbacks(camera.clone(), input_handler);
-        camera
+    fn build(self) -> Rc<RefCell<Self::Camera>> {
+        Rc::new(RefCell::new(FirstPersonCamera::new(self.proj)))
     }
 }
 
@@ -74,7 +71,6 @@ pub struct FirstPersonCamera {
     forward: Vector3,
     right: Vector3,
     euler: Vector3,
-    move_direction: Vector3,
     active: bool,
 }
 
@@ -86,69 +82,31 @@ impl FirstPersonCamera {
             forward: Vector3::x(),
             right: -Vector3::y(),
             euler: Vector3::zero(),
-            move_direction: Vector3::zero(),
             active: false,
         }
     }
 
-    pub fn register_callbacks(camera: Rc<RefCell<Self>>, input_handler: &mut InputHandler) {
-        let shared_camera = camera.clone();
-        input_handler.register_cursor_callback(Box::new(move |position| {
-            let mut camera = shared_camera.borrow_mut();
-            if camera.active {
-                let PhysicalPosition { x, y } = position;
-                const MOUSE_SENSITIVITY: f32 = 0.5;
-                let delta_x = x - 400.0;
-                let delta_y = y - 300.0;
-                let delta_yaw = (delta_x / 400.0) as f32 * MOUSE_SENSITIVITY;
-                let delta_pitch = (delta_y / 300.0) as f32 * MOUSE_SENSITIVITY;
-                camera.euler.y =
-                    (camera.euler.y + delta_pitch).clamp(-FRAC_PI_2 + 1e-4, FRAC_PI_2 - 1e-4);
-                camera.euler.x = ((camera.euler.x - delta_yaw) / (2.0 * PI)).fract() * (2.0 * PI);
-                camera.forward =
-                    Vector3::from_euler(camera.euler.x, camera.euler.y, camera.euler.z);
-                camera.right = camera.forward.cross(UP).norm();
-            }
-        }));
-        let shared_camera = camera.clone();
-        input_handler.register_key_pressed_callback(
-            KeyCode::KeyW,
-            Box::new(move |()| {
-                let mut camera = shared_camera.borrow_mut();
-                if camera.active {
-                    camera.move_direction = camera.move_direction + camera.forward;
-                }
-            }),
-        );
-        let shared_camera = camera.clone();
-        input_handler.register_key_pressed_callback(
-            KeyCode::KeyS,
-            Box::new(move |()| {
-                let mut camera = shared_camera.borrow_mut();
-                if camera.active {
-                    camera.move_direction = camera.move_direction - camera.forward;
-                }
-            }),
-        );
-        let shared_camera = camera.clone();
-        input_handler.register_key_pressed_callback(
-            KeyCode::KeyD,
-            Box::new(move |()| {
-                let mut camera = shared_camera.borrow_mut();
-                if camera.active {
-                    camera.move_direction = camera.move_direction + camera.right;
-                }
-            }),
-        );
-        let shared_camera = camera.clone();
-        input_handler.register_key_pressed_callback(
-            KeyCode::KeyA,
-            Box::new(move |()| {
-                let mut camera = shared_camera.borrow_mut();
-                if camera.active {
-                    camera.move_direction = camera.move_direction - camera.right;
-                }
-            }),
-        );
+    fn rotate(&mut self, mouse_delta: (f32, f32)) {
+        // Radians per unit of raw mouse motion
+        const MOUSE_SENSITIVITY: f32 = 0.00125;
+        let (delta_x, delta_y) = mouse_delta;
+        let delta_yaw = delta_x * MOUSE_SENSITIVITY;
+        let delta_pitch = delta_y * MOUSE_SENSITIVITY;
+        self.euler.y = (self.euler.y + delta_pitch).clamp(-FRAC_PI_2 + 1e-4, FRAC_PI_2 - 1e-4);
+        self.euler.x = ((self.euler.x - delta_yaw) / (2.0 * PI)).fract() * (2.0 * PI);
+        self.forward = Vector3::from_euler(self.euler.x, self.euler.y, self.euler.z);
+        self.right = self.forward.cross(UP).norm();
+    }
+
+    fn get_move_direction(&self, input: &Input) -> Vector3 {
+        [
+            (KeyCode::KeyW, self.forward),
+            (KeyCode::KeyS, -self.forward),
+            (KeyCode::KeyD, self.right),
+            (KeyCode::KeyA, -self.right),
+        ]
+        .into_iter()
+        .filter(|&(key, _)| input.is_key_pressed(key))
+        .fold(Vector3::zero(), |direction, (_, offset)| direction + offset)
     }
 }
@@ -0,0 +1,173 @@
+use std::{
+    cell::RefCell,
+    f32::consts::{FRAC_PI_2, PI},
+    rc::Rc,
+};
+
+use math::types::{Matrix4, Vector3};
+use winit::event::MouseButton;
+
+use crate::renderer::camera::UP;
+use input::Input;
+
+use super::{Camera, CameraBuilder, CameraMatrices};
+
+impl Camera for OrbitCamera {
+    fn get_position(&self) -> Vector3 {
+        self.target + self.distance * Vector3::from_euler(self.yaw, self.pitch, 0.0)
+    }
+
+    fn get_matrices(&self) -> CameraMatrices {
+        self.into()
+    }
+
+    fn update(&mut self, input: &Input, _elapsed_time: f32) {
+        if self.active || input.is_button_pressed(MouseButton::Left) {
+            self.rotate(input.mouse_delta());
+        }
+        self.zoom(input.scroll());
+    }
+
+    fn set_active(&mut self, active: bool) {
+        self.active = active;
+    }
+}
+
+pub struct OrbitCameraBuilder {
+    proj: Matrix4,
+    target: Vector3,
+    distance: f32,
+    yaw: f32,
+    pitch: f32,
+}
+
+impl OrbitCameraBuilder {
+    pub fn new(proj: Matrix4) -> Self {
+        Self {
+            proj,
+            target: Vector3::zero(),
+            distance: 5.0,
+            yaw: 0.0,
+            pitch: 0.0,
+        }
+    }
+
+    pub fn with_target(self, target: Vector3) -> Self {
+        Self { target, ..self }
+    }
+
+    pub fn with_distance(self, distance: f32) -> Self {
+        Self { distance, ..self }
+    }
+
+    pub fn with_yaw(self, yaw: f32) -> Self {
+        Self { yaw, ..self }
+    }
+
+    pub fn with_pitch(self, pitch: f32) -> Self {
+        Self { pitch, ..self }
+    }
+}
+
+impl CameraBuilder for OrbitCameraBuilder {
+    type Camera = OrbitCamera;
+
+    fn build(self) -> Rc<RefCell<Self::Camera>> {
+        let Self {
+            proj,
+            target,
+            distance,
+            yaw,
+            pitch,
+        } = self;
+        Rc::new(RefCell::new(OrbitCamera::new(
+            proj, target, distance, yaw, pitch,
+        )))
+    }
+}
+
+impl From<&OrbitCamera> for CameraMatrices {
+    fn from(value: &OrbitCamera) -> Self {
+        CameraMatrices {
+            proj: value.proj,
+            view: Matrix4::look_at(value.get_position(), value.target, UP),
+        }
+    }
+}
+
+// Camera looking at the target point from the surface of a sphere around it,
+// yaw and pitch describe direction from the target towards the camera
+pub struct OrbitCamera {
+    proj: Matrix4,
+    target: Vector3,
+    distance: f32,
+    yaw: f32,
+    pitch: f32,
+    active: bool,
+}
+
+impl OrbitCamera {
+    const MIN_DISTANCE: f32 = 0.1;
+    const MAX_PITCH: f32 = FRAC_PI_2 - 1e-4;
+
+    pub fn new(proj: Matrix4, target: Vector3, distance: f32, yaw: f32, pitch: f32) -> Self {
+        Self {
+            proj,
+            target,
+            distance: distance.max(Self::MIN_DISTANCE),
+            yaw,
+            pitch: pitch.clamp(-Self::MAX_PITCH, Self::MAX_PITCH),
+            active: false,
+        }
+    }
+
+    pub fn set_target(&mut self, target: Vector3) {
+        self.target = target;
+    }
+
+    fn rotate(&mut self, mouse_delta: (f32, f32)) {
+        // Radians per unit of raw mouse motion
+        const MOUSE_SENSITIVITY: f32 = 0.0025;
+        let (delta_x, delta_y) = mouse_delta;
+        self.pitch =
+            (self.pitch + delta_y * MOUSE_SENSITIVITY).clamp(-Self::MAX_PITCH, Self::MAX_PITCH);
+        self.yaw = ((self.yaw - delta_x * MOUSE_SENSITIVITY) / (2.0 * PI)).fract() * (2.0 * PI);
+    }
+
+    fn zoom(&mut self, scroll: f32) {
+        // Fraction of current distance covered by single line of scroll
+        const ZOOM_SPEED: f32 = 0.1;
+        self.distance = (self.distance * (1.0 - ZOOM_SPEED).powf(scroll)).max(Self::MIN_DISTANCE);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_position_at_configured_distance_from_target() {
+        let target = Vector3::new(1.0, -2.0, 0.5);
+        let angles = [-PI, -FRAC_PI_2, -0.3, 0.0, 0.7, FRAC_PI_2, 2.5];
+        for yaw in angles {
+            for pitch in angles {
+                let camera = OrbitCamera::new(Matrix4::identity(), target, 4.0, yaw, pitch);
+                let distance = (camera.get_position() - target).length();
+                assert!((distance - 4.0).abs() < 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn test_distance_kept_after_rotation_and_clamped_zoom() {
+        let target = Vector3::new(0.0, 3.0, -1.0);
+        let mut camera = OrbitCamera::new(Matrix4::identity(), target, 2.0, 0.0, 0.0);
+        // Large mouse motion clamps the pitch, camera stays on the sphere around target
+        camera.rotate((350.0, 5000.0));
+        assert_eq!(camera.pitch, OrbitCamera::MAX_PITCH);
+        assert!(((camera.get_position() - target).length() - 2.0).abs() < 1e-4);
+        camera.zoom(1e3);
+        let distance = (camera.get_position() - target).length();
+        assert!((distance - OrbitCamera::MIN_DISTANCE).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,36 @@
+use math::types::{Matrix4, Vector3};
+
+use super::camera::UP;
+
+#[derive(Debug, Clone, Copy)]
+pub struct DirectionalLight {
+    pub direction: Vector3,
+    pub color: Vector3,
+    pub intensity: f32,
+}
+
+impl DirectionalLight {
+    pub fn new(direction: Vector3, color: Vector3, intensity: f32) -> Self {
+        Self {
+            direction: direction.norm(),
+            color,
+            intensity,
+        }
+    }
+
+    // Orthographic light-space transform covering a sphere of given radius around center
+    pub fn get_light_space_matrix(&self, center: Vector3, radius: f32) -> Matrix4 {
+        let direction = self.direction.norm();
+        let up = if (direction * UP).abs() > 0.99 {
+            Vector3::x()
+        } else {
+            UP
+        };
+        let eye = center - 2.0 * radius * direction;
+        let proj = Matrix4::orthographic(
+            Vector3::new(-radius, -radius, radius),
+            Vector3::new(radius, radius, 3.0 * radius),
+        );
+        proj * Matrix4::look_at(eye, center, up)
+    }
+}
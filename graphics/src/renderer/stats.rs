@@ -0,0 +1,13 @@
+// Commands recorded for the last finished frame, memory sizes are totals
+// of buffers and images alive when the frame ended
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RenderStats {
+    pub draw_calls: u32,
+    pub triangles: u64,
+    pub pipeline_binds: u32,
+    pub descriptor_binds: u32,
+    pub buffer_memory: u64,
+    pub texture_memory: u64,
+    // Secondary commands executed in each render pass and subpass, in recording order
+    pub secondary_commands: Vec<u32>,
+}
@@ -0,0 +1,55 @@
+// Region of the rendered frame in fractions of its extent,
+// origin is located in the top left corner of the frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Rect {
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    pub fn full() -> Self {
+        Self::new(0.0, 0.0, 1.0, 1.0)
+    }
+
+    // Splits the region into left and right halves
+    pub fn split_vertical(self) -> (Self, Self) {
+        let width = self.width / 2.0;
+        (
+            Self { width, ..self },
+            Self {
+                x: self.x + width,
+                width,
+                ..self
+            },
+        )
+    }
+}
+
+impl Default for Rect {
+    fn default() -> Self {
+        Self::full()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_vertical_covers_region() {
+        let (left, right) = Rect::full().split_vertical();
+        assert_eq!(left, Rect::new(0.0, 0.0, 0.5, 1.0));
+        assert_eq!(right, Rect::new(0.5, 0.0, 0.5, 1.0));
+    }
+}
@@ -3,18 +3,56 @@ use std::{
     path::{Path, PathBuf},
 };
 
-use crate::model::{EmptyMaterial, Material, Vertex, VertexNone};
+use crate::{
+    model::{EmptyMaterial, Material, Vertex, VertexNone},
+    renderer::ContextId,
+};
 use type_kit::{Cons, Nil};
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PolygonMode {
+    #[default]
+    Fill,
+    // Rasterizes triangle edges only, requires backend support for non-solid fill modes
+    Line,
+}
+
+// Shaders compiled into the renderer backend, loaded without accessing the filesystem
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmbeddedShader {
+    // Writes vertex color as albedo, expects `CommonVertex` layout and `EmptyMaterial`
+    Unlit,
+    // Flat magenta, substituted for shaders which failed to load
+    Error,
+}
+
+impl EmbeddedShader {
+    pub fn name(&self) -> &'static str {
+        match self {
+            EmbeddedShader::Unlit => "unlit",
+            EmbeddedShader::Error => "error",
+        }
+    }
+}
+
 pub trait ShaderType: 'static {
     type Vertex: Vertex;
     type Material: Material;
 
     fn source(&self) -> &Path;
+
+    fn polygon_mode(&self) -> PolygonMode;
+
+    // Embedded shaders are loaded in place of SPIR-V files of the `source` directory
+    fn embedded_shader(&self) -> Option<EmbeddedShader> {
+        None
+    }
 }
 
 pub struct Shader<V: Vertex, M: Material> {
     source: PathBuf,
+    embedded: Option<EmbeddedShader>,
+    polygon_mode: PolygonMode,
     _phantom: PhantomData<(V, M)>,
 }
 
@@ -26,9 +64,32 @@ impl<V: Vertex, M: Material> Shader<V, M> {
     pub fn new(source_path: &str) -> Self {
         Self {
             source: PathBuf::from(source_path),
+            embedded: None,
+            polygon_mode: PolygonMode::Fill,
+            _phantom: PhantomData,
+        }
+    }
+
+    // Source path only names the shader in diagnostics, no files are read
+    pub fn embedded(shader: EmbeddedShader) -> Self {
+        Self {
+            source: Path::new("embedded").join(shader.name()),
+            embedded: Some(shader),
+            polygon_mode: PolygonMode::Fill,
             _phantom: PhantomData,
         }
     }
+
+    pub fn with_polygon_mode(self, polygon_mode: PolygonMode) -> Self {
+        Self {
+            polygon_mode,
+            ..self
+        }
+    }
+
+    pub fn wireframe(self) -> Self {
+        self.with_polygon_mode(PolygonMode::Line)
+    }
 }
 
 impl<V: Vertex, M: Material> ShaderType for Shader<V, M> {
@@ -38,6 +99,14 @@ impl<V: Vertex, M: Material> ShaderType for Shader<V, M> {
     fn source(&self) -> &Path {
         &self.source
     }
+
+    fn polygon_mode(&self) -> PolygonMode {
+        self.polygon_mode
+    }
+
+    fn embedded_shader(&self) -> Option<EmbeddedShader> {
+        self.embedded
+    }
 }
 
 pub trait ShaderTypeList: 'static {
@@ -55,6 +124,10 @@ impl ShaderType for ShaderTypeNil {
     fn source(&self) -> &Path {
         unreachable!()
     }
+
+    fn polygon_mode(&self) -> PolygonMode {
+        unreachable!()
+    }
 }
 
 impl ShaderTypeList for Nil {
@@ -72,6 +145,7 @@ impl<S: ShaderType, N: ShaderTypeList> ShaderTypeList for Cons<Vec<S>, N> {
 #[derive(Debug)]
 pub struct ShaderHandle<S: ShaderType> {
     index: u32,
+    context: ContextId,
     _phantom: PhantomData<S>,
 }
 
@@ -84,9 +158,10 @@ impl<S: ShaderType> Clone for ShaderHandle<S> {
 impl<S: ShaderType> Copy for ShaderHandle<S> {}
 
 impl<S: ShaderType> ShaderHandle<S> {
-    pub fn new(index: u32) -> Self {
+    pub fn new(index: u32, context: ContextId) -> Self {
         Self {
             index,
+            context,
             _phantom: PhantomData,
         }
     }
@@ -94,4 +169,8 @@ impl<S: ShaderType> ShaderHandle<S> {
     pub fn index(&self) -> u32 {
         self.index
     }
+
+    pub fn context(&self) -> ContextId {
+        self.context
+    }
 }
@@ -1,15 +1,60 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use winit::{
     dpi::PhysicalPosition,
-    event::{ElementState, Event, KeyEvent, StartCause, WindowEvent},
+    event::{
+        DeviceEvent, ElementState, Event, KeyEvent, MouseButton, MouseScrollDelta, StartCause,
+        WindowEvent,
+    },
     keyboard::{KeyCode, PhysicalKey},
 };
 
 pub type Callback<Args> = Box<dyn Fn(Args)>;
 
+// Scroll given in pixels is converted to lines assuming this line height
+const SCROLL_PIXELS_PER_LINE: f32 = 20.0;
+
+// Input state snapshot, mouse delta and scroll are accumulated
+// since the last call to `InputHandler::end_frame`
+#[derive(Debug, Clone, Default)]
+pub struct Input {
+    pressed_keys: HashSet<KeyCode>,
+    pressed_buttons: HashSet<MouseButton>,
+    mouse_delta: (f32, f32),
+    scroll: f32,
+}
+
+impl Input {
+    #[inline]
+    pub fn is_key_pressed(&self, key: KeyCode) -> bool {
+        self.pressed_keys.contains(&key)
+    }
+
+    #[inline]
+    pub fn is_button_pressed(&self, button: MouseButton) -> bool {
+        self.pressed_buttons.contains(&button)
+    }
+
+    #[inline]
+    pub fn pressed_keys(&self) -> impl Iterator<Item = KeyCode> + '_ {
+        self.pressed_keys.iter().copied()
+    }
+
+    // Raw mouse motion in device units, independent of cursor position
+    #[inline]
+    pub fn mouse_delta(&self) -> (f32, f32) {
+        self.mouse_delta
+    }
+
+    // Vertical scroll in lines, positive when scrolling up
+    #[inline]
+    pub fn scroll(&self) -> f32 {
+        self.scroll
+    }
+}
+
 pub struct InputHandler {
-    key_states: Vec<bool>,
+    input: Input,
     key_press_callbacks: HashMap<KeyCode, Vec<Callback<()>>>,
     key_state_callbacks: HashMap<KeyCode, Vec<Callback<ElementState>>>,
     cursor_callbacks: Vec<Callback<PhysicalPosition<f64>>>,
@@ -24,7 +69,7 @@ impl Default for InputHandler {
 impl InputHandler {
     pub fn new() -> Self {
         Self {
-            key_states: vec![false; 194],
+            input: Input::default(),
             key_press_callbacks: HashMap::new(),
             key_state_callbacks: HashMap::new(),
             cursor_callbacks: vec![],
@@ -49,13 +94,44 @@ impl InputHandler {
         self.cursor_callbacks.push(callback);
     }
 
+    #[inline]
+    pub fn input(&self) -> &Input {
+        &self.input
+    }
+
+    // Resets per frame accumulated mouse delta and scroll
+    pub fn end_frame(&mut self) {
+        self.input.mouse_delta = (0.0, 0.0);
+        self.input.scroll = 0.0;
+    }
+
+    // Keyboard events carry platform specific data, key transitions
+    // are handled separately so they can be driven without the window
+    fn handle_key(&mut self, key: KeyCode, state: ElementState) {
+        if state.is_pressed() {
+            self.input.pressed_keys.insert(key);
+        } else {
+            self.input.pressed_keys.remove(&key);
+        }
+        if let Some(callbacks) = self.key_state_callbacks.get(&key) {
+            callbacks.iter().for_each(|callback| callback(state));
+        }
+    }
+
     pub fn handle_event(&mut self, event: Event<()>) {
         match event {
             Event::NewEvents(StartCause::Poll) => self
                 .key_press_callbacks
                 .iter()
-                .filter(|(&key, ..)| self.key_states[key as usize])
+                .filter(|(key, ..)| self.input.is_key_pressed(**key))
                 .for_each(|(_, callbacks)| callbacks.iter().for_each(|callback| callback(()))),
+            Event::DeviceEvent {
+                event: DeviceEvent::MouseMotion { delta: (x, y) },
+                ..
+            } => {
+                let (delta_x, delta_y) = self.input.mouse_delta;
+                self.input.mouse_delta = (delta_x + x as f32, delta_y + y as f32);
+            }
             Event::WindowEvent { event, .. } => match event {
                 WindowEvent::KeyboardInput {
                     event:
@@ -66,12 +142,7 @@ impl InputHandler {
                             ..
                         },
                     ..
-                } => {
-                    self.key_states[key as usize] = state.is_pressed();
-                    if let Some(callbacks) = self.key_state_callbacks.get(&key) {
-                        callbacks.iter().for_each(|callback| callback(state));
-                    }
-                }
+                } => self.handle_key(key, state),
                 WindowEvent::CursorMoved { position, .. }
                     if position.x != 0.0 || position.y != 0.0 =>
                 {
@@ -79,9 +150,99 @@ impl InputHandler {
                         .iter()
                         .for_each(|callback| callback(position))
                 }
+                WindowEvent::MouseInput { state, button, .. } => {
+                    if state.is_pressed() {
+                        self.input.pressed_buttons.insert(button);
+                    } else {
+                        self.input.pressed_buttons.remove(&button);
+                    }
+                }
+                WindowEvent::MouseWheel { delta, .. } => {
+                    self.input.scroll += match delta {
+                        MouseScrollDelta::LineDelta(_, y) => y,
+                        MouseScrollDelta::PixelDelta(position) => {
+                            position.y as f32 / SCROLL_PIXELS_PER_LINE
+                        }
+                    };
+                }
+                WindowEvent::Focused(false) => {
+                    // Release events are not delivered to unfocused window
+                    self.input.pressed_keys.clear();
+                    self.input.pressed_buttons.clear();
+                }
                 _ => (),
             },
             _ => (),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use winit::{
+        event::{DeviceEvent, DeviceId, ElementState, Event, MouseButton, WindowEvent},
+        keyboard::KeyCode,
+        window::WindowId,
+    };
+
+    use super::InputHandler;
+
+    fn window_event(event: WindowEvent) -> Event<()> {
+        Event::WindowEvent {
+            window_id: unsafe { WindowId::dummy() },
+            event,
+        }
+    }
+
+    #[test]
+    fn test_key_transitions_update_input_state() {
+        let mut handler = InputHandler::new();
+        let transitions = Rc::new(Cell::new(0));
+        handler.register_key_state_callback(KeyCode::KeyW, {
+            let transitions = transitions.clone();
+            Box::new(move |_| transitions.set(transitions.get() + 1))
+        });
+        handler.handle_key(KeyCode::KeyW, ElementState::Pressed);
+        handler.handle_key(KeyCode::Space, ElementState::Pressed);
+        assert!(handler.input().is_key_pressed(KeyCode::KeyW));
+        assert!(handler.input().is_key_pressed(KeyCode::Space));
+        handler.handle_key(KeyCode::KeyW, ElementState::Released);
+        assert!(!handler.input().is_key_pressed(KeyCode::KeyW));
+        assert!(handler.input().is_key_pressed(KeyCode::Space));
+        assert_eq!(transitions.get(), 2);
+        // Key state is kept across frames until released
+        handler.end_frame();
+        assert!(handler.input().is_key_pressed(KeyCode::Space));
+        handler.handle_event(window_event(WindowEvent::Focused(false)));
+        assert_eq!(handler.input().pressed_keys().count(), 0);
+    }
+
+    #[test]
+    fn test_mouse_events_update_input_state() {
+        let mut handler = InputHandler::new();
+        let device_id = unsafe { DeviceId::dummy() };
+        handler.handle_event(window_event(WindowEvent::MouseInput {
+            device_id,
+            state: ElementState::Pressed,
+            button: MouseButton::Left,
+        }));
+        for _ in 0..2 {
+            handler.handle_event(Event::DeviceEvent {
+                device_id,
+                event: DeviceEvent::MouseMotion { delta: (2.0, -1.0) },
+            });
+        }
+        assert!(handler.input().is_button_pressed(MouseButton::Left));
+        assert_eq!(handler.input().mouse_delta(), (4.0, -2.0));
+        handler.end_frame();
+        assert_eq!(handler.input().mouse_delta(), (0.0, 0.0));
+        handler.handle_event(window_event(WindowEvent::MouseInput {
+            device_id,
+            state: ElementState::Released,
+            button: MouseButton::Left,
+        }));
+        assert!(!handler.input().is_button_pressed(MouseButton::Left));
+    }
+}
@@ -0,0 +1,5 @@
+mod aabb;
+mod ray;
+
+pub use aabb::Aabb;
+pub use ray::Ray;
@@ -0,0 +1,246 @@
+use crate::types::{Matrix4, Vector3, Vector4};
+
+#[cfg(test)]
+mod test_aabb {
+    use crate::types::{Matrix4, Vector3, Vector4};
+
+    use super::Aabb;
+
+    fn unit_box() -> Aabb {
+        Aabb::new(-Vector3::new(1.0, 1.0, 1.0), Vector3::new(1.0, 1.0, 1.0))
+    }
+
+    fn approx_equal(lhs: Aabb, rhs: Aabb) -> bool {
+        lhs.min.approx_equal(rhs.min) && lhs.max.approx_equal(rhs.max)
+    }
+
+    #[test]
+    fn from_points() {
+        let aabb = Aabb::from_points([
+            Vector3::new(1.0, -2.0, 0.0),
+            Vector3::new(-1.0, 3.0, 0.5),
+            Vector3::new(0.0, 0.0, -4.0),
+        ])
+        .unwrap();
+        assert!(approx_equal(
+            aabb,
+            Aabb::new(Vector3::new(-1.0, -2.0, -4.0), Vector3::new(1.0, 3.0, 0.5))
+        ));
+        assert!(Aabb::from_points([]).is_none());
+    }
+
+    #[test]
+    fn single_point_is_degenerate() {
+        let point = Vector3::new(1.0, 2.0, 3.0);
+        let aabb = Aabb::from_points([point]).unwrap();
+        assert!(aabb.extent().approx_equal(Vector3::zero()));
+        assert!(aabb.contains_point(point));
+        assert!(!aabb.contains_point(point + Vector3::new(0.0, 0.0, 1e-3)));
+    }
+
+    #[test]
+    fn union() {
+        let aabb = unit_box().union(Aabb::new(Vector3::zero(), Vector3::new(3.0, 0.5, 0.5)));
+        assert!(approx_equal(
+            aabb,
+            Aabb::new(-Vector3::new(1.0, 1.0, 1.0), Vector3::new(3.0, 1.0, 1.0))
+        ));
+    }
+
+    #[test]
+    fn expand() {
+        let aabb = unit_box().expand(0.5);
+        assert!(aabb.extent().approx_equal(Vector3::new(3.0, 3.0, 3.0)));
+        assert!(aabb.center().approx_equal(Vector3::zero()));
+        // Flat box grows into a volume
+        let flat = Aabb::new(Vector3::zero(), Vector3::new(1.0, 1.0, 0.0)).expand(0.1);
+        assert!((flat.extent().z - 0.2).abs() < 1e-6);
+    }
+
+    #[test]
+    fn contains_point_boundary_inclusive() {
+        let aabb = unit_box();
+        assert!(aabb.contains_point(Vector3::zero()));
+        assert!(aabb.contains_point(Vector3::new(1.0, -1.0, 1.0)));
+        assert!(!aabb.contains_point(Vector3::new(1.01, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn transformed_translation() {
+        let aabb = unit_box().transformed(&Matrix4::translate(Vector3::new(2.0, 0.0, -1.0)));
+        assert!(approx_equal(
+            aabb,
+            Aabb::new(Vector3::new(1.0, -1.0, -2.0), Vector3::new(3.0, 1.0, 0.0))
+        ));
+    }
+
+    #[test]
+    fn transformed_rotation_encloses_corners() {
+        let aabb = Aabb::new(Vector3::zero(), Vector3::new(2.0, 1.0, 1.0));
+        let transform = Matrix4::rotate_z(std::f32::consts::FRAC_PI_4);
+        let transformed = aabb.transformed(&transform);
+        let corners = Aabb::from_points(aabb.corners().map(|corner| {
+            let corner = transform * Vector4::point(corner);
+            corner.xyz()
+        }))
+        .unwrap();
+        // Box of the transformed box is exactly the box of its transformed corners
+        assert!(approx_equal(transformed, corners));
+    }
+
+    #[test]
+    fn transformed_scale() {
+        let aabb = unit_box().transformed(&Matrix4::scale(2.0));
+        assert!(aabb.extent().approx_equal(Vector3::new(4.0, 4.0, 4.0)));
+    }
+
+    #[test]
+    fn intersects_frustum() {
+        // Planes of the [-1, 1] cube, normals pointing inwards
+        let planes = [
+            Vector4::new(1.0, 0.0, 0.0, 1.0),
+            Vector4::new(-1.0, 0.0, 0.0, 1.0),
+            Vector4::new(0.0, 1.0, 0.0, 1.0),
+            Vector4::new(0.0, -1.0, 0.0, 1.0),
+            Vector4::new(0.0, 0.0, 1.0, 1.0),
+            Vector4::new(0.0, 0.0, -1.0, 1.0),
+        ];
+        assert!(unit_box().intersects_frustum(&planes));
+        let outside = Aabb::new(Vector3::new(2.0, 0.0, 0.0), Vector3::new(3.0, 1.0, 1.0));
+        assert!(!outside.intersects_frustum(&planes));
+        let crossing = Aabb::new(Vector3::new(0.5, 0.0, 0.0), Vector3::new(3.0, 1.0, 1.0));
+        assert!(crossing.intersects_frustum(&planes));
+    }
+
+    #[test]
+    fn intersects_perspective_frustum() {
+        // Camera at the origin looking along the x axis, near plane at x = 0.5
+        let proj = Matrix4::perspective(std::f32::consts::FRAC_PI_2, 1.0, 0.5, 100.0);
+        let view = Matrix4::look_at(Vector3::zero(), Vector3::x(), Vector3::z());
+        let planes = (proj * view).frustum_planes();
+        let in_front = unit_box().transformed(&Matrix4::translate(Vector3::new(5.0, 0.0, 0.0)));
+        assert!(in_front.intersects_frustum(&planes));
+        let behind_near = Aabb::new(Vector3::new(0.1, -0.1, -0.1), Vector3::new(0.4, 0.1, 0.1));
+        assert!(!behind_near.intersects_frustum(&planes));
+        let behind_camera =
+            unit_box().transformed(&Matrix4::translate(Vector3::new(-5.0, 0.0, 0.0)));
+        assert!(!behind_camera.intersects_frustum(&planes));
+        let crossing_near = Aabb::new(Vector3::new(0.1, -0.1, -0.1), Vector3::new(1.0, 0.1, 0.1));
+        assert!(crossing_near.intersects_frustum(&planes));
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Aabb {
+    pub min: Vector3,
+    pub max: Vector3,
+}
+
+impl Aabb {
+    #[inline]
+    pub fn new(min: Vector3, max: Vector3) -> Self {
+        Self { min, max }
+    }
+
+    // None when `points` is empty, single point results in degenerate box of zero extent
+    pub fn from_points<I: IntoIterator<Item = Vector3>>(points: I) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+        Some(points.fold(Self::new(first, first), |aabb, point| Self {
+            min: aabb.min.min(point),
+            max: aabb.max.max(point),
+        }))
+    }
+
+    #[inline]
+    pub fn union(self, rhs: Self) -> Self {
+        Self {
+            min: self.min.min(rhs.min),
+            max: self.max.max(rhs.max),
+        }
+    }
+
+    // Moves each face outwards by `margin`, negative margin shrinks the box
+    // and may leave `min` greater than `max`
+    #[inline]
+    pub fn expand(self, margin: f32) -> Self {
+        let margin = Vector3::new(margin, margin, margin);
+        Self {
+            min: self.min - margin,
+            max: self.max + margin,
+        }
+    }
+
+    #[inline]
+    pub fn center(&self) -> Vector3 {
+        0.5 * (self.min + self.max)
+    }
+
+    #[inline]
+    pub fn extent(&self) -> Vector3 {
+        self.max - self.min
+    }
+
+    pub fn corners(&self) -> [Vector3; 8] {
+        let (min, max) = (self.min, self.max);
+        [
+            Vector3::new(min.x, min.y, min.z),
+            Vector3::new(max.x, min.y, min.z),
+            Vector3::new(min.x, max.y, min.z),
+            Vector3::new(max.x, max.y, min.z),
+            Vector3::new(min.x, min.y, max.z),
+            Vector3::new(max.x, min.y, max.z),
+            Vector3::new(min.x, max.y, max.z),
+            Vector3::new(max.x, max.y, max.z),
+        ]
+    }
+
+    // Smallest box enclosing the transformed box, transform is expected to be affine
+    pub fn transformed(&self, transform: &Matrix4) -> Self {
+        let center = (*transform * Vector4::point(self.center())).xyz();
+        let half_extent = 0.5 * self.extent();
+        let half_extent = half_extent.x * transform.i.xyz().abs()
+            + half_extent.y * transform.j.xyz().abs()
+            + half_extent.z * transform.k.xyz().abs();
+        Self {
+            min: center - half_extent,
+            max: center + half_extent,
+        }
+    }
+
+    // Points lying on the faces of the box are contained
+    #[inline]
+    pub fn contains_point(&self, point: Vector3) -> bool {
+        point.x >= self.min.x
+            && point.x <= self.max.x
+            && point.y >= self.min.y
+            && point.y <= self.max.y
+            && point.z >= self.min.z
+            && point.z <= self.max.z
+    }
+
+    // Box is rejected only when it lies entirely on the outer side of any plane,
+    // boxes close to frustum corners may be accepted while being outside of it
+    pub fn intersects_frustum(&self, planes: &[Vector4; 6]) -> bool {
+        planes.iter().all(|plane| {
+            let farthest = Vector3::new(
+                if plane.x >= 0.0 {
+                    self.max.x
+                } else {
+                    self.min.x
+                },
+                if plane.y >= 0.0 {
+                    self.max.y
+                } else {
+                    self.min.y
+                },
+                if plane.z >= 0.0 {
+                    self.max.z
+                } else {
+                    self.min.z
+                },
+            );
+            *plane * Vector4::point(farthest) >= 0.0
+        })
+    }
+}
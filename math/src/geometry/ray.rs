@@ -0,0 +1,222 @@
+use crate::types::Vector3;
+
+use super::Aabb;
+
+#[cfg(test)]
+mod test_ray {
+    use crate::{geometry::Aabb, types::Vector3};
+
+    use super::Ray;
+
+    fn unit_box() -> Aabb {
+        Aabb::new(-Vector3::new(1.0, 1.0, 1.0), Vector3::new(1.0, 1.0, 1.0))
+    }
+
+    #[test]
+    fn at() {
+        let ray = Ray::new(Vector3::x(), 2.0 * Vector3::y());
+        assert!(ray.at(1.5).approx_equal(Vector3::new(1.0, 3.0, 0.0)));
+    }
+
+    #[test]
+    fn aabb_hit() {
+        let ray = Ray::new(Vector3::new(-5.0, 0.0, 0.0), Vector3::x());
+        assert_eq!(ray.intersect_aabb(&unit_box()), Some(4.0));
+        // Distance is measured in units of the direction length
+        let ray = Ray::new(Vector3::new(-5.0, 0.0, 0.0), 2.0 * Vector3::x());
+        assert_eq!(ray.intersect_aabb(&unit_box()), Some(2.0));
+    }
+
+    #[test]
+    fn aabb_behind_origin_missed() {
+        let ray = Ray::new(Vector3::new(-5.0, 0.0, 0.0), -Vector3::x());
+        assert_eq!(ray.intersect_aabb(&unit_box()), None);
+    }
+
+    #[test]
+    fn aabb_origin_inside() {
+        let ray = Ray::new(Vector3::new(0.5, 0.0, 0.0), Vector3::y());
+        assert_eq!(ray.intersect_aabb(&unit_box()), Some(0.0));
+    }
+
+    #[test]
+    fn aabb_parallel_axis() {
+        // Direction parallel to x and y slabs, origin outside of the y slab
+        let ray = Ray::new(Vector3::new(0.0, 2.0, -5.0), Vector3::z());
+        assert_eq!(ray.intersect_aabb(&unit_box()), None);
+        // Origin inside of the parallel slabs
+        let ray = Ray::new(Vector3::new(0.5, -0.5, -5.0), Vector3::z());
+        assert_eq!(ray.intersect_aabb(&unit_box()), Some(4.0));
+        // Origin exactly on the slab boundary, which would produce NaN slab distances
+        let ray = Ray::new(Vector3::new(1.0, 0.0, -5.0), Vector3::z());
+        assert_eq!(ray.intersect_aabb(&unit_box()), Some(4.0));
+    }
+
+    #[test]
+    fn aabb_degenerate() {
+        let flat = Aabb::new(Vector3::new(-1.0, -1.0, 0.0), Vector3::new(1.0, 1.0, 0.0));
+        let ray = Ray::new(Vector3::new(0.0, 0.0, -3.0), Vector3::z());
+        assert_eq!(ray.intersect_aabb(&flat), Some(3.0));
+        let point = Aabb::new(Vector3::zero(), Vector3::zero());
+        assert_eq!(ray.intersect_aabb(&point), Some(3.0));
+        let ray = Ray::new(Vector3::new(0.5, 0.0, -3.0), Vector3::z());
+        assert_eq!(ray.intersect_aabb(&point), None);
+    }
+
+    #[test]
+    fn aabb_zero_direction() {
+        let ray = Ray::new(Vector3::new(0.5, 0.0, 0.0), Vector3::zero());
+        assert_eq!(ray.intersect_aabb(&unit_box()), Some(0.0));
+        let ray = Ray::new(Vector3::new(2.0, 0.0, 0.0), Vector3::zero());
+        assert_eq!(ray.intersect_aabb(&unit_box()), None);
+    }
+
+    #[test]
+    fn sphere_hit() {
+        let ray = Ray::new(Vector3::new(0.0, 0.0, -5.0), Vector3::z());
+        let t = ray.intersect_sphere(Vector3::zero(), 2.0).unwrap();
+        assert!((t - 3.0).abs() < 1e-6);
+        let tangent = Ray::new(Vector3::new(2.0, 0.0, -5.0), Vector3::z());
+        let t = tangent.intersect_sphere(Vector3::zero(), 2.0).unwrap();
+        assert!((t - 5.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn sphere_miss() {
+        let ray = Ray::new(Vector3::new(0.0, 3.0, -5.0), Vector3::z());
+        assert_eq!(ray.intersect_sphere(Vector3::zero(), 2.0), None);
+        let behind = Ray::new(Vector3::new(0.0, 0.0, -5.0), -Vector3::z());
+        assert_eq!(behind.intersect_sphere(Vector3::zero(), 2.0), None);
+    }
+
+    #[test]
+    fn sphere_origin_inside() {
+        let ray = Ray::new(Vector3::new(0.0, 0.5, 0.0), Vector3::x());
+        assert_eq!(ray.intersect_sphere(Vector3::zero(), 2.0), Some(0.0));
+    }
+
+    #[test]
+    fn triangle_hit() {
+        let (a, b, c) = (
+            Vector3::new(-1.0, -1.0, 0.0),
+            Vector3::new(1.0, -1.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0),
+        );
+        let ray = Ray::new(Vector3::new(0.0, 0.0, 2.0), -Vector3::z());
+        let t = ray.intersect_triangle(a, b, c).unwrap();
+        assert!((t - 2.0).abs() < 1e-6);
+        // Triangles are double sided
+        let ray = Ray::new(Vector3::new(0.0, 0.0, -2.0), Vector3::z());
+        let t = ray.intersect_triangle(a, b, c).unwrap();
+        assert!((t - 2.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn triangle_miss() {
+        let (a, b, c) = (
+            Vector3::new(-1.0, -1.0, 0.0),
+            Vector3::new(1.0, -1.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0),
+        );
+        let outside = Ray::new(Vector3::new(1.0, 1.0, 2.0), -Vector3::z());
+        assert_eq!(outside.intersect_triangle(a, b, c), None);
+        let behind = Ray::new(Vector3::new(0.0, 0.0, 2.0), Vector3::z());
+        assert_eq!(behind.intersect_triangle(a, b, c), None);
+        let parallel = Ray::new(Vector3::new(-5.0, 0.0, 0.0), Vector3::x());
+        assert_eq!(parallel.intersect_triangle(a, b, c), None);
+        let degenerate = Ray::new(Vector3::new(0.0, 0.0, 2.0), -Vector3::z());
+        assert_eq!(degenerate.intersect_triangle(a, b, a), None);
+    }
+}
+
+// Distances returned by intersection routines are measured in units of `dir`
+// length, so that `at(t)` gives the intersection point, `dir` needs not be normalized
+#[derive(Debug, Clone, Copy)]
+pub struct Ray {
+    pub origin: Vector3,
+    pub dir: Vector3,
+}
+
+impl Ray {
+    // Determinant threshold below which ray is treated as parallel to the triangle plane
+    const TRIANGLE_EPS: f32 = 1e-7;
+
+    #[inline]
+    pub fn new(origin: Vector3, dir: Vector3) -> Self {
+        Self { origin, dir }
+    }
+
+    #[inline]
+    pub fn at(&self, t: f32) -> Vector3 {
+        self.origin + t * self.dir
+    }
+
+    // Distance to the nearest point of the box, boxes are solid,
+    // so 0 is returned when the ray starts inside of the box
+    pub fn intersect_aabb(&self, aabb: &Aabb) -> Option<f32> {
+        let mut near = 0.0f32;
+        let mut far = f32::INFINITY;
+        for axis in 0..3 {
+            let (origin, dir) = (self.origin[axis], self.dir[axis]);
+            let (min, max) = (aabb.min[axis], aabb.max[axis]);
+            if dir == 0.0 {
+                // Parallel to the slab, handled separately, as the slab distances
+                // would be NaN for the origin lying on the slab boundary
+                if origin < min || origin > max {
+                    return None;
+                }
+            } else {
+                let inv = dir.recip();
+                let (t0, t1) = ((min - origin) * inv, (max - origin) * inv);
+                near = near.max(t0.min(t1));
+                far = far.min(t0.max(t1));
+                if near > far {
+                    return None;
+                }
+            }
+        }
+        Some(near)
+    }
+
+    // Distance to the nearest point of the solid sphere, 0 when the ray starts inside of it
+    pub fn intersect_sphere(&self, center: Vector3, radius: f32) -> Option<f32> {
+        let offset = self.origin - center;
+        let c = offset.length_square() - radius * radius;
+        if c <= 0.0 {
+            return Some(0.0);
+        }
+        let a = self.dir.length_square();
+        let b = offset.dot(self.dir);
+        let discriminant = b * b - a * c;
+        if a == 0.0 || b >= 0.0 || discriminant < 0.0 {
+            // Origin outside of the sphere and pointing away from its center
+            return None;
+        }
+        Some((-b - discriminant.sqrt()) / a)
+    }
+
+    // Moller-Trumbore intersection with double sided triangle, None when the ray is parallel
+    // to the triangle plane or the triangle is degenerate, hits at the origin are ignored
+    pub fn intersect_triangle(&self, a: Vector3, b: Vector3, c: Vector3) -> Option<f32> {
+        let edge_1 = b - a;
+        let edge_2 = c - a;
+        let p = self.dir.cross(edge_2);
+        let det = edge_1.dot(p);
+        if det.abs() < Self::TRIANGLE_EPS {
+            return None;
+        }
+        let inv_det = det.recip();
+        let s = self.origin - a;
+        let u = s.dot(p) * inv_det;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+        let q = s.cross(edge_1);
+        let v = self.dir.dot(q) * inv_det;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+        let t = edge_2.dot(q) * inv_det;
+        (t > Self::TRIANGLE_EPS).then_some(t)
+    }
+}
@@ -58,6 +58,42 @@ mod test_transform {
         assert!(p.approx_equal(Vector3::new(0.0, 1.0, 2.0f32.sqrt())));
     }
 
+    #[test]
+    fn inverse_transform() {
+        let t = get_transform();
+        let m: Matrix4 = (t * t.inverse()).into();
+        assert!(m.approx_equal(Matrix4::identity()));
+        let p = Vector3::new(1.0, 2.0, 3.0);
+        assert!((t.inverse() * (t * p)).approx_equal(p));
+    }
+
+    #[test]
+    fn transform_direction() {
+        let t = get_transform();
+        let d = t.transform_direction(Vector3::x());
+        let moved = t.translate(Vector3::new(5.0, -2.0, 1.0));
+        assert!(moved.transform_direction(Vector3::x()).approx_equal(d));
+        assert!(d.approx_equal(t * Vector3::x() - t * Vector3::zero()));
+    }
+
+    #[test]
+    fn lerp_endpoints() {
+        let (t_a, t_b) = get_transforms();
+        let p = Vector3::new(1.0, 2.0, 3.0);
+        assert!((t_a.lerp(&t_b, 0.0) * p).approx_equal(t_a * p));
+        assert!((t_a.lerp(&t_b, 1.0) * p).approx_equal(t_b * p));
+        assert!((t_a.lerp(&t_b, 2.0) * p).approx_equal(t_b * p));
+    }
+
+    #[test]
+    fn lerp_rotation_midpoint() {
+        let t_a = Transform::identity();
+        let t_b = Transform::identity().rotate(Vector3::z(), std::f32::consts::PI);
+        // Half turn has no unique shorter arc, either direction is valid
+        let p = t_a.lerp(&t_b, 0.5) * Vector3::x();
+        assert!(p.approx_equal(Vector3::y()) || p.approx_equal(-Vector3::y()));
+    }
+
     #[test]
     fn from_matrix() {
         let m = get_matrix();
@@ -162,6 +198,29 @@ impl Transform {
         let t_inv = -(q_inv * self.t);
         Self { q: q_inv, t: t_inv }
     }
+
+    // Transform holds no scale, quaternion rotation is normalized by `Quat::inv`
+    // so rigid inverse is exact for non-unit quaternions as well
+    #[inline]
+    pub fn inverse(&self) -> Self {
+        self.inv()
+    }
+
+    #[inline]
+    pub fn transform_direction(&self, dir: Vector3) -> Vector3 {
+        self.q * dir
+    }
+
+    // Translation is interpolated linearly and rotation spherically, `t` is clamped
+    // to [0, 1] range so that 0 and 1 reproduce `self` and `other` respectively
+    #[inline]
+    pub fn lerp(&self, other: &Transform, t: f32) -> Transform {
+        let t = t.clamp(0.0, 1.0);
+        Self {
+            q: self.q.slerp(other.q, t),
+            t: self.t + t * (other.t - self.t),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -1,6 +1,14 @@
 use crate::types::{Matrix4, Vector3, Vector4};
 
 impl Matrix4 {
+    // Gribb/Hartmann extraction of frustum planes from view-projection matrix,
+    // planes are ordered left, right, bottom, top, near, far with normals facing
+    // inside, point is inside of the plane when `plane * Vector4::point(p) >= 0`
+    pub fn frustum_planes(self) -> [Vector4; 6] {
+        let Matrix4 { i, j, k, l } = self.transpose();
+        [l + i, l - i, l + j, l - j, k, l - k].map(|plane| plane / Vector3::from(plane).length())
+    }
+
     #[inline]
     pub fn perspective(fov_y_rad: f32, aspect_ratio: f32, z_near: f32, z_far: f32) -> Matrix4 {
         let x_scale = (fov_y_rad * 0.5).tan().recip();
@@ -34,3 +42,38 @@ impl Matrix4 {
         }
     }
 }
+
+#[cfg(test)]
+mod test_projection {
+    use crate::types::{Matrix4, Vector3, Vector4};
+
+    const EPS: f32 = 1e-4;
+
+    fn is_inside(planes: &[Vector4; 6], point: Vector3) -> bool {
+        planes
+            .iter()
+            .all(|&plane| plane * Vector4::point(point) >= -EPS)
+    }
+
+    #[test]
+    fn frustum_planes_are_normalized() {
+        let proj = Matrix4::perspective(std::f32::consts::FRAC_PI_2, 1.5, 0.1, 100.0);
+        for plane in proj.frustum_planes() {
+            assert!((Vector3::from(plane).length() - 1.0).abs() < EPS);
+        }
+    }
+
+    #[test]
+    fn frustum_planes_classify_points() {
+        let proj = Matrix4::perspective(std::f32::consts::FRAC_PI_2, 1.0, 0.1, 100.0);
+        let view = Matrix4::look_at(Vector3::zero(), Vector3::x(), Vector3::z());
+        let planes = (proj * view).frustum_planes();
+        assert!(is_inside(&planes, Vector3::new(5.0, 0.0, 0.0)));
+        assert!(is_inside(&planes, Vector3::new(5.0, 4.0, -4.0)));
+        assert!(!is_inside(&planes, Vector3::new(-5.0, 0.0, 0.0)));
+        assert!(!is_inside(&planes, Vector3::new(0.05, 0.0, 0.0)));
+        assert!(!is_inside(&planes, Vector3::new(200.0, 0.0, 0.0)));
+        assert!(!is_inside(&planes, Vector3::new(5.0, 6.0, 0.0)));
+        assert!(!is_inside(&planes, Vector3::new(5.0, 0.0, -6.0)));
+    }
+}
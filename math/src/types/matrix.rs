@@ -18,6 +18,14 @@ mod test_matrix_2 {
         Matrix2::new(Vector2::new(1.0, 0.0), Vector2::new(2.0, 3.0))
     }
 
+    #[test]
+    fn le_bytes_round_trip() {
+        let m = get_matrix_2();
+        assert!(Matrix2::try_from_le_bytes(&m.to_le_bytes())
+            .unwrap()
+            .approx_equal(m));
+    }
+
     #[test]
     fn mul() {
         let m = get_matrix_2();
@@ -164,6 +172,14 @@ impl From<Matrix4> for Matrix2 {
 }
 
 impl Matrix2 {
+    #[inline]
+    pub fn to_le_bytes(&self) -> [u8; 16] {
+        let mut bytes = [0; 16];
+        bytes[0..8].copy_from_slice(&self.i.to_le_bytes());
+        bytes[8..16].copy_from_slice(&self.j.to_le_bytes());
+        bytes
+    }
+
     #[inline]
     pub fn try_from_le_bytes(bytes: &[u8]) -> Result<Self, Box<dyn Error>> {
         Ok(Self {
@@ -257,6 +273,14 @@ mod test_matrix_3 {
         )
     }
 
+    #[test]
+    fn le_bytes_round_trip() {
+        let m = get_matrix_3();
+        assert!(Matrix3::try_from_le_bytes(&m.to_le_bytes())
+            .unwrap()
+            .approx_equal(m));
+    }
+
     #[test]
     fn mul() {
         let m = get_matrix_3();
@@ -426,6 +450,15 @@ impl From<Matrix4> for Matrix3 {
 }
 
 impl Matrix3 {
+    #[inline]
+    pub fn to_le_bytes(&self) -> [u8; 36] {
+        let mut bytes = [0; 36];
+        bytes[0..12].copy_from_slice(&self.i.to_le_bytes());
+        bytes[12..24].copy_from_slice(&self.j.to_le_bytes());
+        bytes[24..36].copy_from_slice(&self.k.to_le_bytes());
+        bytes
+    }
+
     #[inline]
     pub fn try_from_le_bytes(bytes: &[u8]) -> Result<Self, Box<dyn Error>> {
         Ok(Self {
@@ -561,6 +594,14 @@ mod test_matrix_4 {
         )
     }
 
+    #[test]
+    fn le_bytes_round_trip() {
+        let m = get_matrix_4();
+        assert!(Matrix4::try_from_le_bytes(&m.to_le_bytes())
+            .unwrap()
+            .approx_equal(m));
+    }
+
     #[test]
     fn mul() {
         let m = get_matrix_4();
@@ -724,6 +765,16 @@ impl From<Matrix3> for Matrix4 {
 }
 
 impl Matrix4 {
+    #[inline]
+    pub fn to_le_bytes(&self) -> [u8; 64] {
+        let mut bytes = [0; 64];
+        bytes[0..16].copy_from_slice(&self.i.to_le_bytes());
+        bytes[16..32].copy_from_slice(&self.j.to_le_bytes());
+        bytes[32..48].copy_from_slice(&self.k.to_le_bytes());
+        bytes[48..64].copy_from_slice(&self.l.to_le_bytes());
+        bytes
+    }
+
     #[inline]
     pub fn try_from_le_bytes(bytes: &[u8]) -> Result<Self, Box<dyn Error>> {
         Ok(Self {
@@ -1,5 +1,5 @@
 use bytemuck::{Pod, Zeroable};
-use std::ops::Mul;
+use std::ops::{Add, Mul};
 
 use super::{Matrix3, Vector3};
 
@@ -49,6 +49,13 @@ mod test_quat {
         assert!((m_inv * Vector3::y()).approx_equal(Vector3::x()));
     }
 
+    #[test]
+    fn slerp() {
+        let q = Quat::identity().slerp(get_quat(), 0.5);
+        let q_expected = Quat::axis_angle(Vector3::z(), std::f32::consts::FRAC_PI_4);
+        assert!((q * Vector3::x()).approx_equal(q_expected * Vector3::x()));
+    }
+
     #[test]
     fn from_matrix() {
         let m = get_matrix();
@@ -68,6 +75,19 @@ pub struct Quat {
     pub k: f32,
 }
 
+impl Add for Quat {
+    type Output = Self;
+    #[inline]
+    fn add(self, rhs: Quat) -> Self::Output {
+        Self {
+            r: self.r + rhs.r,
+            i: self.i + rhs.i,
+            j: self.j + rhs.j,
+            k: self.k + rhs.k,
+        }
+    }
+}
+
 impl Mul<Quat> for f32 {
     type Output = Quat;
     #[inline]
@@ -230,4 +250,28 @@ impl Quat {
     pub fn is_valid(self) -> bool {
         self.r.is_finite() && self.i.is_finite() && self.j.is_finite() && self.k.is_finite()
     }
+
+    #[inline]
+    pub fn dot(self, rhs: Self) -> f32 {
+        self.r * rhs.r + self.i * rhs.i + self.j * rhs.j + self.k * rhs.k
+    }
+
+    // Interpolates along the shorter arc, nearly parallel quaternions
+    // are interpolated linearly to avoid division by small sine
+    #[inline]
+    pub fn slerp(self, rhs: Self, t: f32) -> Self {
+        let (lhs, rhs) = (self.norm(), rhs.norm());
+        let cos = lhs.dot(rhs);
+        let (rhs, cos) = if cos < 0.0 {
+            (-1.0 * rhs, -cos)
+        } else {
+            (rhs, cos)
+        };
+        if cos > 1.0 - 1e-4 {
+            return ((1.0 - t) * lhs + t * rhs).norm();
+        }
+        let angle = cos.acos();
+        let sin = angle.sin();
+        (((1.0 - t) * angle).sin() / sin) * lhs + ((t * angle).sin() / sin) * rhs
+    }
 }
@@ -138,6 +138,14 @@ impl From<Vector2> for [f32; 2] {
 }
 
 impl Vector2 {
+    #[inline]
+    pub fn to_le_bytes(&self) -> [u8; 8] {
+        let mut bytes = [0; 8];
+        bytes[0..4].copy_from_slice(&self.x.to_le_bytes());
+        bytes[4..8].copy_from_slice(&self.y.to_le_bytes());
+        bytes
+    }
+
     #[inline]
     pub fn try_from_le_bytes(bytes: &[u8]) -> Result<Self, Box<dyn Error>> {
         Ok(Self {
@@ -198,16 +206,222 @@ impl Vector2 {
             y: self.y * rhs.y,
         }
     }
+
+    #[inline]
+    pub fn dot(self, rhs: Self) -> f32 {
+        self * rhs
+    }
+
+    #[inline]
+    pub fn min(self, rhs: Self) -> Self {
+        Self {
+            x: self.x.min(rhs.x),
+            y: self.y.min(rhs.y),
+        }
+    }
+
+    #[inline]
+    pub fn max(self, rhs: Self) -> Self {
+        Self {
+            x: self.x.max(rhs.x),
+            y: self.y.max(rhs.y),
+        }
+    }
+
+    // Componentwise clamp, panics as `f32::clamp` when any component of `min` exceeds `max`
+    #[inline]
+    pub fn clamp(self, min: Self, max: Self) -> Self {
+        Self {
+            x: self.x.clamp(min.x, max.x),
+            y: self.y.clamp(min.y, max.y),
+        }
+    }
+
+    #[inline]
+    pub fn abs(self) -> Self {
+        Self {
+            x: self.x.abs(),
+            y: self.y.abs(),
+        }
+    }
+
+    #[inline]
+    pub fn min_elem(self) -> f32 {
+        self.x.min(self.y)
+    }
+
+    #[inline]
+    pub fn max_elem(self) -> f32 {
+        self.x.max(self.y)
+    }
+
+    // `t` is not clamped, values outside of [0, 1] extrapolate past `self` or `rhs`
+    #[inline]
+    pub fn lerp(self, rhs: Self, t: f32) -> Self {
+        self + t * (rhs - self)
+    }
 }
 
 #[cfg(test)]
 mod test_vector_3 {
-    use super::Vector3;
+    use std::f32::consts::{FRAC_PI_2, PI};
+
+    use super::{Vector2, Vector3, Vector4};
     #[test]
     fn cross() {
         assert!(Vector3::x().cross(Vector3::y()).approx_equal(Vector3::z()));
         assert!(Vector3::y().cross(Vector3::x()).approx_equal(-Vector3::z()));
     }
+
+    #[test]
+    fn le_bytes_round_trip() {
+        let v2 = Vector2::new(1.5, -2.0);
+        assert!(Vector2::try_from_le_bytes(&v2.to_le_bytes())
+            .unwrap()
+            .approx_equal(v2));
+        let v3 = Vector3::new(1.5, -2.0, 3.25);
+        assert!(Vector3::try_from_le_bytes(&v3.to_le_bytes())
+            .unwrap()
+            .approx_equal(v3));
+        let v4 = Vector4::new(1.5, -2.0, 3.25, -4.5);
+        assert!(Vector4::try_from_le_bytes(&v4.to_le_bytes())
+            .unwrap()
+            .approx_equal(v4));
+    }
+
+    #[test]
+    fn angle_between() {
+        assert!((Vector3::x().angle_between(Vector3::y()) - FRAC_PI_2).abs() < 1e-6);
+        assert!(Vector3::x().angle_between(2.0 * Vector3::x()).abs() < 1e-6);
+        assert!((Vector3::x().angle_between(-Vector3::x()) - PI).abs() < 1e-6);
+    }
+
+    #[test]
+    fn project_onto() {
+        let vector = Vector3::new(3.0, 4.0, 0.0);
+        assert!(vector
+            .project_onto(2.0 * Vector3::x())
+            .approx_equal(Vector3::new(3.0, 0.0, 0.0)));
+        assert!(vector
+            .project_onto(Vector3::z())
+            .approx_equal(Vector3::zero()));
+        assert!(vector.project_onto(vector).approx_equal(vector));
+    }
+
+    #[test]
+    fn reflect() {
+        let incident = Vector3::new(1.0, -1.0, 2.0);
+        assert!(incident
+            .reflect(Vector3::y())
+            .approx_equal(Vector3::new(1.0, 1.0, 2.0)));
+        assert!(incident
+            .reflect(-Vector3::z())
+            .approx_equal(Vector3::new(1.0, -1.0, -2.0)));
+    }
+
+    #[test]
+    fn reflect_non_unit_normal() {
+        let incident = Vector3::new(1.0, -1.0, 2.0);
+        let normal = 2.0 * Vector3::y();
+        // Reflected component is scaled by the squared normal length
+        assert!(incident
+            .reflect(normal)
+            .approx_equal(Vector3::new(1.0, 7.0, 2.0)));
+        assert!(incident
+            .reflect(normal.norm())
+            .approx_equal(Vector3::new(1.0, 1.0, 2.0)));
+    }
+
+    #[test]
+    fn componentwise_min_max() {
+        let a = Vector3::new(1.0, -2.0, 3.0);
+        let b = Vector3::new(-1.0, 2.0, 3.0);
+        assert!(a.min(b).approx_equal(Vector3::new(-1.0, -2.0, 3.0)));
+        assert!(a.max(b).approx_equal(Vector3::new(1.0, 2.0, 3.0)));
+        assert!(Vector2::new(0.5, -0.5)
+            .min(Vector2::zero())
+            .approx_equal(Vector2::new(0.0, -0.5)));
+        assert!(Vector4::new(1.0, -1.0, 0.0, 2.0)
+            .max(Vector4::zero())
+            .approx_equal(Vector4::new(1.0, 0.0, 0.0, 2.0)));
+    }
+
+    #[test]
+    fn componentwise_clamp_abs() {
+        let v = Vector3::new(-2.0, 0.5, 4.0);
+        assert!(v
+            .clamp(-Vector3::new(1.0, 1.0, 1.0), Vector3::new(1.0, 1.0, 1.0))
+            .approx_equal(Vector3::new(-1.0, 0.5, 1.0)));
+        assert!(v.abs().approx_equal(Vector3::new(2.0, 0.5, 4.0)));
+        assert!(Vector4::new(-1.0, 2.0, -3.0, -0.0)
+            .abs()
+            .approx_equal(Vector4::new(1.0, 2.0, 3.0, 0.0)));
+        assert!(Vector2::new(3.0, -3.0)
+            .clamp(Vector2::zero(), Vector2::new(1.0, 1.0))
+            .approx_equal(Vector2::new(1.0, 0.0)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn clamp_inverted_bounds_panics() {
+        Vector3::zero().clamp(Vector3::x(), Vector3::zero());
+    }
+
+    #[test]
+    fn min_max_elem() {
+        let v = Vector3::new(2.0, -3.0, 1.0);
+        assert_eq!(v.min_elem(), -3.0);
+        assert_eq!(v.max_elem(), 2.0);
+        assert_eq!(Vector2::new(1.0, 1.0).min_elem(), 1.0);
+        assert_eq!(Vector4::new(0.0, 1.0, -1.0, 5.0).max_elem(), 5.0);
+        // NaN components are ignored as in `f32::min`
+        assert_eq!(Vector3::new(f32::NAN, 1.0, 2.0).min_elem(), 1.0);
+    }
+
+    #[test]
+    fn lerp() {
+        let a = Vector3::new(0.0, 2.0, -1.0);
+        let b = Vector3::new(4.0, 2.0, 1.0);
+        assert!(a.lerp(b, 0.0).approx_equal(a));
+        assert!(a.lerp(b, 1.0).approx_equal(b));
+        assert!(a.lerp(b, 0.25).approx_equal(Vector3::new(1.0, 2.0, -0.5)));
+        // Parameter outside of [0, 1] extrapolates instead of being clamped
+        assert!(a.lerp(b, 1.5).approx_equal(Vector3::new(6.0, 2.0, 2.0)));
+        assert!(a.lerp(b, -0.5).approx_equal(Vector3::new(-2.0, 2.0, -2.0)));
+        assert!(Vector2::zero()
+            .lerp(Vector2::x(), 2.0)
+            .approx_equal(Vector2::new(2.0, 0.0)));
+        assert!(Vector4::zero()
+            .lerp(Vector4::w(), 0.5)
+            .approx_equal(Vector4::new(0.0, 0.0, 0.0, 0.5)));
+    }
+
+    #[test]
+    fn dot() {
+        let a = Vector3::new(1.0, 2.0, 3.0);
+        assert_eq!(a.dot(Vector3::new(4.0, -5.0, 6.0)), 12.0);
+        assert_eq!(a.dot(a), a.length_square());
+        assert_eq!(Vector2::x().dot(Vector2::y()), 0.0);
+        assert_eq!(Vector4::new(1.0, 1.0, 1.0, 1.0).dot(Vector4::w()), 1.0);
+    }
+
+    #[test]
+    fn extend_xyz_round_trip() {
+        let v = Vector3::new(1.0, -2.0, 3.0);
+        let extended = v.extend(1.0);
+        assert!(extended.approx_equal(Vector4::point(v)));
+        assert!(v.extend(0.0).approx_equal(Vector4::vector(v)));
+        assert!(extended.xyz().approx_equal(v));
+    }
+
+    #[test]
+    fn refract() {
+        let incident = Vector3::new(1.0, -1.0, 0.0).norm();
+        let refracted = incident.refract(Vector3::y(), 1.0).unwrap();
+        assert!(refracted.approx_equal(incident));
+        let grazing = Vector3::new(1.0, -0.1, 0.0).norm();
+        assert!(grazing.refract(Vector3::y(), 1.5).is_none());
+    }
 }
 
 #[repr(C)]
@@ -351,6 +565,15 @@ impl From<Vector3> for [f32; 3] {
 }
 
 impl Vector3 {
+    #[inline]
+    pub fn to_le_bytes(&self) -> [u8; 12] {
+        let mut bytes = [0; 12];
+        bytes[0..4].copy_from_slice(&self.x.to_le_bytes());
+        bytes[4..8].copy_from_slice(&self.y.to_le_bytes());
+        bytes[8..12].copy_from_slice(&self.z.to_le_bytes());
+        bytes
+    }
+
     #[inline]
     pub fn try_from_le_bytes(bytes: &[u8]) -> Result<Self, Box<dyn Error>> {
         Ok(Self {
@@ -401,6 +624,16 @@ impl Vector3 {
         }
     }
 
+    #[inline]
+    pub const fn extend(self, w: f32) -> Vector4 {
+        Vector4 {
+            x: self.x,
+            y: self.y,
+            z: self.z,
+            w,
+        }
+    }
+
     #[inline]
     pub fn from_euler(yaw: f32, pitch: f32, _roll: f32) -> Self {
         Self {
@@ -434,6 +667,19 @@ impl Vector3 {
         }
     }
 
+    // Cosine is clamped, so rounding errors of nearly parallel vectors do not produce NaN
+    #[inline]
+    pub fn angle_between(self, rhs: Self) -> f32 {
+        ((self * rhs) / (self.length() * rhs.length()))
+            .clamp(-1.0, 1.0)
+            .acos()
+    }
+
+    #[inline]
+    pub fn project_onto(self, rhs: Self) -> Self {
+        ((self * rhs) / rhs.length_square()) * rhs
+    }
+
     #[inline]
     pub fn is_valid(self) -> bool {
         self.x.is_finite() && self.y.is_finite() && self.z.is_finite()
@@ -452,6 +698,84 @@ impl Vector3 {
             z: self.z * rhs.z,
         }
     }
+
+    #[inline]
+    pub fn dot(self, rhs: Self) -> f32 {
+        self * rhs
+    }
+
+    #[inline]
+    pub fn min(self, rhs: Self) -> Self {
+        Self {
+            x: self.x.min(rhs.x),
+            y: self.y.min(rhs.y),
+            z: self.z.min(rhs.z),
+        }
+    }
+
+    #[inline]
+    pub fn max(self, rhs: Self) -> Self {
+        Self {
+            x: self.x.max(rhs.x),
+            y: self.y.max(rhs.y),
+            z: self.z.max(rhs.z),
+        }
+    }
+
+    // Componentwise clamp, panics as `f32::clamp` when any component of `min` exceeds `max`
+    #[inline]
+    pub fn clamp(self, min: Self, max: Self) -> Self {
+        Self {
+            x: self.x.clamp(min.x, max.x),
+            y: self.y.clamp(min.y, max.y),
+            z: self.z.clamp(min.z, max.z),
+        }
+    }
+
+    #[inline]
+    pub fn abs(self) -> Self {
+        Self {
+            x: self.x.abs(),
+            y: self.y.abs(),
+            z: self.z.abs(),
+        }
+    }
+
+    #[inline]
+    pub fn min_elem(self) -> f32 {
+        self.x.min(self.y).min(self.z)
+    }
+
+    #[inline]
+    pub fn max_elem(self) -> f32 {
+        self.x.max(self.y).max(self.z)
+    }
+
+    // `t` is not clamped, values outside of [0, 1] extrapolate past `self` or `rhs`
+    #[inline]
+    pub fn lerp(self, rhs: Self, t: f32) -> Self {
+        self + t * (rhs - self)
+    }
+
+    // Reflects incident direction about the surface with unit length `normal`, non unit
+    // normal scales the reflected component by its squared length, so it is not normalized here
+    #[inline]
+    pub fn reflect(self, normal: Self) -> Self {
+        self - 2.0 * (self * normal) * normal
+    }
+
+    // Refracts unit length incident direction through the surface with unit length
+    // `normal`, `eta` is the ratio of refractive indices, None on total internal reflection
+    #[inline]
+    pub fn refract(self, normal: Self, eta: f32) -> Option<Self> {
+        let cos_i = self * normal;
+        let k = 1.0 - eta * eta * (1.0 - cos_i * cos_i);
+        if k < 0.0 {
+            None
+        } else {
+            Some(eta * self - (eta * cos_i + k.sqrt()) * normal)
+        }
+    }
 }
 
 #[repr(C)]
@@ -603,6 +927,16 @@ impl From<Vector4> for [f32; 4] {
 }
 
 impl Vector4 {
+    #[inline]
+    pub fn to_le_bytes(&self) -> [u8; 16] {
+        let mut bytes = [0; 16];
+        bytes[0..4].copy_from_slice(&self.x.to_le_bytes());
+        bytes[4..8].copy_from_slice(&self.y.to_le_bytes());
+        bytes[8..12].copy_from_slice(&self.z.to_le_bytes());
+        bytes[12..16].copy_from_slice(&self.w.to_le_bytes());
+        bytes
+    }
+
     #[inline]
     pub fn try_from_le_bytes(bytes: &[u8]) -> Result<Self, Box<dyn Error>> {
         Ok(Self {
@@ -648,6 +982,15 @@ impl Vector4 {
         }
     }
 
+    #[inline]
+    pub const fn xyz(self) -> Vector3 {
+        Vector3 {
+            x: self.x,
+            y: self.y,
+            z: self.z,
+        }
+    }
+
     #[inline]
     pub const fn x() -> Self {
         Self {
@@ -725,4 +1068,66 @@ impl Vector4 {
             w: self.w * rhs.w,
         }
     }
+
+    #[inline]
+    pub fn dot(self, rhs: Self) -> f32 {
+        self * rhs
+    }
+
+    #[inline]
+    pub fn min(self, rhs: Self) -> Self {
+        Self {
+            x: self.x.min(rhs.x),
+            y: self.y.min(rhs.y),
+            z: self.z.min(rhs.z),
+            w: self.w.min(rhs.w),
+        }
+    }
+
+    #[inline]
+    pub fn max(self, rhs: Self) -> Self {
+        Self {
+            x: self.x.max(rhs.x),
+            y: self.y.max(rhs.y),
+            z: self.z.max(rhs.z),
+            w: self.w.max(rhs.w),
+        }
+    }
+
+    // Componentwise clamp, panics as `f32::clamp` when any component of `min` exceeds `max`
+    #[inline]
+    pub fn clamp(self, min: Self, max: Self) -> Self {
+        Self {
+            x: self.x.clamp(min.x, max.x),
+            y: self.y.clamp(min.y, max.y),
+            z: self.z.clamp(min.z, max.z),
+            w: self.w.clamp(min.w, max.w),
+        }
+    }
+
+    #[inline]
+    pub fn abs(self) -> Self {
+        Self {
+            x: self.x.abs(),
+            y: self.y.abs(),
+            z: self.z.abs(),
+            w: self.w.abs(),
+        }
+    }
+
+    #[inline]
+    pub fn min_elem(self) -> f32 {
+        self.x.min(self.y).min(self.z).min(self.w)
+    }
+
+    #[inline]
+    pub fn max_elem(self) -> f32 {
+        self.x.max(self.y).max(self.z).max(self.w)
+    }
+
+    // `t` is not clamped, values outside of [0, 1] extrapolate past `self` or `rhs`
+    #[inline]
+    pub fn lerp(self, rhs: Self, t: f32) -> Self {
+        self + t * (rhs - self)
+    }
 }
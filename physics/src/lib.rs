@@ -1 +1,3 @@
 pub mod shape;
+
+pub use math::geometry::Aabb;
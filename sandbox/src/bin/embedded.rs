@@ -0,0 +1,99 @@
+use graphics::{
+    model::{CommonVertex, EmptyMaterial, MeshBuilder, Model},
+    renderer::{camera::first_person::FirstPersonCameraBuilder, light::DirectionalLight},
+    shader::{EmbeddedShader, Shader},
+};
+use std::{error::Error, result::Result};
+use vulkan::{
+    context::device::{
+        memory::DefaultAllocator,
+        renderer::deferred::{DeferredRenderer, DeferredShader},
+    },
+    VulkanContextBuilder, VulkanRendererBuilder, VulkanRendererConfig,
+};
+use winit::{
+    dpi::PhysicalSize,
+    window::{WindowBuilder, WindowButtons},
+};
+
+use math::{
+    transform::Transform,
+    types::{Matrix4, Vector3},
+};
+use physics::shape::Cube;
+use system::{LoopBuilder, Object};
+
+const RENDERER_MEM_ALLOC_PAGE_SIZE: usize = 128 * 1024 * 1024;
+
+// Scene drawn only with the shaders embedded into the renderer,
+// without skybox, overlay and any asset or user shader files
+fn main() -> Result<(), Box<dyn Error>> {
+    let renderer_builder = VulkanRendererBuilder::<DeferredRenderer<DefaultAllocator>>::new()
+        .with_config(
+            VulkanRendererConfig::builder()
+                .with_page_size(RENDERER_MEM_ALLOC_PAGE_SIZE)
+                .build()?,
+        );
+    let proj = Matrix4::perspective(std::f32::consts::FRAC_PI_3, 600.0 / 800.0, 1e-3, 1e3);
+    let window_builder = WindowBuilder::new()
+        .with_inner_size(PhysicalSize {
+            width: 800,
+            height: 600,
+        })
+        .with_enabled_buttons(WindowButtons::CLOSE | WindowButtons::MINIMIZE)
+        .with_title("r_phy - embedded shaders")
+        .with_transparent(false);
+    let game_loop = LoopBuilder::new()
+        .with_window(window_builder)
+        .with_renderer(renderer_builder)
+        .with_camera(FirstPersonCameraBuilder::new(proj))
+        .build()?;
+    let mut context_builder = VulkanContextBuilder::new()
+        .with_material_type::<EmptyMaterial>()
+        .with_mesh_type::<CommonVertex>()
+        .with_shader_type::<DeferredShader<Shader<CommonVertex, EmptyMaterial>>>();
+    let empty_material = context_builder.add_material(EmptyMaterial::default());
+    let cube_mesh = context_builder.add_mesh::<CommonVertex, _>(Cube::new(1.0f32).into());
+    let ground_mesh = context_builder.add_mesh::<CommonVertex, _>(
+        MeshBuilder::plane_subdivided(
+            0,
+            20.0 * Vector3::x(),
+            20.0 * Vector3::y(),
+            Vector3::new(0.8, 0.8, 0.8),
+            true,
+        )
+        .offset(Vector3::new(-10.0, -10.0, -1.0))
+        .build(),
+    );
+    let unlit_shader = context_builder.add_shader::<DeferredShader<_>, _>(
+        Shader::<CommonVertex, EmptyMaterial>::embedded(EmbeddedShader::Unlit).into(),
+    );
+    let scene = game_loop
+        .scene(context_builder)?
+        .with_directional_light(DirectionalLight::new(
+            Vector3::new(-1.0, -0.5, -2.0),
+            Vector3::new(1.0, 1.0, 1.0),
+            1.0,
+        ))
+        .with_objects(
+            unlit_shader,
+            vec![
+                Object::new(
+                    Model::new(ground_mesh, empty_material),
+                    Transform::identity(),
+                    Box::new(|_, transform| transform),
+                ),
+                Object::new(
+                    Model::new(cube_mesh, empty_material),
+                    Transform::identity().translate(Vector3::new(4.0, 0.0, 0.0)),
+                    Box::new(|elapsed_time, transform| {
+                        Transform::identity()
+                            .rotate(Vector3::z(), elapsed_time * std::f32::consts::FRAC_PI_2)
+                            * transform
+                    }),
+                ),
+            ],
+        );
+    game_loop.run(scene)?;
+    Ok(())
+}
@@ -1,8 +1,12 @@
 use graphics::{
-    model::{CommonVertex, EmptyMaterial, Model, PbrMaterial, SimpleVertex, UnlitMaterial},
+    model::{
+        CommonVertex, EmptyMaterial, MeshBuilder, Model, PbrMaterial, SimpleVertex,
+        TransparentMaterial, UnlitMaterial,
+    },
+    renderer::{light::DirectionalLight, viewport::Rect, ContextBuilder},
     shader::Shader,
 };
-use std::{error::Error, result::Result};
+use std::{error::Error, path::Path, result::Result};
 use vulkan::{
     context::device::{
         memory::DefaultAllocator,
@@ -12,13 +16,16 @@ use vulkan::{
 };
 use winit::{
     dpi::PhysicalSize,
+    keyboard::KeyCode,
     window::{WindowBuilder, WindowButtons},
 };
 
-use graphics::renderer::camera::first_person::FirstPersonCameraBuilder;
+use graphics::renderer::camera::{
+    first_person::FirstPersonCameraBuilder, orbit::OrbitCameraBuilder,
+};
 use math::{
     transform::Transform,
-    types::{Matrix4, Vector3},
+    types::{Matrix4, Vector3, Vector4},
 };
 use physics::shape::Cube;
 use system::{LoopBuilder, Object};
@@ -30,6 +37,7 @@ fn main() -> Result<(), Box<dyn Error>> {
         .with_config(
             VulkanRendererConfig::builder()
                 .with_page_size(RENDERER_MEM_ALLOC_PAGE_SIZE)
+                .with_shader_hot_reload(cfg!(debug_assertions))
                 .build()?,
         );
     let proj = Matrix4::perspective(std::f32::consts::FRAC_PI_3, 600.0 / 800.0, 1e-3, 1e3);
@@ -38,27 +46,56 @@ fn main() -> Result<(), Box<dyn Error>> {
             width: 800,
             height: 600,
         })
-        .with_resizable(false)
         .with_enabled_buttons(WindowButtons::CLOSE | WindowButtons::MINIMIZE)
         .with_title("r_phy")
         .with_transparent(false);
     let camera_builder = FirstPersonCameraBuilder::new(proj);
+    // Split screen with two cameras orbiting the cubes from opposite sides
+    let (left_view, right_view) = Rect::full().split_vertical();
+    let view_proj = Matrix4::perspective(std::f32::consts::FRAC_PI_3, 600.0 / 400.0, 1e-3, 1e3);
+    let view_camera = |yaw: f32| {
+        OrbitCameraBuilder::new(view_proj)
+            .with_target(Vector3::new(4.0, 1.0, 0.0))
+            .with_distance(8.0)
+            .with_yaw(yaw)
+            .with_pitch(0.4)
+    };
     let game_loop = LoopBuilder::new()
         .with_window(window_builder)
         .with_renderer(renderer_builder)
         .with_camera(camera_builder)
+        .with_view(left_view, view_camera(0.0))
+        .with_view(right_view, view_camera(std::f32::consts::PI))
+        .with_fps_overlay(true)
+        .with_profiler(cfg!(feature = "profiling"))
         .build()?;
     let mut context_builder = VulkanContextBuilder::new()
         .with_material_type::<UnlitMaterial>()
         .with_material_type::<PbrMaterial>()
         .with_material_type::<EmptyMaterial>()
+        .with_material_type::<TransparentMaterial>()
         .with_mesh_type::<CommonVertex>()
         .with_mesh_type::<SimpleVertex>()
         .with_shader_type::<DeferredShader<Shader<CommonVertex, EmptyMaterial>>>()
         .with_shader_type::<DeferredShader<Shader<CommonVertex, UnlitMaterial>>>()
-        .with_shader_type::<DeferredShader<Shader<CommonVertex, PbrMaterial>>>();
+        .with_shader_type::<DeferredShader<Shader<CommonVertex, PbrMaterial>>>()
+        .with_shader_type::<DeferredShader<Shader<CommonVertex, TransparentMaterial>>>()
+        .with_skybox(Path::new("_resources/assets/skybox/skybox"))?;
     let empty_material = context_builder.add_material(EmptyMaterial::default());
+    let glass_material =
+        context_builder.add_material(TransparentMaterial::new(Vector4::new(0.2, 0.6, 1.0, 0.4)));
     let cube_mesh = context_builder.add_mesh::<CommonVertex, _>(Cube::new(1.0f32).into());
+    let ground_mesh = context_builder.add_mesh::<CommonVertex, _>(
+        MeshBuilder::plane_subdivided(
+            0,
+            20.0 * Vector3::x(),
+            20.0 * Vector3::y(),
+            Vector3::new(0.8, 0.8, 0.8),
+            true,
+        )
+        .offset(Vector3::new(-10.0, -10.0, -1.0))
+        .build(),
+    );
     // TODO: Explicit type conversion to the type used by selected renderer should not be visible at the front-end
     let checker_shader = context_builder.add_shader::<DeferredShader<_>, _>(
         Shader::<CommonVertex, EmptyMaterial>::new(
@@ -66,29 +103,68 @@ fn main() -> Result<(), Box<dyn Error>> {
         )
         .into(),
     );
-    let scene = game_loop.scene(context_builder)?.with_objects(
-        checker_shader,
-        vec![
-            Object::new(
-                Model::new(cube_mesh, empty_material),
-                Transform::identity().translate(Vector3::new(4.0, 0.0, 0.0)),
-                Box::new(|elapsed_time, transform| {
-                    Transform::identity()
-                        .rotate(Vector3::z(), elapsed_time * std::f32::consts::FRAC_PI_2)
-                        * transform
-                }),
-            ),
-            Object::new(
-                Model::new(cube_mesh, empty_material),
-                Transform::identity().translate(Vector3::new(4.0, 2.0, 0.0)),
-                Box::new(|elapsed_time, transform| {
-                    Transform::identity()
-                        .rotate(Vector3::z(), elapsed_time * std::f32::consts::FRAC_PI_2)
+    let transparent_shader = context_builder.add_shader::<DeferredShader<_>, _>(
+        Shader::<CommonVertex, TransparentMaterial>::new(
+            "_resources/shaders/spv/deferred/transparent",
+        )
+        .into(),
+    );
+    let scene = game_loop
+        .scene(context_builder)?
+        .with_directional_light(DirectionalLight::new(
+            Vector3::new(-1.0, -0.5, -2.0),
+            Vector3::new(1.0, 1.0, 1.0),
+            1.0,
+        ))
+        .with_objects(
+            checker_shader,
+            vec![
+                Object::new(
+                    Model::new(ground_mesh, empty_material),
+                    Transform::identity(),
+                    Box::new(|_, transform| transform),
+                ),
+                Object::new(
+                    Model::new(cube_mesh, empty_material),
+                    Transform::identity().translate(Vector3::new(4.0, 0.0, 0.0)),
+                    Box::new(|elapsed_time, transform| {
+                        Transform::identity()
+                            .rotate(Vector3::z(), elapsed_time * std::f32::consts::FRAC_PI_2)
+                            * transform
+                    }),
+                ),
+                Object::new(
+                    Model::new(cube_mesh, empty_material),
+                    Transform::identity().translate(Vector3::new(4.0, 2.0, 0.0)),
+                    Box::new(|elapsed_time, transform| {
+                        Transform::identity()
+                            .rotate(Vector3::z(), elapsed_time * std::f32::consts::FRAC_PI_2)
+                            * transform
+                    }),
+                ),
+            ],
+        )
+        .with_objects(
+            transparent_shader,
+            vec![Object::with_context(
+                Model::new(cube_mesh, glass_material),
+                Transform::identity().translate(Vector3::new(2.0, 1.0, 0.0)),
+                // Glass cube is moved on the ground plane with arrow keys
+                Box::new(|context, transform| {
+                    let direction = [
+                        (KeyCode::ArrowUp, Vector3::x()),
+                        (KeyCode::ArrowDown, -Vector3::x()),
+                        (KeyCode::ArrowLeft, Vector3::y()),
+                        (KeyCode::ArrowRight, -Vector3::y()),
+                    ]
+                    .into_iter()
+                    .filter(|(key, _)| context.input.is_key_pressed(*key))
+                    .fold(Vector3::zero(), |direction, (_, offset)| direction + offset);
+                    Transform::identity().translate(2.0 * context.elapsed_time * direction)
                         * transform
                 }),
-            ),
-        ],
-    );
+            )],
+        );
     game_loop.run(scene)?;
     Ok(())
 }
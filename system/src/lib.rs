@@ -1,14 +1,35 @@
-use type_kit::{Cons, Nil};
+mod profiler;
+mod scene_graph;
+mod stats;
+mod timestep;
+
+pub use input::Input;
+pub use profiler::Profiler;
+pub use scene_graph::{ObjectId, SceneGraph, SceneGraphError};
+pub use stats::{FrameStats, DEFAULT_STATS_WINDOW};
+pub use timestep::MAX_FIXED_STEPS;
+
+use stats::FrameStatsAccumulator;
+use timestep::FixedTimestep;
+use type_kit::{Cons, GenCollection, GenIndex, GenIndexRaw, Nil};
 use winit::{
-    dpi::PhysicalPosition,
+    dpi::{PhysicalPosition, PhysicalSize},
     event::{ElementState, Event, StartCause, WindowEvent},
     event_loop::{ControlFlow, EventLoop},
     keyboard::KeyCode,
-    window::{Window, WindowBuilder},
+    window::{Window, WindowBuilder, WindowId},
 };
 
-use math::{transform::Transform, types::Matrix4};
-use std::{cell::RefCell, error::Error, rc::Rc, time::Instant};
+use math::{transform::Transform, types::Vector4};
+use std::{
+    any::Any,
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    error::Error,
+    rc::Rc,
+    thread,
+    time::{Duration, Instant},
+};
 
 use graphics::{
     model::Drawable,
@@ -17,7 +38,9 @@ use graphics::{
 
 use graphics::renderer::{
     camera::{Camera, CameraBuilder, CameraNone},
-    ContextBuilder, Renderer, RendererBuilder, RendererContext,
+    light::DirectionalLight,
+    viewport::Rect,
+    ContextBuilder, RenderError, Renderer, RendererBuilder, RendererContext,
 };
 use input::InputHandler;
 
@@ -25,13 +48,27 @@ use input::InputHandler;
 pub struct DrawCommand<S: ShaderType, D: Drawable<Material = S::Material, Vertex = S::Vertex>> {
     shader: ShaderHandle<S>,
     model: D,
-    transform: Matrix4,
+    // Index of the object world transform in the scene graph
+    id: ObjectId,
+}
+
+// Per frame state passed to scene objects update closures, `elapsed_time`
+// is the fixed update duration when the loop runs with fixed timestep
+pub struct UpdateContext<'a> {
+    pub elapsed_time: f32,
+    pub stats: &'a FrameStats,
+    pub input: &'a Input,
 }
 
+pub type ObjectUpdate = Box<dyn Fn(&UpdateContext, Transform) -> Transform>;
+pub type ObjectStatsUpdate = Box<dyn Fn(&FrameStats, Transform) -> Transform>;
+
 pub struct Object<D: Drawable + Clone + Copy> {
     model: D,
     transform: Transform,
-    update: Box<dyn Fn(f32, Transform) -> Transform>,
+    // Transform before the latest update, blended with the current one for rendering
+    previous_transform: Transform,
+    update: ObjectUpdate,
 }
 
 impl<D: Drawable + Clone + Copy> Object<D> {
@@ -40,25 +77,52 @@ impl<D: Drawable + Clone + Copy> Object<D> {
         transform: Transform,
         update: Box<dyn Fn(f32, Transform) -> Transform>,
     ) -> Self {
+        Self::with_context(
+            model,
+            transform,
+            Box::new(move |context, transform| update(context.elapsed_time, transform)),
+        )
+    }
+
+    pub fn with_stats(model: D, transform: Transform, update: ObjectStatsUpdate) -> Self {
+        Self::with_context(
+            model,
+            transform,
+            Box::new(move |context, transform| update(context.stats, transform)),
+        )
+    }
+
+    pub fn with_context(model: D, transform: Transform, update: ObjectUpdate) -> Self {
         Self {
             model,
             transform,
+            previous_transform: transform,
             update,
         }
     }
 
+    // Updated transform is relative to the object parent in the scene graph
     fn update<S: ShaderType<Vertex = D::Vertex, Material = D::Material>>(
         &mut self,
         shader: ShaderHandle<S>,
-        elapsed_time: f32,
+        context: &UpdateContext,
+        id: ObjectId,
+        graph: &mut SceneGraph,
     ) -> DrawCommand<S, D> {
-        self.transform = (self.update)(elapsed_time, self.transform);
+        self.previous_transform = self.transform;
+        self.transform = (self.update)(context, self.transform);
+        let _ = graph.set_local(id, self.transform.into());
         DrawCommand {
             shader,
             model: self.model,
-            transform: self.transform.into(),
+            id,
         }
     }
+
+    fn interpolate(&self, alpha: f32, id: ObjectId, graph: &mut SceneGraph) {
+        let transform = self.previous_transform.lerp(&self.transform, alpha);
+        let _ = graph.set_local(id, transform.into());
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -93,10 +157,34 @@ impl CursorState {
     }
 }
 
+type StatsCallback = Box<dyn FnMut(&FrameStats)>;
+
+// Additional window presenting the scene from its own camera,
+// the camera is not updated from the input
+struct SecondaryWindow {
+    // Kept open until the window is removed from the renderer
+    window: Window,
+    camera: Rc<RefCell<dyn Camera>>,
+}
+
+// Region of the primary window presenting the scene from its own camera,
+// the camera is updated from the input together with the loop camera
+struct View {
+    viewport: Rect,
+    camera: Rc<RefCell<dyn Camera>>,
+}
+
 pub struct LoopBuilder<R: RendererBuilder, C: CameraBuilder> {
     camera: Option<C>,
     renderer: Option<R>,
     window: Option<WindowBuilder>,
+    secondary_windows: Vec<(WindowBuilder, Rc<RefCell<dyn Camera>>)>,
+    views: Vec<View>,
+    max_fps: Option<f32>,
+    fixed_timestep: Option<f32>,
+    on_stats: Option<StatsCallback>,
+    fps_overlay: bool,
+    profiler: bool,
 }
 
 impl Default for LoopBuilder<Nil, CameraNone> {
@@ -111,6 +199,13 @@ impl LoopBuilder<Nil, CameraNone> {
             camera: None,
             window: None,
             renderer: None,
+            secondary_windows: Vec::new(),
+            views: Vec::new(),
+            max_fps: None,
+            fixed_timestep: None,
+            on_stats: None,
+            fps_overlay: false,
+            profiler: false,
         }
     }
 }
@@ -123,51 +218,175 @@ impl<R: RendererBuilder, C: CameraBuilder> LoopBuilder<R, C> {
         }
     }
 
+    // Scene is rendered into each secondary window with the window camera,
+    // closing secondary window leaves the loop running
+    pub fn with_secondary_window(
+        mut self,
+        window: WindowBuilder,
+        camera: impl CameraBuilder,
+    ) -> Self {
+        self.secondary_windows.push((window, camera.build()));
+        self
+    }
+
+    // Once any view is added, the scene is rendered into the primary window
+    // only through views, each from its own camera, e.g. for split screen
+    pub fn with_view(mut self, viewport: Rect, camera: impl CameraBuilder) -> Self {
+        self.views.push(View {
+            viewport,
+            camera: camera.build(),
+        });
+        self
+    }
+
     pub fn with_renderer<N: RendererBuilder>(self, renderer: N) -> LoopBuilder<N, C> {
-        let Self { window, camera, .. } = self;
+        let Self {
+            window,
+            secondary_windows,
+            views,
+            camera,
+            max_fps,
+            fixed_timestep,
+            on_stats,
+            fps_overlay,
+            profiler,
+            ..
+        } = self;
         LoopBuilder {
             renderer: Some(renderer),
             window,
+            secondary_windows,
+            views,
             camera,
+            max_fps,
+            fixed_timestep,
+            on_stats,
+            fps_overlay,
+            profiler,
         }
     }
 
     pub fn with_camera<N: CameraBuilder>(self, camera: N) -> LoopBuilder<R, N> {
         let Self {
-            window, renderer, ..
+            window,
+            secondary_windows,
+            views,
+            renderer,
+            max_fps,
+            fixed_timestep,
+            on_stats,
+            fps_overlay,
+            profiler,
+            ..
         } = self;
         LoopBuilder {
             camera: Some(camera),
             window,
+            secondary_windows,
+            views,
             renderer,
+            max_fps,
+            fixed_timestep,
+            on_stats,
+            fps_overlay,
+            profiler,
+        }
+    }
+
+    // Caps frame rate by sleeping for the remainder of the frame budget
+    pub fn with_max_fps(self, max_fps: f32) -> Self {
+        Self {
+            max_fps: Some(max_fps),
+            ..self
+        }
+    }
+
+    // Objects are updated in steps of constant `dt` seconds, independent of the
+    // frame rate, rendered transforms are interpolated between the last two steps,
+    // `build` fails unless `dt` is positive and finite
+    pub fn with_fixed_timestep(self, dt: f32) -> Self {
+        Self {
+            fixed_timestep: Some(dt),
+            ..self
+        }
+    }
+
+    // Called once per frame after frame submission
+    pub fn on_stats(self, callback: impl FnMut(&FrameStats) + 'static) -> Self {
+        Self {
+            on_stats: Some(Box::new(callback)),
+            ..self
+        }
+    }
+
+    // Draws frame rate averaged over the stats window and draw statistics
+    // of the renderer in the top left corner
+    pub fn with_fps_overlay(self, enabled: bool) -> Self {
+        Self {
+            fps_overlay: enabled,
+            ..self
+        }
+    }
+
+    // Writes profiling spans of the loop and renderer into Chrome trace file,
+    // requires the `profiling` feature, see `Profiler`
+    pub fn with_profiler(self, enabled: bool) -> Self {
+        Self {
+            profiler: enabled,
+            ..self
         }
     }
 
     pub fn build(self) -> Result<Loop<R::Renderer, C::Camera>, Box<dyn Error>> {
         let Self {
             window,
+            secondary_windows,
+            views,
             renderer,
             camera,
+            max_fps,
+            fixed_timestep,
+            on_stats,
+            fps_overlay,
+            profiler,
         } = self;
-        let mut input_handler = InputHandler::new();
+        let fixed_timestep = fixed_timestep
+            .map(|dt| FixedTimestep::new(dt).ok_or("Fixed timestep has to be positive and finite!"))
+            .transpose()?;
+        // Started first, so that renderer initialization is recorded as well
+        let profiler = profiler.then(Profiler::start).transpose()?;
+        let input_handler = InputHandler::new();
         let event_loop = EventLoop::new()?;
         let window = Rc::new(
             window
                 .ok_or("Window configuration not provided for Loop!")?
                 .build(&event_loop)?,
         );
-        let renderer = renderer
+        let mut renderer = renderer
             .ok_or("Renderer backend not selected for Loop!")?
             .build(&window)?;
-        let camera = camera
-            .ok_or("Camera not selected for Loop!")?
-            .build(&mut input_handler);
+        let secondary_windows = secondary_windows
+            .into_iter()
+            .map(|(window, camera)| {
+                let window = window.build(&event_loop)?;
+                let window_id = renderer.add_window(&window)?;
+                Ok((window_id, SecondaryWindow { window, camera }))
+            })
+            .collect::<Result<HashMap<_, _>, Box<dyn Error>>>()?;
+        let camera = camera.ok_or("Camera not selected for Loop!")?.build();
         Ok(Loop {
             event_loop,
             window,
+            secondary_windows,
+            views,
             renderer,
             input_handler,
             camera,
+            max_fps,
+            fixed_timestep,
+            on_stats,
+            fps_overlay,
+            _profiler: profiler,
         })
     }
 }
@@ -189,7 +408,29 @@ pub struct DrawableContainer<
     D: Drawable<Material = S::Material, Vertex = S::Vertex> + Clone + Copy,
 > {
     shader: ShaderHandle<S>,
-    objects: Vec<Object<D>>,
+    objects: GenCollection<(ObjectId, Object<D>)>,
+}
+
+// Location of the object within the drawable collection, containers are
+// identified by their distance from the end of the list which stays fixed
+// as new containers are added in front
+#[derive(Debug, Clone, Copy)]
+pub struct ObjectSlot {
+    container: usize,
+    index: GenIndexRaw,
+}
+
+impl<S: ShaderType, D: Drawable<Material = S::Material, Vertex = S::Vertex> + Clone + Copy>
+    DrawableContainer<S, D>
+{
+    // `container` is the number of containers following this one in the list
+    fn insert(&mut self, container: usize, id: ObjectId, object: Object<D>) -> ObjectSlot {
+        let index = self.objects.push((id, object)).unwrap();
+        ObjectSlot {
+            container,
+            index: index.into_raw(),
+        }
+    }
 }
 
 impl<
@@ -204,11 +445,22 @@ impl<
 }
 
 pub trait DrawCommandCollection: DrawableTypeList {
-    fn draw<R: RendererContext>(self, renderer: &mut R);
+    fn draw<R: RendererContext>(&self, renderer: &mut R, graph: &SceneGraph);
+
+    // Total number of draw commands in the collection
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
 }
 
 impl DrawCommandCollection for Nil {
-    fn draw<R: RendererContext>(self, _renderer: &mut R) {}
+    fn draw<R: RendererContext>(&self, _renderer: &mut R, _graph: &SceneGraph) {}
+
+    fn len(&self) -> usize {
+        0
+    }
 }
 
 impl<
@@ -228,29 +480,57 @@ impl<
         N: DrawCommandCollection,
     > DrawCommandCollection for Cons<Vec<DrawCommand<S, D>>, N>
 {
-    fn draw<R: RendererContext>(self, renderer: &mut R) {
-        for DrawCommand {
-            shader,
-            model,
-            transform,
-        } in self.head
-        {
-            let _ = renderer.draw(shader, &model, &transform);
+    fn draw<R: RendererContext>(&self, renderer: &mut R, graph: &SceneGraph) {
+        // All draw commands of the container share the same shader
+        if let Some(&DrawCommand { shader, .. }) = self.head.first() {
+            let items = self
+                .head
+                .iter()
+                .filter_map(|command| Some((&command.model, graph.world(command.id).ok()?)))
+                .collect::<Vec<_>>();
+            let _ = renderer.draw_batch(shader, &items);
         }
-        self.tail.draw(renderer);
+        self.tail.draw(renderer, graph);
+    }
+
+    fn len(&self) -> usize {
+        self.head.len() + self.tail.len()
     }
 }
 
+// Object spawned during the run loop, along with its shader handle
+// boxed as `(ShaderHandle<S>, Object<D>)`
+type PendingSpawn = (ObjectId, Box<dyn Any>);
+
 pub trait DrawableCollection: DrawableTypeList {
     type DrawCommands: DrawCommandCollection;
-    fn update(&mut self, elapsed_time: f32) -> Self::DrawCommands;
+    fn update(&mut self, context: &UpdateContext, graph: &mut SceneGraph) -> Self::DrawCommands;
+
+    // Sets local transforms in between the last two updates, `alpha` of 0.0
+    // selects the previous and 1.0 the current transform
+    fn interpolate(&self, alpha: f32, graph: &mut SceneGraph);
+
+    // Moves the object into the first container of matching type and shader,
+    // object is handed back when there is none
+    fn spawn(&mut self, spawn: PendingSpawn) -> Result<ObjectSlot, PendingSpawn>;
+
+    // Drops the object stored in `slot`, other objects are not visited
+    fn remove(&mut self, slot: ObjectSlot);
 }
 
 impl DrawableCollection for Nil {
     type DrawCommands = Self;
-    fn update(&mut self, _elapsed_time: f32) -> Self::DrawCommands {
+    fn update(&mut self, _context: &UpdateContext, _graph: &mut SceneGraph) -> Self::DrawCommands {
         Nil::new()
     }
+
+    fn interpolate(&self, _alpha: f32, _graph: &mut SceneGraph) {}
+
+    fn spawn(&mut self, spawn: PendingSpawn) -> Result<ObjectSlot, PendingSpawn> {
+        Err(spawn)
+    }
+
+    fn remove(&mut self, _slot: ObjectSlot) {}
 }
 
 impl<
@@ -261,16 +541,41 @@ impl<
 {
     type DrawCommands = Cons<Vec<DrawCommand<S, D>>, N::DrawCommands>;
 
-    fn update(&mut self, elapsed_time: f32) -> Self::DrawCommands {
-        let draw = self
-            .head
-            .objects
-            .iter_mut()
-            .map(|object| object.update(self.head.shader, elapsed_time))
+    fn update(&mut self, context: &UpdateContext, graph: &mut SceneGraph) -> Self::DrawCommands {
+        let shader = self.head.shader;
+        let draw = (&mut self.head.objects)
+            .into_iter()
+            .map(|(id, object)| object.update(shader, context, *id, graph))
             .collect();
         Cons {
             head: draw,
-            tail: self.tail.update(elapsed_time),
+            tail: self.tail.update(context, graph),
+        }
+    }
+
+    fn interpolate(&self, alpha: f32, graph: &mut SceneGraph) {
+        for (id, object) in &self.head.objects {
+            object.interpolate(alpha, *id, graph);
+        }
+        self.tail.interpolate(alpha, graph);
+    }
+
+    fn spawn(&mut self, spawn: PendingSpawn) -> Result<ObjectSlot, PendingSpawn> {
+        let (id, object) = spawn;
+        match object.downcast::<(ShaderHandle<S>, Object<D>)>() {
+            Ok(object) if object.0.index() == self.head.shader.index() => {
+                Ok(self.head.insert(N::LEN, id, object.1))
+            }
+            Ok(object) => self.tail.spawn((id, object)),
+            Err(object) => self.tail.spawn((id, object)),
+        }
+    }
+
+    fn remove(&mut self, slot: ObjectSlot) {
+        if slot.container == N::LEN {
+            let _ = self.head.objects.pop(GenIndex::from_raw(slot.index));
+        } else {
+            self.tail.remove(slot);
         }
     }
 }
@@ -278,9 +583,17 @@ impl<
 pub struct Loop<R: Renderer, C: Camera> {
     renderer: R,
     window: Rc<Window>,
+    secondary_windows: HashMap<WindowId, SecondaryWindow>,
+    views: Vec<View>,
     event_loop: EventLoop<()>,
     input_handler: InputHandler,
     camera: Rc<RefCell<C>>,
+    max_fps: Option<f32>,
+    fixed_timestep: Option<FixedTimestep>,
+    on_stats: Option<StatsCallback>,
+    fps_overlay: bool,
+    // Flushes recorded trace once the loop has finished
+    _profiler: Option<Profiler>,
 }
 
 pub trait LoopTypes {
@@ -293,9 +606,71 @@ impl<R: Renderer, C: Camera> LoopTypes for Loop<R, C> {
     type Camera = C;
 }
 
+type SceneUpdate = Box<dyn FnMut(&mut SceneCommands, &Input, f32)>;
+
+// Scene mutations requested from the scene update callback, applied after
+// the callback returns and before objects of the frame are updated
+pub struct SceneCommands<'a> {
+    graph: &'a mut SceneGraph,
+    spawned: &'a mut Vec<PendingSpawn>,
+    despawned: &'a mut Vec<ObjectId>,
+}
+
+impl SceneCommands<'_> {
+    // Returned id can be used right away, e.g. for parenting the new object
+    pub fn spawn<
+        S: ShaderType,
+        T: Drawable<Vertex = S::Vertex, Material = S::Material> + Clone + Copy + 'static,
+    >(
+        &mut self,
+        shader: ShaderHandle<S>,
+        object: Object<T>,
+    ) -> ObjectId {
+        let id = self.graph.insert();
+        self.spawned.push((id, Box::new((shader, object))));
+        id
+    }
+
+    // Despawned object is removed along with all of its descendants
+    pub fn despawn(&mut self, id: ObjectId) {
+        self.despawned.push(id);
+    }
+
+    pub fn add_child(&mut self, parent: ObjectId, child: ObjectId) -> Result<(), SceneGraphError> {
+        self.graph.add_child(parent, child)
+    }
+}
+
 pub struct Scene<D: DrawableCollection, B: ContextBuilder> {
-    builder: B,
+    // Taken when the scene starts running
+    builder: Option<B>,
     objects: D,
+    // Container slots of scene objects, used to drop despawned objects
+    // without visiting the remaining ones
+    slots: HashMap<ObjectId, ObjectSlot>,
+    graph: SceneGraph,
+    spawned: Vec<PendingSpawn>,
+    despawned: Vec<ObjectId>,
+    // Ids of objects added with the latest `with_objects` call
+    last_object_ids: Vec<ObjectId>,
+    light: Option<DirectionalLight>,
+    update: Option<SceneUpdate>,
+}
+
+impl<B: ContextBuilder> Scene<Nil, B> {
+    fn new(builder: B) -> Self {
+        Scene {
+            builder: Some(builder),
+            objects: Nil::new(),
+            slots: HashMap::new(),
+            graph: SceneGraph::new(),
+            spawned: Vec::new(),
+            despawned: Vec::new(),
+            last_object_ids: Vec::new(),
+            light: None,
+            update: None,
+        }
+    }
 }
 
 impl<D: DrawableCollection, B: ContextBuilder> Scene<D, B> {
@@ -307,13 +682,189 @@ impl<D: DrawableCollection, B: ContextBuilder> Scene<D, B> {
         shader: ShaderHandle<S>,
         objects: Vec<Object<T>>,
     ) -> Scene<Cons<DrawableContainer<S, T>, D>, B> {
+        let mut graph = self.graph;
+        let mut slots = self.slots;
+        let mut head = DrawableContainer {
+            shader,
+            objects: GenCollection::with_capacity(objects.len()),
+        };
+        let last_object_ids = objects
+            .into_iter()
+            .map(|object| {
+                let id = graph.insert();
+                slots.insert(id, head.insert(D::LEN, id, object));
+                id
+            })
+            .collect();
         Scene {
             builder: self.builder,
+            last_object_ids,
             objects: Cons {
-                head: DrawableContainer { shader, objects },
+                head,
                 tail: self.objects,
             },
+            slots,
+            graph,
+            spawned: self.spawned,
+            despawned: self.despawned,
+            light: self.light,
+            update: self.update,
+        }
+    }
+
+    pub fn last_object_ids(&self) -> &[ObjectId] {
+        &self.last_object_ids
+    }
+
+    // Transform of the child object becomes relative to the parent world transform
+    pub fn add_child(&mut self, parent: ObjectId, child: ObjectId) -> Result<(), SceneGraphError> {
+        self.graph.add_child(parent, child)
+    }
+
+    // Object is added to the first container created for the same shader
+    pub fn spawn<
+        S: ShaderType,
+        T: Drawable<Vertex = S::Vertex, Material = S::Material> + Clone + Copy + 'static,
+    >(
+        &mut self,
+        shader: ShaderHandle<S>,
+        object: Object<T>,
+    ) -> Result<ObjectId, SceneGraphError> {
+        let id = self.graph.insert();
+        match self.objects.spawn((id, Box::new((shader, object)))) {
+            Ok(slot) => {
+                self.slots.insert(id, slot);
+                Ok(id)
+            }
+            Err(_) => {
+                self.graph.remove(id)?;
+                Err(SceneGraphError::MissingContainer)
+            }
+        }
+    }
+
+    // Removes the object along with all of its descendants
+    pub fn despawn(&mut self, id: ObjectId) -> Result<(), SceneGraphError> {
+        for id in self.graph.remove(id)? {
+            if let Some(slot) = self.slots.remove(&id) {
+                self.objects.remove(slot);
+            }
         }
+        Ok(())
+    }
+
+    // Called once per frame before scene objects are updated
+    pub fn with_update(self, mut update: impl FnMut(&Input, f32) + 'static) -> Self {
+        self.with_scene_update(move |_, input, elapsed_time| update(input, elapsed_time))
+    }
+
+    // Update callback which can spawn and despawn scene objects
+    pub fn with_scene_update(
+        self,
+        update: impl FnMut(&mut SceneCommands, &Input, f32) + 'static,
+    ) -> Self {
+        Scene {
+            update: Some(Box::new(update)),
+            ..self
+        }
+    }
+
+    pub fn on_update(&mut self, input: &Input, elapsed_time: f32) {
+        if let Some(update) = self.update.as_mut() {
+            let mut commands = SceneCommands {
+                graph: &mut self.graph,
+                spawned: &mut self.spawned,
+                despawned: &mut self.despawned,
+            };
+            update(&mut commands, input, elapsed_time);
+        }
+        self.apply_commands();
+    }
+
+    // Runs object updates for the frame and propagates the scene graph, with fixed
+    // timestep the draw commands of the latest update are returned, None when
+    // the frame was too short for any update to run
+    fn update_objects(
+        &mut self,
+        timestep: Option<&mut FixedTimestep>,
+        elapsed_time: f32,
+        stats: &FrameStats,
+        input: &Input,
+    ) -> Option<D::DrawCommands> {
+        let draw_commands = match timestep {
+            Some(timestep) => {
+                let context = UpdateContext {
+                    elapsed_time: timestep.dt(),
+                    stats,
+                    input,
+                };
+                let draw_commands = (0..timestep.advance(elapsed_time))
+                    .map(|_| self.objects.update(&context, &mut self.graph))
+                    .last();
+                self.objects.interpolate(timestep.alpha(), &mut self.graph);
+                draw_commands
+            }
+            None => Some(self.objects.update(
+                &UpdateContext {
+                    elapsed_time,
+                    stats,
+                    input,
+                },
+                &mut self.graph,
+            )),
+        };
+        self.graph.propagate();
+        draw_commands
+    }
+
+    // Spawns are applied first, so objects spawned and despawned
+    // within the same frame are never drawn
+    fn apply_commands(&mut self) {
+        for (id, object) in std::mem::take(&mut self.spawned) {
+            match self.objects.spawn((id, object)) {
+                Ok(slot) => {
+                    self.slots.insert(id, slot);
+                }
+                Err((id, _)) => {
+                    eprintln!(
+                        "Failed to spawn object: {}",
+                        SceneGraphError::MissingContainer
+                    );
+                    let _ = self.graph.remove(id);
+                }
+            }
+        }
+        for id in std::mem::take(&mut self.despawned) {
+            // Descendants of already despawned objects are gone as well
+            let _ = self.despawn(id);
+        }
+    }
+
+    pub fn with_directional_light(self, light: DirectionalLight) -> Self {
+        Scene {
+            light: Some(light),
+            ..self
+        }
+    }
+}
+
+// Failed frame is dropped, when its swapchain no longer matches the window the renderer
+// is resized to the window before the next frame, `window` of None is the primary one
+fn recover_frame<R: Renderer>(
+    renderer: &mut R,
+    window: Option<WindowId>,
+    size: PhysicalSize<u32>,
+    error: RenderError,
+) {
+    let result = match (error, window) {
+        (RenderError::SwapchainOutOfDate, None) => renderer.resize(size.width, size.height),
+        (RenderError::SwapchainOutOfDate, Some(window)) => {
+            renderer.resize_window(window, size.width, size.height)
+        }
+        (error, _) => Err(error.into()),
+    };
+    if let Err(err) = result {
+        eprintln!("Failed to render frame: {}", err);
     }
 }
 
@@ -322,10 +873,7 @@ impl<R: Renderer, C: Camera> Loop<R, C> {
         &self,
         builder: B,
     ) -> Result<Scene<Nil, B>, Box<dyn Error>> {
-        Ok(Scene {
-            builder,
-            objects: Nil::new(),
-        })
+        Ok(Scene::new(builder))
     }
 
     pub fn run<D: DrawableCollection, B: ContextBuilder<Renderer = R>>(
@@ -334,12 +882,23 @@ impl<R: Renderer, C: Camera> Loop<R, C> {
     ) -> Result<(), Box<dyn Error>> {
         let Self {
             window,
+            mut secondary_windows,
+            views,
             event_loop,
-            renderer,
+            mut renderer,
             mut input_handler,
             camera,
+            max_fps,
+            mut fixed_timestep,
+            mut on_stats,
+            fps_overlay,
+            _profiler,
         } = self;
-        let mut context = scene.builder.build(&renderer)?;
+        let mut context = scene
+            .builder
+            .take()
+            .ok_or("Scene context already built!")?
+            .build(&renderer)?;
         let cursor_state = Rc::new(RefCell::new(CursorState::new()));
         let shared_cursor_state = cursor_state.clone();
         let shared_window = window.clone();
@@ -356,19 +915,55 @@ impl<R: Renderer, C: Camera> Loop<R, C> {
                 }
             }),
         );
+        let light_enabled = Rc::new(Cell::new(true));
+        let shared_light_enabled = light_enabled.clone();
+        input_handler.register_key_state_callback(
+            KeyCode::KeyL,
+            Box::new(move |state| {
+                if let ElementState::Pressed = state {
+                    shared_light_enabled.set(!shared_light_enabled.get());
+                }
+            }),
+        );
         let mut draw_commands = None;
+        let mut stats = FrameStatsAccumulator::new(DEFAULT_STATS_WINDOW);
+        let frame_budget = max_fps.map(|max_fps| Duration::from_secs_f32(1.0 / max_fps));
         let mut previous_frame_time = Instant::now();
         event_loop.set_control_flow(ControlFlow::Poll);
         event_loop.run(|event, elwt| {
             input_handler.handle_event(event.clone());
             match event {
                 Event::NewEvents(StartCause::Poll) => {
+                    graphics::profile_scope!("update");
                     let current_frame_time = Instant::now();
                     let elapsed_time = (current_frame_time - previous_frame_time).as_secs_f32();
                     previous_frame_time = current_frame_time;
+                    stats.push_frame_time(elapsed_time);
 
-                    camera.borrow_mut().update(elapsed_time);
-                    draw_commands = Some(scene.objects.update(elapsed_time));
+                    let input = input_handler.input();
+                    camera.borrow_mut().update(input, elapsed_time);
+                    for view in &views {
+                        view.camera.borrow_mut().update(input, elapsed_time);
+                    }
+                    {
+                        graphics::profile_scope!("scene_update");
+                        scene.on_update(input, elapsed_time);
+                    }
+                    {
+                        graphics::profile_scope!("objects_update");
+                        // Frames too short for a fixed update keep drawing
+                        // commands of the previous one
+                        if let Some(commands) = scene.update_objects(
+                            fixed_timestep.as_mut(),
+                            elapsed_time,
+                            stats.stats(),
+                            input,
+                        ) {
+                            draw_commands = Some(commands);
+                        }
+                    }
+                    input_handler.end_frame();
+                    stats.set_update_time(current_frame_time.elapsed());
                     if let CursorState::Locked = *(*cursor_state).borrow() {
                         let window_extent = window.inner_size();
                         let _ = window.set_cursor_position(PhysicalPosition {
@@ -379,17 +974,95 @@ impl<R: Renderer, C: Camera> Loop<R, C> {
                 }
                 Event::WindowEvent {
                     event: WindowEvent::CloseRequested,
-                    ..
+                    window_id,
                 } => {
-                    elwt.exit();
+                    if window_id == window.id() {
+                        elwt.exit();
+                    } else if secondary_windows.contains_key(&window_id) {
+                        if let Err(err) = renderer.remove_window(window_id) {
+                            eprintln!("Failed to remove window: {}", err);
+                        }
+                        secondary_windows.remove(&window_id);
+                    }
+                }
+                Event::WindowEvent {
+                    event: WindowEvent::Resized(size),
+                    window_id,
+                } => {
+                    let result = if window_id == window.id() {
+                        renderer.resize(size.width, size.height)
+                    } else if secondary_windows.contains_key(&window_id) {
+                        renderer.resize_window(window_id, size.width, size.height)
+                    } else {
+                        Ok(())
+                    };
+                    if let Err(err) = result {
+                        eprintln!("Failed to resize renderer: {}", err);
+                    }
                 }
                 Event::AboutToWait => {
+                    graphics::profile_scope!("render");
+                    let render_start = Instant::now();
                     let camera: &C = &(*camera).borrow();
-                    let _ = context.begin_frame(camera);
-                    if let Some(draw_commands) = draw_commands.take() {
-                        draw_commands.draw(&mut context);
+                    context.set_directional_light(scene.light.filter(|_| light_enabled.get()));
+                    let result = context.begin_frame(camera).and_then(|()| {
+                        if views.is_empty() {
+                            if let Some(draw_commands) = &draw_commands {
+                                draw_commands.draw(&mut context, &scene.graph);
+                            }
+                        }
+                        for view in &views {
+                            let _ = context.begin_view(view.viewport, &*view.camera.borrow());
+                            if let Some(draw_commands) = &draw_commands {
+                                draw_commands.draw(&mut context, &scene.graph);
+                            }
+                            let _ = context.end_view();
+                        }
+                        if fps_overlay {
+                            // Render stats are those of the previously finished frame
+                            let render_stats = context.stats();
+                            let text = format!(
+                                "FPS {:.0} draws {} triangles {}",
+                                stats.stats().fps,
+                                render_stats.draw_calls,
+                                render_stats.triangles
+                            );
+                            context.draw_text(8.0, 8.0, &text, Vector4::new(1.0, 1.0, 1.0, 1.0));
+                        }
+                        context.end_frame()
+                    });
+                    if let Err(err) = result {
+                        recover_frame(&mut renderer, None, window.inner_size(), err);
+                    }
+                    for (&window_id, secondary) in &secondary_windows {
+                        let result = context
+                            .begin_frame_for(window_id, &*secondary.camera.borrow())
+                            .and_then(|()| {
+                                if let Some(draw_commands) = &draw_commands {
+                                    draw_commands.draw(&mut context, &scene.graph);
+                                }
+                                context.end_frame()
+                            });
+                        if let Err(err) = result {
+                            recover_frame(
+                                &mut renderer,
+                                Some(window_id),
+                                secondary.window.inner_size(),
+                                err,
+                            );
+                        }
+                    }
+                    stats.set_render_time(render_start.elapsed());
+                    if let Some(on_stats) = on_stats.as_mut() {
+                        on_stats(stats.stats());
+                    }
+                    if let Some(frame_budget) = frame_budget {
+                        graphics::profile_scope!("frame_limit");
+                        let frame_duration = previous_frame_time.elapsed();
+                        if frame_duration < frame_budget {
+                            thread::sleep(frame_budget - frame_duration);
+                        }
                     }
-                    let _ = context.end_frame();
                 }
                 _ => (),
             }
@@ -397,3 +1070,210 @@ impl<R: Renderer, C: Camera> Loop<R, C> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, error::Error, rc::Rc};
+
+    use graphics::{
+        model::{CommonVertex, EmptyMaterial, MaterialHandle, MeshHandle, Model},
+        renderer::{ContextId, RenderError, Renderer},
+        shader::{Shader, ShaderHandle},
+    };
+    use math::transform::Transform;
+    use type_kit::Nil;
+    use winit::{
+        dpi::PhysicalSize,
+        window::{Window, WindowId},
+    };
+
+    use crate::{
+        recover_frame, timestep::FixedTimestep, DrawCommandCollection, DrawableCollection,
+        FrameStats, Input, Object, ObjectId, Scene, UpdateContext,
+    };
+
+    type TestShader = Shader<CommonVertex, EmptyMaterial>;
+    type TestModel = Model<EmptyMaterial, CommonVertex>;
+
+    fn object() -> Object<TestModel> {
+        Object::new(
+            Model::new(
+                MeshHandle::new(0, ContextId::next()),
+                MaterialHandle::new(0, ContextId::next()),
+            ),
+            Transform::identity(),
+            Box::new(|_, transform| transform),
+        )
+    }
+
+    // Runs single frame of the scene update, returns number of recorded draw commands
+    fn frame<D: DrawableCollection>(scene: &mut Scene<D, Nil>) -> usize {
+        let input = Input::default();
+        scene.on_update(&input, 1.0 / 60.0);
+        let commands = scene.objects.update(
+            &UpdateContext {
+                elapsed_time: 1.0 / 60.0,
+                stats: &FrameStats::default(),
+                input: &input,
+            },
+            &mut scene.graph,
+        );
+        scene.graph.propagate();
+        commands.len()
+    }
+
+    #[test]
+    fn test_spawn_and_despawn_across_frames() {
+        let shader = ShaderHandle::<TestShader>::new(0, ContextId::next());
+        let spawned = Rc::new(Cell::new(None::<ObjectId>));
+        let frame_index = Rc::new(Cell::new(0));
+        let (shared_spawned, shared_frame_index) = (spawned.clone(), frame_index.clone());
+        let mut scene = Scene::new(Nil::new())
+            .with_objects(shader, vec![object(), object()])
+            .with_scene_update(move |commands, _, _| {
+                match shared_frame_index.get() {
+                    1 => shared_spawned.set(Some(commands.spawn(shader, object()))),
+                    3 => commands.despawn(shared_spawned.get().unwrap()),
+                    _ => (),
+                }
+                shared_frame_index.set(shared_frame_index.get() + 1);
+            });
+
+        assert_eq!(frame(&mut scene), 2);
+        assert_eq!(frame(&mut scene), 3);
+        assert_eq!(frame(&mut scene), 3);
+        assert_eq!(frame(&mut scene), 2);
+        assert_eq!(frame(&mut scene), 2);
+
+        let stale = spawned.get().unwrap();
+        assert!(scene.despawn(stale).is_err());
+        assert_eq!(frame_index.get(), 5);
+    }
+
+    #[test]
+    fn test_despawn_removes_children_and_keeps_world_transforms() {
+        let shader = ShaderHandle::<TestShader>::new(0, ContextId::next());
+        let mut scene = Scene::new(Nil::new()).with_objects(shader, vec![object()]);
+        let root = scene.last_object_ids()[0];
+        let child = scene.spawn(shader, object()).unwrap();
+        let other = scene.spawn(shader, object()).unwrap();
+        scene.add_child(root, child).unwrap();
+        assert_eq!(frame(&mut scene), 3);
+
+        scene.despawn(root).unwrap();
+        assert_eq!(frame(&mut scene), 1);
+        assert!(!scene.graph.contains(child));
+        assert!(scene.graph.world(other).is_ok());
+    }
+
+    #[test]
+    fn test_only_surviving_object_is_drawn() {
+        let shader = ShaderHandle::<TestShader>::new(0, ContextId::next());
+        let mut scene =
+            Scene::new(Nil::new()).with_objects(shader, Vec::<Object<TestModel>>::new());
+        let removed = scene.spawn(shader, object()).unwrap();
+        let survivor = scene.spawn(shader, object()).unwrap();
+        scene.despawn(removed).unwrap();
+        let input = Input::default();
+        let commands = scene.objects.update(
+            &UpdateContext {
+                elapsed_time: 1.0 / 60.0,
+                stats: &FrameStats::default(),
+                input: &input,
+            },
+            &mut scene.graph,
+        );
+        let drawn = commands
+            .head
+            .iter()
+            .map(|command| command.id)
+            .collect::<Vec<_>>();
+        assert_eq!(drawn, vec![survivor]);
+    }
+
+    #[test]
+    fn test_spawn_without_matching_container_fails() {
+        let shader = ShaderHandle::<TestShader>::new(0, ContextId::next());
+        let mut scene = Scene::new(Nil::new()).with_objects(shader, vec![object()]);
+        let other_shader = ShaderHandle::<TestShader>::new(1, ContextId::next());
+        assert!(scene.spawn(other_shader, object()).is_err());
+        assert_eq!(scene.graph.len(), 1);
+        assert!(scene.spawn(shader, object()).is_ok());
+        assert_eq!(frame(&mut scene), 2);
+    }
+
+    #[test]
+    fn test_fixed_timestep_update_count_independent_of_frame_rate() {
+        const DT: f32 = 1.0 / 64.0;
+        let shader = ShaderHandle::<TestShader>::new(0, ContextId::next());
+        let updates = Rc::new(Cell::new(0));
+        let shared_updates = updates.clone();
+        let counted = Object::new(
+            Model::new(
+                MeshHandle::new(0, ContextId::next()),
+                MaterialHandle::new(0, ContextId::next()),
+            ),
+            Transform::identity(),
+            Box::new(move |elapsed_time, transform| {
+                assert_eq!(elapsed_time, DT);
+                shared_updates.set(shared_updates.get() + 1);
+                transform
+            }),
+        );
+        let mut scene = Scene::new(Nil::new()).with_objects(shader, vec![counted]);
+        let mut timestep = FixedTimestep::new(DT).unwrap();
+        let input = Input::default();
+        // Frames shorter than, equal to and longer than the update add up to a second
+        let frame_times = [1.0 / 128.0, 1.0 / 32.0, 3.0 / 128.0];
+        for &frame_time in frame_times.iter().cycle().take(48) {
+            scene.update_objects(
+                Some(&mut timestep),
+                frame_time,
+                &FrameStats::default(),
+                &input,
+            );
+        }
+        assert_eq!(updates.get(), 64);
+    }
+
+    // Records resizes requested by the loop
+    #[derive(Default)]
+    struct ResizeRecorder {
+        resized: Vec<(u32, u32)>,
+    }
+
+    impl Renderer for ResizeRecorder {
+        fn resize(&mut self, width: u32, height: u32) -> Result<(), Box<dyn Error>> {
+            self.resized.push((width, height));
+            Ok(())
+        }
+
+        fn add_window(&mut self, window: &Window) -> Result<WindowId, Box<dyn Error>> {
+            Ok(window.id())
+        }
+
+        fn remove_window(&mut self, _window: WindowId) -> Result<(), Box<dyn Error>> {
+            Ok(())
+        }
+
+        fn resize_window(
+            &mut self,
+            _window: WindowId,
+            _width: u32,
+            _height: u32,
+        ) -> Result<(), Box<dyn Error>> {
+            unreachable!()
+        }
+    }
+
+    #[test]
+    fn test_out_of_date_swapchain_resizes_renderer() {
+        let mut renderer = ResizeRecorder::default();
+        let size = PhysicalSize::new(640, 480);
+        recover_frame(&mut renderer, None, size, RenderError::SwapchainOutOfDate);
+        assert_eq!(renderer.resized, vec![(640, 480)]);
+        // Other failures drop the frame without touching the swapchain
+        recover_frame(&mut renderer, None, size, RenderError::DeviceLost);
+        assert_eq!(renderer.resized, vec![(640, 480)]);
+    }
+}
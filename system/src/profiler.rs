@@ -0,0 +1,30 @@
+use std::error::Error;
+
+// Records spans of the `profiling` feature into `trace-<timestamp>.json`
+// of the working directory, the file can be opened as a flamegraph
+// in Perfetto UI or chrome://tracing. Trace is flushed on drop
+#[cfg(feature = "profiling")]
+pub struct Profiler {
+    _guard: tracing_chrome::FlushGuard,
+}
+
+#[cfg(feature = "profiling")]
+impl Profiler {
+    pub fn start() -> Result<Self, Box<dyn Error>> {
+        use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+
+        let (layer, guard) = tracing_chrome::ChromeLayerBuilder::new().build();
+        tracing_subscriber::registry().with(layer).try_init()?;
+        Ok(Self { _guard: guard })
+    }
+}
+
+#[cfg(not(feature = "profiling"))]
+pub struct Profiler;
+
+#[cfg(not(feature = "profiling"))]
+impl Profiler {
+    pub fn start() -> Result<Self, Box<dyn Error>> {
+        Err("Profiler requires the `profiling` feature to be enabled")?
+    }
+}
@@ -0,0 +1,307 @@
+use std::{error::Error, fmt::Display};
+
+use math::types::Matrix4;
+use type_kit::{GenCollection, GenCollectionError, GenIndex};
+
+#[derive(Debug, Clone)]
+struct SceneNode {
+    parent: Option<ObjectId>,
+    children: Vec<ObjectId>,
+    local: Matrix4,
+    world: Matrix4,
+}
+
+// Generational scene object index, ids of despawned objects are never reused
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ObjectId(GenIndex<SceneNode>);
+
+#[derive(Debug, Clone, Copy)]
+pub enum SceneGraphError {
+    InvalidObject(ObjectId),
+    Cycle { parent: ObjectId, child: ObjectId },
+    MissingContainer,
+    Storage(GenCollectionError),
+}
+
+impl Display for SceneGraphError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SceneGraphError::InvalidObject(id) => {
+                write!(f, "Object {:?} not present in the scene", id.0)
+            }
+            SceneGraphError::Cycle { parent, child } => write!(
+                f,
+                "Parenting object {:?} to {:?} would create a cycle",
+                child.0, parent.0
+            ),
+            SceneGraphError::MissingContainer => {
+                write!(f, "Scene has no objects container matching spawned object")
+            }
+            SceneGraphError::Storage(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl Error for SceneGraphError {}
+
+impl From<GenCollectionError> for SceneGraphError {
+    fn from(value: GenCollectionError) -> Self {
+        SceneGraphError::Storage(value)
+    }
+}
+
+// Parent links of scene objects along with their local and world transforms,
+// objects without a parent use local transform as the world one
+#[derive(Debug, Default)]
+pub struct SceneGraph {
+    nodes: GenCollection<SceneNode>,
+    // Objects ordered so that each parent precedes its children,
+    // rebuilt on the next propagation after the hierarchy changes
+    order: Vec<ObjectId>,
+    order_dirty: bool,
+}
+
+impl SceneGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.nodes.len() == 0
+    }
+
+    #[inline]
+    pub fn contains(&self, id: ObjectId) -> bool {
+        self.nodes.contains(id.0)
+    }
+
+    // Registers new root object with identity transform
+    pub fn insert(&mut self) -> ObjectId {
+        let index = self
+            .nodes
+            .push(SceneNode {
+                parent: None,
+                children: Vec::new(),
+                local: Matrix4::identity(),
+                world: Matrix4::identity(),
+            })
+            .unwrap();
+        self.order_dirty = true;
+        ObjectId(index)
+    }
+
+    // Removes the object along with all of its descendants, returns removed ids,
+    // only the removed subtree is visited
+    pub fn remove(&mut self, id: ObjectId) -> Result<Vec<ObjectId>, SceneGraphError> {
+        if let Some(parent) = self.node(id)?.parent {
+            self.detach(parent, id)?;
+        }
+        let mut removed = vec![id];
+        let mut next = 0;
+        while let Some(&id) = removed.get(next) {
+            removed.extend(self.nodes.pop(id.0)?.children);
+            next += 1;
+        }
+        self.order_dirty = true;
+        Ok(removed)
+    }
+
+    fn detach(&mut self, parent: ObjectId, child: ObjectId) -> Result<(), SceneGraphError> {
+        let children = &mut self.node_mut(parent)?.children;
+        if let Some(position) = children.iter().position(|&id| id == child) {
+            children.swap_remove(position);
+        }
+        Ok(())
+    }
+
+    fn node(&self, id: ObjectId) -> Result<&SceneNode, SceneGraphError> {
+        self.nodes
+            .get(id.0)
+            .map_err(|_| SceneGraphError::InvalidObject(id))
+    }
+
+    fn node_mut(&mut self, id: ObjectId) -> Result<&mut SceneNode, SceneGraphError> {
+        self.nodes
+            .get_mut(id.0)
+            .map_err(|_| SceneGraphError::InvalidObject(id))
+    }
+
+    pub fn parent(&self, id: ObjectId) -> Option<ObjectId> {
+        self.node(id).ok().and_then(|node| node.parent)
+    }
+
+    fn ancestors(&self, id: ObjectId) -> impl Iterator<Item = ObjectId> + '_ {
+        std::iter::successors(Some(id), |&id| self.parent(id))
+    }
+
+    // Child previously parented to other object is moved under the new parent
+    pub fn add_child(&mut self, parent: ObjectId, child: ObjectId) -> Result<(), SceneGraphError> {
+        self.node(parent)?;
+        self.node(child)?;
+        if self.ancestors(parent).any(|id| id == child) {
+            return Err(SceneGraphError::Cycle { parent, child });
+        }
+        if let Some(previous) = self.node_mut(child)?.parent.replace(parent) {
+            self.detach(previous, child)?;
+        }
+        self.node_mut(parent)?.children.push(child);
+        self.order_dirty = true;
+        Ok(())
+    }
+
+    // Objects are sorted by their depth in the hierarchy, which places
+    // every parent before its children
+    fn update_order(&mut self) {
+        let mut order = self
+            .nodes
+            .iter_with_indices()
+            .map(|(index, _)| {
+                let id = ObjectId(index);
+                (self.ancestors(id).count(), id)
+            })
+            .collect::<Vec<_>>();
+        order.sort_by_key(|&(depth, _)| depth);
+        self.order = order.into_iter().map(|(_, id)| id).collect();
+        self.order_dirty = false;
+    }
+
+    #[inline]
+    pub fn set_local(&mut self, id: ObjectId, local: Matrix4) -> Result<(), SceneGraphError> {
+        self.node_mut(id)?.local = local;
+        Ok(())
+    }
+
+    pub fn propagate(&mut self) {
+        if self.order_dirty {
+            self.update_order();
+        }
+        for &id in &self.order {
+            let SceneNode { parent, local, .. } = self.nodes[id.0];
+            self.nodes[id.0].world = match parent {
+                Some(parent) => self.nodes[parent.0].world * local,
+                None => local,
+            };
+        }
+    }
+
+    #[inline]
+    pub fn world(&self, id: ObjectId) -> Result<Matrix4, SceneGraphError> {
+        Ok(self.node(id)?.world)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use math::{
+        transform::Transform,
+        types::{Vector3, Vector4},
+    };
+
+    use super::*;
+
+    fn world_position(graph: &SceneGraph, id: ObjectId) -> Vector4 {
+        graph.world(id).unwrap() * Vector4::point(Vector3::zero())
+    }
+
+    fn insert_objects<const N: usize>(graph: &mut SceneGraph) -> [ObjectId; N] {
+        [(); N].map(|_| graph.insert())
+    }
+
+    #[test]
+    fn test_rotating_root_moves_grandchild() {
+        let mut graph = SceneGraph::new();
+        let [root, child, grandchild] = insert_objects(&mut graph);
+        graph.add_child(root, child).unwrap();
+        graph.add_child(child, grandchild).unwrap();
+
+        let offset = Transform::identity().translate(Vector3::x()).into();
+        let rotation = Transform::identity()
+            .rotate(Vector3::z(), std::f32::consts::FRAC_PI_2)
+            .into();
+        graph.set_local(root, rotation).unwrap();
+        graph.set_local(child, offset).unwrap();
+        graph.set_local(grandchild, offset).unwrap();
+        graph.propagate();
+
+        assert!(world_position(&graph, root).approx_equal(Vector4::point(Vector3::zero())));
+        assert!(world_position(&graph, child).approx_equal(Vector4::point(Vector3::y())));
+        assert!(world_position(&graph, grandchild).approx_equal(Vector4::point(2.0 * Vector3::y())));
+    }
+
+    #[test]
+    fn test_children_added_before_parents_are_propagated_in_order() {
+        let mut graph = SceneGraph::new();
+        let [grandchild, child, root] = insert_objects(&mut graph);
+        graph.add_child(child, grandchild).unwrap();
+        graph.add_child(root, child).unwrap();
+
+        let offset = Transform::identity().translate(Vector3::z()).into();
+        for id in [grandchild, child, root] {
+            graph.set_local(id, offset).unwrap();
+        }
+        graph.propagate();
+
+        assert!(world_position(&graph, grandchild).approx_equal(Vector4::point(3.0 * Vector3::z())));
+    }
+
+    #[test]
+    fn test_add_child_rejects_cycles() {
+        let mut graph = SceneGraph::new();
+        let [root, child, grandchild] = insert_objects(&mut graph);
+        graph.add_child(root, child).unwrap();
+        graph.add_child(child, grandchild).unwrap();
+
+        assert!(matches!(
+            graph.add_child(grandchild, root),
+            Err(SceneGraphError::Cycle { parent, child }) if parent == grandchild && child == root
+        ));
+        assert!(matches!(
+            graph.add_child(root, root),
+            Err(SceneGraphError::Cycle { .. })
+        ));
+        assert_eq!(graph.parent(root), None);
+    }
+
+    #[test]
+    fn test_remove_despawns_descendants_and_invalidates_ids() {
+        let mut graph = SceneGraph::new();
+        let [root, child, grandchild, other] = insert_objects(&mut graph);
+        graph.add_child(root, child).unwrap();
+        graph.add_child(child, grandchild).unwrap();
+
+        let mut removed = graph.remove(child).unwrap();
+        removed.sort_by_key(|id| id.0.into_raw().index());
+        assert_eq!(removed, [child, grandchild]);
+        assert!(graph.contains(root) && graph.contains(other));
+        assert!(!graph.contains(child) && !graph.contains(grandchild));
+        assert!(matches!(
+            graph.remove(child),
+            Err(SceneGraphError::InvalidObject(id)) if id == child
+        ));
+
+        let reused = graph.insert();
+        assert_ne!(reused, child);
+        assert_ne!(reused, grandchild);
+        graph.propagate();
+        assert!(world_position(&graph, reused).approx_equal(Vector4::point(Vector3::zero())));
+    }
+
+    #[test]
+    fn test_remove_skips_children_moved_to_other_parent() {
+        let mut graph = SceneGraph::new();
+        let [first, second, child] = insert_objects(&mut graph);
+        graph.add_child(first, child).unwrap();
+        graph.add_child(second, child).unwrap();
+
+        assert_eq!(graph.remove(first).unwrap(), [first]);
+        assert_eq!(graph.parent(child), Some(second));
+        assert_eq!(graph.remove(second).unwrap(), [second, child]);
+        assert!(graph.is_empty());
+    }
+}
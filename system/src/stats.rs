@@ -0,0 +1,109 @@
+use std::{collections::VecDeque, time::Duration};
+
+pub const DEFAULT_STATS_WINDOW: usize = 240;
+
+// Times in seconds, fps values computed over the last `DEFAULT_STATS_WINDOW` frames
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FrameStats {
+    pub frame_time: f32,
+    pub update_time: f32,
+    pub render_time: f32,
+    pub fps: f32,
+    pub low_1_percent_fps: f32,
+    pub frame_count: u64,
+}
+
+pub(crate) struct FrameStatsAccumulator {
+    stats: FrameStats,
+    frame_times: VecDeque<f32>,
+    window_len: usize,
+}
+
+impl FrameStatsAccumulator {
+    pub fn new(window_len: usize) -> Self {
+        Self {
+            stats: FrameStats::default(),
+            frame_times: VecDeque::with_capacity(window_len),
+            window_len: window_len.max(1),
+        }
+    }
+
+    #[inline]
+    pub fn stats(&self) -> &FrameStats {
+        &self.stats
+    }
+
+    pub fn push_frame_time(&mut self, frame_time: f32) {
+        if self.frame_times.len() == self.window_len {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(frame_time);
+        self.stats.frame_time = frame_time;
+        self.stats.fps = average_fps(self.frame_times.iter().copied());
+        self.stats.low_1_percent_fps = low_percentile_fps(&self.frame_times, 0.01);
+        self.stats.frame_count += 1;
+    }
+
+    #[inline]
+    pub fn set_update_time(&mut self, update_time: Duration) {
+        self.stats.update_time = update_time.as_secs_f32();
+    }
+
+    #[inline]
+    pub fn set_render_time(&mut self, render_time: Duration) {
+        self.stats.render_time = render_time.as_secs_f32();
+    }
+}
+
+fn average_fps(frame_times: impl ExactSizeIterator<Item = f32>) -> f32 {
+    let num_frames = frame_times.len();
+    let total_time: f32 = frame_times.sum();
+    if total_time > 0.0 {
+        num_frames as f32 / total_time
+    } else {
+        0.0
+    }
+}
+
+// Average fps of the slowest `fraction` of frames, at least one frame is always taken
+fn low_percentile_fps(frame_times: &VecDeque<f32>, fraction: f32) -> f32 {
+    let mut sorted = frame_times.iter().copied().collect::<Vec<_>>();
+    sorted.sort_by(|a, b| b.total_cmp(a));
+    let num_frames = ((sorted.len() as f32 * fraction).ceil() as usize).max(1);
+    average_fps(sorted[..num_frames.min(sorted.len())].iter().copied())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_average_fps_matches_synthetic_timestep() {
+        let mut accumulator = FrameStatsAccumulator::new(DEFAULT_STATS_WINDOW);
+        for _ in 0..100 {
+            accumulator.push_frame_time(1.0 / 60.0);
+        }
+        let stats = accumulator.stats();
+        assert!((stats.fps - 60.0).abs() < 1e-2);
+        assert!((stats.low_1_percent_fps - 60.0).abs() < 1e-2);
+        assert_eq!(stats.frame_count, 100);
+    }
+
+    #[test]
+    fn test_average_fps_follows_window() {
+        let mut accumulator = FrameStatsAccumulator::new(10);
+        for _ in 0..10 {
+            accumulator.push_frame_time(1.0 / 60.0);
+        }
+        for _ in 0..5 {
+            accumulator.push_frame_time(1.0 / 30.0);
+        }
+        // Half of the window at each timestep
+        assert!((accumulator.stats().fps - 40.0).abs() < 1e-2);
+        for _ in 0..5 {
+            accumulator.push_frame_time(1.0 / 30.0);
+        }
+        assert!((accumulator.stats().fps - 30.0).abs() < 1e-2);
+        assert!((accumulator.stats().frame_time - 1.0 / 30.0).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,64 @@
+// Upper bound of updates run within a single frame, time exceeding it is dropped
+// so that slow frames do not accumulate ever growing number of updates
+pub const MAX_FIXED_STEPS: usize = 8;
+
+// Accumulates frame times and splits them into updates of constant duration,
+// times in seconds
+pub(crate) struct FixedTimestep {
+    dt: f32,
+    accumulator: f32,
+}
+
+impl FixedTimestep {
+    // Returns None unless `dt` is positive and finite
+    pub fn new(dt: f32) -> Option<Self> {
+        (dt.is_finite() && dt > 0.0).then_some(Self {
+            dt,
+            accumulator: 0.0,
+        })
+    }
+
+    #[inline]
+    pub fn dt(&self) -> f32 {
+        self.dt
+    }
+
+    // Returns number of updates to run for the frame
+    pub fn advance(&mut self, frame_time: f32) -> usize {
+        self.accumulator += frame_time;
+        let steps = (self.accumulator / self.dt).floor() as usize;
+        self.accumulator -= steps as f32 * self.dt;
+        if steps > MAX_FIXED_STEPS {
+            self.accumulator = 0.0;
+        }
+        steps.min(MAX_FIXED_STEPS)
+    }
+
+    // Fraction of the next update already elapsed, used to blend
+    // previous and current state of the scene for rendering
+    #[inline]
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator / self.dt).clamp(0.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slow_frame_steps_are_capped() {
+        let mut timestep = FixedTimestep::new(1.0 / 64.0).unwrap();
+        assert_eq!(timestep.advance(1.0), MAX_FIXED_STEPS);
+        assert_eq!(timestep.alpha(), 0.0);
+        assert_eq!(timestep.advance(1.0 / 128.0), 0);
+        assert_eq!(timestep.alpha(), 0.5);
+    }
+
+    #[test]
+    fn test_invalid_dt_is_rejected() {
+        for dt in [0.0, -1.0 / 64.0, f32::NAN, f32::INFINITY] {
+            assert!(FixedTimestep::new(dt).is_none());
+        }
+    }
+}
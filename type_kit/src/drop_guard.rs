@@ -106,9 +106,24 @@ pub(crate) mod test_types {
 
 #[cfg(test)]
 mod tests {
+    use std::{cell::Cell, convert::Infallible};
+
     use super::*;
     use test_types::{FaillingCreate, FaillingDestroy, A, B, C, E};
 
+    // Counts calls to destroy of the resource
+    struct Counted<'a>(&'a Cell<usize>);
+
+    impl Destroy for Counted<'_> {
+        type Context<'a> = ();
+        type DestroyError = Infallible;
+
+        fn destroy<'a>(&mut self, _context: Self::Context<'a>) -> DestroyResult<Self> {
+            self.0.set(self.0.get() + 1);
+            Ok(())
+        }
+    }
+
     #[test]
     fn test_drop_guard_destroyed_before_drop() {
         let c = C {};
@@ -119,11 +134,100 @@ mod tests {
 
     #[test]
     #[should_panic]
-    #[cfg(debug_assertions)]
+    #[cfg(all(debug_assertions, feature = "strict-destroy"))]
     fn test_drop_guard_not_destroyed_panic_on_drop_in_debug() {
         let _ = DropGuard::new(A(42));
     }
 
+    #[test]
+    #[cfg(not(feature = "strict-destroy"))]
+    fn test_drop_guard_not_destroyed_no_panic_without_strict_destroy() {
+        let _ = DropGuard::new(A(42));
+    }
+
+    #[test]
+    fn test_drop_guard_armed_drop_does_not_destroy() {
+        let counter = Cell::new(0);
+        let guard = DropGuard::new(Counted(&counter));
+        assert!(!guard.is_destroyed());
+        // Leaked resource is reported, but never destroyed implicitly
+        #[cfg(not(feature = "strict-destroy"))]
+        drop(guard);
+        #[cfg(feature = "strict-destroy")]
+        std::mem::forget(guard);
+        assert_eq!(counter.get(), 0);
+    }
+
+    #[test]
+    fn test_drop_guard_disarmed_drop() {
+        let counter = Cell::new(0);
+        let mut guard = DropGuard::new(Counted(&counter));
+        guard.finalize().unwrap();
+        assert!(guard.is_destroyed());
+        drop(guard);
+        assert_eq!(counter.get(), 1);
+    }
+
+    #[test]
+    fn test_drop_guard_into_inner() {
+        let counter = Cell::new(0);
+        let guard = DropGuard::new(Counted(&counter));
+        let mut inner = guard.into_inner();
+        assert_eq!(counter.get(), 0);
+        inner.finalize().unwrap();
+        assert_eq!(counter.get(), 1);
+    }
+
+    struct Wrapped<'a>(Counted<'a>);
+
+    impl Destroy for Wrapped<'_> {
+        type Context<'a> = ();
+        type DestroyError = Infallible;
+
+        fn destroy<'a>(&mut self, context: Self::Context<'a>) -> DestroyResult<Self> {
+            self.0.destroy(context)
+        }
+    }
+
+    #[test]
+    fn test_drop_guard_map_destroys_once() {
+        let counter = Cell::new(0);
+        {
+            let mut guard = DropGuard::new(Counted(&counter)).map(Wrapped);
+            assert_eq!(counter.get(), 0);
+            guard.finalize().unwrap();
+        }
+        assert_eq!(counter.get(), 1);
+    }
+
+    #[test]
+    fn test_drop_guard_try_map() {
+        let counter = Cell::new(0);
+        let mut guard = DropGuard::new(Counted(&counter))
+            .try_map(|inner| Ok::<_, E>(Wrapped(inner)))
+            .unwrap();
+        guard.finalize().unwrap();
+        assert_eq!(counter.get(), 1);
+
+        let result = DropGuard::new(Counted(&counter)).try_map(Err::<Wrapped, _>);
+        let mut inner = result.err().unwrap();
+        assert_eq!(counter.get(), 1);
+        inner.finalize().unwrap();
+        assert_eq!(counter.get(), 2);
+    }
+
+    #[test]
+    fn test_drop_guard_into_inner_dropped_without_destroy() {
+        let counter = Cell::new(0);
+        let mut guard = DropGuard::new(Counted(&counter));
+        assert!(std::ptr::eq(guard.as_ref().0, &counter));
+        assert!(std::ptr::eq(guard.as_mut().0, &counter));
+        {
+            let _inner = guard.into_inner();
+        }
+        assert_eq!(counter.get(), 0);
+    }
+
     #[test]
     #[cfg(not(debug_assertions))]
     fn test_drop_guard_not_destroyed_no_panic_on_drop_in_release() {
@@ -203,8 +307,11 @@ mod tests {
     }
 }
 
+#[cfg(debug_assertions)]
+use std::any::type_name;
+#[cfg(not(debug_assertions))]
+use std::mem::ManuallyDrop;
 use std::{
-    any::type_name,
     error::Error,
     fmt::{Debug, Display, Formatter},
     ops::{Deref, DerefMut},
@@ -355,6 +462,8 @@ pub struct DropGuard<T: Destroy> {
     inner: Option<T>,
     #[cfg(not(debug_assertions))]
     inner: T,
+    #[cfg(not(debug_assertions))]
+    destroyed: bool,
 }
 
 impl<T: Destroy> DropGuard<T> {
@@ -362,7 +471,57 @@ impl<T: Destroy> DropGuard<T> {
     pub fn new(inner: T) -> Self {
         #[cfg(debug_assertions)]
         let inner = Some(inner);
-        Self { inner }
+        Self {
+            inner,
+            #[cfg(not(debug_assertions))]
+            destroyed: false,
+        }
+    }
+
+    #[inline]
+    pub fn is_destroyed(&self) -> bool {
+        #[cfg(debug_assertions)]
+        let destroyed = self.inner.is_none();
+        #[cfg(not(debug_assertions))]
+        let destroyed = self.destroyed;
+        destroyed
+    }
+
+    // Disarms the guard handing ownership of the inner resource to the caller,
+    // which becomes responsible for destroying it
+    #[inline]
+    pub fn into_inner(self) -> T {
+        #[cfg(debug_assertions)]
+        {
+            let mut guard = self;
+            guard
+                .inner
+                .take()
+                .expect("DropGuard inner resource was already destroyed")
+        }
+        #[cfg(not(debug_assertions))]
+        {
+            let guard = ManuallyDrop::new(self);
+            // Safety: guard is not dropped, so the inner resource is moved out only once
+            unsafe { std::ptr::read(&guard.inner) }
+        }
+    }
+
+    // Passes the inner resource through `f`, guard of the returned resource
+    // takes over the responsibility for its destruction
+    #[inline]
+    pub fn map<U: Destroy, F: FnOnce(T) -> U>(self, f: F) -> DropGuard<U> {
+        DropGuard::new(f(self.into_inner()))
+    }
+
+    // Fallible counterpart of `map`, on error `f` is responsible for the resource,
+    // either destroying it or handing it back through the error value
+    #[inline]
+    pub fn try_map<U: Destroy, E, F: FnOnce(T) -> Result<U, E>>(
+        self,
+        f: F,
+    ) -> Result<DropGuard<U>, E> {
+        f(self.into_inner()).map(DropGuard::new)
     }
 }
 
@@ -456,6 +615,7 @@ impl<T: Destroy> Destroy for DropGuard<T> {
             self.inner
                 .destroy(context)
                 .map_err(|err| DropGuardError::DestroyError(err))?;
+            self.destroyed = true;
             Ok(())
         }
     }
@@ -525,10 +685,12 @@ impl<T: Destroy> AsMut<T> for DropGuard<T> {
     }
 }
 
+// Leaked resources are reported only in debug builds,
+// `strict-destroy` feature turns the report into panic
 impl<T: Destroy> Drop for DropGuard<T> {
     #[inline]
     fn drop(&mut self) {
-        #[cfg(debug_assertions)]
+        #[cfg(all(debug_assertions, feature = "strict-destroy"))]
         if self.inner.is_some() {
             panic!(
                 "DropGuard<{}> inner resource was not destroyed before drop! \
@@ -536,5 +698,13 @@ impl<T: Destroy> Drop for DropGuard<T> {
                 &type_name::<T>(),
             )
         }
+        #[cfg(all(debug_assertions, not(feature = "strict-destroy")))]
+        if self.inner.is_some() {
+            eprintln!(
+                "DropGuard<{}> inner resource was not destroyed before drop! \
+                 Ensure DropGuard::destroy is called before it's dropped",
+                &type_name::<T>(),
+            )
+        }
     }
 }
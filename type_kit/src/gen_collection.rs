@@ -161,6 +161,252 @@ mod tests {
         assert_eq!(items, vec!["Item 1", "Item 2"]);
     }
 
+    #[test]
+    fn test_iter_with_indices() {
+        let mut collection = GenCollection::default();
+        let index1 = collection.push("Item 1").unwrap();
+        let index2 = collection.push("Item 2").unwrap();
+        let index3 = collection.push("Item 3").unwrap();
+        collection.pop(index1).unwrap();
+
+        let mut items: Vec<_> = collection.iter_with_indices().collect();
+        items.sort_by_key(|(_, item)| **item);
+        assert_eq!(items, vec![(index2, &"Item 2"), (index3, &"Item 3")]);
+    }
+
+    #[test]
+    fn test_contains() {
+        let mut collection = GenCollection::default();
+        let index = collection.push("Item 1").unwrap();
+        assert!(collection.contains(index));
+
+        collection.pop(index).unwrap();
+        assert!(!collection.contains(index));
+
+        let reused = collection.push("Item 2").unwrap();
+        assert!(collection.contains(reused));
+        assert!(!collection.contains(index));
+        assert!(!collection.contains(GenIndex::wrap(0, 10)));
+    }
+
+    #[test]
+    fn test_get_or_insert_with_existing() {
+        let mut collection = GenCollection::default();
+        let index = collection.push("Item 1").unwrap();
+
+        let (returned, item) = collection
+            .get_or_insert_with(Some(index), || "Item 2")
+            .unwrap();
+        assert_eq!(returned, index);
+        assert_eq!(item, &"Item 1");
+        assert_eq!(collection.len(), 1);
+    }
+
+    #[test]
+    fn test_get_or_insert_with_vacant() {
+        let mut collection = GenCollection::default();
+        let (index, item) = collection.get_or_insert_with(None, || "Item 1").unwrap();
+        assert_eq!(item, &"Item 1");
+        assert_eq!(collection.get(index).unwrap(), &"Item 1");
+
+        let (index, _) = collection
+            .get_or_insert_with(Some(GenIndex::wrap(0, 10)), || "Item 2")
+            .unwrap();
+        assert_eq!(collection.get(index).unwrap(), &"Item 2");
+    }
+
+    #[test]
+    fn test_get_or_insert_with_stale_generation() {
+        let mut collection = GenCollection::default();
+        let stale = collection.push("Item 1").unwrap();
+        collection.pop(stale).unwrap();
+        let current = collection.push("Item 2").unwrap();
+
+        let (index, item) = collection
+            .get_or_insert_with(Some(stale), || "Item 3")
+            .unwrap();
+        assert_ne!(index, stale);
+        assert_ne!(index, current);
+        assert_eq!(item, &"Item 3");
+        assert_eq!(collection.get(current).unwrap(), &"Item 2");
+        assert_eq!(collection.len(), 2);
+    }
+
+    #[test]
+    fn test_gen_index_raw_round_trip() {
+        let mut collection = GenCollection::default();
+        collection.push("Item 1").unwrap();
+        let index = collection.push("Item 2").unwrap();
+
+        let raw = index.into_raw();
+        assert_eq!(raw, GenIndexRaw::new(raw.index(), raw.generation()));
+        assert_eq!(GenIndex::<&str>::from_raw(raw), index);
+        assert_eq!(collection.validate(raw), Some(index));
+        assert_eq!(collection.get(index).unwrap(), &"Item 2");
+    }
+
+    #[test]
+    fn test_validate_rejects_stale_raw_index() {
+        let mut collection = GenCollection::default();
+        let stale = collection.push("Item 1").unwrap().into_raw();
+        collection.pop(GenIndex::from_raw(stale)).unwrap();
+        assert_eq!(collection.validate(stale), None);
+
+        let current = collection.push("Item 2").unwrap();
+        assert_eq!(current.into_raw().index(), stale.index());
+        assert_eq!(collection.validate(stale), None);
+        assert_eq!(collection.validate(current.into_raw()), Some(current));
+        assert_eq!(collection.validate(GenIndexRaw::new(16, 0)), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_gen_index_raw_serde_round_trip() {
+        let mut collection = GenCollection::default();
+        let stale = collection.push("Item 1").unwrap();
+        collection.pop(stale).unwrap();
+        let index = collection.push("Item 2").unwrap();
+
+        let serialized = serde_json::to_string(&[stale.into_raw(), index.into_raw()]).unwrap();
+        let [stale, current]: [GenIndexRaw; 2] = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(collection.validate(stale), None);
+        assert_eq!(collection.validate(current), Some(index));
+    }
+
+    #[test]
+    fn test_with_capacity_and_reserve() {
+        let mut collection = GenCollection::with_capacity(16);
+        assert!(collection.capacity() >= 16);
+        collection.push("Item 1").unwrap();
+        collection.reserve(64);
+        assert!(collection.capacity() >= 65);
+        assert_eq!(collection.len(), 1);
+    }
+
+    #[test]
+    fn test_occupied_len_excludes_borrowed() {
+        let mut collection = GenCollection::default();
+        collection.push("Item 1").unwrap();
+        let index = collection.push("Item 2").unwrap();
+
+        let borrowed = collection.borrow(index).unwrap();
+        assert_eq!(collection.len(), 2);
+        assert_eq!(collection.occupied_len(), 1);
+        collection.put_back(borrowed).unwrap();
+        assert_eq!(collection.occupied_len(), 2);
+    }
+
+    #[test]
+    fn test_shrink_to_fit_drops_trailing_cells() {
+        let mut collection = GenCollection::default();
+        let indices = (0..100)
+            .map(|i| collection.push(i).unwrap())
+            .collect::<Vec<_>>();
+        collection.filter_drain(|&item| item >= 10);
+        collection.shrink_to_fit();
+
+        assert_eq!(collection.len(), 10);
+        assert_eq!(collection.indices.len(), 10);
+        assert!(collection.capacity() < 100);
+        for (i, &index) in indices.iter().enumerate() {
+            assert_eq!(collection.contains(index), i < 10);
+        }
+    }
+
+    #[test]
+    fn test_shrink_to_fit_preserves_scattered_items() {
+        let mut collection = GenCollection::default();
+        let indices = (0..256)
+            .map(|i| collection.push(i).unwrap())
+            .collect::<Vec<_>>();
+        collection.filter_drain(|&item| item % 64 != 0);
+        let capacity = collection.capacity();
+        collection.shrink_to_fit();
+
+        assert!(collection.capacity() < capacity);
+        assert_eq!(collection.indices.len(), 193);
+        for (i, &index) in indices.iter().enumerate() {
+            match i % 64 {
+                0 => assert_eq!(collection.get(index).unwrap(), &i),
+                _ => assert!(collection.get(index).is_err()),
+            }
+        }
+    }
+
+    #[test]
+    fn test_shrink_to_fit_keeps_dropped_indices_invalid() {
+        let mut collection = GenCollection::default();
+        let first = collection.push("Item 1").unwrap();
+        let stale = collection.push("Item 2").unwrap();
+        collection.pop(stale).unwrap();
+        collection.shrink_to_fit();
+
+        let index = collection.push("Item 3").unwrap();
+        assert!(collection.get(stale).is_err());
+        assert_eq!(collection.get(index).unwrap(), &"Item 3");
+        assert_eq!(collection.get(first).unwrap(), &"Item 1");
+    }
+
+    #[test]
+    fn test_shrink_to_fit_compacts_free_list() {
+        let mut collection = GenCollection::default();
+        let indices = (0..8)
+            .map(|i| collection.push(i).unwrap())
+            .collect::<Vec<_>>();
+        collection.pop(indices[5]).unwrap();
+        collection.pop(indices[1]).unwrap();
+        collection.pop(indices[3]).unwrap();
+        collection.shrink_to_fit();
+
+        // Free cells are reused in ascending order after compaction
+        assert_eq!(collection.push(10).unwrap().index, 1);
+        assert_eq!(collection.push(11).unwrap().index, 3);
+        assert_eq!(collection.push(12).unwrap().index, 5);
+        assert_eq!(collection.push(13).unwrap().index, 8);
+    }
+
+    #[test]
+    fn test_exhausted_generation_retires_cell() {
+        let mut collection = GenCollection::default();
+        let index = collection.push(0).unwrap();
+        collection.indices[index.index].set_generation(usize::MAX - 1);
+        let index = GenIndex::wrap(usize::MAX - 1, index.index);
+        collection.pop(index).unwrap();
+
+        // Last generation available for the cell is still handed out
+        let last = collection.push(1).unwrap();
+        assert_eq!((last.index, last.generation), (0, usize::MAX));
+        collection.pop(last).unwrap();
+
+        // Exhausted cell is not reused, stale indices stay invalid
+        let next = collection.push(2).unwrap();
+        assert_eq!(next.index, 1);
+        assert!(!collection.contains(last));
+        assert!(!collection.contains(GenIndex::wrap(0, 0)));
+
+        collection.filter_drain(|_| true);
+        collection.shrink_to_fit();
+        assert_eq!(collection.indices.len(), 1);
+        assert_eq!(collection.push(3).unwrap().index, 1);
+        assert!(!collection.contains(last));
+    }
+
+    #[test]
+    fn test_exhausted_cell_insert_fails() {
+        let mut collection = GenCollection::default();
+        let index = collection.push(0).unwrap();
+        collection.indices[index.index].set_generation(usize::MAX);
+        let index = GenIndex::wrap(usize::MAX, index.index);
+        collection.pop(index).unwrap();
+        // Forced back into the free list, insert refuses to wrap the generation
+        collection.next_free = Some(index.index);
+        assert!(matches!(
+            collection.push(1),
+            Err(GenCollectionError::GenerationExhausted)
+        ));
+        assert_eq!(collection.len(), 0);
+    }
+
     #[test]
     fn test_drain() {
         let mut collection = GenCollection::default();
@@ -360,6 +606,7 @@ pub enum GenCollectionError {
     CellEmpty,
     CellOccupied,
     CellBorrowed,
+    GenerationExhausted,
     // TODO: Temporary until separate TypeGuardCollection type is implemented
     TypeGuardConversion(TypeGuardConversionError),
 }
@@ -389,6 +636,9 @@ impl Display for GenCollectionError {
             GenCollectionError::CellBorrowed => {
                 write!(f, "Cell is borrowed")
             }
+            GenCollectionError::GenerationExhausted => {
+                write!(f, "Cell generation exhausted")
+            }
             GenCollectionError::TypeGuardConversion(err) => write!(f, "{}", err),
         }
     }
@@ -419,13 +669,31 @@ mod cell {
 
     impl LockedCell {
         #[inline]
-        pub(super) fn new(item_index: usize) -> Self {
+        pub(super) fn new(item_index: usize, generation: usize) -> Self {
             Self {
                 cell: GenCell::Occupied(Occupied { item_index }),
-                generation: 0,
+                generation,
             }
         }
 
+        // Generation of the last item stored in the cell, also valid for empty cells
+        #[inline]
+        pub(super) fn last_generation(&self) -> usize {
+            self.generation
+        }
+
+        // Cell which reached the maximal generation can not be reused without
+        // aliasing indices of its previous items, it is retired once emptied
+        #[inline]
+        pub(super) fn is_exhausted(&self) -> bool {
+            self.generation == usize::MAX
+        }
+
+        #[cfg(test)]
+        pub(super) fn set_generation(&mut self, generation: usize) {
+            self.generation = generation;
+        }
+
         #[inline]
         pub(super) fn generation(&self) -> GenCollectionResult<usize> {
             match self.cell {
@@ -476,7 +744,10 @@ mod cell {
         ) -> GenCollectionResult<(usize, Option<usize>)> {
             match self.cell {
                 GenCell::Empty(Empty { next_free }) => {
-                    self.generation += 1;
+                    self.generation = self
+                        .generation
+                        .checked_add(1)
+                        .ok_or(GenCollectionError::GenerationExhausted)?;
                     self.cell = GenCell::Occupied(Occupied { item_index });
                     Ok((self.generation, next_free))
                 }
@@ -507,6 +778,26 @@ mod cell {
                 _ => false,
             }
         }
+
+        #[inline]
+        pub(super) fn is_empty(&self) -> bool {
+            matches!(self.cell, GenCell::Empty(..))
+        }
+
+        #[inline]
+        pub(super) fn set_next_free(
+            &mut self,
+            next_free: Option<usize>,
+        ) -> GenCollectionResult<()> {
+            match &mut self.cell {
+                GenCell::Empty(cell) => {
+                    cell.next_free = next_free;
+                    Ok(())
+                }
+                GenCell::Occupied(..) => Err(GenCollectionError::CellOccupied),
+                GenCell::Borrowed(..) => Err(GenCollectionError::CellBorrowed),
+            }
+        }
     }
 
     #[allow(private_interfaces)]
@@ -620,29 +911,45 @@ impl<T> Debug for GenIndex<T> {
     }
 }
 
+// Untyped GenIndex, can be stored and serialized independently of the collection
+// item type, `GenCollection::validate` restores typed index for live items
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GenIndexRaw {
     index: usize,
     generation: usize,
 }
 
+impl GenIndexRaw {
+    #[inline]
+    pub fn new(index: usize, generation: usize) -> Self {
+        Self { index, generation }
+    }
+
+    #[inline]
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    #[inline]
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+}
+
 impl<T: 'static> FromGuard for GenIndex<T> {
     type Inner = GenIndexRaw;
 
     #[inline]
     fn into_inner(self) -> GenIndexRaw {
-        GenIndexRaw {
-            index: self.index,
-            generation: self.generation,
-        }
+        self.into_raw()
     }
 }
 
 impl<T> From<Valid<GenIndex<T>>> for GenIndex<T> {
     #[inline]
     fn from(value: Valid<GenIndex<T>>) -> Self {
-        let GenIndexRaw { index, generation } = value.into_inner();
-        GenIndex::wrap(generation, index)
+        GenIndex::from_raw(value.into_inner())
     }
 }
 
@@ -656,6 +963,19 @@ impl<T> GenIndex<T> {
         }
     }
 
+    #[inline]
+    pub fn into_raw(self) -> GenIndexRaw {
+        GenIndexRaw {
+            index: self.index,
+            generation: self.generation,
+        }
+    }
+
+    #[inline]
+    pub fn from_raw(raw: GenIndexRaw) -> Self {
+        Self::wrap(raw.generation, raw.index)
+    }
+
     #[inline]
     pub fn mark<C, M: Marker>(self) -> Marked<Self, M>
     where
@@ -671,6 +991,9 @@ pub struct GenCollection<T> {
     indices: Vec<LockedCell>,
     mapping: Vec<usize>,
     next_free: Option<usize>,
+    // Generation assigned to newly created cells, kept above generations of cells
+    // dropped by `shrink_to_fit` so that their outstanding indices stay invalid
+    first_generation: usize,
 }
 
 impl<T> Default for GenCollection<T> {
@@ -681,6 +1004,7 @@ impl<T> Default for GenCollection<T> {
             indices: Vec::new(),
             mapping: Vec::new(),
             next_free: None,
+            first_generation: 0,
         }
     }
 }
@@ -707,16 +1031,72 @@ impl<T> GenCollection<T> {
         Self::default()
     }
 
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            items: Vec::with_capacity(capacity),
+            indices: Vec::with_capacity(capacity),
+            mapping: Vec::with_capacity(capacity),
+            next_free: None,
+            first_generation: 0,
+        }
+    }
+
+    // Number of stored items, including the ones currently borrowed
     #[inline]
     pub fn len(&self) -> usize {
         self.items.len()
     }
 
+    // Number of stored items which are not currently borrowed
+    #[inline]
+    pub fn occupied_len(&self) -> usize {
+        self.mapping
+            .iter()
+            .filter(|&&cell_index| self.indices[cell_index].is_occupied())
+            .count()
+    }
+
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.items.capacity()
+    }
+
+    #[inline]
+    pub fn reserve(&mut self, additional: usize) {
+        self.items.reserve(additional);
+        self.mapping.reserve(additional);
+        self.indices.reserve(additional);
+    }
+
+    // Drops trailing empty cells, relinks remaining free cells in ascending order
+    // and releases excess memory. Indices of the dropped cells stay invalid,
+    // cells created afterwards start from generation above any dropped one
+    pub fn shrink_to_fit(&mut self) {
+        while let Some(cell) = self.indices.last() {
+            // Retired cells are kept, generation of the new cell in their place
+            // could not be raised above the generation of the retired one
+            if !cell.is_empty() || cell.is_exhausted() {
+                break;
+            }
+            self.first_generation = self.first_generation.max(cell.last_generation() + 1);
+            self.indices.pop();
+        }
+        let mut next_free = None;
+        for (cell_index, cell) in self.indices.iter_mut().enumerate().rev() {
+            if !cell.is_exhausted() && cell.set_next_free(next_free).is_ok() {
+                next_free = Some(cell_index);
+            }
+        }
+        self.next_free = next_free;
+        self.items.shrink_to_fit();
+        self.mapping.shrink_to_fit();
+        self.indices.shrink_to_fit();
+    }
+
     #[inline]
     pub fn push(&mut self, item: T) -> GenCollectionResult<GenIndex<T>> {
         let item_index = self.items.len();
-        self.items.push(MaybeUninit::new(item));
-
         let (generation, cell_index) = if let Some(index) = self.next_free {
             let cell = &mut self.indices[index];
             let (generation, next_free) = cell.insert(item_index)?;
@@ -724,10 +1104,12 @@ impl<T> GenCollection<T> {
             (generation, index)
         } else {
             let index = self.indices.len();
-            self.indices.push(LockedCell::new(item_index));
-            (0, index)
+            let generation = self.first_generation;
+            self.indices.push(LockedCell::new(item_index, generation));
+            (generation, index)
         };
 
+        self.items.push(MaybeUninit::new(item));
         self.mapping.push(cell_index);
         Ok(GenIndex::wrap(generation, cell_index))
     }
@@ -736,10 +1118,22 @@ impl<T> GenCollection<T> {
     pub fn pop(&mut self, index: GenIndex<T>) -> GenCollectionResult<T> {
         let next_free = self.next_free;
         let item_index = self.get_cell_mut_unlocked(index)?.pop(next_free)?;
-        self.next_free.replace(index.index);
+        self.release_cell(index.index);
         unsafe { Ok(self.swap_remove(item_index)) }
     }
 
+    // Links emptied cell into the free list, unless its generation is exhausted,
+    // retired cells are never reused so their indices can not alias new items
+    #[inline]
+    fn release_cell(&mut self, cell_index: usize) {
+        let cell = &mut self.indices[cell_index];
+        if cell.is_exhausted() {
+            let _ = cell.set_next_free(None);
+        } else {
+            self.next_free = Some(cell_index);
+        }
+    }
+
     #[inline]
     pub fn get(&self, index: GenIndex<T>) -> GenCollectionResult<&T> {
         let item_index = self.get_cell_unlocked(index)?.item_index()?;
@@ -752,6 +1146,46 @@ impl<T> GenCollection<T> {
         Ok(unsafe { self.items[item_index].assume_init_mut() })
     }
 
+    #[inline]
+    pub fn contains(&self, index: GenIndex<T>) -> bool {
+        self.indices.get(index.index).is_some_and(|cell| {
+            cell.is_occupied() && cell.generation().is_ok_and(|gen| gen == index.generation)
+        })
+    }
+
+    // Re-types raw index, returns None when it does not refer to a live item
+    #[inline]
+    pub fn validate(&self, raw: GenIndexRaw) -> Option<GenIndex<T>> {
+        let index = GenIndex::from_raw(raw);
+        self.get_cell_unlocked(index).is_ok().then_some(index)
+    }
+
+    // Returns item stored under `index`, pushes item created with `f` when the index
+    // is missing, out of bounds or refers to a cell reused by a newer generation
+    #[inline]
+    pub fn get_or_insert_with<F>(
+        &mut self,
+        index: Option<GenIndex<T>>,
+        f: F,
+    ) -> GenCollectionResult<(GenIndex<T>, &mut T)>
+    where
+        F: FnOnce() -> T,
+    {
+        let index = match index {
+            Some(index) if self.get_cell_unlocked(index).is_ok() => index,
+            _ => self.push(f())?,
+        };
+        Ok((index, self.get_mut(index)?))
+    }
+
+    #[inline]
+    pub fn iter_with_indices(&self) -> GenCollectionIndexedIter<'_, T> {
+        GenCollectionIndexedIter {
+            collection: self,
+            next: 0,
+        }
+    }
+
     #[inline]
     pub fn drain(&mut self) -> Vec<T> {
         self.filter_drain(|_| true)
@@ -768,8 +1202,8 @@ impl<T> GenCollection<T> {
             let cell_index = self.mapping[i];
             let cell = &mut self.indices[cell_index];
             if cell.is_occupied() && predicate(unsafe { self.items[i].assume_init_ref() }) {
-                let next_free = self.next_free.replace(cell_index);
-                let _ = cell.unlock_unchecked().pop(next_free);
+                let _ = cell.unlock_unchecked().pop(self.next_free);
+                self.release_cell(cell_index);
                 removed.push(unsafe { self.swap_remove(i) });
             } else {
                 i += 1;
@@ -842,14 +1276,14 @@ impl<T> DerefMut for Borrowed<T> {
 
 impl<T> GenCollection<T> {
     #[inline]
-    fn borrow(&mut self, index: GenIndex<T>) -> GenCollectionResult<Borrowed<T>> {
+    pub(crate) fn borrow(&mut self, index: GenIndex<T>) -> GenCollectionResult<Borrowed<T>> {
         let item_index = self.get_cell_mut_unlocked(index.clone())?.borrow()?;
         let item = unsafe { self.items[item_index].assume_init_read() };
         Ok(Borrowed { item, index })
     }
 
     #[inline]
-    fn put_back(&mut self, borrow: Borrowed<T>) -> GenCollectionResult<()> {
+    pub(crate) fn put_back(&mut self, borrow: Borrowed<T>) -> GenCollectionResult<()> {
         let Borrowed { item, index } = borrow;
         let item_index = self.get_cell_mut_unlocked(index)?.put_back()?;
         self.items[item_index] = MaybeUninit::new(item);
@@ -912,6 +1346,37 @@ impl<'a, T> IntoIterator for &'a GenCollection<T> {
     }
 }
 
+#[derive(Debug, Clone, Copy)]
+pub struct GenCollectionIndexedIter<'a, T> {
+    collection: &'a GenCollection<T>,
+    next: usize,
+}
+
+impl<'a, T> Iterator for GenCollectionIndexedIter<'a, T> {
+    type Item = (GenIndex<T>, &'a T);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let indices = &self.collection.indices;
+        let mapping = &self.collection.mapping;
+        let items = &self.collection.items;
+
+        while self.next < items.len() {
+            let item_index = self.next;
+            self.next += 1;
+            let cell_index = mapping[item_index];
+            let cell = &indices[cell_index];
+            if cell.is_occupied() {
+                let generation = cell.generation().unwrap();
+                return Some((GenIndex::wrap(generation, cell_index), unsafe {
+                    items[item_index].assume_init_ref()
+                }));
+            }
+        }
+        None
+    }
+}
+
 #[derive(Debug)]
 pub struct GenCollectionMutIter<'a, T> {
     collection: &'a mut GenCollection<T>,
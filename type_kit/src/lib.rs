@@ -1,9 +1,11 @@
 mod drop_guard;
 mod gen_collection;
+mod sync_gen_collection;
 mod type_guard;
 mod type_list;
 
 pub use drop_guard::*;
 pub use gen_collection::*;
+pub use sync_gen_collection::*;
 pub use type_guard::*;
 pub use type_list::*;
@@ -0,0 +1,250 @@
+use std::{
+    ops::{Deref, DerefMut},
+    sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
+
+use crate::{Borrowed, GenCollection, GenCollectionResult, GenIndex};
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, thread};
+
+    use super::*;
+
+    #[test]
+    fn test_push_get_pop() {
+        let collection = SyncGenCollection::default();
+        let index1 = collection.push("Item 1").unwrap();
+        let index2 = collection.push("Item 2").unwrap();
+
+        assert_eq!(*collection.get(index1).unwrap(), "Item 1");
+        *collection.get_mut(index2).unwrap() = "Updated Item 2";
+        assert_eq!(collection.pop(index2).unwrap(), "Updated Item 2");
+        assert!(collection.get(index2).is_err());
+        assert_eq!(collection.len(), 1);
+    }
+
+    #[test]
+    fn test_borrow_puts_back_on_drop() {
+        let collection = SyncGenCollection::default();
+        let index = collection.push(42).unwrap();
+
+        {
+            let mut borrowed = collection.borrow(index).unwrap();
+            assert!(collection.borrow(index).is_err());
+            assert!(collection.get(index).is_err());
+            *borrowed = 31;
+        }
+        assert_eq!(*collection.get(index).unwrap(), 31);
+    }
+
+    #[test]
+    fn test_concurrent_push_pop_never_reuses_generation() {
+        const NUM_THREADS: usize = 8;
+        const NUM_ITEMS: usize = 1000;
+        let collection = SyncGenCollection::default();
+        let pushed = thread::scope(|scope| {
+            let workers = (0..NUM_THREADS)
+                .map(|thread| {
+                    let collection = &collection;
+                    scope.spawn(move || {
+                        let mut pushed = Vec::with_capacity(NUM_ITEMS);
+                        for i in 0..NUM_ITEMS {
+                            let item = thread * NUM_ITEMS + i;
+                            let index = collection.push(item).unwrap();
+                            assert_eq!(*collection.get(index).unwrap(), item);
+                            if i % 2 == 1 {
+                                let (index, item) = pushed[i - 1];
+                                assert_eq!(collection.pop(index).unwrap(), item);
+                            }
+                            pushed.push((index, item));
+                        }
+                        pushed
+                    })
+                })
+                .collect::<Vec<_>>();
+            workers
+                .into_iter()
+                .flat_map(|worker| worker.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+        let unique = pushed
+            .iter()
+            .map(|(index, _)| *index)
+            .collect::<HashSet<_>>();
+        assert_eq!(unique.len(), NUM_THREADS * NUM_ITEMS);
+        assert_eq!(collection.len(), NUM_THREADS * NUM_ITEMS / 2);
+        for (index, item) in pushed {
+            if let Ok(stored) = collection.get(index) {
+                assert_eq!(*stored, item);
+            }
+        }
+    }
+}
+
+// GenCollection shared between threads, cell state transitions are serialized
+// by single reader-writer lock. Borrowed items are moved out of the collection
+// so the lock is not held while borrow guard is alive
+#[derive(Debug)]
+pub struct SyncGenCollection<T> {
+    inner: RwLock<GenCollection<T>>,
+}
+
+impl<T> Default for SyncGenCollection<T> {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            inner: RwLock::new(GenCollection::default()),
+        }
+    }
+}
+
+impl<T> From<GenCollection<T>> for SyncGenCollection<T> {
+    #[inline]
+    fn from(collection: GenCollection<T>) -> Self {
+        Self {
+            inner: RwLock::new(collection),
+        }
+    }
+}
+
+impl<T> SyncGenCollection<T> {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> GenCollection<T> {
+        self.inner
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.read().len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    #[inline]
+    pub fn push(&self, item: T) -> GenCollectionResult<GenIndex<T>> {
+        self.write().push(item)
+    }
+
+    #[inline]
+    pub fn pop(&self, index: GenIndex<T>) -> GenCollectionResult<T> {
+        self.write().pop(index)
+    }
+
+    #[inline]
+    pub fn contains(&self, index: GenIndex<T>) -> bool {
+        self.read().contains(index)
+    }
+
+    #[inline]
+    pub fn get(&self, index: GenIndex<T>) -> GenCollectionResult<SyncGenRef<'_, T>> {
+        let guard = self.read();
+        guard.get(index)?;
+        Ok(SyncGenRef { guard, index })
+    }
+
+    #[inline]
+    pub fn get_mut(&self, index: GenIndex<T>) -> GenCollectionResult<SyncGenRefMut<'_, T>> {
+        let mut guard = self.write();
+        guard.get_mut(index)?;
+        Ok(SyncGenRefMut { guard, index })
+    }
+
+    #[inline]
+    pub fn borrow(&self, index: GenIndex<T>) -> GenCollectionResult<SyncBorrowed<'_, T>> {
+        let borrowed = self.write().borrow(index)?;
+        Ok(SyncBorrowed {
+            collection: self,
+            borrowed: Some(borrowed),
+        })
+    }
+
+    // Lock poisoning is ignored, panics while the lock is held leave cells consistent
+    #[inline]
+    fn read(&self) -> RwLockReadGuard<'_, GenCollection<T>> {
+        self.inner.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    #[inline]
+    fn write(&self) -> RwLockWriteGuard<'_, GenCollection<T>> {
+        self.inner.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+// Shared access to the item, holds collection read lock
+pub struct SyncGenRef<'a, T> {
+    guard: RwLockReadGuard<'a, GenCollection<T>>,
+    index: GenIndex<T>,
+}
+
+impl<'a, T> Deref for SyncGenRef<'a, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.guard.get(self.index).unwrap()
+    }
+}
+
+// Exclusive access to the item, holds collection write lock
+pub struct SyncGenRefMut<'a, T> {
+    guard: RwLockWriteGuard<'a, GenCollection<T>>,
+    index: GenIndex<T>,
+}
+
+impl<'a, T> Deref for SyncGenRefMut<'a, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.guard.get(self.index).unwrap()
+    }
+}
+
+impl<'a, T> DerefMut for SyncGenRefMut<'a, T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.guard.get_mut(self.index).unwrap()
+    }
+}
+
+// Item moved out of the collection, put back when the guard is dropped
+pub struct SyncBorrowed<'a, T> {
+    collection: &'a SyncGenCollection<T>,
+    borrowed: Option<Borrowed<T>>,
+}
+
+impl<'a, T> Deref for SyncBorrowed<'a, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.borrowed.as_ref().unwrap()
+    }
+}
+
+impl<'a, T> DerefMut for SyncBorrowed<'a, T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.borrowed.as_mut().unwrap()
+    }
+}
+
+impl<'a, T> Drop for SyncBorrowed<'a, T> {
+    #[inline]
+    fn drop(&mut self) {
+        if let Some(borrowed) = self.borrowed.take() {
+            let _ = self.collection.write().put_back(borrowed);
+        }
+    }
+}
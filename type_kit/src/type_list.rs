@@ -1,5 +1,5 @@
 use std::{
-    any::type_name,
+    any::{type_name, Any},
     convert::Infallible,
     error::Error,
     fmt::{Debug, Display, Formatter},
@@ -24,6 +24,20 @@ mod tests {
         assert_eq!(*str_item, "Item");
     }
 
+    #[test]
+    fn test_maybe_contains() {
+        let mut list = Nil::new().append(2.5).append(42).append("Item");
+        assert_eq!(MaybeContains::<i32>::try_get(&list), Some(&42));
+        assert_eq!(MaybeContains::<&str>::try_get(&list), Some(&"Item"));
+        assert_eq!(MaybeContains::<u8>::try_get(&list), None);
+        assert_eq!(MaybeContains::<i32>::try_get(&Nil::new()), None);
+        if let Some(item) = MaybeContains::<f64>::try_get_mut(&mut list) {
+            *item = 1.5;
+        }
+        assert_eq!(*list.get::<f64, _>(), 1.5);
+        assert!(MaybeContains::<String>::try_get_mut(&mut list).is_none());
+    }
+
     #[test]
     fn test_type_list_len() {
         let list = Nil::new().append(3.14).append(42).append("Item");
@@ -36,6 +50,27 @@ mod tests {
         assert_eq!(list.len(), 3);
     }
 
+    #[test]
+    fn test_take_preserves_order() {
+        let list = Nil::new().append(2.5).append(42).append("Item");
+        let (item, rest) = ContainsOwned::<i32, _>::take(list);
+        assert_eq!(item, 42);
+        assert_eq!(rest, Nil::new().append(2.5).append("Item"));
+        let (item, rest) = ContainsOwned::<f64, _>::take(rest);
+        assert_eq!(item, 2.5);
+        assert_eq!(rest, Nil::new().append("Item"));
+    }
+
+    #[test]
+    fn test_replace() {
+        let mut list = Nil::new().append(vec![1]).append(42).append("Item");
+        let previous = list.replace(vec![1, 2, 3]);
+        assert_eq!(previous, vec![1]);
+        assert_eq!(*list.get::<Vec<i32>, _>(), vec![1, 2, 3]);
+        assert_eq!(list.replace("Other"), "Item");
+        assert_eq!(list.len(), 3);
+    }
+
     #[test]
     fn test_nil_types_are_empty() {
         let nil = Nil::new();
@@ -245,6 +280,134 @@ impl<O, S, T: Marker, N: Contains<S, T>> Contains<S, There<T>> for Cons<O, N> {
     }
 }
 
+/// Allows moving the element out of the list, `Remainder` is the list
+/// with that element removed and order of the other elements preserved
+///
+/// ```
+/// use type_kit::{Contains, ContainsOwned, Nil, TypeList};
+///
+/// let list = Nil::new().append(2.5).append(42).append("Item");
+/// let (item, rest) = ContainsOwned::<i32, _>::take(list);
+/// assert_eq!(item, 42);
+/// assert_eq!(*rest.get::<&str, _>(), "Item");
+/// ```
+///
+/// Only types held by the list can be taken out of it
+///
+/// ```compile_fail,E0277
+/// use type_kit::{ContainsOwned, Nil, TypeList};
+///
+/// let list = Nil::new().append(2.5f64).append(42i32);
+/// let (item, rest) = ContainsOwned::<u8, _>::take(list);
+/// ```
+///
+/// The taken element is no longer part of the remainder
+///
+/// ```compile_fail,E0277
+/// use type_kit::{Contains, ContainsOwned, Nil, TypeList};
+///
+/// let list = Nil::new().append(2.5).append(42).append("Item");
+/// let (item, rest) = ContainsOwned::<i32, _>::take(list);
+/// let item = rest.get::<i32, _>();
+/// ```
+///
+/// Lists of non `Copy` elements are consumed by `take`
+///
+/// ```compile_fail,E0382
+/// use type_kit::{Contains, ContainsOwned, Nil, TypeList};
+///
+/// let list = Nil::new().append(vec![1]).append(String::from("Item"));
+/// let (item, rest) = ContainsOwned::<String, _>::take(list);
+/// let item = list.get::<Vec<i32>, _>();
+/// ```
+pub trait ContainsOwned<T, M: Marker>: Contains<T, M> {
+    type Remainder;
+
+    fn take(self) -> (T, Self::Remainder);
+
+    #[inline]
+    fn replace(&mut self, value: T) -> T {
+        std::mem::replace(self.get_mut(), value)
+    }
+}
+
+impl<H> ContainsOwned<H, Here> for Fin<H> {
+    type Remainder = Nil;
+
+    #[inline]
+    fn take(self) -> (H, Self::Remainder) {
+        (self.head, Nil::new())
+    }
+}
+
+impl<S, N> ContainsOwned<S, Here> for Cons<S, N> {
+    type Remainder = N;
+
+    #[inline]
+    fn take(self) -> (S, Self::Remainder) {
+        (self.head, self.tail)
+    }
+}
+
+impl<O, S, T: Marker, N: ContainsOwned<S, T>> ContainsOwned<S, There<T>> for Cons<O, N> {
+    type Remainder = Cons<O, N::Remainder>;
+
+    #[inline]
+    fn take(self) -> (S, Self::Remainder) {
+        let (item, tail) = self.tail.take();
+        (item, Cons::new(self.head, tail))
+    }
+}
+
+// Runtime counterpart of `Contains` for generic code over lists which may not
+// hold the type, elements are compared by `TypeId` and the first match is returned
+pub trait MaybeContains<T: 'static> {
+    fn try_get(&self) -> Option<&T>;
+    fn try_get_mut(&mut self) -> Option<&mut T>;
+}
+
+impl<T: 'static, U> MaybeContains<T> for TypedNil<U> {
+    #[inline]
+    fn try_get(&self) -> Option<&T> {
+        None
+    }
+
+    #[inline]
+    fn try_get_mut(&mut self) -> Option<&mut T> {
+        None
+    }
+}
+
+impl<T: 'static, H: 'static> MaybeContains<T> for Fin<H> {
+    #[inline]
+    fn try_get(&self) -> Option<&T> {
+        (&self.head as &dyn Any).downcast_ref()
+    }
+
+    #[inline]
+    fn try_get_mut(&mut self) -> Option<&mut T> {
+        (&mut self.head as &mut dyn Any).downcast_mut()
+    }
+}
+
+impl<T: 'static, H: 'static, N: MaybeContains<T>> MaybeContains<T> for Cons<H, N> {
+    #[inline]
+    fn try_get(&self) -> Option<&T> {
+        match (&self.head as &dyn Any).downcast_ref() {
+            Some(item) => Some(item),
+            None => self.tail.try_get(),
+        }
+    }
+
+    #[inline]
+    fn try_get_mut(&mut self) -> Option<&mut T> {
+        match (&mut self.head as &mut dyn Any).downcast_mut() {
+            Some(item) => Some(item),
+            None => self.tail.try_get_mut(),
+        }
+    }
+}
+
 impl<H, T> Cons<H, T> {
     #[inline]
     pub fn new(head: H, tail: T) -> Self {
@@ -332,9 +495,123 @@ impl<T, N: TypeList> TypeList for Cons<T, N> {
     type Next = N;
 }
 
+// Type of the list element at position N counted from the list head, implemented
+// for N up to 16, indexing past the end resolves to the terminating TypedNil item type
+pub trait Nth<const N: usize>: TypeList {
+    type Output;
+}
+
+pub type At<L, const N: usize> = <L as Nth<N>>::Output;
+
+impl<L: TypeList> Nth<0> for L {
+    type Output = L::Item;
+}
+
+macro_rules! impl_nth {
+    ($($n:literal => $prev:literal),* $(,)?) => {
+        $(
+            impl<L: TypeList> Nth<$n> for L
+            where
+                L::Next: Nth<$prev>,
+            {
+                type Output = <L::Next as Nth<$prev>>::Output;
+            }
+        )*
+    };
+}
+
+impl_nth!(
+    1 => 0, 2 => 1, 3 => 2, 4 => 3, 5 => 4, 6 => 5, 7 => 6, 8 => 7,
+    9 => 8, 10 => 9, 11 => 10, 12 => 11, 13 => 12, 14 => 13, 15 => 14, 16 => 15,
+);
+
+// Called for each element of the list, in order from the list head
+pub trait Visitor {
+    fn visit<T>(&mut self, item: &T);
+}
+
+// Fallible mutable counterpart of `Visitor`, first error stops the iteration
+pub trait TryVisitorMut {
+    type Error;
+
+    fn try_visit_mut<T>(&mut self, item: &mut T) -> Result<(), Self::Error>;
+}
+
+// Combines each element of the list into the accumulator, in order from the list head
+pub trait Folder {
+    type Acc;
+
+    fn fold<T>(&mut self, acc: Self::Acc, item: &T) -> Self::Acc;
+}
+
+pub trait VisitList {
+    fn visit<V: Visitor>(&self, visitor: &mut V);
+
+    fn try_visit_mut<V: TryVisitorMut>(&mut self, visitor: &mut V) -> Result<(), V::Error>;
+
+    fn fold<F: Folder>(&self, folder: &mut F, init: F::Acc) -> F::Acc;
+
+    #[inline]
+    fn for_each<V: Visitor>(&self, visitor: &mut V) {
+        self.visit(visitor);
+    }
+}
+
+impl<N> VisitList for TypedNil<N> {
+    #[inline]
+    fn visit<V: Visitor>(&self, _visitor: &mut V) {}
+
+    #[inline]
+    fn fold<F: Folder>(&self, _folder: &mut F, init: F::Acc) -> F::Acc {
+        init
+    }
+
+    #[inline]
+    fn try_visit_mut<V: TryVisitorMut>(&mut self, _visitor: &mut V) -> Result<(), V::Error> {
+        Ok(())
+    }
+}
+
+impl<T> VisitList for Fin<T> {
+    #[inline]
+    fn visit<V: Visitor>(&self, visitor: &mut V) {
+        visitor.visit(&self.head);
+    }
+
+    #[inline]
+    fn fold<F: Folder>(&self, folder: &mut F, init: F::Acc) -> F::Acc {
+        folder.fold(init, &self.head)
+    }
+
+    #[inline]
+    fn try_visit_mut<V: TryVisitorMut>(&mut self, visitor: &mut V) -> Result<(), V::Error> {
+        visitor.try_visit_mut(&mut self.head)
+    }
+}
+
+impl<T, N: VisitList> VisitList for Cons<T, N> {
+    #[inline]
+    fn visit<V: Visitor>(&self, visitor: &mut V) {
+        visitor.visit(&self.head);
+        self.tail.visit(visitor);
+    }
+
+    #[inline]
+    fn fold<F: Folder>(&self, folder: &mut F, init: F::Acc) -> F::Acc {
+        let acc = folder.fold(init, &self.head);
+        self.tail.fold(folder, acc)
+    }
+
+    #[inline]
+    fn try_visit_mut<V: TryVisitorMut>(&mut self, visitor: &mut V) -> Result<(), V::Error> {
+        visitor.try_visit_mut(&mut self.head)?;
+        self.tail.try_visit_mut(visitor)
+    }
+}
+
 #[cfg(test)]
 mod test_macro {
-    use crate::{list_type, list_value, unpack_list, Cons, Nil};
+    use crate::{list_type, list_value, unpack_list, At, Cons, Fin, Nil};
 
     trait AssertEqualTypes<A, B> {}
 
@@ -349,6 +626,16 @@ mod test_macro {
         let _: &dyn AssertEqualTypes<GeneratedList, ExpectedList> = &();
     }
 
+    #[test]
+    fn test_nth_picks_element_at_index() {
+        type List = list_type![u8, u16, u32, Nil];
+
+        let _: &dyn AssertEqualTypes<At<List, 0>, u8> = &();
+        let _: &dyn AssertEqualTypes<At<List, 1>, u16> = &();
+        let _: &dyn AssertEqualTypes<At<List, 2>, u32> = &();
+        let _: &dyn AssertEqualTypes<At<Fin<i64>, 0>, i64> = &();
+    }
+
     #[test]
     fn text_list_macro_generates_correct_value() {
         let list = list_value![8u8, 16u16, 32u32, Nil::new()];
@@ -368,35 +655,122 @@ mod test_macro {
     }
 }
 
+#[cfg(test)]
+mod test_visit {
+    use std::any::type_name;
+
+    use crate::{list_value, Cons, Folder, Nil, TryVisitorMut, VisitList, Visitor};
+
+    struct TypeNames(Vec<&'static str>);
+
+    impl Visitor for TypeNames {
+        fn visit<T>(&mut self, _item: &T) {
+            self.0.push(type_name::<T>());
+        }
+    }
+
+    struct VisitUntil(usize, Vec<&'static str>);
+
+    impl TryVisitorMut for VisitUntil {
+        type Error = &'static str;
+
+        fn try_visit_mut<T>(&mut self, _item: &mut T) -> Result<(), Self::Error> {
+            if self.1.len() == self.0 {
+                return Err(type_name::<T>());
+            }
+            self.1.push(type_name::<T>());
+            Ok(())
+        }
+    }
+
+    struct ByteCount;
+
+    impl Folder for ByteCount {
+        type Acc = usize;
+
+        fn fold<T>(&mut self, acc: Self::Acc, _item: &T) -> Self::Acc {
+            acc + size_of::<T>()
+        }
+    }
+
+    #[test]
+    fn test_fold_sums_element_sizes() {
+        let list: Cons<u8, Cons<u16, Nil>> = list_value![1u8, 2u16, Nil::new()];
+        assert_eq!(list.fold(&mut ByteCount, 0), 3);
+        assert_eq!(Nil::new().fold(&mut ByteCount, 0), 0);
+    }
+
+    #[test]
+    fn test_for_each_visits_in_order() {
+        let list = list_value![1u8, 2u16, Nil::new()];
+        let mut names = TypeNames(Vec::new());
+        list.for_each(&mut names);
+        assert_eq!(names.0, [type_name::<u8>(), type_name::<u16>()]);
+    }
+
+    #[test]
+    fn test_visit_collects_type_names() {
+        let list = list_value![1u8, 2.0f32, "three", 4u64, 'c', Nil::new()];
+        let mut names = TypeNames(Vec::new());
+        list.visit(&mut names);
+        assert_eq!(
+            names.0,
+            [
+                type_name::<u8>(),
+                type_name::<f32>(),
+                type_name::<&str>(),
+                type_name::<u64>(),
+                type_name::<char>(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_try_visit_mut_stops_on_error() {
+        let mut list = list_value![1u8, 2u16, 3u32, 4u64, 5i32, Nil::new()];
+        let mut visitor = VisitUntil(3, Vec::new());
+        let result = list.try_visit_mut(&mut visitor);
+        assert_eq!(result, Err(type_name::<u64>()));
+        assert_eq!(
+            visitor.1,
+            [type_name::<u8>(), type_name::<u16>(), type_name::<u32>()]
+        );
+    }
+}
+
+// Expands into nested `Cons` type, last argument is used as the list tail,
+// e.g. `list_type![u8, u16, Nil]` is `Cons<u8, Cons<u16, Nil>>`
 #[macro_export]
 macro_rules! list_type {
     [$head:ty, $tail:ty] => {
-        Cons<$head, $tail>
+        $crate::Cons<$head, $tail>
     };
     [$head:ty $(, $tail:ty)*] => {
-        Cons<$head, list_type![$($tail),*]>
+        $crate::Cons<$head, $crate::list_type![$($tail),*]>
     };
 }
 
+// Value counterpart of `list_type!`, last expression is used as the list tail
 #[macro_export]
 macro_rules! list_value {
     [$head:expr, $tail:expr] => {
-        Cons::new($head, $tail)
+        $crate::Cons::new($head, $tail)
     };
     [$head:expr $(, $tail:expr)*] => {
-        Cons::new($head, list_value![$($tail),*])
+        $crate::Cons::new($head, $crate::list_value![$($tail),*])
     };
 }
 
+// Destructuring pattern matching list built with `list_value!`
 #[macro_export]
 macro_rules! unpack_list {
     [$tail:ident] => {
         $tail
     };
     [$head:ident $(, $tail:ident)*] => {
-        Cons {
+        $crate::Cons {
             head: $head,
-            tail: unpack_list![$($tail),*]
+            tail: $crate::unpack_list![$($tail),*]
         }
     };
 }
@@ -0,0 +1,17 @@
+#version 460 core
+
+#define VULKAN 100
+
+layout(location = 0) in vec3 world_pos;
+
+layout(location = 0) out vec4 gAlbedo;
+layout(location = 1) out vec4 gNormal;
+layout(location = 2) out vec4 gPosition;
+
+void main() {
+    // Flat shaded, vertex normals may not be present in the bound vertex layout
+    vec3 norm = normalize(cross(dFdy(world_pos), dFdx(world_pos)));
+    gAlbedo = vec4(1.0, 0.0, 1.0, 1.0);
+    gNormal = vec4(norm, 1.0);
+    gPosition = vec4(world_pos, 1.0);
+}
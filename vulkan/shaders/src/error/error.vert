@@ -0,0 +1,24 @@
+#version 460 core
+
+#define VULKAN 100
+
+// Declared as vec4 so that any vertex layout with position at location 0 can be bound,
+// missing components are filled with default values and w is forced to 1.0
+layout(location = 0) in vec4 pos;
+
+layout(location = 0) out vec3 world_pos;
+
+layout(set = 0, binding = 0) uniform FrameGlobals {
+    mat4 view;
+    mat4 proj;
+} c;
+
+layout(push_constant) uniform Model {
+    mat4 model;
+} m;
+
+void main() {
+    vec4 world = m.model * vec4(pos.xyz, 1.0);
+    world_pos = world.xyz;
+    gl_Position = c.proj * c.view * world;
+}
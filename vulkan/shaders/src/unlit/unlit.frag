@@ -0,0 +1,17 @@
+#version 460 core
+
+#define VULKAN 100
+
+layout(location = 0) in vec3 world_pos;
+layout(location = 1) in vec3 world_norm;
+layout(location = 2) in vec3 vertex_color;
+
+layout(location = 0) out vec4 gAlbedo;
+layout(location = 1) out vec4 gNormal;
+layout(location = 2) out vec4 gPosition;
+
+void main() {
+    gAlbedo = vec4(vertex_color, 1.0);
+    gNormal = vec4(world_norm, 1.0);
+    gPosition = vec4(world_pos, 1.0);
+}
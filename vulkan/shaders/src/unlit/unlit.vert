@@ -0,0 +1,31 @@
+#version 460 core
+
+#define VULKAN 100
+
+layout(location = 0) in vec3 pos;
+layout(location = 1) in vec3 color;
+layout(location = 2) in vec3 norm;
+
+layout(location = 0) out vec3 world_pos;
+layout(location = 1) out vec3 world_norm;
+layout(location = 2) out vec3 vertex_color;
+
+layout(set = 0, binding = 0) uniform FrameGlobals {
+    mat4 view;
+    mat4 proj;
+} c;
+
+// Packed normal matrix following the model matrix in the push constant range
+// does not match std430 layout of mat3, so normals are transformed with
+// the model matrix, which is correct for uniform scale only
+layout(push_constant) uniform Model {
+    mat4 model;
+} m;
+
+void main() {
+    vec4 world = m.model * vec4(pos, 1.0);
+    world_pos = world.xyz;
+    world_norm = (m.model * vec4(norm, 0.0)).xyz;
+    vertex_color = color;
+    gl_Position = c.proj * c.view * world;
+}
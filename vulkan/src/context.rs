@@ -1,10 +1,14 @@
 mod debug;
+pub mod destruction;
 pub mod device;
 pub mod error;
 mod surface;
+pub mod window;
 
 use self::{
+    destruction::DestructionQueue,
     device::{
+        enumerate_physical_devices,
         memory::MemoryProperties,
         raw::{
             allocator::{
@@ -14,17 +18,17 @@ use self::{
                 RawCollection, Resource, ResourceIndex, ResourceStorage, ResourceStorageList,
             },
         },
-        Device,
+        swapchain::PresentSurface,
+        Device, DeviceConfig, DeviceInfo, PresentTarget,
     },
     error::{ResourceResult, VkError, VkResult},
     surface::Surface,
 };
 use ash::extensions::{ext, khr};
-#[cfg(debug_assertions)]
-use debug::DebugUtils;
+pub use debug::{DebugCallback, DebugConfig, DebugMessage, Severity, DEFAULT_DEBUG_HISTORY_LEN};
+use debug::{DebugState, DebugUtils};
 use std::cell::RefCell;
 use std::convert::Infallible;
-use std::error::Error;
 use std::ffi::{c_char, CStr};
 use std::ops::{Deref, DerefMut};
 use type_kit::{
@@ -106,41 +110,45 @@ impl DerefMut for Instance {
 }
 
 impl Create for Instance {
-    type Config<'a> = ();
+    type Config<'a> = (&'a DebugState, bool);
     type CreateError = VkError;
 
-    fn create<'a, 'b>(_: Self::Config<'a>, _: Self::Context<'b>) -> CreateResult<Self> {
+    fn create<'a, 'b>(config: Self::Config<'a>, _: Self::Context<'b>) -> CreateResult<Self> {
+        let (config, presentable) = config;
         let entry = unsafe { ash::Entry::load()? };
-        let required_extensions = Surface::iterate_required_extensions();
-
-        #[cfg(debug_assertions)]
-        let required_extensions =
-            required_extensions.chain(DebugUtils::iterate_required_extensions());
+        let required_extensions = presentable
+            .then(Surface::iterate_required_extensions)
+            .into_iter()
+            .flatten();
+        let required_extensions = required_extensions.chain(
+            config
+                .validation()
+                .then(DebugUtils::iterate_required_extensions)
+                .into_iter()
+                .flatten(),
+        );
 
         let enabled_extension_names =
             check_required_extension_support(&entry, required_extensions)?;
-        #[cfg(debug_assertions)]
-        let enabled_layer_names = DebugUtils::check_required_layer_support(&entry)?;
+        let enabled_layer_names = if config.validation() {
+            DebugUtils::check_required_layer_support(&entry)?
+        } else {
+            Vec::new()
+        };
 
         let application_info = vk::ApplicationInfo {
             api_version: vk::API_VERSION_1_1,
             ..Default::default()
         };
 
-        #[cfg(debug_assertions)]
-        let mut debug_messenger_info = DebugUtils::create_info();
+        let mut debug_messenger_info = DebugUtils::create_info(config);
 
-        let create_info = {
-            #[cfg(debug_assertions)]
-            {
-                vk::InstanceCreateInfo::builder()
-                    .push_next(&mut debug_messenger_info)
-                    .enabled_layer_names(&enabled_layer_names)
-            }
-            #[cfg(not(debug_assertions))]
-            {
-                vk::InstanceCreateInfo::builder()
-            }
+        let create_info = if config.validation() {
+            vk::InstanceCreateInfo::builder()
+                .push_next(&mut debug_messenger_info)
+                .enabled_layer_names(&enabled_layer_names)
+        } else {
+            vk::InstanceCreateInfo::builder()
         };
 
         let create_info = create_info
@@ -170,11 +178,12 @@ impl Destroy for Instance {
 pub struct Context {
     allocators: Box<RefCell<DropGuard<AllocatorStorage>>>,
     storage: Box<RefCell<DropGuard<ResourceStorage>>>,
+    destruction: RefCell<DestructionQueue<Context>>,
     device: DropGuard<Device>,
-    surface: DropGuard<Surface>,
-    #[cfg(debug_assertions)]
-    debug_utils: DropGuard<DebugUtils>,
+    surface: Option<DropGuard<Surface>>,
+    debug_utils: Option<DropGuard<DebugUtils>>,
     instance: DropGuard<Instance>,
+    debug_state: Box<DebugState>,
 }
 
 trait DeviceExtension: Sized {
@@ -189,25 +198,135 @@ impl DeviceExtension for khr::Swapchain {
 }
 
 impl Context {
-    pub fn build(window: &Window) -> Result<Self, Box<dyn Error>> {
-        let instance = Instance::initialize(())?;
-        #[cfg(debug_assertions)]
-        let debug_utils = DebugUtils::create((), &instance)?;
-        let surface = Surface::create(window, &instance)?;
-        let device = Device::create(&surface, &instance)?;
+    pub fn build(window: &Window, debug: DebugConfig, device: &DeviceConfig) -> VkResult<Self> {
+        Self::build_with_target(Some(window), vk::Extent2D::default(), debug, device)
+    }
+
+    // Context without window surface, frames are rendered into
+    // offscreen color images of given extent instead of swapchain images
+    pub fn build_offscreen(
+        extent: vk::Extent2D,
+        debug: DebugConfig,
+        device: &DeviceConfig,
+    ) -> VkResult<Self> {
+        Self::build_with_target(None, extent, debug, device)
+    }
+
+    // Lists physical devices available before the context is created,
+    // device indices match the ones expected by DevicePreference::ByIndex
+    pub fn enumerate_devices() -> VkResult<Vec<DeviceInfo>> {
+        let debug_state = DebugState::new(DebugConfig {
+            validation: false,
+            ..Default::default()
+        });
+        let mut instance = Instance::initialize((&debug_state, false))?;
+        let devices = enumerate_physical_devices(&instance);
+        let _ = instance.finalize();
+        devices
+    }
+
+    fn build_with_target(
+        window: Option<&Window>,
+        offscreen_extent: vk::Extent2D,
+        debug: DebugConfig,
+        device: &DeviceConfig,
+    ) -> VkResult<Self> {
+        let debug_state = Box::new(DebugState::new(debug));
+        let instance = Instance::initialize((&*debug_state, window.is_some()))?;
+        let debug_utils = debug_state
+            .validation()
+            .then(|| DebugUtils::create(&debug_state, &instance))
+            .transpose()?;
+        let surface = window
+            .map(|window| Surface::create(window, &instance))
+            .transpose()?;
+        let target = match &surface {
+            Some(surface) => PresentTarget::Surface(surface),
+            None => PresentTarget::Offscreen(offscreen_extent),
+        };
+        let device = Device::create((target, debug_state.validation(), device), &instance)?;
         let allocators = Box::new(RefCell::new(DropGuard::new(AllocatorStorage::new())));
         let storage = Box::new(RefCell::new(DropGuard::new(ResourceStorage::new())));
         Ok(Self {
             allocators,
             storage,
+            destruction: RefCell::new(DestructionQueue::new()),
             device: DropGuard::new(device),
-            surface: DropGuard::new(surface),
-            #[cfg(debug_assertions)]
-            debug_utils: DropGuard::new(debug_utils),
+            surface: surface.map(DropGuard::new),
+            debug_utils: debug_utils.map(DropGuard::new),
             instance: DropGuard::new(instance),
+            debug_state,
         })
     }
 
+    #[inline]
+    pub fn is_offscreen(&self) -> bool {
+        self.surface.is_none()
+    }
+
+    // Surface of the window the context was built for, offscreen images are
+    // presented in its place when the context has no window
+    #[inline]
+    pub fn primary_surface(&self) -> PresentSurface<'_> {
+        PresentSurface {
+            surface: self.surface.as_deref(),
+            properties: self.device.surface_properties(),
+        }
+    }
+
+    // Most recent validation layer messages, oldest first
+    pub fn recent_debug_messages(&self) -> Vec<DebugMessage> {
+        self.debug_state.recent_messages()
+    }
+
+    pub fn update_surface_extent(&mut self, window_extent: vk::Extent2D) -> VkResult<()> {
+        let Self {
+            device, surface, ..
+        } = self;
+        match surface {
+            Some(surface) => device.update_surface_properties(surface, window_extent),
+            None => {
+                device.update_offscreen_extent(window_extent);
+                Ok(())
+            }
+        }
+    }
+
+    // Destroys `resource` once the frames recorded so far have finished,
+    // in place of waiting for the device to become idle
+    pub fn defer_destroy<T, F>(&self, resource: T, destroy: F)
+    where
+        T: Destroy + 'static,
+        T::DestroyError: Into<VkError>,
+        F: FnOnce(&mut T, &Context) -> DestroyResult<T> + 'static,
+    {
+        self.destruction
+            .borrow_mut()
+            .defer_destroy(resource, destroy)
+    }
+
+    pub fn defer_destroy_guarded<T, F>(&self, resource: DropGuard<T>, destroy: F)
+    where
+        T: Destroy + 'static,
+        T::DestroyError: Into<VkError>,
+        F: FnOnce(&mut T, &Context) -> DestroyResult<T> + 'static,
+    {
+        self.destruction
+            .borrow_mut()
+            .defer_destroy_guarded(resource, destroy)
+    }
+
+    // Called by the renderer after each frame submission, `fence` is the fence
+    // of the submitted frame. Resources of the finished frames are destroyed
+    pub fn tick_destruction_queue(&self, fence: vk::Fence) -> VkResult<()> {
+        let completed = {
+            let mut destruction = self.destruction.borrow_mut();
+            destruction.frame_submitted(fence);
+            destruction.take_completed(self)?
+        };
+        completed.destroy(self)
+    }
+
     #[inline]
     pub(crate) fn load<E: DeviceExtension>(&self) -> E {
         E::load(&self.instance, &self.device)
@@ -217,12 +336,17 @@ impl Context {
 impl Drop for Context {
     fn drop(&mut self) {
         let _ = self.device.wait_idle();
+        let deferred = self.destruction.get_mut().take_all();
+        let _ = deferred.destroy(self);
         let _ = self.storage.borrow_mut().destroy(&self);
         let _ = self.allocators.borrow_mut().destroy(&self);
         let _ = self.device.destroy(&self.instance);
-        let _ = self.surface.destroy(&self.instance);
-        #[cfg(debug_assertions)]
-        let _ = self.debug_utils.destroy(&self.instance);
+        if let Some(surface) = &mut self.surface {
+            let _ = surface.destroy(&self.instance);
+        }
+        if let Some(debug_utils) = &mut self.debug_utils {
+            let _ = debug_utils.destroy(&self.instance);
+        }
         let _ = self.instance.finalize();
     }
 }
@@ -1,9 +1,12 @@
 #![allow(unused)]
 
 use std::{
+    collections::VecDeque,
     convert::Infallible,
     error::Error,
     ffi::{c_char, c_void, CStr},
+    fmt::{Debug, Formatter},
+    sync::{Arc, Mutex},
 };
 
 use ash::{extensions::ext, vk};
@@ -15,12 +18,135 @@ use super::{
     Instance,
 };
 
-unsafe extern "system" fn debug_messenger_callback(
+pub const DEFAULT_DEBUG_HISTORY_LEN: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Verbose,
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    fn from_flags(flags: vk::DebugUtilsMessageSeverityFlagsEXT) -> Self {
+        match flags {
+            vk::DebugUtilsMessageSeverityFlagsEXT::ERROR => Severity::Error,
+            vk::DebugUtilsMessageSeverityFlagsEXT::WARNING => Severity::Warning,
+            vk::DebugUtilsMessageSeverityFlagsEXT::INFO => Severity::Info,
+            _ => Severity::Verbose,
+        }
+    }
+
+    // All severity flags at or above given severity
+    fn flags(self) -> vk::DebugUtilsMessageSeverityFlagsEXT {
+        [
+            (
+                Severity::Verbose,
+                vk::DebugUtilsMessageSeverityFlagsEXT::VERBOSE,
+            ),
+            (Severity::Info, vk::DebugUtilsMessageSeverityFlagsEXT::INFO),
+            (
+                Severity::Warning,
+                vk::DebugUtilsMessageSeverityFlagsEXT::WARNING,
+            ),
+            (
+                Severity::Error,
+                vk::DebugUtilsMessageSeverityFlagsEXT::ERROR,
+            ),
+        ]
+        .into_iter()
+        .filter(|&(severity, _)| severity >= self)
+        .fold(
+            vk::DebugUtilsMessageSeverityFlagsEXT::empty(),
+            |flags, (_, flag)| flags | flag,
+        )
+    }
+}
+
+pub type DebugCallback = Arc<dyn Fn(Severity, &str) + Send + Sync>;
+
+#[derive(Clone)]
+pub struct DebugConfig {
+    pub validation: bool,
+    pub severity: Severity,
+    pub history_len: usize,
+    pub callback: Option<DebugCallback>,
+}
+
+impl Default for DebugConfig {
+    fn default() -> Self {
+        Self {
+            validation: cfg!(debug_assertions),
+            severity: Severity::Info,
+            history_len: DEFAULT_DEBUG_HISTORY_LEN,
+            callback: None,
+        }
+    }
+}
+
+impl Debug for DebugConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DebugConfig")
+            .field("validation", &self.validation)
+            .field("severity", &self.severity)
+            .field("history_len", &self.history_len)
+            .field("callback", &self.callback.is_some())
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DebugMessage {
+    pub severity: Severity,
+    pub message: String,
+}
+
+// Shared with the debug messenger through its user data pointer,
+// must stay at stable address for the whole lifetime of the instance
+pub struct DebugState {
+    config: DebugConfig,
+    history: Mutex<VecDeque<DebugMessage>>,
+}
+
+impl DebugState {
+    pub fn new(config: DebugConfig) -> Self {
+        Self {
+            history: Mutex::new(VecDeque::with_capacity(config.history_len)),
+            config,
+        }
+    }
+
+    #[inline]
+    pub fn validation(&self) -> bool {
+        self.config.validation
+    }
+
+    pub fn recent_messages(&self) -> Vec<DebugMessage> {
+        self.history
+            .lock()
+            .map(|history| history.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    fn push(&self, message: DebugMessage) {
+        if self.config.history_len == 0 {
+            return;
+        }
+        if let Ok(mut history) = self.history.lock() {
+            if history.len() == self.config.history_len {
+                history.pop_front();
+            }
+            history.push_back(message);
+        }
+    }
+}
+
+fn print_message(
     message_severity: vk::DebugUtilsMessageSeverityFlagsEXT,
     message_type: vk::DebugUtilsMessageTypeFlagsEXT,
-    message: *const vk::DebugUtilsMessengerCallbackDataEXT,
-    _: *mut c_void,
-) -> vk::Bool32 {
+    message: &str,
+) {
     let message_severity = match message_severity {
         vk::DebugUtilsMessageSeverityFlagsEXT::ERROR => "ERROR".red(),
         vk::DebugUtilsMessageSeverityFlagsEXT::WARNING => "WARNING".yellow(),
@@ -39,8 +165,27 @@ unsafe extern "system" fn debug_messenger_callback(
         _ => "UNKNOWN".magenta(),
     }
     .bold();
-    let message = CStr::from_ptr((*message).p_message).to_string_lossy();
     println!("[{}][{}]:{}", message_severity, message_type, message);
+}
+
+unsafe extern "system" fn debug_messenger_callback(
+    message_severity: vk::DebugUtilsMessageSeverityFlagsEXT,
+    message_type: vk::DebugUtilsMessageTypeFlagsEXT,
+    message: *const vk::DebugUtilsMessengerCallbackDataEXT,
+    user_data: *mut c_void,
+) -> vk::Bool32 {
+    let text = CStr::from_ptr((*message).p_message).to_string_lossy();
+    let state = (user_data as *const DebugState).as_ref();
+    match state.and_then(|state| state.config.callback.as_ref()) {
+        Some(callback) => callback(Severity::from_flags(message_severity), &text),
+        None => print_message(message_severity, message_type, &text),
+    }
+    if let Some(state) = state {
+        state.push(DebugMessage {
+            severity: Severity::from_flags(message_severity),
+            message: text.into_owned(),
+        });
+    }
     vk::FALSE
 }
 
@@ -50,15 +195,14 @@ pub(super) struct DebugUtils {
 }
 
 impl DebugUtils {
-    pub fn create_info() -> vk::DebugUtilsMessengerCreateInfoEXT {
+    pub fn create_info(state: &DebugState) -> vk::DebugUtilsMessengerCreateInfoEXT {
         vk::DebugUtilsMessengerCreateInfoEXT {
-            message_severity: vk::DebugUtilsMessageSeverityFlagsEXT::ERROR
-                | vk::DebugUtilsMessageSeverityFlagsEXT::WARNING
-                | vk::DebugUtilsMessageSeverityFlagsEXT::INFO,
+            message_severity: state.config.severity.flags(),
             message_type: vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION
                 | vk::DebugUtilsMessageTypeFlagsEXT::PERFORMANCE
                 | vk::DebugUtilsMessageTypeFlagsEXT::GENERAL,
             pfn_user_callback: Some(debug_messenger_callback),
+            p_user_data: state as *const DebugState as *mut c_void,
             ..Default::default()
         }
     }
@@ -92,15 +236,16 @@ impl DebugUtils {
 }
 
 impl Create for DebugUtils {
-    type Config<'a> = ();
+    type Config<'a> = &'a DebugState;
     type CreateError = VkError;
 
     fn create<'a>(
-        _config: Self::Config<'a>,
+        config: Self::Config<'a>,
         context: Self::Context<'a>,
     ) -> Result<Self, Self::CreateError> {
         let loader: ext::DebugUtils = context.load();
-        let messenger = unsafe { loader.create_debug_utils_messenger(&Self::create_info(), None)? };
+        let messenger =
+            unsafe { loader.create_debug_utils_messenger(&Self::create_info(config), None)? };
         Ok(Self { messenger, loader })
     }
 }
@@ -0,0 +1,264 @@
+use std::collections::VecDeque;
+
+use ash::vk;
+use type_kit::{Destroy, DestroyResult, DropGuard};
+
+use super::{
+    device::Device,
+    error::{VkError, VkResult},
+    Context,
+};
+
+// Fence status lookup of the destruction queue context,
+// simulated fence timelines implement it in tests
+pub trait FenceStatus {
+    fn is_signaled(&self, fence: vk::Fence) -> VkResult<bool>;
+}
+
+impl FenceStatus for Device {
+    #[inline]
+    fn is_signaled(&self, fence: vk::Fence) -> VkResult<bool> {
+        Ok(unsafe { self.get_fence_status(fence)? })
+    }
+}
+
+impl FenceStatus for Context {
+    #[inline]
+    fn is_signaled(&self, fence: vk::Fence) -> VkResult<bool> {
+        (**self).is_signaled(fence)
+    }
+}
+
+type DeferredEntry<C> = Box<dyn FnOnce(&C) -> VkResult<()>>;
+
+// Entries removed from the queue, destroyed only after the queue borrow
+// is released, so that destroyed resources may defer further destruction
+pub struct DeferredBatch<C: 'static> {
+    entries: Vec<DeferredEntry<C>>,
+}
+
+impl<C: 'static> DeferredBatch<C> {
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // Every entry is destroyed even if some of them fail, first error is returned
+    pub fn destroy(self, context: &C) -> VkResult<()> {
+        let mut result = Ok(());
+        for entry in self.entries {
+            if let Err(error) = entry(context) {
+                if result.is_ok() {
+                    result = Err(error);
+                }
+            }
+        }
+        result
+    }
+}
+
+// Resources which may still be referenced by command buffers in flight,
+// each is tagged with the frame being recorded when it was deferred and
+// destroyed once the fence of that frame has signaled. Frames are submitted
+// to single queue in order, so signaled fence completes all earlier frames
+pub struct DestructionQueue<C: 'static> {
+    frame: u64,
+    // Fences of submitted frames not yet known to be finished, oldest first
+    submitted: VecDeque<(u64, vk::Fence)>,
+    pending: VecDeque<(u64, DeferredEntry<C>)>,
+}
+
+impl<C: 'static> Default for DestructionQueue<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: 'static> DestructionQueue<C> {
+    pub fn new() -> Self {
+        Self {
+            frame: 0,
+            submitted: VecDeque::new(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    // `destroy` receives the queue context once the resource is no longer in use,
+    // it is expected to call `Destroy::destroy` with the context the resource needs
+    pub fn defer_destroy<T, F>(&mut self, resource: T, destroy: F)
+    where
+        T: Destroy + 'static,
+        T::DestroyError: Into<VkError>,
+        F: FnOnce(&mut T, &C) -> DestroyResult<T> + 'static,
+    {
+        self.defer_destroy_guarded(DropGuard::new(resource), destroy)
+    }
+
+    // Guard is kept armed while the resource waits in the queue,
+    // so that resources dropped with the queue are still reported
+    pub fn defer_destroy_guarded<T, F>(&mut self, resource: DropGuard<T>, destroy: F)
+    where
+        T: Destroy + 'static,
+        T::DestroyError: Into<VkError>,
+        F: FnOnce(&mut T, &C) -> DestroyResult<T> + 'static,
+    {
+        let entry: DeferredEntry<C> = Box::new(move |context| {
+            let mut resource = resource.into_inner();
+            destroy(&mut resource, context).map_err(Into::into)
+        });
+        self.pending.push_back((self.frame, entry));
+    }
+
+    // Closes the frame being recorded, `fence` is signaled once its commands finish
+    pub fn frame_submitted(&mut self, fence: vk::Fence) {
+        self.submitted.push_back((self.frame, fence));
+        self.frame += 1;
+    }
+
+    // Takes entries of the frames whose fences have signaled, does not wait
+    pub fn take_completed(&mut self, fences: &impl FenceStatus) -> VkResult<DeferredBatch<C>> {
+        let mut completed = None;
+        for (index, &(frame, fence)) in self.submitted.iter().enumerate().rev() {
+            if fences.is_signaled(fence)? {
+                completed = Some((index, frame));
+                break;
+            }
+        }
+        let mut entries = Vec::new();
+        if let Some((index, completed)) = completed {
+            self.submitted.drain(..=index);
+            while self
+                .pending
+                .front()
+                .is_some_and(|&(frame, _)| frame <= completed)
+            {
+                if let Some((_, entry)) = self.pending.pop_front() {
+                    entries.push(entry);
+                }
+            }
+        }
+        Ok(DeferredBatch { entries })
+    }
+
+    // Takes every entry regardless of frame, device must be idle
+    pub fn take_all(&mut self) -> DeferredBatch<C> {
+        self.submitted.clear();
+        DeferredBatch {
+            entries: self.pending.drain(..).map(|(_, entry)| entry).collect(),
+        }
+    }
+}
+
+impl<C: FenceStatus + 'static> DestructionQueue<C> {
+    #[inline]
+    pub fn tick(&mut self, context: &C) -> VkResult<()> {
+        self.take_completed(context)?.destroy(context)
+    }
+
+    #[inline]
+    pub fn flush(&mut self, context: &C) -> VkResult<()> {
+        self.take_all().destroy(context)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, convert::Infallible, rc::Rc};
+
+    use ash::vk::Handle;
+
+    use super::*;
+
+    // Fence of frame N signals once the timeline reaches N + 1
+    #[derive(Default)]
+    struct Timeline {
+        finished: Cell<u64>,
+    }
+
+    impl Timeline {
+        fn fence(frame: u64) -> vk::Fence {
+            vk::Fence::from_raw(frame + 1)
+        }
+    }
+
+    impl FenceStatus for Timeline {
+        fn is_signaled(&self, fence: vk::Fence) -> VkResult<bool> {
+            Ok(fence.as_raw() <= self.finished.get())
+        }
+    }
+
+    struct Counted {
+        destroyed: Rc<Cell<usize>>,
+    }
+
+    impl Destroy for Counted {
+        type Context<'a> = &'a Timeline;
+        type DestroyError = Infallible;
+
+        fn destroy<'a>(&mut self, _context: Self::Context<'a>) -> DestroyResult<Self> {
+            self.destroyed.set(self.destroyed.get() + 1);
+            Ok(())
+        }
+    }
+
+    fn counted(destroyed: &Rc<Cell<usize>>) -> Counted {
+        Counted {
+            destroyed: destroyed.clone(),
+        }
+    }
+
+    #[test]
+    fn test_resources_destroyed_after_frame_fence() {
+        let timeline = Timeline::default();
+        let destroyed = Rc::new(Cell::new(0));
+        let mut queue = DestructionQueue::<Timeline>::new();
+        queue.defer_destroy(counted(&destroyed), |resource, context| {
+            resource.destroy(context)
+        });
+        queue.frame_submitted(Timeline::fence(0));
+        queue.defer_destroy_guarded(DropGuard::new(counted(&destroyed)), |resource, context| {
+            resource.destroy(context)
+        });
+        queue.frame_submitted(Timeline::fence(1));
+        queue.frame_submitted(Timeline::fence(2));
+        queue.tick(&timeline).unwrap();
+        assert_eq!(destroyed.get(), 0);
+        timeline.finished.set(1);
+        queue.tick(&timeline).unwrap();
+        assert_eq!((destroyed.get(), queue.len()), (1, 1));
+        timeline.finished.set(3);
+        queue.tick(&timeline).unwrap();
+        assert_eq!((destroyed.get(), queue.len()), (2, 0));
+    }
+
+    #[test]
+    fn test_unsubmitted_frame_resources_kept_until_flush() {
+        let timeline = Timeline::default();
+        let destroyed = Rc::new(Cell::new(0));
+        let mut queue = DestructionQueue::<Timeline>::new();
+        queue.frame_submitted(Timeline::fence(0));
+        queue.defer_destroy(counted(&destroyed), |resource, context| {
+            resource.destroy(context)
+        });
+        timeline.finished.set(1);
+        queue.tick(&timeline).unwrap();
+        assert_eq!(destroyed.get(), 0);
+        queue.flush(&timeline).unwrap();
+        assert_eq!((destroyed.get(), queue.len()), (1, 0));
+    }
+}
@@ -4,32 +4,50 @@ pub mod frame;
 pub mod framebuffer;
 pub mod memory;
 pub mod pipeline;
+pub mod profiler;
 pub mod raw;
 pub mod render_pass;
 pub mod renderer;
 pub mod resources;
+pub mod sampler;
+pub mod screenshot;
+pub mod stats;
 pub mod swapchain;
+pub mod tracker;
 
 use super::{
     error::{DeviceNotSuitable, VkError, VkResult},
     Instance,
 };
 
-use self::command::TransientCommandPools;
+use self::{
+    command::TransientCommandPools,
+    framebuffer::FramebufferCache,
+    render_pass::RenderPassCache,
+    sampler::{SamplerCache, SamplerConfig},
+    stats::RenderStatsCounter,
+    tracker::ResourceTracker,
+};
 use super::surface::{PhysicalDeviceSurfaceProperties, Surface};
-use ash::{self, vk};
+use ash::{self, extensions::ext, vk};
 use colored::Colorize;
 use std::convert::Infallible;
-use std::ffi::c_char;
-use std::fmt::{Debug, Formatter};
+use std::ffi::{c_char, CString};
+use std::fmt::{Debug, Display, Formatter};
 use std::ops::Deref;
 use std::{
     collections::{HashMap, HashSet},
-    error::Error,
     ffi::CStr,
 };
 use type_kit::{Create, Destroy, DestroyResult};
 
+// Test-only hook simulating devices which expose single queue family,
+// thread local so that it does not leak into concurrently running tests
+#[cfg(test)]
+thread_local! {
+    static SINGLE_QUEUE_FAMILY: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+}
+
 #[derive(Debug, Clone, Copy)]
 struct QueueFamilies {
     graphics: u32,
@@ -41,6 +59,18 @@ impl QueueFamilies {
     pub fn get(
         properties: &PhysicalDeviceProperties,
         surface_properties: &PhysicalDeviceSurfaceProperties,
+    ) -> Result<Self, DeviceNotSuitable> {
+        Self::select(
+            &properties.queue_families,
+            &surface_properties.supported_queue_families,
+        )
+    }
+
+    // Dedicated queue families are preferred, operations share single family
+    // when device does not expose separate ones
+    fn select(
+        queue_families: &[(vk::QueueFamilyProperties, u32)],
+        supported_queue_families: &HashSet<u32>,
     ) -> Result<Self, DeviceNotSuitable> {
         let mut queue_usages = HashMap::new();
         let mut try_use_queue_family = |queue: &mut Option<u32>, queue_family_index: u32| {
@@ -60,26 +90,29 @@ impl QueueFamilies {
             }
         };
         let (mut graphics, mut compute, mut transfer) = (None, None, None);
-        for &(properties, queue_family_index) in &properties.queue_families {
+        for &(properties, queue_family_index) in queue_families {
             if graphics.is_none()
                 && properties.queue_flags.contains(vk::QueueFlags::GRAPHICS)
-                && surface_properties
-                    .supported_queue_families
-                    .contains(&queue_family_index)
+                && supported_queue_families.contains(&queue_family_index)
             {
                 try_use_queue_family(&mut graphics, queue_family_index);
             }
             if properties.queue_flags.contains(vk::QueueFlags::COMPUTE) {
                 try_use_queue_family(&mut compute, queue_family_index);
             }
-            if transfer.is_none() && properties.queue_flags.contains(vk::QueueFlags::TRANSFER) {
+            if properties.queue_flags.contains(vk::QueueFlags::TRANSFER) {
                 try_use_queue_family(&mut transfer, queue_family_index);
             }
         }
+        let graphics = graphics.ok_or(DeviceNotSuitable::MissingQueueFamilyIndex("Graphics"))?;
+        let compute = compute.ok_or(DeviceNotSuitable::MissingQueueFamilyIndex("Compute"))?;
+        // Graphics and compute families support transfer operations implicitly,
+        // even when TRANSFER flag is not reported for any of them
+        let transfer = transfer.unwrap_or(graphics);
         Ok(Self {
-            graphics: graphics.ok_or(DeviceNotSuitable::MissingQueueFamilyIndex(&"Graphics"))?,
-            compute: compute.ok_or(DeviceNotSuitable::MissingQueueFamilyIndex(&"Compute"))?,
-            transfer: transfer.ok_or(DeviceNotSuitable::MissingQueueFamilyIndex(&"Transfer"))?,
+            graphics,
+            compute,
+            transfer,
         })
     }
 }
@@ -144,10 +177,27 @@ impl PhysicalDeviceProperties {
     ) -> vk::PhysicalDeviceFeatures {
         vk::PhysicalDeviceFeatures {
             sample_rate_shading: features.sample_rate_shading,
+            sampler_anisotropy: features.sampler_anisotropy,
+            fill_mode_non_solid: features.fill_mode_non_solid,
+            depth_bias_clamp: features.depth_bias_clamp,
             ..Default::default()
         }
     }
 
+    // Number of optional features enabled on the device, used to rank suitable devices
+    fn enabled_feature_count(&self) -> usize {
+        let features = &self.enabled_features;
+        [
+            features.sample_rate_shading,
+            features.sampler_anisotropy,
+            features.fill_mode_non_solid,
+            features.depth_bias_clamp,
+        ]
+        .into_iter()
+        .filter(|&enabled| enabled == vk::TRUE)
+        .count()
+    }
+
     pub fn get(
         instance: &ash::Instance,
         physical_device: vk::PhysicalDevice,
@@ -220,20 +270,89 @@ impl PhysicalDeviceProperties {
                 }
             })
         });
+        #[cfg(test)]
+        if SINGLE_QUEUE_FAMILY.get() {
+            quque_properties.retain(|(properties, _)| {
+                properties
+                    .queue_flags
+                    .contains(vk::QueueFlags::GRAPHICS | vk::QueueFlags::COMPUTE)
+            });
+            quque_properties.truncate(1);
+        }
         quque_properties
     }
 }
 
 #[derive(Debug, Clone, Copy)]
 struct AttachmentFormats {
+    // Surface format, shared by the combined attachment resolved into the swapchain
     color: vk::Format,
+    // Intermediate G-Buffer targets are never sRGB encoded
+    albedo: vk::Format,
+    normal: vk::Format,
+    position: vk::Format,
     depth_stencil: vk::Format,
 }
 
+// Reported when the sample count requested in the device config
+// is not supported by the device for both color and depth attachments
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SampleCountFallback {
+    pub requested: vk::SampleCountFlags,
+    pub selected: vk::SampleCountFlags,
+}
+
+impl Display for SampleCountFallback {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Sample count {} not supported, falling back to {}",
+            self.requested.as_raw(),
+            self.selected.as_raw()
+        )
+    }
+}
+
+// Highest supported count is used when none is requested
+fn select_sample_count(
+    supported: vk::SampleCountFlags,
+    requested: Option<vk::SampleCountFlags>,
+) -> (vk::SampleCountFlags, Option<SampleCountFallback>) {
+    let max_supported = [
+        vk::SampleCountFlags::TYPE_64,
+        vk::SampleCountFlags::TYPE_32,
+        vk::SampleCountFlags::TYPE_16,
+        vk::SampleCountFlags::TYPE_8,
+        vk::SampleCountFlags::TYPE_4,
+        vk::SampleCountFlags::TYPE_2,
+    ]
+    .into_iter()
+    .find(|&sample_count| supported.contains(sample_count))
+    .unwrap_or(vk::SampleCountFlags::TYPE_1);
+    match requested {
+        None => (max_supported, None),
+        Some(requested)
+            if requested.as_raw().is_power_of_two()
+                && (supported | vk::SampleCountFlags::TYPE_1).contains(requested) =>
+        {
+            (requested, None)
+        }
+        Some(requested) => (
+            max_supported,
+            Some(SampleCountFallback {
+                requested,
+                selected: max_supported,
+            }),
+        ),
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct AttachmentProperties {
     formats: AttachmentFormats,
     msaa_samples: vk::SampleCountFlags,
+    supported_samples: vk::SampleCountFlags,
+    sample_count_fallback: Option<SampleCountFallback>,
 }
 
 impl AttachmentProperties {
@@ -242,6 +361,10 @@ impl AttachmentProperties {
         vk::Format::D24_UNORM_S8_UINT,
         vk::Format::D16_UNORM_S8_UINT,
     ];
+    // Color attachment support of these formats is mandatory
+    const ALBEDO_FORMAT: vk::Format = vk::Format::R8G8B8A8_UNORM;
+    const NORMAL_FORMAT: vk::Format = vk::Format::R16G16B16A16_SFLOAT;
+    const POSITION_FORMAT: vk::Format = vk::Format::R32G32B32A32_SFLOAT;
 
     pub fn get(
         instance: &ash::Instance,
@@ -261,28 +384,21 @@ impl AttachmentProperties {
                     .contains(vk::FormatFeatureFlags::DEPTH_STENCIL_ATTACHMENT)
             })
             .ok_or(DeviceNotSuitable::MissingDepthAndStencilFormat)?;
-        let msaa_samples = [
-            vk::SampleCountFlags::TYPE_64,
-            vk::SampleCountFlags::TYPE_32,
-            vk::SampleCountFlags::TYPE_16,
-            vk::SampleCountFlags::TYPE_8,
-            vk::SampleCountFlags::TYPE_4,
-            vk::SampleCountFlags::TYPE_2,
-        ]
-        .into_iter()
-        .find(|&sample_count| {
-            (properties.generic.limits.framebuffer_color_sample_counts
-                & properties.generic.limits.framebuffer_depth_sample_counts)
-                .contains(sample_count)
-        })
-        .unwrap_or(vk::SampleCountFlags::TYPE_1);
+        let supported_samples = properties.generic.limits.framebuffer_color_sample_counts
+            & properties.generic.limits.framebuffer_depth_sample_counts;
+        let (msaa_samples, _) = select_sample_count(supported_samples, None);
 
         Ok(Self {
             formats: AttachmentFormats {
                 color,
+                albedo: Self::ALBEDO_FORMAT,
+                normal: Self::NORMAL_FORMAT,
+                position: Self::POSITION_FORMAT,
                 depth_stencil,
             },
             msaa_samples,
+            supported_samples,
+            sample_count_fallback: None,
         })
     }
 }
@@ -313,6 +429,12 @@ pub struct Device {
     physical_device: PhysicalDevice,
     command_pools: TransientCommandPools,
     device_queues: DeviceQueues,
+    debug_utils: Option<ext::DebugUtils>,
+    tracker: ResourceTracker,
+    stats: RenderStatsCounter,
+    samplers: SamplerCache,
+    framebuffers: FramebufferCache,
+    render_passes: RenderPassCache,
     device: ash::Device,
 }
 
@@ -340,14 +462,34 @@ impl Deref for Device {
     }
 }
 
+#[derive(Clone, Copy)]
+pub enum PresentTarget<'a> {
+    Surface(&'a Surface),
+    Offscreen(vk::Extent2D),
+}
+
 fn check_physical_device_suitable(
     physical_device: vk::PhysicalDevice,
     instance: &ash::Instance,
-    surface: &Surface,
+    target: PresentTarget,
+    color_space: OutputColorSpace,
+    present_mode: PresentMode,
 ) -> Result<PhysicalDevice, DeviceNotSuitable> {
     let properties = PhysicalDeviceProperties::get(instance, physical_device)?;
-    let surface_properties =
-        PhysicalDeviceSurfaceProperties::get(surface, physical_device, &properties.queue_families)?;
+    let surface_properties = match target {
+        PresentTarget::Surface(surface) => PhysicalDeviceSurfaceProperties::get(
+            surface,
+            physical_device,
+            &properties.queue_families,
+            color_space,
+            present_mode,
+        )?,
+        PresentTarget::Offscreen(extent) => PhysicalDeviceSurfaceProperties::offscreen(
+            extent,
+            &properties.queue_families,
+            color_space,
+        )?,
+    };
     let attachment_properties =
         AttachmentProperties::get(instance, physical_device, &properties, &surface_properties)?;
     let queue_families = QueueFamilies::get(&properties, &surface_properties)?;
@@ -360,25 +502,201 @@ fn check_physical_device_suitable(
     })
 }
 
-fn pick_physical_device(instance: &ash::Instance, surface: &Surface) -> VkResult<PhysicalDevice> {
-    let (suitable_devices, discarded_devices) = unsafe { instance.enumerate_physical_devices()? }
+// Physical device chosen when more than one suitable device is present,
+// name and index overrides reject every other device
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum DevicePreference {
+    #[default]
+    Discrete,
+    Integrated,
+    ByName(String),
+    ByIndex(usize),
+}
+
+impl DevicePreference {
+    // Higher score is preferred, None when device is rejected by explicit override
+    fn score(&self, info: &DeviceInfo) -> Option<usize> {
+        let type_score = |preferred, fallback| match info.device_type {
+            device_type if device_type == preferred => 2,
+            device_type if device_type == fallback => 1,
+            _ => 0,
+        };
+        match self {
+            DevicePreference::Discrete => Some(type_score(
+                vk::PhysicalDeviceType::DISCRETE_GPU,
+                vk::PhysicalDeviceType::INTEGRATED_GPU,
+            )),
+            DevicePreference::Integrated => Some(type_score(
+                vk::PhysicalDeviceType::INTEGRATED_GPU,
+                vk::PhysicalDeviceType::DISCRETE_GPU,
+            )),
+            DevicePreference::ByName(name) => (info.name == *name).then_some(0),
+            DevicePreference::ByIndex(index) => (info.index == *index).then_some(0),
+        }
+    }
+}
+
+// Encoding of the image presented to the swapchain. With `Srgb` the surface uses
+// `_SRGB` format encoding the shading pass output, with `Linear` the surface uses
+// `_UNORM` format and the shading pass applies sRGB transfer function itself
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputColorSpace {
+    Linear,
+    #[default]
+    Srgb,
+}
+
+impl OutputColorSpace {
+    pub fn preferred_formats(self) -> &'static [vk::Format] {
+        match self {
+            OutputColorSpace::Srgb => &[vk::Format::B8G8R8A8_SRGB, vk::Format::R8G8B8A8_SRGB],
+            OutputColorSpace::Linear => &[vk::Format::B8G8R8A8_UNORM, vk::Format::R8G8B8A8_UNORM],
+        }
+    }
+
+    pub fn offscreen_format(self) -> vk::Format {
+        match self {
+            OutputColorSpace::Srgb => vk::Format::R8G8B8A8_SRGB,
+            OutputColorSpace::Linear => vk::Format::R8G8B8A8_UNORM,
+        }
+    }
+
+    // Color space in effect for the surface format, formats which are not
+    // sRGB encoded by the hardware are treated as linear
+    pub fn of_format(format: vk::Format) -> Self {
+        match format {
+            vk::Format::R8G8B8A8_SRGB
+            | vk::Format::B8G8R8A8_SRGB
+            | vk::Format::A8B8G8R8_SRGB_PACK32 => OutputColorSpace::Srgb,
+            _ => OutputColorSpace::Linear,
+        }
+    }
+}
+
+// Presentation mode of the swapchain. Unsupported modes are replaced with the
+// closest supported one, down to FIFO which every surface has to support
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PresentMode {
+    // Presentation waits for vertical blank, frame rate is capped at refresh rate
+    Fifo,
+    // Image queued for presentation is replaced by newer one, no tearing
+    #[default]
+    Mailbox,
+    // Image is presented right away, lowest latency but may tear
+    Immediate,
+}
+
+impl PresentMode {
+    pub fn preferred_modes(self) -> &'static [vk::PresentModeKHR] {
+        match self {
+            PresentMode::Fifo => &[vk::PresentModeKHR::FIFO],
+            PresentMode::Mailbox => &[vk::PresentModeKHR::MAILBOX, vk::PresentModeKHR::FIFO],
+            PresentMode::Immediate => &[
+                vk::PresentModeKHR::IMMEDIATE,
+                vk::PresentModeKHR::MAILBOX,
+                vk::PresentModeKHR::FIFO,
+            ],
+        }
+    }
+
+    // Relaxed FIFO is not selected by the renderer, it is reported as FIFO
+    pub fn of_mode(mode: vk::PresentModeKHR) -> Self {
+        match mode {
+            vk::PresentModeKHR::MAILBOX => PresentMode::Mailbox,
+            vk::PresentModeKHR::IMMEDIATE => PresentMode::Immediate,
+            _ => PresentMode::Fifo,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DeviceConfig {
+    pub preference: DevicePreference,
+    pub sampler: SamplerConfig,
+    // Sample count of G-Buffer attachments, highest supported when None
+    pub sample_count: Option<vk::SampleCountFlags>,
+    pub output_color_space: OutputColorSpace,
+    pub present_mode: PresentMode,
+}
+
+#[derive(Debug, Clone)]
+pub struct DeviceInfo {
+    pub index: usize,
+    pub name: String,
+    pub device_type: vk::PhysicalDeviceType,
+    pub vendor_id: u32,
+    pub api_version: u32,
+    pub heap_sizes: Vec<vk::DeviceSize>,
+}
+
+impl DeviceInfo {
+    fn get(instance: &ash::Instance, physical_device: vk::PhysicalDevice, index: usize) -> Self {
+        let properties = unsafe { instance.get_physical_device_properties(physical_device) };
+        let memory = unsafe { instance.get_physical_device_memory_properties(physical_device) };
+        let name = unsafe { CStr::from_ptr(&properties.device_name as *const _) };
+        Self {
+            index,
+            name: name.to_string_lossy().into_owned(),
+            device_type: properties.device_type,
+            vendor_id: properties.vendor_id,
+            api_version: properties.api_version,
+            heap_sizes: memory.memory_heaps[..memory.memory_heap_count as usize]
+                .iter()
+                .map(|heap| heap.size)
+                .collect(),
+        }
+    }
+}
+
+pub fn enumerate_physical_devices(instance: &ash::Instance) -> VkResult<Vec<DeviceInfo>> {
+    let devices = unsafe { instance.enumerate_physical_devices()? }
         .into_iter()
-        .map(|physical_device| check_physical_device_suitable(physical_device, instance, surface))
-        .partition::<Vec<_>, _>(Result::is_ok);
-    let physical_device = suitable_devices
+        .enumerate()
+        .map(|(index, physical_device)| DeviceInfo::get(instance, physical_device, index))
+        .collect();
+    Ok(devices)
+}
+
+fn pick_physical_device(
+    instance: &ash::Instance,
+    target: PresentTarget,
+    preference: &DevicePreference,
+    color_space: OutputColorSpace,
+    present_mode: PresentMode,
+) -> VkResult<PhysicalDevice> {
+    let mut discarded_devices = Vec::new();
+    let mut selected: Option<((usize, usize), PhysicalDevice)> = None;
+    for (index, physical_device) in unsafe { instance.enumerate_physical_devices()? }
         .into_iter()
-        .next()
-        .ok_or_else(|| {
-            let discarded_devices = discarded_devices
-                .into_iter()
-                .map(|result| match result {
-                    Err(cause) => cause,
-                    Ok(..) => unreachable!(),
-                })
-                .collect();
-            VkError::NoSuitablePhysicalDevice(discarded_devices)
-        })?
-        .unwrap();
+        .enumerate()
+    {
+        let info = DeviceInfo::get(instance, physical_device, index);
+        let result = preference
+            .score(&info)
+            .ok_or(DeviceNotSuitable::RejectedByPreference)
+            .and_then(|score| {
+                check_physical_device_suitable(
+                    physical_device,
+                    instance,
+                    target,
+                    color_space,
+                    present_mode,
+                )
+                .map(|device| (score, device))
+            });
+        match result {
+            Ok((score, device)) => {
+                let score = (score, device.properties.enabled_feature_count());
+                // Earlier enumerated device is kept when scores are equal
+                if selected.as_ref().is_none_or(|(best, _)| score > *best) {
+                    selected = Some((score, device));
+                }
+            }
+            Err(cause) => discarded_devices.push((info.name, cause)),
+        }
+    }
+    let (_, physical_device) =
+        selected.ok_or(VkError::NoSuitablePhysicalDevice(discarded_devices))?;
     println!(
         "Using {} Physical Device",
         physical_device
@@ -391,7 +709,152 @@ fn pick_physical_device(instance: &ash::Instance, surface: &Surface) -> VkResult
 }
 
 impl Device {
-    pub fn wait_idle(&self) -> Result<(), Box<dyn Error>> {
+    pub fn max_sampler_anisotropy(&self) -> Option<f32> {
+        let properties = &self.physical_device.properties;
+        (properties.enabled_features.sampler_anisotropy == vk::TRUE)
+            .then_some(properties.generic.limits.max_sampler_anisotropy)
+    }
+
+    pub fn non_coherent_atom_size(&self) -> usize {
+        self.physical_device
+            .properties
+            .generic
+            .limits
+            .non_coherent_atom_size as usize
+    }
+
+    pub fn max_push_constants_size(&self) -> u32 {
+        self.physical_device
+            .properties
+            .generic
+            .limits
+            .max_push_constants_size
+    }
+
+    pub fn wireframe_supported(&self) -> bool {
+        self.physical_device
+            .properties
+            .enabled_features
+            .fill_mode_non_solid
+            == vk::TRUE
+    }
+
+    pub fn update_surface_properties(
+        &mut self,
+        surface: &Surface,
+        window_extent: vk::Extent2D,
+    ) -> VkResult<()> {
+        self.physical_device.surface_properties.update_capabilities(
+            surface,
+            self.physical_device.handle,
+            window_extent,
+        )
+    }
+
+    pub fn update_offscreen_extent(&mut self, extent: vk::Extent2D) {
+        self.physical_device
+            .surface_properties
+            .update_offscreen_extent(extent);
+    }
+
+    // Format of the swapchain images and of the combined G-Buffer attachment
+    #[inline]
+    pub fn output_format(&self) -> vk::Format {
+        self.physical_device.attachment_properties.formats.color
+    }
+
+    // Linear when the selected surface format does not encode the output to sRGB,
+    // also when sRGB was requested but no sRGB surface format is available
+    #[inline]
+    pub fn output_color_space(&self) -> OutputColorSpace {
+        OutputColorSpace::of_format(self.output_format())
+    }
+
+    // Mode selected for the primary surface, which may differ from the configured one
+    // when it is not supported, offscreen targets always report FIFO
+    #[inline]
+    pub fn present_mode(&self) -> PresentMode {
+        PresentMode::of_mode(self.physical_device.surface_properties.present_mode)
+    }
+
+    // Number of meaningful bits of timestamps written by the graphics queue,
+    // zero when the queue does not support timestamp queries
+    pub fn timestamp_valid_bits(&self) -> u32 {
+        let graphics = self.physical_device.queue_families.graphics;
+        self.physical_device
+            .properties
+            .queue_families
+            .iter()
+            .find(|&&(_, index)| index == graphics)
+            .map_or(0, |(properties, _)| properties.timestamp_valid_bits)
+    }
+
+    // Nanoseconds elapsed per timestamp increment
+    #[inline]
+    pub fn timestamp_period(&self) -> f32 {
+        self.physical_device
+            .properties
+            .generic
+            .limits
+            .timestamp_period
+    }
+
+    // Set when the configured sample count was replaced with a supported one
+    #[inline]
+    pub fn sample_count_fallback(&self) -> Option<SampleCountFallback> {
+        self.physical_device
+            .attachment_properties
+            .sample_count_fallback
+    }
+
+    #[inline]
+    pub fn surface_properties(&self) -> &PhysicalDeviceSurfaceProperties {
+        &self.physical_device.surface_properties
+    }
+
+    // Properties of additional window surface, rendering into it must be possible
+    // with the graphics queue and render passes created for the primary surface format
+    pub fn get_window_surface_properties(
+        &self,
+        surface: &Surface,
+        window_extent: vk::Extent2D,
+    ) -> VkResult<PhysicalDeviceSurfaceProperties> {
+        let mut properties = PhysicalDeviceSurfaceProperties::get(
+            surface,
+            self.physical_device.handle,
+            &self.physical_device.properties.queue_families,
+            self.output_color_space(),
+            self.present_mode(),
+        )
+        .map_err(|err| VkError::ConfigError(format!("Window surface unusable: {}", err)))?;
+        if !properties
+            .supported_queue_families
+            .contains(&self.physical_device.queue_families.graphics)
+        {
+            Err(VkError::ConfigError(
+                "Window surface not supported by graphics queue".to_string(),
+            ))?;
+        }
+        if properties.surface_format != self.physical_device.surface_properties.surface_format {
+            Err(VkError::ConfigError(format!(
+                "Window surface format {:?} differs from primary surface format",
+                properties.surface_format.format
+            )))?;
+        }
+        properties.update_capabilities(surface, self.physical_device.handle, window_extent)?;
+        Ok(properties)
+    }
+
+    pub fn update_window_surface_properties(
+        &self,
+        surface: &Surface,
+        properties: &mut PhysicalDeviceSurfaceProperties,
+        window_extent: vk::Extent2D,
+    ) -> VkResult<()> {
+        properties.update_capabilities(surface, self.physical_device.handle, window_extent)
+    }
+
+    pub fn wait_idle(&self) -> VkResult<()> {
         unsafe {
             self.device.device_wait_idle()?;
         }
@@ -399,32 +862,68 @@ impl Device {
     }
 }
 
+impl Device {
+    // Labels Vulkan object so validation messages reference it by name,
+    // no-op when validation is disabled
+    pub fn set_debug_name<H: vk::Handle>(&self, handle: H, name: &str) -> VkResult<()> {
+        if let Some(debug_utils) = &self.debug_utils {
+            let name = CString::new(name).unwrap_or_default();
+            let name_info = vk::DebugUtilsObjectNameInfoEXT::builder()
+                .object_type(H::TYPE)
+                .object_handle(handle.as_raw())
+                .object_name(&name);
+            unsafe {
+                debug_utils.set_debug_utils_object_name(self.device.handle(), &name_info)?;
+            }
+        }
+        Ok(())
+    }
+}
+
 impl Create for Device {
-    type Config<'a> = &'a Surface;
+    type Config<'a> = (PresentTarget<'a>, bool, &'a DeviceConfig);
     type CreateError = VkError;
 
     fn create<'a, 'b>(
         config: Self::Config<'a>,
         context: Self::Context<'b>,
     ) -> type_kit::CreateResult<Self> {
-        let physical_device = pick_physical_device(context, config)?;
+        let (target, debug_names, device_config) = config;
+        let mut physical_device = pick_physical_device(
+            context,
+            target,
+            &device_config.preference,
+            device_config.output_color_space,
+            device_config.present_mode,
+        )?;
+        let attachment_properties = &mut physical_device.attachment_properties;
+        (
+            attachment_properties.msaa_samples,
+            attachment_properties.sample_count_fallback,
+        ) = select_sample_count(
+            attachment_properties.supported_samples,
+            device_config.sample_count,
+        );
         let queue_builder = DeviceQueueBuilder::new(physical_device.queue_families);
-        let device = unsafe {
-            context.create_device(
-                physical_device.handle,
-                &vk::DeviceCreateInfo::builder()
-                    .queue_create_infos(&queue_builder.get_device_queue_create_infos())
-                    .enabled_extension_names(&physical_device.properties.enabled_extension_names)
-                    .enabled_features(&physical_device.properties.enabled_features),
-                None,
-            )?
-        };
+        let queue_create_infos = queue_builder.get_device_queue_create_infos();
+        let create_info = vk::DeviceCreateInfo::builder()
+            .queue_create_infos(&queue_create_infos)
+            .enabled_extension_names(&physical_device.properties.enabled_extension_names)
+            .enabled_features(&physical_device.properties.enabled_features);
+        let device = unsafe { context.create_device(physical_device.handle, &create_info, None)? };
         let device_queues = queue_builder.get_device_queues(&device);
         let command_pools = TransientCommandPools::create(&device, physical_device.queue_families)?;
+        let debug_utils = debug_names.then(|| context.load::<ext::DebugUtils>());
         Ok(Self {
             physical_device,
             command_pools,
             device_queues,
+            debug_utils,
+            tracker: ResourceTracker::default(),
+            stats: RenderStatsCounter::default(),
+            samplers: SamplerCache::new(device_config.sampler),
+            framebuffers: FramebufferCache::default(),
+            render_passes: RenderPassCache::default(),
             device,
         })
     }
@@ -435,6 +934,14 @@ impl Destroy for Device {
     type DestroyError = Infallible;
 
     fn destroy<'a>(&mut self, _context: Self::Context<'a>) -> DestroyResult<Self> {
+        // Owners release tracked resources before the device, panicking again
+        // while already unwinding would abort and hide the original failure
+        #[cfg(debug_assertions)]
+        if !std::thread::panicking() {
+            self.assert_no_leaks();
+        }
+        self.destroy_samplers();
+        self.destroy_framebuffers();
         self.destroy_render_passes();
         self.destroy_pipeline_layouts();
         self.destroy_descriptor_set_layouts();
@@ -445,3 +952,185 @@ impl Destroy for Device {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use command::{operation::Transfer, SubmitSemaphoreState};
+    use memory::{DefaultAllocator, DeviceLocal};
+    use resources::{
+        buffer::{Buffer, BufferBuilder, BufferInfo, BufferPartial},
+        PartialBuilder,
+    };
+
+    use crate::context::{Context, DebugConfig};
+
+    use super::*;
+
+    fn queue_family(queue_flags: vk::QueueFlags) -> vk::QueueFamilyProperties {
+        vk::QueueFamilyProperties {
+            queue_flags,
+            queue_count: 1,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_single_queue_family_shared_by_all_operations() {
+        let families = [(
+            queue_family(
+                vk::QueueFlags::GRAPHICS | vk::QueueFlags::COMPUTE | vk::QueueFlags::TRANSFER,
+            ),
+            0,
+        )];
+        let selected = QueueFamilies::select(&families, &HashSet::from([0])).unwrap();
+        assert_eq!(
+            (selected.graphics, selected.compute, selected.transfer),
+            (0, 0, 0)
+        );
+        // Colliding families are requested from the device only once
+        let infos = DeviceQueueBuilder::new(selected).get_device_queue_create_infos();
+        assert_eq!(infos.len(), 1);
+        assert_eq!(infos[0].queue_family_index, 0);
+    }
+
+    #[test]
+    fn test_transfer_falls_back_to_graphics_family() {
+        // Transfer support is implied by graphics and compute, even when not reported
+        let families = [
+            (queue_family(vk::QueueFlags::COMPUTE), 1),
+            (
+                queue_family(vk::QueueFlags::GRAPHICS | vk::QueueFlags::COMPUTE),
+                0,
+            ),
+        ];
+        let selected = QueueFamilies::select(&families, &HashSet::from([0])).unwrap();
+        assert_eq!(
+            (selected.graphics, selected.compute, selected.transfer),
+            (0, 1, 0)
+        );
+        assert_eq!(
+            DeviceQueueBuilder::new(selected)
+                .get_device_queue_create_infos()
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_dedicated_queue_families_preferred() {
+        // Families are ordered by the number of supported operations
+        let families = [
+            (queue_family(vk::QueueFlags::TRANSFER), 2),
+            (
+                queue_family(vk::QueueFlags::COMPUTE | vk::QueueFlags::TRANSFER),
+                1,
+            ),
+            (
+                queue_family(
+                    vk::QueueFlags::GRAPHICS | vk::QueueFlags::COMPUTE | vk::QueueFlags::TRANSFER,
+                ),
+                0,
+            ),
+        ];
+        let selected = QueueFamilies::select(&families, &HashSet::from([0])).unwrap();
+        assert_eq!(
+            (selected.graphics, selected.compute, selected.transfer),
+            (0, 1, 2)
+        );
+        // Graphics family has to support presentation to the target
+        assert!(matches!(
+            QueueFamilies::select(&families, &HashSet::from([1])),
+            Err(DeviceNotSuitable::MissingQueueFamilyIndex("Graphics"))
+        ));
+    }
+
+    #[test]
+    #[ignore = "requires Vulkan capable device"]
+    fn test_context_with_single_queue_family() {
+        SINGLE_QUEUE_FAMILY.set(true);
+        let context = Context::build_offscreen(
+            vk::Extent2D {
+                width: 64,
+                height: 64,
+            },
+            DebugConfig::default(),
+            &DeviceConfig::default(),
+        )
+        .unwrap();
+        SINGLE_QUEUE_FAMILY.set(false);
+        let QueueFamilies {
+            graphics,
+            compute,
+            transfer,
+        } = context.physical_device.queue_families;
+        assert_eq!((compute, transfer), (graphics, graphics));
+        assert_eq!(
+            context.device_queues.transfer,
+            context.device_queues.graphics
+        );
+
+        // Concurrent sharing between operations collapses to the single family
+        let info = BufferInfo {
+            size: 256,
+            usage: vk::BufferUsageFlags::TRANSFER_DST,
+            sharing_mode: vk::SharingMode::CONCURRENT,
+            queue_families: &[transfer, graphics],
+        };
+        let mut allocator = DefaultAllocator {};
+        let allocator = RefCell::new(&mut allocator);
+        let mut buffer = Buffer::<DeviceLocal, _>::create(
+            BufferPartial::prepare(BufferBuilder::new(info), &context).unwrap(),
+            (&context, &allocator),
+        )
+        .unwrap();
+        let command = context
+            .begin_primary_command(context.allocate_transient_command::<Transfer>().unwrap())
+            .unwrap();
+        // Shared transfer queue accepts submissions
+        let command = context
+            .submit_command(
+                context.finish_command(command).unwrap(),
+                SubmitSemaphoreState {
+                    semaphores: &[],
+                    masks: &[],
+                },
+                &[],
+            )
+            .unwrap()
+            .wait()
+            .unwrap();
+        context.free_command(command);
+        buffer.destroy((&context, &allocator)).unwrap();
+    }
+
+    #[test]
+    fn test_unsupported_sample_count_falls_back_to_supported() {
+        let supported = vk::SampleCountFlags::TYPE_1
+            | vk::SampleCountFlags::TYPE_2
+            | vk::SampleCountFlags::TYPE_4;
+        assert_eq!(
+            select_sample_count(supported, None),
+            (vk::SampleCountFlags::TYPE_4, None)
+        );
+        assert_eq!(
+            select_sample_count(supported, Some(vk::SampleCountFlags::TYPE_2)),
+            (vk::SampleCountFlags::TYPE_2, None)
+        );
+        for requested in [
+            vk::SampleCountFlags::TYPE_8,
+            vk::SampleCountFlags::from_raw(3),
+        ] {
+            let (selected, fallback) = select_sample_count(supported, Some(requested));
+            assert_eq!(selected, vk::SampleCountFlags::TYPE_4);
+            assert_eq!(
+                fallback,
+                Some(SampleCountFallback {
+                    requested,
+                    selected
+                })
+            );
+        }
+    }
+}
@@ -14,12 +14,18 @@ use self::{
 use super::{
     descriptor::DescriptorBindingData,
     framebuffer::{AttachmentList, Clear, FramebufferHandle},
-    memory::{Allocator, MemoryProperties},
-    pipeline::{GraphicsPipelineConfig, PipelineBindData, PushConstant, PushConstantDataRef},
+    memory::{Allocator, DefaultAllocator, MemoryProperties},
+    pipeline::{
+        GraphicsPipelineConfig, PipelineBindData, PipelineLayoutOverlay, PushConstant,
+        PushConstantDataRef,
+    },
+    profiler::GpuProfiler,
     render_pass::{RenderPass, RenderPassConfig, Subpass},
     resources::{
-        buffer::Buffer, image::Image2D, BufferType, LayoutSkybox, MeshPackBinding,
-        MeshRangeBindData, Skybox,
+        buffer::{Buffer, ByteRange},
+        image::Image2D,
+        BufferType, LayoutSkybox, MeshPackBinding, MeshRangeBindData, OverlayBindData, Skybox,
+        TextOverlay,
     },
     swapchain::SwapchainFrame,
     Device, QueueFamilies,
@@ -41,6 +47,8 @@ pub mod level {
 
         fn buffer(command: &Self::CommandData) -> vk::CommandBuffer;
 
+        fn persistent_buffers(allocator: &Self::PersistentAllocator) -> &[vk::CommandBuffer];
+
         fn create_persistent_allocator(
             device: &Device,
             command_pool: vk::CommandPool,
@@ -49,11 +57,24 @@ pub mod level {
 
         fn destory_persistent_alocator(device: &Device, allocator: &mut Self::PersistentAllocator);
 
+        fn wait_persistent_allocator(
+            device: &Device,
+            allocator: &Self::PersistentAllocator,
+        ) -> VkResult<()>;
+
         fn allocate_persistent_command_buffer(
             allocator: &mut Self::PersistentAllocator,
         ) -> (usize, Self::CommandData);
     }
 
+    // Returns current index of round-robin allocation and advances it,
+    // wrapping back to the first buffer after the last one was handed out
+    pub(super) fn next_persistent_index(index: &mut usize, len: usize) -> usize {
+        let current = *index;
+        *index = (current + 1) % len;
+        current
+    }
+
     pub struct PrimaryPersistenAllocator {
         index: usize,
         buffers: Vec<vk::CommandBuffer>,
@@ -73,8 +94,7 @@ pub mod level {
         fn allocate_persistent_command_buffer(
             allocator: &mut Self::PersistentAllocator,
         ) -> (usize, Self::CommandData) {
-            let index = allocator.index;
-            allocator.index = (allocator.index + 1) % allocator.buffers.len();
+            let index = next_persistent_index(&mut allocator.index, allocator.buffers.len());
             (
                 index,
                 Self {
@@ -126,11 +146,26 @@ pub mod level {
             }
         }
 
+        fn wait_persistent_allocator(
+            device: &Device,
+            allocator: &Self::PersistentAllocator,
+        ) -> VkResult<()> {
+            // Fences are left signaled, so following begin_primary_command does not block
+            unsafe { device.wait_for_fences(&allocator.fences, true, u64::MAX)? };
+            Ok(())
+        }
+
         fn buffer(command: &Self::CommandData) -> vk::CommandBuffer {
             command.buffer
         }
+
+        fn persistent_buffers(allocator: &Self::PersistentAllocator) -> &[vk::CommandBuffer] {
+            &allocator.buffers
+        }
     }
 
+    // Secondary buffers have no fences of their own, pool owning them may only be
+    // reset or resized after primary commands executing them have completed
     pub struct SecondaryPersistentAllocator {
         index: usize,
         buffers: Vec<vk::CommandBuffer>,
@@ -148,8 +183,7 @@ pub mod level {
         fn allocate_persistent_command_buffer(
             allocator: &mut Self::PersistentAllocator,
         ) -> (usize, Self::CommandData) {
-            let index = allocator.index;
-            allocator.index = (allocator.index + 1) % allocator.buffers.len();
+            let index = next_persistent_index(&mut allocator.index, allocator.buffers.len());
             (
                 index,
                 Self {
@@ -180,9 +214,21 @@ pub mod level {
             // Buffers are destroyed with the command pool
         }
 
+        fn wait_persistent_allocator(
+            _device: &Device,
+            _allocator: &Self::PersistentAllocator,
+        ) -> VkResult<()> {
+            // Secondary buffers are synchronized through primary buffers executing them
+            Ok(())
+        }
+
         fn buffer(command: &Self::CommandData) -> vk::CommandBuffer {
             command.buffer
         }
+
+        fn persistent_buffers(allocator: &Self::PersistentAllocator) -> &[vk::CommandBuffer] {
+            &allocator.buffers
+        }
     }
 }
 
@@ -222,8 +268,8 @@ pub mod operation {
         fn get_queue_family_index(device: &Device) -> u32 {
             device.physical_device.queue_families.compute
         }
-        fn get_transient_command_pool(_device: &Device) -> vk::CommandPool {
-            unimplemented!()
+        fn get_transient_command_pool(device: &Device) -> vk::CommandPool {
+            device.command_pools.compute
         }
     }
     impl Operation for Transfer {
@@ -259,6 +305,36 @@ impl<L: Level, O: Operation> PersistentCommandPool<L, O> {
         };
         (index, NewCommand(command))
     }
+
+    pub fn len(&self) -> usize {
+        L::persistent_buffers(&self.allocator).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Waits for all pending submissions and resets every buffer in the pool,
+    // previously recorded commands are invalid afterwards
+    pub fn reset_all(&self, device: &Device) -> VkResult<()> {
+        L::wait_persistent_allocator(device, &self.allocator)?;
+        unsafe {
+            device.reset_command_pool(self.command_pool, vk::CommandPoolResetFlags::empty())?;
+        }
+        Ok(())
+    }
+
+    // Waits for all pending submissions and replaces pool buffers with `new_size`
+    // freshly allocated ones, round-robin allocation starts again from the first
+    pub fn resize(&mut self, device: &Device, new_size: usize) -> VkResult<()> {
+        L::wait_persistent_allocator(device, &self.allocator)?;
+        L::destory_persistent_alocator(device, &mut self.allocator);
+        unsafe {
+            device.free_command_buffers(self.command_pool, L::persistent_buffers(&self.allocator));
+        }
+        self.allocator = L::create_persistent_allocator(device, self.command_pool, new_size)?;
+        Ok(())
+    }
 }
 
 impl<L: Level, O: Operation> Create for PersistentCommandPool<L, O> {
@@ -274,6 +350,9 @@ impl<L: Level, O: Operation> Create for PersistentCommandPool<L, O> {
                 None,
             )?
         };
+        context
+            .tracker()
+            .register(command_pool, "PersistentCommandPool");
         let allocator = L::create_persistent_allocator(context, command_pool, config)?;
         Ok(PersistentCommandPool {
             command_pool,
@@ -289,6 +368,7 @@ impl<L: Level, O: Operation> Destroy for PersistentCommandPool<L, O> {
 
     fn destroy<'a>(&mut self, context: Self::Context<'a>) -> DestroyResult<Self> {
         L::destory_persistent_alocator(context, &mut self.allocator);
+        context.tracker().unregister(self.command_pool);
         unsafe {
             context.destroy_command_pool(self.command_pool, None);
         }
@@ -336,10 +416,46 @@ impl Device {
                         ..Default::default()
                     }),
             )?;
+            // Viewport is dynamic state of every graphics pipeline,
+            // secondary commands do not inherit it from the primary one
+            let vk::Extent2D { width, height } = framebuffer.extent;
+            self.device.cmd_set_viewport(
+                Secondary::buffer(&command.data),
+                0,
+                &[vk::Viewport {
+                    x: 0.0,
+                    y: 0.0,
+                    width: width as f32,
+                    height: height as f32,
+                    min_depth: 0.0,
+                    max_depth: 1.0,
+                }],
+            );
+            self.device.cmd_set_scissor(
+                Secondary::buffer(&command.data),
+                0,
+                &[vk::Rect2D {
+                    offset: vk::Offset2D { x: 0, y: 0 },
+                    extent: framebuffer.extent,
+                }],
+            );
         }
         Ok(BeginCommand(command))
     }
 
+    // Waits until the previous submission of the command has finished, its fence
+    // is left signaled, so the command may still be dropped without being submitted
+    pub fn wait_primary_command<T, O: Operation>(
+        &self,
+        command: &NewCommand<T, Primary, O>,
+    ) -> VkResult<()> {
+        unsafe {
+            self.device
+                .wait_for_fences(&[command.0.data.fence], true, u64::MAX)?;
+        }
+        Ok(())
+    }
+
     pub fn begin_primary_command<T, O: Operation>(
         &self,
         command: NewCommand<T, Primary, O>,
@@ -358,6 +474,12 @@ impl Device {
         Ok(BeginCommand(command))
     }
 
+    pub fn begin_compute_command(
+        &self,
+    ) -> VkResult<BeginCommand<Transient, Primary, operation::Compute>> {
+        self.begin_primary_command(self.allocate_transient_command()?)
+    }
+
     pub fn record_command<
         T,
         L: Level,
@@ -403,9 +525,29 @@ impl<'a, T, L: Level, O: Operation> From<&'a BeginCommand<T, L, O>> for &'a Comm
     }
 }
 
+// Pipeline stage and access of the image in `layout` synchronized by layout transitions,
+// images in GENERAL layout are expected to be accessed by compute shaders
+fn layout_stage_access(layout: vk::ImageLayout) -> (vk::PipelineStageFlags, vk::AccessFlags) {
+    match layout {
+        vk::ImageLayout::GENERAL => (
+            vk::PipelineStageFlags::COMPUTE_SHADER,
+            vk::AccessFlags::SHADER_READ | vk::AccessFlags::SHADER_WRITE,
+        ),
+        vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL => (
+            vk::PipelineStageFlags::FRAGMENT_SHADER | vk::PipelineStageFlags::COMPUTE_SHADER,
+            vk::AccessFlags::SHADER_READ,
+        ),
+        _ => (
+            vk::PipelineStageFlags::TRANSFER,
+            vk::AccessFlags::TRANSFER_READ | vk::AccessFlags::TRANSFER_WRITE,
+        ),
+    }
+}
+
 impl<'a, T, L: Level, O: Operation> RecordingCommand<'a, T, L, O> {
     pub fn next_render_pass(self) -> Self {
         let RecordingCommand(command, device) = self;
+        device.stats().begin_pass();
         unsafe {
             device.cmd_next_subpass(
                 L::buffer(&command.data),
@@ -418,6 +560,7 @@ impl<'a, T, L: Level, O: Operation> RecordingCommand<'a, T, L, O> {
     pub fn write_secondary(self, secondary: &FinishedCommand<T, Secondary, O>) -> Self {
         let FinishedCommand(secondary) = secondary;
         let RecordingCommand(command, device) = self;
+        device.stats().secondary_command();
         unsafe {
             device.cmd_execute_commands(
                 L::buffer(&command.data),
@@ -464,19 +607,19 @@ impl<'a, T, L: Level, O: Operation> RecordingCommand<'a, T, L, O> {
             base_level + level_count <= image.mip_levels,
             "Image mip level count exceeded!"
         );
+        let (src_stage, src_access_mask) = layout_stage_access(old_layout);
+        let (dst_stage, dst_access_mask) = layout_stage_access(new_layout);
         unsafe {
             device.cmd_pipeline_barrier(
                 L::buffer(&command.data),
-                vk::PipelineStageFlags::TRANSFER,
-                vk::PipelineStageFlags::TRANSFER,
+                src_stage,
+                dst_stage,
                 vk::DependencyFlags::BY_REGION,
                 &[],
                 &[],
                 &[vk::ImageMemoryBarrier {
-                    src_access_mask: vk::AccessFlags::TRANSFER_READ
-                        | vk::AccessFlags::TRANSFER_WRITE,
-                    dst_access_mask: vk::AccessFlags::TRANSFER_READ
-                        | vk::AccessFlags::TRANSFER_WRITE,
+                    src_access_mask,
+                    dst_access_mask,
                     old_layout,
                     new_layout,
                     src_queue_family_index: O::get_queue_family_index(device),
@@ -499,29 +642,205 @@ impl<'a, T, L: Level, O: Operation> RecordingCommand<'a, T, L, O> {
         RecordingCommand(command, device)
     }
 
+    // Blits presentable image left in PRESENT_SRC_KHR layout by the render pass
+    // into `dst` in TRANSFER_DST_OPTIMAL layout, converting between their formats.
+    // Presentable image is returned to the state the render pass left it in,
+    // so that commands handling render pass output can follow
+    pub fn blit_presentable_image_to_image<'b, M: MemoryProperties, A: Allocator>(
+        self,
+        image: vk::Image,
+        extent: vk::Extent2D,
+        dst: impl Into<&'b mut Image2D<M, A>>,
+    ) -> Self {
+        let dst = dst.into();
+        self.presentable_image_barrier(
+            image,
+            (
+                vk::ImageLayout::PRESENT_SRC_KHR,
+                vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+            ),
+            (
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::AccessFlags::TRANSFER_READ,
+            ),
+        )
+        .blit_image(
+            (image, vk::ImageLayout::TRANSFER_SRC_OPTIMAL),
+            (dst.image, vk::ImageLayout::TRANSFER_DST_OPTIMAL),
+            extent,
+        )
+        .presentable_image_barrier(
+            image,
+            (
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::AccessFlags::TRANSFER_READ,
+            ),
+            (
+                vk::ImageLayout::PRESENT_SRC_KHR,
+                vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                vk::AccessFlags::empty(),
+            ),
+        )
+    }
+
+    // Counterpart of `blit_presentable_image_to_image`, `src` is expected
+    // in TRANSFER_SRC_OPTIMAL layout
+    pub fn blit_image_to_presentable_image<'b, M: MemoryProperties, A: Allocator>(
+        self,
+        src: impl Into<&'b Image2D<M, A>>,
+        image: vk::Image,
+        extent: vk::Extent2D,
+    ) -> Self {
+        let src = src.into();
+        self.presentable_image_barrier(
+            image,
+            (
+                vk::ImageLayout::PRESENT_SRC_KHR,
+                vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+            ),
+            (
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::AccessFlags::TRANSFER_WRITE,
+            ),
+        )
+        .blit_image(
+            (src.image, vk::ImageLayout::TRANSFER_SRC_OPTIMAL),
+            (image, vk::ImageLayout::TRANSFER_DST_OPTIMAL),
+            extent,
+        )
+        .presentable_image_barrier(
+            image,
+            (
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::AccessFlags::TRANSFER_WRITE,
+            ),
+            (
+                vk::ImageLayout::PRESENT_SRC_KHR,
+                vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                vk::AccessFlags::empty(),
+            ),
+        )
+    }
+
+    fn presentable_image_barrier(
+        self,
+        image: vk::Image,
+        (old_layout, src_stage, src_access_mask): (
+            vk::ImageLayout,
+            vk::PipelineStageFlags,
+            vk::AccessFlags,
+        ),
+        (new_layout, dst_stage, dst_access_mask): (
+            vk::ImageLayout,
+            vk::PipelineStageFlags,
+            vk::AccessFlags,
+        ),
+    ) -> Self {
+        let RecordingCommand(command, device) = self;
+        unsafe {
+            device.cmd_pipeline_barrier(
+                L::buffer(&command.data),
+                src_stage,
+                dst_stage,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                &[vk::ImageMemoryBarrier {
+                    src_access_mask,
+                    dst_access_mask,
+                    old_layout,
+                    new_layout,
+                    src_queue_family_index: O::get_queue_family_index(device),
+                    dst_queue_family_index: O::get_queue_family_index(device),
+                    image,
+                    subresource_range: vk::ImageSubresourceRange {
+                        aspect_mask: vk::ImageAspectFlags::COLOR,
+                        base_mip_level: 0,
+                        level_count: 1,
+                        base_array_layer: 0,
+                        layer_count: 1,
+                    },
+                    ..Default::default()
+                }],
+            );
+        }
+        RecordingCommand(command, device)
+    }
+
+    fn blit_image(
+        self,
+        (src, src_layout): (vk::Image, vk::ImageLayout),
+        (dst, dst_layout): (vk::Image, vk::ImageLayout),
+        extent: vk::Extent2D,
+    ) -> Self {
+        let RecordingCommand(command, device) = self;
+        let subresource = vk::ImageSubresourceLayers {
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            mip_level: 0,
+            base_array_layer: 0,
+            layer_count: 1,
+        };
+        let offsets = [
+            vk::Offset3D { x: 0, y: 0, z: 0 },
+            vk::Offset3D {
+                x: extent.width as i32,
+                y: extent.height as i32,
+                z: 1,
+            },
+        ];
+        unsafe {
+            device.cmd_blit_image(
+                L::buffer(&command.data),
+                src,
+                src_layout,
+                dst,
+                dst_layout,
+                &[vk::ImageBlit {
+                    src_subresource: subresource,
+                    src_offsets: offsets,
+                    dst_subresource: subresource,
+                    dst_offsets: offsets,
+                }],
+                vk::Filter::NEAREST,
+            );
+        }
+        RecordingCommand(command, device)
+    }
+
     pub fn generate_mip<'b, 'c, M: MemoryProperties, A: Allocator>(
         self,
         image: impl Into<&'c mut Image2D<M, A>>,
         array_layer: u32,
+        base_layout: vk::ImageLayout,
     ) -> Self {
         let image = image.into();
-        let image_mip_levels = image.mip_levels;
-        // debug_assert!(
-        //     image.layout == vk::ImageLayout::TRANSFER_DST_OPTIMAL,
-        //     "Invalid image layout for mip levels generation!"
-        // );
-        (1..image_mip_levels)
-            .fold(self, |command, level| {
-                command.generate_mip_level(image.image, image.extent, level, array_layer)
-            })
-            .change_layout(
-                image,
-                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+        debug_assert!(
+            base_layout != vk::ImageLayout::UNDEFINED,
+            "Invalid base level layout for mip levels generation!"
+        );
+        let command = if base_layout != vk::ImageLayout::TRANSFER_SRC_OPTIMAL {
+            self.change_layout(
+                &mut *image,
+                base_layout,
                 vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
                 array_layer,
-                image_mip_levels - 1,
+                0,
                 1,
             )
+        } else {
+            self
+        };
+        // Each generated level is left in TRANSFER_SRC_OPTIMAL layout,
+        // so that all mip levels share the same layout afterwards
+        (1..image.mip_levels).fold(command, |command, level| {
+            command.generate_mip_level(image.image, image.extent, level, array_layer)
+        })
     }
 
     fn generate_mip_level(
@@ -542,31 +861,6 @@ impl<'a, T, L: Level, O: Operation> RecordingCommand<'a, T, L, O> {
         };
         let RecordingCommand(command, device) = self;
         unsafe {
-            device.cmd_pipeline_barrier(
-                L::buffer(&command.data),
-                vk::PipelineStageFlags::TRANSFER,
-                vk::PipelineStageFlags::TRANSFER,
-                vk::DependencyFlags::BY_REGION,
-                &[],
-                &[],
-                &[vk::ImageMemoryBarrier {
-                    src_access_mask: vk::AccessFlags::TRANSFER_WRITE,
-                    dst_access_mask: vk::AccessFlags::TRANSFER_READ,
-                    old_layout: vk::ImageLayout::TRANSFER_DST_OPTIMAL,
-                    new_layout: vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
-                    src_queue_family_index: O::get_queue_family_index(device),
-                    dst_queue_family_index: O::get_queue_family_index(device),
-                    image,
-                    subresource_range: vk::ImageSubresourceRange {
-                        aspect_mask: vk::ImageAspectFlags::COLOR,
-                        base_mip_level: level - 1,
-                        level_count: 1,
-                        base_array_layer: layer,
-                        layer_count: 1,
-                    },
-                    ..Default::default()
-                }],
-            );
             device.cmd_pipeline_barrier(
                 L::buffer(&command.data),
                 vk::PipelineStageFlags::TRANSFER,
@@ -630,6 +924,31 @@ impl<'a, T, L: Level, O: Operation> RecordingCommand<'a, T, L, O> {
                 }],
                 vk::Filter::LINEAR,
             );
+            device.cmd_pipeline_barrier(
+                L::buffer(&command.data),
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::DependencyFlags::BY_REGION,
+                &[],
+                &[],
+                &[vk::ImageMemoryBarrier {
+                    src_access_mask: vk::AccessFlags::TRANSFER_WRITE,
+                    dst_access_mask: vk::AccessFlags::TRANSFER_READ,
+                    old_layout: vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    new_layout: vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                    src_queue_family_index: O::get_queue_family_index(device),
+                    dst_queue_family_index: O::get_queue_family_index(device),
+                    image,
+                    subresource_range: vk::ImageSubresourceRange {
+                        aspect_mask: vk::ImageAspectFlags::COLOR,
+                        base_mip_level: level,
+                        level_count: 1,
+                        base_array_layer: layer,
+                        layer_count: 1,
+                    },
+                    ..Default::default()
+                }],
+            );
         }
         RecordingCommand(command, device)
     }
@@ -678,21 +997,173 @@ impl<'a, T, L: Level, O: Operation> RecordingCommand<'a, T, L, O> {
         RecordingCommand(command, device)
     }
 
+    pub fn copy_image_to_buffer<
+        'b,
+        'c,
+        S: MemoryProperties,
+        D: MemoryProperties,
+        A1: Allocator,
+        A2: Allocator,
+    >(
+        self,
+        src: impl Into<&'b Image2D<S, A1>>,
+        dst: impl Into<&'c mut Buffer<D, A2>>,
+        src_layer: u32,
+        src_mip_level: u32,
+    ) -> Self {
+        let RecordingCommand(command, device) = self;
+        let src = src.into();
+        let dst = dst.into();
+        let extent = src.mip_level_extent(src_mip_level);
+        unsafe {
+            device.cmd_copy_image_to_buffer(
+                L::buffer(&command.data),
+                src.image,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                dst.handle(),
+                &[vk::BufferImageCopy {
+                    buffer_offset: 0,
+                    buffer_row_length: 0,
+                    buffer_image_height: 0,
+                    image_subresource: vk::ImageSubresourceLayers {
+                        aspect_mask: vk::ImageAspectFlags::COLOR,
+                        mip_level: src_mip_level,
+                        base_array_layer: src_layer,
+                        layer_count: 1,
+                    },
+                    image_offset: vk::Offset3D { x: 0, y: 0, z: 0 },
+                    image_extent: vk::Extent3D {
+                        width: extent.width,
+                        height: extent.height,
+                        depth: 1,
+                    },
+                }],
+            );
+        }
+        RecordingCommand(command, device)
+    }
+
+    // Copies presentable image left in PRESENT_SRC_KHR layout by the render pass
+    // into tightly packed buffer rows, making the copied data visible to the host
+    pub fn copy_presentable_image_to_buffer<'b, M: MemoryProperties, A: Allocator>(
+        self,
+        image: vk::Image,
+        extent: vk::Extent2D,
+        dst: impl Into<&'b mut Buffer<M, A>>,
+    ) -> Self {
+        let RecordingCommand(command, device) = self;
+        let dst = dst.into();
+        let subresource_range = vk::ImageSubresourceRange {
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            base_mip_level: 0,
+            level_count: 1,
+            base_array_layer: 0,
+            layer_count: 1,
+        };
+        unsafe {
+            device.cmd_pipeline_barrier(
+                L::buffer(&command.data),
+                vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                &[vk::ImageMemoryBarrier {
+                    src_access_mask: vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+                    dst_access_mask: vk::AccessFlags::TRANSFER_READ,
+                    old_layout: vk::ImageLayout::PRESENT_SRC_KHR,
+                    new_layout: vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                    src_queue_family_index: O::get_queue_family_index(device),
+                    dst_queue_family_index: O::get_queue_family_index(device),
+                    image,
+                    subresource_range,
+                    ..Default::default()
+                }],
+            );
+            device.cmd_copy_image_to_buffer(
+                L::buffer(&command.data),
+                image,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                dst.handle(),
+                &[vk::BufferImageCopy {
+                    buffer_offset: 0,
+                    buffer_row_length: 0,
+                    buffer_image_height: 0,
+                    image_subresource: vk::ImageSubresourceLayers {
+                        aspect_mask: vk::ImageAspectFlags::COLOR,
+                        mip_level: 0,
+                        base_array_layer: 0,
+                        layer_count: 1,
+                    },
+                    image_offset: vk::Offset3D { x: 0, y: 0, z: 0 },
+                    image_extent: vk::Extent3D {
+                        width: extent.width,
+                        height: extent.height,
+                        depth: 1,
+                    },
+                }],
+            );
+            device.cmd_pipeline_barrier(
+                L::buffer(&command.data),
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::HOST | vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                vk::DependencyFlags::empty(),
+                &[vk::MemoryBarrier {
+                    src_access_mask: vk::AccessFlags::TRANSFER_WRITE,
+                    dst_access_mask: vk::AccessFlags::HOST_READ,
+                    ..Default::default()
+                }],
+                &[],
+                &[vk::ImageMemoryBarrier {
+                    src_access_mask: vk::AccessFlags::TRANSFER_READ,
+                    dst_access_mask: vk::AccessFlags::empty(),
+                    old_layout: vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                    new_layout: vk::ImageLayout::PRESENT_SRC_KHR,
+                    src_queue_family_index: O::get_queue_family_index(device),
+                    dst_queue_family_index: O::get_queue_family_index(device),
+                    image,
+                    subresource_range,
+                    ..Default::default()
+                }],
+            );
+        }
+        RecordingCommand(command, device)
+    }
+
     pub fn begin_render_pass<A: AttachmentList, C: RenderPassConfig<Attachments = A>>(
         self,
         frame: &SwapchainFrame<A>,
         render_pass: &RenderPass<C>,
         clear_values: &Clear<C::Attachments>,
+    ) -> Self {
+        self.begin_render_pass_with_framebuffer(
+            frame.framebuffer,
+            frame.render_area,
+            render_pass,
+            clear_values,
+        )
+    }
+
+    pub fn begin_render_pass_with_framebuffer<
+        A: AttachmentList,
+        C: RenderPassConfig<Attachments = A>,
+    >(
+        self,
+        framebuffer: FramebufferHandle<A>,
+        render_area: vk::Rect2D,
+        render_pass: &RenderPass<C>,
+        clear_values: &Clear<C::Attachments>,
     ) -> Self {
         let RecordingCommand(command, device) = self;
         let clear_values = clear_values.get_clear_values();
+        device.stats().begin_pass();
         unsafe {
             device.cmd_begin_render_pass(
                 L::buffer(&command.data),
                 &vk::RenderPassBeginInfo {
                     render_pass: render_pass.handle,
-                    framebuffer: frame.framebuffer.framebuffer,
-                    render_area: frame.render_area,
+                    framebuffer: framebuffer.framebuffer,
+                    render_area,
                     clear_value_count: clear_values.len() as u32,
                     p_clear_values: clear_values.as_ptr(),
                     ..Default::default()
@@ -711,9 +1182,234 @@ impl<'a, T, L: Level, O: Operation> RecordingCommand<'a, T, L, O> {
         RecordingCommand(command, device)
     }
 
+    pub fn memory_barrier(
+        self,
+        src_stage: vk::PipelineStageFlags,
+        src_access: vk::AccessFlags,
+        dst_stage: vk::PipelineStageFlags,
+        dst_access: vk::AccessFlags,
+    ) -> Self {
+        let RecordingCommand(command, device) = self;
+        unsafe {
+            device.cmd_pipeline_barrier(
+                L::buffer(&command.data),
+                src_stage,
+                dst_stage,
+                vk::DependencyFlags::empty(),
+                &[vk::MemoryBarrier {
+                    src_access_mask: src_access,
+                    dst_access_mask: dst_access,
+                    ..Default::default()
+                }],
+                &[],
+                &[],
+            );
+        }
+        RecordingCommand(command, device)
+    }
+
+    // Makes depth attachment writes wait for earlier fragment shader reads of `image`,
+    // previous contents are discarded as the attachment is cleared on load
+    pub fn depth_attachment_barrier<M: MemoryProperties, A: Allocator>(
+        self,
+        image: &Image2D<M, A>,
+    ) -> Self {
+        let RecordingCommand(command, device) = self;
+        unsafe {
+            device.cmd_pipeline_barrier(
+                L::buffer(&command.data),
+                vk::PipelineStageFlags::FRAGMENT_SHADER,
+                vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS
+                    | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                &[vk::ImageMemoryBarrier {
+                    src_access_mask: vk::AccessFlags::SHADER_READ,
+                    dst_access_mask: vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_READ
+                        | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+                    old_layout: vk::ImageLayout::UNDEFINED,
+                    new_layout: vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+                    src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+                    dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+                    image: image.image,
+                    subresource_range: vk::ImageSubresourceRange {
+                        aspect_mask: vk::ImageAspectFlags::DEPTH,
+                        base_mip_level: 0,
+                        level_count: image.mip_levels,
+                        base_array_layer: 0,
+                        layer_count: image.array_layers,
+                    },
+                    ..Default::default()
+                }],
+            );
+        }
+        RecordingCommand(command, device)
+    }
+
+    // Has to be recorded outside of the render pass
+    pub fn reset_queries(self, profiler: &GpuProfiler) -> Self {
+        let RecordingCommand(command, device) = self;
+        unsafe {
+            device.cmd_reset_query_pool(
+                L::buffer(&command.data),
+                profiler.query_pool(),
+                0,
+                profiler.query_count(),
+            );
+        }
+        RecordingCommand(command, device)
+    }
+
+    // Timestamp is written once all previously submitted commands reach `stage`,
+    // `TOP_OF_PIPE` marks the beginning and `BOTTOM_OF_PIPE` the end of the work
+    pub fn write_timestamp(
+        self,
+        profiler: &GpuProfiler,
+        query_index: u32,
+        stage: vk::PipelineStageFlags,
+    ) -> Self {
+        debug_assert!(
+            query_index < profiler.query_count(),
+            "Timestamp query index out of range!"
+        );
+        let RecordingCommand(command, device) = self;
+        unsafe {
+            device.cmd_write_timestamp(
+                L::buffer(&command.data),
+                stage,
+                profiler.query_pool(),
+                query_index,
+            );
+        }
+        RecordingCommand(command, device)
+    }
+
+    pub fn buffer_barrier<'b, M: MemoryProperties, A: Allocator>(
+        self,
+        buffer: impl Into<&'b Buffer<M, A>>,
+        src_stage: vk::PipelineStageFlags,
+        dst_stage: vk::PipelineStageFlags,
+        src_access: vk::AccessFlags,
+        dst_access: vk::AccessFlags,
+        range: ByteRange,
+    ) -> Self {
+        let buffer = buffer.into();
+        let RecordingCommand(command, device) = self;
+        let barrier = buffer_range_barrier(
+            buffer.handle(),
+            O::get_queue_family_index(device),
+            (src_access, dst_access),
+            range,
+        );
+        unsafe {
+            device.cmd_pipeline_barrier(
+                L::buffer(&command.data),
+                src_stage,
+                dst_stage,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[barrier],
+                &[],
+            );
+        }
+        RecordingCommand(command, device)
+    }
+
+    // Transfers buffer ownership from `S` to `D` queue family, has to be recorded
+    // both into releasing command on `S` queue and acquiring command on `D` queue,
+    // with submissions ordered by semaphore, see `Device::requires_queue_transfer`.
+    // When both operations share queue family it acts as regular buffer barrier
+    pub fn queue_transfer_barrier<
+        'b,
+        S: Operation,
+        D: Operation,
+        M: MemoryProperties,
+        A: Allocator,
+    >(
+        self,
+        buffer: impl Into<&'b Buffer<M, A>>,
+        src_stage: vk::PipelineStageFlags,
+        dst_stage: vk::PipelineStageFlags,
+        src_access: vk::AccessFlags,
+        dst_access: vk::AccessFlags,
+        range: ByteRange,
+    ) -> Self {
+        let buffer = buffer.into();
+        let RecordingCommand(command, device) = self;
+        unsafe {
+            device.cmd_pipeline_barrier(
+                L::buffer(&command.data),
+                src_stage,
+                dst_stage,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[vk::BufferMemoryBarrier {
+                    src_access_mask: src_access,
+                    dst_access_mask: dst_access,
+                    src_queue_family_index: S::get_queue_family_index(device),
+                    dst_queue_family_index: D::get_queue_family_index(device),
+                    buffer: buffer.handle(),
+                    offset: range.beg as vk::DeviceSize,
+                    size: range.len() as vk::DeviceSize,
+                    ..Default::default()
+                }],
+                &[],
+            );
+        }
+        RecordingCommand(command, device)
+    }
+
+    // Makes storage buffer written by compute shader visible as vertex input
+    // of the following draw commands recorded on the same queue
+    pub fn compute_to_vertex_barrier<'b, M: MemoryProperties, A: Allocator>(
+        self,
+        buffer: impl Into<&'b Buffer<M, A>>,
+        range: ByteRange,
+    ) -> Self {
+        self.buffer_barrier(
+            buffer,
+            vk::PipelineStageFlags::COMPUTE_SHADER,
+            vk::PipelineStageFlags::VERTEX_INPUT,
+            vk::AccessFlags::SHADER_WRITE,
+            vk::AccessFlags::VERTEX_ATTRIBUTE_READ,
+            range,
+        )
+    }
+
+    // Viewport and scissor are dynamic states of every graphics pipeline,
+    // both are set to the full framebuffer when secondary command begins
+    pub fn set_viewport(self, area: vk::Rect2D) -> Self {
+        let RecordingCommand(command, device) = self;
+        unsafe {
+            device.cmd_set_viewport(
+                L::buffer(&command.data),
+                0,
+                &[vk::Viewport {
+                    x: area.offset.x as f32,
+                    y: area.offset.y as f32,
+                    width: area.extent.width as f32,
+                    height: area.extent.height as f32,
+                    min_depth: 0.0,
+                    max_depth: 1.0,
+                }],
+            );
+        }
+        RecordingCommand(command, device)
+    }
+
+    pub fn set_scissor(self, area: vk::Rect2D) -> Self {
+        let RecordingCommand(command, device) = self;
+        unsafe {
+            device.cmd_set_scissor(L::buffer(&command.data), 0, &[area]);
+        }
+        RecordingCommand(command, device)
+    }
+
     pub fn bind_pipeline(self, pipeline: impl Into<PipelineBindData>) -> Self {
         let binding = pipeline.into();
         let RecordingCommand(command, device) = self;
+        device.stats().pipeline_bind();
         unsafe {
             device.cmd_bind_pipeline(
                 L::buffer(&command.data),
@@ -728,12 +1424,6 @@ impl<'a, T, L: Level, O: Operation> RecordingCommand<'a, T, L, O> {
         let pack = pack.into();
         let RecordingCommand(command, device) = self;
         unsafe {
-            device.cmd_bind_index_buffer(
-                L::buffer(&command.data),
-                pack.buffer,
-                pack.buffer_ranges[BufferType::Index].beg as vk::DeviceSize,
-                vk::IndexType::UINT32,
-            );
             device.cmd_bind_vertex_buffers(
                 L::buffer(&command.data),
                 0,
@@ -763,6 +1453,37 @@ impl<'a, T, L: Level, O: Operation> RecordingCommand<'a, T, L, O> {
             .draw_mesh(skybox.mesh_pack.get(0))
     }
 
+    pub fn draw_overlay<
+        C: GraphicsPipelineConfig<Layout = PipelineLayoutOverlay<DefaultAllocator>>,
+    >(
+        self,
+        overlay: &TextOverlay<C>,
+        vertices: OverlayBindData,
+    ) -> Self {
+        if vertices.vertex_count == 0 {
+            return self;
+        }
+        let RecordingCommand(command, device) =
+            self.bind_pipeline(&*overlay.pipeline).bind_descriptor_set(
+                &overlay
+                    .descriptor
+                    .get(0)
+                    .get_binding_data(&overlay.pipeline)
+                    .unwrap(),
+            );
+        unsafe {
+            device.cmd_bind_vertex_buffers(
+                L::buffer(&command.data),
+                0,
+                &[vertices.buffer],
+                &[vertices.offset],
+            );
+            device.cmd_draw(L::buffer(&command.data), vertices.vertex_count, 1, 0, 0);
+            device.stats().draw(vertices.vertex_count, 1);
+        }
+        RecordingCommand(command, device)
+    }
+
     pub fn push_constants<'b, P: PushConstant + Pod>(
         self,
         push_constant: impl Into<PushConstantDataRef<'b, P>>,
@@ -784,10 +1505,11 @@ impl<'a, T, L: Level, O: Operation> RecordingCommand<'a, T, L, O> {
     pub fn bind_descriptor_set<'b>(self, descriptor: impl Into<&'b DescriptorBindingData>) -> Self {
         let binding = descriptor.into();
         let RecordingCommand(command, device) = self;
+        device.stats().descriptor_bind();
         unsafe {
             device.cmd_bind_descriptor_sets(
                 L::buffer(&command.data),
-                vk::PipelineBindPoint::GRAPHICS,
+                binding.bind_point,
                 binding.pipeline_layout,
                 binding.set_index,
                 &[binding.set],
@@ -797,23 +1519,83 @@ impl<'a, T, L: Level, O: Operation> RecordingCommand<'a, T, L, O> {
         RecordingCommand(command, device)
     }
 
+    pub fn dispatch(self, x: u32, y: u32, z: u32) -> Self {
+        let RecordingCommand(command, device) = self;
+        unsafe {
+            device.cmd_dispatch(L::buffer(&command.data), x, y, z);
+        }
+        RecordingCommand(command, device)
+    }
+
     pub fn draw_mesh(self, mesh: impl Into<MeshRangeBindData>) -> Self {
+        self.draw_mesh_instanced(mesh, 1, 0)
+    }
+
+    pub fn draw_mesh_instanced(
+        self,
+        mesh: impl Into<MeshRangeBindData>,
+        instance_count: u32,
+        first_instance: u32,
+    ) -> Self {
         let binding = mesh.into();
+        let draw = draw_indexed_command(&binding, instance_count, first_instance);
         let RecordingCommand(command, device) = self;
+        device.stats().draw(draw.index_count, draw.instance_count);
         unsafe {
+            device.cmd_bind_index_buffer(
+                L::buffer(&command.data),
+                binding.index_buffer,
+                binding.index_buffer_offset,
+                binding.index_type,
+            );
             device.cmd_draw_indexed(
                 L::buffer(&command.data),
-                binding.index_count,
-                1,
-                binding.index_offset,
-                binding.vertex_offset,
-                0,
+                draw.index_count,
+                draw.instance_count,
+                draw.first_index,
+                draw.vertex_offset,
+                draw.first_instance,
             )
         }
         RecordingCommand(command, device)
     }
 }
 
+// Barrier over `range` of the buffer without queue family ownership transfer
+fn buffer_range_barrier(
+    buffer: vk::Buffer,
+    queue_family_index: u32,
+    (src_access, dst_access): (vk::AccessFlags, vk::AccessFlags),
+    range: ByteRange,
+) -> vk::BufferMemoryBarrier {
+    vk::BufferMemoryBarrier {
+        src_access_mask: src_access,
+        dst_access_mask: dst_access,
+        src_queue_family_index: queue_family_index,
+        dst_queue_family_index: queue_family_index,
+        buffer,
+        offset: range.beg as vk::DeviceSize,
+        size: range.len() as vk::DeviceSize,
+        ..Default::default()
+    }
+}
+
+// Arguments of indexed draw of the bound mesh range, index buffer offset
+// already points at the first index of the range
+fn draw_indexed_command(
+    binding: &MeshRangeBindData,
+    instance_count: u32,
+    first_instance: u32,
+) -> vk::DrawIndexedIndirectCommand {
+    vk::DrawIndexedIndirectCommand {
+        index_count: binding.index_count,
+        instance_count,
+        first_index: 0,
+        vertex_offset: binding.vertex_offset,
+        first_instance,
+    }
+}
+
 pub struct SubmitSemaphoreState<'a> {
     pub semaphores: &'a [vk::Semaphore],
     pub masks: &'a [vk::PipelineStageFlags],
@@ -827,7 +1609,20 @@ impl<'a, T, L: Level, O: Operation> From<&'a FinishedCommand<T, L, O>> for &'a C
     }
 }
 
+impl<T, O: Operation> FinishedCommand<T, Primary, O> {
+    // Fence signaled once the command submitted next finishes execution
+    pub fn fence(&self) -> vk::Fence {
+        self.0.data.fence
+    }
+}
+
 impl Device {
+    // Resources shared between queues of different families require ownership
+    // transfer and semaphore between the submissions, barrier suffices otherwise
+    pub fn requires_queue_transfer<S: Operation, D: Operation>(&self) -> bool {
+        S::get_queue_family_index(self) != D::get_queue_family_index(self)
+    }
+
     pub fn submit_command<'a, T, O: Operation>(
         &'a self,
         command: FinishedCommand<T, Primary, O>,
@@ -894,6 +1689,7 @@ impl<'a, O: Operation> SubmitedCommand<'a, Persistent, Primary, O> {
 pub(super) struct TransientCommandPools {
     transfer: vk::CommandPool,
     graphics: vk::CommandPool,
+    compute: vk::CommandPool,
 }
 
 impl TransientCommandPools {
@@ -914,13 +1710,26 @@ impl TransientCommandPools {
                 None,
             )?
         };
-        Ok(Self { transfer, graphics })
+        let compute = unsafe {
+            device.create_command_pool(
+                &vk::CommandPoolCreateInfo::builder()
+                    .queue_family_index(queue_families.compute)
+                    .flags(vk::CommandPoolCreateFlags::TRANSIENT),
+                None,
+            )?
+        };
+        Ok(Self {
+            transfer,
+            graphics,
+            compute,
+        })
     }
 
     pub fn destroy(&mut self, device: &ash::Device) {
         unsafe {
             device.destroy_command_pool(self.transfer, None);
-            device.destroy_command_pool(self.graphics, None)
+            device.destroy_command_pool(self.graphics, None);
+            device.destroy_command_pool(self.compute, None)
         };
     }
 }
@@ -954,14 +1763,28 @@ impl Device {
             _phantom: PhantomData,
         }))
     }
-    pub fn free_command<'a, T: 'static, O: 'static + Operation>(
-        &self,
-        command: impl Into<&'a Command<T, Primary, O>>,
-    ) {
-        let &Command {
-            data: Primary { buffer, fence },
-            ..
-        } = command.into();
+
+    /// Consumes the submitted command, the command can't be used after it is freed
+    ///
+    /// ```compile_fail,E0382
+    /// use vulkan::context::device::{
+    ///     command::{level::Primary, operation::Graphics, SubmitedCommand, Transient},
+    ///     Device,
+    /// };
+    ///
+    /// fn use_after_free(device: &Device, command: SubmitedCommand<Transient, Primary, Graphics>) {
+    ///     device.free_command(command);
+    ///     let _ = command.wait();
+    /// }
+    /// ```
+    pub fn free_command<O: Operation>(&self, command: SubmitedCommand<Transient, Primary, O>) {
+        let SubmitedCommand(
+            Command {
+                data: Primary { buffer, fence },
+                ..
+            },
+            _,
+        ) = command;
         unsafe {
             self.device
                 .free_command_buffers(O::get_transient_command_pool(self), &[buffer]);
@@ -969,3 +1792,221 @@ impl Device {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use ash::vk::Handle;
+    use graphics::model::{Image, ImageColorSpace};
+    use type_kit::Destroy;
+
+    use crate::context::{
+        device::{
+            memory::DefaultAllocator, resources::image::ImageReader, stats::RenderStatsCounter,
+            DeviceConfig,
+        },
+        Context, DebugConfig,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_instanced_draw_passes_instance_range() {
+        let binding = MeshRangeBindData {
+            index_buffer: vk::Buffer::null(),
+            index_buffer_offset: 48,
+            index_type: vk::IndexType::UINT16,
+            index_count: 36,
+            vertex_offset: 24,
+        };
+        let draw = draw_indexed_command(&binding, 8, 2);
+        assert_eq!(draw.index_count, 36);
+        assert_eq!(draw.instance_count, 8);
+        assert_eq!(draw.first_instance, 2);
+        assert_eq!(draw.first_index, 0);
+        assert_eq!(draw.vertex_offset, 24);
+        // Every instance is counted in the recorded triangles
+        let stats = RenderStatsCounter::default();
+        stats.draw(draw.index_count, draw.instance_count);
+        stats.draw(binding.index_count, 1);
+        let recorded = stats.get();
+        assert_eq!(recorded.draw_calls, 2);
+        assert_eq!(recorded.triangles, 9 * 12);
+    }
+
+    #[test]
+    fn test_buffer_barrier_covers_sub_range() {
+        let mut range = ByteRange::new(1024);
+        let _ = range.take::<u8>(256).unwrap();
+        let range = range.take::<u32>(32).unwrap();
+        let barrier = buffer_range_barrier(
+            vk::Buffer::from_raw(7),
+            2,
+            (
+                vk::AccessFlags::SHADER_WRITE,
+                vk::AccessFlags::VERTEX_ATTRIBUTE_READ,
+            ),
+            range,
+        );
+        assert_eq!(barrier.buffer, vk::Buffer::from_raw(7));
+        assert_eq!((barrier.offset, barrier.size), (256, 128));
+        assert_eq!(barrier.src_access_mask, vk::AccessFlags::SHADER_WRITE);
+        assert_eq!(
+            barrier.dst_access_mask,
+            vk::AccessFlags::VERTEX_ATTRIBUTE_READ
+        );
+        assert_eq!(barrier.src_queue_family_index, 2);
+        assert_eq!(barrier.dst_queue_family_index, 2);
+    }
+
+    fn offscreen_context() -> Context {
+        Context::build_offscreen(
+            vk::Extent2D {
+                width: 64,
+                height: 64,
+            },
+            DebugConfig::default(),
+            &DeviceConfig::default(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    #[ignore = "requires Vulkan capable device"]
+    fn test_reset_all_waits_for_pending_submissions() {
+        let context = offscreen_context();
+        let mut pool =
+            PersistentCommandPool::<Primary, operation::Graphics>::create(2, &context).unwrap();
+        // Every buffer of the pool is submitted without waiting on its fence
+        for _ in 0..pool.len() {
+            let (_, command) = pool.next();
+            let command = context.begin_primary_command(command).unwrap();
+            let _ = context
+                .submit_command(
+                    context.finish_command(command).unwrap(),
+                    SubmitSemaphoreState {
+                        semaphores: &[],
+                        masks: &[],
+                    },
+                    &[],
+                )
+                .unwrap();
+        }
+        pool.reset_all(&context).unwrap();
+        // Fences are left signaled, so recording starts again without blocking
+        for _ in 0..pool.len() {
+            let (_, command) = pool.next();
+            assert!(unsafe { context.get_fence_status(command.0.data.fence) }.unwrap());
+            let command = context.begin_primary_command(command).unwrap();
+            let _ = context.finish_command(command).unwrap();
+        }
+        pool.destroy(&context).unwrap();
+    }
+
+    #[test]
+    fn test_persistent_index_wraps_around() {
+        let mut index = 0;
+        let indices = (0..7)
+            .map(|_| level::next_persistent_index(&mut index, 3))
+            .collect::<Vec<_>>();
+        assert_eq!(indices, [0, 1, 2, 0, 1, 2, 0]);
+        assert_eq!(index, 1);
+        // Single buffer pool always hands out the same buffer
+        let mut index = 0;
+        assert_eq!(level::next_persistent_index(&mut index, 1), 0);
+        assert_eq!(level::next_persistent_index(&mut index, 1), 0);
+    }
+
+    #[test]
+    #[ignore = "requires Vulkan capable device"]
+    fn test_resize_restarts_round_robin_allocation() {
+        let context = offscreen_context();
+        let mut primary =
+            PersistentCommandPool::<Primary, operation::Graphics>::create(2, &context).unwrap();
+        let mut secondary =
+            PersistentCommandPool::<Secondary, operation::Graphics>::create(2, &context).unwrap();
+        let _ = primary.next();
+        let _ = secondary.next();
+        primary.resize(&context, 3).unwrap();
+        secondary.resize(&context, 1).unwrap();
+        assert_eq!(primary.len(), 3);
+        assert_eq!(secondary.len(), 1);
+        // Allocation starts from the first buffer and wraps at the new size
+        let indices = (0..4).map(|_| primary.next().0).collect::<Vec<_>>();
+        assert_eq!(indices, [0, 1, 2, 0]);
+        let indices = (0..2).map(|_| secondary.next().0).collect::<Vec<_>>();
+        assert_eq!(indices, [0, 0]);
+        // Every buffer of the resized pool comes with its own signaled fence
+        let mut fences = (0..primary.len())
+            .map(|_| primary.next().1 .0.data.fence)
+            .collect::<Vec<_>>();
+        assert!(fences
+            .iter()
+            .all(|&fence| unsafe { context.get_fence_status(fence) }.unwrap()));
+        fences.sort_by_key(|fence| fence.as_raw());
+        fences.dedup();
+        assert_eq!(fences.len(), 3);
+        primary.destroy(&context).unwrap();
+        secondary.destroy(&context).unwrap();
+    }
+
+    const EXTENT: u32 = 256;
+
+    // Left half is single texel black and white checker, right half is solid red
+    fn encode_half_checker_png() -> Vec<u8> {
+        let data = (0..EXTENT)
+            .flat_map(|y| (0..EXTENT).map(move |x| (x, y)))
+            .flat_map(|(x, y)| match (x < EXTENT / 2, (x + y) % 2 == 0) {
+                (true, true) => [255, 255, 255, 255],
+                (true, false) => [0, 0, 0, 255],
+                (false, _) => [255, 0, 0, 255],
+            })
+            .collect::<Vec<u8>>();
+        let mut png = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png, EXTENT, EXTENT);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&data).unwrap();
+        writer.finish().unwrap();
+        png
+    }
+
+    #[test]
+    #[ignore = "requires Vulkan capable device"]
+    fn test_generated_mip_levels_downsample_base_level() {
+        let context = offscreen_context();
+        let image = Image::Buffer(encode_half_checker_png());
+        let reader = ImageReader::image(&image)
+            .unwrap()
+            .with_color_space(ImageColorSpace::Linear);
+        let mut allocator = DefaultAllocator {};
+        let mut texture = context.load_texture(&mut allocator, reader).unwrap();
+        assert_eq!(texture.image.mip_levels, 9);
+        let layout = texture.image.layout;
+        let base = context
+            .read_back_image_level(&mut texture.image, layout, 0)
+            .unwrap();
+        assert_eq!(&base[..8], &[255, 255, 255, 255, 0, 0, 0, 255]);
+        for level in [1, 4] {
+            let extent = EXTENT >> level;
+            let mip = context
+                .read_back_image_level(&mut texture.image, layout, level)
+                .unwrap();
+            assert_eq!(mip.len(), (extent * extent * 4) as usize);
+            // Checker averages to mid gray, solid half keeps its color
+            for (index, texel) in mip.chunks(4).enumerate() {
+                if (index as u32 % extent) < extent / 2 {
+                    assert!(texel[..3].iter().all(|c| (126..=129).contains(c)));
+                } else {
+                    assert_eq!(texel[..3], [255, 0, 0]);
+                }
+                assert_eq!(texel[3], 255);
+            }
+        }
+        let last = context
+            .read_back_image_level(&mut texture.image, layout, 8)
+            .unwrap();
+        assert_eq!(last.len(), 4);
+        let _ = texture.destroy((&context, &mut allocator));
+    }
+}
@@ -19,7 +19,9 @@ use ash::vk;
 use crate::context::error::VkError;
 
 use super::{
-    pipeline::{GraphicsPipeline, GraphicsPipelineConfig, Layout},
+    pipeline::{
+        ComputePipeline, ComputePipelineConfig, GraphicsPipeline, GraphicsPipelineConfig, Layout,
+    },
     Device,
 };
 
@@ -117,6 +119,7 @@ pub struct DescriptorBindingData {
     pub set_index: u32,
     pub set: vk::DescriptorSet,
     pub pipeline_layout: vk::PipelineLayout,
+    pub bind_point: vk::PipelineBindPoint,
 }
 
 impl<T: DescriptorLayout> Descriptor<T> {
@@ -135,8 +138,28 @@ impl<T: DescriptorLayout> Descriptor<T> {
             set_index,
             set: self.set,
             pipeline_layout: pipeline.layout().into(),
+            bind_point: vk::PipelineBindPoint::GRAPHICS,
         })
     }
+
+    pub fn get_compute_binding_data<C: ComputePipelineConfig>(
+        &self,
+        pipeline: &ComputePipeline<C>,
+    ) -> DescriptorBindingData {
+        let set_index = C::Layout::sets().get_set_index::<T>().unwrap_or_else(|| {
+            panic!(
+                "DescriptorSet {} not present in layout DescriptorSets {}",
+                type_name::<T>(),
+                type_name::<<C::Layout as Layout>::Descriptors>()
+            )
+        });
+        DescriptorBindingData {
+            set_index,
+            set: self.set,
+            pipeline_layout: pipeline.layout().into(),
+            bind_point: vk::PipelineBindPoint::COMPUTE,
+        }
+    }
 }
 
 impl<L: DescriptorLayout> Create for DescriptorPool<L> {
@@ -4,9 +4,14 @@ use ash::vk;
 use bytemuck::{AnyBitPattern, Zeroable};
 
 use crate::context::device::{
-    framebuffer::InputAttachment, memory::Allocator, resources::image::Texture2D,
+    frame::FrameGlobals,
+    framebuffer::InputAttachment,
+    memory::Allocator,
+    resources::{
+        image::{StorageImage, Texture2D},
+        LightUniform, ShadowMapSampler,
+    },
 };
-use graphics::renderer::camera::CameraMatrices;
 use type_kit::{Cons, Nil};
 
 use super::{DescriptorBinding, DescriptorLayoutBuilder};
@@ -31,6 +36,14 @@ impl PipelineStage for FragmentStage {
     const STAGE: vk::ShaderStageFlags = vk::ShaderStageFlags::FRAGMENT;
 }
 
+#[repr(C)]
+#[derive(Debug)]
+pub struct ComputeStage;
+
+impl PipelineStage for ComputeStage {
+    const STAGE: vk::ShaderStageFlags = vk::ShaderStageFlags::COMPUTE;
+}
+
 #[repr(C)]
 #[derive(Debug)]
 pub struct PodUniform<T: Clone + Copy + AnyBitPattern, S: PipelineStage> {
@@ -102,7 +115,69 @@ impl<T: Clone + Copy + AnyBitPattern, S: PipelineStage> DescriptorBinding for Po
     }
 }
 
-impl DescriptorBinding for CameraMatrices {
+// Element of storage buffer array bound as single descriptor
+#[repr(C)]
+#[derive(Debug)]
+pub struct PodStorage<T: Clone + Copy + AnyBitPattern, S: PipelineStage> {
+    pub data: T,
+    _phantom: PhantomData<S>,
+}
+
+unsafe impl<T: Clone + Copy + AnyBitPattern, S: PipelineStage> Zeroable for PodStorage<T, S> {}
+
+unsafe impl<T: Clone + Copy + AnyBitPattern, S: PipelineStage> AnyBitPattern for PodStorage<T, S> {}
+
+impl<T: Clone + Copy + AnyBitPattern, S: PipelineStage> Clone for PodStorage<T, S> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: Clone + Copy + AnyBitPattern, S: PipelineStage> Copy for PodStorage<T, S> {}
+
+impl<T: Clone + Copy + AnyBitPattern, S: PipelineStage> From<T> for PodStorage<T, S> {
+    fn from(data: T) -> Self {
+        Self {
+            data,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: Clone + Copy + AnyBitPattern, S: PipelineStage> DescriptorBinding for PodStorage<T, S> {
+    fn has_data() -> bool {
+        size_of::<Self>() > 0
+    }
+
+    fn get_descriptor_set_binding(binding: u32) -> vk::DescriptorSetLayoutBinding {
+        vk::DescriptorSetLayoutBinding {
+            binding,
+            descriptor_type: vk::DescriptorType::STORAGE_BUFFER,
+            descriptor_count: 1,
+            stage_flags: S::STAGE,
+            p_immutable_samplers: std::ptr::null(),
+        }
+    }
+
+    fn get_descriptor_write(binding: u32) -> vk::WriteDescriptorSet {
+        vk::WriteDescriptorSet {
+            dst_binding: binding,
+            dst_array_element: 0,
+            descriptor_count: 1,
+            descriptor_type: vk::DescriptorType::STORAGE_BUFFER,
+            ..Default::default()
+        }
+    }
+
+    fn get_descriptor_pool_size(num_sets: u32) -> vk::DescriptorPoolSize {
+        vk::DescriptorPoolSize {
+            ty: vk::DescriptorType::STORAGE_BUFFER,
+            descriptor_count: num_sets,
+        }
+    }
+}
+
+impl DescriptorBinding for FrameGlobals {
     fn has_data() -> bool {
         true
     }
@@ -112,7 +187,7 @@ impl DescriptorBinding for CameraMatrices {
             binding,
             descriptor_type: vk::DescriptorType::UNIFORM_BUFFER,
             descriptor_count: 1,
-            stage_flags: vk::ShaderStageFlags::VERTEX,
+            stage_flags: vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
             p_immutable_samplers: std::ptr::null(),
         }
     }
@@ -201,10 +276,84 @@ impl DescriptorBinding for InputAttachment {
     }
 }
 
-pub type CameraDescriptorSet = DescriptorLayoutBuilder<Cons<CameraMatrices, Nil>>;
+impl DescriptorBinding for ShadowMapSampler {
+    fn has_data() -> bool {
+        true
+    }
+
+    fn get_descriptor_set_binding(binding: u32) -> vk::DescriptorSetLayoutBinding {
+        vk::DescriptorSetLayoutBinding {
+            binding,
+            descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+            descriptor_count: 1,
+            stage_flags: vk::ShaderStageFlags::FRAGMENT,
+            p_immutable_samplers: std::ptr::null(),
+        }
+    }
+
+    fn get_descriptor_write(binding: u32) -> vk::WriteDescriptorSet {
+        vk::WriteDescriptorSet {
+            dst_binding: binding,
+            dst_array_element: 0,
+            descriptor_count: 1,
+            descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+            ..Default::default()
+        }
+    }
+
+    fn get_descriptor_pool_size(num_sets: u32) -> vk::DescriptorPoolSize {
+        vk::DescriptorPoolSize {
+            ty: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+            descriptor_count: num_sets,
+        }
+    }
+}
+
+impl DescriptorBinding for StorageImage {
+    fn has_data() -> bool {
+        true
+    }
+
+    fn get_descriptor_set_binding(binding: u32) -> vk::DescriptorSetLayoutBinding {
+        vk::DescriptorSetLayoutBinding {
+            binding,
+            descriptor_type: vk::DescriptorType::STORAGE_IMAGE,
+            descriptor_count: 1,
+            stage_flags: vk::ShaderStageFlags::COMPUTE,
+            p_immutable_samplers: std::ptr::null(),
+        }
+    }
+
+    fn get_descriptor_write(binding: u32) -> vk::WriteDescriptorSet {
+        vk::WriteDescriptorSet {
+            dst_binding: binding,
+            dst_array_element: 0,
+            descriptor_count: 1,
+            descriptor_type: vk::DescriptorType::STORAGE_IMAGE,
+            ..Default::default()
+        }
+    }
+
+    fn get_descriptor_pool_size(num_sets: u32) -> vk::DescriptorPoolSize {
+        vk::DescriptorPoolSize {
+            ty: vk::DescriptorType::STORAGE_IMAGE,
+            descriptor_count: num_sets,
+        }
+    }
+}
+
+// Frame wide data bound once per frame at set 0 of the deferred shaders,
+// custom pipeline layouts opt in by including it in their descriptor sets
+pub type FrameDescriptorSet = DescriptorLayoutBuilder<Cons<FrameGlobals, Nil>>;
 
 pub type TextureDescriptorSet<A> = DescriptorLayoutBuilder<Cons<Texture2D<A>, Nil>>;
 
+pub type StorageImageDescriptorSet = DescriptorLayoutBuilder<Cons<StorageImage, Nil>>;
+
+pub type ShadowDescriptorSet = DescriptorLayoutBuilder<
+    Cons<PodUniform<LightUniform, FragmentStage>, Cons<ShadowMapSampler, Nil>>,
+>;
+
 pub type GBufferDescriptorSet = DescriptorLayoutBuilder<
     Cons<
         // Albedo
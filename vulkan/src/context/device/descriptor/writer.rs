@@ -4,7 +4,10 @@ use ash::vk;
 use bytemuck::AnyBitPattern;
 
 use crate::context::device::{
-    command::operation::Operation, memory::Allocator, resources::buffer::UniformBuffer, Device,
+    command::operation::Operation,
+    memory::Allocator,
+    resources::buffer::{StorageBufferBinding, UniformBuffer},
+    Device,
 };
 
 use super::{Descriptor, DescriptorBinding, DescriptorLayout};
@@ -96,6 +99,44 @@ impl<T: DescriptorLayout> DescriptorSetWriter<T> {
         self
     }
 
+    // Buffer is split evenly between descriptor sets,
+    // each set binds its part of the buffer as a whole
+    pub fn write_storage_buffer<U: AnyBitPattern + DescriptorBinding>(
+        mut self,
+        buffer: impl Into<StorageBufferBinding<U>>,
+    ) -> Self {
+        let buffer = buffer.into();
+        let writes = T::get_descriptor_writes::<U>();
+        if writes.is_empty() {
+            panic!(
+                "Invalid DescriptorBinding type {} for descriptor layout {}",
+                type_name::<U>(),
+                type_name::<T>()
+            )
+        }
+        debug_assert_eq!(
+            buffer.len % self.num_sets,
+            0,
+            "StorageBuffer length not divisible by DescriptorPool size!"
+        );
+        let set_len = buffer.len / self.num_sets;
+        let buffer_write_base_index = self.bufer_writes.len();
+        self.bufer_writes
+            .extend((0..self.num_sets).map(|index| vk::DescriptorBufferInfo {
+                buffer: buffer.buffer,
+                offset: (size_of::<U>() * set_len * index) as vk::DeviceSize,
+                range: (size_of::<U>() * set_len) as vk::DeviceSize,
+            }));
+        self.writes.extend((0..self.num_sets).flat_map(|set_index| {
+            writes.iter().map(move |&write| SetWrite::Buffer {
+                set_index,
+                buffer_write_index: buffer_write_base_index + set_index,
+                write,
+            })
+        }));
+        self
+    }
+
     pub fn write_images<'a, B, I>(mut self, images: &'a [I]) -> Self
     where
         B: DescriptorBinding,
@@ -188,3 +229,131 @@ impl Device {
             .collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use type_kit::{Cons, Create, Destroy, Nil};
+
+    use crate::context::{
+        device::{
+            command::operation::Compute,
+            descriptor::{
+                ComputeStage, DescriptorLayoutBuilder, DescriptorPool, PodStorage, PodUniform,
+            },
+            memory::DefaultAllocator,
+            resources::{
+                buffer::{
+                    HostStorageBuffer, HostStorageBufferPartial, StorageBufferBuilder,
+                    UniformBufferBuilder, UniformBufferPartial,
+                },
+                PartialBuilder,
+            },
+            DeviceConfig,
+        },
+        Context, DebugConfig,
+    };
+
+    use super::*;
+
+    type Params = PodUniform<[f32; 4], ComputeStage>;
+    type Elements = PodStorage<u32, ComputeStage>;
+    type MixedDescriptorSet = DescriptorLayoutBuilder<Cons<Params, Cons<Elements, Nil>>>;
+
+    const NUM_SETS: usize = 2;
+    const SET_LEN: usize = 16;
+
+    #[test]
+    fn test_mixed_layout_bindings_and_pool_sizes() {
+        let bindings = MixedDescriptorSet::get_descriptor_set_bindings();
+        let types = bindings
+            .iter()
+            .map(|binding| (binding.binding, binding.descriptor_type))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            types,
+            [
+                (0, vk::DescriptorType::UNIFORM_BUFFER),
+                (1, vk::DescriptorType::STORAGE_BUFFER)
+            ]
+        );
+        let writes = MixedDescriptorSet::get_descriptor_writes::<Elements>();
+        assert_eq!(writes.len(), 1);
+        assert_eq!(writes[0].dst_binding, 1);
+        let mut pool_sizes = MixedDescriptorSet::get_descriptor_pool_sizes(NUM_SETS as u32)
+            .into_iter()
+            .map(|size| (size.ty.as_raw(), size.descriptor_count))
+            .collect::<Vec<_>>();
+        pool_sizes.sort();
+        assert_eq!(
+            pool_sizes,
+            [
+                (vk::DescriptorType::UNIFORM_BUFFER.as_raw(), NUM_SETS as u32),
+                (vk::DescriptorType::STORAGE_BUFFER.as_raw(), NUM_SETS as u32)
+            ]
+        );
+    }
+
+    #[test]
+    #[ignore = "requires Vulkan capable device"]
+    fn test_descriptor_set_mixing_uniform_and_storage_buffer() {
+        let context = Context::build_offscreen(
+            vk::Extent2D {
+                width: 64,
+                height: 64,
+            },
+            DebugConfig::default(),
+            &DeviceConfig::default(),
+        )
+        .unwrap();
+        let mut allocator = DefaultAllocator {};
+        let allocator = RefCell::new(&mut allocator);
+        let mut uniform = UniformBuffer::<Params, Compute, _>::create(
+            UniformBufferPartial::prepare(UniformBufferBuilder::new(NUM_SETS), &context).unwrap(),
+            (&context, &allocator),
+        )
+        .unwrap();
+        let mut storage = HostStorageBuffer::create(
+            HostStorageBufferPartial::prepare(
+                StorageBufferBuilder::<Elements, Compute>::new(NUM_SETS * SET_LEN),
+                &context,
+            )
+            .unwrap(),
+            (&context, &allocator),
+        )
+        .unwrap();
+
+        let writer = DescriptorSetWriter::<MixedDescriptorSet>::new(NUM_SETS)
+            .write_buffer(&uniform)
+            .write_storage_buffer(&storage);
+        // Each set gets its own uniform element and its own part of the storage buffer
+        let infos = writer
+            .bufer_writes
+            .iter()
+            .map(|info| (info.buffer, info.offset, info.range))
+            .collect::<Vec<_>>();
+        let uniform_size = size_of::<Params>() as vk::DeviceSize;
+        let storage_size = (size_of::<Elements>() * SET_LEN) as vk::DeviceSize;
+        assert_eq!(
+            infos,
+            [
+                (uniform.handle(), 0, uniform_size),
+                (uniform.handle(), uniform_size, uniform_size),
+                (storage.handle(), 0, storage_size),
+                (storage.handle(), storage_size, storage_size),
+            ]
+        );
+        assert_eq!(writer.writes.len(), 2 * NUM_SETS);
+
+        let mut pool = DescriptorPool::create(writer, &context).unwrap();
+        assert_eq!(pool.len(), NUM_SETS);
+        assert_ne!(
+            vk::DescriptorSet::from(pool.get(0)),
+            vk::DescriptorSet::from(pool.get(1))
+        );
+        pool.destroy(&context).unwrap();
+        storage.destroy((&context, &allocator)).unwrap();
+        uniform.destroy((&context, &allocator)).unwrap();
+    }
+}
@@ -5,18 +5,24 @@
 
 use std::{cell::RefCell, convert::Infallible, error::Error, marker::PhantomData};
 
+use ash::vk;
 use type_kit::{
     Create, CreateCollection, CreateResult, Destroy, DestroyCollection, DestroyResult, DropGuard,
     DropGuardError,
 };
 
-use crate::context::{error::VkError, Context};
+use crate::context::{
+    error::{VkError, VkResult},
+    Context,
+};
+use bytemuck::{Pod, Zeroable};
 use graphics::{
     model::Drawable,
-    renderer::camera::CameraMatrices,
+    renderer::{camera::CameraMatrices, light::DirectionalLight, viewport::Rect},
     shader::{ShaderHandle, ShaderType},
 };
-use math::types::Matrix4;
+use math::types::{Matrix4, Vector2, Vector4};
+use winit::window::WindowId;
 
 use super::{
     command::{
@@ -24,43 +30,74 @@ use super::{
         operation::Graphics,
         BeginCommand, Persistent, PersistentCommandPool,
     },
-    descriptor::{CameraDescriptorSet, Descriptor, DescriptorPool, DescriptorSetWriter},
+    descriptor::{Descriptor, DescriptorPool, DescriptorSetWriter, FrameDescriptorSet},
     framebuffer::AttachmentList,
-    memory::{Allocator, DefaultAllocator},
+    memory::{Allocator, DefaultAllocator, StaticAllocator},
     pipeline::{
-        GraphicsPipelineConfig, GraphicsPipelineListBuilder, GraphicsPipelinePackList, ModuleLoader,
+        GraphicsPipelineConfig, GraphicsPipelineListBuilder, GraphicsPipelinePackList,
+        ModuleLoader, ShaderLoadConfig,
     },
+    profiler::GpuPassTimings,
     resources::{
         buffer::{UniformBuffer, UniformBufferBuilder, UniformBufferPartial},
-        MaterialPackList, MeshPackList, PartialBuilder,
+        LayoutSkybox, MaterialPackList, MeshPackList, PartialBuilder, Skybox,
     },
-    swapchain::{Swapchain, SwapchainFrame, SwapchainImageSync},
+    screenshot::ScreenshotRequest,
+    swapchain::{PresentStatus, SwapchainFrame, SwapchainImageSync},
     Device,
 };
 
 pub trait Frame: 'static {
     type Shader<S: ShaderType>: ShaderType + GraphicsPipelineConfig + ModuleLoader;
     type Context<P: GraphicsPipelinePackList>: FrameContext
-        + for<'a> Create<Context<'a> = &'a Context>;
+        + for<'a> Create<Context<'a> = &'a Context, CreateError = VkError>;
 
     fn load_context<P: GraphicsPipelinePackList>(
         &self,
         context: &Context,
         pipelines: &impl GraphicsPipelineListBuilder<Pack = P>,
+        config: ShaderLoadConfig,
     ) -> CreateResult<Self::Context<P>>;
 }
 
+// Swapchain the frame is rendered into, additional windows
+// are registered with the renderer under their window ids
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FrameTarget {
+    Primary,
+    Window(WindowId),
+}
+
+// Views recorded within a single frame, including the full frame view
+// of the camera passed to `FrameContext::begin_frame`
+pub const MAX_FRAME_VIEWS: usize = 4;
+
 pub trait FrameContext: Sized {
     const REQUIRED_COMMANDS: usize;
     type Attachments: AttachmentList;
     type State;
+    type SkyboxPipeline: GraphicsPipelineConfig<Layout = LayoutSkybox<StaticAllocator>>;
 
     fn begin_frame(
         &mut self,
         device: &Device,
+        target: FrameTarget,
+        camera: &CameraMatrices,
+        skybox: Option<&Skybox<StaticAllocator, Self::SkyboxPipeline>>,
+    ) -> Result<(), Box<dyn Error>>;
+
+    // Draws following the call are rendered into `viewport` region of the frame,
+    // regions of views with draws are expected not to overlap
+    fn begin_view(
+        &mut self,
+        device: &Device,
+        viewport: Rect,
         camera: &CameraMatrices,
+        skybox: Option<&Skybox<StaticAllocator, Self::SkyboxPipeline>>,
     ) -> Result<(), Box<dyn Error>>;
 
+    fn end_view(&mut self) -> Result<(), Box<dyn Error>>;
+
     fn draw<
         A1: Allocator,
         A2: Allocator,
@@ -77,30 +114,100 @@ pub trait FrameContext: Sized {
         mesh_packs: &V,
     );
 
-    fn end_frame(&mut self, device: &Device) -> Result<(), Box<dyn Error>>;
+    fn draw_batch<
+        A1: Allocator,
+        A2: Allocator,
+        S: ShaderType,
+        D: Drawable<Material = S::Material, Vertex = S::Vertex>,
+        M: MaterialPackList<A2>,
+        V: MeshPackList<A1>,
+    >(
+        &mut self,
+        shader: ShaderHandle<S>,
+        items: &[(&D, Matrix4)],
+        material_packs: &M,
+        mesh_packs: &V,
+    );
+
+    fn set_directional_light(&mut self, light: Option<DirectionalLight>);
+
+    // Queues text drawn over the current frame at swapchain pixel coordinates
+    fn draw_text(&mut self, x: f32, y: f32, text: &str, color: Vector4);
+
+    // Enables tonemapping of the presented frame with given exposure, None disables it
+    fn set_tonemapping(&mut self, exposure: Option<f32>);
+
+    fn reload_modified_shaders(&mut self, device: &Device) -> Vec<VkError>;
+
+    fn reload_shader<S: ShaderType>(
+        &mut self,
+        device: &Device,
+        shader: ShaderHandle<S>,
+    ) -> VkResult<()>;
+
+    // Checks whether pipeline of the shader was loaded under the handle index
+    fn contains_shader<S: ShaderType>(&self, shader: ShaderHandle<S>) -> bool;
+
+    // Returns fence signaled once the submitted frame has finished executing,
+    // along with the state of the swapchain the frame was presented into
+    fn end_frame(&mut self, device: &Device) -> Result<(vk::Fence, PresentStatus), Box<dyn Error>>;
+
+    // Color image of the last frame rendered to offscreen target as RGBA8 rows
+    fn read_back_frame(&self, device: &Device) -> VkResult<Vec<u8>>;
+
+    fn capture_screenshot(&mut self, request: ScreenshotRequest);
+
+    // Pass durations of the latest primary target frame whose timestamps were read back
+    fn gpu_pass_timings(&self) -> Option<GpuPassTimings>;
 }
 
-pub struct CameraUniform {
-    pub descriptors: DropGuard<DescriptorPool<CameraDescriptorSet>>,
-    pub uniform_buffer: DropGuard<UniformBuffer<CameraMatrices, Graphics, DefaultAllocator>>,
+// Shader data shared by all draws of the frame, camera matrices come first
+// so shaders declaring only the camera block remain compatible
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Zeroable, Pod)]
+pub struct FrameGlobals {
+    pub camera: CameraMatrices,
+    // Swapchain extent in pixels
+    pub viewport: Vector2,
+    // Seconds elapsed since the renderer context creation
+    pub time: f32,
+    _padding: f32,
+}
+
+impl FrameGlobals {
+    pub fn new(camera: CameraMatrices, viewport: Vector2, time: f32) -> Self {
+        Self {
+            camera,
+            viewport,
+            time,
+            _padding: 0.0,
+        }
+    }
+}
+
+// Uniform buffer region and descriptor set is allocated for each view of each frame
+// in flight, so that data of the frame being recorded does not race frames in flight
+pub struct FrameUniform {
+    pub descriptors: DropGuard<DescriptorPool<FrameDescriptorSet>>,
+    pub uniform_buffer: DropGuard<UniformBuffer<FrameGlobals, Graphics, DefaultAllocator>>,
 }
 
 pub struct FrameData<C: FrameContext> {
     pub swapchain_frame: SwapchainFrame<C::Attachments>,
     pub primary_command: BeginCommand<Persistent, Primary, Graphics>,
-    pub camera_descriptor: Descriptor<CameraDescriptorSet>,
+    pub frame_descriptor: Descriptor<FrameDescriptorSet>,
     pub renderer_state: C::State,
 }
 
 pub struct FramePool<F: FrameContext> {
     pub image_sync: Vec<SwapchainImageSync>,
-    pub camera_uniform: CameraUniform,
+    pub frame_uniform: FrameUniform,
     pub primary_commands: PersistentCommandPool<Primary, Graphics>,
     pub secondary_commands: PersistentCommandPool<Secondary, Graphics>,
     _phantom: PhantomData<F>,
 }
 
-impl Create for CameraUniform {
+impl Create for FrameUniform {
     type Config<'a> = usize;
     type CreateError = VkError;
 
@@ -115,17 +222,27 @@ impl Create for CameraUniform {
             (context, &RefCell::new(&mut DefaultAllocator {})),
         )?;
         let descriptors = DescriptorPool::create(
-            DescriptorSetWriter::<CameraDescriptorSet>::new(config).write_buffer(&uniform_buffer),
+            DescriptorSetWriter::<FrameDescriptorSet>::new(config).write_buffer(&uniform_buffer),
             context,
         )?;
-        Ok(CameraUniform {
+        Ok(FrameUniform {
             descriptors: DropGuard::new(descriptors),
             uniform_buffer: DropGuard::new(uniform_buffer),
         })
     }
 }
 
-impl Destroy for CameraUniform {
+impl FrameUniform {
+    pub fn index(frame_index: usize, view_index: usize) -> usize {
+        debug_assert!(
+            view_index < MAX_FRAME_VIEWS,
+            "Frame view index out of range!"
+        );
+        frame_index * MAX_FRAME_VIEWS + view_index
+    }
+}
+
+impl Destroy for FrameUniform {
     type Context<'a> = &'a Device;
     type DestroyError = DropGuardError<Infallible>;
 
@@ -137,23 +254,31 @@ impl Destroy for CameraUniform {
     }
 }
 
+impl<F: FrameContext> FramePool<F> {
+    // Primary and secondary command buffer counts for the number of frames in flight
+    pub fn command_buffer_counts(frames_in_flight: usize) -> (usize, usize) {
+        (frames_in_flight, frames_in_flight * F::REQUIRED_COMMANDS)
+    }
+}
+
 impl<F: FrameContext> Create for FramePool<F> {
-    type Config<'a> = &'a Swapchain<F::Attachments>;
+    // Number of frames in flight, independent of the swapchain image count
+    type Config<'a> = usize;
     type CreateError = VkError;
 
     fn create<'a, 'b>(config: Self::Config<'a>, context: Self::Context<'b>) -> CreateResult<Self> {
-        let image_sync = (0..config.num_images)
+        let (num_primary, num_secondary) = Self::command_buffer_counts(config);
+        let image_sync = (0..config)
             .map(|_| ())
             .create(context)
             .collect::<Result<Vec<_>, _>>()?;
-        let primary_commands = PersistentCommandPool::create(config.num_images, context)?;
-        let secondary_commands =
-            PersistentCommandPool::create(config.num_images * F::REQUIRED_COMMANDS, context)?;
-        let camera_uniform = CameraUniform::create(config.num_images, context)?;
+        let primary_commands = PersistentCommandPool::create(num_primary, context)?;
+        let secondary_commands = PersistentCommandPool::create(num_secondary, context)?;
+        let frame_uniform = FrameUniform::create(config * MAX_FRAME_VIEWS, context)?;
 
         Ok(FramePool {
             image_sync,
-            camera_uniform,
+            frame_uniform,
             primary_commands,
             secondary_commands,
             _phantom: PhantomData,
@@ -169,7 +294,7 @@ impl<F: FrameContext> Destroy for FramePool<F> {
         self.image_sync.iter_mut().destroy(context)?;
         self.primary_commands.destroy(context)?;
         self.secondary_commands.destroy(context)?;
-        self.camera_uniform.destroy(context)?;
+        self.frame_uniform.destroy(context)?;
         Ok(())
     }
 }
@@ -1,12 +1,17 @@
 pub mod presets;
 
-use std::{marker::PhantomData, usize};
+use std::{
+    cell::RefCell,
+    collections::{hash_map::Entry, HashMap},
+    marker::PhantomData,
+    usize,
+};
 
 use ash::vk::{self, Extent2D};
 
 use crate::context::{
     device::{AttachmentProperties, Device},
-    error::VkResult,
+    error::{VkError, VkResult},
 };
 use type_kit::{Cons, Nil};
 
@@ -16,10 +21,20 @@ use super::{
     resources::image::Image2D,
 };
 
+// Setters return false when the attachment does not take value of given kind
 pub trait ClearValue {
     fn get(&self) -> Option<vk::ClearValue>;
+
+    fn set_color(&mut self, _color: vk::ClearColorValue) -> bool {
+        false
+    }
+
+    fn set_depth_stencil(&mut self, _depth_stencil: vk::ClearDepthStencilValue) -> bool {
+        false
+    }
 }
 
+#[derive(Default)]
 pub struct ClearNone {}
 
 impl ClearValue for ClearNone {
@@ -32,22 +47,53 @@ pub struct ClearColor {
     pub color: vk::ClearColorValue,
 }
 
+impl Default for ClearColor {
+    fn default() -> Self {
+        Self {
+            color: vk::ClearColorValue {
+                float32: [0.0, 0.0, 0.0, 1.0],
+            },
+        }
+    }
+}
+
 impl ClearValue for ClearColor {
     fn get(&self) -> Option<vk::ClearValue> {
         Some(vk::ClearValue { color: self.color })
     }
+
+    fn set_color(&mut self, color: vk::ClearColorValue) -> bool {
+        self.color = color;
+        true
+    }
 }
 
 pub struct ClearDeptStencil {
     pub depth_stencil: vk::ClearDepthStencilValue,
 }
 
+impl Default for ClearDeptStencil {
+    fn default() -> Self {
+        Self {
+            depth_stencil: vk::ClearDepthStencilValue {
+                depth: 1.0,
+                stencil: 0,
+            },
+        }
+    }
+}
+
 impl ClearValue for ClearDeptStencil {
     fn get(&self) -> Option<vk::ClearValue> {
         Some(vk::ClearValue {
             depth_stencil: self.depth_stencil,
         })
     }
+
+    fn set_depth_stencil(&mut self, depth_stencil: vk::ClearDepthStencilValue) -> bool {
+        self.depth_stencil = depth_stencil;
+        true
+    }
 }
 
 impl ClearValue for Nil {
@@ -80,6 +126,9 @@ pub trait ClearValueList {
     fn get(&self) -> Option<vk::ClearValue>;
 
     fn next(&self) -> &Self::Next;
+
+    // Items are indexed in attachment order, head of the list first
+    fn item_mut(&mut self, index: usize) -> Option<&mut dyn ClearValue>;
 }
 
 impl ClearValueList for Nil {
@@ -94,6 +143,10 @@ impl ClearValueList for Nil {
     fn next(&self) -> &Self::Next {
         unreachable!()
     }
+
+    fn item_mut(&mut self, _index: usize) -> Option<&mut dyn ClearValue> {
+        None
+    }
 }
 
 impl<C: ClearValue, N: ClearValueList> ClearValueList for Cons<C, N> {
@@ -108,15 +161,25 @@ impl<C: ClearValue, N: ClearValueList> ClearValueList for Cons<C, N> {
     fn next(&self) -> &Self::Next {
         &self.tail
     }
+
+    fn item_mut(&mut self, index: usize) -> Option<&mut dyn ClearValue> {
+        match index {
+            0 => Some(&mut self.head),
+            _ => self.tail.item_mut(index - 1),
+        }
+    }
 }
 
 pub struct ClearValueBuilder<C: ClearValueList> {
     clear_values: C,
 }
 
-impl Default for ClearValueBuilder<Nil> {
+// Colors cleared to opaque black, depth to the far plane
+impl<C: ClearValueList + Default> Default for ClearValueBuilder<C> {
     fn default() -> Self {
-        Self::new()
+        Self {
+            clear_values: C::default(),
+        }
     }
 }
 
@@ -139,6 +202,40 @@ impl<V: ClearValueList> ClearValueBuilder<V> {
         }
     }
 
+    pub fn with_color(mut self, index: usize, color: [f32; 4]) -> VkResult<Self> {
+        let item = self.clear_values.item_mut(index).ok_or_else(|| {
+            VkError::ConfigError(format!(
+                "Clear color index {} out of range for {} attachments",
+                index,
+                V::LEN
+            ))
+        })?;
+        if !item.set_color(vk::ClearColorValue { float32: color }) {
+            Err(VkError::ConfigError(format!(
+                "Attachment {} is not cleared as color attachment",
+                index
+            )))?;
+        }
+        Ok(self)
+    }
+
+    // Applies to every depth stencil attachment of the list
+    pub fn with_depth_stencil(mut self, depth: f32, stencil: u32) -> VkResult<Self> {
+        let depth_stencil = vk::ClearDepthStencilValue { depth, stencil };
+        let mut updated = false;
+        for index in 0..V::LEN {
+            if let Some(item) = self.clear_values.item_mut(index) {
+                updated |= item.set_depth_stencil(depth_stencil);
+            }
+        }
+        if !updated {
+            Err(VkError::ConfigError(
+                "No depth stencil attachment to clear".to_string(),
+            ))?;
+        }
+        Ok(self)
+    }
+
     pub fn get_clear_values(&self) -> Vec<vk::ClearValue> {
         self.clear_values.values().into_iter().flatten().collect()
     }
@@ -328,10 +425,26 @@ impl<A: AttachmentList> AttachmentReferences for AttachmentReferenceBuilder<A> {
 pub struct AttachmentTransition {
     pub load_op: vk::AttachmentLoadOp,
     pub store_op: vk::AttachmentStoreOp,
+    // Used only by attachments with stencil aspect
+    pub stencil_load_op: vk::AttachmentLoadOp,
+    pub stencil_store_op: vk::AttachmentStoreOp,
     pub initial_layout: vk::ImageLayout,
     pub final_layout: vk::ImageLayout,
 }
 
+impl Default for AttachmentTransition {
+    fn default() -> Self {
+        Self {
+            load_op: vk::AttachmentLoadOp::DONT_CARE,
+            store_op: vk::AttachmentStoreOp::DONT_CARE,
+            stencil_load_op: vk::AttachmentLoadOp::DONT_CARE,
+            stencil_store_op: vk::AttachmentStoreOp::DONT_CARE,
+            initial_layout: vk::ImageLayout::UNDEFINED,
+            final_layout: vk::ImageLayout::UNDEFINED,
+        }
+    }
+}
+
 pub trait AttachmentTransitionList {
     const LEN: usize;
     type Next: AttachmentTransitionList;
@@ -596,12 +709,14 @@ pub type Clear<A> = ClearValueBuilder<<A as AttachmentList>::ClearListType>;
 pub struct Framebuffer<A: AttachmentList> {
     pub framebuffer: vk::Framebuffer,
     pub attachments: Vec<vk::ImageView>,
+    pub extent: vk::Extent2D,
     _phantom: PhantomData<A>,
 }
 
 #[derive(Debug)]
 pub struct FramebufferHandle<A: AttachmentList> {
     pub framebuffer: vk::Framebuffer,
+    pub extent: vk::Extent2D,
     _phantom: PhantomData<A>,
 }
 
@@ -615,6 +730,7 @@ impl<A: AttachmentList> From<&Framebuffer<A>> for FramebufferHandle<A> {
     fn from(framebuffer: &Framebuffer<A>) -> Self {
         Self {
             framebuffer: framebuffer.framebuffer,
+            extent: framebuffer.extent,
             _phantom: PhantomData,
         }
     }
@@ -622,6 +738,64 @@ impl<A: AttachmentList> From<&Framebuffer<A>> for FramebufferHandle<A> {
 
 impl<A: AttachmentList> Copy for FramebufferHandle<A> {}
 
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct FramebufferKey {
+    render_pass: vk::RenderPass,
+    attachments: Vec<vk::ImageView>,
+    extent: (u32, u32),
+}
+
+// Framebuffers shared between all builds with equal render pass, attachment
+// views and extent, along with the number of Framebuffer objects using them.
+// Handle is destroyed once the last of its users is destroyed, so entries
+// of the swapchain recreated on resize do not outlive the old image views
+#[derive(Debug, Default)]
+pub struct FramebufferCache {
+    framebuffers: RefCell<HashMap<FramebufferKey, (vk::Framebuffer, usize)>>,
+}
+
+impl FramebufferCache {
+    // Shares framebuffer cached under the key, `create` is called only on cache miss
+    fn acquire(
+        &self,
+        key: FramebufferKey,
+        create: impl FnOnce() -> VkResult<vk::Framebuffer>,
+    ) -> VkResult<vk::Framebuffer> {
+        let mut framebuffers = self.framebuffers.borrow_mut();
+        match framebuffers.entry(key) {
+            Entry::Occupied(mut entry) => {
+                let (framebuffer, users) = entry.get_mut();
+                *users += 1;
+                Ok(*framebuffer)
+            }
+            Entry::Vacant(entry) => Ok(entry.insert((create()?, 1)).0),
+        }
+    }
+
+    // Returns the handle once its last user is released, it should be destroyed then
+    fn release(&self, framebuffer: vk::Framebuffer) -> Option<vk::Framebuffer> {
+        let mut framebuffers = self.framebuffers.borrow_mut();
+        let key = framebuffers
+            .iter_mut()
+            .find(|(_, (handle, _))| *handle == framebuffer)
+            .and_then(|(key, (_, users))| {
+                *users -= 1;
+                (*users == 0).then(|| key.clone())
+            })?;
+        framebuffers
+            .remove(&key)
+            .map(|(framebuffer, _)| framebuffer)
+    }
+
+    fn drain(&self) -> Vec<vk::Framebuffer> {
+        self.framebuffers
+            .borrow_mut()
+            .drain()
+            .map(|(_, (framebuffer, _))| framebuffer)
+            .collect()
+    }
+}
+
 impl Device {
     pub fn build_framebuffer<C: RenderPassConfig>(
         &self,
@@ -630,24 +804,139 @@ impl Device {
     ) -> VkResult<Framebuffer<C::Attachments>> {
         let render_pass = self.get_render_pass::<C>()?;
         let attachments = builder.get_attachments();
-        let create_info = vk::FramebufferCreateInfo::builder()
-            .attachments(&attachments)
-            .render_pass(render_pass.handle)
-            .width(extent.width)
-            .height(extent.height)
-            .layers(1);
-        let framebuffer = unsafe { self.device.create_framebuffer(&create_info, None)? };
+        let key = FramebufferKey {
+            render_pass: render_pass.handle,
+            attachments: attachments.clone(),
+            extent: (extent.width, extent.height),
+        };
+        let framebuffer = self.framebuffers.acquire(key, || {
+            let create_info = vk::FramebufferCreateInfo::builder()
+                .attachments(&attachments)
+                .render_pass(render_pass.handle)
+                .width(extent.width)
+                .height(extent.height)
+                .layers(1);
+            Ok(unsafe { self.device.create_framebuffer(&create_info, None)? })
+        })?;
         Ok(Framebuffer {
             framebuffer,
             attachments,
+            extent,
             _phantom: PhantomData,
         })
     }
 
     pub fn destroy_framebuffer<A: AttachmentList>(&self, framebuffer: &mut Framebuffer<A>) {
-        unsafe {
-            self.device
-                .destroy_framebuffer(framebuffer.framebuffer, None);
+        if let Some(framebuffer) = self.framebuffers.release(framebuffer.framebuffer) {
+            unsafe {
+                self.device.destroy_framebuffer(framebuffer, None);
+            }
+        }
+    }
+
+    pub fn destroy_framebuffers(&self) {
+        for framebuffer in self.framebuffers.drain() {
+            unsafe {
+                self.device.destroy_framebuffer(framebuffer, None);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ash::vk::Handle;
+
+    use super::presets::{AttachmentsGBuffer, AttachmentsShadowMap};
+    use super::*;
+
+    fn color(value: &vk::ClearValue) -> [f32; 4] {
+        unsafe { value.color.float32 }
+    }
+
+    fn key(view: u64, extent: u32) -> FramebufferKey {
+        FramebufferKey {
+            render_pass: vk::RenderPass::from_raw(1),
+            attachments: vec![vk::ImageView::from_raw(view)],
+            extent: (extent, extent),
         }
     }
+
+    #[test]
+    fn test_identical_builds_share_framebuffer_until_last_release() {
+        let cache = FramebufferCache::default();
+        let created = std::cell::Cell::new(0);
+        let create = |handle: u64| {
+            let created = &created;
+            move || {
+                created.set(created.get() + 1);
+                Ok(vk::Framebuffer::from_raw(handle))
+            }
+        };
+        let first = cache.acquire(key(10, 64), create(100)).unwrap();
+        let second = cache.acquire(key(10, 64), create(200)).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(created.get(), 1);
+        // Different extent of the same attachments is a separate framebuffer
+        let resized = cache.acquire(key(10, 32), create(300)).unwrap();
+        assert_ne!(resized, first);
+        assert_eq!(created.get(), 2);
+        assert_eq!(cache.release(first), None);
+        assert_eq!(cache.release(second), Some(first));
+        // Released entry is created again by the next build
+        assert_eq!(
+            cache.acquire(key(10, 64), create(400)).unwrap().as_raw(),
+            400
+        );
+        assert_eq!(cache.release(vk::Framebuffer::from_raw(500)), None);
+        let mut remaining = cache.drain();
+        remaining.sort_by_key(|framebuffer| framebuffer.as_raw());
+        assert_eq!(
+            remaining,
+            [
+                vk::Framebuffer::from_raw(300),
+                vk::Framebuffer::from_raw(400)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_failed_creation_not_cached() {
+        let cache = FramebufferCache::default();
+        let result = cache.acquire(key(10, 64), || {
+            Err(vk::Result::ERROR_OUT_OF_HOST_MEMORY.into())
+        });
+        assert!(result.is_err());
+        assert!(cache.drain().is_empty());
+    }
+
+    #[test]
+    fn test_custom_clear_values_follow_attachment_order() {
+        let clear = Clear::<AttachmentsGBuffer>::default()
+            .with_color(1, [0.2, 0.4, 0.6, 1.0])
+            .unwrap()
+            .with_depth_stencil(0.0, 7)
+            .unwrap();
+        let values = clear.get_clear_values();
+        // Resolve attachment takes no clear value
+        assert_eq!(values.len(), AttachmentsGBuffer::LEN - 1);
+        assert_eq!(color(&values[0]), [0.0, 0.0, 0.0, 1.0]);
+        assert_eq!(color(&values[1]), [0.2, 0.4, 0.6, 1.0]);
+        assert_eq!(color(&values[2]), [0.0, 0.0, 0.0, 1.0]);
+        assert_eq!(color(&values[3]), [0.0, 0.0, 0.0, 1.0]);
+        let depth_stencil = unsafe { values[4].depth_stencil };
+        assert_eq!((depth_stencil.depth, depth_stencil.stencil), (0.0, 7));
+    }
+
+    #[test]
+    fn test_invalid_clear_values_rejected() {
+        let clear = Clear::<AttachmentsGBuffer>::default();
+        assert!(clear.with_color(AttachmentsGBuffer::LEN, [1.0; 4]).is_err());
+        let clear = Clear::<AttachmentsGBuffer>::default();
+        assert!(clear.with_color(4, [1.0; 4]).is_err());
+        let clear = Clear::<AttachmentsShadowMap>::default();
+        assert!(clear.with_color(0, [1.0; 4]).is_err());
+        let clear = Clear::<Nil>::default();
+        assert!(clear.with_depth_stencil(1.0, 0).is_err());
+    }
 }
@@ -20,6 +20,45 @@ impl Attachment for ColorMultisampled {
     }
 }
 
+pub struct AlbedoMultisampled {}
+
+impl Attachment for AlbedoMultisampled {
+    type Clear = ClearColor;
+
+    fn get_format(properties: &AttachmentProperties) -> AttachmentFormatInfo {
+        AttachmentFormatInfo {
+            format: properties.formats.albedo,
+            samples: properties.msaa_samples,
+        }
+    }
+}
+
+pub struct NormalMultisampled {}
+
+impl Attachment for NormalMultisampled {
+    type Clear = ClearColor;
+
+    fn get_format(properties: &AttachmentProperties) -> AttachmentFormatInfo {
+        AttachmentFormatInfo {
+            format: properties.formats.normal,
+            samples: properties.msaa_samples,
+        }
+    }
+}
+
+pub struct PositionMultisampled {}
+
+impl Attachment for PositionMultisampled {
+    type Clear = ClearColor;
+
+    fn get_format(properties: &AttachmentProperties) -> AttachmentFormatInfo {
+        AttachmentFormatInfo {
+            format: properties.formats.position,
+            samples: properties.msaa_samples,
+        }
+    }
+}
+
 pub struct DepthStencilMultisampled {}
 
 impl Attachment for DepthStencilMultisampled {
@@ -46,14 +85,27 @@ impl Attachment for Resolve {
     }
 }
 
+pub struct ShadowMapDepth {}
+
+impl Attachment for ShadowMapDepth {
+    type Clear = ClearDeptStencil;
+
+    fn get_format(_properties: &AttachmentProperties) -> AttachmentFormatInfo {
+        AttachmentFormatInfo {
+            format: vk::Format::D32_SFLOAT,
+            samples: vk::SampleCountFlags::TYPE_1,
+        }
+    }
+}
+
 pub type AttachmentsGBuffer = Cons<
     AttachmentImage<ColorMultisampled>, // Combined
     Cons<
-        AttachmentImage<ColorMultisampled>, // Albedo
+        AttachmentImage<AlbedoMultisampled>,
         Cons<
-            AttachmentImage<ColorMultisampled>, // Normal
+            AttachmentImage<NormalMultisampled>,
             Cons<
-                AttachmentImage<ColorMultisampled>, // Position
+                AttachmentImage<PositionMultisampled>,
                 Cons<
                     AttachmentImage<DepthStencilMultisampled>,
                     Cons<AttachmentImage<Resolve>, Nil>,
@@ -62,3 +114,5 @@ pub type AttachmentsGBuffer = Cons<
         >,
     >,
 >;
+
+pub type AttachmentsShadowMap = Cons<AttachmentImage<ShadowMapDepth>, Nil>;
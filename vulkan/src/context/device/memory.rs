@@ -10,12 +10,19 @@ use std::{
 
 use ash::{self, vk};
 
+use crate::context::error::VkResult;
+
 use super::{resources::buffer::ByteRange, Device};
 
 pub use allocator::*;
 
 pub trait MemoryProperties: 'static {
     fn properties() -> vk::MemoryPropertyFlags;
+
+    // Properties accepted when no memory type offers the preferred ones
+    fn fallback_properties() -> vk::MemoryPropertyFlags {
+        Self::properties()
+    }
 }
 
 #[derive(Debug)]
@@ -30,10 +37,16 @@ impl MemoryProperties for HostVisible {
 #[derive(Debug)]
 pub struct HostCoherent;
 
+// Falls back to non-coherent host visible memory, writes to such memory
+// have to be flushed explicitly, see `PersistentBuffer::flush`
 impl MemoryProperties for HostCoherent {
     fn properties() -> vk::MemoryPropertyFlags {
         vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT
     }
+
+    fn fallback_properties() -> vk::MemoryPropertyFlags {
+        vk::MemoryPropertyFlags::HOST_VISIBLE
+    }
 }
 
 #[derive(Debug)]
@@ -63,7 +76,44 @@ impl From<vk::Buffer> for Resource {
     }
 }
 
+// Flushed range is expanded to multiples of `nonCoherentAtomSize`, range which
+// would then exceed the memory chunk is flushed up to the end of the allocation
+fn get_flush_range(
+    memory: vk::DeviceMemory,
+    chunk: ByteRange,
+    range: ByteRange,
+    atom_size: usize,
+) -> vk::MappedMemoryRange {
+    let range = ByteRange {
+        beg: chunk.beg + range.beg,
+        end: chunk.beg + range.end,
+    }
+    .align_outer(atom_size);
+    let size = if range.end <= chunk.end {
+        range.len() as vk::DeviceSize
+    } else {
+        vk::WHOLE_SIZE
+    };
+    vk::MappedMemoryRange::builder()
+        .memory(memory)
+        .offset(range.beg as vk::DeviceSize)
+        .size(size)
+        .build()
+}
+
 impl Device {
+    pub fn flush_memory<C: Memory>(&self, memory: &C, range: ByteRange) -> VkResult<()> {
+        let MemoryChunkRaw {
+            memory,
+            range: chunk,
+        } = *memory.chunk();
+        let flush_range = get_flush_range(memory, chunk, range, self.non_coherent_atom_size());
+        unsafe {
+            self.flush_mapped_memory_ranges(&[flush_range])?;
+        }
+        Ok(())
+    }
+
     pub fn bind_memory<T: Into<Resource>, C: Memory>(
         &self,
         resource: T,
@@ -88,6 +138,13 @@ pub struct MemoryChunkRaw {
     range: ByteRange,
 }
 
+impl MemoryChunkRaw {
+    #[inline]
+    pub fn size(&self) -> vk::DeviceSize {
+        self.range.len() as vk::DeviceSize
+    }
+}
+
 pub struct MemoryChunk<M: MemoryProperties> {
     raw: MemoryChunkRaw,
     _phantom: PhantomData<M>,
@@ -187,3 +244,36 @@ impl<M: MemoryProperties> Memory for MemoryChunk<M> {
 //         }
 //     }
 // }
+
+#[cfg(test)]
+mod tests {
+    use ash::vk::Handle;
+
+    use super::*;
+
+    #[test]
+    fn test_flush_range_aligned_to_atom_size() {
+        let memory = vk::DeviceMemory::from_raw(1);
+        let chunk = ByteRange { beg: 256, end: 768 };
+        // Odd offset and size inside the chunk are expanded to the enclosing atoms
+        let range = get_flush_range(memory, chunk, ByteRange { beg: 3, end: 70 }, 64);
+        assert_eq!((range.offset, range.size), (256, 128));
+        let range = get_flush_range(memory, chunk, ByteRange { beg: 65, end: 66 }, 64);
+        assert_eq!((range.offset, range.size), (320, 64));
+        // Already aligned range is left unchanged
+        let range = get_flush_range(memory, chunk, ByteRange { beg: 128, end: 256 }, 64);
+        assert_eq!((range.offset, range.size), (384, 128));
+        assert_eq!(range.memory, memory);
+    }
+
+    #[test]
+    fn test_flush_range_past_chunk_end_uses_whole_size() {
+        let memory = vk::DeviceMemory::from_raw(1);
+        // Chunk which ends in the middle of an atom
+        let chunk = ByteRange { beg: 100, end: 201 };
+        let range = get_flush_range(memory, chunk, ByteRange { beg: 1, end: 101 }, 64);
+        assert_eq!((range.offset, range.size), (64, vk::WHOLE_SIZE));
+        let range = get_flush_range(memory, chunk, ByteRange { beg: 1, end: 27 }, 64);
+        assert_eq!((range.offset, range.size), (64, 64));
+    }
+}
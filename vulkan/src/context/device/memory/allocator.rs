@@ -15,14 +15,17 @@ pub use default::*;
 pub use page::*;
 pub use r#static::*;
 
-use crate::context::{device::Device, error::AllocResult};
+use crate::context::{
+    device::Device,
+    error::{AllocResult, VkResult},
+};
 
 use super::{DeviceLocal, HostCoherent, HostVisible, Memory, MemoryProperties, Resource};
 
 pub trait AllocatorCreate: Sized + 'static {
     type Config;
 
-    fn create(device: &Device, config: &Self::Config) -> Result<Self, Box<dyn Error>>;
+    fn create(device: &Device, config: &Self::Config) -> VkResult<Self>;
     fn destroy(&mut self, device: &Device);
 }
 
@@ -146,25 +149,39 @@ impl<M: MemoryProperties> AllocReqTyped<M> {
         properties: &PhysicalDeviceMemoryProperties,
     ) -> Option<u32> {
         let memory_type_bits = self.requirements.memory_type_bits;
-        let memory_properties = M::properties();
-
-        properties
-            .memory_types
-            .iter()
-            .zip(0u32..)
-            .find_map(|(memory, type_index)| {
-                if (1 << type_index & memory_type_bits == 1 << type_index)
-                    && memory.property_flags.contains(memory_properties)
-                {
-                    Some(type_index)
-                } else {
-                    None
-                }
+
+        [M::properties(), M::fallback_properties()]
+            .into_iter()
+            .find_map(|memory_properties| {
+                properties
+                    .memory_types
+                    .iter()
+                    .zip(0u32..)
+                    .find_map(|(memory, type_index)| {
+                        if (1 << type_index & memory_type_bits == 1 << type_index)
+                            && memory.property_flags.contains(memory_properties)
+                        {
+                            Some(type_index)
+                        } else {
+                            None
+                        }
+                    })
             })
     }
 }
 
 impl Device {
+    // Memory type picked for the request may lack HOST_COHERENT property
+    // when it was selected using fallback properties
+    pub fn is_host_coherent(&self, req: &AllocReq) -> bool {
+        let properties = &self.physical_device.properties.memory;
+        req.get_memory_type_index(properties).is_some_and(|index| {
+            properties.memory_types[index as usize]
+                .property_flags
+                .contains(vk::MemoryPropertyFlags::HOST_COHERENT)
+        })
+    }
+
     pub fn get_alloc_req<T: Into<Resource>, M: MemoryProperties>(
         &self,
         resource: T,
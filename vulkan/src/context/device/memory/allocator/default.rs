@@ -1,4 +1,4 @@
-use std::{error::Error, marker::PhantomData};
+use std::marker::PhantomData;
 
 use ash::vk;
 
@@ -8,7 +8,7 @@ use crate::context::{
         resources::buffer::ByteRange,
         Device,
     },
-    error::AllocError,
+    error::{AllocError, VkResult},
 };
 use type_kit::Nil;
 
@@ -19,7 +19,7 @@ pub struct DefaultAllocator {}
 impl AllocatorCreate for DefaultAllocator {
     type Config = Nil;
 
-    fn create(_device: &Device, _config: &Self::Config) -> Result<Self, Box<dyn Error>> {
+    fn create(_device: &Device, _config: &Self::Config) -> VkResult<Self> {
         Ok(DefaultAllocator {})
     }
 
@@ -47,6 +47,7 @@ impl Allocator for DefaultAllocator {
                 None,
             )?
         };
+        device.tracker().register(memory, "DefaultAllocator memory");
         Ok(MemoryChunk {
             raw: MemoryChunkRaw {
                 memory,
@@ -57,6 +58,7 @@ impl Allocator for DefaultAllocator {
     }
 
     fn free<M: MemoryProperties>(&mut self, device: &Device, allocation: &mut Self::Allocation<M>) {
+        device.tracker().unregister(allocation.memory);
         unsafe {
             device.free_memory(allocation.memory, None);
         }
@@ -1,6 +1,5 @@
 use std::{
     cell::RefCell,
-    error::Error,
     ffi::c_void,
     fmt::{self, Debug, Formatter},
     marker::PhantomData,
@@ -16,7 +15,7 @@ use crate::{
             resources::buffer::ByteRange,
             Device,
         },
-        error::{AllocError, AllocResult},
+        error::{AllocError, AllocResult, VkResult},
     },
     VulkanRendererConfig,
 };
@@ -173,7 +172,7 @@ pub struct PageAllocator {
 impl AllocatorCreate for PageAllocator {
     type Config = PageAllocatorConfig;
 
-    fn create(device: &Device, config: &Self::Config) -> Result<Self, Box<dyn Error>> {
+    fn create(device: &Device, config: &Self::Config) -> VkResult<Self> {
         let properties = &device.physical_device.properties;
         let memory_types = (0..properties.memory.memory_types.len() as u32)
             .map(|index| PageType {
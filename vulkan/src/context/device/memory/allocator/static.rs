@@ -1,4 +1,4 @@
-use std::{error::Error, marker::PhantomData};
+use std::marker::PhantomData;
 
 use ash::vk::{self, MemoryRequirements, PhysicalDeviceMemoryProperties};
 
@@ -8,7 +8,7 @@ use crate::context::{
         resources::buffer::ByteRange,
         Device,
     },
-    error::{AllocError, AllocResult},
+    error::{AllocError, AllocResult, VkResult},
 };
 
 use super::{AllocReq, AllocReqTyped, Allocator, AllocatorCreate};
@@ -44,7 +44,7 @@ pub struct StaticAllocator {
 impl AllocatorCreate for StaticAllocator {
     type Config = StaticAllocatorConfig;
 
-    fn create(device: &Device, config: &Self::Config) -> Result<Self, Box<dyn std::error::Error>> {
+    fn create(device: &Device, config: &Self::Config) -> VkResult<Self> {
         let allocations = config
             .allocations
             .iter()
@@ -70,7 +70,7 @@ impl AllocatorCreate for StaticAllocator {
                         range: ByteRange::empty(),
                     }
                 };
-                Result::<_, Box<dyn Error>>::Ok(memory)
+                VkResult::Ok(memory)
             })
             .collect::<Result<Vec<_>, _>>()?;
         Ok(StaticAllocator { allocations })
@@ -1,15 +1,21 @@
+mod compute;
+mod embedded;
 mod graphics;
 mod layout;
 mod push_constant;
+mod reflection;
 mod states;
 
+pub use compute::*;
+pub use embedded::*;
 pub use graphics::*;
 pub use layout::*;
 pub use push_constant::*;
+pub use reflection::*;
 pub use states::*;
 
 use ash::{self, vk};
-use std::{ffi::CStr, marker::PhantomData, path::Path};
+use std::{ffi::CStr, io::Cursor, marker::PhantomData, path::Path};
 
 use crate::context::error::{ShaderError, ShaderResult};
 
@@ -37,6 +43,7 @@ impl ShaderModule {
             Some(stem) => match stem {
                 "frag" => Ok(vk::ShaderStageFlags::FRAGMENT),
                 "vert" => Ok(vk::ShaderStageFlags::VERTEX),
+                "comp" => Ok(vk::ShaderStageFlags::COMPUTE),
                 stem => Err(ShaderError::UnknowStage(stem.to_string()))?,
             },
             None => Err(ShaderError::InvalidFile(path.to_string_lossy().to_string()))?,
@@ -96,7 +103,8 @@ impl<'b> ModuleLoader for ShaderDirectory<'b> {
         let modules = Modules {
             modules: self
                 .path
-                .read_dir()?
+                .read_dir()
+                .map_err(ShaderError::io(self.path))?
                 .flatten()
                 .filter_map(|entry| {
                     entry
@@ -113,18 +121,25 @@ impl<'b> ModuleLoader for ShaderDirectory<'b> {
 
 impl Device {
     fn load_shader_module(&self, path: &Path) -> ShaderResult<ShaderModule> {
-        let code = std::fs::read(path)?;
+        let code = std::fs::read(path).map_err(ShaderError::io(path))?;
         let stage = ShaderModule::get_shader_stage(path)?;
-        let create_info = vk::ShaderModuleCreateInfo {
-            code_size: code.len(),
-            p_code: code.as_ptr() as *const _,
-            ..Default::default()
-        };
+        self.create_shader_module(&code, stage)
+    }
+
+    fn create_shader_module(
+        &self,
+        code: &[u8],
+        stage: vk::ShaderStageFlags,
+    ) -> ShaderResult<ShaderModule> {
+        // Copied into u32 words, byte slices (e.g. from include_bytes!) are not 4 byte aligned
+        let code = ash::util::read_spv(&mut Cursor::new(code))?;
+        let create_info = vk::ShaderModuleCreateInfo::builder().code(&code);
         let module = unsafe { self.device.create_shader_module(&create_info, None)? };
         Ok(ShaderModule { module, stage })
     }
 }
 
+#[derive(Debug, Clone, Copy)]
 pub struct PipelineBindData {
     pub bind_point: vk::PipelineBindPoint,
     pub pipeline: vk::Pipeline,
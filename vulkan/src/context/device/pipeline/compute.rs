@@ -0,0 +1,127 @@
+use std::{any::type_name, convert::Infallible, marker::PhantomData};
+
+use ash::vk;
+use bytemuck::AnyBitPattern;
+use type_kit::{Create, Destroy, DestroyResult};
+
+use crate::context::{device::Device, error::VkError};
+
+use super::{
+    Layout, ModuleLoader, PipelineBindData, PipelineLayout, PushConstant, PushConstantDataRef,
+};
+
+pub trait ComputePipelineConfig: 'static {
+    type Layout: Layout;
+}
+
+pub struct ComputePipelineBuilder<L: Layout> {
+    _phantom: PhantomData<L>,
+}
+
+impl<L: Layout> ComputePipelineConfig for ComputePipelineBuilder<L> {
+    type Layout = L;
+}
+
+#[derive(Debug)]
+pub struct ComputePipeline<T: ComputePipelineConfig> {
+    handle: vk::Pipeline,
+    layout: vk::PipelineLayout,
+    _phantom: PhantomData<T>,
+}
+
+// Module loader is expected to provide single compute stage module
+impl<T: ComputePipelineConfig> Create for ComputePipeline<T> {
+    type Config<'a> = (PipelineLayout<T::Layout>, &'a dyn ModuleLoader);
+    type CreateError = VkError;
+
+    fn create<'a, 'b>(
+        config: Self::Config<'a>,
+        context: Self::Context<'b>,
+    ) -> type_kit::CreateResult<Self> {
+        let (layout, modules) = config;
+        let layout = layout.into();
+        let modules = modules.load(context)?;
+        let stages = modules.get_stages_info();
+        let stage = match stages.stages.as_slice() {
+            [stage] if stage.stage == vk::ShaderStageFlags::COMPUTE => *stage,
+            _ => Err(VkError::ConfigError(format!(
+                "Compute pipeline {} requires single compute stage module",
+                type_name::<T>()
+            )))?,
+        };
+        let create_infos = [vk::ComputePipelineCreateInfo {
+            stage,
+            layout,
+            ..Default::default()
+        }];
+        let &handle = unsafe {
+            context
+                .create_compute_pipelines(vk::PipelineCache::null(), &create_infos, None)
+                .map_err(|(_, err)| err)?
+                .first()
+                .unwrap()
+        };
+        Ok(ComputePipeline {
+            handle,
+            layout,
+            _phantom: PhantomData,
+        })
+    }
+}
+
+impl<T: ComputePipelineConfig> Destroy for ComputePipeline<T> {
+    type Context<'a> = &'a Device;
+    type DestroyError = Infallible;
+
+    fn destroy<'a>(&mut self, context: Self::Context<'a>) -> DestroyResult<Self> {
+        unsafe {
+            context.destroy_pipeline(self.handle, None);
+        }
+        Ok(())
+    }
+}
+
+impl<C: ComputePipelineConfig> From<&ComputePipeline<C>> for PipelineBindData {
+    fn from(value: &ComputePipeline<C>) -> Self {
+        PipelineBindData {
+            bind_point: vk::PipelineBindPoint::COMPUTE,
+            pipeline: value.handle,
+        }
+    }
+}
+
+impl<C: ComputePipelineConfig> ComputePipeline<C> {
+    pub fn layout(&self) -> PipelineLayout<C::Layout> {
+        PipelineLayout {
+            layout: self.layout,
+            _phantom: PhantomData,
+        }
+    }
+
+    pub fn get_push_range<'a, P: PushConstant + AnyBitPattern>(
+        &self,
+        push_constant_data: &'a P,
+    ) -> PushConstantDataRef<'a, P> {
+        PushConstantDataRef {
+            range: C::Layout::ranges().try_get_range::<P>().unwrap_or_else(|| {
+                panic!(
+                    "PushConstant {} not present in layout PushConstantRanges {}!",
+                    type_name::<P>(),
+                    type_name::<<C::Layout as Layout>::PushConstants>(),
+                )
+            }),
+            layout: self.layout,
+            data: push_constant_data,
+        }
+    }
+}
+
+impl Device {
+    pub fn create_compute_pipeline<C: ComputePipelineConfig>(
+        &self,
+        modules: &dyn ModuleLoader,
+    ) -> type_kit::CreateResult<ComputePipeline<C>> {
+        let layout = self.get_pipeline_layout::<C::Layout>()?;
+        ComputePipeline::create((layout, modules), self)
+    }
+}
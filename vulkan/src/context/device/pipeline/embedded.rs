@@ -0,0 +1,161 @@
+use ash::vk;
+use graphics::shader::EmbeddedShader;
+
+use crate::context::{device::Device, error::ShaderResult};
+
+use super::{
+    get_shader_input_format, GraphicsPipelineConfig, Layout, ModuleLoader, Modules, PipelineStates,
+    PushConstantRanges, VertexInput,
+};
+
+// SPIR-V compiled from `vulkan/shaders/src/`, see `compile_shaders` binary
+const UNLIT_VERT: &[u8] = include_bytes!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/shaders/spv/unlit/vert.spv"
+));
+const UNLIT_FRAG: &[u8] = include_bytes!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/shaders/spv/unlit/frag.spv"
+));
+const ERROR_VERT: &[u8] = include_bytes!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/shaders/spv/error/vert.spv"
+));
+const ERROR_FRAG: &[u8] = include_bytes!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/shaders/spv/error/frag.spv"
+));
+
+// Size of the model matrix read by the error shader from vertex stage push constants
+const ERROR_PUSH_CONSTANT_SIZE: u32 = 64;
+
+pub fn get_embedded_modules(shader: EmbeddedShader) -> [(&'static [u8], vk::ShaderStageFlags); 2] {
+    match shader {
+        EmbeddedShader::Unlit => [
+            (UNLIT_VERT, vk::ShaderStageFlags::VERTEX),
+            (UNLIT_FRAG, vk::ShaderStageFlags::FRAGMENT),
+        ],
+        EmbeddedShader::Error => [
+            (ERROR_VERT, vk::ShaderStageFlags::VERTEX),
+            (ERROR_FRAG, vk::ShaderStageFlags::FRAGMENT),
+        ],
+    }
+}
+
+impl ModuleLoader for EmbeddedShader {
+    fn load<'a>(&self, device: &'a Device) -> ShaderResult<Modules<'a>> {
+        // Modules are pushed one by one, so the ones already created
+        // are destroyed by Modules drop when a later one fails
+        let mut modules = Modules {
+            modules: Vec::new(),
+            device,
+        };
+        for (code, stage) in get_embedded_modules(*self) {
+            modules
+                .modules
+                .push(device.create_shader_module(code, stage)?);
+        }
+        Ok(modules)
+    }
+}
+
+// Error shader reads position as floating point vec4 from location 0 and
+// model matrix from the beginning of the vertex stage push constant range
+pub fn is_error_shader_compatible<C: GraphicsPipelineConfig>() -> bool {
+    let position =
+        <<C::PipelineStates as PipelineStates>::VertexInput as VertexInput>::get_attribute_descriptions()
+            .into_iter()
+            .find(|attribute| attribute.location == 0)
+            .map(|attribute| get_shader_input_format(attribute.format));
+    let float_position = matches!(
+        position,
+        Some(
+            vk::Format::R32G32_SFLOAT
+                | vk::Format::R32G32B32_SFLOAT
+                | vk::Format::R32G32B32A32_SFLOAT
+        )
+    );
+    let model_matrix = PushConstantRanges::<<C::Layout as Layout>::PushConstants>::get_ranges()
+        .iter()
+        .any(|range| {
+            range.stage_flags.contains(vk::ShaderStageFlags::VERTEX)
+                && range.offset == 0
+                && range.size >= ERROR_PUSH_CONSTANT_SIZE
+        });
+    float_position && model_matrix
+}
+
+#[cfg(test)]
+mod tests {
+    use bytemuck::{Pod, Zeroable};
+    use graphics::{
+        model::{CommonVertex, Component, EmptyMaterial, Vertex},
+        shader::Shader,
+    };
+
+    use crate::context::{
+        device::{
+            pipeline::{reflect_shader_interface, validate_vertex_interface},
+            renderer::deferred::DeferredShader,
+        },
+        error::{InterfaceMismatch, ShaderError},
+    };
+
+    use super::*;
+
+    type UnlitShader = DeferredShader<Shader<CommonVertex, EmptyMaterial>>;
+
+    // Same attributes as `CommonVertex`, trailing padding makes the host
+    // vertex size larger than the stride derived from its components
+    #[repr(C)]
+    #[derive(Debug, Clone, Copy, Default, Zeroable, Pod)]
+    struct PaddedVertex {
+        vertex: CommonVertex,
+        padding: [f32; 2],
+    }
+
+    impl Vertex for PaddedVertex {
+        fn components() -> &'static [Component] {
+            CommonVertex::components()
+        }
+    }
+
+    #[test]
+    fn test_embedded_unlit_matches_common_vertex() {
+        validate_vertex_interface::<UnlitShader>("embedded/unlit", UNLIT_VERT).unwrap();
+    }
+
+    #[test]
+    fn test_padded_vertex_stride_mismatch_reported() {
+        type PaddedShader = DeferredShader<Shader<PaddedVertex, EmptyMaterial>>;
+        let error = validate_vertex_interface::<PaddedShader>("embedded/unlit", UNLIT_VERT);
+        let Err(ShaderError::InterfaceMismatch(name, mismatches)) = error else {
+            panic!("expected interface mismatch, got {:?}", error);
+        };
+        assert_eq!(name, "embedded/unlit");
+        // Shader inputs match the attributes, only the binding stride differs
+        let [InterfaceMismatch::VertexStride {
+            binding,
+            expected,
+            found,
+        }] = mismatches[..]
+        else {
+            panic!(
+                "expected single vertex stride mismatch, got {:?}",
+                mismatches
+            );
+        };
+        assert_eq!(binding, 0);
+        assert_eq!(expected, size_of::<PaddedVertex>() as u32);
+        assert_eq!(found, size_of::<CommonVertex>() as u32);
+    }
+
+    #[test]
+    fn test_embedded_error_reads_position_and_model_matrix() {
+        let interface = reflect_shader_interface(ERROR_VERT).unwrap();
+        assert_eq!(interface.inputs, vec![(0, vk::Format::R32G32B32A32_SFLOAT)]);
+        assert_eq!(interface.push_constant_size, Some(ERROR_PUSH_CONSTANT_SIZE));
+        assert!(reflect_shader_interface(ERROR_FRAG).is_some());
+        assert!(is_error_shader_compatible::<UnlitShader>());
+    }
+}
@@ -21,6 +21,13 @@ pub trait GraphicsPipelineConfig: 'static {
     type PipelineStates: PipelineStates;
     type RenderPass: RenderPassConfig<Attachments = Self::Attachments>;
     type Subpass: Subpass<Self::Attachments>;
+    type TransparentStates: PipelineStates;
+    type TransparentSubpass: Subpass<Self::Attachments>;
+
+    // Pipeline is created for the transparent subpass and states instead when set
+    fn transparent() -> bool {
+        false
+    }
 }
 
 pub struct GraphicsPipelineBuilder<
@@ -40,4 +47,6 @@ impl<L: Layout, P: PipelineStates, R: RenderPassConfig, S: Subpass<R::Attachment
     type PipelineStates = P;
     type RenderPass = R;
     type Subpass = S;
+    type TransparentStates = P;
+    type TransparentSubpass = S;
 }
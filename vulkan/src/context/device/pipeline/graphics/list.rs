@@ -1,22 +1,24 @@
 use crate::context::{
     device::{pipeline::ModuleLoader, Device},
-    error::VkResult,
+    error::{VkError, VkResult},
 };
 use graphics::shader::ShaderType;
 use type_kit::{Cons, Create, Destroy, Nil, TypeList};
 
-use super::{GraphicsPipelineConfig, PipelinePack, PipelinePackRef, PipelinePackRefMut};
+use super::{
+    GraphicsPipelineConfig, PipelinePack, PipelinePackRef, PipelinePackRefMut, ShaderLoadConfig,
+};
 
 pub trait GraphicsPipelineListBuilder: TypeList {
     type Pack: GraphicsPipelinePackList;
 
-    fn build(&self, device: &Device) -> VkResult<Self::Pack>;
+    fn build(&self, device: &Device, config: ShaderLoadConfig) -> VkResult<Self::Pack>;
 }
 
 impl GraphicsPipelineListBuilder for Nil {
     type Pack = Nil;
 
-    fn build(&self, _device: &Device) -> VkResult<Self::Pack> {
+    fn build(&self, _device: &Device, _config: ShaderLoadConfig) -> VkResult<Self::Pack> {
         Ok(Nil::new())
     }
 }
@@ -26,12 +28,12 @@ impl<T: GraphicsPipelineConfig + ModuleLoader + ShaderType, N: GraphicsPipelineL
 {
     type Pack = Cons<PipelinePack<T>, N::Pack>;
 
-    fn build(&self, device: &Device) -> VkResult<Self::Pack> {
+    fn build(&self, device: &Device, config: ShaderLoadConfig) -> VkResult<Self::Pack> {
         let mut pack = PipelinePack::create((), device)?;
-        device.load_pipelines(&mut pack, &self.head)?;
+        device.load_pipelines(&mut pack, &self.head, config)?;
         Ok(Cons {
             head: pack,
-            tail: self.tail.build(device)?,
+            tail: self.tail.build(device, config)?,
         })
     }
 }
@@ -39,6 +41,10 @@ impl<T: GraphicsPipelineConfig + ModuleLoader + ShaderType, N: GraphicsPipelineL
 pub trait GraphicsPipelinePackList: TypeList + 'static {
     fn destroy(&mut self, device: &Device);
 
+    fn reload_modified(&mut self, device: &Device) -> Vec<VkError>;
+
+    fn reload_all(&mut self, device: &Device) -> Vec<VkError>;
+
     fn try_get<P: GraphicsPipelineConfig>(&self) -> Option<PipelinePackRef<P>>;
 
     fn try_get_mut<P: GraphicsPipelineConfig>(&mut self) -> Option<PipelinePackRefMut<P>>;
@@ -47,6 +53,14 @@ pub trait GraphicsPipelinePackList: TypeList + 'static {
 impl GraphicsPipelinePackList for Nil {
     fn destroy(&mut self, _device: &Device) {}
 
+    fn reload_modified(&mut self, _device: &Device) -> Vec<VkError> {
+        Vec::new()
+    }
+
+    fn reload_all(&mut self, _device: &Device) -> Vec<VkError> {
+        Vec::new()
+    }
+
     fn try_get<P: GraphicsPipelineConfig>(&self) -> Option<PipelinePackRef<P>> {
         None
     }
@@ -64,6 +78,18 @@ impl<T: GraphicsPipelineConfig + ShaderType, N: GraphicsPipelinePackList> Graphi
         self.tail.destroy(device);
     }
 
+    fn reload_modified(&mut self, device: &Device) -> Vec<VkError> {
+        let mut errors = self.head.reload_modified(device);
+        errors.extend(self.tail.reload_modified(device));
+        errors
+    }
+
+    fn reload_all(&mut self, device: &Device) -> Vec<VkError> {
+        let mut errors = self.head.reload_all(device);
+        errors.extend(self.tail.reload_all(device));
+        errors
+    }
+
     fn try_get<P: GraphicsPipelineConfig>(&self) -> Option<PipelinePackRef<P>> {
         if let Ok(pipelines) = (&self.head).try_into() {
             Some(pipelines)
@@ -2,19 +2,24 @@ use std::{
     any::{type_name, TypeId},
     convert::Infallible,
     marker::PhantomData,
+    path::{Path, PathBuf},
+    time::SystemTime,
 };
 
 use ash::vk;
 use bytemuck::AnyBitPattern;
+use graphics::shader::{EmbeddedShader, PolygonMode, ShaderType};
 use type_kit::{Create, CreateResult, Destroy, DestroyResult};
 
 use crate::context::{
     device::{
         pipeline::{
-            get_pipeline_states_info, Layout, ModuleLoader, PipelineBindData, PipelineLayout,
-            PushConstant, PushConstantDataRef,
+            get_embedded_modules, get_pipeline_states_info, is_error_shader_compatible,
+            validate_shader_interface, validate_vertex_interface, Layout, ModuleLoader,
+            PipelineBindData, PipelineLayout, PipelineStates, PushConstant, PushConstantDataRef,
+            ShaderDirectory,
         },
-        render_pass::RenderPassConfig,
+        render_pass::{RenderPassConfig, Subpass},
         Device,
     },
     error::{VkError, VkResult},
@@ -22,9 +27,62 @@ use crate::context::{
 
 use super::GraphicsPipelineConfig;
 
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ShaderLoadConfig {
+    // Checks vertex shader interface against the pipeline config before creating the pipeline
+    pub validate: bool,
+    // Shader errors are returned instead of substituting the embedded error shader
+    pub strict: bool,
+}
+
+// Shader directory the pipeline was loaded from, used to detect modified SPIR-V files
+#[derive(Debug)]
+struct PipelineSource {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    polygon_mode: PolygonMode,
+}
+
+impl PipelineSource {
+    fn new(path: &Path, polygon_mode: PolygonMode) -> Self {
+        Self {
+            path: path.to_owned(),
+            modified: get_last_modified(path),
+            polygon_mode,
+        }
+    }
+
+    fn load<T: GraphicsPipelineConfig>(
+        &self,
+        layout: vk::PipelineLayout,
+        device: &Device,
+    ) -> VkResult<GraphicsPipeline<T>> {
+        GraphicsPipeline::create(
+            (
+                PipelineLayout {
+                    layout,
+                    _phantom: PhantomData,
+                },
+                &ShaderDirectory::new(&self.path),
+                self.polygon_mode,
+            ),
+            device,
+        )
+    }
+}
+
+fn get_last_modified(path: &Path) -> Option<SystemTime> {
+    path.read_dir()
+        .ok()?
+        .flatten()
+        .filter_map(|entry| entry.metadata().ok()?.modified().ok())
+        .max()
+}
+
 #[derive(Debug)]
 pub struct PipelinePackData {
     pipelines: Vec<vk::Pipeline>,
+    sources: Vec<Option<PipelineSource>>,
     layout: vk::PipelineLayout,
 }
 
@@ -42,54 +100,29 @@ pub struct GraphicsPipeline<T: GraphicsPipelineConfig> {
 }
 
 impl<T: GraphicsPipelineConfig> Create for GraphicsPipeline<T> {
-    type Config<'a> = (PipelineLayout<T::Layout>, &'a dyn ModuleLoader);
+    type Config<'a> = (PipelineLayout<T::Layout>, &'a dyn ModuleLoader, PolygonMode);
     type CreateError = VkError;
 
     fn create<'a, 'b>(
         config: Self::Config<'a>,
         context: Self::Context<'b>,
     ) -> type_kit::CreateResult<Self> {
-        let (layout, modules) = config;
-        let extent = context
-            .physical_device
-            .surface_properties
-            .get_current_extent();
+        let (layout, modules, polygon_mode) = config;
         let layout = layout.into();
-        let render_pass = context.get_render_pass::<T::RenderPass>()?;
-        let states = get_pipeline_states_info::<T::Attachments, T::Subpass, T::PipelineStates>(
-            &context.physical_device,
-            extent,
-        );
-        let modules = modules.load(context)?;
-        let stages = modules.get_stages_info();
-        let subpass = T::RenderPass::try_get_subpass_index::<T::Subpass>().unwrap_or_else(|| {
-            panic!(
-                "Subpass {} not present in RenderPass {}!",
-                type_name::<T::Subpass>(),
-                type_name::<T::RenderPass>(),
-            )
-        }) as u32;
-        let create_infos = [vk::GraphicsPipelineCreateInfo {
-            subpass,
-            layout,
-            render_pass: render_pass.handle,
-            p_vertex_input_state: &states.vertex_input.create_info,
-            p_input_assembly_state: &states.input_assembly,
-            p_viewport_state: &states.viewport.create_info,
-            p_rasterization_state: &states.rasterization,
-            p_depth_stencil_state: &states.depth_stencil,
-            p_color_blend_state: &states.color_blend.create_info,
-            p_multisample_state: &states.multisample,
-            stage_count: stages.stages.len() as u32,
-            p_stages: stages.stages.as_ptr(),
-            ..Default::default()
-        }];
-        let &handle = unsafe {
-            context
-                .create_graphics_pipelines(vk::PipelineCache::null(), &create_infos, None)
-                .map_err(|(_, err)| err)?
-                .first()
-                .unwrap()
+        let handle = if T::transparent() {
+            create_pipeline::<T, T::TransparentStates, T::TransparentSubpass>(
+                context,
+                layout,
+                modules,
+                polygon_mode,
+            )?
+        } else {
+            create_pipeline::<T, T::PipelineStates, T::Subpass>(
+                context,
+                layout,
+                modules,
+                polygon_mode,
+            )?
         };
         Ok(GraphicsPipeline {
             handle,
@@ -99,6 +132,62 @@ impl<T: GraphicsPipelineConfig> Create for GraphicsPipeline<T> {
     }
 }
 
+fn create_pipeline<T: GraphicsPipelineConfig, P: PipelineStates, S: Subpass<T::Attachments>>(
+    device: &Device,
+    layout: vk::PipelineLayout,
+    modules: &dyn ModuleLoader,
+    polygon_mode: PolygonMode,
+) -> VkResult<vk::Pipeline> {
+    let extent = device
+        .physical_device
+        .surface_properties
+        .get_current_extent();
+    let render_pass = device.get_render_pass::<T::RenderPass>()?;
+    let states = get_pipeline_states_info::<T::Attachments, S, P>(
+        &device.physical_device,
+        extent,
+        polygon_mode,
+    );
+    let modules = modules.load(device)?;
+    let stages = modules.get_stages_info();
+    // Viewport and scissor are set when recording commands, so that single
+    // pipeline serves render targets of any extent
+    let dynamic_states = [vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
+    let dynamic_state =
+        vk::PipelineDynamicStateCreateInfo::builder().dynamic_states(&dynamic_states);
+    let subpass = T::RenderPass::try_get_subpass_index::<S>().unwrap_or_else(|| {
+        panic!(
+            "Subpass {} not present in RenderPass {}!",
+            type_name::<S>(),
+            type_name::<T::RenderPass>(),
+        )
+    }) as u32;
+    let create_infos = [vk::GraphicsPipelineCreateInfo {
+        subpass,
+        layout,
+        render_pass: render_pass.handle,
+        p_vertex_input_state: &states.vertex_input.create_info,
+        p_input_assembly_state: &states.input_assembly,
+        p_viewport_state: &states.viewport.create_info,
+        p_rasterization_state: &states.rasterization,
+        p_depth_stencil_state: &states.depth_stencil,
+        p_color_blend_state: &states.color_blend.create_info,
+        p_multisample_state: &states.multisample,
+        p_dynamic_state: &*dynamic_state,
+        stage_count: stages.stages.len() as u32,
+        p_stages: stages.stages.as_ptr(),
+        ..Default::default()
+    }];
+    let &handle = unsafe {
+        device
+            .create_graphics_pipelines(vk::PipelineCache::null(), &create_infos, None)
+            .map_err(|(_, err)| err)?
+            .first()
+            .unwrap()
+    };
+    Ok(handle)
+}
+
 impl<T: GraphicsPipelineConfig> Destroy for GraphicsPipeline<T> {
     type Context<'a> = &'a Device;
     type DestroyError = Infallible;
@@ -174,6 +263,68 @@ impl<T: GraphicsPipelineConfig> PipelinePack<T> {
 
     pub fn insert(&mut self, pipeline: GraphicsPipeline<T>) {
         self.data.pipelines.push(pipeline.handle);
+        self.data.sources.push(None);
+    }
+
+    fn insert_with_source(
+        &mut self,
+        pipeline: GraphicsPipeline<T>,
+        source: &Path,
+        polygon_mode: PolygonMode,
+    ) {
+        self.data.pipelines.push(pipeline.handle);
+        self.data
+            .sources
+            .push(Some(PipelineSource::new(source, polygon_mode)));
+    }
+
+    // Recreates pipelines whose shader files changed since they were loaded,
+    // pipelines which failed to be recreated are kept unchanged
+    pub fn reload_modified(&mut self, device: &Device) -> Vec<VkError> {
+        self.reload(device, false)
+    }
+
+    // Recreates all pipelines loaded from shader directories,
+    // regardless of shader files modification time
+    pub fn reload_all(&mut self, device: &Device) -> Vec<VkError> {
+        self.reload(device, true)
+    }
+
+    fn reload(&mut self, device: &Device, force: bool) -> Vec<VkError> {
+        let layout = self.data.layout;
+        let mut errors = Vec::new();
+        let mut reloaded = Vec::new();
+        for (index, source) in self.data.sources.iter_mut().enumerate() {
+            if let Some(source) = source {
+                let modified = get_last_modified(&source.path);
+                if force || modified > source.modified {
+                    source.modified = source.modified.max(modified);
+                    match source.load::<T>(layout, device) {
+                        Ok(pipeline) => {
+                            if let Err(err) = device
+                                .set_debug_name(pipeline.handle, &source.path.to_string_lossy())
+                            {
+                                errors.push(err);
+                            }
+                            reloaded.push((index, pipeline))
+                        }
+                        Err(err) => errors.push(err),
+                    }
+                }
+            }
+        }
+        if !reloaded.is_empty() {
+            if let Err(err) = unsafe { device.device_wait_idle() } {
+                errors.push(err.into());
+            }
+            for (index, pipeline) in reloaded {
+                let old = std::mem::replace(&mut self.data.pipelines[index], pipeline.handle);
+                unsafe {
+                    device.destroy_pipeline(old, None);
+                }
+            }
+        }
+        errors
     }
 }
 
@@ -266,20 +417,87 @@ impl<'a, T: GraphicsPipelineConfig> PipelinePackRefMut<'a, T> {
 
     pub fn insert(&mut self, pipeline: GraphicsPipeline<T>) {
         self.data.pipelines.push(pipeline.handle);
+        self.data.sources.push(None);
+    }
+
+    // Recreates single pipeline from its shader directory regardless of modification time,
+    // pipeline handle is swapped in place so indices held by shader handles stay valid
+    pub fn reload_pipeline(&mut self, device: &Device, index: usize) -> VkResult<()> {
+        let source = self
+            .data
+            .sources
+            .get_mut(index)
+            .and_then(Option::as_mut)
+            .ok_or(VkError::MissingResource("PipelineSource"))?;
+        let pipeline = source.load::<T>(self.data.layout, device)?;
+        source.modified = get_last_modified(&source.path);
+        device.set_debug_name(pipeline.handle, &source.path.to_string_lossy())?;
+        unsafe { device.device_wait_idle()? };
+        let old = std::mem::replace(&mut self.data.pipelines[index], pipeline.handle);
+        unsafe {
+            device.destroy_pipeline(old, None);
+        }
+        Ok(())
     }
 }
 
 impl Device {
-    pub fn load_pipelines<S: GraphicsPipelineConfig + ModuleLoader>(
+    pub fn load_pipelines<S: GraphicsPipelineConfig + ModuleLoader + ShaderType>(
         &self,
         pack: &mut PipelinePack<S>,
         pipelines: &[S],
+        config: ShaderLoadConfig,
     ) -> VkResult<()> {
         for pipeline in pipelines.iter() {
-            pack.insert(GraphicsPipeline::create((pack.layout(), pipeline), self)?);
+            let polygon_mode = pipeline.polygon_mode();
+            if polygon_mode != PolygonMode::Fill && !self.wireframe_supported() {
+                Err(VkError::FeatureNotSupported("fillModeNonSolid"))?;
+            }
+            let created = match self.load_pipeline(pack, pipeline, polygon_mode, config) {
+                Err(VkError::ShaderError(err))
+                    if !config.strict && is_error_shader_compatible::<S>() =>
+                {
+                    eprintln!(
+                        "Failed to load shader {}, using error shader instead: {}",
+                        pipeline.source().to_string_lossy(),
+                        err
+                    );
+                    GraphicsPipeline::create(
+                        (pack.layout(), &EmbeddedShader::Error, polygon_mode),
+                        self,
+                    )?
+                }
+                result => result?,
+            };
+            self.set_debug_name(created.handle, &pipeline.source().to_string_lossy())?;
+            // Source of the failed shader is kept, so that hot reload replaces
+            // the error shader once the shader files are fixed
+            match pipeline.embedded_shader() {
+                Some(_) => pack.insert(created),
+                None => pack.insert_with_source(created, pipeline.source(), polygon_mode),
+            }
         }
         Ok(())
     }
+
+    fn load_pipeline<S: GraphicsPipelineConfig + ModuleLoader + ShaderType>(
+        &self,
+        pack: &PipelinePack<S>,
+        pipeline: &S,
+        polygon_mode: PolygonMode,
+        config: ShaderLoadConfig,
+    ) -> VkResult<GraphicsPipeline<S>> {
+        if config.validate {
+            match pipeline.embedded_shader() {
+                Some(embedded) => {
+                    let [(code, _), _] = get_embedded_modules(embedded);
+                    validate_vertex_interface::<S>(&pipeline.source().to_string_lossy(), code)?
+                }
+                None => validate_shader_interface::<S>(pipeline.source())?,
+            }
+        }
+        GraphicsPipeline::create((pack.layout(), pipeline, polygon_mode), self)
+    }
 }
 
 impl<T: GraphicsPipelineConfig> Create for PipelinePack<T> {
@@ -290,6 +508,7 @@ impl<T: GraphicsPipelineConfig> Create for PipelinePack<T> {
         let layout = context.get_pipeline_layout::<T::Layout>()?.into();
         let data = PipelinePackData {
             pipelines: Vec::new(),
+            sources: Vec::new(),
             layout,
         };
         Ok(PipelinePack {
@@ -310,3 +529,89 @@ impl<T: GraphicsPipelineConfig> Destroy for PipelinePack<T> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use graphics::{
+        model::{CommonVertex, EmptyMaterial},
+        shader::Shader,
+    };
+
+    use crate::context::{
+        device::{renderer::deferred::DeferredShader, DeviceConfig},
+        Context, DebugConfig,
+    };
+
+    use super::*;
+
+    type TestShader = DeferredShader<Shader<CommonVertex, EmptyMaterial>>;
+
+    // Copies compiled shader directory, so that its files can be modified by the test
+    fn copy_shader_directory(source: &Path, name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("r_phy_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        for entry in source.read_dir().unwrap().flatten() {
+            std::fs::copy(entry.path(), path.join(entry.file_name())).unwrap();
+        }
+        path
+    }
+
+    #[test]
+    #[ignore = "requires Vulkan capable device"]
+    fn test_reload_pipeline_replaces_handle_after_shader_modified() {
+        let source = Path::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../_resources/shaders/spv/deferred/gbuffer_write/checker"
+        ));
+        let path = copy_shader_directory(source, "reload_pipeline");
+        let context = Context::build_offscreen(
+            vk::Extent2D {
+                width: 64,
+                height: 64,
+            },
+            DebugConfig::default(),
+            &DeviceConfig::default(),
+        )
+        .unwrap();
+        let mut pack = PipelinePack::<TestShader>::create((), &context).unwrap();
+        let shader: TestShader = Shader::new(&path.to_string_lossy()).into();
+        context
+            .load_pipelines(
+                &mut pack,
+                &[shader],
+                ShaderLoadConfig {
+                    validate: true,
+                    strict: true,
+                },
+            )
+            .unwrap();
+        let before = pack.get(0).handle;
+
+        // Rewriting SPIR-V file bumps its modification time
+        let module = path.read_dir().unwrap().flatten().next().unwrap().path();
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        std::fs::write(&module, std::fs::read(&module).unwrap()).unwrap();
+        PipelinePackRefMut::<TestShader>::try_from(&mut pack)
+            .unwrap()
+            .reload_pipeline(&context, 0)
+            .unwrap();
+        let after = pack.get(0).handle;
+        assert_ne!(before, after);
+        assert_eq!(pack.len(), 1);
+        assert_eq!(pack.layout().layout, pack.get(0).layout);
+
+        // Modification is recorded by the reload, pipeline is not recreated again
+        assert!(pack.reload_modified(&context).is_empty());
+        assert_eq!(pack.get(0).handle, after);
+
+        // Failed reload keeps the previous pipeline in place
+        std::fs::remove_dir_all(&path).unwrap();
+        let result = PipelinePackRefMut::<TestShader>::try_from(&mut pack)
+            .unwrap()
+            .reload_pipeline(&context, 0);
+        assert!(result.is_err());
+        assert_eq!(pack.get(0).handle, after);
+        pack.destroy(&context).unwrap();
+    }
+}
@@ -2,10 +2,15 @@ use graphics::model::CommonVertex;
 
 use crate::context::device::{
     pipeline::{
-        PipelineLayoutGBuffer, PipelineLayoutNoMaterial, PipelineLayoutSkybox,
-        StatesDepthTestEnabled, StatesDepthWriteDisabled, StatesSkybox,
+        PipelineLayoutGBuffer, PipelineLayoutNoMaterial, PipelineLayoutOverlay,
+        PipelineLayoutShadowMap, PipelineLayoutSkybox, StatesDepthTestEnabled,
+        StatesDepthWriteDisabled, StatesOverlay, StatesShadowMap, StatesSkybox,
     },
-    render_pass::{DeferedRenderPass, GBufferDepthPrepas, GBufferShadingPass, GBufferSkyboxPass},
+    render_pass::{
+        DeferedRenderPass, GBufferDepthPrepas, GBufferOverlayPass, GBufferShadingPass,
+        GBufferSkyboxPass, ShadowMapPass, ShadowMapRenderPass,
+    },
+    resources::OverlayVertex,
 };
 
 use super::GraphicsPipelineBuilder;
@@ -24,9 +29,16 @@ pub type GBufferSkyboxPipeline<At, Al> = GraphicsPipelineBuilder<
     GBufferSkyboxPass<At>,
 >;
 
-pub type GBufferDepthPrepasPipeline<A> = GraphicsPipelineBuilder<
+pub type GBufferOverlayPipeline<At, Al> = GraphicsPipelineBuilder<
+    PipelineLayoutOverlay<Al>,
+    StatesOverlay<OverlayVertex>,
+    DeferedRenderPass<At>,
+    GBufferOverlayPass<At>,
+>;
+
+pub type GBufferDepthPrepasPipeline<A, V = CommonVertex> = GraphicsPipelineBuilder<
     PipelineLayoutNoMaterial,
-    StatesDepthTestEnabled<CommonVertex>,
+    StatesDepthTestEnabled<V>,
     DeferedRenderPass<A>,
     GBufferDepthPrepas<A>,
 >;
@@ -37,3 +49,10 @@ pub type GBufferShadingPassPipeline<A> = GraphicsPipelineBuilder<
     DeferedRenderPass<A>,
     GBufferShadingPass<A>,
 >;
+
+pub type ShadowMapPipeline<A, V = CommonVertex> = GraphicsPipelineBuilder<
+    PipelineLayoutShadowMap,
+    StatesShadowMap<V>,
+    ShadowMapRenderPass<A>,
+    ShadowMapPass<A>,
+>;
@@ -3,7 +3,7 @@ mod presets;
 pub use presets::*;
 
 use std::{
-    any::TypeId,
+    any::{type_name, TypeId},
     collections::HashMap,
     marker::PhantomData,
     sync::{Once, RwLock},
@@ -16,7 +16,7 @@ use crate::context::{
         descriptor::{DescriptorBinding, DescriptorLayout},
         Device,
     },
-    error::VkResult,
+    error::{VkError, VkResult},
 };
 use type_kit::{Cons, Nil};
 
@@ -158,6 +158,39 @@ impl<N: PushConstantList> PushConstantRanges<N> {
     pub fn try_get_range<P: PushConstant>(&self) -> Option<vk::PushConstantRange> {
         Self::try_get_next_range::<P, N>(0)
     }
+
+    fn next_exceeding_range<L: PushConstantList>(
+        offset: u32,
+        limit: u32,
+    ) -> Option<(&'static str, u32)> {
+        if !L::exhausted() {
+            let range = L::Item::range(offset);
+            let end = offset + range.size;
+            if end > limit {
+                Some((type_name::<L::Item>(), end))
+            } else {
+                Self::next_exceeding_range::<L::Next>(end, limit)
+            }
+        } else {
+            None
+        }
+    }
+
+    // Name of the first push constant which range ends past the `limit`
+    // along with the end offset of its range
+    pub fn try_get_exceeding_range(limit: u32) -> Option<(&'static str, u32)> {
+        Self::next_exceeding_range::<N>(0, limit)
+    }
+}
+
+fn validate_push_constants<N: PushConstantList>(limit: u32) -> VkResult<()> {
+    match PushConstantRanges::<N>::try_get_exceeding_range(limit) {
+        Some((name, end)) => Err(VkError::ConfigError(format!(
+            "Push constant {} range ends at {} bytes, exceeding device limit of {} bytes",
+            name, end, limit
+        ))),
+        None => Ok(()),
+    }
 }
 
 pub trait DescriptorLayoutList: 'static {
@@ -337,6 +370,7 @@ impl Device {
     }
 
     pub fn get_pipeline_layout<L: Layout>(&self) -> VkResult<PipelineLayout<L>> {
+        validate_push_constants::<L::PushConstants>(self.max_push_constants_size())?;
         let push_ranges = PushConstantRanges::<L::PushConstants>::get_ranges();
         let layout_map = get_pipeline_layout_map();
         let layout = if let Some(layout) = {
@@ -373,3 +407,55 @@ impl Device {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::mem::size_of;
+
+    use super::*;
+
+    // Minimal maxPushConstantsSize guaranteed by the specification
+    const MIN_PUSH_CONSTANTS_SIZE: u32 = 128;
+
+    struct OversizedPushConstant {
+        _data: [f32; 32],
+    }
+
+    impl PushConstant for OversizedPushConstant {
+        fn range(offset: u32) -> vk::PushConstantRange {
+            vk::PushConstantRange {
+                stage_flags: vk::ShaderStageFlags::FRAGMENT,
+                offset,
+                size: size_of::<Self>() as u32,
+            }
+        }
+    }
+
+    #[test]
+    fn test_push_constants_within_limit_accepted() {
+        assert!(validate_push_constants::<Nil>(MIN_PUSH_CONSTANTS_SIZE).is_ok());
+        assert!(validate_push_constants::<Cons<ModelMatrix, Nil>>(MIN_PUSH_CONSTANTS_SIZE).is_ok());
+        assert!(validate_push_constants::<Cons<OversizedPushConstant, Nil>>(
+            MIN_PUSH_CONSTANTS_SIZE
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_oversized_push_constant_rejected() {
+        // Type fits the limit on its own, but its range starts after model matrix
+        type Constants = Cons<ModelMatrix, Cons<OversizedPushConstant, Nil>>;
+        assert_eq!(
+            PushConstantRanges::<Constants>::try_get_exceeding_range(MIN_PUSH_CONSTANTS_SIZE),
+            Some((type_name::<OversizedPushConstant>(), 192))
+        );
+        let Err(VkError::ConfigError(message)) =
+            validate_push_constants::<Constants>(MIN_PUSH_CONSTANTS_SIZE)
+        else {
+            panic!("Expected push constant limit error");
+        };
+        assert!(message.contains("OversizedPushConstant"));
+        assert!(message.contains("192"));
+        assert!(message.contains("128"));
+    }
+}
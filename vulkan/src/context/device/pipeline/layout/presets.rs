@@ -4,7 +4,10 @@ use ash::vk;
 use bytemuck::{Pod, Zeroable};
 
 use crate::context::device::{
-    descriptor::{CameraDescriptorSet, GBufferDescriptorSet, TextureDescriptorSet},
+    descriptor::{
+        FrameDescriptorSet, GBufferDescriptorSet, ShadowDescriptorSet, StorageImageDescriptorSet,
+        TextureDescriptorSet,
+    },
     resources::Material,
 };
 use graphics::renderer::camera::CameraMatrices;
@@ -64,15 +67,39 @@ impl PushConstant for CameraMatrices {
     }
 }
 
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Zeroable, Pod)]
+pub struct TonemapParams {
+    pub exposure: f32,
+}
+
+impl PushConstant for TonemapParams {
+    fn range(offset: u32) -> vk::PushConstantRange {
+        vk::PushConstantRange {
+            stage_flags: vk::ShaderStageFlags::COMPUTE,
+            offset,
+            size: size_of::<Self>() as u32,
+        }
+    }
+}
+
 pub type PipelineLayoutMaterial<M> = PipelineLayoutBuilder<
-    Cons<<M as Material>::DescriptorLayout, Cons<CameraDescriptorSet, Nil>>,
+    Cons<<M as Material>::DescriptorLayout, Cons<FrameDescriptorSet, Nil>>,
     Cons<ModelNormalMatrix, Nil>,
 >;
 
 pub type PipelineLayoutSkybox<A> =
     PipelineLayoutBuilder<Cons<TextureDescriptorSet<A>, Nil>, Cons<CameraMatrices, Nil>>;
 
+pub type PipelineLayoutOverlay<A> = PipelineLayoutBuilder<Cons<TextureDescriptorSet<A>, Nil>, Nil>;
+
 pub type PipelineLayoutNoMaterial =
-    PipelineLayoutBuilder<Cons<CameraDescriptorSet, Nil>, Cons<ModelMatrix, Nil>>;
+    PipelineLayoutBuilder<Cons<FrameDescriptorSet, Nil>, Cons<ModelMatrix, Nil>>;
+
+pub type PipelineLayoutGBuffer =
+    PipelineLayoutBuilder<Cons<ShadowDescriptorSet, Cons<GBufferDescriptorSet, Nil>>, Nil>;
+
+pub type PipelineLayoutShadowMap = PipelineLayoutBuilder<Nil, Cons<ModelMatrix, Nil>>;
 
-pub type PipelineLayoutGBuffer = PipelineLayoutBuilder<Cons<GBufferDescriptorSet, Nil>, Nil>;
+pub type PipelineLayoutTonemap =
+    PipelineLayoutBuilder<Cons<StorageImageDescriptorSet, Nil>, Cons<TonemapParams, Nil>>;
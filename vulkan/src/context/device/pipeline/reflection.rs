@@ -0,0 +1,368 @@
+use std::{collections::HashMap, path::Path};
+
+use ash::vk;
+
+use crate::context::error::{InterfaceMismatch, ShaderError, ShaderResult};
+
+use super::{
+    get_shader_input_format, GraphicsPipelineConfig, Layout, PipelineStates, PushConstantRanges,
+    VertexInput,
+};
+
+const SPIRV_MAGIC: u32 = 0x07230203;
+const SPIRV_HEADER_LEN: usize = 5;
+
+// Subset of SPIR-V opcodes, decorations and storage classes needed to
+// reflect vertex shader inputs and push constant block size
+const OP_DECORATE: u32 = 71;
+const OP_MEMBER_DECORATE: u32 = 72;
+const OP_TYPE_INT: u32 = 21;
+const OP_TYPE_FLOAT: u32 = 22;
+const OP_TYPE_VECTOR: u32 = 23;
+const OP_TYPE_MATRIX: u32 = 24;
+const OP_TYPE_ARRAY: u32 = 28;
+const OP_TYPE_STRUCT: u32 = 30;
+const OP_TYPE_POINTER: u32 = 32;
+const OP_CONSTANT: u32 = 43;
+const OP_VARIABLE: u32 = 59;
+
+const DECORATION_MATRIX_STRIDE: u32 = 7;
+const DECORATION_ARRAY_STRIDE: u32 = 6;
+const DECORATION_BUILT_IN: u32 = 11;
+const DECORATION_LOCATION: u32 = 30;
+const DECORATION_OFFSET: u32 = 35;
+
+const STORAGE_CLASS_INPUT: u32 = 1;
+const STORAGE_CLASS_PUSH_CONSTANT: u32 = 9;
+
+#[derive(Debug, Clone)]
+enum SpirvType {
+    Int { width: u32, signed: bool },
+    Float { width: u32 },
+    Vector { component: u32, count: u32 },
+    Matrix { column: u32, count: u32 },
+    Array { element: u32, length: u32 },
+    Struct { members: Vec<u32> },
+    Pointer { pointee: u32 },
+}
+
+#[derive(Debug, Default)]
+struct SpirvModule {
+    types: HashMap<u32, SpirvType>,
+    constants: HashMap<u32, u32>,
+    variables: Vec<(u32, u32, u32)>,
+    locations: HashMap<u32, u32>,
+    built_ins: Vec<u32>,
+    array_strides: HashMap<u32, u32>,
+    member_offsets: HashMap<(u32, u32), u32>,
+    member_matrix_strides: HashMap<(u32, u32), u32>,
+}
+
+#[derive(Debug, Default)]
+pub struct ShaderInterface {
+    pub inputs: Vec<(u32, vk::Format)>,
+    pub push_constant_size: Option<u32>,
+}
+
+fn read_words(code: &[u8]) -> Option<Vec<u32>> {
+    if !code.len().is_multiple_of(4) || code.len() < 4 * SPIRV_HEADER_LEN {
+        return None;
+    }
+    let words = code
+        .chunks_exact(4)
+        .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .collect::<Vec<_>>();
+    match words[0] {
+        SPIRV_MAGIC => Some(words),
+        magic if magic.swap_bytes() == SPIRV_MAGIC => {
+            Some(words.into_iter().map(u32::swap_bytes).collect())
+        }
+        _ => None,
+    }
+}
+
+impl SpirvModule {
+    fn parse(words: &[u32]) -> Option<Self> {
+        let mut module = SpirvModule::default();
+        let mut offset = SPIRV_HEADER_LEN;
+        while offset < words.len() {
+            let word_count = (words[offset] >> 16) as usize;
+            let opcode = words[offset] & 0xffff;
+            if word_count == 0 || offset + word_count > words.len() {
+                return None;
+            }
+            let operands = &words[offset + 1..offset + word_count];
+            module.parse_instruction(opcode, operands)?;
+            offset += word_count;
+        }
+        Some(module)
+    }
+
+    fn parse_instruction(&mut self, opcode: u32, operands: &[u32]) -> Option<()> {
+        match opcode {
+            OP_DECORATE => match *operands.get(1)? {
+                DECORATION_LOCATION => {
+                    self.locations.insert(operands[0], *operands.get(2)?);
+                }
+                DECORATION_BUILT_IN => self.built_ins.push(operands[0]),
+                DECORATION_ARRAY_STRIDE => {
+                    self.array_strides.insert(operands[0], *operands.get(2)?);
+                }
+                _ => (),
+            },
+            OP_MEMBER_DECORATE => match *operands.get(2)? {
+                DECORATION_OFFSET => {
+                    self.member_offsets
+                        .insert((operands[0], operands[1]), *operands.get(3)?);
+                }
+                DECORATION_MATRIX_STRIDE => {
+                    self.member_matrix_strides
+                        .insert((operands[0], operands[1]), *operands.get(3)?);
+                }
+                _ => (),
+            },
+            OP_TYPE_INT => {
+                self.types.insert(
+                    *operands.first()?,
+                    SpirvType::Int {
+                        width: *operands.get(1)?,
+                        signed: *operands.get(2)? != 0,
+                    },
+                );
+            }
+            OP_TYPE_FLOAT => {
+                self.types.insert(
+                    *operands.first()?,
+                    SpirvType::Float {
+                        width: *operands.get(1)?,
+                    },
+                );
+            }
+            OP_TYPE_VECTOR => {
+                self.types.insert(
+                    *operands.first()?,
+                    SpirvType::Vector {
+                        component: *operands.get(1)?,
+                        count: *operands.get(2)?,
+                    },
+                );
+            }
+            OP_TYPE_MATRIX => {
+                self.types.insert(
+                    *operands.first()?,
+                    SpirvType::Matrix {
+                        column: *operands.get(1)?,
+                        count: *operands.get(2)?,
+                    },
+                );
+            }
+            OP_TYPE_ARRAY => {
+                let length = *self.constants.get(operands.get(2)?)?;
+                self.types.insert(
+                    *operands.first()?,
+                    SpirvType::Array {
+                        element: *operands.get(1)?,
+                        length,
+                    },
+                );
+            }
+            OP_TYPE_STRUCT => {
+                self.types.insert(
+                    *operands.first()?,
+                    SpirvType::Struct {
+                        members: operands[1..].to_vec(),
+                    },
+                );
+            }
+            OP_TYPE_POINTER => {
+                self.types.insert(
+                    *operands.first()?,
+                    SpirvType::Pointer {
+                        pointee: *operands.get(2)?,
+                    },
+                );
+            }
+            OP_CONSTANT => {
+                // Only 32-bit constants are relevant, as used for array lengths
+                self.constants.insert(*operands.get(1)?, *operands.get(2)?);
+            }
+            OP_VARIABLE => {
+                self.variables
+                    .push((*operands.first()?, *operands.get(1)?, *operands.get(2)?));
+            }
+            _ => (),
+        }
+        Some(())
+    }
+
+    fn get_format(&self, type_id: u32) -> vk::Format {
+        let (component, count) = match self.types.get(&type_id) {
+            Some(SpirvType::Vector { component, count }) => (*component, *count),
+            Some(_) => (type_id, 1),
+            None => return vk::Format::UNDEFINED,
+        };
+        match (self.types.get(&component), count) {
+            (Some(SpirvType::Float { width: 32 }), 1) => vk::Format::R32_SFLOAT,
+            (Some(SpirvType::Float { width: 32 }), 2) => vk::Format::R32G32_SFLOAT,
+            (Some(SpirvType::Float { width: 32 }), 3) => vk::Format::R32G32B32_SFLOAT,
+            (Some(SpirvType::Float { width: 32 }), 4) => vk::Format::R32G32B32A32_SFLOAT,
+            (Some(SpirvType::Int { width: 32, signed }), count) => match (signed, count) {
+                (true, 1) => vk::Format::R32_SINT,
+                (true, 2) => vk::Format::R32G32_SINT,
+                (true, 3) => vk::Format::R32G32B32_SINT,
+                (true, 4) => vk::Format::R32G32B32A32_SINT,
+                (false, 1) => vk::Format::R32_UINT,
+                (false, 2) => vk::Format::R32G32_UINT,
+                (false, 3) => vk::Format::R32G32B32_UINT,
+                (false, 4) => vk::Format::R32G32B32A32_UINT,
+                _ => vk::Format::UNDEFINED,
+            },
+            _ => vk::Format::UNDEFINED,
+        }
+    }
+
+    fn get_size(&self, type_id: u32, matrix_stride: Option<u32>) -> Option<u32> {
+        match self.types.get(&type_id)? {
+            SpirvType::Int { width, .. } | SpirvType::Float { width } => Some(width / 8),
+            SpirvType::Vector { component, count } => {
+                Some(self.get_size(*component, None)? * count)
+            }
+            SpirvType::Matrix { column, count } => {
+                let stride = match matrix_stride {
+                    Some(stride) => stride,
+                    None => self.get_size(*column, None)?,
+                };
+                Some(stride * count)
+            }
+            SpirvType::Array { element, length } => {
+                let stride = match self.array_strides.get(&type_id) {
+                    Some(&stride) => stride,
+                    None => self.get_size(*element, matrix_stride)?,
+                };
+                Some(stride * length)
+            }
+            SpirvType::Struct { members } => members
+                .iter()
+                .zip(0u32..)
+                .map(|(&member, index)| {
+                    let offset = self.member_offsets.get(&(type_id, index)).copied()?;
+                    let stride = self.member_matrix_strides.get(&(type_id, index)).copied();
+                    Some(offset + self.get_size(member, stride)?)
+                })
+                .try_fold(0, |size, end| Some(size.max(end?))),
+            SpirvType::Pointer { .. } => None,
+        }
+    }
+
+    fn get_interface(&self) -> Option<ShaderInterface> {
+        let mut interface = ShaderInterface::default();
+        for &(type_id, variable, storage_class) in &self.variables {
+            let pointee = match self.types.get(&type_id)? {
+                SpirvType::Pointer { pointee } => *pointee,
+                _ => return None,
+            };
+            match storage_class {
+                STORAGE_CLASS_INPUT if !self.built_ins.contains(&variable) => {
+                    if let Some(&location) = self.locations.get(&variable) {
+                        interface.inputs.push((location, self.get_format(pointee)));
+                    }
+                }
+                STORAGE_CLASS_PUSH_CONSTANT => {
+                    interface.push_constant_size = Some(self.get_size(pointee, None)?);
+                }
+                _ => (),
+            }
+        }
+        interface.inputs.sort_by_key(|&(location, _)| location);
+        Some(interface)
+    }
+}
+
+pub fn reflect_shader_interface(code: &[u8]) -> Option<ShaderInterface> {
+    let words = read_words(code)?;
+    SpirvModule::parse(&words)?.get_interface()
+}
+
+fn find_vertex_module(directory: &Path) -> ShaderResult<Option<std::path::PathBuf>> {
+    for entry in directory
+        .read_dir()
+        .map_err(ShaderError::io(directory))?
+        .flatten()
+    {
+        let path = entry.path();
+        if path.file_stem().and_then(|stem| stem.to_str()) == Some("vert") {
+            return Ok(Some(path));
+        }
+    }
+    Ok(None)
+}
+
+// Compares vertex shader inputs and push constant block found in the SPIR-V module
+// against the vertex input state and push constant ranges declared by the pipeline config
+pub fn validate_shader_interface<C: GraphicsPipelineConfig>(directory: &Path) -> ShaderResult<()> {
+    let Some(path) = find_vertex_module(directory)? else {
+        return Ok(());
+    };
+    let code = std::fs::read(&path).map_err(ShaderError::io(&path))?;
+    validate_vertex_interface::<C>(&path.to_string_lossy(), &code)
+}
+
+// Same as `validate_shader_interface` for vertex module already read into memory,
+// `name` identifies the module in returned errors
+pub fn validate_vertex_interface<C: GraphicsPipelineConfig>(
+    name: &str,
+    code: &[u8],
+) -> ShaderResult<()> {
+    let interface = reflect_shader_interface(code)
+        .ok_or_else(|| ShaderError::InvalidSpirv(name.to_string()))?;
+    let attributes =
+        <<C::PipelineStates as PipelineStates>::VertexInput as VertexInput>::get_attribute_descriptions();
+    let mut mismatches = interface
+        .inputs
+        .iter()
+        .filter_map(|&(location, found)| {
+            let expected = attributes
+                .iter()
+                .find(|attribute| attribute.location == location)
+                .map(|attribute| get_shader_input_format(attribute.format));
+            (expected != Some(found)).then_some(InterfaceMismatch::VertexInput {
+                location,
+                expected,
+                found,
+            })
+        })
+        .collect::<Vec<_>>();
+    // Declared stride must match the size of the vertex type, otherwise
+    // each consecutive vertex would be read from shifted offset
+    let bindings =
+        <<C::PipelineStates as PipelineStates>::VertexInput as VertexInput>::get_binding_descriptions();
+    let strides =
+        <<C::PipelineStates as PipelineStates>::VertexInput as VertexInput>::get_memory_strides();
+    mismatches.extend(
+        bindings
+            .iter()
+            .zip(strides)
+            .filter(|&(binding, expected)| binding.stride != expected)
+            .map(|(binding, expected)| InterfaceMismatch::VertexStride {
+                binding: binding.binding,
+                expected,
+                found: binding.stride,
+            }),
+    );
+    if let Some(found) = interface.push_constant_size {
+        let expected = PushConstantRanges::<<C::Layout as Layout>::PushConstants>::get_ranges()
+            .iter()
+            .filter(|range| range.stage_flags.contains(vk::ShaderStageFlags::VERTEX))
+            .map(|range| range.offset + range.size)
+            .max()
+            .unwrap_or(0);
+        if found > expected {
+            mismatches.push(InterfaceMismatch::PushConstantSize { expected, found });
+        }
+    }
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(ShaderError::InterfaceMismatch(name.to_string(), mismatches))
+    }
+}
@@ -13,7 +13,10 @@ use crate::context::device::{
     render_pass::Subpass,
     AttachmentProperties, PhysicalDevice, PhysicalDeviceProperties,
 };
-use graphics::model::{Vertex, VertexNone};
+use graphics::{
+    model::{ComponentFormat, Vertex, VertexNone},
+    shader::PolygonMode,
+};
 use type_kit::{Cons, Nil};
 
 pub struct VertexInputInfo {
@@ -43,21 +46,37 @@ pub trait VertexInput: 'static {
     fn get_binding_descriptions() -> Vec<vk::VertexInputBindingDescription>;
 
     fn get_attribute_descriptions() -> Vec<vk::VertexInputAttributeDescription>;
+
+    // Sizes of host vertex types bound at consecutive bindings
+    fn get_memory_strides() -> Vec<u32>;
 }
 
 pub trait VertexBinding: 'static {
     fn get_binding_description(binding: u32) -> vk::VertexInputBindingDescription;
 
     fn get_attribute_descriptions(binding: u32) -> Vec<vk::VertexInputAttributeDescription>;
+
+    fn get_memory_stride() -> u32;
 }
 
-fn infer_vertex_format(size: usize) -> vk::Format {
-    match size {
-        4 => vk::Format::R32_SFLOAT,
-        8 => vk::Format::R32G32_SFLOAT,
-        12 => vk::Format::R32G32B32_SFLOAT,
-        16 => vk::Format::R32G32B32A32_SFLOAT,
-        _ => panic!("Unsupported vertex component size"),
+fn get_vertex_format(format: ComponentFormat) -> vk::Format {
+    match format {
+        ComponentFormat::Float => vk::Format::R32_SFLOAT,
+        ComponentFormat::Float2 => vk::Format::R32G32_SFLOAT,
+        ComponentFormat::Float3 => vk::Format::R32G32B32_SFLOAT,
+        ComponentFormat::Float4 => vk::Format::R32G32B32A32_SFLOAT,
+        ComponentFormat::Half2 => vk::Format::R16G16_SFLOAT,
+        ComponentFormat::Snorm16x2 => vk::Format::R16G16_SNORM,
+        ComponentFormat::Unorm16x4 => vk::Format::R16G16B16A16_UNORM,
+    }
+}
+
+// Float vector type through which the shader reads attribute of given format
+pub fn get_shader_input_format(format: vk::Format) -> vk::Format {
+    match format {
+        vk::Format::R16G16_SFLOAT | vk::Format::R16G16_SNORM => vk::Format::R32G32_SFLOAT,
+        vk::Format::R16G16B16A16_UNORM => vk::Format::R32G32B32A32_SFLOAT,
+        _ => format,
     }
 }
 
@@ -66,7 +85,7 @@ impl<V: Vertex> VertexBinding for V {
         let last = V::components().last().unwrap();
         vk::VertexInputBindingDescription {
             binding,
-            stride: (last.offset + last.size) as u32,
+            stride: (last.offset + last.format.size()) as u32,
             input_rate: vk::VertexInputRate::VERTEX,
         }
     }
@@ -79,12 +98,16 @@ impl<V: Vertex> VertexBinding for V {
                 |(component, location)| vk::VertexInputAttributeDescription {
                     binding,
                     location,
-                    format: infer_vertex_format(component.size),
+                    format: get_vertex_format(component.format),
                     offset: component.offset as u32,
                 },
             )
             .collect()
     }
+
+    fn get_memory_stride() -> u32 {
+        size_of::<V>() as u32
+    }
 }
 
 pub trait VertexBindingList: 'static {
@@ -161,6 +184,21 @@ impl<L: VertexBindingList> VertexBindingBuilder<L> {
         Self::next_attribute_descriptions::<L>(0, attributes.iter_mut());
         attributes.into_iter().flatten().collect()
     }
+
+    fn next_memory_stride<'a, N: VertexBindingList>(mut iter: impl Iterator<Item = &'a mut u32>) {
+        if !N::exhausted() {
+            if let Some(entry) = iter.next() {
+                *entry = N::Item::get_memory_stride();
+                Self::next_memory_stride::<N::Next>(iter)
+            }
+        }
+    }
+
+    pub fn get_memory_strides() -> Vec<u32> {
+        let mut strides = vec![0; L::len()];
+        Self::next_memory_stride::<L>(strides.iter_mut());
+        strides
+    }
 }
 
 impl<L: VertexBindingList> VertexInput for VertexBindingBuilder<L> {
@@ -171,6 +209,10 @@ impl<L: VertexBindingList> VertexInput for VertexBindingBuilder<L> {
     fn get_attribute_descriptions() -> Vec<vk::VertexInputAttributeDescription> {
         Self::get_attribute_descriptions()
     }
+
+    fn get_memory_strides() -> Vec<u32> {
+        Self::get_memory_strides()
+    }
 }
 
 pub trait VertexAssembly: 'static {
@@ -181,8 +223,72 @@ pub trait DepthStencil: 'static {
     fn get_state() -> vk::PipelineDepthStencilStateCreateInfo;
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct DepthBias {
+    pub constant: f32,
+    pub slope: f32,
+    pub clamp: f32,
+}
+
 pub trait Rasterization: 'static {
     fn get_state() -> vk::PipelineRasterizationStateCreateInfo;
+
+    fn depth_bias() -> Option<DepthBias>;
+}
+
+pub trait DepthBiasConfig: 'static {
+    fn get_depth_bias() -> DepthBias;
+}
+
+// Rasterization state `R` with depth bias `B` applied, used by depth only passes
+pub struct DepthBiased<R: Rasterization, B: DepthBiasConfig> {
+    _phantom: PhantomData<(R, B)>,
+}
+
+impl<R: Rasterization, B: DepthBiasConfig> Rasterization for DepthBiased<R, B> {
+    fn get_state() -> vk::PipelineRasterizationStateCreateInfo {
+        R::get_state()
+    }
+
+    fn depth_bias() -> Option<DepthBias> {
+        Some(B::get_depth_bias())
+    }
+}
+
+fn get_polygon_mode(polygon_mode: PolygonMode) -> vk::PolygonMode {
+    match polygon_mode {
+        PolygonMode::Fill => vk::PolygonMode::FILL,
+        PolygonMode::Line => vk::PolygonMode::LINE,
+    }
+}
+
+// Depth bias clamp requires depthBiasClamp device feature, left disabled otherwise
+fn get_rasterization_state<R: Rasterization>(
+    enabled_features: &vk::PhysicalDeviceFeatures,
+    polygon_mode: PolygonMode,
+) -> vk::PipelineRasterizationStateCreateInfo {
+    let state = vk::PipelineRasterizationStateCreateInfo {
+        polygon_mode: get_polygon_mode(polygon_mode),
+        ..R::get_state()
+    };
+    match R::depth_bias() {
+        Some(DepthBias {
+            constant,
+            slope,
+            clamp,
+        }) => vk::PipelineRasterizationStateCreateInfo {
+            depth_bias_enable: vk::TRUE,
+            depth_bias_constant_factor: constant,
+            depth_bias_slope_factor: slope,
+            depth_bias_clamp: if enabled_features.depth_bias_clamp == vk::TRUE {
+                clamp
+            } else {
+                0.0
+            },
+            ..state
+        },
+        None => state,
+    }
 }
 
 pub struct ViewportInfo {
@@ -378,12 +484,16 @@ pub struct PipelineStatesInfo<S: PipelineStates> {
 pub(super) fn get_pipeline_states_info<A: AttachmentList, P: Subpass<A>, S: PipelineStates>(
     physical_device: &PhysicalDevice,
     extent: Extent2D,
+    polygon_mode: PolygonMode,
 ) -> PipelineStatesInfo<S> {
     PipelineStatesInfo {
         vertex_input: S::VertexInput::get_state(),
         input_assembly: S::VertexAssembly::get_input_assembly(),
         viewport: S::Viewport::get_state(extent),
-        rasterization: S::Rasterization::get_state(),
+        rasterization: get_rasterization_state::<S::Rasterization>(
+            &physical_device.properties.enabled_features,
+            polygon_mode,
+        ),
         depth_stencil: S::DepthStencil::get_state(),
         color_blend: S::ColorBlend::get_state::<A>(&P::references()),
         multisample: S::Multisample::get_state(
@@ -393,3 +503,61 @@ pub(super) fn get_pipeline_states_info<A: AttachmentList, P: Subpass<A>, S: Pipe
         _phantom: PhantomData,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_polygon_mode_reaches_rasterization_state() {
+        let features = vk::PhysicalDeviceFeatures::default();
+        let state = get_rasterization_state::<CullBack>(&features, PolygonMode::Line);
+        assert_eq!(state.polygon_mode, vk::PolygonMode::LINE);
+        assert_eq!(state.cull_mode, vk::CullModeFlags::BACK);
+        let state = get_rasterization_state::<CullBack>(&features, PolygonMode::Fill);
+        assert_eq!(state.polygon_mode, vk::PolygonMode::FILL);
+    }
+
+    struct TestDepthBias {}
+
+    impl DepthBiasConfig for TestDepthBias {
+        fn get_depth_bias() -> DepthBias {
+            DepthBias {
+                constant: 2.0,
+                slope: 3.0,
+                clamp: 0.5,
+            }
+        }
+    }
+
+    #[test]
+    fn test_depth_bias_reaches_rasterization_state() {
+        let state = get_rasterization_state::<CullBack>(
+            &vk::PhysicalDeviceFeatures::default(),
+            PolygonMode::Fill,
+        );
+        assert_eq!(state.depth_bias_enable, vk::FALSE);
+
+        type Biased = DepthBiased<CullFront, TestDepthBias>;
+        let features = vk::PhysicalDeviceFeatures {
+            depth_bias_clamp: vk::TRUE,
+            ..Default::default()
+        };
+        let state = get_rasterization_state::<Biased>(&features, PolygonMode::Fill);
+        assert_eq!(state.depth_bias_enable, vk::TRUE);
+        assert_eq!(state.depth_bias_constant_factor, 2.0);
+        assert_eq!(state.depth_bias_slope_factor, 3.0);
+        assert_eq!(state.depth_bias_clamp, 0.5);
+        // Rasterization state of the wrapped preset is preserved
+        assert_eq!(state.cull_mode, vk::CullModeFlags::FRONT);
+
+        // Clamp is dropped when the device does not support it
+        let state = get_rasterization_state::<Biased>(
+            &vk::PhysicalDeviceFeatures::default(),
+            PolygonMode::Fill,
+        );
+        assert_eq!(state.depth_bias_enable, vk::TRUE);
+        assert_eq!(state.depth_bias_constant_factor, 2.0);
+        assert_eq!(state.depth_bias_clamp, 0.0);
+    }
+}
@@ -1,12 +1,15 @@
 use ash::vk;
 
-use crate::context::device::{AttachmentProperties, PhysicalDeviceProperties};
+use crate::context::device::{
+    resources::SHADOW_MAP_EXTENT, AttachmentProperties, PhysicalDeviceProperties,
+};
 use graphics::model::CommonVertex;
 use type_kit::{Cons, Nil};
 
 use super::{
-    Blend, ColorBlendBuilder, DepthStencil, Multisample, PipelineStatesBuilder, Rasterization,
-    VertexAssembly, VertexBindingBuilder, Viewport, ViewportInfo,
+    Blend, ColorBlendBuilder, DepthBias, DepthBiasConfig, DepthStencil, Multisample,
+    PipelineStatesBuilder, Rasterization, VertexAssembly, VertexBindingBuilder, Viewport,
+    ViewportInfo,
 };
 
 pub struct TriangleList {}
@@ -71,6 +74,10 @@ impl Rasterization for CullBack {
             ..Default::default()
         }
     }
+
+    fn depth_bias() -> Option<DepthBias> {
+        None
+    }
 }
 
 pub struct CullFront {}
@@ -85,6 +92,22 @@ impl Rasterization for CullFront {
             ..Default::default()
         }
     }
+
+    fn depth_bias() -> Option<DepthBias> {
+        None
+    }
+}
+
+pub struct ShadowMapDepthBias {}
+
+impl DepthBiasConfig for ShadowMapDepthBias {
+    fn get_depth_bias() -> DepthBias {
+        DepthBias {
+            constant: 1.25,
+            slope: 1.75,
+            clamp: 0.0,
+        }
+    }
 }
 
 pub struct ViewportDefault {}
@@ -118,6 +141,14 @@ impl Viewport for ViewportDefault {
     }
 }
 
+pub struct ViewportShadowMap {}
+
+impl Viewport for ViewportShadowMap {
+    fn get_state(_image_extent: vk::Extent2D) -> ViewportInfo {
+        ViewportDefault::get_state(SHADOW_MAP_EXTENT)
+    }
+}
+
 pub struct ViewportHalfLeft {}
 
 impl Viewport for ViewportHalfLeft {
@@ -224,6 +255,21 @@ impl Multisample for Multisampled {
     }
 }
 
+pub struct SingleSampled {}
+
+impl Multisample for SingleSampled {
+    fn get_state(
+        _device: &PhysicalDeviceProperties,
+        _attachments: &AttachmentProperties,
+    ) -> vk::PipelineMultisampleStateCreateInfo {
+        vk::PipelineMultisampleStateCreateInfo {
+            rasterization_samples: vk::SampleCountFlags::TYPE_1,
+            sample_shading_enable: vk::FALSE,
+            ..Default::default()
+        }
+    }
+}
+
 pub type MeshVertexInput<V> = VertexBindingBuilder<Cons<V, Nil>>;
 
 pub type StatesSkybox = PipelineStatesBuilder<
@@ -236,6 +282,16 @@ pub type StatesSkybox = PipelineStatesBuilder<
     Multisampled,
 >;
 
+pub type StatesOverlay<V> = PipelineStatesBuilder<
+    MeshVertexInput<V>,
+    TriangleList,
+    DepthTestDisabled,
+    CullBack,
+    ViewportDefault,
+    AlphaBlend,
+    SingleSampled,
+>;
+
 pub type StatesDepthWriteDisabled<V> = PipelineStatesBuilder<
     MeshVertexInput<V>,
     TriangleList,
@@ -246,6 +302,16 @@ pub type StatesDepthWriteDisabled<V> = PipelineStatesBuilder<
     Multisampled,
 >;
 
+pub type StatesTransparent<V> = PipelineStatesBuilder<
+    MeshVertexInput<V>,
+    TriangleList,
+    DepthWriteDisabled,
+    CullBack,
+    ViewportDefault,
+    AlphaBlend,
+    Multisampled,
+>;
+
 pub type StatesDepthTestEnabled<V> = PipelineStatesBuilder<
     MeshVertexInput<V>,
     TriangleList,
@@ -255,3 +321,13 @@ pub type StatesDepthTestEnabled<V> = PipelineStatesBuilder<
     AlphaBlend,
     Multisampled,
 >;
+
+pub type StatesShadowMap<V> = PipelineStatesBuilder<
+    MeshVertexInput<V>,
+    TriangleList,
+    DepthTestEnabled,
+    CullFront,
+    ViewportShadowMap,
+    AlphaBlend,
+    SingleSampled,
+>;
@@ -0,0 +1,176 @@
+use std::convert::Infallible;
+
+use ash::vk;
+use type_kit::{Create, CreateResult, Destroy, DestroyResult};
+
+use crate::context::error::{VkError, VkResult};
+
+use super::Device;
+
+fn ticks_to_milliseconds(ticks: u64, valid_bits: u32, timestamp_period: f32) -> f64 {
+    let ticks = match valid_bits {
+        64.. => ticks,
+        bits => ticks & ((1u64 << bits) - 1),
+    };
+    ticks as f64 * timestamp_period as f64 / 1_000_000.0
+}
+
+// Durations of the deferred renderer passes measured on the GPU, in milliseconds
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct GpuPassTimings {
+    pub gbuffer: f64,
+    pub lighting: f64,
+}
+
+// Pool of timestamp queries written by the graphics queue, queries are reset
+// with `RecordingCommand::reset_queries` before being written in a submission
+pub struct GpuProfiler {
+    query_pool: vk::QueryPool,
+    query_count: u32,
+    valid_bits: u32,
+    timestamp_period: f32,
+}
+
+impl GpuProfiler {
+    #[inline]
+    pub fn query_pool(&self) -> vk::QueryPool {
+        self.query_pool
+    }
+
+    #[inline]
+    pub fn query_count(&self) -> u32 {
+        self.query_count
+    }
+
+    // Timestamps of all queries in milliseconds, blocks until every query is written,
+    // so command writing them has to be submitted beforehand
+    pub fn resolve(&self, device: &Device) -> VkResult<Vec<f64>> {
+        let mut ticks = vec![0u64; self.query_count as usize];
+        unsafe {
+            device.get_query_pool_results(
+                self.query_pool,
+                0,
+                self.query_count,
+                &mut ticks,
+                vk::QueryResultFlags::TYPE_64 | vk::QueryResultFlags::WAIT,
+            )?;
+        }
+        Ok(ticks
+            .into_iter()
+            .map(|ticks| ticks_to_milliseconds(ticks, self.valid_bits, self.timestamp_period))
+            .collect())
+    }
+}
+
+impl Create for GpuProfiler {
+    type Config<'a> = u32;
+    type CreateError = VkError;
+
+    fn create<'a, 'b>(config: Self::Config<'a>, context: Self::Context<'b>) -> CreateResult<Self> {
+        let valid_bits = context.timestamp_valid_bits();
+        if valid_bits == 0 {
+            Err(VkError::ConfigError(
+                "Timestamp queries not supported by the graphics queue".to_string(),
+            ))?;
+        }
+        let query_pool = unsafe {
+            context.create_query_pool(
+                &vk::QueryPoolCreateInfo::builder()
+                    .query_type(vk::QueryType::TIMESTAMP)
+                    .query_count(config),
+                None,
+            )?
+        };
+        Ok(GpuProfiler {
+            query_pool,
+            query_count: config,
+            valid_bits,
+            timestamp_period: context.timestamp_period(),
+        })
+    }
+}
+
+impl Destroy for GpuProfiler {
+    type Context<'a> = &'a Device;
+    type DestroyError = Infallible;
+
+    fn destroy<'a>(&mut self, context: Self::Context<'a>) -> DestroyResult<Self> {
+        unsafe {
+            context.destroy_query_pool(self.query_pool, None);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use type_kit::DropGuard;
+
+    use crate::context::{
+        device::{
+            command::{operation::Graphics, SubmitSemaphoreState},
+            DeviceConfig,
+        },
+        Context, DebugConfig,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_ticks_converted_to_milliseconds() {
+        assert_eq!(ticks_to_milliseconds(2_000_000, 64, 1.0), 2.0);
+        assert_eq!(ticks_to_milliseconds(1_000, 64, 1000.0), 1.0);
+        // Bits above the valid range are undefined
+        assert_eq!(
+            ticks_to_milliseconds(u64::MAX, 32, 1.0),
+            u32::MAX as f64 / 1e6
+        );
+    }
+
+    #[test]
+    #[ignore = "requires Vulkan capable device"]
+    fn test_timestamps_around_workload_increase() {
+        let context = Context::build_offscreen(
+            vk::Extent2D {
+                width: 64,
+                height: 64,
+            },
+            DebugConfig::default(),
+            &DeviceConfig::default(),
+        )
+        .unwrap();
+        let mut profiler = DropGuard::new(GpuProfiler::create(2, &context).unwrap());
+        let command = context
+            .begin_primary_command(context.allocate_transient_command::<Graphics>().unwrap())
+            .unwrap();
+        let command = context.record_command(command, |command| {
+            command
+                .reset_queries(&profiler)
+                .write_timestamp(&profiler, 0, vk::PipelineStageFlags::TOP_OF_PIPE)
+                .memory_barrier(
+                    vk::PipelineStageFlags::ALL_COMMANDS,
+                    vk::AccessFlags::MEMORY_WRITE,
+                    vk::PipelineStageFlags::ALL_COMMANDS,
+                    vk::AccessFlags::MEMORY_READ,
+                )
+                .write_timestamp(&profiler, 1, vk::PipelineStageFlags::BOTTOM_OF_PIPE)
+        });
+        let submitted = context
+            .submit_command(
+                context.finish_command(command).unwrap(),
+                SubmitSemaphoreState {
+                    semaphores: &[],
+                    masks: &[],
+                },
+                &[],
+            )
+            .unwrap()
+            .wait()
+            .unwrap();
+        context.free_command(submitted);
+        let timestamps = profiler.resolve(&context).unwrap();
+        assert_eq!(timestamps.len(), 2);
+        assert!(timestamps[1] - timestamps[0] >= 0.0);
+        profiler.destroy(&context).unwrap();
+    }
+}
@@ -332,22 +332,25 @@ impl Context {
         req: &AllocationRequest<M>,
     ) -> AllocatorResult<u32> {
         let memory_type_bits = req.requirements.memory_type_bits;
-        let memory_properties = M::properties();
-
-        self.physical_device
-            .properties
-            .memory
-            .memory_types
-            .iter()
-            .zip(0u32..)
-            .find_map(|(memory, type_index)| {
-                if (1 << type_index & memory_type_bits == 1 << type_index)
-                    && memory.property_flags.contains(memory_properties)
-                {
-                    Some(type_index)
-                } else {
-                    None
-                }
+
+        [M::properties(), M::fallback_properties()]
+            .into_iter()
+            .find_map(|memory_properties| {
+                self.physical_device
+                    .properties
+                    .memory
+                    .memory_types
+                    .iter()
+                    .zip(0u32..)
+                    .find_map(|(memory, type_index)| {
+                        if (1 << type_index & memory_type_bits == 1 << type_index)
+                            && memory.property_flags.contains(memory_properties)
+                        {
+                            Some(type_index)
+                        } else {
+                            None
+                        }
+                    })
             })
             .ok_or(AllocatorError::UnsupportedMemoryType)
     }
@@ -6,7 +6,7 @@ use std::{
     any::TypeId,
     collections::HashMap,
     marker::PhantomData,
-    sync::{Once, RwLock},
+    sync::{PoisonError, RwLock},
 };
 
 use ash::vk;
@@ -23,12 +23,43 @@ use super::framebuffer::{
     References, Transitions,
 };
 
-fn get_render_pass_map() -> &'static RwLock<HashMap<TypeId, vk::RenderPass>> {
-    static mut RENDER_PASSES: Option<RwLock<HashMap<TypeId, vk::RenderPass>>> = None;
-    static INIT: Once = Once::new();
-    unsafe {
-        INIT.call_once(|| RENDER_PASSES = Some(RwLock::new(HashMap::new())));
-        RENDER_PASSES.as_ref().unwrap()
+// Render passes created by the device for each RenderPassConfig type,
+// handles belong to the owning device and are destroyed along with it
+#[derive(Debug, Default)]
+pub struct RenderPassCache {
+    render_passes: RwLock<HashMap<TypeId, vk::RenderPass>>,
+}
+
+impl RenderPassCache {
+    // Cached render pass is looked up under the read lock first,
+    // `create` is called only on cache miss while holding the write lock
+    fn acquire(
+        &self,
+        key: TypeId,
+        create: impl FnOnce() -> VkResult<vk::RenderPass>,
+    ) -> VkResult<vk::RenderPass> {
+        if let Some(&render_pass) = self.render_passes.read()?.get(&key) {
+            return Ok(render_pass);
+        }
+        let mut writer = self.render_passes.write()?;
+        // Other thread could have created the render pass since the read lock was released
+        match writer.get(&key) {
+            Some(&render_pass) => Ok(render_pass),
+            None => {
+                let render_pass = create()?;
+                writer.insert(key, render_pass);
+                Ok(render_pass)
+            }
+        }
+    }
+
+    fn drain(&self) -> Vec<vk::RenderPass> {
+        self.render_passes
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .drain()
+            .map(|(_, render_pass)| render_pass)
+            .collect()
     }
 }
 
@@ -46,8 +77,8 @@ fn get_descriptions(
             store_op: transition.store_op,
             initial_layout: transition.initial_layout,
             final_layout: transition.final_layout,
-            stencil_load_op: vk::AttachmentLoadOp::DONT_CARE,
-            stencil_store_op: vk::AttachmentStoreOp::DONT_CARE,
+            stencil_load_op: transition.stencil_load_op,
+            stencil_store_op: transition.stencil_store_op,
             ..Default::default()
         })
         .collect()
@@ -497,18 +528,9 @@ impl Device {
     }
 
     pub fn get_render_pass<C: RenderPassConfig>(&self) -> VkResult<RenderPass<C>> {
-        let render_pass_map = get_render_pass_map();
-        let render_pass = if let Some(render_pass) = {
-            let reader = render_pass_map.read()?;
-            reader.get(&TypeId::of::<C>()).copied()
-        } {
-            render_pass
-        } else {
-            let mut writer = render_pass_map.write()?;
-            let render_pass = self.create_render_pass_raw::<C>()?;
-            writer.insert(TypeId::of::<C>(), render_pass);
-            render_pass
-        };
+        let render_pass = self
+            .render_passes
+            .acquire(TypeId::of::<C>(), || self.create_render_pass_raw::<C>())?;
         Ok(RenderPass {
             handle: render_pass,
             _phantom: PhantomData,
@@ -516,9 +538,145 @@ impl Device {
     }
 
     pub fn destroy_render_passes(&self) {
-        let exclusive_lock = get_render_pass_map().write().unwrap();
-        exclusive_lock.iter().for_each(|(_, &render_pass)| {
+        for render_pass in self.render_passes.drain() {
             unsafe { self.device.destroy_render_pass(render_pass, None) };
-        })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicU64, Ordering},
+        Barrier,
+    };
+
+    use ash::vk::Handle;
+
+    use crate::context::{
+        device::{framebuffer::presets::AttachmentsShadowMap, DeviceConfig},
+        Context, DebugConfig,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_concurrent_acquire_creates_single_render_pass() {
+        const THREADS: usize = 8;
+        let cache = RenderPassCache::default();
+        let created = AtomicU64::new(0);
+        let barrier = Barrier::new(THREADS);
+        let create = || {
+            let count = created.fetch_add(1, Ordering::SeqCst) + 1;
+            Ok(vk::RenderPass::from_raw(count))
+        };
+        let handles = std::thread::scope(|scope| {
+            let threads = (0..THREADS)
+                .map(|_| {
+                    scope.spawn(|| {
+                        barrier.wait();
+                        cache.acquire(TypeId::of::<u32>(), create).unwrap()
+                    })
+                })
+                .collect::<Vec<_>>();
+            threads
+                .into_iter()
+                .map(|thread| thread.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+        assert_eq!(created.load(Ordering::SeqCst), 1);
+        assert!(handles.iter().all(|&handle| handle == handles[0]));
+        // Distinct configurations get their own render passes
+        let other = cache.acquire(TypeId::of::<u64>(), create).unwrap();
+        assert_ne!(other, handles[0]);
+        assert_eq!(cache.drain().len(), 2);
+        assert!(cache.drain().is_empty());
+    }
+
+    #[test]
+    fn test_render_pass_caches_are_independent() {
+        let (first, second) = (RenderPassCache::default(), RenderPassCache::default());
+        let key = TypeId::of::<u32>();
+        let handle = first
+            .acquire(key, || Ok(vk::RenderPass::from_raw(1)))
+            .unwrap();
+        // Render pass of one device is never handed out by the cache of another
+        let other = second
+            .acquire(key, || Ok(vk::RenderPass::from_raw(2)))
+            .unwrap();
+        assert_ne!(handle, other);
+        assert_eq!(first.drain(), [handle]);
+        assert_eq!(second.drain(), [other]);
+    }
+
+    #[test]
+    #[ignore = "requires Vulkan capable device"]
+    fn test_devices_own_distinct_render_passes() {
+        type Config = ShadowMapRenderPass<AttachmentsShadowMap>;
+        let build = || {
+            Context::build_offscreen(
+                vk::Extent2D {
+                    width: 64,
+                    height: 64,
+                },
+                DebugConfig::default(),
+                &DeviceConfig::default(),
+            )
+            .unwrap()
+        };
+        let (first, second) = (build(), build());
+        let first_handle = first.get_render_pass::<Config>().unwrap().handle;
+        let second_handle = second.get_render_pass::<Config>().unwrap().handle;
+        assert_ne!(first_handle, second_handle);
+        // Each device keeps returning its own cached handle
+        assert_eq!(
+            first.get_render_pass::<Config>().unwrap().handle,
+            first_handle
+        );
+        assert_eq!(
+            second.get_render_pass::<Config>().unwrap().handle,
+            second_handle
+        );
+        // Destroying render passes of one device leaves the other cache untouched
+        second.destroy_render_passes();
+        assert!(second.render_passes.drain().is_empty());
+        assert_eq!(
+            first.get_render_pass::<Config>().unwrap().handle,
+            first_handle
+        );
+    }
+
+    #[test]
+    fn test_stencil_ops_in_attachment_description() {
+        let format = AttachmentFormatInfo {
+            format: vk::Format::D24_UNORM_S8_UINT,
+            samples: vk::SampleCountFlags::TYPE_1,
+        };
+        let transitions = vec![
+            AttachmentTransition {
+                load_op: vk::AttachmentLoadOp::CLEAR,
+                store_op: vk::AttachmentStoreOp::DONT_CARE,
+                stencil_load_op: vk::AttachmentLoadOp::LOAD,
+                stencil_store_op: vk::AttachmentStoreOp::STORE,
+                initial_layout: vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+                final_layout: vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+            },
+            AttachmentTransition::default(),
+        ];
+        let descriptions = get_descriptions(vec![format, format], transitions);
+        assert_eq!(descriptions[0].load_op, vk::AttachmentLoadOp::CLEAR);
+        assert_eq!(descriptions[0].stencil_load_op, vk::AttachmentLoadOp::LOAD);
+        assert_eq!(
+            descriptions[0].stencil_store_op,
+            vk::AttachmentStoreOp::STORE
+        );
+        assert_eq!(
+            descriptions[1].stencil_load_op,
+            vk::AttachmentLoadOp::DONT_CARE
+        );
+        assert_eq!(
+            descriptions[1].stencil_store_op,
+            vk::AttachmentStoreOp::DONT_CARE
+        );
     }
 }
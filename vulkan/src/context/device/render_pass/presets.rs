@@ -1,8 +1,9 @@
 use ash::vk;
 
 use crate::context::device::framebuffer::{
-    presets::AttachmentsGBuffer, AttachmentList, AttachmentReference, AttachmentReferenceBuilder,
-    AttachmentTarget, AttachmentTransition, AttachmentTransitionBuilder, References, Transitions,
+    presets::{AttachmentsGBuffer, AttachmentsShadowMap},
+    AttachmentList, AttachmentReference, AttachmentReferenceBuilder, AttachmentTarget,
+    AttachmentTransition, AttachmentTransitionBuilder, References, Transitions,
 };
 use type_kit::Nil;
 
@@ -37,6 +38,7 @@ impl TransitionList<AttachmentsGBuffer> for DeferedRenderPassTransitions<Attachm
                 store_op: vk::AttachmentStoreOp::DONT_CARE,
                 initial_layout: vk::ImageLayout::UNDEFINED,
                 final_layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                ..Default::default()
             })
             .push(AttachmentTransition {
                 // Albedo
@@ -44,6 +46,7 @@ impl TransitionList<AttachmentsGBuffer> for DeferedRenderPassTransitions<Attachm
                 store_op: vk::AttachmentStoreOp::DONT_CARE,
                 initial_layout: vk::ImageLayout::UNDEFINED,
                 final_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                ..Default::default()
             })
             .push(AttachmentTransition {
                 // Normal
@@ -51,6 +54,7 @@ impl TransitionList<AttachmentsGBuffer> for DeferedRenderPassTransitions<Attachm
                 store_op: vk::AttachmentStoreOp::DONT_CARE,
                 initial_layout: vk::ImageLayout::UNDEFINED,
                 final_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                ..Default::default()
             })
             .push(AttachmentTransition {
                 // Position
@@ -58,6 +62,7 @@ impl TransitionList<AttachmentsGBuffer> for DeferedRenderPassTransitions<Attachm
                 store_op: vk::AttachmentStoreOp::DONT_CARE,
                 initial_layout: vk::ImageLayout::UNDEFINED,
                 final_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                ..Default::default()
             })
             .push(AttachmentTransition {
                 // Depth
@@ -65,13 +70,15 @@ impl TransitionList<AttachmentsGBuffer> for DeferedRenderPassTransitions<Attachm
                 store_op: vk::AttachmentStoreOp::DONT_CARE,
                 initial_layout: vk::ImageLayout::UNDEFINED,
                 final_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                ..Default::default()
             })
             .push(AttachmentTransition {
-                // Resolve
+                // Resolve, stored so offscreen targets can be read back
                 load_op: vk::AttachmentLoadOp::DONT_CARE,
-                store_op: vk::AttachmentStoreOp::DONT_CARE,
+                store_op: vk::AttachmentStoreOp::STORE,
                 initial_layout: vk::ImageLayout::UNDEFINED,
                 final_layout: vk::ImageLayout::PRESENT_SRC_KHR,
+                ..Default::default()
             })
     }
 }
@@ -160,6 +167,32 @@ impl Subpass<AttachmentsGBuffer> for GBufferShadingPass<AttachmentsGBuffer> {
                 layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
                 usage: vk::ImageUsageFlags::INPUT_ATTACHMENT,
             }))
+            .push(None)
+    }
+}
+
+// Forward pass of transparent materials blended over the shaded image,
+// resolves combined attachment into the swapchain image
+pub struct GBufferTransparentPass<A: AttachmentList> {
+    _phantom: std::marker::PhantomData<A>,
+}
+
+impl Subpass<AttachmentsGBuffer> for GBufferTransparentPass<AttachmentsGBuffer> {
+    fn references() -> References<AttachmentsGBuffer> {
+        AttachmentReferenceBuilder::new()
+            .push(Some(AttachmentReference {
+                target: AttachmentTarget::Color,
+                layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                usage: vk::ImageUsageFlags::COLOR_ATTACHMENT,
+            }))
+            .push(None)
+            .push(None)
+            .push(None)
+            .push(Some(AttachmentReference {
+                target: AttachmentTarget::DepthStencil,
+                layout: vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL,
+                usage: vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT,
+            }))
             .push(Some(AttachmentReference {
                 target: AttachmentTarget::Resolve,
                 layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
@@ -168,6 +201,27 @@ impl Subpass<AttachmentsGBuffer> for GBufferShadingPass<AttachmentsGBuffer> {
     }
 }
 
+// Screen space overlay drawn directly over the resolved swapchain image
+pub struct GBufferOverlayPass<A: AttachmentList> {
+    _phantom: std::marker::PhantomData<A>,
+}
+
+impl Subpass<AttachmentsGBuffer> for GBufferOverlayPass<AttachmentsGBuffer> {
+    fn references() -> References<AttachmentsGBuffer> {
+        AttachmentReferenceBuilder::new()
+            .push(None)
+            .push(None)
+            .push(None)
+            .push(None)
+            .push(None)
+            .push(Some(AttachmentReference {
+                target: AttachmentTarget::Color,
+                layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                usage: vk::ImageUsageFlags::COLOR_ATTACHMENT,
+            }))
+    }
+}
+
 pub struct GBufferSkyboxPass<A: AttachmentList> {
     _phantom: std::marker::PhantomData<A>,
 }
@@ -192,15 +246,56 @@ impl Subpass<AttachmentsGBuffer> for GBufferSkyboxPass<AttachmentsGBuffer> {
     }
 }
 
+pub struct ShadowMapRenderPassTransitions<A: AttachmentList> {
+    _phantom: std::marker::PhantomData<A>,
+}
+
+impl TransitionList<AttachmentsShadowMap> for ShadowMapRenderPassTransitions<AttachmentsShadowMap> {
+    fn transitions() -> Transitions<AttachmentsShadowMap> {
+        AttachmentTransitionBuilder::new().push(AttachmentTransition {
+            // Shadow map depth, shared between frames in flight, transitioned by the
+            // barrier recorded before the pass so writes wait for previous frame reads
+            load_op: vk::AttachmentLoadOp::CLEAR,
+            store_op: vk::AttachmentStoreOp::STORE,
+            initial_layout: vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+            final_layout: vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL,
+            ..Default::default()
+        })
+    }
+}
+
+pub struct ShadowMapPass<A: AttachmentList> {
+    _phantom: std::marker::PhantomData<A>,
+}
+
+impl Subpass<AttachmentsShadowMap> for ShadowMapPass<AttachmentsShadowMap> {
+    fn references() -> References<AttachmentsShadowMap> {
+        AttachmentReferenceBuilder::new().push(Some(AttachmentReference {
+            target: AttachmentTarget::DepthStencil,
+            layout: vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+            usage: vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT,
+        }))
+    }
+}
+
 // pub type EmptyRenderPass = RenderPassBuilder<TypedNil<Nil>, EmptyRenderPassTransitions>;
 
 pub type DeferedRenderPass<A> = RenderPassBuilder<
     Cons<
-        GBufferShadingPass<A>,
+        GBufferOverlayPass<A>,
         Cons<
-            GBufferWritePass<A>,
-            Cons<GBufferSkyboxPass<A>, Cons<GBufferDepthPrepas<A>, TypedNil<A>>>,
+            GBufferTransparentPass<A>,
+            Cons<
+                GBufferShadingPass<A>,
+                Cons<
+                    GBufferWritePass<A>,
+                    Cons<GBufferSkyboxPass<A>, Cons<GBufferDepthPrepas<A>, TypedNil<A>>>,
+                >,
+            >,
         >,
     >,
     DeferedRenderPassTransitions<A>,
 >;
+
+pub type ShadowMapRenderPass<A> =
+    RenderPassBuilder<Cons<ShadowMapPass<A>, TypedNil<A>>, ShadowMapRenderPassTransitions<A>>;
@@ -1,47 +1,75 @@
 mod commands;
 mod draw_graph;
+mod timestamps;
+mod tonemap;
 
-use std::{cell::RefCell, convert::Infallible, error::Error, path::Path, rc::Rc};
+use std::{
+    cell::RefCell, collections::HashMap, convert::Infallible, error::Error, path::Path, rc::Rc,
+    time::Instant,
+};
 
 use ash::vk;
 
-use commands::Commands;
+use commands::{Commands, FrameOptions};
 use draw_graph::DrawGraph;
+use timestamps::DeferredRendererTimestamps;
+use tonemap::{DeferredRendererTonemap, TonemapPipeline};
 
 use graphics::{
-    model::{CommonVertex, Drawable, MeshBuilder},
-    renderer::camera::CameraMatrices,
-    shader::{ShaderHandle, ShaderType},
+    model::{CommonVertex, Drawable, Material, MeshBuilder, PackedStaticVertex},
+    renderer::{camera::CameraMatrices, light::DirectionalLight, viewport::Rect},
+    shader::{EmbeddedShader, PolygonMode, ShaderHandle, ShaderType},
 };
 use type_kit::{Create, CreateResult, Destroy, DestroyResult, DropGuard, DropGuardError};
+use winit::window::{Window, WindowId};
 
 use crate::context::{
     device::{
-        descriptor::{DescriptorPool, DescriptorSetWriter, GBufferDescriptorSet},
-        frame::{Frame, FrameContext, FrameData, FramePool},
+        command::operation::Graphics,
+        descriptor::{
+            Descriptor, DescriptorPool, DescriptorSetWriter, FragmentStage, FrameDescriptorSet,
+            GBufferDescriptorSet, PodUniform, ShadowDescriptorSet,
+        },
+        frame::{
+            Frame, FrameContext, FrameData, FrameGlobals, FramePool, FrameTarget, FrameUniform,
+            MAX_FRAME_VIEWS,
+        },
         framebuffer::{
-            presets::AttachmentsGBuffer, AttachmentReferences, AttachmentsBuilder, Builder,
-            InputAttachment,
+            presets::{
+                AlbedoMultisampled, AttachmentsGBuffer, AttachmentsShadowMap, ColorMultisampled,
+                NormalMultisampled, PositionMultisampled,
+            },
+            AttachmentReferences, AttachmentsBuilder, Builder, InputAttachment,
         },
-        memory::{Allocator, DeviceLocal},
+        memory::{Allocator, DefaultAllocator, DeviceLocal, StaticAllocator},
         pipeline::{
-            GBufferDepthPrepasPipeline, GBufferShadingPassPipeline, GBufferSkyboxPipeline,
-            GraphicsPipeline, GraphicsPipelineConfig, GraphicsPipelineListBuilder,
-            GraphicsPipelinePackList, ModuleLoader, Modules, PipelineLayoutMaterial,
-            ShaderDirectory, StatesDepthWriteDisabled,
+            ComputePipeline, GBufferDepthPrepasPipeline, GBufferOverlayPipeline,
+            GBufferShadingPassPipeline, GBufferSkyboxPipeline, GraphicsPipeline,
+            GraphicsPipelineConfig, GraphicsPipelineListBuilder, GraphicsPipelinePackList,
+            ModuleLoader, Modules, PipelineBindData, PipelineLayoutMaterial, ShaderDirectory,
+            ShaderLoadConfig, ShadowMapPipeline, StatesDepthWriteDisabled, StatesTransparent,
         },
+        profiler::GpuPassTimings,
         render_pass::{
-            DeferedRenderPass, GBufferShadingPass, GBufferWritePass, RenderPass, Subpass,
+            DeferedRenderPass, GBufferShadingPass, GBufferTransparentPass, GBufferWritePass,
+            RenderPass, ShadowMapRenderPass, Subpass,
         },
-        resources::{image::Image2D, MaterialPackList, MeshPack, MeshPackList, Skybox},
-        swapchain::Swapchain,
+        resources::{
+            buffer::{UniformBuffer, UniformBufferBuilder, UniformBufferPartial},
+            image::Image2D,
+            LightUniform, MaterialPackList, MeshPack, MeshPackList, PartialBuilder, ShadowMap,
+            ShadowMapSampler, Skybox, TextOverlay,
+        },
+        screenshot::{ScreenshotRequest, ScreenshotState},
+        swapchain::{PresentStatus, PresentSurface, Swapchain},
         Device,
     },
-    error::{ShaderResult, VkError},
+    error::{ShaderResult, VkError, VkResult},
+    window::RenderTargetWindow,
     Context,
 };
 
-use math::types::{Matrix4, Vector3};
+use math::types::{Matrix4, Vector2, Vector3, Vector4};
 
 pub struct DeferredShader<S: ShaderType> {
     shader: S,
@@ -54,6 +82,14 @@ impl<S: ShaderType> ShaderType for DeferredShader<S> {
     fn source(&self) -> &Path {
         self.shader.source()
     }
+
+    fn polygon_mode(&self) -> PolygonMode {
+        self.shader.polygon_mode()
+    }
+
+    fn embedded_shader(&self) -> Option<EmbeddedShader> {
+        self.shader.embedded_shader()
+    }
 }
 impl<S: ShaderType> GraphicsPipelineConfig for DeferredShader<S> {
     type Attachments = AttachmentsGBuffer;
@@ -61,6 +97,12 @@ impl<S: ShaderType> GraphicsPipelineConfig for DeferredShader<S> {
     type PipelineStates = StatesDepthWriteDisabled<S::Vertex>;
     type RenderPass = DeferedRenderPass<AttachmentsGBuffer>;
     type Subpass = GBufferWritePass<AttachmentsGBuffer>;
+    type TransparentStates = StatesTransparent<S::Vertex>;
+    type TransparentSubpass = GBufferTransparentPass<AttachmentsGBuffer>;
+
+    fn transparent() -> bool {
+        <S::Material as Material>::transparent()
+    }
 }
 
 impl<S: ShaderType> From<S> for DeferredShader<S> {
@@ -71,7 +113,10 @@ impl<S: ShaderType> From<S> for DeferredShader<S> {
 
 impl<S: ShaderType> ModuleLoader for DeferredShader<S> {
     fn load<'a>(&self, device: &'a Device) -> ShaderResult<Modules<'a>> {
-        ShaderDirectory::new(self.shader.source()).load(device)
+        match self.shader.embedded_shader() {
+            Some(embedded) => embedded.load(device),
+            None => ShaderDirectory::new(self.shader.source()).load(device),
+        }
     }
 }
 
@@ -83,10 +128,24 @@ pub struct GBuffer<A: Allocator> {
     pub depth: DropGuard<Image2D<DeviceLocal, A>>,
 }
 
+type GBufferPipelines = (
+    GraphicsPipeline<GBufferDepthPrepasPipeline<AttachmentsGBuffer>>,
+    GraphicsPipeline<GBufferDepthPrepasPipeline<AttachmentsGBuffer, PackedStaticVertex>>,
+    GraphicsPipeline<GBufferShadingPassPipeline<AttachmentsGBuffer>>,
+);
+
 struct DeferredRendererPipelines<P: GraphicsPipelinePackList> {
     write_pass: P,
     depth_prepass: DropGuard<GraphicsPipeline<GBufferDepthPrepasPipeline<AttachmentsGBuffer>>>,
+    depth_prepass_packed: DropGuard<
+        GraphicsPipeline<GBufferDepthPrepasPipeline<AttachmentsGBuffer, PackedStaticVertex>>,
+    >,
     shading_pass: DropGuard<GraphicsPipeline<GBufferShadingPassPipeline<AttachmentsGBuffer>>>,
+    shadow_pass: DropGuard<GraphicsPipeline<ShadowMapPipeline<AttachmentsShadowMap>>>,
+    shadow_pass_packed:
+        DropGuard<GraphicsPipeline<ShadowMapPipeline<AttachmentsShadowMap, PackedStaticVertex>>>,
+    // Created once tonemapping is first enabled
+    tonemap: Option<DropGuard<ComputePipeline<TonemapPipeline>>>,
 }
 
 struct DeferredRendererFrameData<A: Allocator> {
@@ -97,25 +156,71 @@ struct DeferredRendererFrameData<A: Allocator> {
 
 struct DeferredRendererResources<A: Allocator> {
     mesh: DropGuard<MeshPack<CommonVertex, A>>,
-    skybox: DropGuard<Skybox<A, GBufferSkyboxPipeline<AttachmentsGBuffer, A>>>,
+    shadow_map: DropGuard<ShadowMap<A>>,
+}
+
+struct DeferredRendererWindow<A: Allocator> {
+    window: DropGuard<RenderTargetWindow>,
+    frame_data: DropGuard<DeferredRendererFrameData<A>>,
+}
+
+struct DeferredRendererLight {
+    descriptors: DropGuard<DescriptorPool<ShadowDescriptorSet>>,
+    uniform_buffer: DropGuard<
+        UniformBuffer<PodUniform<LightUniform, FragmentStage>, Graphics, DefaultAllocator>,
+    >,
+}
+
+// Frame resources allocated for each swapchain the context renders into
+struct DeferredRendererTarget<A: Allocator, P: GraphicsPipelinePackList> {
+    frames: FramePool<DeferredRendererContext<A, P>>,
+    light: DeferredRendererLight,
+    overlay: DropGuard<TextOverlay<GBufferOverlayPipeline<AttachmentsGBuffer, DefaultAllocator>>>,
+    timestamps: DropGuard<DeferredRendererTimestamps>,
+    tonemap: Option<DropGuard<DeferredRendererTonemap>>,
+    extent: vk::Extent2D,
 }
 
 pub struct DeferredRendererContext<A: Allocator, P: GraphicsPipelinePackList> {
     renderer: Rc<RefCell<DropGuard<DeferredRenderer<A>>>>,
     pipelines: DeferredRendererPipelines<P>,
-    frames: FramePool<Self>,
+    light: Option<DirectionalLight>,
+    // Exposure of the tonemap pass, pass is skipped when disabled
+    exposure: Option<f32>,
+    target: DeferredRendererTarget<A, P>,
+    current_target: FrameTarget,
+    // Resources of targets other than the current one,
+    // swapped in when the frame is begun for them
+    targets: HashMap<FrameTarget, DeferredRendererTarget<A, P>>,
     current_frame: Option<FrameData<Self>>,
+    screenshot: ScreenshotState,
+    start_time: Instant,
+}
+
+// Region of the frame rendered from a single camera
+struct DeferredRendererView {
+    area: vk::Rect2D,
+    frame_descriptor: Descriptor<FrameDescriptorSet>,
+    view_matrix: Matrix4,
+    draw_graph: DrawGraph,
 }
 
 pub struct DeferredRendererFrameState<P: GraphicsPipelinePackList> {
     commands: Commands<P>,
-    draw_graph: DrawGraph,
+    // Full frame view comes first, followed by views begun within the frame
+    views: Vec<DeferredRendererView>,
+    current_view: usize,
+    light_space: Option<Matrix4>,
+    frame_index: usize,
 }
 
 pub struct DeferredRenderer<A: Allocator> {
     render_pass: RenderPass<DeferedRenderPass<AttachmentsGBuffer>>,
+    shadow_render_pass: RenderPass<ShadowMapRenderPass<AttachmentsShadowMap>>,
     frame_data: DropGuard<DeferredRendererFrameData<A>>,
+    windows: HashMap<WindowId, DeferredRendererWindow<A>>,
     resources: DropGuard<DeferredRendererResources<A>>,
+    frames_in_flight: usize,
 }
 
 impl<A: Allocator> Frame for Rc<RefCell<DropGuard<DeferredRenderer<A>>>> {
@@ -126,48 +231,159 @@ impl<A: Allocator> Frame for Rc<RefCell<DropGuard<DeferredRenderer<A>>>> {
         &self,
         context: &Context,
         pipelines: &impl GraphicsPipelineListBuilder<Pack = P>,
+        config: ShaderLoadConfig,
     ) -> CreateResult<Self::Context<P>> {
         let renderer = self.clone();
-        let pipelines = pipelines.build(context)?;
+        let pipelines = pipelines.build(context, config)?;
         DeferredRendererContext::create((renderer, pipelines), context)
     }
 }
 
 impl<A: Allocator, P: GraphicsPipelinePackList> FrameContext for DeferredRendererContext<A, P> {
-    const REQUIRED_COMMANDS: usize = P::LEN + 3;
+    const REQUIRED_COMMANDS: usize = P::LEN + 6;
     type Attachments = AttachmentsGBuffer;
     type State = DeferredRendererFrameState<P>;
+    type SkyboxPipeline = GBufferSkyboxPipeline<AttachmentsGBuffer, StaticAllocator>;
 
     fn begin_frame(
         &mut self,
         device: &Device,
+        target: FrameTarget,
         camera_matrices: &CameraMatrices,
+        skybox: Option<&Skybox<StaticAllocator, Self::SkyboxPipeline>>,
     ) -> Result<(), Box<dyn Error>> {
-        let (index, primary_command) = self.frames.primary_commands.next();
+        self.select_target(device, target)?;
+        self.update_swapchain()?;
+        let (index, primary_command) = self.target.frames.primary_commands.next();
+        device.wait_primary_command(&primary_command)?;
+        // Image is acquired before the command fence is reset, so the frame skipped
+        // due to out of date swapchain leaves the fence signaled for its next use
+        let swapchain_frame = {
+            graphics::profile_scope!("acquire_image");
+            self.renderer
+                .borrow()
+                .frame_data(target)?
+                .swapchain
+                .get_frame(self.target.frames.image_sync[index])?
+        };
+        if target == FrameTarget::Primary {
+            self.screenshot.poll(device, index)?;
+        }
         let primary_command = device.begin_primary_command(primary_command)?;
-        let swapchain_frame = self
-            .renderer
-            .borrow()
-            .frame_data
-            .swapchain
-            .get_frame(self.frames.image_sync[index])?;
-        let camera_descriptor = self.frames.camera_uniform.descriptors.get(index);
-        self.frames.camera_uniform.uniform_buffer[index] = *camera_matrices;
-        let commands =
-            self.prepare_commands(device, &swapchain_frame, camera_descriptor, camera_matrices)?;
-        let draw_graph = DrawGraph::new();
+        self.target.timestamps.resolve(device, index)?;
+        let DeferredRendererTarget {
+            frames,
+            light,
+            extent,
+            ..
+        } = &mut self.target;
+        let uniform_index = FrameUniform::index(index, 0);
+        let frame_descriptor = frames.frame_uniform.descriptors.get(uniform_index);
+        frames.frame_uniform.uniform_buffer[uniform_index] = FrameGlobals::new(
+            *camera_matrices,
+            Vector2::new(extent.width as f32, extent.height as f32),
+            self.start_time.elapsed().as_secs_f32(),
+        );
+        let light_descriptor = light.descriptors.get(index);
+        let light_uniform = LightUniform::from(self.light.as_ref())
+            .with_output_color_space(device.output_color_space());
+        light.uniform_buffer[index] = light_uniform.into();
+        frames.frame_uniform.uniform_buffer.flush(device)?;
+        light.uniform_buffer.flush(device)?;
+        let light_space = self.light.map(|_| light_uniform.light_space);
+        let view = DeferredRendererView {
+            area: view_area(Rect::full(), *extent),
+            frame_descriptor,
+            view_matrix: camera_matrices.view,
+            draw_graph: DrawGraph::new(),
+        };
+        graphics::profile_scope!("prepare_commands");
+        let commands = self.prepare_commands(
+            device,
+            &swapchain_frame,
+            light_descriptor,
+            camera_matrices,
+            skybox,
+            index,
+        )?;
         self.current_frame.replace(FrameData {
             swapchain_frame,
             primary_command,
-            camera_descriptor,
+            frame_descriptor,
             renderer_state: DeferredRendererFrameState {
                 commands,
-                draw_graph,
+                views: vec![view],
+                current_view: 0,
+                light_space,
+                frame_index: index,
             },
         });
         Ok(())
     }
 
+    fn begin_view(
+        &mut self,
+        device: &Device,
+        viewport: Rect,
+        camera_matrices: &CameraMatrices,
+        skybox: Option<&Skybox<StaticAllocator, Self::SkyboxPipeline>>,
+    ) -> Result<(), Box<dyn Error>> {
+        let (frame_index, view_index) = {
+            let state = &self
+                .current_frame
+                .as_ref()
+                .ok_or("current_frame is None!")?
+                .renderer_state;
+            (state.frame_index, state.views.len())
+        };
+        if view_index == MAX_FRAME_VIEWS {
+            return Err(format!(
+                "At most {} views can be rendered in a frame!",
+                MAX_FRAME_VIEWS
+            )
+            .into());
+        }
+        let DeferredRendererTarget { frames, extent, .. } = &mut self.target;
+        let uniform_index = FrameUniform::index(frame_index, view_index);
+        frames.frame_uniform.uniform_buffer[uniform_index] = FrameGlobals::new(
+            *camera_matrices,
+            Vector2::new(extent.width as f32, extent.height as f32),
+            self.start_time.elapsed().as_secs_f32(),
+        );
+        frames.frame_uniform.uniform_buffer.flush(device)?;
+        let view = DeferredRendererView {
+            area: view_area(viewport, *extent),
+            frame_descriptor: frames.frame_uniform.descriptors.get(uniform_index),
+            view_matrix: camera_matrices.view,
+            draw_graph: DrawGraph::new(),
+        };
+        if let Some(mut current_frame) = self.current_frame.take() {
+            // Skybox of the view is drawn over the full frame skybox within the view area
+            let skybox_pass = current_frame.renderer_state.commands.skybox_pass;
+            current_frame.renderer_state.commands.skybox_pass =
+                device.record_command(skybox_pass, |command| {
+                    let command = command.set_viewport(view.area).set_scissor(view.area);
+                    match skybox {
+                        Some(skybox) => command.draw_skybox(skybox, *camera_matrices),
+                        None => command,
+                    }
+                });
+            current_frame.renderer_state.views.push(view);
+            current_frame.renderer_state.current_view = view_index;
+            self.current_frame.replace(current_frame);
+        }
+        Ok(())
+    }
+
+    fn end_view(&mut self) -> Result<(), Box<dyn Error>> {
+        let current_frame = self
+            .current_frame
+            .as_mut()
+            .ok_or("current_frame is None!")?;
+        current_frame.renderer_state.current_view = 0;
+        Ok(())
+    }
+
     fn draw<
         T1: Allocator,
         T2: Allocator,
@@ -186,23 +402,153 @@ impl<A: Allocator, P: GraphicsPipelinePackList> FrameContext for DeferredRendere
         self.append_draw_call(material_packs, mesh_packs, shader, drawable, transform);
     }
 
-    fn end_frame(&mut self, device: &Device) -> Result<(), Box<dyn Error>> {
+    fn draw_batch<
+        T1: Allocator,
+        T2: Allocator,
+        S: ShaderType,
+        D: Drawable<Material = S::Material, Vertex = S::Vertex>,
+        M: MaterialPackList<T2>,
+        V: MeshPackList<T1>,
+    >(
+        &mut self,
+        shader: ShaderHandle<S>,
+        items: &[(&D, Matrix4)],
+        material_packs: &M,
+        mesh_packs: &V,
+    ) {
+        self.append_draw_batch(material_packs, mesh_packs, shader, items);
+    }
+
+    fn set_directional_light(&mut self, light: Option<DirectionalLight>) {
+        self.light = light;
+    }
+
+    fn draw_text(&mut self, x: f32, y: f32, text: &str, color: Vector4) {
+        self.target.overlay.draw_text(x, y, text, color);
+    }
+
+    fn set_tonemapping(&mut self, exposure: Option<f32>) {
+        self.exposure = exposure;
+    }
+
+    fn reload_modified_shaders(&mut self, device: &Device) -> Vec<VkError> {
+        self.pipelines.write_pass.reload_modified(device)
+    }
+
+    fn reload_shader<S: ShaderType>(
+        &mut self,
+        device: &Device,
+        shader: ShaderHandle<S>,
+    ) -> VkResult<()> {
+        self.pipelines
+            .write_pass
+            .try_get_mut::<DeferredShader<S>>()
+            .ok_or(VkError::MissingResource("PipelinePack"))?
+            .reload_pipeline(device, shader.index() as usize)
+    }
+
+    fn contains_shader<S: ShaderType>(&self, shader: ShaderHandle<S>) -> bool {
+        self.pipelines
+            .write_pass
+            .try_get::<DeferredShader<S>>()
+            .is_some_and(|pack| (shader.index() as usize) < pack.len())
+    }
+
+    fn end_frame(&mut self, device: &Device) -> Result<(vk::Fence, PresentStatus), Box<dyn Error>> {
         let FrameData {
             swapchain_frame,
             primary_command,
             renderer_state,
             ..
         } = self.current_frame.take().ok_or("current_frame is None!")?;
-        let commands = self.record_draw_calls(device, renderer_state, &swapchain_frame)?;
-        let primary_command =
-            self.record_primary_command(device, primary_command, commands, &swapchain_frame)?;
+        let frame_index = renderer_state.frame_index;
+        // Screenshots are taken of the primary window only
+        let mut capture = match self.current_target {
+            FrameTarget::Primary => self.screenshot.begin_capture(
+                device,
+                &self.renderer.borrow().frame_data.swapchain,
+                &swapchain_frame,
+            ),
+            FrameTarget::Window(_) => None,
+        };
+        let commands = {
+            graphics::profile_scope!("record_draw_calls");
+            self.record_draw_calls(device, renderer_state, &swapchain_frame)?
+        };
+        let exposure = self.prepare_tonemap(device)?;
+        let primary_command = {
+            graphics::profile_scope!("record_primary_command");
+            self.record_primary_command(
+                device,
+                primary_command,
+                commands,
+                &swapchain_frame,
+                FrameOptions {
+                    exposure,
+                    capture: capture.as_mut(),
+                    frame_index,
+                },
+            )?
+        };
+        let fence = primary_command.fence();
         let renderer = self.renderer.borrow();
-        device.present_frame(
-            &renderer.frame_data.swapchain,
-            primary_command,
-            swapchain_frame,
-        )?;
-        Ok(())
+        let status = {
+            graphics::profile_scope!("present");
+            device.present_frame(
+                &renderer.frame_data(self.current_target)?.swapchain,
+                primary_command,
+                swapchain_frame,
+            )?
+        };
+        self.target.timestamps.frame_submitted(frame_index);
+        if let Some(capture) = capture {
+            self.screenshot.submitted(capture, frame_index, fence);
+        }
+        Ok((fence, status))
+    }
+
+    fn read_back_frame(&self, device: &Device) -> VkResult<Vec<u8>> {
+        self.renderer.borrow_mut().read_back_frame(device)
+    }
+
+    fn capture_screenshot(&mut self, request: ScreenshotRequest) {
+        self.screenshot.request(request);
+    }
+
+    fn gpu_pass_timings(&self) -> Option<GpuPassTimings> {
+        match self.current_target {
+            FrameTarget::Primary => self.target.timestamps.timings(),
+            _ => self
+                .targets
+                .get(&FrameTarget::Primary)?
+                .timestamps
+                .timings(),
+        }
+    }
+}
+
+// Pixel region of the frame covered by the viewport, edges are rounded
+// so that adjacent viewports share them without gaps
+fn view_area(viewport: Rect, extent: vk::Extent2D) -> vk::Rect2D {
+    let to_pixels =
+        |fraction: f32, size: u32| (fraction.clamp(0.0, 1.0) * size as f32).round() as u32;
+    let (left, right) = (
+        to_pixels(viewport.x, extent.width),
+        to_pixels(viewport.x + viewport.width, extent.width),
+    );
+    let (top, bottom) = (
+        to_pixels(viewport.y, extent.height),
+        to_pixels(viewport.y + viewport.height, extent.height),
+    );
+    vk::Rect2D {
+        offset: vk::Offset2D {
+            x: left as i32,
+            y: top as i32,
+        },
+        extent: vk::Extent2D {
+            width: right.saturating_sub(left),
+            height: bottom.saturating_sub(top),
+        },
     }
 }
 
@@ -222,19 +568,23 @@ impl<A: Allocator> GBuffer<A> {
 }
 
 impl<A: Allocator> Create for GBuffer<A> {
-    type Config<'a> = ();
+    type Config<'a> = vk::Extent2D;
     type CreateError = VkError;
 
     fn create<'a, 'b>(
-        _: Self::Config<'a>,
+        config: Self::Config<'a>,
         context: Self::Context<'b>,
     ) -> type_kit::CreateResult<Self> {
         let (device, allocator) = context;
-        let combined = device.create_color_attachment_image(allocator)?;
-        let albedo = device.create_color_attachment_image(allocator)?;
-        let normal = device.create_color_attachment_image(allocator)?;
-        let position = device.create_color_attachment_image(allocator)?;
-        let depth = device.create_depth_stencil_attachment_image(allocator)?;
+        let combined =
+            device.create_color_attachment_image::<ColorMultisampled, _>(config, allocator)?;
+        let albedo =
+            device.create_color_attachment_image::<AlbedoMultisampled, _>(config, allocator)?;
+        let normal =
+            device.create_color_attachment_image::<NormalMultisampled, _>(config, allocator)?;
+        let position =
+            device.create_color_attachment_image::<PositionMultisampled, _>(config, allocator)?;
+        let depth = device.create_depth_stencil_attachment_image(config, allocator)?;
         Ok(GBuffer {
             combined: DropGuard::new(combined),
             albedo: DropGuard::new(albedo),
@@ -260,23 +610,64 @@ impl<A: Allocator> Destroy for GBuffer<A> {
     }
 }
 
+impl Create for DeferredRendererLight {
+    type Config<'a> = (usize, ShadowMapSampler);
+    type CreateError = VkError;
+
+    fn create<'a, 'b>(
+        config: Self::Config<'a>,
+        context: Self::Context<'b>,
+    ) -> type_kit::CreateResult<Self> {
+        let (num_frames, shadow_map) = config;
+        let buffer_partial =
+            UniformBufferPartial::prepare(UniformBufferBuilder::new(num_frames), context)?;
+        let uniform_buffer = UniformBuffer::create(
+            buffer_partial,
+            (context, &RefCell::new(&mut DefaultAllocator {})),
+        )?;
+        let descriptors = DescriptorPool::create(
+            DescriptorSetWriter::<ShadowDescriptorSet>::new(num_frames)
+                .write_buffer(&uniform_buffer)
+                .write_images::<ShadowMapSampler, _>(&vec![shadow_map; num_frames]),
+            context,
+        )?;
+        Ok(DeferredRendererLight {
+            descriptors: DropGuard::new(descriptors),
+            uniform_buffer: DropGuard::new(uniform_buffer),
+        })
+    }
+}
+
+impl Destroy for DeferredRendererLight {
+    type Context<'a> = &'a Device;
+    type DestroyError = DropGuardError<Infallible>;
+
+    fn destroy<'a>(&mut self, context: Self::Context<'a>) -> DestroyResult<Self> {
+        self.descriptors.destroy(context)?;
+        self.uniform_buffer
+            .destroy((context, &RefCell::new(&mut DefaultAllocator {})))?;
+        Ok(())
+    }
+}
+
 impl<A: Allocator> Create for DeferredRendererFrameData<A> {
-    type Config<'a> = ();
+    type Config<'a> = PresentSurface<'a>;
     type CreateError = VkError;
 
     fn create<'a, 'b>(
-        _: Self::Config<'a>,
+        config: Self::Config<'a>,
         context: Self::Context<'b>,
     ) -> type_kit::CreateResult<Self> {
         let (device, allocator) = context;
-        let g_buffer = GBuffer::create((), (device, allocator))?;
+        let g_buffer =
+            GBuffer::create(config.properties.get_current_extent(), (device, allocator))?;
         let framebuffer_builder = |swapchain_image, extent| {
             device.build_framebuffer::<DeferedRenderPass<AttachmentsGBuffer>>(
                 g_buffer.get_framebuffer_builder(swapchain_image),
                 extent,
             )
         };
-        let swapchain = Swapchain::create(&framebuffer_builder, device)?;
+        let swapchain = Swapchain::create((config, &framebuffer_builder), device)?;
         let descriptors = DescriptorPool::create(
             DescriptorSetWriter::<GBufferDescriptorSet>::new(1).write_images::<InputAttachment, _>(
                 &GBufferShadingPass::<AttachmentsGBuffer>::references()
@@ -314,10 +705,6 @@ impl<A: Allocator> Create for DeferredRendererResources<A> {
         context: Self::Context<'b>,
     ) -> type_kit::CreateResult<Self> {
         let (device, allocator) = context;
-        let skybox = Skybox::create(
-            Path::new("_resources/assets/skybox/skybox"),
-            (device, allocator),
-        )?;
         let mesh = device.load_mesh_pack(
             allocator,
             &[MeshBuilder::plane_subdivided(
@@ -330,10 +717,10 @@ impl<A: Allocator> Create for DeferredRendererResources<A> {
             .offset(Vector3::new(-1.0, -1.0, 0.0))
             .build()],
         )?;
-
+        let shadow_map = ShadowMap::create((), (device, allocator))?;
         Ok(DeferredRendererResources {
             mesh: DropGuard::new(mesh),
-            skybox: DropGuard::new(skybox),
+            shadow_map: DropGuard::new(shadow_map),
         })
     }
 }
@@ -345,11 +732,59 @@ impl<A: Allocator> Destroy for DeferredRendererResources<A> {
     fn destroy<'a>(&mut self, context: Self::Context<'a>) -> DestroyResult<Self> {
         let (device, allocator) = context;
         self.mesh.destroy((device, &RefCell::new(allocator)))?;
-        self.skybox.destroy((device, allocator))?;
+        self.shadow_map.destroy((device, allocator))?;
         Ok(())
     }
 }
 
+impl<P: GraphicsPipelinePackList> DeferredRendererPipelines<P> {
+    fn create_gbuffer_pipelines(device: &Device) -> VkResult<GBufferPipelines> {
+        let depth_prepass = GraphicsPipeline::create(
+            (
+                device.get_pipeline_layout()?,
+                &ShaderDirectory::new(Path::new("_resources/shaders/spv/deferred/depth_prepass")),
+                PolygonMode::Fill,
+            ),
+            device,
+        )?;
+        let depth_prepass_packed = GraphicsPipeline::create(
+            (
+                device.get_pipeline_layout()?,
+                &ShaderDirectory::new(Path::new(
+                    "_resources/shaders/spv/deferred/depth_prepass_packed",
+                )),
+                PolygonMode::Fill,
+            ),
+            device,
+        )?;
+        let shading_pass = GraphicsPipeline::create(
+            (
+                device.get_pipeline_layout()?,
+                &ShaderDirectory::new(Path::new("_resources/shaders/spv/deferred/gbuffer_combine")),
+                PolygonMode::Fill,
+            ),
+            device,
+        )?;
+        Ok((depth_prepass, depth_prepass_packed, shading_pass))
+    }
+
+    // Depth only passes draw every mesh pack, pipeline variant
+    // must match the vertex layout of the pack being drawn
+    fn depth_prepass_for(&self, packed: bool) -> PipelineBindData {
+        match packed {
+            true => (&*self.depth_prepass_packed).into(),
+            false => (&*self.depth_prepass).into(),
+        }
+    }
+
+    fn shadow_pass_for(&self, packed: bool) -> PipelineBindData {
+        match packed {
+            true => (&*self.shadow_pass_packed).into(),
+            false => (&*self.shadow_pass).into(),
+        }
+    }
+}
+
 impl<P: GraphicsPipelinePackList> Create for DeferredRendererPipelines<P> {
     type Config<'a> = P;
     type CreateError = VkError;
@@ -358,24 +793,34 @@ impl<P: GraphicsPipelinePackList> Create for DeferredRendererPipelines<P> {
         config: Self::Config<'a>,
         context: Self::Context<'b>,
     ) -> type_kit::CreateResult<Self> {
-        let depth_prepass = GraphicsPipeline::create(
+        let (depth_prepass, depth_prepass_packed, shading_pass) =
+            Self::create_gbuffer_pipelines(context)?;
+        let shadow_pass = GraphicsPipeline::create(
             (
                 context.get_pipeline_layout()?,
-                &ShaderDirectory::new(Path::new("_resources/shaders/spv/deferred/depth_prepass")),
+                &ShaderDirectory::new(Path::new("_resources/shaders/spv/deferred/shadow_map")),
+                PolygonMode::Fill,
             ),
             context,
         )?;
-        let shading_pass = GraphicsPipeline::create(
+        let shadow_pass_packed = GraphicsPipeline::create(
             (
                 context.get_pipeline_layout()?,
-                &ShaderDirectory::new(Path::new("_resources/shaders/spv/deferred/gbuffer_combine")),
+                &ShaderDirectory::new(Path::new(
+                    "_resources/shaders/spv/deferred/shadow_map_packed",
+                )),
+                PolygonMode::Fill,
             ),
             context,
         )?;
         Ok(DeferredRendererPipelines {
             write_pass: config,
             depth_prepass: DropGuard::new(depth_prepass),
+            depth_prepass_packed: DropGuard::new(depth_prepass_packed),
             shading_pass: DropGuard::new(shading_pass),
+            shadow_pass: DropGuard::new(shadow_pass),
+            shadow_pass_packed: DropGuard::new(shadow_pass_packed),
+            tonemap: None,
         })
     }
 }
@@ -387,61 +832,336 @@ impl<P: GraphicsPipelinePackList> Destroy for DeferredRendererPipelines<P> {
     fn destroy<'a>(&mut self, context: Self::Context<'a>) -> DestroyResult<Self> {
         self.write_pass.destroy(context);
         let _ = self.depth_prepass.destroy(context);
+        let _ = self.depth_prepass_packed.destroy(context);
         let _ = self.shading_pass.destroy(context);
+        let _ = self.shadow_pass.destroy(context);
+        let _ = self.shadow_pass_packed.destroy(context);
+        if let Some(tonemap) = &mut self.tonemap {
+            let _ = tonemap.destroy(context);
+        }
         Ok(())
     }
 }
 
 impl<A: Allocator> Create for DeferredRenderer<A> {
-    type Config<'a> = ();
+    // Number of frames in flight of every frame target
+    type Config<'a> = usize;
     type CreateError = VkError;
 
     fn create<'a, 'b>(
-        _: Self::Config<'a>,
+        config: Self::Config<'a>,
         context: Self::Context<'b>,
     ) -> type_kit::CreateResult<Self> {
         let (context, allocator) = context;
         let render_pass = context.get_render_pass()?;
-        let frame_data = DeferredRendererFrameData::create((), (context, allocator))?;
+        let shadow_render_pass = context.get_render_pass()?;
+        let frame_data =
+            DeferredRendererFrameData::create(context.primary_surface(), (context, allocator))?;
         let resources = DeferredRendererResources::create((), (context, allocator))?;
         Ok(DeferredRenderer {
             render_pass,
+            shadow_render_pass,
             frame_data: DropGuard::new(frame_data),
+            windows: HashMap::new(),
             resources: DropGuard::new(resources),
+            frames_in_flight: config,
         })
     }
 }
 
+impl<A: Allocator> DeferredRenderer<A> {
+    // Recreates swapchain along with size dependent G-Buffer attachments,
+    // surface properties of the context should be updated beforehand
+    pub fn recreate_frame_data(&mut self, context: &Context, allocator: &mut A) -> VkResult<()> {
+        self.frame_data.destroy((context, allocator))?;
+        self.frame_data = DropGuard::new(DeferredRendererFrameData::create(
+            context.primary_surface(),
+            (context, allocator),
+        )?);
+        Ok(())
+    }
+
+    // Creates surface and swapchain of additional window,
+    // drawn with resources and pipelines shared with the primary one
+    pub fn add_window(
+        &mut self,
+        context: &Context,
+        window: &Window,
+        allocator: &mut A,
+    ) -> VkResult<()> {
+        let mut render_target = RenderTargetWindow::create(window, context)?;
+        let frame_data = match DeferredRendererFrameData::create(
+            render_target.present_surface(),
+            (context, allocator),
+        ) {
+            Ok(frame_data) => frame_data,
+            Err(err) => {
+                let _ = render_target.destroy(context);
+                return Err(err);
+            }
+        };
+        self.windows.insert(
+            window.id(),
+            DeferredRendererWindow {
+                window: DropGuard::new(render_target),
+                frame_data: DropGuard::new(frame_data),
+            },
+        );
+        Ok(())
+    }
+
+    // Device should be idle so that frames rendered into the window have finished
+    pub fn remove_window(
+        &mut self,
+        context: &Context,
+        window: WindowId,
+        allocator: &mut A,
+    ) -> VkResult<()> {
+        let mut window = self
+            .windows
+            .remove(&window)
+            .ok_or(VkError::MissingResource("window"))?;
+        window.destroy((context, allocator))?;
+        Ok(())
+    }
+
+    // Counterpart of `recreate_frame_data` for additional windows
+    pub fn recreate_window_frame_data(
+        &mut self,
+        context: &Context,
+        window: WindowId,
+        window_extent: vk::Extent2D,
+        allocator: &mut A,
+    ) -> VkResult<()> {
+        let DeferredRendererWindow { window, frame_data } = self
+            .windows
+            .get_mut(&window)
+            .ok_or(VkError::MissingResource("window"))?;
+        window.update_surface_extent(context, window_extent)?;
+        frame_data.destroy((context, allocator))?;
+        *frame_data = DropGuard::new(DeferredRendererFrameData::create(
+            window.present_surface(),
+            (context, allocator),
+        )?);
+        Ok(())
+    }
+
+    fn has_target(&self, target: FrameTarget) -> bool {
+        match target {
+            FrameTarget::Primary => true,
+            FrameTarget::Window(window) => self.windows.contains_key(&window),
+        }
+    }
+
+    fn frame_data(&self, target: FrameTarget) -> VkResult<&DeferredRendererFrameData<A>> {
+        match target {
+            FrameTarget::Primary => Ok(&self.frame_data),
+            FrameTarget::Window(window) => self
+                .windows
+                .get(&window)
+                .map(|window| &*window.frame_data)
+                .ok_or(VkError::MissingResource("window")),
+        }
+    }
+
+    // Copies color image of the last presented offscreen frame into host memory,
+    // device should be idle so that frame rendering has finished
+    pub fn read_back_frame(&mut self, device: &Device) -> VkResult<Vec<u8>> {
+        let image = self
+            .frame_data
+            .swapchain
+            .presented_image_mut()
+            .ok_or(VkError::MissingResource("presented offscreen frame"))?;
+        device.read_back_image(image, vk::ImageLayout::PRESENT_SRC_KHR)
+    }
+}
+
 impl<A: Allocator> Destroy for DeferredRenderer<A> {
     type Context<'a> = (&'a Context, &'a mut A);
     type DestroyError = DropGuardError<Infallible>;
 
     fn destroy<'a>(&mut self, context: Self::Context<'a>) -> DestroyResult<Self> {
         let (device, allocator) = context;
+        for window in self.windows.values_mut() {
+            window.destroy((device, allocator))?;
+        }
         self.frame_data.destroy((device, allocator))?;
         self.resources.destroy((device, allocator))?;
         Ok(())
     }
 }
 
+impl<A: Allocator> Destroy for DeferredRendererWindow<A> {
+    type Context<'a> = (&'a Context, &'a mut A);
+    type DestroyError = DropGuardError<Infallible>;
+
+    fn destroy<'a>(&mut self, context: Self::Context<'a>) -> DestroyResult<Self> {
+        let (context, allocator) = context;
+        self.frame_data.destroy((context, allocator))?;
+        self.window.destroy(context)?;
+        Ok(())
+    }
+}
+
+impl<A: Allocator, P: GraphicsPipelinePackList> Create for DeferredRendererTarget<A, P> {
+    type Config<'a> = (&'a Swapchain<AttachmentsGBuffer>, ShadowMapSampler, usize);
+    type CreateError = VkError;
+
+    fn create<'a, 'b>(config: Self::Config<'a>, context: Self::Context<'b>) -> CreateResult<Self> {
+        let (swapchain, shadow_map, frames_in_flight) = config;
+        let light = DeferredRendererLight::create((frames_in_flight, shadow_map), context)?;
+        let overlay = TextOverlay::create((frames_in_flight, swapchain.extent), context)?;
+        let frames = FramePool::create(frames_in_flight, context)?;
+        let timestamps = DeferredRendererTimestamps::create(frames_in_flight, context)?;
+        Ok(DeferredRendererTarget {
+            frames,
+            light,
+            overlay: DropGuard::new(overlay),
+            timestamps: DropGuard::new(timestamps),
+            tonemap: None,
+            extent: swapchain.extent,
+        })
+    }
+}
+
+impl<A: Allocator, P: GraphicsPipelinePackList> Destroy for DeferredRendererTarget<A, P> {
+    type Context<'a> = &'a Device;
+    type DestroyError = DropGuardError<Infallible>;
+
+    fn destroy<'a>(&mut self, context: Self::Context<'a>) -> DestroyResult<Self> {
+        self.light.destroy(context)?;
+        self.overlay.destroy(context)?;
+        self.timestamps.destroy(context)?;
+        if let Some(tonemap) = &mut self.tonemap {
+            tonemap.destroy(context)?;
+        }
+        self.frames.destroy(context)?;
+        Ok(())
+    }
+}
+
+impl<A: Allocator, P: GraphicsPipelinePackList> DeferredRendererContext<A, P> {
+    // Makes resources of the frame target current, resources of targets
+    // removed from the renderer since the last frame are released
+    fn select_target(
+        &mut self,
+        device: &Device,
+        target: FrameTarget,
+    ) -> Result<(), Box<dyn Error>> {
+        let renderer = self.renderer.borrow();
+        let removed = self
+            .targets
+            .keys()
+            .copied()
+            .filter(|&target| !renderer.has_target(target))
+            .collect::<Vec<_>>();
+        for target in removed {
+            if let Some(mut resources) = self.targets.remove(&target) {
+                resources.destroy(device)?;
+            }
+        }
+        if target != self.current_target {
+            let resources = match self.targets.remove(&target) {
+                Some(resources) => resources,
+                None => DeferredRendererTarget::create(
+                    (
+                        &renderer.frame_data(target)?.swapchain,
+                        renderer.resources.shadow_map.sampler(),
+                        renderer.frames_in_flight,
+                    ),
+                    device,
+                )?,
+            };
+            let previous = std::mem::replace(&mut self.target, resources);
+            self.targets.insert(self.current_target, previous);
+            self.current_target = target;
+        }
+        Ok(())
+    }
+
+    // Tonemap resources of the target are created once the pass is first recorded
+    // for it and recreated after resize, when frames using them have finished.
+    // Returns exposure of the pass, pass is skipped when swapchain images
+    // cannot be blitted to and from
+    fn prepare_tonemap(&mut self, device: &Device) -> Result<Option<f32>, Box<dyn Error>> {
+        let Some(exposure) = self.exposure else {
+            return Ok(None);
+        };
+        let transfer_supported = self
+            .renderer
+            .borrow()
+            .frame_data(self.current_target)?
+            .swapchain
+            .transfer_supported;
+        if !transfer_supported {
+            return Ok(None);
+        }
+        if self.pipelines.tonemap.is_none() {
+            let pipeline = device.create_compute_pipeline::<TonemapPipeline>(
+                &ShaderDirectory::new(Path::new("_resources/shaders/spv/deferred/tonemap")),
+            )?;
+            self.pipelines.tonemap = Some(DropGuard::new(pipeline));
+        }
+        if let Some(tonemap) = &mut self.target.tonemap {
+            if tonemap.extent() != self.target.extent {
+                tonemap.destroy(device)?;
+                self.target.tonemap = None;
+            }
+        }
+        if self.target.tonemap.is_none() {
+            let tonemap = DeferredRendererTonemap::create(self.target.extent, device)?;
+            self.target.tonemap = Some(DropGuard::new(tonemap));
+        }
+        Ok(Some(exposure))
+    }
+
+    // Picks up swapchain recreated by the renderer since the last frame
+    fn update_swapchain(&mut self) -> Result<(), Box<dyn Error>> {
+        let extent = self
+            .renderer
+            .borrow()
+            .frame_data(self.current_target)?
+            .swapchain
+            .extent;
+        if extent != self.target.extent {
+            self.target.overlay.set_extent(extent);
+            self.target.extent = extent;
+        }
+        Ok(())
+    }
+}
+
 impl<A: Allocator, P: GraphicsPipelinePackList> Create for DeferredRendererContext<A, P> {
     type Config<'a> = (Rc<RefCell<DropGuard<DeferredRenderer<A>>>>, P);
     type CreateError = VkError;
 
     fn create<'a, 'b>(config: Self::Config<'a>, context: Self::Context<'b>) -> CreateResult<Self> {
         let (renderer, pipelines) = config;
-        let (pipelines, frames) = {
+        let (pipelines, target) = {
             let renderer = renderer.borrow();
             (
                 DeferredRendererPipelines::create(pipelines, context)?,
-                FramePool::create(&renderer.frame_data.swapchain, context)?,
+                DeferredRendererTarget::create(
+                    (
+                        &renderer.frame_data.swapchain,
+                        renderer.resources.shadow_map.sampler(),
+                        renderer.frames_in_flight,
+                    ),
+                    context,
+                )?,
             )
         };
         Ok(DeferredRendererContext {
             renderer: renderer.clone(),
             pipelines,
-            frames,
+            light: None,
+            exposure: None,
+            target,
+            current_target: FrameTarget::Primary,
+            targets: HashMap::new(),
             current_frame: None,
+            screenshot: ScreenshotState::default(),
+            start_time: Instant::now(),
         })
     }
 }
@@ -451,8 +1171,34 @@ impl<A: Allocator, P: GraphicsPipelinePackList> Destroy for DeferredRendererCont
     type DestroyError = DropGuardError<Infallible>;
 
     fn destroy<'a>(&mut self, context: Self::Context<'a>) -> DestroyResult<Self> {
+        self.screenshot.destroy(context)?;
         self.pipelines.destroy(context)?;
-        self.frames.destroy(context)?;
+        self.target.destroy(context)?;
+        for target in self.targets.values_mut() {
+            target.destroy(context)?;
+        }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_view_areas_share_edge() {
+        let extent = vk::Extent2D {
+            width: 801,
+            height: 600,
+        };
+        let (left, right) = Rect::full().split_vertical();
+        let (left, right) = (view_area(left, extent), view_area(right, extent));
+        assert_eq!(left.offset.x, 0);
+        assert_eq!(
+            left.offset.x as u32 + left.extent.width,
+            right.offset.x as u32
+        );
+        assert_eq!(right.offset.x as u32 + right.extent.width, extent.width);
+        assert_eq!(left.extent.height, extent.height);
+    }
+}
@@ -8,110 +8,195 @@ use crate::context::device::{
         operation::Graphics,
         BeginCommand, FinishedCommand, Persistent,
     },
-    descriptor::{CameraDescriptorSet, Descriptor},
+    descriptor::{Descriptor, ShadowDescriptorSet},
     framebuffer::{
-        presets::AttachmentsGBuffer, ClearColor, ClearDeptStencil, ClearNone, ClearValueBuilder,
+        presets::{AttachmentsGBuffer, AttachmentsShadowMap},
+        Clear,
     },
-    memory::Allocator,
-    pipeline::GraphicsPipelinePackList,
-    render_pass::{GBufferDepthPrepas, GBufferShadingPass, GBufferSkyboxPass},
+    memory::{Allocator, StaticAllocator},
+    pipeline::{GBufferSkyboxPipeline, GraphicsPipelinePackList},
+    render_pass::{
+        GBufferDepthPrepas, GBufferOverlayPass, GBufferShadingPass, GBufferSkyboxPass,
+        GBufferTransparentPass, ShadowMapPass,
+    },
+    resources::Skybox,
+    screenshot::ScreenshotCapture,
     swapchain::SwapchainFrame,
     Device,
 };
 use graphics::renderer::camera::CameraMatrices;
 
-use super::DeferredRendererContext;
+use super::{
+    timestamps::{GBUFFER_BEGIN, GBUFFER_END, LIGHTING_END},
+    DeferredRendererContext,
+};
 
 pub(super) struct Commands<P: GraphicsPipelinePackList> {
     pub write_pass: Vec<BeginCommand<Persistent, Secondary, Graphics>>,
     pub depth_prepass: BeginCommand<Persistent, Secondary, Graphics>,
     pub shading_pass: BeginCommand<Persistent, Secondary, Graphics>,
     pub skybox_pass: BeginCommand<Persistent, Secondary, Graphics>,
+    pub transparent_pass: BeginCommand<Persistent, Secondary, Graphics>,
+    pub overlay_pass: BeginCommand<Persistent, Secondary, Graphics>,
+    pub shadow_pass: BeginCommand<Persistent, Secondary, Graphics>,
     pub _phantom: PhantomData<P>,
 }
 
+// Per frame state of the primary command, tonemap pass is recorded when
+// `exposure` is provided, its resources are expected to be prepared beforehand
+pub(super) struct FrameOptions<'a> {
+    pub exposure: Option<f32>,
+    pub capture: Option<&'a mut ScreenshotCapture>,
+    pub frame_index: usize,
+}
+
 impl<A: Allocator, P: GraphicsPipelinePackList> DeferredRendererContext<A, P> {
     pub(super) fn prepare_commands(
         &mut self,
         device: &Device,
         swapchain_frame: &SwapchainFrame<AttachmentsGBuffer>,
-        camera_descriptor: Descriptor<CameraDescriptorSet>,
+        light_descriptor: Descriptor<ShadowDescriptorSet>,
         camera_matrices: &CameraMatrices,
+        skybox: Option<
+            &Skybox<StaticAllocator, GBufferSkyboxPipeline<AttachmentsGBuffer, StaticAllocator>>,
+        >,
+        frame_index: usize,
     ) -> Result<Commands<P>, Box<dyn Error>> {
         let renderer = self.renderer.borrow();
+        let timestamps = &self.target.timestamps;
+        let frame_data = renderer.frame_data(self.current_target)?;
+        let shadow_pass = {
+            let (_, command) = self.target.frames.secondary_commands.next();
+            device.record_command(
+                device.begin_secondary_command::<_, _, _, ShadowMapPass<AttachmentsShadowMap>>(
+                    command,
+                    renderer.shadow_render_pass,
+                    renderer.resources.shadow_map.framebuffer(),
+                )?,
+                |command| command.bind_pipeline(&*self.pipelines.shadow_pass),
+            )
+        };
         let depth_prepass = {
-            let (_, command) = self.frames.secondary_commands.next();
+            let (_, command) = self.target.frames.secondary_commands.next();
             device.record_command(
                 device.begin_secondary_command::<_, _, _, GBufferDepthPrepas<AttachmentsGBuffer>>(
                     command,
                     renderer.render_pass,
                     swapchain_frame.framebuffer,
                 )?,
+                // Camera descriptor is bound for each view when draw calls are recorded
                 |command| {
-                    command
-                        .bind_pipeline(&*self.pipelines.depth_prepass)
-                        .bind_descriptor_set(
-                            &camera_descriptor
-                                .get_binding_data(&self.pipelines.depth_prepass)
-                                .unwrap(),
-                        )
+                    timestamps.write(
+                        command,
+                        frame_index,
+                        GBUFFER_BEGIN,
+                        vk::PipelineStageFlags::TOP_OF_PIPE,
+                    )
                 },
             )
         };
-        let (_, shading_pass) = self.frames.secondary_commands.next();
+        let (_, shading_pass) = self.target.frames.secondary_commands.next();
         let shading_pass = device.begin_secondary_command::<_, _, _, GBufferShadingPass<_>>(
             shading_pass,
             renderer.render_pass,
             swapchain_frame.framebuffer,
         )?;
+        // G-Buffer subpasses end once all commands preceding the shading pass finish
         let shading_pass = device.record_command(shading_pass, |command| {
-            command
+            let command = timestamps
+                .write(
+                    command,
+                    frame_index,
+                    GBUFFER_END,
+                    vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                )
                 .bind_pipeline(&*self.pipelines.shading_pass)
                 .bind_descriptor_set(
-                    &renderer
-                        .frame_data
+                    &frame_data
                         .descriptors
                         .get(0)
                         .get_binding_data(&self.pipelines.shading_pass)
                         .unwrap(),
                 )
+                .bind_descriptor_set(
+                    &light_descriptor
+                        .get_binding_data(&self.pipelines.shading_pass)
+                        .unwrap(),
+                )
                 .bind_mesh_pack(&*renderer.resources.mesh)
-                .draw_mesh(renderer.resources.mesh.get(0))
+                .draw_mesh(renderer.resources.mesh.get(0));
+            timestamps.write(
+                command,
+                frame_index,
+                LIGHTING_END,
+                vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+            )
         });
-        let (_, skybox_pass) = self.frames.secondary_commands.next();
+        let (_, skybox_pass) = self.target.frames.secondary_commands.next();
         let skybox_pass = device.begin_secondary_command::<_, _, _, GBufferSkyboxPass<_>>(
             skybox_pass,
             renderer.render_pass,
             swapchain_frame.framebuffer,
         )?;
-        let skybox_pass = device.record_command(skybox_pass, |command| {
-            command.draw_skybox(&renderer.resources.skybox, *camera_matrices)
+        let skybox_pass = device.record_command(skybox_pass, |command| match skybox {
+            Some(skybox) => command.draw_skybox(skybox, *camera_matrices),
+            None => command,
         });
+        let (_, transparent_pass) = self.target.frames.secondary_commands.next();
+        let transparent_pass = device
+            .begin_secondary_command::<_, _, _, GBufferTransparentPass<_>>(
+                transparent_pass,
+                renderer.render_pass,
+                swapchain_frame.framebuffer,
+            )?;
+        let (_, overlay_pass) = self.target.frames.secondary_commands.next();
+        let overlay_pass = device.begin_secondary_command::<_, _, _, GBufferOverlayPass<_>>(
+            overlay_pass,
+            renderer.render_pass,
+            swapchain_frame.framebuffer,
+        )?;
         let write_pass = Vec::with_capacity(P::LEN);
         Ok(Commands {
             write_pass,
             depth_prepass,
             shading_pass,
             skybox_pass,
+            transparent_pass,
+            overlay_pass,
+            shadow_pass,
             _phantom: PhantomData,
         })
     }
 
     pub(super) fn record_primary_command(
-        &self,
+        &mut self,
         device: &Device,
         primary_command: BeginCommand<Persistent, Primary, Graphics>,
         commands: Commands<P>,
         swapchain_frame: &SwapchainFrame<AttachmentsGBuffer>,
+        options: FrameOptions,
     ) -> Result<FinishedCommand<Persistent, Primary, Graphics>, Box<dyn Error>> {
+        let FrameOptions {
+            exposure,
+            capture,
+            frame_index,
+        } = options;
         let Commands {
             write_pass,
             depth_prepass,
             shading_pass,
             skybox_pass,
+            transparent_pass,
+            overlay_pass,
+            shadow_pass,
             ..
         } = commands;
         let renderer = self.renderer.borrow();
+        let presentable_image = renderer
+            .frame_data(self.current_target)?
+            .swapchain
+            .get_image(swapchain_frame);
+        let shadow_pass = device.finish_command(shadow_pass)?;
         let depth_prepass = device.finish_command(depth_prepass)?;
         let skybox_pass = device.finish_command(skybox_pass)?;
         let write_pass = write_pass
@@ -119,50 +204,63 @@ impl<A: Allocator, P: GraphicsPipelinePackList> DeferredRendererContext<A, P> {
             .flat_map(|command| device.finish_command(command))
             .collect::<Vec<_>>();
         let shading_pass = device.finish_command(shading_pass)?;
+        let transparent_pass = device.finish_command(transparent_pass)?;
+        let overlay_pass = device.finish_command(overlay_pass)?;
 
-        let clear_values = ClearValueBuilder::new()
-            .push(ClearNone {})
-            .push(ClearDeptStencil {
-                depth_stencil: vk::ClearDepthStencilValue {
-                    depth: 1.0,
-                    stencil: 0,
-                },
-            })
-            .push(ClearColor {
-                color: vk::ClearColorValue {
-                    float32: [0.0, 0.0, 0.0, 1.0],
-                },
-            })
-            .push(ClearColor {
-                color: vk::ClearColorValue {
-                    float32: [0.0, 0.0, 0.0, 1.0],
-                },
-            })
-            .push(ClearColor {
-                color: vk::ClearColorValue {
-                    float32: [0.0, 0.0, 0.0, 1.0],
-                },
-            })
-            .push(ClearColor {
-                color: vk::ClearColorValue {
-                    float32: [0.0, 0.0, 0.0, 1.0],
-                },
-            });
+        let clear_values = Clear::<AttachmentsGBuffer>::default();
+        let shadow_clear_values = Clear::<AttachmentsShadowMap>::default();
         let primary_command = device.record_command(primary_command, |command| {
-            let command = command
+            let command = self
+                .target
+                .timestamps
+                .reset(command, frame_index)
+                .depth_attachment_barrier(renderer.resources.shadow_map.image())
+                .begin_render_pass_with_framebuffer(
+                    renderer.resources.shadow_map.framebuffer(),
+                    renderer.resources.shadow_map.render_area(),
+                    &renderer.shadow_render_pass,
+                    &shadow_clear_values,
+                )
+                .write_secondary(&shadow_pass)
+                .end_render_pass()
+                .memory_barrier(
+                    vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS
+                        | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS,
+                    vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+                    vk::PipelineStageFlags::FRAGMENT_SHADER,
+                    vk::AccessFlags::SHADER_READ,
+                )
                 .begin_render_pass(swapchain_frame, &renderer.render_pass, &clear_values)
                 .write_secondary(&depth_prepass)
                 .next_render_pass()
                 .write_secondary(&skybox_pass)
                 .next_render_pass();
-            write_pass
+            let command = write_pass
                 .into_iter()
                 .fold(command, |command, write_pass| {
                     command.write_secondary(&write_pass)
                 })
                 .next_render_pass()
                 .write_secondary(&shading_pass)
-                .end_render_pass()
+                .next_render_pass()
+                .write_secondary(&transparent_pass)
+                .next_render_pass()
+                .write_secondary(&overlay_pass)
+                .end_render_pass();
+            let command = match (exposure, &self.pipelines.tonemap, &mut self.target.tonemap) {
+                (Some(exposure), Some(pipeline), Some(tonemap)) => {
+                    tonemap.record(command, pipeline, presentable_image, exposure)
+                }
+                _ => command,
+            };
+            match capture {
+                Some(capture) => command.copy_presentable_image_to_buffer(
+                    capture.image(),
+                    capture.extent(),
+                    capture.buffer_mut(),
+                ),
+                None => command,
+            }
         });
         Ok(device.finish_command(primary_command)?)
     }
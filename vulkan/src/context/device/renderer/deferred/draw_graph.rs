@@ -4,7 +4,7 @@ use std::{
 };
 
 use graphics::{
-    model::{Drawable, Material, MaterialHandle, Vertex},
+    model::{Drawable, Material, MaterialHandle, PackedStaticVertex, Vertex},
     shader::{ShaderHandle, ShaderType},
 };
 
@@ -52,6 +52,10 @@ impl BufferIndex {
         let mesh_pack_index = TypeId::of::<V>();
         Self { mesh_pack_index }
     }
+
+    fn is<V: Vertex>(&self) -> bool {
+        self.mesh_pack_index == TypeId::of::<V>()
+    }
 }
 
 pub struct BufferState {
@@ -108,6 +112,17 @@ pub struct PipelineState {
 pub struct DrawGraph {
     // TODO: Change representation to use indexed linear buffers
     pub pipeline_states: HashMap<PipelineIndex, PipelineState>,
+    pub transparent_states: HashMap<PipelineIndex, PipelineState>,
+}
+
+// Single instance of transparent drawable, ordered by view space depth of its origin
+struct TransparentDraw<'a> {
+    pipeline_state: &'a PipelineState,
+    descriptor_state: &'a DescriptorState,
+    buffer_state: &'a BufferState,
+    model_state: &'a ModelState,
+    instance: &'a Matrix4,
+    depth: f32,
 }
 
 impl<A: Allocator, P: GraphicsPipelinePackList> DeferredRendererContext<A, P> {
@@ -126,54 +141,98 @@ impl<A: Allocator, P: GraphicsPipelinePackList> DeferredRendererContext<A, P> {
         drawable: &D,
         transform: &Matrix4,
     ) {
+        self.append_draw_batch(
+            material_packs,
+            mesh_packs,
+            shader,
+            &[(drawable, *transform)],
+        );
+    }
+
+    // Pipeline state is resolved once for the whole batch, items are sorted by material
+    // and mesh so that descriptor and mesh pack states are looked up once per group
+    pub(super) fn append_draw_batch<
+        T1: Allocator,
+        T2: Allocator,
+        S: ShaderType,
+        D: Drawable,
+        M: MaterialPackList<T2>,
+        V: MeshPackList<T1>,
+    >(
+        &mut self,
+        material_packs: &M,
+        mesh_packs: &V,
+        shader: ShaderHandle<S>,
+        items: &[(&D, Matrix4)],
+    ) {
+        if items.is_empty() {
+            return;
+        }
         if let Some(mut current_frame) = self.current_frame.take() {
             let state = &mut current_frame.renderer_state;
+            let view = &mut state.views[state.current_view];
+            let frame_descriptor = view.frame_descriptor;
             let pipeline_index = PipelineIndex::get(shader);
-            let pipeline_state = state
-                .draw_graph
-                .pipeline_states
+            // Transparent materials are routed into forward pass recorded after lighting
+            let pipeline_states = if <S::Material as Material>::transparent() {
+                &mut view.draw_graph.transparent_states
+            } else {
+                &mut view.draw_graph.pipeline_states
+            };
+            let pipeline_state = pipeline_states
                 .entry(pipeline_index)
                 .or_insert_with(|| self.get_pipeline_state(shader));
-            let descriptor_index = DescriptorIndex::get(drawable.material());
-            let descriptor_state = pipeline_state
-                .descriptor_states
-                .entry(descriptor_index)
-                .or_insert_with(|| {
-                    let material_binding_data =
-                        material_packs.try_get::<D::Material>().map(|pack| {
-                            let material_descriptor =
-                                pack.get_descriptor(descriptor_index.material_index as usize);
-                            self.get_descriptor_binding_data(material_descriptor, shader)
-                        });
-                    let camera_binding_data = Some(
-                        self.get_descriptor_binding_data(current_frame.camera_descriptor, shader),
-                    );
-                    DescriptorState {
-                        sets: [material_binding_data, camera_binding_data]
-                            .into_iter()
-                            .flatten()
-                            .collect(),
-                        buffer_states: HashMap::new(),
-                    }
-                });
+            let mut items = items.iter().collect::<Vec<_>>();
+            items.sort_by_key(|(drawable, _)| {
+                (drawable.material().index(), drawable.mesh().index())
+            });
             let mesh_pack = LazyCell::new(|| mesh_packs.try_get::<D::Vertex>().unwrap());
-            let buffer_index = BufferIndex::get::<D::Vertex>();
-            let buffer_state = descriptor_state
-                .buffer_states
-                .entry(buffer_index)
-                .or_insert_with(|| BufferState {
-                    mesh_pack_binding: (*mesh_pack).into(),
-                    model_states: HashMap::new(),
-                });
-            let model_index = ModelIndex::get(drawable);
-            buffer_state
-                .model_states
-                .entry(model_index)
-                .and_modify(|model_states| model_states.instances.push(*transform))
-                .or_insert_with(|| ModelState {
-                    mesh_bind_data: (*mesh_pack).get(model_index.mesh_index as usize).into(),
-                    instances: vec![*transform],
-                });
+            for material_items in items
+                .chunk_by(|(lhs, _), (rhs, _)| lhs.material().index() == rhs.material().index())
+            {
+                let descriptor_index = DescriptorIndex::get(material_items[0].0.material());
+                let descriptor_state = pipeline_state
+                    .descriptor_states
+                    .entry(descriptor_index)
+                    .or_insert_with(|| {
+                        let material_binding_data =
+                            material_packs.try_get::<D::Material>().map(|pack| {
+                                let material_descriptor =
+                                    pack.get_descriptor(descriptor_index.material_index as usize);
+                                self.get_descriptor_binding_data(material_descriptor, shader)
+                            });
+                        let camera_binding_data =
+                            Some(self.get_descriptor_binding_data(frame_descriptor, shader));
+                        DescriptorState {
+                            sets: [material_binding_data, camera_binding_data]
+                                .into_iter()
+                                .flatten()
+                                .collect(),
+                            buffer_states: HashMap::new(),
+                        }
+                    });
+                let buffer_index = BufferIndex::get::<D::Vertex>();
+                let buffer_state = descriptor_state
+                    .buffer_states
+                    .entry(buffer_index)
+                    .or_insert_with(|| BufferState {
+                        mesh_pack_binding: (*mesh_pack).into(),
+                        model_states: HashMap::new(),
+                    });
+                for (drawable, transform) in material_items {
+                    let model_index = ModelIndex::get(*drawable);
+                    buffer_state
+                        .model_states
+                        .entry(model_index)
+                        .and_modify(|model_states| model_states.instances.push(*transform))
+                        .or_insert_with(|| ModelState {
+                            mesh_bind_data: (*mesh_pack)
+                                .get(model_index.mesh_index as usize)
+                                .into(),
+                            instances: vec![*transform],
+                        });
+                }
+            }
             self.current_frame.replace(current_frame);
         }
     }
@@ -191,53 +250,116 @@ impl<A: Allocator, P: GraphicsPipelinePackList> DeferredRendererContext<A, P> {
                     mut write_pass,
                     shading_pass,
                     skybox_pass,
+                    transparent_pass,
+                    overlay_pass,
+                    shadow_pass,
                     ..
                 },
-            draw_graph,
+            mut views,
+            light_space,
+            frame_index,
             ..
         } = state;
         let renderer = self.renderer.borrow();
-        let depth_prepass = device.record_command(depth_prepass, |command| {
-            draw_graph
-                .pipeline_states
-                .iter()
-                .fold(command, |command, (_, pipeline_state)| {
-                    pipeline_state.descriptor_states.iter().fold(
-                        command,
-                        |command, (_, descriptor_state)| {
-                            descriptor_state.buffer_states.iter().fold(
-                                command,
-                                |command, (_, buffer_state)| {
-                                    let command =
-                                        command.bind_mesh_pack(buffer_state.mesh_pack_binding);
-                                    buffer_state.model_states.iter().fold(
-                                        command,
-                                        |command, (_, model_state)| {
-                                            model_state.instances.iter().fold(
-                                                command,
-                                                |command, instance| {
-                                                    command
-                                                        .push_constants(
-                                                            self.pipelines
-                                                                .depth_prepass
-                                                                .get_push_range::<ModelMatrix>(
-                                                                    &instance.into(),
-                                                                ),
-                                                        )
-                                                        .draw_mesh(model_state.mesh_bind_data)
-                                                },
+        // Shadow map is shared by all views, so drawables visible in multiple views
+        // are rendered into it once for each of them
+        let shadow_pass = match light_space {
+            Some(light_space) => device.record_command(shadow_pass, |command| {
+                views
+                    .iter()
+                    .flat_map(|view| view.draw_graph.pipeline_states.values())
+                    .flat_map(|pipeline_state| pipeline_state.descriptor_states.values())
+                    .flat_map(|descriptor_state| descriptor_state.buffer_states.iter())
+                    .fold(command, |command, (buffer_index, buffer_state)| {
+                        let command = command
+                            .bind_pipeline(
+                                self.pipelines
+                                    .shadow_pass_for(buffer_index.is::<PackedStaticVertex>()),
+                            )
+                            .bind_mesh_pack(buffer_state.mesh_pack_binding);
+                        buffer_state
+                            .model_states
+                            .values()
+                            .fold(command, |command, model_state| {
+                                model_state
+                                    .instances
+                                    .iter()
+                                    .fold(command, |command, instance| {
+                                        command
+                                            .push_constants(
+                                                self.pipelines
+                                                    .shadow_pass
+                                                    .get_push_range::<ModelMatrix>(
+                                                        &(&(light_space * *instance)).into(),
+                                                    ),
                                             )
-                                        },
-                                    )
-                                },
+                                            .draw_mesh(model_state.mesh_bind_data)
+                                    })
+                            })
+                    })
+            }),
+            None => shadow_pass,
+        };
+        let depth_prepass = device.record_command(depth_prepass, |command| {
+            views.iter().fold(command, |command, view| {
+                let command = command
+                    .set_viewport(view.area)
+                    .set_scissor(view.area)
+                    .bind_pipeline(&*self.pipelines.depth_prepass)
+                    .bind_descriptor_set(
+                        &view
+                            .frame_descriptor
+                            .get_binding_data(&self.pipelines.depth_prepass)
+                            .unwrap(),
+                    );
+                view.draw_graph
+                    .pipeline_states
+                    .values()
+                    .flat_map(|pipeline_state| pipeline_state.descriptor_states.values())
+                    .flat_map(|descriptor_state| descriptor_state.buffer_states.iter())
+                    .fold(command, |command, (buffer_index, buffer_state)| {
+                        let command = command
+                            .bind_pipeline(
+                                self.pipelines
+                                    .depth_prepass_for(buffer_index.is::<PackedStaticVertex>()),
                             )
-                        },
-                    )
-                })
+                            .bind_mesh_pack(buffer_state.mesh_pack_binding);
+                        buffer_state
+                            .model_states
+                            .values()
+                            .fold(command, |command, model_state| {
+                                model_state
+                                    .instances
+                                    .iter()
+                                    .fold(command, |command, instance| {
+                                        command
+                                            .push_constants(
+                                                self.pipelines
+                                                    .depth_prepass
+                                                    .get_push_range::<ModelMatrix>(
+                                                        &instance.into(),
+                                                    ),
+                                            )
+                                            .draw_mesh(model_state.mesh_bind_data)
+                                    })
+                            })
+                    })
+            })
         });
 
-        for (_, pipeline_state) in draw_graph.pipeline_states {
-            let (_, command) = self.frames.secondary_commands.next();
+        // Draws of all views using the same pipeline are recorded into single
+        // secondary command, keeping the number of commands independent of views count
+        let mut view_pipeline_states = HashMap::<_, Vec<_>>::new();
+        for view in views.iter_mut() {
+            for (pipeline_index, pipeline_state) in view.draw_graph.pipeline_states.drain() {
+                view_pipeline_states
+                    .entry(pipeline_index)
+                    .or_default()
+                    .push((view.area, pipeline_state));
+            }
+        }
+        for (_, pipeline_states) in view_pipeline_states {
+            let (_, command) = self.target.frames.secondary_commands.next();
             let command = device.record_command(
                 device.begin_secondary_command::<_, _, _, GBufferWritePass<AttachmentsGBuffer>>(
                     command,
@@ -245,50 +367,129 @@ impl<A: Allocator, P: GraphicsPipelinePackList> DeferredRendererContext<A, P> {
                     swapchain_frame.framebuffer,
                 )?,
                 |command| {
-                    let command = command.bind_pipeline(pipeline_state.pipeline_bind_data);
-                    pipeline_state.descriptor_states.iter().fold(
-                        command,
-                        |command, (_, descriptor_state)| {
-                            let command = descriptor_state
-                                .sets
-                                .iter()
-                                .fold(command, |c, set| c.bind_descriptor_set(set));
-                            descriptor_state.buffer_states.iter().fold(
+                    pipeline_states
+                        .iter()
+                        .fold(command, |command, (area, pipeline_state)| {
+                            let command = command
+                                .set_viewport(*area)
+                                .set_scissor(*area)
+                                .bind_pipeline(pipeline_state.pipeline_bind_data);
+                            pipeline_state.descriptor_states.values().fold(
                                 command,
-                                |command, (_, buffer_state)| {
-                                    let command =
-                                        command.bind_mesh_pack(buffer_state.mesh_pack_binding);
-                                    buffer_state.model_states.iter().fold(
+                                |command, descriptor_state| {
+                                    let command = descriptor_state
+                                        .sets
+                                        .iter()
+                                        .fold(command, |c, set| c.bind_descriptor_set(set));
+                                    descriptor_state.buffer_states.values().fold(
                                         command,
-                                        |command, (_, model_state)| {
-                                            model_state.instances.iter().fold(
+                                        |command, buffer_state| {
+                                            let command = command
+                                                .bind_mesh_pack(buffer_state.mesh_pack_binding);
+                                            buffer_state.model_states.values().fold(
                                                 command,
-                                                |command, instance| {
-                                                    command
-                                                        .push_constants(pipeline_state
-                                                            .push_constant_mapper
-                                                            .map_push_constant::<ModelNormalMatrix>(
-                                                                &instance.into()
-                                                            ).unwrap())
-                                                        .draw_mesh(model_state.mesh_bind_data)
+                                                |command, model_state| {
+                                                    model_state.instances.iter().fold(
+                                                        command,
+                                                        |command, instance| {
+                                                            command
+                                                                .push_constants(
+                                                                    pipeline_state
+                                                                        .push_constant_mapper
+                                                                        .map_push_constant::<
+                                                                            ModelNormalMatrix,
+                                                                        >(
+                                                                            &instance.into()
+                                                                        )
+                                                                        .unwrap(),
+                                                                )
+                                                                .draw_mesh(
+                                                                    model_state.mesh_bind_data,
+                                                                )
+                                                        },
+                                                    )
                                                 },
                                             )
                                         },
                                     )
                                 },
                             )
-                        },
-                    )
+                        })
                 },
             );
             write_pass.push(command);
         }
 
+        // Sorted draws interleave pipelines and materials, so state is bound for each draw
+        let transparent_pass = device.record_command(transparent_pass, |command| {
+            views.iter().fold(command, |command, view| {
+                let view_matrix = view.view_matrix;
+                let mut transparent_draws = view
+                    .draw_graph
+                    .transparent_states
+                    .values()
+                    .flat_map(|pipeline_state| {
+                        pipeline_state.descriptor_states.values().flat_map(
+                            move |descriptor_state| {
+                                descriptor_state.buffer_states.values().flat_map(
+                                    move |buffer_state| {
+                                        buffer_state.model_states.values().flat_map(
+                                            move |model_state| {
+                                                model_state.instances.iter().map(move |instance| {
+                                                    TransparentDraw {
+                                                        pipeline_state,
+                                                        descriptor_state,
+                                                        buffer_state,
+                                                        model_state,
+                                                        instance,
+                                                        // Camera looks down the negative z axis
+                                                        depth: -(view_matrix * instance.l).z,
+                                                    }
+                                                })
+                                            },
+                                        )
+                                    },
+                                )
+                            },
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                transparent_draws.sort_by(|lhs, rhs| rhs.depth.total_cmp(&lhs.depth));
+                let command = command.set_viewport(view.area).set_scissor(view.area);
+                transparent_draws.iter().fold(command, |command, draw| {
+                    let pipeline_state = draw.pipeline_state;
+                    let command = command.bind_pipeline(pipeline_state.pipeline_bind_data);
+                    let command = draw
+                        .descriptor_state
+                        .sets
+                        .iter()
+                        .fold(command, |c, set| c.bind_descriptor_set(set));
+                    command
+                        .bind_mesh_pack(draw.buffer_state.mesh_pack_binding)
+                        .push_constants(
+                            pipeline_state
+                                .push_constant_mapper
+                                .map_push_constant::<ModelNormalMatrix>(&draw.instance.into())
+                                .unwrap(),
+                        )
+                        .draw_mesh(draw.model_state.mesh_bind_data)
+                })
+            })
+        });
+
+        let overlay_vertices = self.target.overlay.flush(device, frame_index)?;
+        let overlay_pass = device.record_command(overlay_pass, |command| {
+            command.draw_overlay(&self.target.overlay, overlay_vertices)
+        });
+
         Ok(Commands {
             depth_prepass,
             write_pass,
             shading_pass,
             skybox_pass,
+            transparent_pass,
+            overlay_pass,
+            shadow_pass,
             _phantom: PhantomData,
         })
     }
@@ -328,6 +529,7 @@ impl DrawGraph {
     pub(super) fn new() -> Self {
         Self {
             pipeline_states: HashMap::new(),
+            transparent_states: HashMap::new(),
         }
     }
 }
@@ -0,0 +1,116 @@
+use std::convert::Infallible;
+
+use ash::vk;
+use type_kit::{
+    Create, CreateResult, Destroy, DestroyCollection, DestroyResult, DropGuard, DropGuardError,
+};
+
+use crate::context::{
+    device::{
+        command::{level::Level, operation::Operation, RecordingCommand},
+        profiler::{GpuPassTimings, GpuProfiler},
+        Device,
+    },
+    error::{VkError, VkResult},
+};
+
+pub(super) const GBUFFER_BEGIN: u32 = 0;
+pub(super) const GBUFFER_END: u32 = 1;
+pub(super) const LIGHTING_END: u32 = 2;
+const QUERY_COUNT: u32 = 3;
+
+// Timestamps around the G-Buffer and lighting subpasses, each frame in flight
+// writes its own queries, read back once the frame slot is reused.
+// Timestamps are skipped when the graphics queue does not support them
+pub(super) struct DeferredRendererTimestamps {
+    profilers: Vec<DropGuard<GpuProfiler>>,
+    // Frames whose queries were submitted and not yet resolved
+    submitted: Vec<bool>,
+    timings: Option<GpuPassTimings>,
+}
+
+impl DeferredRendererTimestamps {
+    #[inline]
+    pub fn timings(&self) -> Option<GpuPassTimings> {
+        self.timings
+    }
+
+    // Frame fence has to be waited on beforehand
+    pub fn resolve(&mut self, device: &Device, frame_index: usize) -> VkResult<()> {
+        if let (Some(profiler), Some(submitted)) = (
+            self.profilers.get(frame_index),
+            self.submitted.get_mut(frame_index),
+        ) {
+            if *submitted {
+                let timestamps = profiler.resolve(device)?;
+                self.timings = Some(GpuPassTimings {
+                    gbuffer: timestamps[GBUFFER_END as usize] - timestamps[GBUFFER_BEGIN as usize],
+                    lighting: timestamps[LIGHTING_END as usize] - timestamps[GBUFFER_END as usize],
+                });
+                *submitted = false;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn frame_submitted(&mut self, frame_index: usize) {
+        if let Some(submitted) = self.submitted.get_mut(frame_index) {
+            *submitted = true;
+        }
+    }
+
+    pub fn reset<'a, T, L: Level, O: Operation>(
+        &self,
+        command: RecordingCommand<'a, T, L, O>,
+        frame_index: usize,
+    ) -> RecordingCommand<'a, T, L, O> {
+        match self.profilers.get(frame_index) {
+            Some(profiler) => command.reset_queries(profiler),
+            None => command,
+        }
+    }
+
+    pub fn write<'a, T, L: Level, O: Operation>(
+        &self,
+        command: RecordingCommand<'a, T, L, O>,
+        frame_index: usize,
+        query_index: u32,
+        stage: vk::PipelineStageFlags,
+    ) -> RecordingCommand<'a, T, L, O> {
+        match self.profilers.get(frame_index) {
+            Some(profiler) => command.write_timestamp(profiler, query_index, stage),
+            None => command,
+        }
+    }
+}
+
+impl Create for DeferredRendererTimestamps {
+    // Number of frames in flight
+    type Config<'a> = usize;
+    type CreateError = VkError;
+
+    fn create<'a, 'b>(config: Self::Config<'a>, context: Self::Context<'b>) -> CreateResult<Self> {
+        let profilers = if context.timestamp_valid_bits() > 0 {
+            (0..config)
+                .map(|_| Ok(DropGuard::new(GpuProfiler::create(QUERY_COUNT, context)?)))
+                .collect::<VkResult<Vec<_>>>()?
+        } else {
+            Vec::new()
+        };
+        Ok(DeferredRendererTimestamps {
+            submitted: vec![false; profilers.len()],
+            profilers,
+            timings: None,
+        })
+    }
+}
+
+impl Destroy for DeferredRendererTimestamps {
+    type Context<'a> = &'a Device;
+    type DestroyError = DropGuardError<Infallible>;
+
+    fn destroy<'a>(&mut self, context: Self::Context<'a>) -> DestroyResult<Self> {
+        self.profilers.iter_mut().destroy(context)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,184 @@
+use std::convert::Infallible;
+
+use ash::vk;
+use type_kit::{Create, CreateResult, Destroy, DestroyResult, DropGuard, DropGuardError};
+
+use crate::context::{
+    device::{
+        command::{level::Level, operation::Operation, RecordingCommand},
+        descriptor::{DescriptorPool, DescriptorSetWriter, StorageImageDescriptorSet},
+        memory::{DefaultAllocator, DeviceLocal},
+        pipeline::{ComputePipeline, ComputePipelineBuilder, PipelineLayoutTonemap, TonemapParams},
+        resources::image::{Image2D, StorageImage, StorageImageFormat},
+        Device,
+    },
+    error::VkError,
+};
+
+pub(super) type TonemapPipeline = ComputePipelineBuilder<PipelineLayoutTonemap>;
+
+// Matches local size declared by the tonemap compute shader
+const WORKGROUP_SIZE: u32 = 8;
+
+// Floating point copy of the presented frame processed in place by the tonemap pass,
+// frame is blitted into the image after the render pass ends and blitted back afterwards
+pub(super) struct DeferredRendererTonemap {
+    image: DropGuard<Image2D<DeviceLocal, DefaultAllocator>>,
+    descriptors: DropGuard<DescriptorPool<StorageImageDescriptorSet>>,
+}
+
+impl DeferredRendererTonemap {
+    #[inline]
+    pub fn extent(&self) -> vk::Extent2D {
+        self.image.extent
+    }
+
+    pub fn record<'a, T, L: Level, O: Operation>(
+        &mut self,
+        command: RecordingCommand<'a, T, L, O>,
+        pipeline: &ComputePipeline<TonemapPipeline>,
+        presentable_image: vk::Image,
+        exposure: f32,
+    ) -> RecordingCommand<'a, T, L, O> {
+        let extent = self.image.extent;
+        let image = &mut *self.image;
+        // Previous contents are overwritten, so they are discarded with UNDEFINED layout
+        command
+            .change_layout(
+                &mut *image,
+                vk::ImageLayout::UNDEFINED,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                0,
+                0,
+                1,
+            )
+            .blit_presentable_image_to_image(presentable_image, extent, &mut *image)
+            .change_layout(
+                &mut *image,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                vk::ImageLayout::GENERAL,
+                0,
+                0,
+                1,
+            )
+            .bind_pipeline(pipeline)
+            .bind_descriptor_set(&self.descriptors.get(0).get_compute_binding_data(pipeline))
+            .push_constants(pipeline.get_push_range(&TonemapParams { exposure }))
+            .dispatch(
+                extent.width.div_ceil(WORKGROUP_SIZE),
+                extent.height.div_ceil(WORKGROUP_SIZE),
+                1,
+            )
+            .change_layout(
+                &mut *image,
+                vk::ImageLayout::GENERAL,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                0,
+                0,
+                1,
+            )
+            .blit_image_to_presentable_image(&*image, presentable_image, extent)
+    }
+}
+
+impl Create for DeferredRendererTonemap {
+    type Config<'a> = vk::Extent2D;
+    type CreateError = VkError;
+
+    fn create<'a, 'b>(config: Self::Config<'a>, context: Self::Context<'b>) -> CreateResult<Self> {
+        let image = context.create_storage_image(
+            config,
+            StorageImageFormat::Float,
+            &mut DefaultAllocator {},
+        )?;
+        let descriptors = DescriptorPool::create(
+            DescriptorSetWriter::<StorageImageDescriptorSet>::new(1)
+                .write_images::<StorageImage, _>(&[image.storage_image()]),
+            context,
+        )?;
+        Ok(DeferredRendererTonemap {
+            image: DropGuard::new(image),
+            descriptors: DropGuard::new(descriptors),
+        })
+    }
+}
+
+impl Destroy for DeferredRendererTonemap {
+    type Context<'a> = &'a Device;
+    type DestroyError = DropGuardError<Infallible>;
+
+    fn destroy<'a>(&mut self, context: Self::Context<'a>) -> DestroyResult<Self> {
+        self.descriptors.destroy(context)?;
+        self.image.destroy((context, &mut DefaultAllocator {}))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::context::{
+        device::{command::SubmitSemaphoreState, pipeline::ShaderDirectory, DeviceConfig},
+        Context, DebugConfig,
+    };
+
+    use super::*;
+
+    #[test]
+    #[ignore = "requires Vulkan capable device"]
+    fn test_tonemap_dispatch_on_compute_queue() {
+        // Shaders are loaded relative to the workspace root
+        std::env::set_current_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/..")).unwrap();
+        let extent = vk::Extent2D {
+            width: 64,
+            height: 64,
+        };
+        let context =
+            Context::build_offscreen(extent, DebugConfig::default(), &DeviceConfig::default())
+                .unwrap();
+        let mut pipeline = context
+            .create_compute_pipeline::<TonemapPipeline>(&ShaderDirectory::new(Path::new(
+                "_resources/shaders/spv/deferred/tonemap",
+            )))
+            .unwrap();
+        let mut tonemap = DeferredRendererTonemap::create(extent, &context).unwrap();
+        let image = &mut *tonemap.image;
+        let descriptors = &tonemap.descriptors;
+        let command = context.begin_compute_command().unwrap();
+        let command = context.record_command(command, |command| {
+            command
+                .change_layout(
+                    &mut *image,
+                    vk::ImageLayout::UNDEFINED,
+                    vk::ImageLayout::GENERAL,
+                    0,
+                    0,
+                    1,
+                )
+                .bind_pipeline(&pipeline)
+                .bind_descriptor_set(&descriptors.get(0).get_compute_binding_data(&pipeline))
+                .push_constants(pipeline.get_push_range(&TonemapParams { exposure: 1.0 }))
+                .dispatch(
+                    extent.width.div_ceil(WORKGROUP_SIZE),
+                    extent.height.div_ceil(WORKGROUP_SIZE),
+                    1,
+                )
+        });
+        let command = context
+            .submit_command(
+                context.finish_command(command).unwrap(),
+                SubmitSemaphoreState {
+                    semaphores: &[],
+                    masks: &[],
+                },
+                &[],
+            )
+            .unwrap()
+            .wait()
+            .unwrap();
+        context.free_command(command);
+        tonemap.destroy(&context).unwrap();
+        pipeline.destroy(&context).unwrap();
+    }
+}
@@ -1,9 +1,13 @@
 mod core;
 mod material;
 mod mesh;
+mod overlay;
+mod shadow_map;
 mod skybox;
 
 pub use core::*;
 pub use material::*;
 pub use mesh::*;
+pub use overlay::*;
+pub use shadow_map::*;
 pub use skybox::*;
@@ -1,11 +1,13 @@
 mod persistent;
 mod range;
 mod staging;
+mod storage;
 mod uniform;
 
 pub use persistent::*;
 pub use range::*;
 pub use staging::*;
+pub use storage::*;
 use type_kit::{Create, Destroy, DestroyResult};
 pub use uniform::*;
 
@@ -15,7 +17,7 @@ use std::{cell::RefCell, convert::Infallible, marker::PhantomData, usize};
 
 use crate::context::{
     device::{
-        memory::{AllocReq, AllocReqTyped, Allocator, MemoryProperties},
+        memory::{AllocReq, AllocReqTyped, Allocator, Memory, MemoryProperties},
         Device,
     },
     error::{VkError, VkResult},
@@ -84,12 +86,21 @@ impl<'a, M: MemoryProperties> PartialBuilder<'a> for BufferPartial<M> {
                 },
             ..
         } = config;
+        // Operations may share single queue family on devices without dedicated ones,
+        // concurrent sharing requires at least two distinct queue families
+        let mut unique_families = queue_families.to_vec();
+        unique_families.sort_unstable();
+        unique_families.dedup();
+        let sharing_mode = match unique_families.len() {
+            0 | 1 => vk::SharingMode::EXCLUSIVE,
+            _ => sharing_mode,
+        };
         let create_info = vk::BufferCreateInfo {
             usage,
             sharing_mode,
             size: size as u64,
-            queue_family_index_count: queue_families.len() as u32,
-            p_queue_family_indices: queue_families.as_ptr(),
+            queue_family_index_count: unique_families.len() as u32,
+            p_queue_family_indices: unique_families.as_ptr(),
             ..Default::default()
         };
         let buffer = unsafe { device.create_buffer(&create_info, None)? };
@@ -114,6 +125,9 @@ impl<M: MemoryProperties, A: Allocator> Create for Buffer<M, A> {
         let BufferPartial { size, buffer, req } = config;
         let memory = allocator.borrow_mut().allocate(device, req)?;
         device.bind_memory(buffer, &memory)?;
+        device
+            .stats()
+            .memory_bound(buffer.into(), memory.chunk().size());
         Ok(Buffer {
             size,
             buffer,
@@ -128,6 +142,9 @@ impl<M: MemoryProperties, A: Allocator> Destroy for Buffer<M, A> {
 
     fn destroy<'a>(&mut self, context: Self::Context<'a>) -> DestroyResult<Self> {
         let (device, allocator) = context;
+        device
+            .stats()
+            .memory_released(self.buffer.into(), self.memory.chunk().size());
         unsafe {
             device.destroy_buffer(self.buffer, None);
         }
@@ -15,11 +15,16 @@ use super::{Buffer, BufferBuilder, BufferPartial, ByteRange};
 
 pub struct PersistentBufferPartial {
     buffer: BufferPartial<HostCoherent>,
+    coherent: bool,
 }
 
+// Writes to buffers placed in non-coherent memory are tracked
+// as single dirty range which is flushed before command submission
 pub struct PersistentBuffer<A: Allocator> {
     pub buffer: Buffer<HostCoherent, A>,
     pub ptr: Option<*mut c_void>,
+    coherent: bool,
+    dirty: ByteRange,
 }
 
 impl<'a, A: Allocator> From<&'a PersistentBuffer<A>> for &'a Buffer<HostCoherent, A> {
@@ -40,7 +45,10 @@ impl<'a> PartialBuilder<'a> for PersistentBufferPartial {
 
     fn prepare(config: Self::Config, device: &Device) -> VkResult<Self> {
         let buffer = BufferPartial::prepare(config, device)?;
-        Ok(PersistentBufferPartial { buffer })
+        let coherent = buffer
+            .requirements()
+            .all(|req| device.is_host_coherent(&req));
+        Ok(PersistentBufferPartial { buffer, coherent })
     }
 
     fn requirements(&self) -> impl Iterator<Item = AllocReq> {
@@ -68,10 +76,29 @@ impl<A: Allocator> Create for PersistentBuffer<A> {
         Ok(PersistentBuffer {
             buffer,
             ptr: Some(ptr),
+            coherent: config.coherent,
+            dirty: ByteRange::empty(),
         })
     }
 }
 
+impl<A: Allocator> PersistentBuffer<A> {
+    #[inline]
+    pub fn mark_dirty(&mut self, range: ByteRange) {
+        if !self.coherent {
+            self.dirty = self.dirty.union(range);
+        }
+    }
+
+    pub fn flush(&mut self, device: &Device) -> VkResult<()> {
+        if self.dirty.len() != 0 {
+            device.flush_memory(&self.buffer.memory, self.dirty)?;
+            self.dirty = ByteRange::empty();
+        }
+        Ok(())
+    }
+}
+
 impl<A: Allocator> Destroy for PersistentBuffer<A> {
     type Context<'a> = (&'a Device, &'a RefCell<&'a mut A>);
     type DestroyError = Infallible;
@@ -2,6 +2,8 @@ use std::marker::PhantomData;
 
 use bytemuck::AnyBitPattern;
 
+use crate::context::error::RangeError;
+
 #[repr(C)]
 #[derive(Debug, Clone, Copy, Default)]
 pub struct ByteRange {
@@ -42,30 +44,66 @@ impl ByteRange {
     }
 
     pub fn take<T: AnyBitPattern>(&mut self, count: usize) -> Option<ByteRange> {
-        let beg = ByteRange::align::<T>(self.beg);
-        let end = beg + count * size_of::<T>();
-        if end <= self.end {
-            self.beg = end;
-            Some(ByteRange { beg, end })
-        } else {
-            None
-        }
+        self.checked_take::<T>(count).ok()
     }
 
     pub fn alloc_raw(&mut self, size: usize, alignment: usize) -> Option<ByteRange> {
+        self.checked_alloc_raw(size, alignment).ok()
+    }
+
+    pub fn checked_take<T: AnyBitPattern>(
+        &mut self,
+        count: usize,
+    ) -> Result<ByteRange, RangeError> {
+        self.checked_alloc_raw(
+            count.saturating_mul(size_of::<T>()),
+            std::mem::align_of::<T>(),
+        )
+    }
+
+    // Allocation which ends exactly at the end of the range is valid,
+    // sizes overflowing the address space are reported as exhausting the range
+    pub fn checked_alloc_raw(
+        &mut self,
+        size: usize,
+        alignment: usize,
+    ) -> Result<ByteRange, RangeError> {
         let beg = ByteRange::align_raw(self.beg, alignment);
-        let end = beg + size;
-        if end <= self.end {
-            self.beg = end;
-            Some(ByteRange { beg, end })
-        } else {
-            None
+        match beg.checked_add(size) {
+            Some(end) if end <= self.end => {
+                self.beg = end;
+                Ok(ByteRange { beg, end })
+            }
+            _ => Err(RangeError {
+                requested: (beg - self.beg).saturating_add(size),
+                remaining: self.len(),
+            }),
         }
     }
 
     pub fn len(&self) -> usize {
         self.end - self.beg
     }
+
+    // Smallest range containing both ranges, empty ranges are ignored
+    pub fn union(&self, other: ByteRange) -> ByteRange {
+        match (self.len(), other.len()) {
+            (0, _) => other,
+            (_, 0) => *self,
+            _ => ByteRange {
+                beg: self.beg.min(other.beg),
+                end: self.end.max(other.end),
+            },
+        }
+    }
+
+    // Expands the range so that both its bounds are multiples of `alignment`
+    pub fn align_outer(&self, alignment: usize) -> ByteRange {
+        ByteRange {
+            beg: (self.beg / alignment) * alignment,
+            end: ByteRange::align_raw(self.end, alignment),
+        }
+    }
 }
 
 impl<T: AnyBitPattern> From<Range<T>> for ByteRange {
@@ -121,3 +159,63 @@ impl<T: AnyBitPattern> Range<T> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extended_ranges_are_aligned_and_disjoint() {
+        let mut range = ByteRange::empty();
+        let bytes = range.extend::<u8>(3);
+        let floats = range.extend::<f32>(2);
+        let raw = range.extend_raw(5, 16);
+        assert_eq!((bytes.beg, bytes.end), (0, 3));
+        assert_eq!((floats.beg, floats.end), (4, 12));
+        assert_eq!((raw.beg, raw.end), (16, 21));
+        assert_eq!((range.beg, range.end), (0, 21));
+    }
+
+    #[test]
+    fn test_taken_ranges_are_aligned_and_disjoint() {
+        let mut range = ByteRange::new(64);
+        let bytes = range.take::<u8>(3).unwrap();
+        let floats = range.take::<f32>(2).unwrap();
+        let raw = range.alloc_raw(8, 16).unwrap();
+        assert_eq!((bytes.beg, bytes.end), (0, 3));
+        assert_eq!((floats.beg, floats.end), (4, 12));
+        assert_eq!((raw.beg, raw.end), (16, 24));
+        assert!(bytes.end <= floats.beg && floats.end <= raw.beg);
+        assert_eq!(range.len(), 40);
+    }
+
+    #[test]
+    fn test_exact_fit_allocation_succeeds() {
+        let mut range = ByteRange::new(16);
+        let _ = range.alloc_raw(1, 1).unwrap();
+        let fit = range.checked_alloc_raw(12, 4).unwrap();
+        assert_eq!((fit.beg, fit.end), (4, 16));
+        assert_eq!(range.len(), 0);
+        assert!(range.take::<u8>(0).is_some());
+    }
+
+    #[test]
+    fn test_exhausted_range_reports_padded_request() {
+        let mut range = ByteRange::new(16);
+        let _ = range.alloc_raw(1, 1).unwrap();
+        let error = range.checked_alloc_raw(13, 4).unwrap_err();
+        assert_eq!((error.requested, error.remaining), (16, 15));
+        // Failed allocation leaves the range untouched
+        assert_eq!((range.beg, range.end), (1, 16));
+    }
+
+    #[test]
+    fn test_overflowing_size_rejected() {
+        let mut range = ByteRange::new(16);
+        let _ = range.alloc_raw(1, 1).unwrap();
+        let error = range.checked_alloc_raw(usize::MAX, 4).unwrap_err();
+        assert_eq!((error.requested, error.remaining), (usize::MAX, 15));
+        assert!(range.checked_take::<u64>(usize::MAX).is_err());
+        assert_eq!((range.beg, range.end), (1, 16));
+    }
+}
@@ -10,8 +10,9 @@ use type_kit::{Create, Destroy, DestroyResult};
 use crate::context::{
     device::{
         command::{
+            level::Primary,
             operation::{self, Operation},
-            SubmitSemaphoreState,
+            SubmitSemaphoreState, SubmitedCommand, Transient,
         },
         memory::{Allocator, DefaultAllocator, DeviceLocal, HostCoherent},
         resources::{
@@ -70,6 +71,20 @@ impl<'a> From<&'a mut StagingBuffer> for &'a mut Buffer<HostCoherent, DefaultAll
     }
 }
 
+#[must_use = "pending transfer must be waited on to free its command buffer"]
+pub struct TransferToken<'a> {
+    command: SubmitedCommand<'a, Transient, Primary, operation::Transfer>,
+}
+
+impl<'a> TransferToken<'a> {
+    pub fn wait(self, device: &Device) -> VkResult<()> {
+        graphics::profile_scope!("staging_wait");
+        let command = self.command.wait()?;
+        device.free_command(command);
+        Ok(())
+    }
+}
+
 impl StagingBuffer {
     pub fn transfer_buffer_data<'b, D: Allocator>(
         &self,
@@ -77,6 +92,19 @@ impl StagingBuffer {
         dst: impl Into<&'b mut Buffer<DeviceLocal, D>>,
         dst_offset: vk::DeviceSize,
     ) -> VkResult<()> {
+        self.transfer_buffer_data_async(device, dst, dst_offset)?
+            .wait(device)
+    }
+
+    // Staging buffer stays borrowed until returned token is waited on,
+    // so multiple uploads can be submitted before blocking on any of them
+    pub fn transfer_buffer_data_async<'a, 'b, D: Allocator>(
+        &'a self,
+        device: &'a Device,
+        dst: impl Into<&'b mut Buffer<DeviceLocal, D>>,
+        dst_offset: vk::DeviceSize,
+    ) -> VkResult<TransferToken<'a>> {
+        graphics::profile_scope!("staging_transfer");
         let command = device.allocate_transient_command::<operation::Transfer>()?;
         let command = device.begin_primary_command(command)?;
         let command = device.record_command(command, |command| {
@@ -90,18 +118,15 @@ impl StagingBuffer {
                 }],
             )
         });
-        let command = device
-            .submit_command(
-                device.finish_command(command)?,
-                SubmitSemaphoreState {
-                    semaphores: &[],
-                    masks: &[],
-                },
-                &[],
-            )?
-            .wait()?;
-        device.free_command(&command);
-        Ok(())
+        let command = device.submit_command(
+            device.finish_command(command)?,
+            SubmitSemaphoreState {
+                semaphores: &[],
+                masks: &[],
+            },
+            &[],
+        )?;
+        Ok(TransferToken { command })
     }
 
     pub fn transfer_image_data<'b, A: Allocator>(
@@ -111,6 +136,7 @@ impl StagingBuffer {
         dst_array_layer: u32,
         dst_final_layout: vk::ImageLayout,
     ) -> VkResult<()> {
+        graphics::profile_scope!("staging_transfer");
         let dst: &mut _ = dst.into();
         debug_assert!(
             dst.array_layers > dst_array_layer,
@@ -131,7 +157,11 @@ impl StagingBuffer {
                     1,
                 )
                 .copy_image(self, dst.borrow_mut(), dst_array_layer)
-                .generate_mip(dst.borrow_mut(), dst_array_layer)
+                .generate_mip(
+                    dst.borrow_mut(),
+                    dst_array_layer,
+                    vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                )
                 .change_layout(
                     dst.borrow_mut(),
                     vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
@@ -152,8 +182,7 @@ impl StagingBuffer {
                 &[],
             )?
             .wait()?;
-        // Shouldn't free_command consume Command instead of taking it by reference?
-        device.free_command(&command);
+        device.free_command(command);
         Ok(())
     }
 
@@ -225,3 +254,122 @@ impl Destroy for StagingBuffer {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::context::{
+        device::{command::operation::Graphics, resources::buffer::BufferPartial, DeviceConfig},
+        Context, DebugConfig,
+    };
+
+    use super::*;
+
+    const LEN: usize = 64;
+
+    fn staging_buffer_with(device: &Device, data: &[u32]) -> StagingBuffer {
+        let mut builder = StagingBufferBuilder::new();
+        let range = builder.append::<u32>(data.len());
+        let mut staging = StagingBuffer::create(builder, device).unwrap();
+        staging.write_range(range).write(data);
+        staging
+    }
+
+    // Destination is written on the transfer queue and read back on the graphics queue
+    fn device_buffer(
+        device: &Device,
+        queue_families: &[u32],
+    ) -> Buffer<DeviceLocal, DefaultAllocator> {
+        let info = BufferInfo {
+            size: LEN * size_of::<u32>(),
+            usage: vk::BufferUsageFlags::TRANSFER_SRC | vk::BufferUsageFlags::TRANSFER_DST,
+            sharing_mode: vk::SharingMode::CONCURRENT,
+            queue_families,
+        };
+        let partial = BufferPartial::prepare(BufferBuilder::new(info), device).unwrap();
+        Buffer::create(partial, (device, &RefCell::new(&mut DefaultAllocator {}))).unwrap()
+    }
+
+    fn read_back(device: &Device, src: &Buffer<DeviceLocal, DefaultAllocator>) -> Vec<u32> {
+        let info = BufferInfo {
+            size: src.size(),
+            usage: vk::BufferUsageFlags::TRANSFER_DST,
+            sharing_mode: vk::SharingMode::EXCLUSIVE,
+            queue_families: &[Graphics::get_queue_family_index(device)],
+        };
+        let partial = PersistentBufferPartial::prepare(BufferBuilder::new(info), device).unwrap();
+        let mut buffer =
+            PersistentBuffer::create(partial, (device, &RefCell::new(&mut DefaultAllocator {})))
+                .unwrap();
+        let command = device
+            .begin_primary_command(device.allocate_transient_command::<Graphics>().unwrap())
+            .unwrap();
+        let command = device.record_command(command, |command| {
+            command.copy_buffer(
+                src,
+                &mut buffer,
+                &[vk::BufferCopy {
+                    src_offset: 0,
+                    dst_offset: 0,
+                    size: src.size() as vk::DeviceSize,
+                }],
+            )
+        });
+        let command = device
+            .submit_command(
+                device.finish_command(command).unwrap(),
+                SubmitSemaphoreState {
+                    semaphores: &[],
+                    masks: &[],
+                },
+                &[],
+            )
+            .unwrap()
+            .wait()
+            .unwrap();
+        device.free_command(command);
+        let data =
+            unsafe { std::slice::from_raw_parts(buffer.ptr.unwrap() as *const u32, LEN).to_vec() };
+        let _ = buffer.destroy((device, &RefCell::new(&mut DefaultAllocator {})));
+        data
+    }
+
+    #[test]
+    #[ignore = "requires Vulkan capable device"]
+    fn test_async_transfers_complete_after_waiting_on_tokens() {
+        let context = Context::build_offscreen(
+            vk::Extent2D {
+                width: 64,
+                height: 64,
+            },
+            DebugConfig::default(),
+            &DeviceConfig::default(),
+        )
+        .unwrap();
+        let queue_families = [
+            operation::Transfer::get_queue_family_index(&context),
+            Graphics::get_queue_family_index(&context),
+        ];
+        let first_data = (0..LEN as u32).collect::<Vec<_>>();
+        let second_data = (0..LEN as u32).map(|value| !value).collect::<Vec<_>>();
+        let mut first_staging = staging_buffer_with(&context, &first_data);
+        let mut second_staging = staging_buffer_with(&context, &second_data);
+        let mut first = device_buffer(&context, &queue_families);
+        let mut second = device_buffer(&context, &queue_families);
+        // Both transfers are in flight before waiting on any of them
+        let first_token = first_staging
+            .transfer_buffer_data_async(&context, &mut first, 0)
+            .unwrap();
+        let second_token = second_staging
+            .transfer_buffer_data_async(&context, &mut second, 0)
+            .unwrap();
+        first_token.wait(&context).unwrap();
+        second_token.wait(&context).unwrap();
+        assert_eq!(read_back(&context, &first), first_data);
+        assert_eq!(read_back(&context, &second), second_data);
+        for buffer in [&mut first, &mut second] {
+            let _ = buffer.destroy((&context, &RefCell::new(&mut DefaultAllocator {})));
+        }
+        let _ = first_staging.destroy(&context);
+        let _ = second_staging.destroy(&context);
+    }
+}
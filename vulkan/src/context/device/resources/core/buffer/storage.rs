@@ -0,0 +1,273 @@
+use std::{cell::RefCell, convert::Infallible, marker::PhantomData};
+
+use ash::vk;
+use bytemuck::AnyBitPattern;
+use type_kit::{Create, CreateResult, Destroy, DestroyResult};
+
+use crate::context::{
+    device::{
+        command::operation::Operation,
+        memory::{AllocReq, Allocator, DeviceLocal, HostCoherent},
+        resources::PartialBuilder,
+        Device,
+    },
+    error::{VkError, VkResult},
+};
+
+use super::{
+    Buffer, BufferBuilder, BufferInfo, BufferPartial, ByteRange, PersistentBuffer,
+    PersistentBufferPartial, StagingBuffer, StagingBufferBuilder,
+};
+
+pub struct StorageBufferBuilder<U: AnyBitPattern, O: Operation> {
+    len: usize,
+    _phantom: PhantomData<(U, O)>,
+}
+
+impl<U: AnyBitPattern, O: Operation> StorageBufferBuilder<U, O> {
+    pub fn new(len: usize) -> Self {
+        Self {
+            len,
+            _phantom: PhantomData,
+        }
+    }
+
+    fn info<'a>(&self, queue_families: &'a [u32]) -> BufferInfo<'a> {
+        BufferInfo {
+            size: size_of::<U>() * self.len,
+            usage: vk::BufferUsageFlags::STORAGE_BUFFER | vk::BufferUsageFlags::TRANSFER_DST,
+            sharing_mode: vk::SharingMode::EXCLUSIVE,
+            queue_families,
+        }
+    }
+}
+
+// Buffer handle along with its length, used for descriptor writes
+pub struct StorageBufferBinding<U: AnyBitPattern> {
+    pub buffer: vk::Buffer,
+    pub len: usize,
+    _phantom: PhantomData<U>,
+}
+
+impl<U: AnyBitPattern, O: Operation, A: Allocator> From<&StorageBuffer<U, O, A>>
+    for StorageBufferBinding<U>
+{
+    fn from(value: &StorageBuffer<U, O, A>) -> Self {
+        StorageBufferBinding {
+            buffer: value.handle(),
+            len: value.len,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<U: AnyBitPattern, O: Operation, A: Allocator> From<&HostStorageBuffer<U, O, A>>
+    for StorageBufferBinding<U>
+{
+    fn from(value: &HostStorageBuffer<U, O, A>) -> Self {
+        StorageBufferBinding {
+            buffer: value.handle(),
+            len: value.len,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+// Device local storage buffer, contents are uploaded through staging buffer
+pub struct StorageBuffer<U: AnyBitPattern, O: Operation, A: Allocator> {
+    len: usize,
+    buffer: Buffer<DeviceLocal, A>,
+    _phantom: PhantomData<(U, O)>,
+}
+
+pub struct StorageBufferPartial<U: AnyBitPattern, O: Operation> {
+    len: usize,
+    buffer: BufferPartial<DeviceLocal>,
+    _phantom: PhantomData<(U, O)>,
+}
+
+impl<'a, U: AnyBitPattern, O: Operation> PartialBuilder<'a> for StorageBufferPartial<U, O> {
+    type Config = StorageBufferBuilder<U, O>;
+    type Target<A: Allocator> = StorageBuffer<U, O, A>;
+
+    fn prepare(config: Self::Config, device: &Device) -> VkResult<Self> {
+        let queue_families = [O::get_queue_family_index(device)];
+        let buffer =
+            BufferPartial::prepare(BufferBuilder::new(config.info(&queue_families)), device)?;
+        Ok(StorageBufferPartial {
+            len: config.len,
+            buffer,
+            _phantom: PhantomData,
+        })
+    }
+
+    fn requirements(&self) -> impl Iterator<Item = AllocReq> {
+        self.buffer.requirements()
+    }
+}
+
+impl<'a, U: AnyBitPattern, O: Operation, A: Allocator> From<&'a StorageBuffer<U, O, A>>
+    for &'a Buffer<DeviceLocal, A>
+{
+    fn from(value: &'a StorageBuffer<U, O, A>) -> Self {
+        &value.buffer
+    }
+}
+
+impl<'a, U: AnyBitPattern, O: Operation, A: Allocator> From<&'a mut StorageBuffer<U, O, A>>
+    for &'a mut Buffer<DeviceLocal, A>
+{
+    fn from(value: &'a mut StorageBuffer<U, O, A>) -> Self {
+        &mut value.buffer
+    }
+}
+
+impl<U: AnyBitPattern, O: Operation, A: Allocator> StorageBuffer<U, O, A> {
+    pub fn handle(&self) -> vk::Buffer {
+        self.buffer.handle()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // Blocks until the transfer completes
+    pub fn upload(&mut self, device: &Device, data: &[U]) -> VkResult<()> {
+        debug_assert!(data.len() <= self.len, "Out of range StorageBuffer upload!");
+        let mut builder = StagingBufferBuilder::new();
+        let range = builder.append::<U>(data.len());
+        let mut staging_buffer = StagingBuffer::create(builder, device)?;
+        staging_buffer.write_range::<U>(range).write(data);
+        let result = staging_buffer.transfer_buffer_data(device, &mut self.buffer, 0);
+        let _ = staging_buffer.destroy(device);
+        result
+    }
+}
+
+impl<U: AnyBitPattern, O: Operation, A: Allocator> Create for StorageBuffer<U, O, A> {
+    type Config<'a> = StorageBufferPartial<U, O>;
+    type CreateError = VkError;
+
+    fn create<'a, 'b>(config: Self::Config<'a>, context: Self::Context<'b>) -> CreateResult<Self> {
+        let (device, allocator) = context;
+        let buffer = Buffer::create(config.buffer, (device, allocator))?;
+        Ok(StorageBuffer {
+            len: config.len,
+            buffer,
+            _phantom: PhantomData,
+        })
+    }
+}
+
+impl<U: AnyBitPattern, O: Operation, A: Allocator> Destroy for StorageBuffer<U, O, A> {
+    type Context<'a> = (&'a Device, &'a RefCell<&'a mut A>);
+    type DestroyError = Infallible;
+
+    fn destroy<'a>(&mut self, context: Self::Context<'a>) -> DestroyResult<Self> {
+        self.buffer.destroy(context)?;
+        Ok(())
+    }
+}
+
+// Persistently mapped storage buffer for data updated by the host every frame
+pub struct HostStorageBuffer<U: AnyBitPattern, O: Operation, A: Allocator> {
+    len: usize,
+    buffer: PersistentBuffer<A>,
+    _phantom: PhantomData<(U, O)>,
+}
+
+pub struct HostStorageBufferPartial<U: AnyBitPattern, O: Operation> {
+    len: usize,
+    buffer: PersistentBufferPartial,
+    _phantom: PhantomData<(U, O)>,
+}
+
+impl<'a, U: AnyBitPattern, O: Operation> PartialBuilder<'a> for HostStorageBufferPartial<U, O> {
+    type Config = StorageBufferBuilder<U, O>;
+    type Target<A: Allocator> = HostStorageBuffer<U, O, A>;
+
+    fn prepare(config: Self::Config, device: &Device) -> VkResult<Self> {
+        let queue_families = [O::get_queue_family_index(device)];
+        let buffer = PersistentBufferPartial::prepare(
+            BufferBuilder::new(config.info(&queue_families)),
+            device,
+        )?;
+        Ok(HostStorageBufferPartial {
+            len: config.len,
+            buffer,
+            _phantom: PhantomData,
+        })
+    }
+
+    fn requirements(&self) -> impl Iterator<Item = AllocReq> {
+        self.buffer.requirements()
+    }
+}
+
+impl<'a, U: AnyBitPattern, O: Operation, A: Allocator> From<&'a HostStorageBuffer<U, O, A>>
+    for &'a Buffer<HostCoherent, A>
+{
+    fn from(value: &'a HostStorageBuffer<U, O, A>) -> Self {
+        &value.buffer.buffer
+    }
+}
+
+impl<U: AnyBitPattern, O: Operation, A: Allocator> HostStorageBuffer<U, O, A> {
+    pub fn handle(&self) -> vk::Buffer {
+        self.buffer.buffer.handle()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn as_slice(&self) -> &[U] {
+        let ptr = self.buffer.ptr.unwrap() as *const U;
+        unsafe { std::slice::from_raw_parts(ptr, self.len) }
+    }
+
+    // Whole buffer is considered modified and flushed with the next `flush`
+    pub fn as_mut_slice(&mut self) -> &mut [U] {
+        self.buffer
+            .mark_dirty(ByteRange::new(size_of::<U>() * self.len));
+        let ptr = self.buffer.ptr.unwrap() as *mut U;
+        unsafe { std::slice::from_raw_parts_mut(ptr, self.len) }
+    }
+
+    pub fn flush(&mut self, device: &Device) -> VkResult<()> {
+        self.buffer.flush(device)
+    }
+}
+
+impl<U: AnyBitPattern, O: Operation, A: Allocator> Create for HostStorageBuffer<U, O, A> {
+    type Config<'a> = HostStorageBufferPartial<U, O>;
+    type CreateError = VkError;
+
+    fn create<'a, 'b>(config: Self::Config<'a>, context: Self::Context<'b>) -> CreateResult<Self> {
+        let (device, allocator) = context;
+        let buffer = PersistentBuffer::create(config.buffer, (device, allocator))?;
+        Ok(HostStorageBuffer {
+            len: config.len,
+            buffer,
+            _phantom: PhantomData,
+        })
+    }
+}
+
+impl<U: AnyBitPattern, O: Operation, A: Allocator> Destroy for HostStorageBuffer<U, O, A> {
+    type Context<'a> = (&'a Device, &'a RefCell<&'a mut A>);
+    type DestroyError = Infallible;
+
+    fn destroy<'a>(&mut self, context: Self::Context<'a>) -> DestroyResult<Self> {
+        self.buffer.destroy(context)?;
+        Ok(())
+    }
+}
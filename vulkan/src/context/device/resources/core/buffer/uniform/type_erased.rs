@@ -17,7 +17,8 @@ use crate::context::{
         memory::{AllocReq, Allocator, HostCoherent},
         resources::{
             buffer::{
-                Buffer, BufferBuilder, BufferInfo, PersistentBuffer, PersistentBufferPartial,
+                Buffer, BufferBuilder, BufferInfo, ByteRange, PersistentBuffer,
+                PersistentBufferPartial,
             },
             PartialBuilder,
         },
@@ -139,11 +140,21 @@ impl<U: AnyBitPattern, O: Operation, A: Allocator> IndexMut<usize>
 {
     fn index_mut(&mut self, index: usize) -> &mut Self::Output {
         debug_assert!(index < self.len, "Out of range UniformBuffer access!");
+        self.buffer.mark_dirty(ByteRange {
+            beg: index * size_of::<U>(),
+            end: (index + 1) * size_of::<U>(),
+        });
         let ptr = self.buffer.ptr.unwrap() as *mut U;
         unsafe { ptr.add(index).as_mut().unwrap() }
     }
 }
 
+impl<U: AnyBitPattern, O: Operation, A: Allocator> UniformBufferRef<'_, U, O, A> {
+    pub fn flush(&mut self, device: &Device) -> VkResult<()> {
+        self.buffer.flush(device)
+    }
+}
+
 impl<O: Operation, A: Allocator> Create for UniformBufferTypeErased<O, A> {
     type Config<'a> = UniformBufferErasedPartial<O>;
     type CreateError = VkError;
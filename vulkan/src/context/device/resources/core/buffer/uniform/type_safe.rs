@@ -15,7 +15,8 @@ use crate::context::{
         memory::{AllocReq, Allocator, HostCoherent},
         resources::{
             buffer::{
-                Buffer, BufferBuilder, BufferInfo, PersistentBuffer, PersistentBufferPartial,
+                Buffer, BufferBuilder, BufferInfo, ByteRange, PersistentBuffer,
+                PersistentBufferPartial,
             },
             PartialBuilder,
         },
@@ -103,6 +104,10 @@ impl<U: AnyBitPattern, O: Operation, A: Allocator> Index<usize> for UniformBuffe
 impl<U: AnyBitPattern, O: Operation, A: Allocator> IndexMut<usize> for UniformBuffer<U, O, A> {
     fn index_mut(&mut self, index: usize) -> &mut Self::Output {
         debug_assert!(index < self.len, "Out of range UniformBuffer access!");
+        self.buffer.mark_dirty(ByteRange {
+            beg: index * size_of::<U>(),
+            end: (index + 1) * size_of::<U>(),
+        });
         let ptr = self.buffer.ptr.unwrap() as *mut U;
         unsafe { ptr.add(index).as_mut().unwrap() }
     }
@@ -116,6 +121,10 @@ impl<U: AnyBitPattern, O: Operation, A: Allocator> UniformBuffer<U, O, A> {
     pub fn len(&self) -> usize {
         self.len
     }
+
+    pub fn flush(&mut self, device: &Device) -> VkResult<()> {
+        self.buffer.flush(device)
+    }
 }
 
 impl<U: AnyBitPattern, O: Operation, A: Allocator> Create for UniformBuffer<U, O, A> {
@@ -1,12 +1,15 @@
+mod readback;
 mod reader;
 mod texture;
 
 use crate::context::{
     device::{
-        memory::{AllocReq, AllocReqTyped, Allocator, DeviceLocal, MemoryProperties},
+        framebuffer::{Attachment, AttachmentFormatInfo},
+        memory::{AllocReq, AllocReqTyped, Allocator, DeviceLocal, Memory, MemoryProperties},
         Device,
     },
     error::{VkError, VkResult},
+    surface::PhysicalDeviceSurfaceProperties,
 };
 
 use super::PartialBuilder;
@@ -82,6 +85,38 @@ pub struct Image2DPartial<M: MemoryProperties> {
     req: AllocReqTyped<M>,
 }
 
+// Channel format of images written by compute shaders
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageImageFormat {
+    Unorm,
+    Float,
+}
+
+impl From<StorageImageFormat> for vk::Format {
+    fn from(format: StorageImageFormat) -> Self {
+        match format {
+            StorageImageFormat::Unorm => vk::Format::R8G8B8A8_UNORM,
+            StorageImageFormat::Float => vk::Format::R16G16B16A16_SFLOAT,
+        }
+    }
+}
+
+// Image bound as STORAGE_IMAGE descriptor, accessed in GENERAL layout
+#[derive(Debug, Clone, Copy)]
+pub struct StorageImage {
+    image_view: vk::ImageView,
+}
+
+impl From<&StorageImage> for vk::DescriptorImageInfo {
+    fn from(storage_image: &StorageImage) -> Self {
+        vk::DescriptorImageInfo {
+            sampler: vk::Sampler::null(),
+            image_view: storage_image.image_view,
+            image_layout: vk::ImageLayout::GENERAL,
+        }
+    }
+}
+
 pub struct Image2D<M: MemoryProperties, A: Allocator> {
     pub array_layers: u32,
     pub mip_levels: u32,
@@ -93,17 +128,20 @@ pub struct Image2D<M: MemoryProperties, A: Allocator> {
 }
 
 impl Device {
-    pub fn create_color_attachment_image<A: Allocator>(
+    // Format and sample count are the ones of the attachment `T`
+    pub fn create_color_attachment_image<T: Attachment, A: Allocator>(
         &self,
+        extent: vk::Extent2D,
         allocator: &mut A,
     ) -> VkResult<Image2D<DeviceLocal, A>> {
-        let extent = self.physical_device.surface_properties.get_current_extent();
+        let AttachmentFormatInfo { format, samples } =
+            T::get_format(&self.physical_device.attachment_properties);
         let partial = Image2DPartial::prepare(
             Image2DBuilder::new(Image2DInfo {
                 extent,
-                format: self.physical_device.attachment_properties.formats.color,
+                format,
                 flags: vk::ImageCreateFlags::empty(),
-                samples: self.physical_device.attachment_properties.msaa_samples,
+                samples,
                 usage: vk::ImageUsageFlags::COLOR_ATTACHMENT
                     | vk::ImageUsageFlags::TRANSIENT_ATTACHMENT
                     | vk::ImageUsageFlags::INPUT_ATTACHMENT,
@@ -117,11 +155,36 @@ impl Device {
         Image2D::create(partial, (self, allocator))
     }
 
+    // Single sampled color image standing in for swapchain image in offscreen mode
+    pub fn create_offscreen_target_image<A: Allocator>(
+        &self,
+        surface_properties: &PhysicalDeviceSurfaceProperties,
+        allocator: &mut A,
+    ) -> VkResult<Image2D<DeviceLocal, A>> {
+        let partial = Image2DPartial::prepare(
+            Image2DBuilder::new(Image2DInfo {
+                extent: surface_properties.get_current_extent(),
+                format: surface_properties.surface_format.format,
+                flags: vk::ImageCreateFlags::empty(),
+                samples: vk::SampleCountFlags::TYPE_1,
+                usage: vk::ImageUsageFlags::COLOR_ATTACHMENT
+                    | vk::ImageUsageFlags::TRANSFER_SRC
+                    | vk::ImageUsageFlags::TRANSFER_DST,
+                aspect_mask: vk::ImageAspectFlags::COLOR,
+                view_type: vk::ImageViewType::TYPE_2D,
+                array_layers: 1,
+                mip_levels: 1,
+            }),
+            self,
+        )?;
+        Image2D::create(partial, (self, allocator))
+    }
+
     pub fn create_depth_stencil_attachment_image<A: Allocator>(
         &self,
+        extent: vk::Extent2D,
         allocator: &mut A,
     ) -> VkResult<Image2D<DeviceLocal, A>> {
-        let extent = self.physical_device.surface_properties.get_current_extent();
         let partial = Image2DPartial::prepare(
             Image2DBuilder::new(Image2DInfo {
                 extent,
@@ -143,6 +206,73 @@ impl Device {
         )?;
         Image2D::create(partial, (self, allocator))
     }
+
+    pub fn create_shadow_map_image<A: Allocator>(
+        &self,
+        allocator: &mut A,
+        extent: vk::Extent2D,
+    ) -> VkResult<Image2D<DeviceLocal, A>> {
+        let partial = Image2DPartial::prepare(
+            Image2DBuilder::new(Image2DInfo {
+                extent,
+                format: vk::Format::D32_SFLOAT,
+                flags: vk::ImageCreateFlags::empty(),
+                samples: vk::SampleCountFlags::TYPE_1,
+                usage: vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT | vk::ImageUsageFlags::SAMPLED,
+                aspect_mask: vk::ImageAspectFlags::DEPTH,
+                view_type: vk::ImageViewType::TYPE_2D,
+                array_layers: 1,
+                mip_levels: 1,
+            }),
+            self,
+        )?;
+        Image2D::create(partial, (self, allocator))
+    }
+
+    // Image written by compute passes, transfer destination usage allows
+    // filling it with the contents of rendered frame before processing
+    pub fn create_storage_image<A: Allocator>(
+        &self,
+        extent: vk::Extent2D,
+        format: StorageImageFormat,
+        allocator: &mut A,
+    ) -> VkResult<Image2D<DeviceLocal, A>> {
+        let partial = Image2DPartial::prepare(
+            Image2DBuilder::new(Image2DInfo {
+                extent,
+                format: format.into(),
+                flags: vk::ImageCreateFlags::empty(),
+                samples: vk::SampleCountFlags::TYPE_1,
+                usage: vk::ImageUsageFlags::STORAGE
+                    | vk::ImageUsageFlags::SAMPLED
+                    | vk::ImageUsageFlags::TRANSFER_SRC
+                    | vk::ImageUsageFlags::TRANSFER_DST,
+                aspect_mask: vk::ImageAspectFlags::COLOR,
+                view_type: vk::ImageViewType::TYPE_2D,
+                array_layers: 1,
+                mip_levels: 1,
+            }),
+            self,
+        )?;
+        Image2D::create(partial, (self, allocator))
+    }
+}
+
+impl<M: MemoryProperties, A: Allocator> Image2D<M, A> {
+    // Image has to be created with storage usage
+    pub fn storage_image(&self) -> StorageImage {
+        StorageImage {
+            image_view: self.image_view,
+        }
+    }
+
+    // Each mip level halves the previous one, down to a single texel
+    pub fn mip_level_extent(&self, level: u32) -> vk::Extent2D {
+        vk::Extent2D {
+            width: (self.extent.width >> level).max(1),
+            height: (self.extent.height >> level).max(1),
+        }
+    }
 }
 
 impl<M: MemoryProperties, A: Allocator> Create for Image2D<M, A> {
@@ -157,6 +287,9 @@ impl<M: MemoryProperties, A: Allocator> Create for Image2D<M, A> {
         let Image2DPartial { image, info, req } = config;
         let memory = allocator.allocate(device, req)?;
         device.bind_memory(image, &memory)?;
+        device
+            .stats()
+            .memory_bound(image.into(), memory.chunk().size());
         let view_info = vk::ImageViewCreateInfo::builder()
             .components(vk::ComponentMapping::default())
             .format(info.format)
@@ -188,6 +321,9 @@ impl<M: MemoryProperties, A: Allocator> Destroy for Image2D<M, A> {
 
     fn destroy<'a>(&mut self, context: Self::Context<'a>) -> DestroyResult<Self> {
         let (device, allocator) = context;
+        device
+            .stats()
+            .memory_released(self.image.into(), self.memory.chunk().size());
         unsafe {
             device.destroy_image_view(self.image_view, None);
             device.destroy_image(self.image, None);
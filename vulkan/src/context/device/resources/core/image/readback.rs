@@ -0,0 +1,101 @@
+use std::cell::RefCell;
+
+use ash::vk;
+use type_kit::{Create, Destroy};
+
+use crate::context::{
+    device::{
+        command::{
+            operation::{self, Operation},
+            SubmitSemaphoreState,
+        },
+        memory::{Allocator, DefaultAllocator, MemoryProperties},
+        resources::{
+            buffer::{BufferBuilder, BufferInfo, PersistentBuffer, PersistentBufferPartial},
+            PartialBuilder,
+        },
+        Device,
+    },
+    error::VkResult,
+};
+
+use super::Image2D;
+
+// Size of single texel of color images which can be read back, RGBA8 formats only
+const READBACK_TEXEL_SIZE: usize = 4;
+
+impl Device {
+    // Copies first mip level of color image into host visible memory, returns
+    // tightly packed rows of RGBA8 texels. Image is expected to be in `layout`
+    // with all previous writes finished, and is transitioned back to it afterwards
+    pub fn read_back_image<M: MemoryProperties, A: Allocator>(
+        &self,
+        image: &mut Image2D<M, A>,
+        layout: vk::ImageLayout,
+    ) -> VkResult<Vec<u8>> {
+        self.read_back_image_level(image, layout, 0)
+    }
+
+    // Same as `read_back_image` for the selected mip level of the first array layer
+    pub fn read_back_image_level<M: MemoryProperties, A: Allocator>(
+        &self,
+        image: &mut Image2D<M, A>,
+        layout: vk::ImageLayout,
+        mip_level: u32,
+    ) -> VkResult<Vec<u8>> {
+        let extent = image.mip_level_extent(mip_level);
+        let size = extent.width as usize * extent.height as usize * READBACK_TEXEL_SIZE;
+        let info = BufferInfo {
+            size,
+            usage: vk::BufferUsageFlags::TRANSFER_DST,
+            sharing_mode: vk::SharingMode::EXCLUSIVE,
+            queue_families: &[operation::Graphics::get_queue_family_index(self)],
+        };
+        let partial = PersistentBufferPartial::prepare(BufferBuilder::new(info), self)?;
+        let mut buffer =
+            PersistentBuffer::create(partial, (self, &RefCell::new(&mut DefaultAllocator {})))?;
+        let command =
+            self.begin_primary_command(self.allocate_transient_command::<operation::Graphics>()?)?;
+        let command = self.record_command(command, |command| {
+            command
+                .memory_barrier(
+                    vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                    vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+                    vk::PipelineStageFlags::TRANSFER,
+                    vk::AccessFlags::TRANSFER_READ,
+                )
+                .change_layout(
+                    &mut *image,
+                    layout,
+                    vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                    0,
+                    mip_level,
+                    1,
+                )
+                .copy_image_to_buffer(&*image, &mut buffer, 0, mip_level)
+                .change_layout(
+                    &mut *image,
+                    vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                    layout,
+                    0,
+                    mip_level,
+                    1,
+                )
+        });
+        let command = self
+            .submit_command(
+                self.finish_command(command)?,
+                SubmitSemaphoreState {
+                    semaphores: &[],
+                    masks: &[],
+                },
+                &[],
+            )?
+            .wait()?;
+        self.free_command(command);
+        let data =
+            unsafe { std::slice::from_raw_parts(buffer.ptr.unwrap() as *const u8, size).to_vec() };
+        let _ = buffer.destroy((self, &RefCell::new(&mut DefaultAllocator {})));
+        Ok(data)
+    }
+}
@@ -1,7 +1,7 @@
 use std::{borrow::Borrow, fs::File, io::Read, marker::PhantomData, path::Path};
 
 use ash::vk;
-use graphics::model::Image;
+use graphics::model::{Image, ImageColorSpace};
 use png::{BitDepth, ColorType, Transformations};
 use strum::IntoEnumIterator;
 
@@ -50,16 +50,28 @@ impl<'a, R: Read> PngImageReader<'a, R> {
         Ok(())
     }
 
-    fn info(&self) -> Result<Image2DInfo, ImageError> {
+    fn info(&self, color_space: ImageColorSpace) -> Result<Image2DInfo, ImageError> {
         let info = self.reader.info();
         let extent = vk::Extent2D {
             width: info.width,
             height: info.height,
         };
-        let format = match self.reader.output_color_type() {
-            (ColorType::Rgba, BitDepth::Eight) => vk::Format::R8G8B8A8_SRGB,
-            (ColorType::GrayscaleAlpha, BitDepth::Eight) => vk::Format::R8G8_SRGB,
-            (color_type, bit_depth) => Err(ImageError::UnsupportedFormat(color_type, bit_depth))?,
+        let format = match (self.reader.output_color_type(), color_space) {
+            ((ColorType::Rgba, BitDepth::Eight), ImageColorSpace::Srgb) => {
+                vk::Format::R8G8B8A8_SRGB
+            }
+            ((ColorType::Rgba, BitDepth::Eight), ImageColorSpace::Linear) => {
+                vk::Format::R8G8B8A8_UNORM
+            }
+            ((ColorType::GrayscaleAlpha, BitDepth::Eight), ImageColorSpace::Srgb) => {
+                vk::Format::R8G8_SRGB
+            }
+            ((ColorType::GrayscaleAlpha, BitDepth::Eight), ImageColorSpace::Linear) => {
+                vk::Format::R8G8_UNORM
+            }
+            ((color_type, bit_depth), _) => {
+                Err(ImageError::UnsupportedFormat(color_type, bit_depth))?
+            }
         };
         let mip_levels = get_max_mip_level(extent);
         Ok(Image2DInfo {
@@ -133,9 +145,9 @@ impl ImageCubeReader {
         Ok(Self { faces })
     }
 
-    fn info(&self) -> Result<Image2DInfo, ImageError> {
+    fn info(&self, color_space: ImageColorSpace) -> Result<Image2DInfo, ImageError> {
         let (_, reader) = &self.faces.first().ok_or(ImageError::ExhaustedImageRead)?;
-        let info = reader.info()?;
+        let info = reader.info(color_space)?;
         Ok(Image2DInfo {
             array_layers: 6,
             view_type: vk::ImageViewType::CUBE,
@@ -152,6 +164,7 @@ impl ImageCubeReader {
 
 pub struct ImageReader<'a> {
     reader: ImageReaderInner<'a>,
+    color_space: ImageColorSpace,
 }
 
 enum ImageReaderInner<'a> {
@@ -163,7 +176,10 @@ enum ImageReaderInner<'a> {
 impl<'a> ImageReader<'a> {
     pub fn cube(path: &Path) -> Result<Self, ImageError> {
         let reader = ImageReaderInner::Cube(ImageCubeReader::prepare(path)?);
-        Ok(Self { reader })
+        Ok(Self {
+            reader,
+            color_space: ImageColorSpace::Srgb,
+        })
     }
 
     pub fn image(image: &'a Image) -> Result<Self, ImageError> {
@@ -172,8 +188,20 @@ impl<'a> ImageReader<'a> {
             Image::Buffer(data) => {
                 ImageReaderInner::Buffer(Some(PngImageReader::from_buffer(data)?))
             }
+            Image::Texture(_) => Err(ImageError::SharedTexture)?,
         };
-        Ok(Self { reader })
+        Ok(Self {
+            reader,
+            color_space: ImageColorSpace::Srgb,
+        })
+    }
+
+    // Images are read as sRGB encoded by default
+    pub fn with_color_space(self, color_space: ImageColorSpace) -> Self {
+        Self {
+            color_space,
+            ..self
+        }
     }
 
     pub fn required_buffer_size(&self) -> Result<usize, ImageError> {
@@ -201,12 +229,12 @@ impl<'a> ImageReader<'a> {
             ImageReaderInner::File(reader) => reader
                 .as_ref()
                 .ok_or(ImageError::ExhaustedImageRead)?
-                .info(),
+                .info(self.color_space),
             ImageReaderInner::Buffer(reader) => reader
                 .as_ref()
                 .ok_or(ImageError::ExhaustedImageRead)?
-                .info(),
-            ImageReaderInner::Cube(reader) => reader.info(),
+                .info(self.color_space),
+            ImageReaderInner::Cube(reader) => reader.info(self.color_space),
         }
     }
 
@@ -232,3 +260,46 @@ impl<'a> ImageReader<'a> {
         Ok(dst_layer)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode_png(color_type: ColorType, data: &[u8]) -> Vec<u8> {
+        let mut png = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png, 2, 2);
+        encoder.set_color(color_type);
+        encoder.set_depth(BitDepth::Eight);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(data).unwrap();
+        writer.finish().unwrap();
+        png
+    }
+
+    fn read_format(image: &Image, color_space: ImageColorSpace) -> vk::Format {
+        ImageReader::image(image)
+            .unwrap()
+            .with_color_space(color_space)
+            .info()
+            .unwrap()
+            .format
+    }
+
+    #[test]
+    fn test_image_format_follows_color_space() {
+        let rgba = Image::Buffer(encode_png(ColorType::Rgba, &[128; 16]));
+        assert_eq!(
+            read_format(&rgba, ImageColorSpace::Srgb),
+            vk::Format::R8G8B8A8_SRGB
+        );
+        assert_eq!(
+            read_format(&rgba, ImageColorSpace::Linear),
+            vk::Format::R8G8B8A8_UNORM
+        );
+        let grayscale = Image::Buffer(encode_png(ColorType::GrayscaleAlpha, &[128; 8]));
+        assert_eq!(
+            read_format(&grayscale, ImageColorSpace::Linear),
+            vk::Format::R8G8_UNORM
+        );
+    }
+}
@@ -10,6 +10,7 @@ use crate::context::{
             buffer::{StagingBuffer, StagingBufferBuilder},
             PartialBuilder,
         },
+        sampler::SamplerDesc,
         Device,
     },
     error::{VkError, VkResult},
@@ -20,8 +21,16 @@ use super::{Image2D, Image2DBuilder, Image2DPartial, ImageReader};
 pub struct Texture2DPartial<'a> {
     image: Image2DPartial<DeviceLocal>,
     reader: ImageReader<'a>,
+    sampler: SamplerDesc,
 }
 
+impl<'a> Texture2DPartial<'a> {
+    pub fn with_sampler_desc(self, sampler: SamplerDesc) -> Self {
+        Self { sampler, ..self }
+    }
+}
+
+// Sampler is owned by the device sampler cache and shared with other textures
 pub struct Texture2D<A: Allocator> {
     pub image: Image2D<DeviceLocal, A>,
     pub sampler: vk::Sampler,
@@ -46,6 +55,7 @@ impl<'a> PartialBuilder<'a> for Texture2DPartial<'a> {
         Ok(Texture2DPartial {
             image,
             reader: config,
+            sampler: SamplerDesc::default(),
         })
     }
 
@@ -71,7 +81,11 @@ impl<A: Allocator> Create for Texture2D<A> {
 
     fn create<'a, 'b>(config: Self::Config<'a>, context: Self::Context<'b>) -> CreateResult<Self> {
         let (device, allocator) = context;
-        let Texture2DPartial { image, mut reader } = config;
+        let Texture2DPartial {
+            image,
+            mut reader,
+            sampler,
+        } = config;
         let mut image = Image2D::create(image, (device, allocator))?;
         let mut builder = StagingBufferBuilder::new();
         let image_range = builder.append::<u8>(reader.required_buffer_size()?);
@@ -90,16 +104,7 @@ impl<A: Allocator> Create for Texture2D<A> {
             image.layout = vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL;
             let _ = staging_buffer.destroy(device);
         }
-        let create_info = vk::SamplerCreateInfo::builder()
-            .mag_filter(vk::Filter::LINEAR)
-            .min_filter(vk::Filter::LINEAR)
-            .mipmap_mode(vk::SamplerMipmapMode::LINEAR)
-            .address_mode_u(vk::SamplerAddressMode::REPEAT)
-            .address_mode_u(vk::SamplerAddressMode::REPEAT)
-            .border_color(vk::BorderColor::FLOAT_OPAQUE_BLACK)
-            .min_lod(0.0)
-            .max_lod(image.mip_levels as f32);
-        let sampler = unsafe { device.create_sampler(&create_info, None)? };
+        let sampler = device.get_sampler(sampler)?;
         Ok(Texture2D { image, sampler })
     }
 }
@@ -110,9 +115,6 @@ impl<A: Allocator> Destroy for Texture2D<A> {
 
     fn destroy<'a>(&mut self, context: Self::Context<'a>) -> DestroyResult<Self> {
         let (device, allocator) = context;
-        unsafe {
-            device.destroy_sampler(self.sampler, None);
-        }
         let _ = self.image.destroy((device, allocator));
         Ok(())
     }
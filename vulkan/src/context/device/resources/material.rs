@@ -1,19 +1,91 @@
 mod list;
 mod pack;
 
-use std::marker::PhantomData;
+use std::{marker::PhantomData, mem::size_of};
 
 pub use list::*;
 pub use pack::*;
 
 use ash::vk;
-use graphics::model::Material as MaterialBase;
+use bytemuck::{AnyBitPattern, Zeroable};
+use graphics::model::{Material as MaterialBase, MaterialSampling};
+use math::types::Vector2;
 use type_kit::{Cons, Nil};
 
 use crate::context::device::descriptor::{
-    DescriptorBinding, DescriptorLayout, DescriptorLayoutBuilder, FragmentStage, PodUniform,
+    DescriptorBinding, DescriptorLayout, DescriptorLayoutBuilder,
 };
 
+// Packed as single vec4 in shaders, `uv * scale + offset`
+#[repr(C, align(16))]
+#[derive(Debug, Clone, Copy, AnyBitPattern)]
+pub struct UvTransform {
+    pub scale: Vector2,
+    pub offset: Vector2,
+}
+
+impl From<MaterialSampling> for UvTransform {
+    fn from(sampling: MaterialSampling) -> Self {
+        Self {
+            scale: sampling.uv_scale,
+            offset: sampling.uv_offset,
+        }
+    }
+}
+
+// Material uniform data followed by its texture coordinates transform,
+// trailing member keeps layout of the user data unchanged for shaders which do not read it
+#[repr(C)]
+pub struct MaterialUniform<M: Material> {
+    pub data: M::Uniform,
+    pub uv_transform: UvTransform,
+}
+
+unsafe impl<M: Material> Zeroable for MaterialUniform<M> {}
+
+unsafe impl<M: Material + 'static> AnyBitPattern for MaterialUniform<M> {}
+
+impl<M: Material> Clone for MaterialUniform<M> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<M: Material> Copy for MaterialUniform<M> {}
+
+impl<M: Material> DescriptorBinding for MaterialUniform<M> {
+    fn has_data() -> bool {
+        size_of::<M::Uniform>() > 0 || M::NUM_IMAGES > 0
+    }
+
+    fn get_descriptor_set_binding(binding: u32) -> vk::DescriptorSetLayoutBinding {
+        vk::DescriptorSetLayoutBinding {
+            binding,
+            descriptor_type: vk::DescriptorType::UNIFORM_BUFFER,
+            descriptor_count: 1,
+            stage_flags: vk::ShaderStageFlags::FRAGMENT,
+            p_immutable_samplers: std::ptr::null(),
+        }
+    }
+
+    fn get_descriptor_write(binding: u32) -> vk::WriteDescriptorSet {
+        vk::WriteDescriptorSet {
+            dst_binding: binding,
+            dst_array_element: 0,
+            descriptor_count: 1,
+            descriptor_type: vk::DescriptorType::UNIFORM_BUFFER,
+            ..Default::default()
+        }
+    }
+
+    fn get_descriptor_pool_size(num_sets: u32) -> vk::DescriptorPoolSize {
+        vk::DescriptorPoolSize {
+            ty: vk::DescriptorType::UNIFORM_BUFFER,
+            descriptor_count: num_sets,
+        }
+    }
+}
+
 pub struct TextureSamplers<M: Material> {
     _phantom: PhantomData<M>,
 }
@@ -56,7 +128,27 @@ pub trait Material: MaterialBase {
 }
 
 impl<T: MaterialBase> Material for T {
-    type DescriptorLayout = DescriptorLayoutBuilder<
-        Cons<PodUniform<T::Uniform, FragmentStage>, Cons<TextureSamplers<T>, Nil>>,
-    >;
+    type DescriptorLayout =
+        DescriptorLayoutBuilder<Cons<MaterialUniform<T>, Cons<TextureSamplers<T>, Nil>>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::mem::{offset_of, size_of};
+
+    use graphics::model::{PbrMaterial, TransparentMaterial, UnlitMaterial};
+
+    use super::{MaterialUniform, UvTransform};
+
+    #[test]
+    fn test_uv_transform_follows_material_data() {
+        // Offsets must match std140 layout of the shader uniform blocks
+        assert_eq!(offset_of!(MaterialUniform<UnlitMaterial>, uv_transform), 16);
+        assert_eq!(
+            offset_of!(MaterialUniform<TransparentMaterial>, uv_transform),
+            16
+        );
+        assert_eq!(offset_of!(MaterialUniform<PbrMaterial>, uv_transform), 48);
+        assert_eq!(size_of::<UvTransform>(), 16);
+    }
 }
@@ -1,11 +1,17 @@
-use std::{cell::RefCell, error::Error};
-
-use crate::context::device::{
-    memory::{AllocReq, Allocator},
-    resources::DummyPack,
-    Device,
+use std::cell::RefCell;
+
+use crate::context::{
+    device::{
+        memory::{AllocReq, Allocator},
+        resources::{image::Texture2D, DummyPack},
+        Device,
+    },
+    error::VkResult,
+};
+use graphics::{
+    model::{MaterialCollection, MaterialTypeList},
+    renderer::ContextId,
 };
-use graphics::model::{MaterialCollection, MaterialTypeList};
 use type_kit::{Cons, Destroy, Nil, TypedNil};
 
 use super::{Material, MaterialPack, MaterialPackPartial, MaterialPackRef};
@@ -13,10 +19,12 @@ use super::{Material, MaterialPack, MaterialPackPartial, MaterialPackRef};
 pub trait MaterialPackListBuilder: MaterialTypeList {
     type Pack<A: Allocator>: MaterialPackList<A>;
 
+    // Shared textures referenced by materials must belong to the `context`
     fn prepare<A: Allocator>(
         &self,
         device: &Device,
-    ) -> Result<impl MaterialPackListPartial<Pack<A> = Self::Pack<A>>, Box<dyn Error>>;
+        context: ContextId,
+    ) -> VkResult<impl MaterialPackListPartial<Pack<A> = Self::Pack<A>>>;
 }
 
 impl MaterialPackListBuilder for Nil {
@@ -25,7 +33,8 @@ impl MaterialPackListBuilder for Nil {
     fn prepare<A: Allocator>(
         &self,
         _device: &Device,
-    ) -> Result<impl MaterialPackListPartial<Pack<A> = Self::Pack<A>>, Box<dyn Error>> {
+        _context: ContextId,
+    ) -> VkResult<impl MaterialPackListPartial<Pack<A> = Self::Pack<A>>> {
         Ok(Nil::new())
     }
 }
@@ -36,16 +45,17 @@ impl<M: Material, N: MaterialPackListBuilder> MaterialPackListBuilder for Cons<V
     fn prepare<A: Allocator>(
         &self,
         device: &Device,
-    ) -> Result<impl MaterialPackListPartial<Pack<A> = Self::Pack<A>>, Box<dyn Error>> {
+        context: ContextId,
+    ) -> VkResult<impl MaterialPackListPartial<Pack<A> = Self::Pack<A>>> {
         let materials = self.get();
         let partial = if !materials.is_empty() {
-            Some(device.prepare_material_pack(materials)?)
+            Some(device.prepare_material_pack(materials, context)?)
         } else {
             None
         };
         Ok(Cons {
             head: partial,
-            tail: self.next().prepare(device)?,
+            tail: self.next().prepare(device, context)?,
         })
     }
 }
@@ -59,7 +69,8 @@ pub trait MaterialPackListPartial: Sized {
         self,
         device: &Device,
         allocator: &mut A,
-    ) -> Result<Self::Pack<A>, Box<dyn Error>>;
+        shared: &[Texture2D<A>],
+    ) -> VkResult<Self::Pack<A>>;
 }
 
 impl MaterialPackListPartial for Nil {
@@ -73,7 +84,8 @@ impl MaterialPackListPartial for Nil {
         self,
         _device: &Device,
         _allocator: &mut A,
-    ) -> Result<Self::Pack<A>, Box<dyn Error>> {
+        _shared: &[Texture2D<A>],
+    ) -> VkResult<Self::Pack<A>> {
         Ok(TypedNil::new())
     }
 }
@@ -95,16 +107,17 @@ impl<'a, M: Material, N: MaterialPackListPartial> MaterialPackListPartial
         self,
         device: &Device,
         allocator: &mut A,
-    ) -> Result<Self::Pack<A>, Box<dyn Error>> {
+        shared: &[Texture2D<A>],
+    ) -> VkResult<Self::Pack<A>> {
         let Self { head, tail } = self;
         let pack = if let Some(pack) = head {
-            Some(device.allocate_material_pack_memory(allocator, pack)?)
+            Some(device.allocate_material_pack_memory(allocator, pack, shared)?)
         } else {
             None
         };
         Ok(Cons {
             head: pack,
-            tail: tail.allocate(device, allocator)?,
+            tail: tail.allocate(device, allocator, shared)?,
         })
     }
 }
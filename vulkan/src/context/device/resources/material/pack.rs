@@ -1,13 +1,19 @@
-use std::{any::TypeId, cell::RefCell, convert::Infallible, error::Error, marker::PhantomData};
+use std::{
+    any::{type_name, TypeId},
+    cell::RefCell,
+    convert::Infallible,
+    marker::PhantomData,
+};
 
+use ash::vk;
+use bytemuck::Zeroable;
 use type_kit::{Create, Destroy, DestroyResult, DropGuard};
 
 use crate::context::{
     device::{
         command::operation::Graphics,
         descriptor::{
-            Descriptor, DescriptorPool, DescriptorPoolRef, DescriptorSetWriter, FragmentStage,
-            PodUniform,
+            Descriptor, DescriptorBinding, DescriptorPool, DescriptorPoolRef, DescriptorSetWriter,
         },
         memory::{AllocReq, Allocator},
         resources::{
@@ -15,27 +21,81 @@ use crate::context::{
             image::{ImageReader, Texture2D, Texture2DPartial},
             PartialBuilder,
         },
+        sampler::SamplerDesc,
         Device,
     },
-    error::VkResult,
+    error::{VkError, VkResult},
+};
+
+use graphics::{
+    model::{AddressMode, Filter, Image, MaterialSampling},
+    renderer::ContextId,
 };
 
-use super::{Material, TextureSamplers};
+use super::{Material, MaterialUniform, TextureSamplers};
 
-struct MaterialUniformPartial<'a, M: Material> {
-    uniform: UniformBufferPartial<PodUniform<M::Uniform, FragmentStage>, Graphics>,
-    data: Vec<&'a M::Uniform>,
+struct MaterialUniformPartial<M: Material> {
+    uniform: UniformBufferPartial<MaterialUniform<M>, Graphics>,
+    data: Vec<MaterialUniform<M>>,
 }
 
 pub struct MaterialPackData<M: Material, A: Allocator> {
     textures: Option<Vec<Texture2D<A>>>,
-    uniforms: Option<DropGuard<UniformBuffer<PodUniform<M::Uniform, FragmentStage>, Graphics, A>>>,
+    uniforms: Option<DropGuard<UniformBuffer<MaterialUniform<M>, Graphics, A>>>,
     descriptors: DropGuard<DescriptorPool<M::DescriptorLayout>>,
 }
 
+// Texture of the material image slot, shared textures are loaded by the context
+// and sampled with the sampler of the material referencing them
+enum MaterialTexturePartial<'a> {
+    Owned(Box<Texture2DPartial<'a>>),
+    Shared(usize, SamplerDesc),
+}
+
+enum MaterialTextureSlot {
+    Owned,
+    Shared(usize, vk::Sampler),
+}
+
+// Descriptor of the texture bound to the material image slot
+#[derive(Debug, Clone, Copy)]
+pub struct MaterialImage(vk::DescriptorImageInfo);
+
+impl From<&MaterialImage> for vk::DescriptorImageInfo {
+    fn from(image: &MaterialImage) -> Self {
+        image.0
+    }
+}
+
+// Owned textures are consumed in order of the slots referencing them
+fn resolve_material_images(
+    slots: &[MaterialTextureSlot],
+    owned: &[vk::DescriptorImageInfo],
+    shared: &[vk::DescriptorImageInfo],
+) -> VkResult<Vec<MaterialImage>> {
+    let mut owned = owned.iter();
+    slots
+        .iter()
+        .map(|slot| {
+            let info = match *slot {
+                MaterialTextureSlot::Owned => *owned
+                    .next()
+                    .ok_or(VkError::MissingResource("Material texture"))?,
+                MaterialTextureSlot::Shared(index, sampler) => vk::DescriptorImageInfo {
+                    sampler,
+                    ..*shared
+                        .get(index)
+                        .ok_or(VkError::MissingResource("Shared texture"))?
+                },
+            };
+            Ok(MaterialImage(info))
+        })
+        .collect()
+}
+
 pub struct MaterialPackPartial<'a, M: Material> {
-    textures: Option<Vec<Texture2DPartial<'a>>>,
-    uniforms: Option<MaterialUniformPartial<'a, M>>,
+    textures: Option<Vec<MaterialTexturePartial<'a>>>,
+    uniforms: Option<MaterialUniformPartial<M>>,
     num_materials: usize,
 }
 
@@ -50,7 +110,10 @@ impl<'a, M: Material> MaterialPackPartial<'a, M> {
             alloc_reqs.extend(
                 textures
                     .iter()
-                    .map(|texture| texture.requirements())
+                    .filter_map(|texture| match texture {
+                        MaterialTexturePartial::Owned(texture) => Some(texture.requirements()),
+                        MaterialTexturePartial::Shared(..) => None,
+                    })
                     .flatten(),
             );
         }
@@ -104,21 +167,62 @@ impl<'a, M: Material> MaterialPackRef<'a, M> {
     }
 }
 
+fn material_sampler_desc(sampling: MaterialSampling) -> SamplerDesc {
+    let address_mode = match sampling.address_mode {
+        AddressMode::Repeat => vk::SamplerAddressMode::REPEAT,
+        AddressMode::MirroredRepeat => vk::SamplerAddressMode::MIRRORED_REPEAT,
+        AddressMode::ClampToEdge => vk::SamplerAddressMode::CLAMP_TO_EDGE,
+    };
+    let (filter, mipmap_mode) = match sampling.filter {
+        Filter::Nearest => (vk::Filter::NEAREST, vk::SamplerMipmapMode::NEAREST),
+        Filter::Linear => (vk::Filter::LINEAR, vk::SamplerMipmapMode::LINEAR),
+    };
+    SamplerDesc {
+        filter,
+        mipmap_mode,
+        ..SamplerDesc::default()
+    }
+    .with_address_mode(address_mode)
+}
+
 impl Device {
     fn prepare_material_pack_textures<'a, M: Material>(
         &self,
         materials: &'a [M],
-    ) -> VkResult<Option<Vec<Texture2DPartial<'a>>>> {
+        context: ContextId,
+    ) -> VkResult<Option<Vec<MaterialTexturePartial<'a>>>> {
         if M::NUM_IMAGES > 0 {
             let textures = materials
                 .iter()
                 .flat_map(|material| {
+                    let sampler = material_sampler_desc(material.sampling());
                     // TODO: It would be better to create vector of iterators and flatten them
                     // Currently unable to do this because of the lifetime of the iterator
                     material
                         .images()
                         .unwrap()
-                        .map(|image| Texture2DPartial::prepare(ImageReader::image(image)?, self))
+                        .enumerate()
+                        .map(|(index, image)| match image {
+                            Image::Texture(handle) if handle.context() != context => {
+                                Err(VkError::HandleFromDifferentContext {
+                                    expected: context,
+                                    found: handle.context(),
+                                })
+                            }
+                            Image::Texture(handle) => Ok(MaterialTexturePartial::Shared(
+                                handle.index() as usize,
+                                sampler,
+                            )),
+                            image => {
+                                let reader = ImageReader::image(image)?
+                                    .with_color_space(M::image_color_space(index));
+                                Texture2DPartial::prepare(reader, self).map(|texture| {
+                                    MaterialTexturePartial::Owned(Box::new(
+                                        texture.with_sampler_desc(sampler),
+                                    ))
+                                })
+                            }
+                        })
                         .collect::<Vec<_>>()
                 })
                 .collect::<Result<Vec<_>, _>>()?;
@@ -131,23 +235,46 @@ impl Device {
     fn allocate_material_pack_textures_memory<'a, A: Allocator>(
         &self,
         allocator: &mut A,
-        textures: Vec<Texture2DPartial<'a>>,
-    ) -> VkResult<Vec<Texture2D<A>>> {
-        textures
-            .into_iter()
-            .map(|texture| Texture2D::create(texture, (self, allocator)))
-            .collect()
+        textures: Vec<MaterialTexturePartial<'a>>,
+        shared: &[Texture2D<A>],
+    ) -> VkResult<(Vec<Texture2D<A>>, Vec<MaterialImage>)> {
+        let mut owned = Vec::new();
+        let mut slots = Vec::with_capacity(textures.len());
+        for texture in textures {
+            match texture {
+                MaterialTexturePartial::Owned(texture) => {
+                    owned.push(Texture2D::create(*texture, (self, &mut *allocator))?);
+                    slots.push(MaterialTextureSlot::Owned);
+                }
+                MaterialTexturePartial::Shared(index, sampler) => {
+                    slots.push(MaterialTextureSlot::Shared(
+                        index,
+                        self.get_sampler(sampler)?,
+                    ));
+                }
+            }
+        }
+        let images = resolve_material_images(
+            &slots,
+            &owned.iter().map(Into::into).collect::<Vec<_>>(),
+            &shared.iter().map(Into::into).collect::<Vec<_>>(),
+        )?;
+        Ok((owned, images))
     }
 
     fn prepare_material_pack_uniforms<'a, M: Material>(
         &self,
         materials: &'a [M],
-    ) -> Result<Option<MaterialUniformPartial<'a, M>>, Box<dyn Error>> {
-        let data = materials
-            .iter()
-            .filter_map(|material| material.uniform())
-            .collect::<Vec<_>>();
-        if !data.is_empty() {
+    ) -> VkResult<Option<MaterialUniformPartial<M>>> {
+        if MaterialUniform::<M>::has_data() {
+            // Materials without user data still provide their texture coordinates transform
+            let data = materials
+                .iter()
+                .map(|material| MaterialUniform {
+                    data: material.uniform().copied().unwrap_or_else(Zeroable::zeroed),
+                    uv_transform: material.sampling().into(),
+                })
+                .collect::<Vec<_>>();
             let uniform =
                 UniformBufferPartial::prepare(UniformBufferBuilder::new(materials.len()), self)?;
             Ok(Some(MaterialUniformPartial { uniform, data }))
@@ -156,25 +283,26 @@ impl Device {
         }
     }
 
-    fn allocate_material_pack_uniforms_memory<'a, M: Material, A: Allocator>(
+    fn allocate_material_pack_uniforms_memory<M: Material, A: Allocator>(
         &self,
         allocator: &mut A,
-        partial: MaterialUniformPartial<'a, M>,
-    ) -> Result<UniformBuffer<PodUniform<M::Uniform, FragmentStage>, Graphics, A>, Box<dyn Error>>
-    {
+        partial: MaterialUniformPartial<M>,
+    ) -> VkResult<UniformBuffer<MaterialUniform<M>, Graphics, A>> {
         let MaterialUniformPartial { uniform, data } = partial;
         let mut uniform_buffer = UniformBuffer::create(uniform, (self, &RefCell::new(allocator)))?;
         for (index, uniform) in data.into_iter().enumerate() {
-            *uniform_buffer[index].as_inner_mut() = *uniform;
+            uniform_buffer[index] = uniform;
         }
+        uniform_buffer.flush(self)?;
         Ok(uniform_buffer)
     }
 
     pub fn prepare_material_pack<'a, M: Material>(
         &self,
         materials: &'a [M],
-    ) -> Result<MaterialPackPartial<'a, M>, Box<dyn Error>> {
-        let textures = self.prepare_material_pack_textures(materials)?;
+        context: ContextId,
+    ) -> VkResult<MaterialPackPartial<'a, M>> {
+        let textures = self.prepare_material_pack_textures(materials, context)?;
         let uniforms = self.prepare_material_pack_uniforms(materials)?;
         Ok(MaterialPackPartial {
             textures,
@@ -183,20 +311,41 @@ impl Device {
         })
     }
 
+    fn set_material_pack_debug_names<M: Material, A: Allocator>(
+        &self,
+        textures: Option<&[Texture2D<A>]>,
+        uniforms: Option<vk::Buffer>,
+    ) -> VkResult<()> {
+        let name = type_name::<M>();
+        for (index, texture) in textures.into_iter().flatten().enumerate() {
+            self.set_debug_name(
+                texture.image.image,
+                &format!("MaterialPack<{}>::texture[{}]", name, index),
+            )?;
+        }
+        if let Some(uniforms) = uniforms {
+            self.set_debug_name(uniforms, &format!("MaterialPack<{}>::uniforms", name))?;
+        }
+        Ok(())
+    }
+
     pub fn allocate_material_pack_memory<'a, M: Material, A: Allocator>(
         &self,
         allocator: &mut A,
         partial: MaterialPackPartial<'a, M>,
-    ) -> Result<MaterialPack<M, A>, Box<dyn Error>> {
+        shared: &[Texture2D<A>],
+    ) -> VkResult<MaterialPack<M, A>> {
         let MaterialPackPartial {
             textures,
             uniforms,
             num_materials,
         } = partial;
-        let textures = if let Some(textures) = textures {
-            Some(self.allocate_material_pack_textures_memory(allocator, textures)?)
+        let (textures, images) = if let Some(textures) = textures {
+            let (textures, images) =
+                self.allocate_material_pack_textures_memory(allocator, textures, shared)?;
+            (Some(textures), Some(images))
         } else {
-            None
+            (None, None)
         };
         let uniforms = if let Some(uniforms) = uniforms {
             Some(DropGuard::new(
@@ -205,9 +354,13 @@ impl Device {
         } else {
             None
         };
+        self.set_material_pack_debug_names::<M, A>(
+            textures.as_deref(),
+            uniforms.as_ref().map(|uniforms| uniforms.handle()),
+        )?;
         let writer = DescriptorSetWriter::<M::DescriptorLayout>::new(num_materials);
-        let writer = if let Some(textures) = &textures {
-            writer.write_images::<TextureSamplers<M>, _>(textures)
+        let writer = if let Some(images) = &images {
+            writer.write_images::<TextureSamplers<M>, _>(images)
         } else {
             writer
         };
@@ -229,9 +382,12 @@ impl Device {
         &self,
         allocator: &mut A,
         materials: &[M],
-    ) -> Result<MaterialPack<M, A>, Box<dyn Error>> {
-        let pack = self.prepare_material_pack(materials)?;
-        let pack = self.allocate_material_pack_memory(allocator, pack)?;
+        context: ContextId,
+        shared: &[Texture2D<A>],
+    ) -> VkResult<MaterialPack<M, A>> {
+        graphics::profile_scope!("load_material_pack");
+        let pack = self.prepare_material_pack(materials, context)?;
+        let pack = self.allocate_material_pack_memory(allocator, pack, shared)?;
         Ok(pack)
     }
 }
@@ -255,3 +411,48 @@ impl<M: Material, A: Allocator> Destroy for MaterialPack<M, A> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use ash::vk::{self, Handle};
+
+    use super::{resolve_material_images, MaterialTextureSlot};
+
+    fn image_info(view: u64, sampler: u64) -> vk::DescriptorImageInfo {
+        vk::DescriptorImageInfo {
+            sampler: vk::Sampler::from_raw(sampler),
+            image_view: vk::ImageView::from_raw(view),
+            image_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+        }
+    }
+
+    #[test]
+    fn test_shared_albedo_texture_written_with_material_sampler() {
+        let owned = [image_info(1, 10), image_info(2, 10)];
+        let shared = [image_info(100, 20), image_info(101, 20)];
+        let material_sampler = vk::Sampler::from_raw(30);
+        // Albedo slot references second shared texture, normal and metallic-roughness
+        // slots use textures owned by the material pack
+        let slots = [
+            MaterialTextureSlot::Shared(1, material_sampler),
+            MaterialTextureSlot::Owned,
+            MaterialTextureSlot::Owned,
+        ];
+        let images = resolve_material_images(&slots, &owned, &shared).unwrap();
+        let infos = images
+            .iter()
+            .map(vk::DescriptorImageInfo::from)
+            .collect::<Vec<_>>();
+        assert_eq!(infos[0].image_view, shared[1].image_view);
+        assert_eq!(infos[0].sampler, material_sampler);
+        assert_eq!(infos[1].image_view, owned[0].image_view);
+        assert_eq!(infos[2].image_view, owned[1].image_view);
+        assert_eq!(infos[2].sampler, owned[1].sampler);
+    }
+
+    #[test]
+    fn test_missing_shared_texture_rejected() {
+        let slots = [MaterialTextureSlot::Shared(0, vk::Sampler::null())];
+        assert!(resolve_material_images(&slots, &[], &[]).is_err());
+    }
+}
@@ -60,17 +60,41 @@ impl BufferRanges {
 pub struct MeshByteRange {
     pub vertices: ByteRange,
     pub indices: ByteRange,
+    pub index_type: vk::IndexType,
 }
 
-impl<V: Vertex> From<MeshByteRange> for MeshRange<V> {
-    fn from(value: MeshByteRange) -> Self {
-        Self {
-            vertices: value.vertices.into(),
-            indices: value.indices.into(),
-        }
+// Meshes with vertex count addressable by u16 store 16 bit indices
+pub fn mesh_index_type(num_vertices: usize) -> vk::IndexType {
+    if num_vertices <= u16::MAX as usize + 1 {
+        vk::IndexType::UINT16
+    } else {
+        vk::IndexType::UINT32
+    }
+}
+
+pub fn index_size(index_type: vk::IndexType) -> usize {
+    match index_type {
+        vk::IndexType::UINT16 => size_of::<u16>(),
+        _ => size_of::<u32>(),
     }
 }
 
+// Index data of each mesh starts at 4 byte aligned offset, so that it can be
+// bound as index buffer of either type, returns ranges relative to the start
+// of index region along with its total size
+fn index_layout<V: Vertex>(meshes: &[Mesh<V>]) -> (Vec<(ByteRange, vk::IndexType)>, usize) {
+    let mut region = ByteRange::empty();
+    let ranges = meshes
+        .iter()
+        .map(|mesh| {
+            let index_type = mesh_index_type(mesh.vertices.len());
+            let size = mesh.indices.len() * index_size(index_type);
+            (region.extend_raw(size, size_of::<u32>()), index_type)
+        })
+        .collect();
+    (ranges, ByteRange::align_raw(region.end, size_of::<u32>()))
+}
+
 pub struct MeshPackDataPartial<'a, V: Vertex> {
     meshes: &'a [Mesh<V>],
     buffer_ranges: BufferRanges,
@@ -104,3 +128,54 @@ impl<'a, A: Allocator> From<&'a MeshPackData<A>> for MeshPackBinding {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use graphics::model::CommonVertex;
+
+    use super::*;
+
+    fn triangle_mesh(num_vertices: usize) -> Mesh<CommonVertex> {
+        Mesh {
+            vertices: vec![CommonVertex::default(); num_vertices].into_boxed_slice(),
+            indices: vec![0, 1, (num_vertices - 1) as u32].into_boxed_slice(),
+        }
+    }
+
+    #[test]
+    fn test_index_layout_of_small_and_large_mesh() {
+        let small = triangle_mesh(3);
+        let large = triangle_mesh(u16::MAX as usize + 2);
+        let (ranges, size) = index_layout(&[small, large]);
+        let [(small_range, small_type), (large_range, large_type)] = ranges[..] else {
+            panic!("Expected range of each mesh");
+        };
+        assert_eq!(small_type, vk::IndexType::UINT16);
+        assert_eq!(large_type, vk::IndexType::UINT32);
+        assert_eq!((small_range.beg, small_range.end), (0, 6));
+        // Padded after three 16 bit indices, so that 32 bit ones start 4 byte aligned
+        assert_eq!((large_range.beg, large_range.end), (8, 20));
+        assert_eq!(size, 20);
+    }
+
+    #[test]
+    fn test_index_layout_total_size_aligned() {
+        let (ranges, size) =
+            index_layout(&[triangle_mesh(u16::MAX as usize + 2), triangle_mesh(3)]);
+        assert!(ranges.iter().all(|(range, _)| range.beg % 4 == 0));
+        assert_eq!((ranges[1].0.beg, ranges[1].0.end), (12, 18));
+        assert_eq!(size, 20);
+    }
+
+    #[test]
+    fn test_largest_u16_addressable_mesh_uses_16_bit_indices() {
+        assert_eq!(
+            mesh_index_type(u16::MAX as usize + 1),
+            vk::IndexType::UINT16
+        );
+        assert_eq!(
+            mesh_index_type(u16::MAX as usize + 2),
+            vk::IndexType::UINT32
+        );
+    }
+}
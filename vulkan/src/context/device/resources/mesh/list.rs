@@ -1,9 +1,12 @@
-use std::{cell::RefCell, error::Error};
-
-use crate::context::device::{
-    memory::{AllocReq, Allocator},
-    resources::{DummyPack, PartialBuilder},
-    Device,
+use std::cell::RefCell;
+
+use crate::context::{
+    device::{
+        memory::{AllocReq, Allocator},
+        resources::{DummyPack, PartialBuilder},
+        Device,
+    },
+    error::VkResult,
 };
 use graphics::model::{Mesh, MeshTypeList, Vertex};
 use type_kit::{Cons, Create, Destroy, Nil, TypedNil};
@@ -39,7 +42,7 @@ pub trait MeshPackListBuilder: MeshTypeList {
     fn prepare<A: Allocator>(
         &self,
         device: &Device,
-    ) -> Result<impl MeshPackListPartial<Pack<A> = Self::Pack<A>>, Box<dyn Error>>;
+    ) -> VkResult<impl MeshPackListPartial<Pack<A> = Self::Pack<A>>>;
 }
 
 impl MeshPackListBuilder for Nil {
@@ -48,7 +51,7 @@ impl MeshPackListBuilder for Nil {
     fn prepare<A: Allocator>(
         &self,
         _device: &Device,
-    ) -> Result<impl MeshPackListPartial<Pack<A> = Self::Pack<A>>, Box<dyn Error>> {
+    ) -> VkResult<impl MeshPackListPartial<Pack<A> = Self::Pack<A>>> {
         Ok(Nil::new())
     }
 }
@@ -59,7 +62,7 @@ impl<V: Vertex, N: MeshPackListBuilder> MeshPackListBuilder for Cons<Vec<Mesh<V>
     fn prepare<A: Allocator>(
         &self,
         device: &Device,
-    ) -> Result<impl MeshPackListPartial<Pack<A> = Self::Pack<A>>, Box<dyn Error>> {
+    ) -> VkResult<impl MeshPackListPartial<Pack<A> = Self::Pack<A>>> {
         let meshes = self.get();
         let partial = if !meshes.is_empty() {
             Some(MeshPackPartial::prepare(self.get(), device)?)
@@ -78,11 +81,7 @@ pub trait MeshPackListPartial: Sized {
 
     fn get_memory_requirements(&self) -> Vec<AllocReq>;
 
-    fn allocate<A: Allocator>(
-        self,
-        device: &Device,
-        allocator: &mut A,
-    ) -> Result<Self::Pack<A>, Box<dyn Error>>;
+    fn allocate<A: Allocator>(self, device: &Device, allocator: &mut A) -> VkResult<Self::Pack<A>>;
 }
 
 impl MeshPackListPartial for Nil {
@@ -96,7 +95,7 @@ impl MeshPackListPartial for Nil {
         self,
         _device: &Device,
         _allocator: &mut A,
-    ) -> Result<Self::Pack<A>, Box<dyn Error>> {
+    ) -> VkResult<Self::Pack<A>> {
         Ok(TypedNil::new())
     }
 }
@@ -114,11 +113,7 @@ impl<'a, V: Vertex, N: MeshPackListPartial> MeshPackListPartial
         alloc_reqs
     }
 
-    fn allocate<A: Allocator>(
-        self,
-        device: &Device,
-        allocator: &mut A,
-    ) -> Result<Self::Pack<A>, Box<dyn Error>> {
+    fn allocate<A: Allocator>(self, device: &Device, allocator: &mut A) -> VkResult<Self::Pack<A>> {
         let Self { head, tail } = self;
         let pack = if let Some(partial) = head {
             Some(MeshPack::create(
@@ -1,4 +1,9 @@
-use std::{any::TypeId, cell::RefCell, convert::Infallible, marker::PhantomData};
+use std::{
+    any::{type_name, TypeId},
+    cell::RefCell,
+    convert::Infallible,
+    marker::PhantomData,
+};
 
 use ash::vk;
 use type_kit::{Create, CreateResult, Destroy, DestroyResult};
@@ -21,7 +26,8 @@ use crate::context::{
 use graphics::model::{Mesh, Vertex};
 
 use super::{
-    BufferRanges, BufferType, MeshByteRange, MeshPackBinding, MeshPackData, MeshPackDataPartial,
+    index_layout, index_size, BufferRanges, BufferType, ByteRange, MeshByteRange, MeshPackBinding,
+    MeshPackData, MeshPackDataPartial,
 };
 
 impl<'a, V: Vertex> PartialBuilder<'a> for MeshPackPartial<'a, V> {
@@ -30,10 +36,10 @@ impl<'a, V: Vertex> PartialBuilder<'a> for MeshPackPartial<'a, V> {
 
     fn prepare(config: Self::Config, device: &Device) -> VkResult<Self> {
         let num_vertices = config.iter().fold(0, |acc, mesh| acc + mesh.vertices.len());
-        let num_indices = config.iter().fold(0, |acc, mesh| acc + mesh.indices.len());
+        let (_, index_region_size) = index_layout(config);
         let mut builder = StagingBufferBuilder::new();
         let vertex_range = builder.append::<V>(num_vertices);
-        let index_range = builder.append::<u32>(num_indices);
+        let index_range = builder.append::<u32>(index_region_size / size_of::<u32>());
         let mut buffer_ranges = BufferRanges::new();
         buffer_ranges.set(BufferType::Vertex, vertex_range);
         buffer_ranges.set(BufferType::Index, index_range);
@@ -85,12 +91,13 @@ impl<V: Vertex, A: Allocator> Create for MeshPack<V, A> {
                 },
         } = config;
         let mut buffer = Buffer::create(buffer, (device, allocator))?;
-        let num_indices = meshes.iter().fold(0, |acc, mesh| acc + mesh.indices.len());
+        device.set_debug_name(buffer.handle(), &format!("MeshPack<{}>", type_name::<V>()))?;
         let num_vertices = meshes.iter().fold(0, |acc, mesh| acc + mesh.vertices.len());
+        let (index_ranges, index_region_size) = index_layout(meshes);
         let mut builder = StagingBufferBuilder::new();
         let vertex_range = builder.append::<V>(num_vertices);
-        let index_range = builder.append::<u32>(num_indices);
-        let (vertex_ranges, index_ranges) = {
+        let index_range = builder.append::<u32>(index_region_size / size_of::<u32>());
+        let vertex_ranges = {
             let mut staging_buffer = StagingBuffer::create(builder, device)?;
             let mut vertex_writer = staging_buffer.write_range::<V>(vertex_range);
             let vertex_ranges = meshes
@@ -98,20 +105,37 @@ impl<V: Vertex, A: Allocator> Create for MeshPack<V, A> {
                 .map(|mesh| vertex_writer.write(&mesh.vertices))
                 .collect::<Vec<_>>();
             let mut index_writer = staging_buffer.write_range::<u32>(index_range);
-            let index_ranges = meshes
-                .iter()
-                .map(|mesh| index_writer.write(&mesh.indices))
-                .collect::<Vec<_>>();
+            let index_bytes =
+                bytemuck::cast_slice_mut::<u32, u8>(index_writer.remaining_as_slice_mut());
+            for (mesh, &(range, index_type)) in meshes.iter().zip(&index_ranges) {
+                let bytes = &mut index_bytes[range.beg..range.end];
+                match index_type {
+                    vk::IndexType::UINT16 => {
+                        let indices = mesh
+                            .indices
+                            .iter()
+                            .map(|&index| index as u16)
+                            .collect::<Vec<_>>();
+                        bytes.copy_from_slice(bytemuck::cast_slice(&indices))
+                    }
+                    _ => bytes.copy_from_slice(bytemuck::cast_slice(&mesh.indices)),
+                }
+            }
             staging_buffer.transfer_buffer_data(device, &mut buffer, 0)?;
             let _ = staging_buffer.destroy(device);
-            (vertex_ranges, index_ranges)
+            vertex_ranges
         };
+        let index_region = buffer_ranges[BufferType::Index];
         let meshes = vertex_ranges
             .into_iter()
             .zip(index_ranges)
-            .map(|(vertices, indices)| MeshByteRange {
+            .map(|(vertices, (indices, index_type))| MeshByteRange {
                 vertices: vertices.into(),
-                indices: indices.into(),
+                indices: ByteRange {
+                    beg: index_region.beg + indices.beg,
+                    end: index_region.beg + indices.end,
+                },
+                index_type,
             })
             .collect();
         let data = MeshPackData {
@@ -176,10 +200,7 @@ impl<'a, V: Vertex, A: Allocator> From<MeshPackRef<'a, V, A>> for MeshPackBindin
 
 impl<'a, V: Vertex, A: Allocator> MeshPackRef<'a, V, A> {
     pub fn get(&self, index: usize) -> MeshRange<V> {
-        MeshRange {
-            vertices: self.data.meshes[index].vertices.into(),
-            indices: self.data.meshes[index].indices.into(),
-        }
+        self.data.get(index)
     }
 
     pub fn as_raw(&self) -> &MeshPackData<A> {
@@ -207,31 +228,57 @@ impl<'a, V: Vertex, A: Allocator> From<&'a MeshPack<V, A>> for MeshPackBinding {
 
 impl<V: Vertex, A: Allocator> MeshPack<V, A> {
     pub fn get(&self, index: usize) -> MeshRange<V> {
-        self.data.meshes[index].into()
+        self.data.get(index)
+    }
+}
+
+impl<A: Allocator> MeshPackData<A> {
+    fn get<V: Vertex>(&self, index: usize) -> MeshRange<V> {
+        let MeshByteRange {
+            vertices,
+            indices,
+            index_type,
+        } = self.meshes[index];
+        MeshRange {
+            buffer: self.buffer.handle(),
+            vertices: vertices.into(),
+            indices,
+            index_type,
+        }
     }
 }
 
+// Index buffer is bound separately for each mesh, as index type
+// may differ between meshes of the same pack
 #[derive(Debug, Clone, Copy)]
 pub struct MeshRangeBindData {
+    pub index_buffer: vk::Buffer,
+    pub index_buffer_offset: vk::DeviceSize,
+    pub index_type: vk::IndexType,
     pub index_count: u32,
-    pub index_offset: u32,
     pub vertex_offset: i32,
 }
 
 impl<V: Vertex> From<MeshRange<V>> for MeshRangeBindData {
     fn from(value: MeshRange<V>) -> Self {
         MeshRangeBindData {
-            index_count: value.indices.len as u32,
-            index_offset: value.indices.first as u32,
+            index_buffer: value.buffer,
+            index_buffer_offset: value.indices.beg as vk::DeviceSize,
+            index_type: value.index_type,
+            index_count: (value.indices.len() / index_size(value.index_type)) as u32,
             vertex_offset: value.vertices.first as i32,
         }
     }
 }
 
+// Vertices are relative to the vertex region of the pack,
+// indices are given as byte range of the pack buffer
 #[derive(Debug, Clone, Copy)]
 pub struct MeshRange<V: Vertex> {
+    pub buffer: vk::Buffer,
     pub vertices: Range<V>,
-    pub indices: Range<u32>,
+    pub indices: ByteRange,
+    pub index_type: vk::IndexType,
 }
 
 impl Device {
@@ -240,6 +287,7 @@ impl Device {
         allocator: &mut A,
         meshes: &[Mesh<V>],
     ) -> VkResult<MeshPack<V, A>> {
+        graphics::profile_scope!("load_mesh_pack");
         let partial = MeshPackPartial::prepare(meshes, self)?;
         MeshPack::create(partial, (self, &RefCell::new(allocator)))
     }
@@ -0,0 +1,242 @@
+mod font;
+
+use std::{cell::RefCell, convert::Infallible, mem::offset_of, path::Path};
+
+use ash::vk;
+use bytemuck::{Pod, Zeroable};
+use graphics::{
+    model::{Component, ComponentFormat, Vertex},
+    shader::PolygonMode,
+};
+use math::types::{Vector2, Vector3, Vector4};
+use type_kit::{Create, Destroy, DestroyResult, DropGuard, DropGuardError};
+
+use crate::context::{
+    device::{
+        command::operation::{Graphics, Operation},
+        descriptor::{DescriptorPool, DescriptorSetWriter, TextureDescriptorSet},
+        memory::DefaultAllocator,
+        pipeline::{
+            GraphicsPipeline, GraphicsPipelineConfig, PipelineLayoutOverlay, ShaderDirectory,
+        },
+        sampler::SamplerDesc,
+        Device,
+    },
+    error::{VkError, VkResult},
+};
+
+use super::{
+    buffer::{BufferBuilder, BufferInfo, ByteRange, PersistentBuffer, PersistentBufferPartial},
+    image::{ImageReader, Texture2D, Texture2DPartial},
+    PartialBuilder,
+};
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, Zeroable, Pod)]
+pub struct OverlayVertex {
+    pos: Vector3,
+    uv: Vector2,
+    color: Vector4,
+}
+
+impl Vertex for OverlayVertex {
+    fn components() -> &'static [Component] {
+        const COMPONENTS: &[Component] = &[
+            Component {
+                format: ComponentFormat::Float3,
+                offset: offset_of!(OverlayVertex, pos),
+            },
+            Component {
+                format: ComponentFormat::Float2,
+                offset: offset_of!(OverlayVertex, uv),
+            },
+            Component {
+                format: ComponentFormat::Float4,
+                offset: offset_of!(OverlayVertex, color),
+            },
+        ];
+        COMPONENTS
+    }
+}
+
+const OVERLAY_SHADER: &str = "_resources/shaders/spv/overlay";
+
+// Screen space text drawn with bitmap font over the final image, quads are
+// accumulated on the host and copied into the frame region of the vertex buffer
+pub struct TextOverlay<L: GraphicsPipelineConfig<Layout = PipelineLayoutOverlay<DefaultAllocator>>>
+{
+    font_atlas: DropGuard<Texture2D<DefaultAllocator>>,
+    pub descriptor: DropGuard<DescriptorPool<TextureDescriptorSet<DefaultAllocator>>>,
+    pub pipeline: DropGuard<GraphicsPipeline<L>>,
+    vertex_buffer: DropGuard<PersistentBuffer<DefaultAllocator>>,
+    vertices: Vec<OverlayVertex>,
+    extent: vk::Extent2D,
+}
+
+// Vertex range written for single frame, ready to be bound for drawing
+#[derive(Debug, Clone, Copy)]
+pub struct OverlayBindData {
+    pub buffer: vk::Buffer,
+    pub offset: vk::DeviceSize,
+    pub vertex_count: u32,
+}
+
+impl<L: GraphicsPipelineConfig<Layout = PipelineLayoutOverlay<DefaultAllocator>>> TextOverlay<L> {
+    const MAX_GLYPHS: usize = 4096;
+    const MAX_VERTICES: usize = Self::MAX_GLYPHS * 6;
+    // Each font texel covers GLYPH_SCALE x GLYPH_SCALE screen pixels
+    const GLYPH_SCALE: f32 = 2.0;
+
+    fn create_pipeline(device: &Device) -> VkResult<GraphicsPipeline<L>> {
+        let layout = device.get_pipeline_layout::<L::Layout>()?;
+        let modules = ShaderDirectory::new(Path::new(OVERLAY_SHADER));
+        GraphicsPipeline::create((layout, &modules, PolygonMode::Fill), device)
+    }
+
+    // Pixel to NDC mapping depends on the swapchain extent,
+    // must be updated after swapchain recreation
+    #[inline]
+    pub fn set_extent(&mut self, extent: vk::Extent2D) {
+        self.extent = extent;
+    }
+
+    // Appends text with its top left corner at (x, y) in swapchain pixels,
+    // text exceeding the per frame glyph budget is dropped
+    pub fn draw_text(&mut self, x: f32, y: f32, text: &str, color: Vector4) {
+        let (width, height) = (self.extent.width as f32, self.extent.height as f32);
+        let to_ndc =
+            |x: f32, y: f32| Vector3::new(2.0 * x / width - 1.0, 2.0 * y / height - 1.0, 0.0);
+        let size = font::GLYPH_SIZE as f32 * Self::GLYPH_SCALE;
+        let (mut pen_x, mut pen_y) = (x, y);
+        for character in text.chars() {
+            if character == '\n' {
+                pen_x = x;
+                pen_y += size;
+                continue;
+            }
+            if self.vertices.len() + 6 > Self::MAX_VERTICES {
+                break;
+            }
+            if !character.is_whitespace() {
+                let ([u_min, v_min], [u_max, v_max]) = font::glyph_uv(font::glyph_index(character));
+                let corners = [
+                    (to_ndc(pen_x, pen_y), Vector2::new(u_min, v_min)),
+                    (to_ndc(pen_x + size, pen_y), Vector2::new(u_max, v_min)),
+                    (
+                        to_ndc(pen_x + size, pen_y + size),
+                        Vector2::new(u_max, v_max),
+                    ),
+                    (to_ndc(pen_x, pen_y + size), Vector2::new(u_min, v_max)),
+                ];
+                // Counter clockwise winding in framebuffer space
+                self.vertices.extend([0, 3, 2, 0, 2, 1].map(|index| {
+                    let (pos, uv) = corners[index];
+                    OverlayVertex { pos, uv, color }
+                }));
+            }
+            pen_x += size;
+        }
+    }
+
+    // Moves text accumulated since the last call into the vertex buffer region
+    // of the frame, region is reused only after the frame has finished
+    pub fn flush(&mut self, device: &Device, frame_index: usize) -> VkResult<OverlayBindData> {
+        let region_size = Self::MAX_VERTICES * size_of::<OverlayVertex>();
+        let beg = frame_index * region_size;
+        let bytes = bytemuck::cast_slice::<_, u8>(&self.vertices);
+        if !bytes.is_empty() {
+            let ptr = self
+                .vertex_buffer
+                .ptr
+                .ok_or(VkError::MissingResource("overlay vertex buffer mapping"))?;
+            unsafe {
+                std::ptr::copy_nonoverlapping(
+                    bytes.as_ptr(),
+                    (ptr as *mut u8).add(beg),
+                    bytes.len(),
+                );
+            }
+            self.vertex_buffer.mark_dirty(ByteRange {
+                beg,
+                end: beg + bytes.len(),
+            });
+            self.vertex_buffer.flush(device)?;
+        }
+        let vertex_count = self.vertices.len() as u32;
+        self.vertices.clear();
+        Ok(OverlayBindData {
+            buffer: self.vertex_buffer.buffer.handle(),
+            offset: beg as vk::DeviceSize,
+            vertex_count,
+        })
+    }
+}
+
+impl<L: GraphicsPipelineConfig<Layout = PipelineLayoutOverlay<DefaultAllocator>>> Create
+    for TextOverlay<L>
+{
+    type Config<'a> = (usize, vk::Extent2D);
+    type CreateError = VkError;
+
+    fn create<'a, 'b>(
+        config: Self::Config<'a>,
+        context: Self::Context<'b>,
+    ) -> type_kit::CreateResult<Self> {
+        let (num_frames, extent) = config;
+        let atlas = font::font_atlas()?;
+        let sampler = SamplerDesc {
+            filter: vk::Filter::NEAREST,
+            mipmap_mode: vk::SamplerMipmapMode::NEAREST,
+            anisotropy: false,
+            max_lod: 0.0,
+            ..Default::default()
+        }
+        .with_address_mode(vk::SamplerAddressMode::CLAMP_TO_EDGE);
+        let font_atlas = Texture2D::create(
+            Texture2DPartial::prepare(ImageReader::image(&atlas)?, context)?
+                .with_sampler_desc(sampler),
+            (context, &mut DefaultAllocator {}),
+        )?;
+        let descriptor = DescriptorPool::create(
+            DescriptorSetWriter::<TextureDescriptorSet<DefaultAllocator>>::new(1)
+                .write_images::<Texture2D<DefaultAllocator>, _>(std::slice::from_ref(&font_atlas)),
+            context,
+        )?;
+        let pipeline = Self::create_pipeline(context)?;
+        let info = BufferInfo {
+            size: num_frames * Self::MAX_VERTICES * size_of::<OverlayVertex>(),
+            usage: vk::BufferUsageFlags::VERTEX_BUFFER,
+            sharing_mode: vk::SharingMode::EXCLUSIVE,
+            queue_families: &[Graphics::get_queue_family_index(context)],
+        };
+        let vertex_buffer = PersistentBuffer::create(
+            PersistentBufferPartial::prepare(BufferBuilder::new(info), context)?,
+            (context, &RefCell::new(&mut DefaultAllocator {})),
+        )?;
+        Ok(TextOverlay {
+            font_atlas: DropGuard::new(font_atlas),
+            descriptor: DropGuard::new(descriptor),
+            pipeline: DropGuard::new(pipeline),
+            vertex_buffer: DropGuard::new(vertex_buffer),
+            vertices: Vec::new(),
+            extent,
+        })
+    }
+}
+
+impl<L: GraphicsPipelineConfig<Layout = PipelineLayoutOverlay<DefaultAllocator>>> Destroy
+    for TextOverlay<L>
+{
+    type Context<'a> = &'a Device;
+    type DestroyError = DropGuardError<Infallible>;
+
+    fn destroy<'a>(&mut self, context: Self::Context<'a>) -> DestroyResult<Self> {
+        self.descriptor.destroy(context)?;
+        self.vertex_buffer
+            .destroy((context, &RefCell::new(&mut DefaultAllocator {})))?;
+        self.font_atlas
+            .destroy((context, &mut DefaultAllocator {}))?;
+        self.pipeline.destroy(context)?;
+        Ok(())
+    }
+}
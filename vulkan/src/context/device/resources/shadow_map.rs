@@ -0,0 +1,170 @@
+use std::convert::Infallible;
+
+use ash::vk;
+use bytemuck::{Pod, Zeroable};
+use graphics::renderer::light::DirectionalLight;
+use math::types::{Matrix4, Vector3, Vector4};
+
+use crate::context::{
+    device::{
+        framebuffer::{
+            presets::AttachmentsShadowMap, AttachmentsBuilder, Framebuffer, FramebufferHandle,
+        },
+        memory::{Allocator, DeviceLocal},
+        render_pass::ShadowMapRenderPass,
+        Device, OutputColorSpace,
+    },
+    error::VkError,
+};
+use type_kit::{Create, Destroy, DestroyResult, DropGuard, DropGuardError};
+
+use super::image::Image2D;
+
+pub const SHADOW_MAP_EXTENT: vk::Extent2D = vk::Extent2D {
+    width: 2048,
+    height: 2048,
+};
+
+// Radius of the region around the world origin covered by the shadow map
+pub const SHADOW_MAP_RADIUS: f32 = 16.0;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Zeroable, Pod)]
+pub struct LightUniform {
+    pub light_space: Matrix4,
+    // w component set to 1.0 when the light is enabled
+    pub direction: Vector4,
+    pub color: Vector4,
+    // x component set to 1.0 when the shading pass applies sRGB encoding itself
+    pub encoding: Vector4,
+    _padding: Vector4,
+}
+
+impl LightUniform {
+    #[inline]
+    pub fn with_output_color_space(self, color_space: OutputColorSpace) -> Self {
+        let manual_srgb = match color_space {
+            OutputColorSpace::Linear => 1.0,
+            OutputColorSpace::Srgb => 0.0,
+        };
+        Self {
+            encoding: Vector4::new(manual_srgb, 0.0, 0.0, 0.0),
+            ..self
+        }
+    }
+}
+
+impl From<Option<&DirectionalLight>> for LightUniform {
+    fn from(light: Option<&DirectionalLight>) -> Self {
+        match light {
+            Some(light) => {
+                let direction = light.direction.norm();
+                let color = light.intensity * light.color;
+                LightUniform {
+                    light_space: light.get_light_space_matrix(Vector3::zero(), SHADOW_MAP_RADIUS),
+                    direction: Vector4::new(direction.x, direction.y, direction.z, 1.0),
+                    color: Vector4::new(color.x, color.y, color.z, 1.0),
+                    encoding: Vector4::zero(),
+                    _padding: Vector4::zero(),
+                }
+            }
+            None => LightUniform::zeroed(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ShadowMapSampler {
+    image_view: vk::ImageView,
+    sampler: vk::Sampler,
+}
+
+impl From<&ShadowMapSampler> for vk::DescriptorImageInfo {
+    fn from(shadow_map: &ShadowMapSampler) -> Self {
+        vk::DescriptorImageInfo {
+            sampler: shadow_map.sampler,
+            image_view: shadow_map.image_view,
+            image_layout: vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL,
+        }
+    }
+}
+
+pub struct ShadowMap<A: Allocator> {
+    image: DropGuard<Image2D<DeviceLocal, A>>,
+    sampler: vk::Sampler,
+    framebuffer: Framebuffer<AttachmentsShadowMap>,
+}
+
+impl<A: Allocator> ShadowMap<A> {
+    pub fn sampler(&self) -> ShadowMapSampler {
+        ShadowMapSampler {
+            image_view: self.image.image_view,
+            sampler: self.sampler,
+        }
+    }
+
+    pub fn image(&self) -> &Image2D<DeviceLocal, A> {
+        &self.image
+    }
+
+    pub fn framebuffer(&self) -> FramebufferHandle<AttachmentsShadowMap> {
+        (&self.framebuffer).into()
+    }
+
+    pub fn render_area(&self) -> vk::Rect2D {
+        vk::Rect2D {
+            offset: vk::Offset2D { x: 0, y: 0 },
+            extent: self.image.extent,
+        }
+    }
+}
+
+impl<A: Allocator> Create for ShadowMap<A> {
+    type Config<'a> = ();
+    type CreateError = VkError;
+
+    fn create<'a, 'b>(
+        _: Self::Config<'a>,
+        context: Self::Context<'b>,
+    ) -> type_kit::CreateResult<Self> {
+        let (device, allocator) = context;
+        let image = device.create_shadow_map_image(allocator, SHADOW_MAP_EXTENT)?;
+        let framebuffer = device.build_framebuffer::<ShadowMapRenderPass<AttachmentsShadowMap>>(
+            AttachmentsBuilder::new().push(image.image_view),
+            SHADOW_MAP_EXTENT,
+        )?;
+        let create_info = vk::SamplerCreateInfo::builder()
+            .mag_filter(vk::Filter::LINEAR)
+            .min_filter(vk::Filter::LINEAR)
+            .mipmap_mode(vk::SamplerMipmapMode::NEAREST)
+            .address_mode_u(vk::SamplerAddressMode::CLAMP_TO_BORDER)
+            .address_mode_v(vk::SamplerAddressMode::CLAMP_TO_BORDER)
+            .address_mode_w(vk::SamplerAddressMode::CLAMP_TO_BORDER)
+            .border_color(vk::BorderColor::FLOAT_OPAQUE_WHITE)
+            .compare_enable(true)
+            .compare_op(vk::CompareOp::LESS_OR_EQUAL)
+            .min_lod(0.0)
+            .max_lod(0.0);
+        let sampler = unsafe { device.create_sampler(&create_info, None)? };
+        Ok(ShadowMap {
+            image: DropGuard::new(image),
+            sampler,
+            framebuffer,
+        })
+    }
+}
+
+impl<A: Allocator> Destroy for ShadowMap<A> {
+    type Context<'a> = (&'a Device, &'a mut A);
+    type DestroyError = DropGuardError<Infallible>;
+
+    fn destroy<'a>(&mut self, context: Self::Context<'a>) -> DestroyResult<Self> {
+        let (device, allocator) = context;
+        device.destroy_framebuffer(&mut self.framebuffer);
+        unsafe {
+            device.destroy_sampler(self.sampler, None);
+        }
+        self.image.destroy((device, allocator))?;
+        Ok(())
+    }
+}
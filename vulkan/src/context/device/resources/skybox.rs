@@ -1,24 +1,32 @@
-use std::{cell::RefCell, convert::Infallible, path::Path};
+use std::{
+    cell::RefCell,
+    convert::Infallible,
+    path::{Path, PathBuf},
+};
 
-use graphics::{model::CommonVertex, renderer::camera::CameraMatrices};
+use graphics::{
+    model::{CommonVertex, Mesh},
+    renderer::camera::CameraMatrices,
+    shader::PolygonMode,
+};
 use physics::shape;
 
 use crate::context::{
     device::{
         descriptor::{DescriptorPool, DescriptorSetWriter, TextureDescriptorSet},
-        memory::Allocator,
+        memory::{AllocReq, Allocator},
         pipeline::{
             GraphicsPipeline, GraphicsPipelineConfig, PipelineLayoutBuilder, ShaderDirectory,
         },
         Device,
     },
-    error::VkError,
+    error::{VkError, VkResult},
 };
 use type_kit::{Cons, Create, Destroy, DestroyResult, DropGuard, DropGuardError, Nil};
 
 use super::{
-    image::{ImageReader, Texture2D},
-    MeshPack,
+    image::{ImageReader, Texture2D, Texture2DPartial},
+    MeshPack, MeshPackPartial, PartialBuilder,
 };
 
 pub type LayoutSkybox<A> =
@@ -33,8 +41,42 @@ pub struct Skybox<A: Allocator, L: GraphicsPipelineConfig<Layout = LayoutSkybox<
 
 const SKYBOX_SHADER: &'static str = "_resources/shaders/spv/skybox";
 
+// Directory of six cube map face images together with the cube mesh they are drawn on
+pub struct SkyboxBuilder {
+    path: PathBuf,
+    mesh: [Mesh<CommonVertex>; 1],
+}
+
+impl SkyboxBuilder {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_owned(),
+            mesh: [shape::Cube::new(1.0).into()],
+        }
+    }
+}
+
+pub struct SkyboxPartial<'a> {
+    cubemap: Texture2DPartial<'a>,
+    mesh_pack: MeshPackPartial<'a, CommonVertex>,
+}
+
+impl<'a> SkyboxPartial<'a> {
+    pub fn prepare(config: &'a SkyboxBuilder, device: &Device) -> VkResult<Self> {
+        let cubemap = Texture2DPartial::prepare(ImageReader::cube(&config.path)?, device)?;
+        let mesh_pack = MeshPackPartial::prepare(&config.mesh, device)?;
+        Ok(Self { cubemap, mesh_pack })
+    }
+
+    pub fn requirements(&self) -> impl Iterator<Item = AllocReq> + '_ {
+        self.cubemap
+            .requirements()
+            .chain(self.mesh_pack.requirements())
+    }
+}
+
 impl<A: Allocator, L: GraphicsPipelineConfig<Layout = LayoutSkybox<A>>> Create for Skybox<A, L> {
-    type Config<'a> = &'a Path;
+    type Config<'a> = SkyboxPartial<'a>;
     type CreateError = VkError;
 
     fn create<'a, 'b>(
@@ -42,7 +84,8 @@ impl<A: Allocator, L: GraphicsPipelineConfig<Layout = LayoutSkybox<A>>> Create f
         context: Self::Context<'b>,
     ) -> type_kit::CreateResult<Self> {
         let (device, allocator) = context;
-        let cubemap = device.load_texture(allocator, ImageReader::cube(config)?)?;
+        let SkyboxPartial { cubemap, mesh_pack } = config;
+        let cubemap = Texture2D::create(cubemap, (device, allocator))?;
         let descriptor = DescriptorPool::create(
             DescriptorSetWriter::<TextureDescriptorSet<A>>::new(1)
                 .write_images::<Texture2D<A>, _>(std::slice::from_ref(&cubemap)),
@@ -50,8 +93,8 @@ impl<A: Allocator, L: GraphicsPipelineConfig<Layout = LayoutSkybox<A>>> Create f
         )?;
         let layout = device.get_pipeline_layout::<L::Layout>()?;
         let modules = ShaderDirectory::new(Path::new(SKYBOX_SHADER));
-        let pipeline = GraphicsPipeline::create((layout, &modules), device)?;
-        let mesh_pack = device.load_mesh_pack(allocator, &[shape::Cube::new(1.0).into()])?;
+        let pipeline = GraphicsPipeline::create((layout, &modules, PolygonMode::Fill), device)?;
+        let mesh_pack = MeshPack::create(mesh_pack, (device, &RefCell::new(allocator)))?;
         Ok(Skybox {
             cubemap: DropGuard::new(cubemap),
             mesh_pack: DropGuard::new(mesh_pack),
@@ -0,0 +1,267 @@
+use std::{
+    cell::RefCell,
+    collections::{hash_map::Entry, HashMap},
+    hash::{Hash, Hasher},
+};
+
+use ash::vk;
+
+use crate::context::error::VkResult;
+
+use super::Device;
+
+#[derive(Debug, Clone, Copy)]
+pub struct SamplerConfig {
+    pub anisotropy: bool,
+    pub max_anisotropy: f32,
+}
+
+impl Default for SamplerConfig {
+    fn default() -> Self {
+        Self {
+            anisotropy: true,
+            max_anisotropy: 16.0,
+        }
+    }
+}
+
+impl SamplerConfig {
+    // Anisotropy is requested only when enabled in config and supported by the device,
+    // max anisotropy is clamped to the device limit
+    fn get_anisotropy(&self, device_max_anisotropy: Option<f32>) -> Option<f32> {
+        match (self.anisotropy, device_max_anisotropy) {
+            (true, Some(device_max)) => Some(self.max_anisotropy.clamp(1.0, device_max)),
+            _ => None,
+        }
+    }
+}
+
+// Sampler parameters used as the cache key, anisotropy level
+// is taken from the device wide SamplerConfig
+#[derive(Debug, Clone, Copy)]
+pub struct SamplerDesc {
+    pub filter: vk::Filter,
+    pub mipmap_mode: vk::SamplerMipmapMode,
+    pub address_u: vk::SamplerAddressMode,
+    pub address_v: vk::SamplerAddressMode,
+    pub address_w: vk::SamplerAddressMode,
+    pub anisotropy: bool,
+    pub max_lod: f32,
+}
+
+impl Default for SamplerDesc {
+    fn default() -> Self {
+        Self {
+            filter: vk::Filter::LINEAR,
+            mipmap_mode: vk::SamplerMipmapMode::LINEAR,
+            address_u: vk::SamplerAddressMode::REPEAT,
+            address_v: vk::SamplerAddressMode::REPEAT,
+            address_w: vk::SamplerAddressMode::REPEAT,
+            anisotropy: true,
+            max_lod: vk::LOD_CLAMP_NONE,
+        }
+    }
+}
+
+impl SamplerDesc {
+    #[inline]
+    pub fn with_address_mode(self, address_mode: vk::SamplerAddressMode) -> Self {
+        Self {
+            address_u: address_mode,
+            address_v: address_mode,
+            address_w: address_mode,
+            ..self
+        }
+    }
+
+    fn key(&self) -> [i32; 7] {
+        [
+            self.filter.as_raw(),
+            self.mipmap_mode.as_raw(),
+            self.address_u.as_raw(),
+            self.address_v.as_raw(),
+            self.address_w.as_raw(),
+            self.anisotropy as i32,
+            self.max_lod.to_bits() as i32,
+        ]
+    }
+}
+
+impl PartialEq for SamplerDesc {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for SamplerDesc {}
+
+impl Hash for SamplerDesc {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state);
+    }
+}
+
+// Samplers shared between all textures created with equal descriptions,
+// destroyed together with the device
+#[derive(Debug, Default)]
+pub struct SamplerCache {
+    config: SamplerConfig,
+    samplers: RefCell<HashMap<SamplerDesc, vk::Sampler>>,
+}
+
+impl SamplerCache {
+    pub fn new(config: SamplerConfig) -> Self {
+        Self {
+            config,
+            samplers: RefCell::new(HashMap::new()),
+        }
+    }
+
+    // Shares sampler cached under the description, `create` is called only on cache miss
+    fn acquire(
+        &self,
+        desc: SamplerDesc,
+        create: impl FnOnce(&vk::SamplerCreateInfo) -> VkResult<vk::Sampler>,
+        device_max_anisotropy: Option<f32>,
+    ) -> VkResult<vk::Sampler> {
+        let mut samplers = self.samplers.borrow_mut();
+        match samplers.entry(desc) {
+            Entry::Occupied(entry) => Ok(*entry.get()),
+            Entry::Vacant(entry) => {
+                let sampler = create(&self.create_info(&desc, device_max_anisotropy))?;
+                Ok(*entry.insert(sampler))
+            }
+        }
+    }
+
+    // Every unique sampler is returned once, the cache is left empty
+    fn drain(&self) -> Vec<vk::Sampler> {
+        self.samplers
+            .borrow_mut()
+            .drain()
+            .map(|(_, sampler)| sampler)
+            .collect()
+    }
+
+    fn create_info(
+        &self,
+        desc: &SamplerDesc,
+        device_max_anisotropy: Option<f32>,
+    ) -> vk::SamplerCreateInfo {
+        let create_info = vk::SamplerCreateInfo::builder()
+            .mag_filter(desc.filter)
+            .min_filter(desc.filter)
+            .mipmap_mode(desc.mipmap_mode)
+            .address_mode_u(desc.address_u)
+            .address_mode_v(desc.address_v)
+            .address_mode_w(desc.address_w)
+            .border_color(vk::BorderColor::FLOAT_OPAQUE_BLACK)
+            .min_lod(0.0)
+            .max_lod(desc.max_lod);
+        let anisotropy = desc
+            .anisotropy
+            .then(|| self.config.get_anisotropy(device_max_anisotropy))
+            .flatten();
+        match anisotropy {
+            Some(max_anisotropy) => create_info
+                .anisotropy_enable(true)
+                .max_anisotropy(max_anisotropy),
+            None => create_info.anisotropy_enable(false),
+        }
+        .build()
+    }
+}
+
+impl Device {
+    pub fn get_sampler(&self, desc: SamplerDesc) -> VkResult<vk::Sampler> {
+        self.samplers.acquire(
+            desc,
+            |create_info| Ok(unsafe { self.create_sampler(create_info, None)? }),
+            self.max_sampler_anisotropy(),
+        )
+    }
+
+    pub fn destroy_samplers(&self) {
+        for sampler in self.samplers.drain() {
+            unsafe {
+                self.device.destroy_sampler(sampler, None);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ash::vk::Handle;
+
+    use super::*;
+
+    #[test]
+    fn test_anisotropy_requires_device_support() {
+        let config = SamplerConfig::default();
+        assert_eq!(config.get_anisotropy(None), None);
+        assert_eq!(config.get_anisotropy(Some(8.0)), Some(8.0));
+        assert_eq!(config.get_anisotropy(Some(32.0)), Some(16.0));
+        let disabled = SamplerConfig {
+            anisotropy: false,
+            ..Default::default()
+        };
+        assert_eq!(disabled.get_anisotropy(Some(16.0)), None);
+    }
+
+    #[test]
+    fn test_create_info_disables_unsupported_anisotropy() {
+        let cache = SamplerCache::new(SamplerConfig::default());
+        let desc = SamplerDesc::default();
+        let create_info = cache.create_info(&desc, None);
+        assert_eq!(create_info.anisotropy_enable, vk::FALSE);
+        let create_info = cache.create_info(&desc, Some(4.0));
+        assert_eq!(create_info.anisotropy_enable, vk::TRUE);
+        assert_eq!(create_info.max_anisotropy, 4.0);
+        let desc = SamplerDesc {
+            anisotropy: false,
+            ..desc
+        };
+        let create_info = cache.create_info(&desc, Some(4.0));
+        assert_eq!(create_info.anisotropy_enable, vk::FALSE);
+    }
+
+    #[test]
+    fn test_equal_descs_share_sampler_destroyed_once() {
+        let cache = SamplerCache::new(SamplerConfig::default());
+        let created = RefCell::new(Vec::new());
+        let create = |_: &vk::SamplerCreateInfo| {
+            let mut created = created.borrow_mut();
+            let sampler = vk::Sampler::from_raw(created.len() as u64 + 1);
+            created.push(sampler);
+            Ok(sampler)
+        };
+        let repeat = SamplerDesc::default();
+        let clamp = repeat.with_address_mode(vk::SamplerAddressMode::CLAMP_TO_EDGE);
+        // Two textures with the default description share single sampler
+        let first = cache.acquire(repeat, create, None).unwrap();
+        let second = cache.acquire(SamplerDesc::default(), create, None).unwrap();
+        assert_eq!(first, second);
+        let other = cache.acquire(clamp, create, None).unwrap();
+        assert_ne!(first, other);
+        assert_eq!(cache.acquire(clamp, create, None).unwrap(), other);
+        assert_eq!(created.borrow().len(), 2);
+
+        let mut destroyed = cache.drain();
+        destroyed.sort_by_key(|sampler| sampler.as_raw());
+        assert_eq!(destroyed, *created.borrow());
+        assert!(cache.drain().is_empty());
+    }
+
+    #[test]
+    fn test_failed_creation_not_cached() {
+        let cache = SamplerCache::new(SamplerConfig::default());
+        let result = cache.acquire(
+            SamplerDesc::default(),
+            |_| Err(vk::Result::ERROR_OUT_OF_HOST_MEMORY.into()),
+            None,
+        );
+        assert!(result.is_err());
+        assert!(cache.drain().is_empty());
+    }
+}
@@ -0,0 +1,260 @@
+use std::{
+    cell::RefCell,
+    convert::Infallible,
+    error::Error,
+    fs::File,
+    io::BufWriter,
+    path::{Path, PathBuf},
+    thread,
+};
+
+use ash::vk;
+use graphics::renderer::{ScreenshotCallback, ScreenshotResult};
+use type_kit::{Create, Destroy, DestroyResult};
+
+use crate::context::error::VkResult;
+
+use super::{
+    command::{operation::Graphics, operation::Operation},
+    framebuffer::AttachmentList,
+    memory::DefaultAllocator,
+    resources::{
+        buffer::{BufferBuilder, BufferInfo, PersistentBuffer, PersistentBufferPartial},
+        PartialBuilder,
+    },
+    swapchain::{Swapchain, SwapchainFrame},
+    Device,
+};
+
+const SCREENSHOT_TEXEL_SIZE: usize = 4;
+
+// Channel order of swapchain formats which can be encoded into PNG file
+#[derive(Debug, Clone, Copy)]
+enum ChannelOrder {
+    Rgba,
+    Bgra,
+}
+
+impl ChannelOrder {
+    fn get(format: vk::Format) -> Option<Self> {
+        match format {
+            vk::Format::R8G8B8A8_SRGB | vk::Format::R8G8B8A8_UNORM => Some(ChannelOrder::Rgba),
+            vk::Format::B8G8R8A8_SRGB | vk::Format::B8G8R8A8_UNORM => Some(ChannelOrder::Bgra),
+            _ => None,
+        }
+    }
+}
+
+pub struct ScreenshotRequest {
+    path: PathBuf,
+    on_complete: ScreenshotCallback,
+}
+
+impl ScreenshotRequest {
+    pub fn new(path: &Path, on_complete: ScreenshotCallback) -> Self {
+        Self {
+            path: path.to_owned(),
+            on_complete,
+        }
+    }
+
+    fn fail(self, error: impl Into<Box<dyn Error + Send + Sync>>) {
+        (self.on_complete)(Err(error.into()))
+    }
+}
+
+// Staging buffer the presented image is copied into while recording the frame
+pub struct ScreenshotCapture {
+    request: ScreenshotRequest,
+    buffer: PersistentBuffer<DefaultAllocator>,
+    image: vk::Image,
+    extent: vk::Extent2D,
+    order: ChannelOrder,
+}
+
+impl ScreenshotCapture {
+    #[inline]
+    pub fn image(&self) -> vk::Image {
+        self.image
+    }
+
+    #[inline]
+    pub fn extent(&self) -> vk::Extent2D {
+        self.extent
+    }
+
+    #[inline]
+    pub fn buffer_mut(&mut self) -> &mut PersistentBuffer<DefaultAllocator> {
+        &mut self.buffer
+    }
+}
+
+// Capture recorded into submitted frame, `fence` belongs to the frame primary command
+struct PendingScreenshot {
+    capture: ScreenshotCapture,
+    frame_index: usize,
+    fence: vk::Fence,
+}
+
+#[derive(Default)]
+pub struct ScreenshotState {
+    requested: Option<ScreenshotRequest>,
+    pending: Option<PendingScreenshot>,
+}
+
+impl ScreenshotState {
+    pub fn request(&mut self, request: ScreenshotRequest) {
+        if let Some(previous) = self.requested.replace(request) {
+            previous.fail("Screenshot request replaced by a newer one");
+        }
+    }
+
+    // Takes requested capture for the frame being recorded, only one capture
+    // is kept in flight, failures are reported through the request callback
+    pub fn begin_capture<A: AttachmentList>(
+        &mut self,
+        device: &Device,
+        swapchain: &Swapchain<A>,
+        frame: &SwapchainFrame<A>,
+    ) -> Option<ScreenshotCapture> {
+        if self.pending.is_some() {
+            return None;
+        }
+        let request = self.requested.take()?;
+        let surface_properties = &device.physical_device.surface_properties;
+        let transfer_supported = swapchain.is_offscreen()
+            || surface_properties
+                .capabilities
+                .supported_usage_flags
+                .contains(vk::ImageUsageFlags::TRANSFER_SRC);
+        if !transfer_supported {
+            request.fail("Swapchain images do not support transfer source usage");
+            return None;
+        }
+        let format = surface_properties.surface_format.format;
+        let Some(order) = ChannelOrder::get(format) else {
+            request.fail(format!("Unsupported swapchain format {:?}", format));
+            return None;
+        };
+        match create_staging_buffer(device, swapchain.extent) {
+            Ok(buffer) => Some(ScreenshotCapture {
+                request,
+                buffer,
+                image: swapchain.get_image(frame),
+                extent: swapchain.extent,
+                order,
+            }),
+            Err(error) => {
+                request.fail(error.to_string());
+                None
+            }
+        }
+    }
+
+    pub fn submitted(&mut self, capture: ScreenshotCapture, frame_index: usize, fence: vk::Fence) {
+        self.pending = Some(PendingScreenshot {
+            capture,
+            frame_index,
+            fence,
+        });
+    }
+
+    // Finishes pending capture once its frame has been executed, waits only when
+    // the primary command of the frame is about to be reused as `frame_index`
+    pub fn poll(&mut self, device: &Device, frame_index: usize) -> VkResult<()> {
+        let Some(pending) = &self.pending else {
+            return Ok(());
+        };
+        let finished = if pending.frame_index == frame_index {
+            unsafe { device.wait_for_fences(&[pending.fence], true, u64::MAX)? };
+            true
+        } else {
+            unsafe { device.get_fence_status(pending.fence)? }
+        };
+        if finished {
+            if let Some(pending) = self.pending.take() {
+                finish_capture(device, pending.capture);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Destroy for ScreenshotState {
+    type Context<'a> = &'a Device;
+    type DestroyError = Infallible;
+
+    fn destroy<'a>(&mut self, context: Self::Context<'a>) -> DestroyResult<Self> {
+        if let Some(request) = self.requested.take() {
+            request.fail("Renderer destroyed before screenshot was captured");
+        }
+        if let Some(pending) = self.pending.take() {
+            match unsafe { context.wait_for_fences(&[pending.fence], true, u64::MAX) } {
+                Ok(()) => finish_capture(context, pending.capture),
+                Err(error) => {
+                    let ScreenshotCapture {
+                        request,
+                        mut buffer,
+                        ..
+                    } = pending.capture;
+                    let _ = buffer.destroy((context, &RefCell::new(&mut DefaultAllocator {})));
+                    request.fail(format!("Vulkan error: {:?}", error));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn create_staging_buffer(
+    device: &Device,
+    extent: vk::Extent2D,
+) -> VkResult<PersistentBuffer<DefaultAllocator>> {
+    let info = BufferInfo {
+        size: extent.width as usize * extent.height as usize * SCREENSHOT_TEXEL_SIZE,
+        usage: vk::BufferUsageFlags::TRANSFER_DST,
+        sharing_mode: vk::SharingMode::EXCLUSIVE,
+        queue_families: &[Graphics::get_queue_family_index(device)],
+    };
+    let partial = PersistentBufferPartial::prepare(BufferBuilder::new(info), device)?;
+    PersistentBuffer::create(partial, (device, &RefCell::new(&mut DefaultAllocator {})))
+}
+
+// Copies the staging buffer contents and hands PNG encoding over to worker thread
+fn finish_capture(device: &Device, capture: ScreenshotCapture) {
+    let ScreenshotCapture {
+        request,
+        mut buffer,
+        extent,
+        order,
+        ..
+    } = capture;
+    let size = extent.width as usize * extent.height as usize * SCREENSHOT_TEXEL_SIZE;
+    let data =
+        unsafe { std::slice::from_raw_parts(buffer.ptr.unwrap() as *const u8, size).to_vec() };
+    let _ = buffer.destroy((device, &RefCell::new(&mut DefaultAllocator {})));
+    thread::spawn(move || {
+        let ScreenshotRequest { path, on_complete } = request;
+        on_complete(write_png(path, extent, order, data));
+    });
+}
+
+fn write_png(
+    path: PathBuf,
+    extent: vk::Extent2D,
+    order: ChannelOrder,
+    mut data: Vec<u8>,
+) -> ScreenshotResult {
+    if let ChannelOrder::Bgra = order {
+        data.chunks_exact_mut(SCREENSHOT_TEXEL_SIZE)
+            .for_each(|texel| texel.swap(0, 2));
+    }
+    let file = File::create(&path)?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), extent.width, extent.height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&data)?;
+    writer.finish()?;
+    Ok(path)
+}
@@ -0,0 +1,127 @@
+use std::cell::{Cell, RefCell};
+
+use graphics::renderer::stats::RenderStats;
+
+use super::{memory::Resource, Device};
+
+// Commands recorded through `RecordingCommand` since the last reset,
+// together with memory bound to buffers and images which are still alive
+#[derive(Debug, Default)]
+pub struct RenderStatsCounter {
+    recorded: RefCell<RenderStats>,
+    buffer_memory: Cell<u64>,
+    texture_memory: Cell<u64>,
+}
+
+impl RenderStatsCounter {
+    pub fn reset(&self) {
+        *self.recorded.borrow_mut() = RenderStats::default();
+    }
+
+    #[inline]
+    pub fn draw(&self, index_count: u32, instance_count: u32) {
+        let mut stats = self.recorded.borrow_mut();
+        stats.draw_calls += 1;
+        stats.triangles += (index_count / 3) as u64 * instance_count as u64;
+    }
+
+    #[inline]
+    pub fn pipeline_bind(&self) {
+        self.recorded.borrow_mut().pipeline_binds += 1;
+    }
+
+    #[inline]
+    pub fn descriptor_bind(&self) {
+        self.recorded.borrow_mut().descriptor_binds += 1;
+    }
+
+    // Secondary commands executed after this call are counted for the new pass
+    #[inline]
+    pub fn begin_pass(&self) {
+        self.recorded.borrow_mut().secondary_commands.push(0);
+    }
+
+    #[inline]
+    pub fn secondary_command(&self) {
+        if let Some(count) = self.recorded.borrow_mut().secondary_commands.last_mut() {
+            *count += 1;
+        }
+    }
+
+    pub fn memory_bound(&self, resource: Resource, size: u64) {
+        let total = self.memory_total(resource);
+        total.set(total.get() + size);
+    }
+
+    pub fn memory_released(&self, resource: Resource, size: u64) {
+        let total = self.memory_total(resource);
+        total.set(total.get().saturating_sub(size));
+    }
+
+    fn memory_total(&self, resource: Resource) -> &Cell<u64> {
+        match resource {
+            Resource::Buffer(_) => &self.buffer_memory,
+            Resource::Image(_) => &self.texture_memory,
+        }
+    }
+
+    pub fn get(&self) -> RenderStats {
+        RenderStats {
+            buffer_memory: self.buffer_memory.get(),
+            texture_memory: self.texture_memory.get(),
+            ..self.recorded.borrow().clone()
+        }
+    }
+}
+
+impl Device {
+    #[inline]
+    pub fn stats(&self) -> &RenderStatsCounter {
+        &self.stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ash::vk;
+
+    use super::*;
+
+    #[test]
+    fn test_counters_accumulate_until_reset() {
+        let counter = RenderStatsCounter::default();
+        counter.memory_bound(Resource::Buffer(vk::Buffer::null()), 1024);
+        counter.memory_bound(Resource::Image(vk::Image::null()), 4096);
+        counter.begin_pass();
+        counter.secondary_command();
+        counter.begin_pass();
+        // Three objects with 12 triangles each, one of them drawn with two instances
+        for instance_count in [1, 1, 2] {
+            counter.pipeline_bind();
+            counter.descriptor_bind();
+            counter.draw(36, instance_count);
+            counter.secondary_command();
+        }
+        assert_eq!(
+            counter.get(),
+            RenderStats {
+                draw_calls: 3,
+                triangles: 48,
+                pipeline_binds: 3,
+                descriptor_binds: 3,
+                buffer_memory: 1024,
+                texture_memory: 4096,
+                secondary_commands: vec![1, 3],
+            }
+        );
+        counter.reset();
+        counter.memory_released(Resource::Buffer(vk::Buffer::null()), 1024);
+        assert_eq!(
+            counter.get(),
+            RenderStats {
+                texture_memory: 4096,
+                ..Default::default()
+            }
+        );
+    }
+}
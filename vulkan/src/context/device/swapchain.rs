@@ -1,10 +1,10 @@
 use ash::{extensions::khr, vk};
-use std::{convert::Infallible, error::Error, ffi::CStr};
+use std::{cell::Cell, convert::Infallible, error::Error, ffi::CStr};
 use type_kit::{Create, CreateResult, Destroy, DestroyResult};
 
 use crate::context::{
     error::{VkError, VkResult},
-    surface::PhysicalDeviceSurfaceProperties,
+    surface::{PhysicalDeviceSurfaceProperties, Surface},
     Context,
 };
 
@@ -15,6 +15,8 @@ use super::{
         FinishedCommand, Persistent, SubmitSemaphoreState,
     },
     framebuffer::{AttachmentList, Framebuffer, FramebufferHandle},
+    memory::{DefaultAllocator, DeviceLocal},
+    resources::image::Image2D,
     Device,
 };
 #[derive(Debug, Clone, Copy)]
@@ -31,17 +33,53 @@ pub struct SwapchainFrame<A: AttachmentList> {
 }
 
 struct SwapchainImage {
-    _image: vk::Image,
+    image: vk::Image,
     view: vk::ImageView,
 }
 
+type OffscreenImage = Image2D<DeviceLocal, DefaultAllocator>;
+
+// Swapchain no longer matching the surface should be recreated before the next frame,
+// the frame presented into it was submitted nonetheless
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PresentStatus {
+    Presented,
+    OutOfDate,
+}
+
+impl PresentStatus {
+    // Suboptimal swapchain still presents the image, but is recreated
+    // along with the out of date one to match the surface again
+    pub fn from_present_result(result: Result<bool, vk::Result>) -> VkResult<Self> {
+        match result {
+            Ok(false) => Ok(PresentStatus::Presented),
+            Ok(true) | Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => Ok(PresentStatus::OutOfDate),
+            Err(error) => Err(error.into()),
+        }
+    }
+}
+
+enum SwapchainTarget {
+    Surface {
+        images: Vec<SwapchainImage>,
+        handle: vk::SwapchainKHR,
+        loader: khr::Swapchain,
+    },
+    // Images are used in round robin order, without acquire and present operations
+    Offscreen {
+        images: Vec<OffscreenImage>,
+        next_image: Cell<u32>,
+        presented_image: Cell<Option<u32>>,
+    },
+}
+
 pub struct Swapchain<A: AttachmentList> {
     pub num_images: usize,
     pub extent: vk::Extent2D,
     pub framebuffers: Vec<Framebuffer<A>>,
-    images: Vec<SwapchainImage>,
-    handle: vk::SwapchainKHR,
-    loader: khr::Swapchain,
+    // Images can be both source and destination of transfer operations
+    pub transfer_supported: bool,
+    target: SwapchainTarget,
 }
 
 pub const fn required_extensions() -> &'static [&'static CStr; 1] {
@@ -54,13 +92,25 @@ impl<A: AttachmentList> Swapchain<A> {
         &self,
         image_sync: SwapchainImageSync,
     ) -> Result<SwapchainFrame<A>, Box<dyn Error>> {
-        let (image_index, _) = unsafe {
-            self.loader.acquire_next_image(
-                self.handle,
-                u64::MAX,
-                image_sync.draw_ready,
-                vk::Fence::null(),
-            )?
+        let image_index = match &self.target {
+            SwapchainTarget::Surface { handle, loader, .. } => {
+                // Out of date swapchain fails the acquire, suboptimal one
+                // is reported once the frame is presented
+                let (image_index, _) = unsafe {
+                    loader.acquire_next_image(
+                        *handle,
+                        u64::MAX,
+                        image_sync.draw_ready,
+                        vk::Fence::null(),
+                    )?
+                };
+                image_index
+            }
+            SwapchainTarget::Offscreen { next_image, .. } => {
+                let image_index = next_image.get();
+                next_image.set((image_index + 1) % self.num_images as u32);
+                image_index
+            }
         };
         let framebuffer = (&self.framebuffers[image_index as usize]).into();
         let render_area = vk::Rect2D {
@@ -74,6 +124,35 @@ impl<A: AttachmentList> Swapchain<A> {
             image_sync,
         })
     }
+
+    #[inline]
+    pub fn is_offscreen(&self) -> bool {
+        matches!(self.target, SwapchainTarget::Offscreen { .. })
+    }
+
+    // Image the frame is rendered to, left in PRESENT_SRC_KHR layout by the render pass
+    pub fn get_image(&self, frame: &SwapchainFrame<A>) -> vk::Image {
+        let index = frame.image_index as usize;
+        match &self.target {
+            SwapchainTarget::Surface { images, .. } => images[index].image,
+            SwapchainTarget::Offscreen { images, .. } => images[index].image,
+        }
+    }
+
+    // Offscreen image written by the most recently presented frame,
+    // left in PRESENT_SRC_KHR layout by the render pass
+    pub fn presented_image_mut(&mut self) -> Option<&mut OffscreenImage> {
+        match &mut self.target {
+            SwapchainTarget::Offscreen {
+                images,
+                presented_image,
+                ..
+            } => presented_image
+                .get()
+                .map(|index| &mut images[index as usize]),
+            SwapchainTarget::Surface { .. } => None,
+        }
+    }
 }
 
 impl Device {
@@ -82,34 +161,50 @@ impl Device {
         swapchain: &Swapchain<A>,
         command: FinishedCommand<Persistent, Primary, Graphics>,
         frame: SwapchainFrame<A>,
-    ) -> Result<(), Box<dyn Error>> {
+    ) -> Result<PresentStatus, Box<dyn Error>> {
         let SwapchainFrame {
             image_index,
             image_sync,
             ..
         } = frame;
-        unsafe {
-            self.submit_command(
-                command,
-                SubmitSemaphoreState {
-                    semaphores: &[image_sync.draw_ready],
-                    masks: &[vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT],
-                },
-                &[image_sync.draw_finished],
-            )?;
-            swapchain.loader.queue_present(
-                self.device_queues.graphics,
-                &vk::PresentInfoKHR {
-                    wait_semaphore_count: 1,
-                    p_wait_semaphores: [image_sync.draw_finished].as_ptr(),
-                    swapchain_count: 1,
-                    p_swapchains: [swapchain.handle].as_ptr(),
-                    p_image_indices: [image_index].as_ptr(),
-                    ..Default::default()
-                },
-            )?;
+        match &swapchain.target {
+            SwapchainTarget::Surface { handle, loader, .. } => unsafe {
+                self.submit_command(
+                    command,
+                    SubmitSemaphoreState {
+                        semaphores: &[image_sync.draw_ready],
+                        masks: &[vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT],
+                    },
+                    &[image_sync.draw_finished],
+                )?;
+                let result = loader.queue_present(
+                    self.device_queues.graphics,
+                    &vk::PresentInfoKHR {
+                        wait_semaphore_count: 1,
+                        p_wait_semaphores: [image_sync.draw_finished].as_ptr(),
+                        swapchain_count: 1,
+                        p_swapchains: [*handle].as_ptr(),
+                        p_image_indices: [image_index].as_ptr(),
+                        ..Default::default()
+                    },
+                );
+                Ok(PresentStatus::from_present_result(result)?)
+            },
+            SwapchainTarget::Offscreen {
+                presented_image, ..
+            } => {
+                self.submit_command(
+                    command,
+                    SubmitSemaphoreState {
+                        semaphores: &[],
+                        masks: &[],
+                    },
+                    &[],
+                )?;
+                presented_image.set(Some(image_index));
+                Ok(PresentStatus::Presented)
+            }
         }
-        Ok(())
     }
 }
 
@@ -136,10 +231,7 @@ impl Context {
                 None,
             )?;
 
-            Ok(SwapchainImage {
-                _image: image,
-                view,
-            })
+            Ok(SwapchainImage { image, view })
         }
     }
 }
@@ -174,6 +266,14 @@ impl Destroy for SwapchainImageSync {
     }
 }
 
+// Presentation target of the swapchain, window surface along with its properties
+// or offscreen images when the surface is not present
+#[derive(Clone, Copy)]
+pub struct PresentSurface<'a> {
+    pub surface: Option<&'a Surface>,
+    pub properties: &'a PhysicalDeviceSurfaceProperties,
+}
+
 pub trait FramebufferBuilder<A: AttachmentList> {
     fn build(&self, image_view: vk::ImageView, extent: vk::Extent2D) -> VkResult<Framebuffer<A>>;
 }
@@ -189,11 +289,48 @@ where
 }
 
 impl<A: AttachmentList> Create for Swapchain<A> {
-    type Config<'a> = &'a dyn FramebufferBuilder<A>;
+    type Config<'a> = (PresentSurface<'a>, &'a dyn FramebufferBuilder<A>);
     type CreateError = VkError;
 
     fn create<'a, 'b>(config: Self::Config<'a>, context: Self::Context<'b>) -> CreateResult<Self> {
-        let surface_properties = &context.physical_device.surface_properties;
+        let (
+            PresentSurface {
+                surface,
+                properties,
+            },
+            builder,
+        ) = config;
+        let image_extent = properties.get_current_extent();
+        let transfer_usage = vk::ImageUsageFlags::TRANSFER_SRC | vk::ImageUsageFlags::TRANSFER_DST;
+        let transfer_supported = surface.is_none()
+            || properties
+                .capabilities
+                .supported_usage_flags
+                .contains(transfer_usage);
+        let (target, image_views) = match surface {
+            Some(surface) => context.create_surface_target(surface, properties)?,
+            None => context.create_offscreen_target(properties)?,
+        };
+        let framebuffers = image_views
+            .into_iter()
+            .map(|image_view| builder.build(image_view, image_extent))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Swapchain {
+            num_images: framebuffers.len(),
+            extent: image_extent,
+            framebuffers,
+            transfer_supported,
+            target,
+        })
+    }
+}
+
+impl Context {
+    fn create_surface_target(
+        &self,
+        surface: &Surface,
+        surface_properties: &PhysicalDeviceSurfaceProperties,
+    ) -> VkResult<(SwapchainTarget, Vec<vk::ImageView>)> {
         let &PhysicalDeviceSurfaceProperties {
             capabilities:
                 vk::SurfaceCapabilitiesKHR {
@@ -205,7 +342,10 @@ impl<A: AttachmentList> Create for Swapchain<A> {
         } = surface_properties;
         let min_image_count = surface_properties.get_image_count();
         let image_extent = surface_properties.get_current_extent();
-        let queue_family_indices = [Graphics::get_queue_family_index(context)];
+        let queue_family_indices = [Graphics::get_queue_family_index(self)];
+        let image_usage = vk::ImageUsageFlags::COLOR_ATTACHMENT
+            | (surface_properties.capabilities.supported_usage_flags
+                & (vk::ImageUsageFlags::TRANSFER_SRC | vk::ImageUsageFlags::TRANSFER_DST));
         let create_info = vk::SwapchainCreateInfoKHR::builder()
             .pre_transform(current_transform)
             .image_extent(image_extent)
@@ -215,33 +355,51 @@ impl<A: AttachmentList> Create for Swapchain<A> {
             .image_color_space(surface_format.color_space)
             .present_mode(present_mode)
             .image_sharing_mode(vk::SharingMode::EXCLUSIVE)
-            .image_usage(vk::ImageUsageFlags::COLOR_ATTACHMENT)
+            .image_usage(image_usage)
             .queue_family_indices(&queue_family_indices)
             .composite_alpha(vk::CompositeAlphaFlagsKHR::OPAQUE)
             .clipped(true)
             .image_array_layers(1)
-            .surface((&*context.surface).into());
-        let loader: khr::Swapchain = context.load();
+            .surface(surface.into());
+        let loader: khr::Swapchain = self.load();
         let handle = unsafe { loader.create_swapchain(&create_info, None)? };
         let images = unsafe {
             loader
                 .get_swapchain_images(handle)?
                 .into_iter()
-                .map(|image| context.create_swapchain_image(image, surface_format))
+                .map(|image| self.create_swapchain_image(image, surface_format))
                 .collect::<Result<Vec<_>, _>>()?
         };
-        let framebuffers = images
-            .iter()
-            .map(|image| config.build(image.view, image_extent))
+        let image_views = images.iter().map(|image| image.view).collect();
+        Ok((
+            SwapchainTarget::Surface {
+                images,
+                handle,
+                loader,
+            },
+            image_views,
+        ))
+    }
+
+    fn create_offscreen_target(
+        &self,
+        surface_properties: &PhysicalDeviceSurfaceProperties,
+    ) -> VkResult<(SwapchainTarget, Vec<vk::ImageView>)> {
+        let num_images = surface_properties.get_image_count();
+        let images = (0..num_images)
+            .map(|_| {
+                self.create_offscreen_target_image(surface_properties, &mut DefaultAllocator {})
+            })
             .collect::<Result<Vec<_>, _>>()?;
-        Ok(Swapchain {
-            num_images: images.len(),
-            extent: image_extent,
-            images,
-            framebuffers,
-            loader,
-            handle,
-        })
+        let image_views = images.iter().map(|image| image.image_view).collect();
+        Ok((
+            SwapchainTarget::Offscreen {
+                images,
+                next_image: Cell::new(0),
+                presented_image: Cell::new(None),
+            },
+            image_views,
+        ))
     }
 }
 
@@ -253,11 +411,22 @@ impl<A: AttachmentList> Destroy for Swapchain<A> {
         self.framebuffers.iter_mut().for_each(|framebuffer| {
             context.destroy_framebuffer(framebuffer);
         });
-        unsafe {
-            self.images
-                .iter_mut()
-                .for_each(|image| context.destroy_image_view(image.view, None));
-            self.loader.destroy_swapchain(self.handle, None);
+        match &mut self.target {
+            SwapchainTarget::Surface {
+                images,
+                handle,
+                loader,
+            } => unsafe {
+                images
+                    .iter_mut()
+                    .for_each(|image| context.destroy_image_view(image.view, None));
+                loader.destroy_swapchain(*handle, None);
+            },
+            SwapchainTarget::Offscreen { images, .. } => {
+                images.iter_mut().for_each(|image| {
+                    let _ = image.destroy((context, &mut DefaultAllocator {}));
+                });
+            }
         }
         Ok(())
     }
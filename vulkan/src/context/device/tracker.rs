@@ -0,0 +1,96 @@
+use ash::vk::Handle;
+
+use super::Device;
+
+// Handles of live resources created through tracked paths, in release builds
+// registration is a no-op and no handles are stored
+#[derive(Debug, Default)]
+pub struct ResourceTracker {
+    #[cfg(debug_assertions)]
+    live: std::cell::RefCell<std::collections::HashMap<(ash::vk::ObjectType, u64), &'static str>>,
+}
+
+impl ResourceTracker {
+    #[inline]
+    pub fn register<H: Handle>(&self, handle: H, name: &'static str) {
+        #[cfg(debug_assertions)]
+        self.live
+            .borrow_mut()
+            .insert((H::TYPE, handle.as_raw()), name);
+        #[cfg(not(debug_assertions))]
+        let _ = (handle, name);
+    }
+
+    #[inline]
+    pub fn unregister<H: Handle>(&self, handle: H) {
+        #[cfg(debug_assertions)]
+        self.live.borrow_mut().remove(&(H::TYPE, handle.as_raw()));
+        #[cfg(not(debug_assertions))]
+        let _ = handle;
+    }
+
+    pub fn get_live(&self) -> Vec<String> {
+        #[cfg(debug_assertions)]
+        return self
+            .live
+            .borrow()
+            .iter()
+            .map(|((object_type, handle), name)| {
+                format!("{} ({:?} {:#x})", name, object_type, handle)
+            })
+            .collect();
+        #[cfg(not(debug_assertions))]
+        Vec::new()
+    }
+
+    pub fn assert_no_leaks(&self) {
+        let live = self.get_live();
+        if !live.is_empty() {
+            panic!("Leaked Vulkan resources:\n\t{}", live.join("\n\t"));
+        }
+    }
+}
+
+impl Device {
+    #[inline]
+    pub fn tracker(&self) -> &ResourceTracker {
+        &self.tracker
+    }
+
+    // Panics listing resources which were created but not destroyed yet,
+    // intended to be called right before the device is destroyed
+    pub fn assert_no_leaks(&self) {
+        self.tracker.assert_no_leaks();
+    }
+}
+
+#[cfg(all(test, debug_assertions))]
+mod tests {
+    use ash::vk;
+
+    use super::*;
+
+    #[test]
+    fn test_registered_resource_listed_until_unregistered() {
+        let tracker = ResourceTracker::default();
+        let memory = vk::DeviceMemory::from_raw(0x10);
+        let pool = vk::CommandPool::from_raw(0x20);
+        tracker.register(memory, "Leaked memory");
+        tracker.register(pool, "Released pool");
+        tracker.unregister(pool);
+        let live = tracker.get_live();
+        assert_eq!(live.len(), 1);
+        assert!(live[0].starts_with("Leaked memory (DEVICE_MEMORY 0x10)"));
+        tracker.unregister(memory);
+        assert!(tracker.get_live().is_empty());
+        tracker.assert_no_leaks();
+    }
+
+    #[test]
+    #[should_panic(expected = "Leaked memory")]
+    fn test_leaked_resource_fails_assertion() {
+        let tracker = ResourceTracker::default();
+        tracker.register(vk::DeviceMemory::from_raw(0x10), "Leaked memory");
+        tracker.assert_no_leaks();
+    }
+}
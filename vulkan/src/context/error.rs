@@ -1,13 +1,19 @@
 use std::{
+    convert::Infallible,
     error::Error,
     ffi::CStr,
     fmt::{Display, Formatter},
-    io, sync,
+    io,
+    path::{Path, PathBuf},
+    sync,
 };
 
 use ash::vk;
+use graphics::renderer::{ContextId, RenderError};
 use png::{BitDepth, ColorType};
-use type_kit::{GenCollectionError, GuardCollectionError, TypeGuardConversionError};
+use type_kit::{
+    DropGuardError, GenCollectionError, GuardCollectionError, TypeGuardConversionError,
+};
 use winit::raw_window_handle::HandleError;
 
 use super::device::resources::image::ImageCubeFace;
@@ -37,7 +43,14 @@ impl From<AllocError> for AllocatorError {
     }
 }
 
-impl Error for AllocatorError {}
+impl Error for AllocatorError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AllocatorError::LegacyAllocError(error) => Some(error),
+            _ => None,
+        }
+    }
+}
 
 pub type AllocatorResult<T> = Result<T, AllocatorError>;
 
@@ -75,7 +88,14 @@ impl Display for ResourceError {
     }
 }
 
-impl Error for ResourceError {}
+impl Error for ResourceError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ResourceError::AllocatorError(error) => Some(error),
+            _ => None,
+        }
+    }
+}
 
 impl From<AllocatorError> for ResourceError {
     fn from(error: AllocatorError) -> Self {
@@ -109,12 +129,73 @@ impl From<vk::Result> for ResourceError {
 
 pub type ResourceResult<T> = Result<T, ResourceError>;
 
+#[derive(Debug, Clone, Copy)]
+pub enum InterfaceMismatch {
+    VertexInput {
+        location: u32,
+        expected: Option<vk::Format>,
+        found: vk::Format,
+    },
+    PushConstantSize {
+        expected: u32,
+        found: u32,
+    },
+    VertexStride {
+        binding: u32,
+        expected: u32,
+        found: u32,
+    },
+}
+
+impl Display for InterfaceMismatch {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            InterfaceMismatch::VertexInput {
+                location,
+                expected: Some(expected),
+                found,
+            } => write!(
+                f,
+                "vertex input at location {}: expected {:?}, found {:?}",
+                location, expected, found
+            ),
+            InterfaceMismatch::VertexInput {
+                location,
+                expected: None,
+                found,
+            } => write!(
+                f,
+                "vertex input at location {}: not provided by vertex type, found {:?}",
+                location, found
+            ),
+            InterfaceMismatch::PushConstantSize { expected, found } => write!(
+                f,
+                "push constant block: expected at most {} bytes, found {} bytes",
+                expected, found
+            ),
+            InterfaceMismatch::VertexStride {
+                binding,
+                expected,
+                found,
+            } => write!(
+                f,
+                "vertex binding {}: vertex type size is {} bytes, declared stride is {} bytes",
+                binding, expected, found
+            ),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum ShaderError {
     UnknowStage(String),
     InvalidFile(String),
     FileError(io::Error),
+    // Reading shader file or directory failed, path is kept for the message
+    Io { path: PathBuf, source: io::Error },
     VkError(vk::Result),
+    InvalidSpirv(String),
+    InterfaceMismatch(String, Vec<InterfaceMismatch>),
 }
 
 impl Display for ShaderError {
@@ -131,15 +212,43 @@ impl Display for ShaderError {
                 )
             }
             ShaderError::FileError(err) => write!(f, "File error: {}", err),
+            ShaderError::Io { path, source } => {
+                write!(f, "Failed to read {}: {}", path.display(), source)
+            }
             ShaderError::VkError(err) => write!(f, "Vulkan error: {}", err),
+            ShaderError::InvalidSpirv(file) => write!(f, "Invalid SPIR-V module: {}", file),
+            ShaderError::InterfaceMismatch(file, mismatches) => {
+                write!(f, "Shader interface mismatch in {}:", file)?;
+                for mismatch in mismatches {
+                    write!(f, "\n\t{}", mismatch)?;
+                }
+                Ok(())
+            }
         }
     }
 }
 
-impl Error for ShaderError {}
+impl Error for ShaderError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ShaderError::FileError(error) => Some(error),
+            ShaderError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
 
 pub type ShaderResult<T> = Result<T, ShaderError>;
 
+impl ShaderError {
+    pub fn io(path: &Path) -> impl FnOnce(io::Error) -> Self + '_ {
+        move |source| ShaderError::Io {
+            path: path.to_owned(),
+            source,
+        }
+    }
+}
+
 impl From<io::Error> for ShaderError {
     fn from(err: io::Error) -> Self {
         ShaderError::FileError(err)
@@ -156,16 +265,20 @@ impl From<vk::Result> for ShaderError {
 pub enum ImageError {
     FileError(io::Error),
     PngDecoderError(png::DecodingError),
+    PngEncoderError(png::EncodingError),
     UnsupportedFormat(ColorType, BitDepth),
     InvalidCubeMap(String),
     MissingCubeMapData(ImageCubeFace),
     ExhaustedImageRead,
+    // Shared textures are loaded by the context, references to them hold no image data
+    SharedTexture,
 }
 
 impl Display for ImageError {
     fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
         match self {
             ImageError::ExhaustedImageRead => write!(f, "Exhausted image read"),
+            ImageError::SharedTexture => write!(f, "Shared texture reference can not be read"),
             ImageError::MissingCubeMapData(entry) => {
                 write!(f, "Missing cubemap entry: {:?}", entry)
             }
@@ -174,6 +287,7 @@ impl Display for ImageError {
             }
             ImageError::FileError(err) => write!(f, "File error: {}", err),
             ImageError::PngDecoderError(err) => write!(f, "PNG decoder error: {}", err),
+            ImageError::PngEncoderError(err) => write!(f, "PNG encoder error: {}", err),
             ImageError::UnsupportedFormat(color_type, bit_depth) => {
                 write!(
                     f,
@@ -185,7 +299,16 @@ impl Display for ImageError {
     }
 }
 
-impl Error for ImageError {}
+impl Error for ImageError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ImageError::FileError(error) => Some(error),
+            ImageError::PngDecoderError(error) => Some(error),
+            ImageError::PngEncoderError(error) => Some(error),
+            _ => None,
+        }
+    }
+}
 
 impl From<io::Error> for ImageError {
     fn from(err: io::Error) -> Self {
@@ -199,6 +322,12 @@ impl From<png::DecodingError> for ImageError {
     }
 }
 
+impl From<png::EncodingError> for ImageError {
+    fn from(err: png::EncodingError) -> Self {
+        ImageError::PngEncoderError(err)
+    }
+}
+
 pub type ImageResult<T> = Result<T, ImageError>;
 
 #[derive(Debug, Clone, Copy)]
@@ -228,6 +357,25 @@ impl Error for AllocError {}
 
 pub type AllocResult<T> = Result<T, AllocError>;
 
+// Sizes are given in bytes, requested size includes alignment padding
+#[derive(Debug, Clone, Copy)]
+pub struct RangeError {
+    pub requested: usize,
+    pub remaining: usize,
+}
+
+impl Display for RangeError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "Range exhausted: requested {} bytes, {} remaining",
+            self.requested, self.remaining
+        )
+    }
+}
+
+impl Error for RangeError {}
+
 #[derive(Debug)]
 pub enum DeviceNotSuitable {
     InvalidDeviceType,
@@ -235,12 +383,33 @@ pub enum DeviceNotSuitable {
     MissingDepthAndStencilFormat,
     MissingQueueFamilyIndex(&'static str),
     ExtensionNotSupported(&'static CStr),
+    RejectedByPreference,
     VkError(vk::Result),
 }
 
 impl Display for DeviceNotSuitable {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Device not suitable")
+        match self {
+            DeviceNotSuitable::InvalidDeviceType => write!(f, "Unsupported device type"),
+            DeviceNotSuitable::MissingSurfaceSupport => write!(f, "Surface not supported"),
+            DeviceNotSuitable::MissingDepthAndStencilFormat => {
+                write!(f, "No supported depth and stencil format")
+            }
+            DeviceNotSuitable::MissingQueueFamilyIndex(queue) => {
+                write!(f, "Missing {} queue family", queue)
+            }
+            DeviceNotSuitable::ExtensionNotSupported(extension) => {
+                write!(
+                    f,
+                    "Extension not supported: {}",
+                    extension.to_string_lossy()
+                )
+            }
+            DeviceNotSuitable::RejectedByPreference => {
+                write!(f, "Not matching requested device preference")
+            }
+            DeviceNotSuitable::VkError(error) => write!(f, "Vulkan error: {}", error),
+        }
     }
 }
 
@@ -259,14 +428,29 @@ pub enum VkError {
     ShaderError(ShaderError),
     ImageError(ImageError),
     AllocationError(AllocError),
-    NoSuitablePhysicalDevice(Vec<DeviceNotSuitable>),
+    // Name of each rejected physical device along with the rejection reason
+    NoSuitablePhysicalDevice(Vec<(String, DeviceNotSuitable)>),
     ExtensionNotSupported(&'static CStr),
     LayerNotSupported(&'static CStr),
+    FeatureNotSupported(&'static str),
     VkError(vk::Result),
     LoadError(ash::LoadingError),
     WindowError(HandleError),
     // Temporary LockError handling, storing the PoisonError.to_string() to elide the lock Guard type
     LockError(String),
+    MissingResource(&'static str),
+    ConfigError(String),
+    DoubleDestroy,
+    // Handle created by the builder of another context, or of the context it was rebuilt from
+    HandleFromDifferentContext {
+        expected: ContextId,
+        found: ContextId,
+    },
+    // Handle index beyond the resources of its type loaded into the context
+    InvalidHandle {
+        resource: &'static str,
+        index: u32,
+    },
 }
 
 impl Display for VkError {
@@ -279,7 +463,10 @@ impl Display for VkError {
             VkError::ImageError(error) => write!(f, "Image error: {}", error),
             VkError::AllocationError(error) => write!(f, "Allocation error: {}", error),
             VkError::NoSuitablePhysicalDevice(devices) => {
-                write!(f, "No suitable device found: {:?}", devices)
+                write!(f, "No suitable device found")?;
+                devices
+                    .iter()
+                    .try_for_each(|(name, cause)| write!(f, "\n    {}: {}", name, cause))
             }
             VkError::ExtensionNotSupported(extension) => {
                 write!(
@@ -291,14 +478,41 @@ impl Display for VkError {
             VkError::LayerNotSupported(layer) => {
                 write!(f, "Layer not supported: {}", layer.to_string_lossy())
             }
+            VkError::FeatureNotSupported(feature) => {
+                write!(f, "Device feature not supported: {}", feature)
+            }
             VkError::VkError(error) => write!(f, "Vulkan error: {:?}", error),
             VkError::LoadError(error) => write!(f, "Loading error: {:?}", error),
             VkError::WindowError(error) => write!(f, "Window error: {:?}", error),
+            VkError::MissingResource(resource) => write!(f, "Missing resource: {}", resource),
+            VkError::ConfigError(error) => write!(f, "Invalid configuration: {}", error),
+            VkError::DoubleDestroy => write!(f, "Resource was already destroyed"),
+            VkError::HandleFromDifferentContext { expected, found } => write!(
+                f,
+                "Handle from different context used: expected {}, found {}",
+                expected, found
+            ),
+            VkError::InvalidHandle { resource, index } => {
+                write!(f, "No {} loaded at index {}", resource, index)
+            }
         }
     }
 }
 
-impl Error for VkError {}
+impl Error for VkError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            VkError::AllocatorError(error) => Some(error),
+            VkError::ResourceError(error) => Some(error),
+            VkError::ShaderError(error) => Some(error),
+            VkError::ImageError(error) => Some(error),
+            VkError::AllocationError(error) => Some(error),
+            VkError::LoadError(error) => Some(error),
+            VkError::WindowError(error) => Some(error),
+            _ => None,
+        }
+    }
+}
 
 impl From<vk::Result> for VkError {
     fn from(error: vk::Result) -> Self {
@@ -336,6 +550,21 @@ impl<T> From<sync::PoisonError<T>> for VkError {
     }
 }
 
+impl From<Infallible> for VkError {
+    fn from(error: Infallible) -> Self {
+        match error {}
+    }
+}
+
+impl<E: Error + Into<VkError>> From<DropGuardError<E>> for VkError {
+    fn from(error: DropGuardError<E>) -> Self {
+        match error {
+            DropGuardError::DestroyError(error) => error.into(),
+            DropGuardError::DoubleDestroy => VkError::DoubleDestroy,
+        }
+    }
+}
+
 impl From<ShaderError> for VkError {
     fn from(error: ShaderError) -> Self {
         VkError::ShaderError(error)
@@ -355,3 +584,53 @@ impl From<AllocatorError> for VkError {
 }
 
 pub type VkResult<T> = Result<T, VkError>;
+
+// Result codes the caller of the renderer context may react to
+fn vk_render_error(result: vk::Result) -> Option<RenderError> {
+    match result {
+        vk::Result::ERROR_DEVICE_LOST => Some(RenderError::DeviceLost),
+        vk::Result::ERROR_OUT_OF_DEVICE_MEMORY => Some(RenderError::OutOfDeviceMemory),
+        vk::Result::ERROR_OUT_OF_HOST_MEMORY => Some(RenderError::OutOfHostMemory),
+        vk::Result::ERROR_OUT_OF_DATE_KHR => Some(RenderError::SwapchainOutOfDate),
+        _ => None,
+    }
+}
+
+impl From<VkError> for RenderError {
+    fn from(error: VkError) -> Self {
+        let kind = match &error {
+            VkError::VkError(result)
+            | VkError::ResourceError(ResourceError::VkError(result))
+            | VkError::AllocationError(AllocError::VulkanError(result)) => vk_render_error(*result),
+            VkError::AllocationError(AllocError::OutOfMemory) => {
+                Some(RenderError::OutOfDeviceMemory)
+            }
+            VkError::HandleFromDifferentContext { .. } | VkError::InvalidHandle { .. } => {
+                Some(RenderError::InvalidHandle(error.to_string()))
+            }
+            _ => None,
+        };
+        kind.unwrap_or(RenderError::Other(Box::new(error)))
+    }
+}
+
+// Frame recording reports errors of various types boxed, known ones
+// are unboxed so that their Vulkan result codes are not hidden from the caller
+pub fn into_render_error(error: Box<dyn Error>) -> RenderError {
+    let error = match error.downcast::<VkError>() {
+        Ok(error) => return (*error).into(),
+        Err(error) => error,
+    };
+    let error = match error.downcast::<vk::Result>() {
+        Ok(result) => return VkError::from(*result).into(),
+        Err(error) => error,
+    };
+    let error = match error.downcast::<ResourceError>() {
+        Ok(error) => return VkError::from(*error).into(),
+        Err(error) => error,
+    };
+    match error.downcast::<AllocError>() {
+        Ok(error) => VkError::from(*error).into(),
+        Err(error) => RenderError::Other(error),
+    }
+}
@@ -11,6 +11,7 @@ use winit::{
     window::Window,
 };
 
+use super::device::{OutputColorSpace, PresentMode};
 use super::error::{DeviceNotSuitable, VkError, VkResult};
 use super::Instance;
 
@@ -44,12 +45,8 @@ fn create_platform_surface(instance: &Instance, window: &Window) -> VkResult<vk:
 }
 
 #[cfg(not(target_os = "windows"))]
-fn create_platform_surface(
-    entry: &Entry,
-    instance: &Instance,
-    window: &Window,
-) -> VkResult<vk::SurfaceKHR> {
-    compile_error!("Current platform not supported!");
+fn create_platform_surface(_instance: &Instance, _window: &Window) -> VkResult<vk::SurfaceKHR> {
+    unimplemented!()
 }
 
 impl Surface {
@@ -61,7 +58,7 @@ impl Surface {
 
     #[cfg(not(target_os = "windows"))]
     pub fn iterate_required_extensions() -> impl Iterator<Item = &'static CStr> {
-        compile_error!("Current platform not supported!");
+        std::iter::empty()
     }
 }
 
@@ -100,38 +97,63 @@ pub struct PhysicalDeviceSurfaceProperties {
     pub capabilities: vk::SurfaceCapabilitiesKHR,
 }
 
-impl PhysicalDeviceSurfaceProperties {
-    const PREFERRED_SURFACE_FORMATS: &'static [vk::Format] =
-        &[vk::Format::R8G8B8A8_SRGB, vk::Format::B8G8R8A8_SRGB];
+// Formats of the requested color space are preferred, formats of the other one
+// are accepted next, so that sRGB encoding is done manually when needed
+fn select_surface_format(
+    supported: &[vk::SurfaceFormatKHR],
+    color_space: OutputColorSpace,
+) -> Option<vk::SurfaceFormatKHR> {
+    let fallback = match color_space {
+        OutputColorSpace::Srgb => OutputColorSpace::Linear,
+        OutputColorSpace::Linear => OutputColorSpace::Srgb,
+    };
+    color_space
+        .preferred_formats()
+        .iter()
+        .chain(fallback.preferred_formats())
+        .find_map(|&pref| {
+            supported.iter().find(|supported| {
+                supported.format == pref
+                    && supported.color_space == vk::ColorSpaceKHR::SRGB_NONLINEAR
+            })
+        })
+        .or(supported.first())
+        .copied()
+}
+
+fn select_present_mode(
+    supported: &[vk::PresentModeKHR],
+    present_mode: PresentMode,
+) -> vk::PresentModeKHR {
+    present_mode
+        .preferred_modes()
+        .iter()
+        .copied()
+        .find(|mode| supported.contains(mode))
+        .unwrap_or(vk::PresentModeKHR::FIFO)
+}
 
+impl PhysicalDeviceSurfaceProperties {
     pub fn get(
         surface: &Surface,
         physical_device: vk::PhysicalDevice,
         quque_families: &[(vk::QueueFamilyProperties, u32)],
+        color_space: OutputColorSpace,
+        present_mode: PresentMode,
     ) -> Result<Self, DeviceNotSuitable> {
         let surface_formats = unsafe {
             surface
                 .loader
                 .get_physical_device_surface_formats(physical_device, surface.handle)?
         };
-        let surface_format = *Self::PREFERRED_SURFACE_FORMATS
-            .iter()
-            .find_map(|&pref| {
-                surface_formats.iter().find(|supported| {
-                    supported.format == pref
-                        && supported.color_space == vk::ColorSpaceKHR::SRGB_NONLINEAR
-                })
-            })
-            .or(surface_formats.first())
+        let surface_format = select_surface_format(&surface_formats, color_space)
             .ok_or(DeviceNotSuitable::MissingSurfaceSupport)?;
-        let present_mode = unsafe {
+        let present_modes = unsafe {
             surface
                 .loader
                 .get_physical_device_surface_present_modes(physical_device, surface.handle)?
-        }
-        .into_iter()
-        .find(|&present_mode| present_mode == vk::PresentModeKHR::MAILBOX)
-        .unwrap_or(vk::PresentModeKHR::FIFO);
+        };
+        let present_mode = select_present_mode(&present_modes, present_mode);
         let supported_queue_families = HashSet::<u32>::from_iter(
             quque_families
                 .iter()
@@ -166,6 +188,68 @@ impl PhysicalDeviceSurfaceProperties {
         })
     }
 
+    // Properties of offscreen render target used in place of window surface,
+    // any graphics capable queue family can be used for rendering
+    pub fn offscreen(
+        extent: vk::Extent2D,
+        quque_families: &[(vk::QueueFamilyProperties, u32)],
+        color_space: OutputColorSpace,
+    ) -> Result<Self, DeviceNotSuitable> {
+        let supported_queue_families = HashSet::<u32>::from_iter(
+            quque_families
+                .iter()
+                .filter(|(properties, _)| properties.queue_flags.contains(vk::QueueFlags::GRAPHICS))
+                .map(|&(_, queue_family_index)| queue_family_index),
+        );
+        if supported_queue_families.is_empty() {
+            Err(DeviceNotSuitable::MissingQueueFamilyIndex(&"Graphics"))?;
+        }
+        Ok(Self {
+            present_mode: vk::PresentModeKHR::FIFO,
+            surface_format: vk::SurfaceFormatKHR {
+                format: color_space.offscreen_format(),
+                color_space: vk::ColorSpaceKHR::SRGB_NONLINEAR,
+            },
+            supported_queue_families,
+            capabilities: vk::SurfaceCapabilitiesKHR {
+                min_image_count: 1,
+                max_image_count: 0,
+                current_extent: extent,
+                min_image_extent: extent,
+                max_image_extent: extent,
+                max_image_array_layers: 1,
+                ..Default::default()
+            },
+        })
+    }
+
+    // Queries surface capabilities again after window resize, surfaces which
+    // leave current extent undefined take the window extent instead
+    pub fn update_capabilities(
+        &mut self,
+        surface: &Surface,
+        physical_device: vk::PhysicalDevice,
+        window_extent: vk::Extent2D,
+    ) -> VkResult<()> {
+        let mut capabilities = unsafe {
+            surface
+                .loader
+                .get_physical_device_surface_capabilities(physical_device, surface.handle)?
+        };
+        if capabilities.current_extent.width == u32::MAX {
+            capabilities.current_extent = window_extent;
+        }
+        self.capabilities = capabilities;
+        Ok(())
+    }
+
+    // Offscreen targets have no surface to query, images take the requested extent
+    pub fn update_offscreen_extent(&mut self, extent: vk::Extent2D) {
+        self.capabilities.current_extent = extent;
+        self.capabilities.min_image_extent = extent;
+        self.capabilities.max_image_extent = extent;
+    }
+
     pub fn get_current_extent(&self) -> vk::Extent2D {
         let vk::SurfaceCapabilitiesKHR {
             current_extent,
@@ -198,3 +282,73 @@ impl PhysicalDeviceSurfaceProperties {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn surface_format(format: vk::Format) -> vk::SurfaceFormatKHR {
+        vk::SurfaceFormatKHR {
+            format,
+            color_space: vk::ColorSpaceKHR::SRGB_NONLINEAR,
+        }
+    }
+
+    #[test]
+    fn test_surface_format_prefers_requested_color_space() {
+        let supported = [
+            surface_format(vk::Format::R8G8B8A8_UNORM),
+            surface_format(vk::Format::B8G8R8A8_UNORM),
+            surface_format(vk::Format::R8G8B8A8_SRGB),
+            surface_format(vk::Format::B8G8R8A8_SRGB),
+        ];
+        let srgb = select_surface_format(&supported, OutputColorSpace::Srgb);
+        assert_eq!(srgb.map(|f| f.format), Some(vk::Format::B8G8R8A8_SRGB));
+        let linear = select_surface_format(&supported, OutputColorSpace::Linear);
+        assert_eq!(linear.map(|f| f.format), Some(vk::Format::B8G8R8A8_UNORM));
+    }
+
+    #[test]
+    fn test_surface_format_falls_back_to_other_color_space() {
+        let supported = [
+            surface_format(vk::Format::A2B10G10R10_UNORM_PACK32),
+            surface_format(vk::Format::R8G8B8A8_UNORM),
+        ];
+        let srgb = select_surface_format(&supported, OutputColorSpace::Srgb);
+        assert_eq!(srgb.map(|f| f.format), Some(vk::Format::R8G8B8A8_UNORM));
+        assert_eq!(
+            OutputColorSpace::of_format(vk::Format::R8G8B8A8_UNORM),
+            OutputColorSpace::Linear
+        );
+        assert_eq!(select_surface_format(&[], OutputColorSpace::Srgb), None);
+    }
+
+    #[test]
+    fn test_present_mode_selects_closest_supported() {
+        let supported = [vk::PresentModeKHR::FIFO, vk::PresentModeKHR::MAILBOX];
+        assert_eq!(
+            select_present_mode(&supported, PresentMode::Mailbox),
+            vk::PresentModeKHR::MAILBOX
+        );
+        assert_eq!(
+            select_present_mode(&supported, PresentMode::Immediate),
+            vk::PresentModeKHR::MAILBOX
+        );
+        assert_eq!(
+            select_present_mode(&supported, PresentMode::Fifo),
+            vk::PresentModeKHR::FIFO
+        );
+    }
+
+    #[test]
+    fn test_unsupported_present_mode_falls_back_to_fifo() {
+        let supported = [vk::PresentModeKHR::FIFO, vk::PresentModeKHR::FIFO_RELAXED];
+        let selected = select_present_mode(&supported, PresentMode::Mailbox);
+        assert_eq!(selected, vk::PresentModeKHR::FIFO);
+        assert_eq!(PresentMode::of_mode(selected), PresentMode::Fifo);
+        assert_eq!(
+            select_present_mode(&[], PresentMode::Immediate),
+            vk::PresentModeKHR::FIFO
+        );
+    }
+}
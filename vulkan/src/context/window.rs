@@ -0,0 +1,74 @@
+use std::convert::Infallible;
+
+use ash::vk;
+use type_kit::{Create, CreateResult, Destroy, DestroyResult, DropGuard, DropGuardError};
+use winit::window::Window;
+
+use super::{
+    device::swapchain::PresentSurface,
+    error::{VkError, VkResult},
+    surface::{PhysicalDeviceSurfaceProperties, Surface},
+    Context,
+};
+
+// Surface of additional window sharing device and resources of the context,
+// properties are tracked separately as each window is resized independently
+pub struct RenderTargetWindow {
+    surface: DropGuard<Surface>,
+    properties: PhysicalDeviceSurfaceProperties,
+}
+
+fn window_extent(window: &Window) -> vk::Extent2D {
+    let size = window.inner_size();
+    vk::Extent2D {
+        width: size.width,
+        height: size.height,
+    }
+}
+
+impl RenderTargetWindow {
+    #[inline]
+    pub fn present_surface(&self) -> PresentSurface<'_> {
+        PresentSurface {
+            surface: Some(&self.surface),
+            properties: &self.properties,
+        }
+    }
+
+    pub fn update_surface_extent(
+        &mut self,
+        context: &Context,
+        window_extent: vk::Extent2D,
+    ) -> VkResult<()> {
+        context.update_window_surface_properties(&self.surface, &mut self.properties, window_extent)
+    }
+}
+
+impl Create for RenderTargetWindow {
+    type Config<'a> = &'a Window;
+    type CreateError = VkError;
+
+    fn create<'a, 'b>(config: Self::Config<'a>, context: Self::Context<'b>) -> CreateResult<Self> {
+        let mut surface = Surface::create(config, &context.instance)?;
+        match context.get_window_surface_properties(&surface, window_extent(config)) {
+            Ok(properties) => Ok(RenderTargetWindow {
+                surface: DropGuard::new(surface),
+                properties,
+            }),
+            Err(err) => {
+                let _ = surface.destroy(&context.instance);
+                Err(err)
+            }
+        }
+    }
+}
+
+impl Destroy for RenderTargetWindow {
+    type Context<'a> = &'a Context;
+    type DestroyError = DropGuardError<Infallible>;
+
+    fn destroy<'a>(&mut self, context: Self::Context<'a>) -> DestroyResult<Self> {
+        self.surface.destroy(&context.instance)?;
+        Ok(())
+    }
+}
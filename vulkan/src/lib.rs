@@ -2,52 +2,133 @@ pub mod context;
 
 use ash::vk;
 use context::device::memory::DefaultAllocator;
+use context::device::profiler::GpuPassTimings;
 use context::device::renderer::deferred::DeferredRenderer;
 use context::device::resources::{
+    image::{ImageReader, Texture2D, Texture2DPartial},
     MaterialPackList, MaterialPackListBuilder, MaterialPackListPartial, MeshPackList,
-    MeshPackListBuilder, MeshPackListPartial,
+    MeshPackListBuilder, MeshPackListPartial, PartialBuilder, Skybox, SkyboxBuilder, SkyboxPartial,
 };
-use context::device::Device;
-use context::Context;
-use math::types::Matrix4;
+use context::device::screenshot::ScreenshotRequest;
+use context::device::{
+    sampler::SamplerConfig, Device, DeviceConfig, DevicePreference, OutputColorSpace, PresentMode,
+    SampleCountFallback,
+};
+use context::error::{into_render_error, VkError, VkResult};
+use context::{Context, DebugConfig, Severity};
+use math::geometry::Aabb;
+use math::types::{Matrix4, Vector4};
 use type_kit::{Cons, Contains, Create, Destroy, DestroyResult, DropGuard, Marker, Nil};
 
 use context::device::{
-    frame::{Frame, FrameContext},
+    frame::{Frame, FrameContext, FrameTarget},
     memory::{AllocatorCreate, StaticAllocator, StaticAllocatorConfig},
-    pipeline::{GraphicsPipelineListBuilder, GraphicsPipelinePackList},
+    pipeline::{GraphicsPipelineListBuilder, GraphicsPipelinePackList, ShaderLoadConfig},
+    swapchain::PresentStatus,
 };
 use graphics::renderer::{
-    camera::Camera, ContextBuilder, Renderer, RendererBuilder, RendererContext,
+    camera::Camera, light::DirectionalLight, stats::RenderStats, viewport::Rect, ContextBuilder,
+    ContextId, RenderError, Renderer, RendererBuilder, RendererContext, ScreenshotCallback,
 };
 use graphics::{
-    model::{Drawable, Material, MaterialHandle, Mesh, MeshHandle, Vertex},
+    model::{
+        Drawable, Image, ImageColorSpace, Material, MaterialHandle, Mesh, MeshHandle,
+        TextureHandle, Vertex,
+    },
     shader::{ShaderHandle, ShaderType},
 };
 use std::convert::Infallible;
-use std::{cell::RefCell, error::Error, marker::PhantomData, rc::Rc};
-use winit::window::Window;
+use std::{
+    cell::RefCell,
+    error::Error,
+    marker::PhantomData,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::Arc,
+};
+use winit::window::{Window, WindowId};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub struct VulkanRendererConfig {
     pub page_size: vk::DeviceSize,
+    pub shader_hot_reload: bool,
+    pub shader_validation: bool,
+    pub strict_shaders: bool,
+    pub frustum_culling: bool,
+    pub tonemapping: bool,
+    // Frames recorded while previous ones are still executing on the GPU
+    pub frames_in_flight: usize,
+    pub device: DeviceConfig,
+    pub debug: DebugConfig,
 }
 
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct VulkanRendererConfigBuilder {
     page_size: Option<vk::DeviceSize>,
+    shader_hot_reload: bool,
+    shader_validation: bool,
+    strict_shaders: bool,
+    frustum_culling: bool,
+    tonemapping: bool,
+    frames_in_flight: Option<usize>,
+    device: DeviceConfig,
+    debug: DebugConfig,
 }
 
 impl VulkanRendererConfig {
+    // Exposure of the tonemap pass until set through the renderer context
+    pub const DEFAULT_EXPOSURE: f32 = 1.0;
+
     pub fn builder() -> VulkanRendererConfigBuilder {
         VulkanRendererConfigBuilder::default()
     }
 }
 
 impl VulkanRendererConfigBuilder {
-    pub fn build(self) -> Result<VulkanRendererConfig, Box<dyn Error>> {
+    // Upper bound of non-coherent atom size and buffer offset alignments allowed
+    // by the spec, limits are powers of two, so it is a multiple of each of them
+    // on any device. Config is built before the device is selected
+    const PAGE_ALIGNMENT: vk::DeviceSize = 256;
+    const DEFAULT_FRAMES_IN_FLIGHT: usize = 2;
+
+    fn validate_page_size(page_size: Option<vk::DeviceSize>) -> VkResult<vk::DeviceSize> {
+        match page_size {
+            None => Err(VkError::ConfigError("Page size not provided".to_string())),
+            Some(0) => Err(VkError::ConfigError(
+                "Page size must be greater than zero".to_string(),
+            )),
+            Some(page_size) if page_size % Self::PAGE_ALIGNMENT != 0 => {
+                Err(VkError::ConfigError(format!(
+                    "Page size {} is not a multiple of the {} bytes memory alignment",
+                    page_size,
+                    Self::PAGE_ALIGNMENT
+                )))
+            }
+            Some(page_size) => Ok(page_size),
+        }
+    }
+
+    fn validate_frames_in_flight(frames_in_flight: Option<usize>) -> VkResult<usize> {
+        match frames_in_flight {
+            None => Ok(Self::DEFAULT_FRAMES_IN_FLIGHT),
+            Some(0) => Err(VkError::ConfigError(
+                "At least one frame in flight is required".to_string(),
+            )),
+            Some(frames_in_flight) => Ok(frames_in_flight),
+        }
+    }
+
+    pub fn build(self) -> VkResult<VulkanRendererConfig> {
         let config = VulkanRendererConfig {
-            page_size: self.page_size.ok_or("Page size not provided")?,
+            page_size: Self::validate_page_size(self.page_size)?,
+            shader_hot_reload: self.shader_hot_reload,
+            shader_validation: self.shader_validation,
+            strict_shaders: self.strict_shaders,
+            frustum_culling: self.frustum_culling,
+            tonemapping: self.tonemapping,
+            frames_in_flight: Self::validate_frames_in_flight(self.frames_in_flight)?,
+            device: self.device,
+            debug: self.debug,
         };
         Ok(config)
     }
@@ -56,6 +137,109 @@ impl VulkanRendererConfigBuilder {
         self.page_size = Some(size as vk::DeviceSize);
         self
     }
+
+    // Sizes command buffers and per frame uniforms, 2 frames are used by default
+    pub fn with_frames_in_flight(mut self, frames_in_flight: usize) -> Self {
+        self.frames_in_flight = Some(frames_in_flight);
+        self
+    }
+
+    // Checks shader files modification time every frame and recreates modified pipelines
+    pub fn with_shader_hot_reload(mut self, enabled: bool) -> Self {
+        self.shader_hot_reload = enabled;
+        self
+    }
+
+    // Validates shader interfaces against pipeline configs in release builds,
+    // validation is always performed in debug builds
+    pub fn with_shader_validation(mut self, enabled: bool) -> Self {
+        self.shader_validation = enabled;
+        self
+    }
+
+    // Fails context build when any shader fails to load or validate, by default such
+    // shaders are replaced with embedded magenta error shader and a warning is logged
+    pub fn with_strict_shaders(mut self, enabled: bool) -> Self {
+        self.strict_shaders = enabled;
+        self
+    }
+
+    // Skips objects drawn with bounds which lie outside of the camera frustum
+    pub fn with_frustum_culling(mut self, enabled: bool) -> Self {
+        self.frustum_culling = enabled;
+        self
+    }
+
+    // Tonemaps presented frames with compute pass, exposure of the pass
+    // is set per frame through the renderer context
+    pub fn with_tonemapping(mut self, enabled: bool) -> Self {
+        self.tonemapping = enabled;
+        self
+    }
+
+    // Discrete GPU is preferred by default, see Context::enumerate_devices
+    // for the list of devices available for name and index overrides
+    pub fn with_device_preference(mut self, preference: DevicePreference) -> Self {
+        self.device.preference = preference;
+        self
+    }
+
+    // Anisotropic filtering of textures with given max anisotropy, clamped to the device
+    // limit, anisotropy is disabled when None or when not supported by the device
+    pub fn with_anisotropy(mut self, max_anisotropy: Option<f32>) -> Self {
+        self.device.sampler = SamplerConfig {
+            anisotropy: max_anisotropy.is_some(),
+            max_anisotropy: max_anisotropy.unwrap_or(1.0),
+        };
+        self
+    }
+
+    // Multisampling of G-Buffer attachments, highest count supported by the device
+    // is used when the requested one is not, see `VulkanRenderer::sample_count_fallback`
+    pub fn with_sample_count(mut self, sample_count: u32) -> Self {
+        self.device.sample_count = Some(vk::SampleCountFlags::from_raw(sample_count));
+        self
+    }
+
+    // Selects `_SRGB` or `_UNORM` swapchain format, with `Linear` the shading pass
+    // encodes its output manually, passes drawn after it write values unchanged
+    pub fn with_output_color_space(mut self, color_space: OutputColorSpace) -> Self {
+        self.device.output_color_space = color_space;
+        self
+    }
+
+    // Closest supported mode is selected at swapchain creation, with FIFO as the
+    // last resort, mode actually in use is reported by `VulkanRenderer::present_mode`
+    pub fn with_present_mode(mut self, present_mode: PresentMode) -> Self {
+        self.device.present_mode = present_mode;
+        self
+    }
+
+    // Enables validation layers and debug messenger, enabled by default in debug builds
+    pub fn with_validation(mut self, enabled: bool) -> Self {
+        self.debug.validation = enabled;
+        self
+    }
+
+    // Routes validation messages to the application instead of printing them to stdout
+    pub fn with_debug_callback(
+        mut self,
+        f: impl Fn(Severity, &str) + Send + Sync + 'static,
+    ) -> Self {
+        self.debug.callback = Some(Arc::new(f));
+        self
+    }
+
+    // Messages below given severity are not reported by the debug messenger
+    pub fn with_debug_severity(mut self, severity: Severity) -> Self {
+        self.debug.severity = severity;
+        self
+    }
+
+    pub fn with_debug_history_len(mut self, len: usize) -> Self {
+        self.debug.history_len = len;
+        self
+    }
 }
 
 #[derive(Debug)]
@@ -92,6 +276,16 @@ where
             _phantom: PhantomData,
         }
     }
+
+    // Builds renderer without window, rendering into offscreen targets of given extent
+    pub fn build_headless(self, extent: (u32, u32)) -> VkResult<VulkanRenderer> {
+        VulkanRenderer::new_headless(
+            extent,
+            self.config.ok_or(VkError::ConfigError(
+                "Configuration not provided".to_string(),
+            ))?,
+        )
+    }
 }
 
 impl<R: Destroy> RendererBuilder for VulkanRendererBuilder<R>
@@ -110,7 +304,8 @@ where
 pub struct VulkanRenderer {
     context: Rc<RefCell<Context>>,
     renderer: Rc<RefCell<DropGuard<DeferredRenderer<DefaultAllocator>>>>,
-    _config: VulkanRendererConfig,
+    config: VulkanRendererConfig,
+    primary_window: Option<WindowId>,
 }
 
 impl Drop for VulkanRenderer {
@@ -118,10 +313,25 @@ impl Drop for VulkanRenderer {
         let context = self.context.borrow();
         let _ = context.wait_idle();
         let mut renderer = self.renderer.borrow_mut();
-        let _ = renderer.destroy((&*context, &mut DefaultAllocator {}));
+        if !renderer.is_destroyed() {
+            if let Err(err) = renderer.destroy((&*context, &mut DefaultAllocator {})) {
+                eprintln!("Failed to destroy renderer: {}", err);
+            }
+        }
     }
 }
 
+// Context builder resources not described by the material, mesh and shader lists
+struct ResourcePackConfig<'a> {
+    textures: &'a [(Image, ImageColorSpace)],
+    skybox: Option<&'a Path>,
+    id: ContextId,
+    shader_config: ShaderLoadConfig,
+}
+
+type FrameSkybox<R, S> =
+    Skybox<StaticAllocator, <<R as Frame>::Context<S> as FrameContext>::SkyboxPipeline>;
+
 pub struct VulkanResourcePack<
     R: Frame,
     M: MaterialPackList<StaticAllocator>,
@@ -130,7 +340,10 @@ pub struct VulkanResourcePack<
 > {
     materials: M,
     meshes: V,
+    // Textures added to the context builder, shared by materials referencing them
+    textures: Vec<Texture2D<StaticAllocator>>,
     renderer_context: R::Context<S>,
+    skybox: Option<DropGuard<FrameSkybox<R, S>>>,
     allocator: StaticAllocator,
 }
 
@@ -147,26 +360,64 @@ impl<
         materials: &impl MaterialPackListBuilder<Pack<StaticAllocator> = M>,
         meshes: &impl MeshPackListBuilder<Pack<StaticAllocator> = V>,
         pipelines: &impl GraphicsPipelineListBuilder<Pack = S>,
-    ) -> Result<Self, Box<dyn Error>> {
+        config: ResourcePackConfig,
+    ) -> VkResult<Self> {
+        let ResourcePackConfig {
+            textures,
+            skybox,
+            id,
+            shader_config,
+        } = config;
         let mut config = StaticAllocatorConfig::create(&context);
+        let skybox = skybox.map(SkyboxBuilder::new);
+        let skybox = skybox
+            .as_ref()
+            .map(|skybox| SkyboxPartial::prepare(skybox, context))
+            .transpose()?;
+        if let Some(skybox) = &skybox {
+            skybox
+                .requirements()
+                .for_each(|req| config.add_allocation(req));
+        }
         let meshes = meshes.prepare(&context)?;
         meshes
             .get_memory_requirements()
             .into_iter()
             .for_each(|req| config.add_allocation(req));
-        let materials = materials.prepare(&context)?;
+        let textures = textures
+            .iter()
+            .map(|(image, color_space)| {
+                let reader = ImageReader::image(image)?.with_color_space(*color_space);
+                Texture2DPartial::prepare(reader, context)
+            })
+            .collect::<VkResult<Vec<_>>>()?;
+        textures
+            .iter()
+            .flat_map(|texture| texture.requirements())
+            .for_each(|req| config.add_allocation(req));
+        let materials = materials.prepare(context, id)?;
         materials
             .get_memory_requirements()
             .into_iter()
             .for_each(|req| config.add_allocation(req));
         let mut allocator = StaticAllocator::create(&context, &config)?;
-        let materials = materials.allocate(&context, &mut allocator)?;
+        let textures = textures
+            .into_iter()
+            .map(|texture| Texture2D::create(texture, (context, &mut allocator)))
+            .collect::<VkResult<Vec<_>>>()?;
+        let materials = materials.allocate(context, &mut allocator, &textures)?;
         let meshes = meshes.allocate(&context, &mut allocator)?;
-        let renderer_context = renderer.load_context(&context, pipelines)?;
+        let skybox = skybox
+            .map(|skybox| Skybox::create(skybox, (context, &mut allocator)))
+            .transpose()?
+            .map(DropGuard::new);
+        let renderer_context = renderer.load_context(context, pipelines, shader_config)?;
         Ok(Self {
             materials,
             meshes,
+            textures,
             renderer_context,
+            skybox,
             allocator,
         })
     }
@@ -188,7 +439,11 @@ impl<
         let destroy_context = (device, &cell_allocator);
         let _ = self.materials.destroy(destroy_context);
         let _ = self.meshes.destroy(destroy_context);
+        for texture in self.textures.iter_mut() {
+            let _ = texture.destroy((device, &mut self.allocator));
+        }
         let _ = self.renderer_context.destroy(context);
+        let _ = self.skybox.destroy((device, &mut self.allocator));
         self.allocator.destroy(context);
         Ok(())
     }
@@ -202,18 +457,125 @@ pub struct VulkanRendererContext<
 > {
     context: Rc<RefCell<Context>>,
     resources: VulkanResourcePack<R, M, V, S>,
+    shader_hot_reload: bool,
+    frustum_culling: bool,
+    tonemapping: bool,
+    // Culling frustum of the current view and of the camera passed to `begin_frame`,
+    // restored once the view ends
+    frustum: [Vector4; 6],
+    frame_frustum: [Vector4; 6],
+    primary_window: Option<WindowId>,
+    stats: RenderStats,
+    // Id of the builder the context was built with, handles are checked against it
+    // by draws in debug builds
+    id: ContextId,
 }
 
 impl VulkanRenderer {
-    pub fn new(window: &Window, config: VulkanRendererConfig) -> Result<Self, Box<dyn Error>> {
-        let context = Context::build(window)?;
-        let renderer = DeferredRenderer::create((), (&context, &mut DefaultAllocator {}))?;
+    // Recreates swapchain and size dependent render targets of the primary window,
+    // or offscreen images of the new extent, pipelines use dynamic viewport state
+    // and are kept unchanged
+    pub fn handle_resize(&mut self, new_extent: vk::Extent2D) -> VkResult<()> {
+        if new_extent.width == 0 || new_extent.height == 0 {
+            // Minimized window, swapchain is kept until it is restored
+            return Ok(());
+        }
+        let mut context = self.context.borrow_mut();
+        context.wait_idle()?;
+        context.update_surface_extent(new_extent)?;
+        self.renderer
+            .borrow_mut()
+            .recreate_frame_data(&context, &mut DefaultAllocator {})?;
+        Ok(())
+    }
+
+    // Registers additional window drawn with resources of the renderer contexts,
+    // each window has its own swapchain and is resized independently
+    pub fn add_window(&mut self, window: &Window) -> VkResult<WindowId> {
+        let context = self.context.borrow();
+        self.renderer
+            .borrow_mut()
+            .add_window(&context, window, &mut DefaultAllocator {})?;
+        Ok(window.id())
+    }
+
+    pub fn remove_window(&mut self, window: WindowId) -> VkResult<()> {
+        let context = self.context.borrow();
+        context.wait_idle()?;
+        self.renderer
+            .borrow_mut()
+            .remove_window(&context, window, &mut DefaultAllocator {})
+    }
+
+    pub fn handle_window_resize(
+        &mut self,
+        window: WindowId,
+        new_extent: vk::Extent2D,
+    ) -> VkResult<()> {
+        if Some(window) == self.primary_window {
+            return self.handle_resize(new_extent);
+        }
+        if new_extent.width == 0 || new_extent.height == 0 {
+            return Ok(());
+        }
+        let context = self.context.borrow();
+        context.wait_idle()?;
+        self.renderer.borrow_mut().recreate_window_frame_data(
+            &context,
+            window,
+            new_extent,
+            &mut DefaultAllocator {},
+        )
+    }
+
+    pub fn new(window: &Window, config: VulkanRendererConfig) -> VkResult<Self> {
+        let context = Context::build(window, config.debug.clone(), &config.device)?;
+        let mut renderer = Self::with_context(context, config)?;
+        renderer.primary_window = Some(window.id());
+        Ok(renderer)
+    }
+
+    // Renderer without window, frames are rendered into offscreen color images
+    // which can be read back with `read_back_frame` after `end_frame`
+    pub fn new_offscreen(extent: vk::Extent2D, config: VulkanRendererConfig) -> VkResult<Self> {
+        let context = Context::build_offscreen(extent, config.debug.clone(), &config.device)?;
+        Self::with_context(context, config)
+    }
+
+    pub fn new_headless(extent: (u32, u32), config: VulkanRendererConfig) -> VkResult<Self> {
+        let (width, height) = extent;
+        Self::new_offscreen(vk::Extent2D { width, height }, config)
+    }
+
+    fn with_context(context: Context, config: VulkanRendererConfig) -> VkResult<Self> {
+        let renderer = DeferredRenderer::create(
+            config.frames_in_flight,
+            (&context, &mut DefaultAllocator {}),
+        )?;
         Ok(Self {
             context: Rc::new(RefCell::new(context)),
             renderer: Rc::new(RefCell::new(DropGuard::new(renderer))),
-            _config: config,
+            config,
+            primary_window: None,
         })
     }
+
+    #[inline]
+    pub fn sample_count_fallback(&self) -> Option<SampleCountFallback> {
+        self.context.borrow().sample_count_fallback()
+    }
+
+    #[inline]
+    pub fn present_mode(&self) -> PresentMode {
+        self.context.borrow().present_mode()
+    }
+
+    // Returns tightly packed RGBA8 rows of the last frame rendered in offscreen mode
+    pub fn read_back_frame(&self) -> VkResult<Vec<u8>> {
+        let context = self.context.borrow();
+        context.wait_idle()?;
+        self.renderer.borrow_mut().read_back_frame(&context)
+    }
 }
 
 impl<
@@ -230,7 +592,118 @@ impl<
     }
 }
 
-impl Renderer for VulkanRenderer {}
+impl<
+        R: Frame,
+        M: MaterialPackList<StaticAllocator>,
+        V: MeshPackList<StaticAllocator>,
+        S: GraphicsPipelinePackList,
+    > VulkanRendererContext<R, M, V, S>
+{
+    // Waits for submitted frames and returns tightly packed RGBA8 rows
+    // of the last frame, available only for headless renderers
+    pub fn read_back_frame(&self) -> VkResult<Vec<u8>> {
+        let context = self.context.borrow();
+        context.wait_idle()?;
+        self.resources.renderer_context.read_back_frame(&context)
+    }
+
+    // GPU durations of the G-Buffer and lighting passes of the primary window,
+    // read back with a delay of frames in flight, None until the first read back
+    // or when the device does not support timestamp queries
+    pub fn gpu_pass_timings(&self) -> Option<GpuPassTimings> {
+        self.resources.renderer_context.gpu_pass_timings()
+    }
+
+    // Handles index packs of the context blindly, so mixing them up between contexts
+    // is caught in debug builds before the draw is recorded
+    fn validate_handles<T: ShaderType, D: Drawable<Material = T::Material, Vertex = T::Vertex>>(
+        &self,
+        shader: ShaderHandle<T>,
+        drawable: &D,
+    ) -> Result<(), VkError> {
+        if cfg!(debug_assertions) {
+            validate_context(self.id, shader.context())?;
+            validate_context(self.id, drawable.mesh().context())?;
+            validate_context(self.id, drawable.material().context())?;
+        }
+        // Shader pipeline is looked up by the handle index while recording the draw
+        if !self.resources.renderer_context.contains_shader(shader) {
+            return Err(VkError::InvalidHandle {
+                resource: "shader",
+                index: shader.index(),
+            });
+        }
+        Ok(())
+    }
+
+    fn begin_frame_for_target<C: Camera + ?Sized>(
+        &mut self,
+        target: FrameTarget,
+        camera: &C,
+    ) -> Result<(), RenderError> {
+        graphics::profile_scope!("begin_frame");
+        let context = self.context.borrow();
+        if self.shader_hot_reload {
+            for err in self
+                .resources
+                .renderer_context
+                .reload_modified_shaders(&context)
+            {
+                eprintln!("Shader reload failed: {}", err);
+            }
+        }
+        let camera_matrices = camera.get_matrices();
+        self.frustum = camera_matrices.frustum_planes();
+        self.frame_frustum = self.frustum;
+        context.stats().reset();
+        self.resources
+            .renderer_context
+            .begin_frame(
+                &context,
+                target,
+                &camera_matrices,
+                self.resources.skybox.as_deref(),
+            )
+            .map_err(into_render_error)
+    }
+
+    // Re-reads SPIR-V files of the shader directory and swaps its pipeline in place,
+    // previously obtained shader handle remains valid after the reload
+    pub fn reload_shader<T: ShaderType>(
+        &mut self,
+        handle: ShaderHandle<T>,
+    ) -> Result<(), Box<dyn Error>> {
+        validate_context(self.id, handle.context())?;
+        let context = self.context.borrow();
+        self.resources
+            .renderer_context
+            .reload_shader(&context, handle)?;
+        Ok(())
+    }
+}
+
+impl Renderer for VulkanRenderer {
+    fn resize(&mut self, width: u32, height: u32) -> Result<(), Box<dyn Error>> {
+        Ok(self.handle_resize(vk::Extent2D { width, height })?)
+    }
+
+    fn add_window(&mut self, window: &Window) -> Result<WindowId, Box<dyn Error>> {
+        Ok(VulkanRenderer::add_window(self, window)?)
+    }
+
+    fn remove_window(&mut self, window: WindowId) -> Result<(), Box<dyn Error>> {
+        Ok(VulkanRenderer::remove_window(self, window)?)
+    }
+
+    fn resize_window(
+        &mut self,
+        window: WindowId,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Box<dyn Error>> {
+        Ok(self.handle_window_resize(window, vk::Extent2D { width, height })?)
+    }
+}
 
 #[derive(Debug)]
 pub struct VulkanContextBuilder<
@@ -242,6 +715,9 @@ pub struct VulkanContextBuilder<
     shaders: S,
     materials: M,
     meshes: V,
+    textures: Vec<(Image, ImageColorSpace)>,
+    skybox: Option<PathBuf>,
+    id: ContextId,
     _phantom: PhantomData<R>,
 }
 
@@ -258,17 +734,50 @@ impl<S: GraphicsPipelineListBuilder, M: MaterialPackListBuilder, V: MeshPackList
     >;
 
     fn build(self, renderer: &Self::Renderer) -> Result<Self::Context, Box<dyn Error>> {
+        let shader_config = ShaderLoadConfig {
+            validate: cfg!(debug_assertions) || renderer.config.shader_validation,
+            strict: renderer.config.strict_shaders,
+        };
         let mut context = renderer.context.borrow_mut();
-        let resources = VulkanResourcePack::load(
+        let mut resources = VulkanResourcePack::load(
             &mut context,
             &renderer.renderer,
             &self.materials,
             &self.meshes,
             &self.shaders,
+            ResourcePackConfig {
+                textures: &self.textures,
+                skybox: self.skybox.as_deref(),
+                id: self.id,
+                shader_config,
+            },
         )?;
+        if renderer.config.tonemapping {
+            resources
+                .renderer_context
+                .set_tonemapping(Some(VulkanRendererConfig::DEFAULT_EXPOSURE));
+        }
         Ok(VulkanRendererContext {
             context: renderer.context.clone(),
             resources,
+            shader_hot_reload: renderer.config.shader_hot_reload,
+            frustum_culling: renderer.config.frustum_culling,
+            tonemapping: renderer.config.tonemapping,
+            frustum: [Vector4::zero(); 6],
+            frame_frustum: [Vector4::zero(); 6],
+            primary_window: renderer.primary_window,
+            stats: RenderStats::default(),
+            id: self.id,
+        })
+    }
+
+    fn with_skybox(self, path: &Path) -> Result<Self, Box<dyn Error>> {
+        // Face images are opened only to validate the directory,
+        // they are read again when the context is built
+        ImageReader::cube(path)?;
+        Ok(Self {
+            skybox: Some(path.to_owned()),
+            ..self
         })
     }
 }
@@ -294,6 +803,9 @@ impl
             shaders: Nil::new(),
             materials: Nil::new(),
             meshes: Nil::new(),
+            textures: Vec::new(),
+            skybox: None,
+            id: ContextId::next(),
             _phantom: PhantomData,
         }
     }
@@ -305,6 +817,14 @@ fn push_and_get_index<V>(vec: &mut Vec<V>, value: V) -> u32 {
     index.try_into().unwrap()
 }
 
+fn validate_context(expected: ContextId, found: ContextId) -> Result<(), VkError> {
+    if found == expected {
+        Ok(())
+    } else {
+        Err(VkError::HandleFromDifferentContext { expected, found })
+    }
+}
+
 impl<
         R: Frame,
         S: GraphicsPipelineListBuilder,
@@ -320,6 +840,9 @@ impl<
             },
             meshes: self.meshes,
             shaders: self.shaders,
+            textures: self.textures,
+            skybox: self.skybox,
+            id: self.id,
             _phantom: PhantomData,
         }
     }
@@ -332,6 +855,9 @@ impl<
             },
             materials: self.materials,
             shaders: self.shaders,
+            textures: self.textures,
+            skybox: self.skybox,
+            id: self.id,
             _phantom: PhantomData,
         }
     }
@@ -346,22 +872,37 @@ impl<
             },
             materials: self.materials,
             meshes: self.meshes,
+            textures: self.textures,
+            skybox: self.skybox,
+            id: self.id,
             _phantom: PhantomData,
         }
     }
 
+    // Texture shared by materials referencing it with `Image::Texture`,
+    // `color_space` applies to all of them regardless of the image slot they use
+    pub fn add_texture(&mut self, image: Image, color_space: ImageColorSpace) -> TextureHandle {
+        TextureHandle::new(
+            push_and_get_index(&mut self.textures, (image, color_space)),
+            self.id,
+        )
+    }
+
     pub fn add_material<N: Material, T: Marker>(&mut self, material: N) -> MaterialHandle<N>
     where
         M: Contains<Vec<N>, T>,
     {
-        MaterialHandle::new(push_and_get_index(self.materials.get_mut(), material))
+        MaterialHandle::new(
+            push_and_get_index(self.materials.get_mut(), material),
+            self.id,
+        )
     }
 
     pub fn add_mesh<N: Vertex, T: Marker>(&mut self, mesh: Mesh<N>) -> MeshHandle<N>
     where
         V: Contains<Vec<Mesh<N>>, T>,
     {
-        MeshHandle::new(push_and_get_index(self.meshes.get_mut(), mesh))
+        MeshHandle::new(push_and_get_index(self.meshes.get_mut(), mesh), self.id)
     }
 
     pub fn add_shader<N: ShaderType + Into<R::Shader<N>>, T: Marker>(
@@ -371,7 +912,10 @@ impl<
     where
         S: Contains<Vec<R::Shader<N>>, T>,
     {
-        ShaderHandle::new(push_and_get_index(self.shaders.get_mut(), shader.into()))
+        ShaderHandle::new(
+            push_and_get_index(self.shaders.get_mut(), shader.into()),
+            self.id,
+        )
     }
 }
 
@@ -387,19 +931,67 @@ impl<
     type Materials = M;
     type Meshes = V;
 
-    fn begin_frame<C: Camera>(&mut self, camera: &C) -> Result<(), Box<dyn Error>> {
+    fn begin_frame<C: Camera>(&mut self, camera: &C) -> Result<(), RenderError> {
+        self.begin_frame_for_target(FrameTarget::Primary, camera)
+    }
+
+    fn begin_frame_for<C: Camera + ?Sized>(
+        &mut self,
+        window: WindowId,
+        camera: &C,
+    ) -> Result<(), RenderError> {
+        let target = match self.primary_window {
+            Some(primary) if primary == window => FrameTarget::Primary,
+            _ => FrameTarget::Window(window),
+        };
+        self.begin_frame_for_target(target, camera)
+    }
+
+    fn end_frame(&mut self) -> Result<(), RenderError> {
+        graphics::profile_scope!("end_frame");
+        let context = self.context.borrow();
+        let (fence, status) = self
+            .resources
+            .renderer_context
+            .end_frame(&context)
+            .map_err(into_render_error)?;
+        self.stats = context.stats().get();
+        context.tick_destruction_queue(fence)?;
+        match status {
+            PresentStatus::Presented => Ok(()),
+            PresentStatus::OutOfDate => Err(RenderError::SwapchainOutOfDate),
+        }
+    }
+
+    fn stats(&self) -> &RenderStats {
+        &self.stats
+    }
+
+    fn begin_view<C: Camera + ?Sized>(
+        &mut self,
+        viewport: Rect,
+        camera: &C,
+    ) -> Result<(), RenderError> {
         let context = self.context.borrow();
         let camera_matrices = camera.get_matrices();
+        self.frustum = camera_matrices.frustum_planes();
         self.resources
             .renderer_context
-            .begin_frame(&context, &camera_matrices)?;
-        Ok(())
+            .begin_view(
+                &context,
+                viewport,
+                &camera_matrices,
+                self.resources.skybox.as_deref(),
+            )
+            .map_err(into_render_error)
     }
 
-    fn end_frame(&mut self) -> Result<(), Box<dyn Error>> {
-        let context = self.context.borrow();
-        self.resources.renderer_context.end_frame(&context)?;
-        Ok(())
+    fn end_view(&mut self) -> Result<(), RenderError> {
+        self.frustum = self.frame_frustum;
+        self.resources
+            .renderer_context
+            .end_view()
+            .map_err(into_render_error)
     }
 
     fn draw<T: ShaderType, D: Drawable<Material = T::Material, Vertex = T::Vertex>>(
@@ -407,7 +999,9 @@ impl<
         shader: ShaderHandle<T>,
         drawable: &D,
         transform: &Matrix4,
-    ) -> Result<(), Box<dyn Error>> {
+    ) -> Result<(), RenderError> {
+        graphics::profile_scope!("draw");
+        self.validate_handles(shader, drawable)?;
         self.resources.renderer_context.draw(
             shader,
             drawable,
@@ -417,4 +1011,718 @@ impl<
         );
         Ok(())
     }
+
+    fn draw_batch<T: ShaderType, D: Drawable<Material = T::Material, Vertex = T::Vertex>>(
+        &mut self,
+        shader: ShaderHandle<T>,
+        items: &[(&D, Matrix4)],
+    ) -> Result<(), RenderError> {
+        graphics::profile_scope!("draw_batch");
+        for (drawable, _) in items {
+            self.validate_handles(shader, *drawable)?;
+        }
+        self.resources.renderer_context.draw_batch(
+            shader,
+            items,
+            &self.resources.materials,
+            &self.resources.meshes,
+        );
+        Ok(())
+    }
+
+    fn draw_with_bounds<T: ShaderType, D: Drawable<Material = T::Material, Vertex = T::Vertex>>(
+        &mut self,
+        shader: ShaderHandle<T>,
+        drawable: &D,
+        transform: &Matrix4,
+        bounds: &Aabb,
+    ) -> Result<(), RenderError> {
+        if self.frustum_culling && !bounds.intersects_frustum(&self.frustum) {
+            return Ok(());
+        }
+        self.draw(shader, drawable, transform)
+    }
+
+    fn set_directional_light(&mut self, light: Option<DirectionalLight>) {
+        self.resources.renderer_context.set_directional_light(light);
+    }
+
+    fn draw_text(&mut self, x: f32, y: f32, text: &str, color: Vector4) {
+        self.resources.renderer_context.draw_text(x, y, text, color);
+    }
+
+    fn set_exposure(&mut self, exposure: f32) {
+        if self.tonemapping {
+            self.resources
+                .renderer_context
+                .set_tonemapping(Some(exposure));
+        }
+    }
+
+    fn capture_screenshot(&mut self, path: &Path, on_complete: ScreenshotCallback) {
+        self.resources
+            .renderer_context
+            .capture_screenshot(ScreenshotRequest::new(path, on_complete));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use context::{
+        device::{
+            frame::FramePool,
+            renderer::deferred::{DeferredRendererContext, DeferredShader},
+        },
+        error::{ImageError, ShaderError},
+    };
+    use graphics::{
+        model::{CommonVertex, EmptyMaterial, Model},
+        renderer::camera::{orbit::OrbitCameraBuilder, CameraBuilder},
+        shader::Shader,
+    };
+    use math::types::Vector3;
+    use physics::shape::Cube;
+
+    use super::*;
+
+    // Writes single pixel face images of uniform color into fresh directory
+    fn write_skybox_faces(name: &str, faces: &[&str], color: [u8; 4]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("r_phy_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        for face in faces {
+            let file = std::fs::File::create(path.join(format!("{}.png", face))).unwrap();
+            let mut encoder = png::Encoder::new(file, 1, 1);
+            encoder.set_color(png::ColorType::Rgba);
+            encoder.set_depth(png::BitDepth::Eight);
+            let mut writer = encoder.write_header().unwrap();
+            writer.write_image_data(&color).unwrap();
+            writer.finish().unwrap();
+        }
+        path
+    }
+
+    const SKYBOX_FACES: [&str; 6] = ["right", "left", "top", "bottom", "front", "back"];
+
+    #[test]
+    fn test_skybox_with_missing_face_rejected() {
+        let complete = write_skybox_faces("skybox_complete", &SKYBOX_FACES, [0, 0, 0, 255]);
+        let partial = write_skybox_faces("skybox_partial", &SKYBOX_FACES[..5], [0, 0, 0, 255]);
+        assert!(VulkanContextBuilder::new().with_skybox(&complete).is_ok());
+        let error = VulkanContextBuilder::new()
+            .with_skybox(&partial)
+            .err()
+            .unwrap();
+        assert!(matches!(
+            error.downcast_ref::<ImageError>(),
+            Some(ImageError::MissingCubeMapData(_))
+        ));
+        let missing = VulkanContextBuilder::new().with_skybox(&complete.join("missing"));
+        assert!(missing.is_err());
+    }
+
+    #[test]
+    fn test_zero_page_size_rejected() {
+        let result = VulkanRendererConfig::builder().with_page_size(0).build();
+        assert!(
+            matches!(result, Err(VkError::ConfigError(message)) if message.contains("greater than zero"))
+        );
+    }
+
+    #[test]
+    fn test_missing_shader_directory_reports_path() {
+        let path = Path::new("_resources/shaders/spv/missing");
+        let result = context::device::pipeline::validate_shader_interface::<
+            DeferredShader<Shader<CommonVertex, EmptyMaterial>>,
+        >(path);
+        let Err(ShaderError::Io {
+            path: error_path,
+            source,
+        }) = result
+        else {
+            panic!("Expected shader io error");
+        };
+        assert_eq!(error_path, path);
+        assert_eq!(source.kind(), std::io::ErrorKind::NotFound);
+        // Path survives conversion into the error returned from the public api
+        let error = VkError::from(ShaderError::Io {
+            path: error_path,
+            source,
+        });
+        assert!(error.to_string().contains("_resources/shaders/spv/missing"));
+        assert!(error.source().is_some());
+    }
+
+    #[test]
+    fn test_aligned_page_size_accepted() {
+        let config = VulkanRendererConfig::builder()
+            .with_page_size(64 * 1024 * 1024)
+            .build()
+            .unwrap();
+        assert_eq!(config.page_size, 64 * 1024 * 1024);
+    }
+
+    #[test]
+    fn test_unaligned_page_size_rejected() {
+        let result = VulkanRendererConfig::builder().with_page_size(1000).build();
+        assert!(matches!(result, Err(VkError::ConfigError(_))));
+    }
+
+    #[test]
+    fn test_frames_in_flight_default() {
+        let config = VulkanRendererConfig::builder()
+            .with_page_size(64 * 1024 * 1024)
+            .build()
+            .unwrap();
+        assert_eq!(config.frames_in_flight, 2);
+    }
+
+    #[test]
+    fn test_zero_frames_in_flight_rejected() {
+        let result = VulkanRendererConfig::builder()
+            .with_page_size(64 * 1024 * 1024)
+            .with_frames_in_flight(0)
+            .build();
+        assert!(matches!(result, Err(VkError::ConfigError(_))));
+    }
+
+    #[test]
+    fn test_frames_in_flight_sizes_primary_commands() {
+        let config = VulkanRendererConfig::builder()
+            .with_page_size(64 * 1024 * 1024)
+            .with_frames_in_flight(3)
+            .build()
+            .unwrap();
+        let (primary, secondary) =
+            FramePool::<DeferredRendererContext<DefaultAllocator, Nil>>::command_buffer_counts(
+                config.frames_in_flight,
+            );
+        assert_eq!(primary, 3);
+        assert_eq!(secondary % primary, 0);
+    }
+
+    #[test]
+    fn test_handle_from_different_context_rejected() {
+        let mut first = VulkanContextBuilder::new().with_material_type::<EmptyMaterial>();
+        let mut second = VulkanContextBuilder::new().with_material_type::<EmptyMaterial>();
+        let first_handle = first.add_material(EmptyMaterial::default());
+        let second_handle = second.add_material(EmptyMaterial::default());
+        // Handles share the index, only the context id tells them apart
+        assert_eq!(first_handle.index(), second_handle.index());
+        assert!(validate_context(first.id, first_handle.context()).is_ok());
+        assert!(matches!(
+            validate_context(second.id, first_handle.context()),
+            Err(VkError::HandleFromDifferentContext { expected, found })
+                if expected == second.id && found == first.id
+        ));
+    }
+
+    #[test]
+    fn test_stale_handle_rejected_after_rebuild() {
+        let mut builder = VulkanContextBuilder::new().with_mesh_type::<CommonVertex>();
+        let stale = builder.add_mesh::<CommonVertex, _>(Cube::new(1.0).into());
+        // Type changes of the builder keep its id, so handles stay valid until rebuild
+        let builder = builder.with_material_type::<EmptyMaterial>();
+        assert!(validate_context(builder.id, stale.context()).is_ok());
+        let rebuilt = VulkanContextBuilder::new().with_mesh_type::<CommonVertex>();
+        assert!(matches!(
+            validate_context(rebuilt.id, stale.context()),
+            Err(VkError::HandleFromDifferentContext { .. })
+        ));
+    }
+
+    #[test]
+    #[ignore = "requires Vulkan capable device"]
+    fn test_offscreen_frame_reads_back_clear_color() {
+        std::env::set_current_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/..")).unwrap();
+        let config = VulkanRendererConfig::builder()
+            .with_page_size(64 * 1024 * 1024)
+            .build()
+            .unwrap();
+        let renderer = VulkanRenderer::new_offscreen(
+            vk::Extent2D {
+                width: 64,
+                height: 64,
+            },
+            config,
+        )
+        .unwrap();
+        let builder = VulkanContextBuilder::new()
+            .with_material_type::<EmptyMaterial>()
+            .with_mesh_type::<CommonVertex>()
+            .with_shader_type::<DeferredShader<Shader<CommonVertex, EmptyMaterial>>>();
+        let mut context = builder.build(&renderer).unwrap();
+        let camera = OrbitCameraBuilder::new(Matrix4::identity()).build();
+        context.begin_frame(&*camera.borrow()).unwrap();
+        context.end_frame().unwrap();
+        // Lighting pass discards pixels without geometry, leaving the opaque black
+        // clear value of the combined attachment, zeroed memory would have no alpha
+        let frame = renderer.read_back_frame().unwrap();
+        assert_eq!(frame.len(), 64 * 64 * 4);
+        assert!(frame.chunks(4).all(|pixel| pixel == [0, 0, 0, 255]));
+    }
+
+    #[test]
+    #[ignore = "requires Vulkan capable device"]
+    fn test_consecutive_resizes_release_previous_targets() {
+        std::env::set_current_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/..")).unwrap();
+        let config = VulkanRendererConfig::builder()
+            .with_page_size(64 * 1024 * 1024)
+            .build()
+            .unwrap();
+        let extent = |size: u32| vk::Extent2D {
+            width: size,
+            height: size,
+        };
+        let mut renderer = VulkanRenderer::new_offscreen(extent(64), config).unwrap();
+        let builder = VulkanContextBuilder::new()
+            .with_material_type::<EmptyMaterial>()
+            .with_mesh_type::<CommonVertex>()
+            .with_shader_type::<DeferredShader<Shader<CommonVertex, EmptyMaterial>>>();
+        let mut context = builder.build(&renderer).unwrap();
+        let camera = OrbitCameraBuilder::new(Matrix4::identity()).build();
+        let mut render = |renderer: &VulkanRenderer| {
+            context.begin_frame(&*camera.borrow()).unwrap();
+            context.end_frame().unwrap();
+            let frame = renderer.read_back_frame().unwrap();
+            let live = renderer.context.borrow().tracker().get_live().len();
+            (frame.len(), context.stats().texture_memory, live)
+        };
+        let (frame_len, texture_memory, live) = render(&renderer);
+        assert_eq!(frame_len, 64 * 64 * 4);
+        // Two resizes in a row, the intermediate targets are never rendered to
+        renderer.handle_resize(extent(128)).unwrap();
+        renderer.handle_resize(extent(96)).unwrap();
+        let (resized_len, resized_memory, _) = render(&renderer);
+        assert_eq!(resized_len, 96 * 96 * 4);
+        assert!(resized_memory > texture_memory);
+        // Returning to the initial extent releases every target allocated on the way
+        renderer.handle_resize(extent(128)).unwrap();
+        renderer.handle_resize(extent(64)).unwrap();
+        assert_eq!(render(&renderer), (frame_len, texture_memory, live));
+    }
+
+    #[test]
+    #[ignore = "requires Vulkan capable device"]
+    fn test_render_stats_count_drawn_objects() {
+        // Renderer shaders are loaded relative to the workspace root
+        std::env::set_current_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/..")).unwrap();
+        let config = VulkanRendererConfig::builder()
+            .with_page_size(64 * 1024 * 1024)
+            .build()
+            .unwrap();
+        let renderer = VulkanRenderer::new_offscreen(
+            vk::Extent2D {
+                width: 64,
+                height: 64,
+            },
+            config,
+        )
+        .unwrap();
+        let mut builder = VulkanContextBuilder::new()
+            .with_material_type::<EmptyMaterial>()
+            .with_mesh_type::<CommonVertex>()
+            .with_shader_type::<DeferredShader<Shader<CommonVertex, EmptyMaterial>>>();
+        let cube: Mesh<CommonVertex> = Cube::new(1.0).into();
+        let cube_triangles = (cube.indices.len() / 3) as u64;
+        let model = Model::new(
+            builder.add_mesh::<CommonVertex, _>(cube),
+            builder.add_material(EmptyMaterial::default()),
+        );
+        let shader = builder.add_shader::<DeferredShader<_>, _>(
+            Shader::<CommonVertex, EmptyMaterial>::new(
+                "_resources/shaders/spv/deferred/gbuffer_write/checker",
+            )
+            .into(),
+        );
+        let mut context = builder.build(&renderer).unwrap();
+        let camera = OrbitCameraBuilder::new(Matrix4::identity()).build();
+        let mut render = |num_objects: usize| {
+            context.begin_frame(&*camera.borrow()).unwrap();
+            for _ in 0..num_objects {
+                context.draw(shader, &model, &Matrix4::identity()).unwrap();
+            }
+            context.end_frame().unwrap();
+            context.stats().clone()
+        };
+        let empty = render(0);
+        let stats = render(3);
+        // Objects are drawn in the depth prepass and in the G-Buffer write pass
+        assert_eq!(stats.draw_calls - empty.draw_calls, 6);
+        assert_eq!(stats.triangles - empty.triangles, 6 * cube_triangles);
+        // Objects share the mesh pack, so the depth prepass pipeline for its vertex type
+        // and the write pass pipeline are bound once for all of them
+        assert_eq!(stats.pipeline_binds - empty.pipeline_binds, 2);
+        // Shadow pass followed by subpasses of the G-Buffer render pass, the write
+        // subpass records single secondary command for the shader
+        assert_eq!(empty.secondary_commands, vec![1, 1, 1, 0, 1, 1, 1]);
+        assert_eq!(stats.secondary_commands, vec![1, 1, 1, 1, 1, 1, 1]);
+        assert_eq!(stats.buffer_memory, empty.buffer_memory);
+        assert!(stats.texture_memory > 0);
+    }
+
+    #[test]
+    #[ignore = "requires Vulkan capable device"]
+    fn test_draw_batch_binds_shared_pipeline_once() {
+        // Renderer shaders are loaded relative to the workspace root
+        std::env::set_current_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/..")).unwrap();
+        let config = VulkanRendererConfig::builder()
+            .with_page_size(64 * 1024 * 1024)
+            .build()
+            .unwrap();
+        let renderer = VulkanRenderer::new_offscreen(
+            vk::Extent2D {
+                width: 64,
+                height: 64,
+            },
+            config,
+        )
+        .unwrap();
+        let mut builder = VulkanContextBuilder::new()
+            .with_material_type::<EmptyMaterial>()
+            .with_mesh_type::<CommonVertex>()
+            .with_shader_type::<DeferredShader<Shader<CommonVertex, EmptyMaterial>>>();
+        let cube: Mesh<CommonVertex> = Cube::new(1.0).into();
+        let model = Model::new(
+            builder.add_mesh::<CommonVertex, _>(cube),
+            builder.add_material(EmptyMaterial::default()),
+        );
+        let shader = builder.add_shader::<DeferredShader<_>, _>(
+            Shader::<CommonVertex, EmptyMaterial>::new(
+                "_resources/shaders/spv/deferred/gbuffer_write/checker",
+            )
+            .into(),
+        );
+        let mut context = builder.build(&renderer).unwrap();
+        let camera = OrbitCameraBuilder::new(Matrix4::identity()).build();
+        let mut render = |num_objects: usize| {
+            let items = (0..num_objects)
+                .map(|_| (&model, Matrix4::identity()))
+                .collect::<Vec<_>>();
+            context.begin_frame(&*camera.borrow()).unwrap();
+            context.draw_batch(shader, &items).unwrap();
+            context.end_frame().unwrap();
+            context.stats().clone()
+        };
+        let empty = render(0);
+        let single = render(1);
+        let batch = render(8);
+        // Each object is drawn in the depth prepass and in the G-Buffer write pass
+        assert_eq!(batch.draw_calls - empty.draw_calls, 16);
+        // Depth prepass and write pass pipelines are bound once for the whole batch
+        assert_eq!(batch.pipeline_binds - empty.pipeline_binds, 2);
+        assert_eq!(batch.pipeline_binds, single.pipeline_binds);
+        assert_eq!(batch.descriptor_binds, single.descriptor_binds);
+    }
+
+    #[test]
+    #[ignore = "requires Vulkan capable device"]
+    fn test_draw_with_bounds_skips_objects_outside_frustum() {
+        // Renderer shaders are loaded relative to the workspace root
+        std::env::set_current_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/..")).unwrap();
+        let config = VulkanRendererConfig::builder()
+            .with_page_size(64 * 1024 * 1024)
+            .with_frustum_culling(true)
+            .build()
+            .unwrap();
+        let renderer = VulkanRenderer::new_offscreen(
+            vk::Extent2D {
+                width: 64,
+                height: 64,
+            },
+            config,
+        )
+        .unwrap();
+        let mut builder = VulkanContextBuilder::new()
+            .with_material_type::<EmptyMaterial>()
+            .with_mesh_type::<CommonVertex>()
+            .with_shader_type::<DeferredShader<Shader<CommonVertex, EmptyMaterial>>>();
+        let cube: Mesh<CommonVertex> = Cube::new(1.0).into();
+        let cube_triangles = (cube.indices.len() / 3) as u64;
+        let model = Model::new(
+            builder.add_mesh::<CommonVertex, _>(cube),
+            builder.add_material(EmptyMaterial::default()),
+        );
+        let shader = builder.add_shader::<DeferredShader<_>, _>(
+            Shader::<CommonVertex, EmptyMaterial>::new(
+                "_resources/shaders/spv/deferred/gbuffer_write/checker",
+            )
+            .into(),
+        );
+        let mut context = builder.build(&renderer).unwrap();
+        let proj = Matrix4::perspective(std::f32::consts::FRAC_PI_2, 1.0, 1e-3, 1e3);
+        let camera = OrbitCameraBuilder::new(proj).with_distance(4.0).build();
+        let bounds = Aabb::new(Vector3::new(-0.5, -0.5, -0.5), Vector3::new(0.5, 0.5, 0.5));
+        // Camera orbits the origin on the x axis, objects placed far along the y axis
+        // lie outside of its field of view
+        let transforms = [
+            Matrix4::identity(),
+            Matrix4::translate(Vector3::new(0.0, 50.0, 0.0)),
+            Matrix4::translate(Vector3::new(0.0, 0.0, 0.5)),
+            Matrix4::translate(Vector3::new(0.0, -50.0, 0.0)),
+        ];
+        let mut render = |transforms: &[Matrix4]| {
+            context.begin_frame(&*camera.borrow()).unwrap();
+            for transform in transforms {
+                context
+                    .draw_with_bounds(shader, &model, transform, &bounds.transformed(transform))
+                    .unwrap();
+            }
+            context.end_frame().unwrap();
+            context.stats().clone()
+        };
+        let empty = render(&[]);
+        let stats = render(&transforms);
+        // Visible half of the objects is drawn in the depth prepass and in the G-Buffer write pass
+        assert_eq!(stats.draw_calls - empty.draw_calls, 4);
+        assert_eq!(stats.triangles - empty.triangles, 4 * cube_triangles);
+    }
+
+    #[test]
+    #[ignore = "requires Vulkan capable device"]
+    fn test_zero_exposure_tonemaps_frame_to_black() {
+        std::env::set_current_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/..")).unwrap();
+        let config = VulkanRendererConfig::builder()
+            .with_page_size(64 * 1024 * 1024)
+            .with_tonemapping(true)
+            .build()
+            .unwrap();
+        let renderer = VulkanRenderer::new_offscreen(
+            vk::Extent2D {
+                width: 64,
+                height: 64,
+            },
+            config,
+        )
+        .unwrap();
+        let mut builder = VulkanContextBuilder::new()
+            .with_material_type::<EmptyMaterial>()
+            .with_mesh_type::<CommonVertex>()
+            .with_shader_type::<DeferredShader<Shader<CommonVertex, EmptyMaterial>>>();
+        let model = Model::new(
+            builder.add_mesh::<CommonVertex, _>(Cube::new(1.0).into()),
+            builder.add_material(EmptyMaterial::default()),
+        );
+        let shader = builder.add_shader::<DeferredShader<_>, _>(
+            Shader::<CommonVertex, EmptyMaterial>::new(
+                "_resources/shaders/spv/deferred/gbuffer_write/checker",
+            )
+            .into(),
+        );
+        let mut context = builder.build(&renderer).unwrap();
+        let camera = OrbitCameraBuilder::new(Matrix4::identity()).build();
+        let mut render = |exposure: f32| {
+            context.set_exposure(exposure);
+            context.begin_frame(&*camera.borrow()).unwrap();
+            context.draw(shader, &model, &Matrix4::identity()).unwrap();
+            context.end_frame().unwrap();
+            context.read_back_frame().unwrap()
+        };
+        let exposed = render(VulkanRendererConfig::DEFAULT_EXPOSURE);
+        let black = render(0.0);
+        assert!(exposed.chunks(4).any(|pixel| pixel[..3] != [0, 0, 0]));
+        assert!(black.chunks(4).all(|pixel| pixel[..3] == [0, 0, 0]));
+    }
+
+    #[test]
+    #[ignore = "requires Vulkan capable device"]
+    fn test_solid_color_skybox_fills_background() {
+        std::env::set_current_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/..")).unwrap();
+        let config = VulkanRendererConfig::builder()
+            .with_page_size(64 * 1024 * 1024)
+            .build()
+            .unwrap();
+        let renderer = VulkanRenderer::new_offscreen(
+            vk::Extent2D {
+                width: 64,
+                height: 64,
+            },
+            config,
+        )
+        .unwrap();
+        // Fully saturated channels are encoded the same in sRGB and linear color space
+        let color = [0, 255, 0, 255];
+        let skybox = write_skybox_faces("skybox_solid", &SKYBOX_FACES, color);
+        let builder = VulkanContextBuilder::new()
+            .with_material_type::<EmptyMaterial>()
+            .with_mesh_type::<CommonVertex>()
+            .with_shader_type::<DeferredShader<Shader<CommonVertex, EmptyMaterial>>>()
+            .with_skybox(&skybox)
+            .unwrap();
+        let mut context = builder.build(&renderer).unwrap();
+        let proj = Matrix4::perspective(std::f32::consts::FRAC_PI_2, 1.0, 1e-3, 1e3);
+        let camera = OrbitCameraBuilder::new(proj).build();
+        context.begin_frame(&*camera.borrow()).unwrap();
+        context.end_frame().unwrap();
+        let frame = context.read_back_frame().unwrap();
+        assert!(frame.chunks(4).all(|pixel| pixel[..3] == color[..3]));
+    }
+
+    #[test]
+    #[ignore = "requires Vulkan capable device"]
+    fn test_draw_with_handle_from_different_context_fails() {
+        std::env::set_current_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/..")).unwrap();
+        let config = VulkanRendererConfig::builder()
+            .with_page_size(64 * 1024 * 1024)
+            .build()
+            .unwrap();
+        let renderer = VulkanRenderer::new_offscreen(
+            vk::Extent2D {
+                width: 64,
+                height: 64,
+            },
+            config,
+        )
+        .unwrap();
+        let builder = || {
+            let mut builder = VulkanContextBuilder::new()
+                .with_material_type::<EmptyMaterial>()
+                .with_mesh_type::<CommonVertex>()
+                .with_shader_type::<DeferredShader<Shader<CommonVertex, EmptyMaterial>>>();
+            let model = Model::new(
+                builder.add_mesh::<CommonVertex, _>(Cube::new(1.0).into()),
+                builder.add_material(EmptyMaterial::default()),
+            );
+            let shader = builder.add_shader::<DeferredShader<_>, _>(
+                Shader::<CommonVertex, EmptyMaterial>::new(
+                    "_resources/shaders/spv/deferred/gbuffer_write/checker",
+                )
+                .into(),
+            );
+            (builder, model, shader)
+        };
+        let (first, first_model, first_shader) = builder();
+        let (second, second_model, second_shader) = builder();
+        let mut first = first.build(&renderer).unwrap();
+        let second = second.build(&renderer).unwrap();
+        let camera = OrbitCameraBuilder::new(Matrix4::identity()).build();
+        first.begin_frame(&*camera.borrow()).unwrap();
+        assert!(first
+            .draw(first_shader, &first_model, &Matrix4::identity())
+            .is_ok());
+        assert!(first
+            .draw(second_shader, &first_model, &Matrix4::identity())
+            .is_err());
+        assert!(first
+            .draw(first_shader, &second_model, &Matrix4::identity())
+            .is_err());
+        first.end_frame().unwrap();
+        // Rebuilt context rejects handles of the context it replaces
+        drop(second);
+        let (rebuilt, _, _) = builder();
+        let mut rebuilt = rebuilt.build(&renderer).unwrap();
+        rebuilt.begin_frame(&*camera.borrow()).unwrap();
+        let error = rebuilt
+            .draw(second_shader, &second_model, &Matrix4::identity())
+            .unwrap_err();
+        assert!(matches!(error, RenderError::InvalidHandle(_)));
+        rebuilt.end_frame().unwrap();
+    }
+
+    #[test]
+    fn test_vk_result_codes_mapped_to_render_errors() {
+        let map = |result: vk::Result| RenderError::from(VkError::from(result));
+        assert!(matches!(
+            map(vk::Result::ERROR_DEVICE_LOST),
+            RenderError::DeviceLost
+        ));
+        assert!(matches!(
+            map(vk::Result::ERROR_OUT_OF_DEVICE_MEMORY),
+            RenderError::OutOfDeviceMemory
+        ));
+        assert!(matches!(
+            map(vk::Result::ERROR_OUT_OF_DATE_KHR),
+            RenderError::SwapchainOutOfDate
+        ));
+        assert!(matches!(
+            map(vk::Result::ERROR_INITIALIZATION_FAILED),
+            RenderError::Other(_)
+        ));
+        // Codes boxed by the frame recording are recovered as well
+        assert!(matches!(
+            into_render_error(Box::new(vk::Result::ERROR_DEVICE_LOST)),
+            RenderError::DeviceLost
+        ));
+        assert!(matches!(
+            into_render_error("current_frame is None!".into()),
+            RenderError::Other(_)
+        ));
+        let first = ContextId::next();
+        let second = ContextId::next();
+        assert!(matches!(
+            into_render_error(Box::new(validate_context(first, second).unwrap_err())),
+            RenderError::InvalidHandle(_)
+        ));
+    }
+
+    #[test]
+    fn test_suboptimal_present_reports_out_of_date_swapchain() {
+        // Suboptimal present succeeds, but the swapchain is recreated all the same
+        assert_eq!(
+            PresentStatus::from_present_result(Ok(true)).unwrap(),
+            PresentStatus::OutOfDate
+        );
+        assert_eq!(
+            PresentStatus::from_present_result(Err(vk::Result::ERROR_OUT_OF_DATE_KHR)).unwrap(),
+            PresentStatus::OutOfDate
+        );
+        assert_eq!(
+            PresentStatus::from_present_result(Ok(false)).unwrap(),
+            PresentStatus::Presented
+        );
+        let error = PresentStatus::from_present_result(Err(vk::Result::ERROR_DEVICE_LOST));
+        assert!(matches!(
+            RenderError::from(error.unwrap_err()),
+            RenderError::DeviceLost
+        ));
+        // Failed acquire is boxed by the frame recording
+        assert!(matches!(
+            into_render_error(Box::new(vk::Result::ERROR_OUT_OF_DATE_KHR)),
+            RenderError::SwapchainOutOfDate
+        ));
+    }
+
+    #[test]
+    #[ignore = "requires Vulkan capable device"]
+    fn test_draw_with_invalid_shader_handle_fails() {
+        std::env::set_current_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/..")).unwrap();
+        let config = VulkanRendererConfig::builder()
+            .with_page_size(64 * 1024 * 1024)
+            .build()
+            .unwrap();
+        let renderer = VulkanRenderer::new_offscreen(
+            vk::Extent2D {
+                width: 64,
+                height: 64,
+            },
+            config,
+        )
+        .unwrap();
+        let mut builder = VulkanContextBuilder::new()
+            .with_material_type::<EmptyMaterial>()
+            .with_mesh_type::<CommonVertex>()
+            .with_shader_type::<DeferredShader<Shader<CommonVertex, EmptyMaterial>>>();
+        let model = Model::new(
+            builder.add_mesh::<CommonVertex, _>(Cube::new(1.0).into()),
+            builder.add_material(EmptyMaterial::default()),
+        );
+        let shader = builder.add_shader::<DeferredShader<_>, _>(
+            Shader::<CommonVertex, EmptyMaterial>::new(
+                "_resources/shaders/spv/deferred/gbuffer_write/checker",
+            )
+            .into(),
+        );
+        // Handle of the right context and type, pointing past the only loaded shader
+        let invalid: ShaderHandle<DeferredShader<Shader<CommonVertex, EmptyMaterial>>> =
+            ShaderHandle::new(shader.index() + 1, shader.context());
+        let mut context = builder.build(&renderer).unwrap();
+        let camera = OrbitCameraBuilder::new(Matrix4::identity()).build();
+        context.begin_frame(&*camera.borrow()).unwrap();
+        let error = context
+            .draw(invalid, &model, &Matrix4::identity())
+            .unwrap_err();
+        assert!(matches!(error, RenderError::InvalidHandle(_)));
+        assert!(context.draw(shader, &model, &Matrix4::identity()).is_ok());
+        context.end_frame().unwrap();
+    }
 }
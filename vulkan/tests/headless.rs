@@ -0,0 +1,142 @@
+use graphics::{
+    model::{CommonVertex, EmptyMaterial, Mesh, Model, TransparentMaterial},
+    renderer::{
+        camera::{orbit::OrbitCameraBuilder, CameraBuilder},
+        ContextBuilder, RendererContext,
+    },
+    shader::Shader,
+};
+use math::types::{Matrix4, Vector3, Vector4};
+use physics::shape::{self, Cube};
+use vulkan::{
+    context::device::renderer::deferred::DeferredShader, VulkanContextBuilder, VulkanRenderer,
+    VulkanRendererConfig,
+};
+
+const EXTENT: u32 = 64;
+
+fn pixel(frame: &[u8], x: u32, y: u32) -> [u8; 4] {
+    let offset = ((y * EXTENT + x) * 4) as usize;
+    frame[offset..offset + 4].try_into().unwrap()
+}
+
+#[test]
+#[ignore = "requires Vulkan capable device"]
+fn test_headless_checker_cube_pixels() {
+    // Renderer shaders are loaded relative to the workspace root
+    std::env::set_current_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/..")).unwrap();
+    let config = VulkanRendererConfig::builder()
+        .with_page_size(64 * 1024 * 1024)
+        .build()
+        .unwrap();
+    let renderer = VulkanRenderer::new_headless((EXTENT, EXTENT), config).unwrap();
+    let mut builder = VulkanContextBuilder::new()
+        .with_material_type::<EmptyMaterial>()
+        .with_mesh_type::<CommonVertex>()
+        .with_shader_type::<DeferredShader<Shader<CommonVertex, EmptyMaterial>>>();
+    let cube: Mesh<CommonVertex> = Cube::new(1.0).into();
+    let model = Model::new(
+        builder.add_mesh::<CommonVertex, _>(cube),
+        builder.add_material(EmptyMaterial::default()),
+    );
+    let shader = builder.add_shader::<DeferredShader<_>, _>(
+        Shader::<CommonVertex, EmptyMaterial>::new(
+            "_resources/shaders/spv/deferred/gbuffer_write/checker",
+        )
+        .into(),
+    );
+    let mut context = builder.build(&renderer).unwrap();
+    // Default yaw and pitch place the camera on the x axis, facing the blue +x face
+    let proj = Matrix4::perspective(std::f32::consts::FRAC_PI_2, 1.0, 1e-3, 1e3);
+    let camera = OrbitCameraBuilder::new(proj).with_distance(2.0).build();
+    context.begin_frame(&*camera.borrow()).unwrap();
+    context.draw(shader, &model, &Matrix4::identity()).unwrap();
+    context.end_frame().unwrap();
+    let frame = context.read_back_frame().unwrap();
+    assert_eq!(frame.len(), (EXTENT * EXTENT * 4) as usize);
+
+    // Corners are not covered by the cube and keep the opaque black clear value
+    for (x, y) in [
+        (0, 0),
+        (EXTENT - 1, 0),
+        (0, EXTENT - 1),
+        (EXTENT - 1, EXTENT - 1),
+    ] {
+        assert_eq!(pixel(&frame, x, y), [0, 0, 0, 255]);
+    }
+    // Without directional light the unlit albedo is written, the checker alternates
+    // between the full face color and its half, encoded according to the output format
+    let is_checker = |[r, g, b, a]: [u8; 4]| [r, g, a] == [0, 0, 255] && b >= 127;
+    let center = EXTENT / 2;
+    for (x, y) in [(center - 1, center - 1), (center, center)] {
+        assert!(is_checker(pixel(&frame, x, y)));
+    }
+    let face = (center - 8..center + 8)
+        .flat_map(|y| (center - 8..center + 8).map(move |x| (x, y)))
+        .map(|(x, y)| pixel(&frame, x, y))
+        .collect::<Vec<_>>();
+    assert!(face.iter().all(|&pixel| is_checker(pixel)));
+    assert!(face.iter().any(|pixel| pixel[2] == 255));
+    assert!(face.iter().any(|pixel| pixel[2] < 255));
+}
+
+#[test]
+#[ignore = "requires Vulkan capable device"]
+fn test_headless_transparent_quads_composite_back_to_front() {
+    std::env::set_current_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/..")).unwrap();
+    let config = VulkanRendererConfig::builder()
+        .with_page_size(64 * 1024 * 1024)
+        .build()
+        .unwrap();
+    let renderer = VulkanRenderer::new_headless((EXTENT, EXTENT), config).unwrap();
+    let mut builder = VulkanContextBuilder::new()
+        .with_material_type::<TransparentMaterial>()
+        .with_mesh_type::<CommonVertex>()
+        .with_shader_type::<DeferredShader<Shader<CommonVertex, TransparentMaterial>>>();
+    // Thin boxes facing the camera placed on the x axis
+    let quad = builder.add_mesh::<CommonVertex, _>(shape::Box::new(0.01, 1.0, 1.0).into());
+    let near = Model::new(
+        quad,
+        builder.add_material(TransparentMaterial::new(Vector4::new(1.0, 0.0, 0.0, 0.5))),
+    );
+    let far = Model::new(
+        quad,
+        builder.add_material(TransparentMaterial::new(Vector4::new(0.0, 1.0, 0.0, 0.5))),
+    );
+    let shader = builder.add_shader::<DeferredShader<_>, _>(
+        Shader::<CommonVertex, TransparentMaterial>::new(
+            "_resources/shaders/spv/deferred/transparent",
+        )
+        .into(),
+    );
+    let mut context = builder.build(&renderer).unwrap();
+    let proj = Matrix4::perspective(std::f32::consts::FRAC_PI_2, 1.0, 1e-3, 1e3);
+    let camera = OrbitCameraBuilder::new(proj).with_distance(4.0).build();
+    context.begin_frame(&*camera.borrow()).unwrap();
+    // Near quad is submitted first, the pass has to reorder draws by depth
+    context
+        .draw(
+            shader,
+            &near,
+            &Matrix4::translate(Vector3::new(1.0, 0.0, 0.0)),
+        )
+        .unwrap();
+    context
+        .draw(
+            shader,
+            &far,
+            &Matrix4::translate(Vector3::new(-1.0, 0.0, 0.0)),
+        )
+        .unwrap();
+    context.end_frame().unwrap();
+    let frame = context.read_back_frame().unwrap();
+
+    // Far green quad blended over black clear value leaves half green, near red quad
+    // blended over it halves the green again, reversed order would favor green instead
+    let center = EXTENT / 2;
+    let [r, g, b, _] = pixel(&frame, center, center);
+    assert!(r > g, "expected near red quad on top, got {:?}", [r, g, b]);
+    assert!(g > 0, "expected far green quad behind, got {:?}", [r, g, b]);
+    assert_eq!(b, 0);
+    assert_eq!(pixel(&frame, 0, 0), [0, 0, 0, 255]);
+}